@@ -0,0 +1,342 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+#![allow(non_snake_case)]
+
+use itertools::*;
+use sgx_dcap::DcapQuote;
+use sgx_types::*;
+use sgx_urts::SgxEnclave;
+use std::env;
+use std::sync::Mutex;
+
+mod pccs_client;
+use pccs_client::{PccsClient, PckPlatformInfo, ENC_PPID_SIZE, PCCS_DEFAULT_URL};
+
+static ENCLAVE_FILE: &'static str = "enclave.signed.so";
+
+// PCK certificate chain (PEM) handed to the enclave through ocall_get_pck_cert_chain
+static PCK_CERT_CHAIN: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+extern "C" {
+    fn enclave_create_report(
+        eid: sgx_enclave_id_t,
+        retval: *mut u32,
+        p_qe3_target: &sgx_target_info_t,
+        p_report: *mut sgx_report_t,
+    ) -> sgx_status_t;
+    fn enclave_get_pck_bound_quote(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        p_quote: *mut u8,
+        maxlen: u32,
+        p_quote_len: *mut u32,
+    ) -> sgx_status_t;
+    fn enclave_verify_quote(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        p_quote: *const u8,
+        quote_size: u32,
+        p_qv_result: *mut sgx_ql_qv_result_t,
+    ) -> sgx_status_t;
+}
+
+// An ECDSA quote with a PCK certificate chain is around 5 KB.
+const QUOTE_MAX_LEN: u32 = 16384;
+
+#[no_mangle]
+pub extern "C" fn ocall_get_pck_cert_chain(
+    p_chain: *mut u8,
+    maxlen: u32,
+    p_chain_len: *mut u32,
+) -> sgx_status_t {
+    let chain = PCK_CERT_CHAIN.lock().unwrap();
+    if chain.is_empty() || chain.len() > maxlen as usize {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    unsafe {
+        std::ptr::copy_nonoverlapping(chain.as_ptr(), p_chain, chain.len());
+        *p_chain_len = chain.len() as u32;
+    }
+    sgx_status_t::SGX_SUCCESS
+}
+
+// The ocalls below forward the enclave's requests to the DCAP Quote Library
+// and Quote Verification Library. The enclave checks what they return.
+#[no_mangle]
+pub extern "C" fn ocall_qe_get_target_info(p_target_info: *mut sgx_target_info_t) -> sgx_quote3_error_t {
+    unsafe { sgx_qe_get_target_info(p_target_info) }
+}
+
+#[no_mangle]
+pub extern "C" fn ocall_qe_get_quote_size(p_quote_size: *mut u32) -> sgx_quote3_error_t {
+    unsafe { sgx_qe_get_quote_size(p_quote_size) }
+}
+
+#[no_mangle]
+pub extern "C" fn ocall_qe_get_quote(
+    p_report: *const sgx_report_t,
+    quote_size: u32,
+    p_quote: *mut u8,
+) -> sgx_quote3_error_t {
+    unsafe { sgx_qe_get_quote(p_report, quote_size, p_quote) }
+}
+
+#[no_mangle]
+pub extern "C" fn ocall_qv_verify_quote(
+    p_quote: *const u8,
+    quote_size: u32,
+    expiration_check_date: time_t,
+    p_collateral_expiration_status: *mut u32,
+    p_quote_verification_result: *mut sgx_ql_qv_result_t,
+    p_qve_report_info: *mut sgx_ql_qe_report_info_t,
+    supplemental_data_size: u32,
+    p_supplemental_data: *mut u8,
+) -> sgx_quote3_error_t {
+    // collateral is fetched from the PCCS configured in
+    // /etc/sgx_default_qcnl.conf
+    unsafe {
+        sgx_qv_verify_quote(
+            p_quote,
+            quote_size,
+            std::ptr::null(),
+            expiration_check_date,
+            p_collateral_expiration_status,
+            p_quote_verification_result,
+            p_qve_report_info,
+            supplemental_data_size,
+            p_supplemental_data,
+        )
+    }
+}
+
+fn init_enclave() -> SgxResult<SgxEnclave> {
+    let mut launch_token: sgx_launch_token_t = [0; 1024];
+    let mut launch_token_updated: i32 = 0;
+    // call sgx_create_enclave to initialize an enclave instance
+    // Debug Support: set 2nd parameter to 1
+    let debug = 0;
+    let mut misc_attr = sgx_misc_attribute_t {
+        secs_attr: sgx_attributes_t { flags: 0, xfrm: 0 },
+        misc_select: 0,
+    };
+    SgxEnclave::create(
+        ENCLAVE_FILE,
+        debug,
+        &mut launch_token,
+        &mut launch_token_updated,
+        &mut misc_attr,
+    )
+}
+
+// Platform identity extracted from the certification data of a quote
+// produced with the PCK-ID retrieval QPL (see dcap-pckretrieval).
+struct PckId {
+    qe_id: Vec<u8>,
+    enc_ppid: Vec<u8>,
+    platform: PckPlatformInfo,
+}
+
+fn parse_pck_id(quote: &[u8]) -> Option<PckId> {
+    if quote.len() < std::mem::size_of::<sgx_quote3_t>() {
+        return None;
+    }
+    let p_quote3: *const sgx_quote3_t = quote.as_ptr() as *const sgx_quote3_t;
+    let quote3: sgx_quote3_t = unsafe { *p_quote3 };
+    let sig_data: &[u8] = &quote[std::mem::size_of::<sgx_quote3_t>()..];
+    if quote3.signature_data_len as usize != sig_data.len() {
+        return None;
+    }
+
+    // sgx_ql_ecdsa_sig_data_t || sgx_ql_auth_data_t || auth_data
+    // || sgx_ql_certification_data_t || certification_data
+    let auth_offset = std::mem::size_of::<sgx_ql_ecdsa_sig_data_t>();
+    let auth_data_offset = auth_offset + std::mem::size_of::<sgx_ql_auth_data_t>();
+    if sig_data.len() < auth_data_offset {
+        return None;
+    }
+    let auth_header: sgx_ql_auth_data_t =
+        unsafe { *(sig_data[auth_offset..].as_ptr() as *const sgx_ql_auth_data_t) };
+
+    let cert_offset = auth_data_offset + auth_header.size as usize;
+    let cert_info_offset = cert_offset + std::mem::size_of::<sgx_ql_certification_data_t>();
+    if sig_data.len() < cert_info_offset {
+        return None;
+    }
+    let cert_header: sgx_ql_certification_data_t =
+        unsafe { *(sig_data[cert_offset..].as_ptr() as *const sgx_ql_certification_data_t) };
+    let cert_info = &sig_data[cert_info_offset..];
+    if cert_info.len() != cert_header.size as usize || cert_info.len() < ENC_PPID_SIZE + 20 {
+        return None;
+    }
+
+    // ENC_PPID || PCEID || CPUSVN || PCESVN
+    let mut platform = PckPlatformInfo::default();
+    let (enc_ppid, tail) = cert_info.split_at(ENC_PPID_SIZE);
+    platform.pce_id.copy_from_slice(&tail[0..2]);
+    platform.cpu_svn.copy_from_slice(&tail[2..18]);
+    platform.pce_svn.copy_from_slice(&tail[18..20]);
+
+    Some(PckId {
+        qe_id: quote3.header.user_data[..16].to_vec(),
+        enc_ppid: enc_ppid.to_vec(),
+        platform,
+    })
+}
+
+fn main() {
+    let enclave = match init_enclave() {
+        Ok(r) => {
+            println!("[+] Init Enclave Successful {}!", r.geteid());
+            r
+        }
+        Err(x) => {
+            println!("[-] Init Enclave Failed {}!", x.as_str());
+            return;
+        }
+    };
+
+    // The PCK-ID retrieval QPL makes the QE emit the encrypted PPID instead
+    // of a PCK certificate chain, which is what we need to query the PCCS.
+    let _l = unsafe { libloading::Library::new("./libdcap_quoteprov.so.1").unwrap() };
+
+    let mut ti: sgx_target_info_t = sgx_target_info_t::default();
+    let qe3_ret = unsafe { sgx_qe_get_target_info(&mut ti as *mut _) };
+    if qe3_ret != sgx_quote3_error_t::SGX_QL_SUCCESS {
+        println!("Error in sgx_qe_get_target_info. {:?}", qe3_ret);
+        return;
+    }
+
+    println!("Step1: Generate PCK-ID quote");
+    let mut retval: u32 = 0;
+    let mut report = sgx_report_t::default();
+    let result = unsafe { enclave_create_report(enclave.geteid(), &mut retval, &ti, &mut report) };
+    if result != sgx_status_t::SGX_SUCCESS || retval != 0 {
+        println!("[-] ECALL enclave_create_report Failed {} {}!", result.as_str(), retval);
+        return;
+    }
+    let pck_id = match generate_quote(&report).as_deref().and_then(parse_pck_id) {
+        Some(r) => r,
+        None => {
+            println!("[-] Cannot extract PCK ID from quote");
+            return;
+        }
+    };
+    println!("QE_ID:\n{:02x}", pck_id.qe_id.iter().format(""));
+    println!("PCE_ID:\n{:02x}", pck_id.platform.pce_id.iter().format(""));
+
+    println!("Step2: Fetch PCK certificate from PCCS");
+    let pccs_url = env::var("PCCS_URL").unwrap_or_else(|_| PCCS_DEFAULT_URL.to_string());
+    let insecure = env::var("PCCS_USE_SECURE_CERT").map(|v| v != "true").unwrap_or(true);
+    let pck_cert = match PccsClient::new(&pccs_url, pck_id.platform, insecure)
+        .and_then(|c| c.fetch_pck_cert(&pck_id.qe_id, &pck_id.enc_ppid))
+    {
+        Ok(r) => r,
+        Err(e) => {
+            println!("[-] Fetch PCK certificate from {} failed: {}", pccs_url, e);
+            return;
+        }
+    };
+    println!("FMSPC: {}, TCBm: {}", pck_cert.fmspc, pck_cert.tcbm);
+    *PCK_CERT_CHAIN.lock().unwrap() = pck_cert.to_pem_chain().into_bytes();
+
+    println!("Step3: Generate quote bound to the verified PCK certificate");
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let mut quote: Vec<u8> = vec![0; QUOTE_MAX_LEN as usize];
+    let mut quote_len: u32 = 0;
+    let result = unsafe {
+        enclave_get_pck_bound_quote(
+            enclave.geteid(),
+            &mut retval,
+            quote.as_mut_ptr(),
+            QUOTE_MAX_LEN,
+            &mut quote_len,
+        )
+    };
+    if result != sgx_status_t::SGX_SUCCESS || retval != sgx_status_t::SGX_SUCCESS {
+        println!(
+            "[-] ECALL enclave_get_pck_bound_quote Failed {} {}!",
+            result.as_str(),
+            retval.as_str()
+        );
+        return;
+    }
+    quote.truncate(quote_len as usize);
+    println!("Quote size: {}", quote.len());
+    match DcapQuote::parse(&quote) {
+        Ok(q) => println!(
+            "Report data (SHA256 of PCK cert):\n{:02x}",
+            q.report_body().report_data.d[..32].iter().format("")
+        ),
+        Err(e) => {
+            println!("[-] Malformed quote: {}", e);
+            return;
+        }
+    }
+
+    println!("Step4: Verify the quote");
+    let mut qv_result = sgx_ql_qv_result_t::SGX_QL_QV_RESULT_UNSPECIFIED;
+    let result = unsafe {
+        enclave_verify_quote(
+            enclave.geteid(),
+            &mut retval,
+            quote.as_ptr(),
+            quote.len() as u32,
+            &mut qv_result,
+        )
+    };
+    if result != sgx_status_t::SGX_SUCCESS || retval != sgx_status_t::SGX_SUCCESS {
+        println!(
+            "[-] ECALL enclave_verify_quote Failed {} {}!",
+            result.as_str(),
+            retval.as_str()
+        );
+        return;
+    }
+    match qv_result {
+        sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OK => println!("Quote verification passed"),
+        sgx_ql_qv_result_t::SGX_QL_QV_RESULT_CONFIG_NEEDED
+        | sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OUT_OF_DATE
+        | sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OUT_OF_DATE_CONFIG_NEEDED
+        | sgx_ql_qv_result_t::SGX_QL_QV_RESULT_SW_HARDENING_NEEDED
+        | sgx_ql_qv_result_t::SGX_QL_QV_RESULT_CONFIG_AND_SW_HARDENING_NEEDED => {
+            println!("Quote verification passed with non-terminal result: {}", qv_result)
+        }
+        _ => println!("[-] Quote verification failed: {}", qv_result),
+    }
+
+    enclave.destroy();
+}
+
+fn generate_quote(app_report: &sgx_report_t) -> Option<Vec<u8>> {
+    let mut quote_size: u32 = 0;
+    let qe3_ret = unsafe { sgx_qe_get_quote_size(&mut quote_size as _) };
+    if qe3_ret != sgx_quote3_error_t::SGX_QL_SUCCESS {
+        println!("Error in sgx_qe_get_quote_size. {:?}", qe3_ret);
+        return None;
+    }
+
+    let mut quote_vec: Vec<u8> = vec![0; quote_size as usize];
+    let qe3_ret =
+        unsafe { sgx_qe_get_quote(app_report as _, quote_size, quote_vec.as_mut_ptr() as _) };
+    if qe3_ret != sgx_quote3_error_t::SGX_QL_SUCCESS {
+        println!("Error in sgx_qe_get_quote. {:?}", qe3_ret);
+        return None;
+    }
+
+    Some(quote_vec)
+}
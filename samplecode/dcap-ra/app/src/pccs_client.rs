@@ -0,0 +1,216 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! A minimal client of the PCCS (Provisioning Certificate Caching Service)
+//! REST API, see "Intel SGX DCAP Provisioning Certificate Caching Service":
+//! https://github.com/intel/SGXDataCenterAttestationPrimitives/tree/master/QuoteGeneration/pccs
+
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+use percent_encoding::percent_decode_str;
+
+pub const PCCS_DEFAULT_URL: &str = "https://localhost:8081";
+const PCK_CERT_PATH: &str = "/sgx/certification/v3/pckcert";
+
+const HEADER_ISSUER_CHAIN: &str = "SGX-PCK-Certificate-Issuer-Chain";
+const HEADER_TCBM: &str = "SGX-TCBm";
+const HEADER_FMSPC: &str = "SGX-FMSPC";
+const HEADER_CA_TYPE: &str = "SGX-PCK-Certificate-CA-Type";
+
+pub const QE_ID_SIZE: usize = 16;
+pub const ENC_PPID_SIZE: usize = 384;
+
+#[derive(Debug)]
+pub enum PccsError {
+    InvalidParameter(&'static str),
+    Http(reqwest::Error),
+    Status(u16, &'static str),
+    MissingHeader(&'static str),
+    Malformed(&'static str),
+}
+
+impl fmt::Display for PccsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PccsError::InvalidParameter(p) => write!(f, "invalid parameter: {}", p),
+            PccsError::Http(e) => write!(f, "http error: {}", e),
+            PccsError::Status(code, msg) => write!(f, "PCCS returned {}: {}", code, msg),
+            PccsError::MissingHeader(h) => write!(f, "missing response header {}", h),
+            PccsError::Malformed(what) => write!(f, "malformed response: {}", what),
+        }
+    }
+}
+
+impl Error for PccsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PccsError::Http(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for PccsError {
+    fn from(e: reqwest::Error) -> PccsError {
+        PccsError::Http(e)
+    }
+}
+
+fn status_message(code: u16) -> &'static str {
+    match code {
+        400 => "Invalid request parameters",
+        401 => "Failed to authenticate or authorize the request",
+        404 => "PCK certificate not found",
+        461 => "The platform was not found in the cache",
+        462 => "Certificates are not available for certain TCBs",
+        500 => "Internal error occurred",
+        502 => "Unable to retrieve the collateral from the Intel SGX PCS",
+        503 => "Service is currently not available",
+        _ => "Unknown error occured",
+    }
+}
+
+/// The TCB level of the platform being provisioned, as found in the
+/// certification data of a PCK-ID quote. All fields are kept in the
+/// little-endian byte order used by the quote and the PCCS API.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PckPlatformInfo {
+    pub pce_id: [u8; 2],
+    pub cpu_svn: [u8; 16],
+    pub pce_svn: [u8; 2],
+}
+
+/// A PCK certificate as returned by `GET /sgx/certification/v3/pckcert`.
+#[derive(Clone, Debug)]
+pub struct PckCert {
+    /// PEM encoded PCK certificate.
+    pub cert: String,
+    /// PEM encoded issuer chain: PCK Platform/Processor CA, then Root CA.
+    pub issuer_chain: String,
+    /// Hex encoded raw TCB (CPUSVN || PCESVN) the certificate was issued for.
+    pub tcbm: String,
+    /// Hex encoded FMSPC of the platform.
+    pub fmspc: String,
+    /// "processor" or "platform", if reported by the PCCS.
+    pub ca_type: Option<String>,
+}
+
+impl PckCert {
+    /// The PCK certificate followed by its issuer chain, in PEM.
+    pub fn to_pem_chain(&self) -> String {
+        let mut chain = self.cert.trim_end().to_owned();
+        chain.push('\n');
+        chain.push_str(self.issuer_chain.trim_end());
+        chain.push('\n');
+        chain
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub struct PccsClient {
+    base_url: String,
+    platform: PckPlatformInfo,
+    http: reqwest::blocking::Client,
+}
+
+impl PccsClient {
+    /// Create a client talking to the PCCS at `base_url`.
+    ///
+    /// A default PCCS installation uses a self-signed TLS certificate, in
+    /// which case `accept_invalid_certs` has to be set. This mirrors the
+    /// `USE_SECURE_CERT` option of Intel's default QPL.
+    pub fn new(
+        base_url: &str,
+        platform: PckPlatformInfo,
+        accept_invalid_certs: bool,
+    ) -> Result<PccsClient, PccsError> {
+        let http = reqwest::blocking::Client::builder()
+            .danger_accept_invalid_certs(accept_invalid_certs)
+            .timeout(Duration::from_secs(30))
+            .build()?;
+
+        Ok(PccsClient {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            platform,
+            http,
+        })
+    }
+
+    /// Fetch the PCK certificate of the platform identified by `qe_id` and
+    /// its RSA-3072 encrypted PPID.
+    pub fn fetch_pck_cert(&self, qe_id: &[u8], enc_ppid: &[u8]) -> Result<PckCert, PccsError> {
+        if qe_id.len() != QE_ID_SIZE {
+            return Err(PccsError::InvalidParameter("qe_id"));
+        }
+        if enc_ppid.len() != ENC_PPID_SIZE {
+            return Err(PccsError::InvalidParameter("enc_ppid"));
+        }
+
+        let url = format!("{}{}", self.base_url, PCK_CERT_PATH);
+        let resp = self
+            .http
+            .get(&url)
+            .query(&[
+                ("encrypted_ppid", to_hex(enc_ppid)),
+                ("cpusvn", to_hex(&self.platform.cpu_svn)),
+                ("pcesvn", to_hex(&self.platform.pce_svn)),
+                ("pceid", to_hex(&self.platform.pce_id)),
+                ("qeid", to_hex(qe_id)),
+            ])
+            .send()?;
+
+        let code = resp.status().as_u16();
+        if code != 200 {
+            return Err(PccsError::Status(code, status_message(code)));
+        }
+
+        let header = |name: &'static str| -> Result<String, PccsError> {
+            resp.headers()
+                .get(name)
+                .ok_or(PccsError::MissingHeader(name))?
+                .to_str()
+                .map(|s| s.to_owned())
+                .map_err(|_| PccsError::Malformed(name))
+        };
+
+        let issuer_chain = percent_decode_str(&header(HEADER_ISSUER_CHAIN)?)
+            .decode_utf8()
+            .map_err(|_| PccsError::Malformed(HEADER_ISSUER_CHAIN))?
+            .into_owned();
+        let tcbm = header(HEADER_TCBM)?;
+        let fmspc = header(HEADER_FMSPC)?;
+        let ca_type = header(HEADER_CA_TYPE).ok();
+
+        let cert = resp.text()?;
+        if !cert.contains("-----BEGIN CERTIFICATE-----") {
+            return Err(PccsError::Malformed("PCK certificate is not PEM"));
+        }
+
+        Ok(PckCert {
+            cert,
+            issuer_chain,
+            tcbm,
+            fmspc,
+            ca_type,
+        })
+    }
+}
@@ -0,0 +1,188 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+#![crate_name = "dcapra"]
+#![crate_type = "staticlib"]
+
+#![cfg_attr(not(target_env = "sgx"), no_std)]
+#![cfg_attr(target_env = "sgx", feature(rustc_private))]
+
+extern crate sgx_types;
+extern crate sgx_dcap;
+extern crate sgx_tcrypto;
+extern crate sgx_trts;
+extern crate sgx_tse;
+#[cfg(not(target_env = "sgx"))]
+#[macro_use]
+extern crate sgx_tstd as std;
+
+extern crate base64;
+extern crate webpki;
+
+use sgx_types::*;
+use sgx_tcrypto::rsgx_sha256_slice;
+use sgx_tse::rsgx_create_report;
+
+use std::slice;
+use std::vec::Vec;
+
+mod pck;
+mod quote;
+
+extern "C" {
+    pub fn ocall_get_pck_cert_chain(
+        ret_val: *mut sgx_status_t,
+        p_chain: *mut u8,
+        maxlen: u32,
+        p_chain_len: *mut u32,
+    ) -> sgx_status_t;
+}
+
+// A PCK certificate together with its issuer chain is roughly 3 KB in PEM.
+const PCK_CHAIN_MAX_LEN: u32 = 8192;
+
+#[no_mangle]
+pub extern "C" fn enclave_create_report(
+    p_qe3_target : &sgx_target_info_t,
+    p_report: &mut sgx_report_t) -> u32 {
+    let empty_data: sgx_report_data_t = sgx_report_data_t::default();
+    match rsgx_create_report(p_qe3_target, &empty_data) {
+        Ok(report) => {
+            *p_report = report;
+            0
+        },
+        Err(x) => {
+            println!("rsgx_create_report failed! {:?}", x);
+            x as u32
+        }
+    }
+}
+
+fn get_pck_cert_chain() -> Result<Vec<u8>, sgx_status_t> {
+    let mut rt: sgx_status_t = sgx_status_t::SGX_ERROR_UNEXPECTED;
+    let mut chain: Vec<u8> = vec![0; PCK_CHAIN_MAX_LEN as usize];
+    let mut chain_len: u32 = 0;
+
+    let res = unsafe {
+        ocall_get_pck_cert_chain(
+            &mut rt as *mut sgx_status_t,
+            chain.as_mut_ptr(),
+            PCK_CHAIN_MAX_LEN,
+            &mut chain_len as *mut u32,
+        )
+    };
+
+    if res != sgx_status_t::SGX_SUCCESS {
+        return Err(res);
+    }
+    if rt != sgx_status_t::SGX_SUCCESS {
+        return Err(rt);
+    }
+    // chain_len comes from the untrusted side
+    if chain_len == 0 || chain_len > PCK_CHAIN_MAX_LEN {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    chain.truncate(chain_len as usize);
+    Ok(chain)
+}
+
+// Workflow:
+// (1) ocall to fetch the PCK certificate chain the app got from the PCCS
+// (2) verify the chain against the Intel SGX Root CA embedded at compile time
+// (3) report_data[..32] = SHA256(PCK cert DER), so that the resulting quote
+//     is bound to the verified PCK certificate
+fn pck_bound_report_data() -> Result<sgx_report_data_t, sgx_status_t> {
+    // (1)
+    let pem_chain = match get_pck_cert_chain() {
+        Ok(r) => r,
+        Err(e) => {
+            println!("ocall_get_pck_cert_chain failed! {:?}", e);
+            return Err(e);
+        }
+    };
+
+    // (2)
+    let der_chain = pck::pem_chain_to_der(&pem_chain)?;
+    pck::verify_pck_cert_chain(&der_chain)?;
+    println!("PCK certificate chain verified against Intel SGX Root CA");
+
+    // (3)
+    let pck_hash = rsgx_sha256_slice(&der_chain[0])?;
+    let mut report_data: sgx_report_data_t = sgx_report_data_t::default();
+    report_data.d[..32].copy_from_slice(&pck_hash);
+    Ok(report_data)
+}
+
+// Workflow:
+// (1) build report data bound to the verified PCK certificate
+// (2) ocall to get the QE target info and create a report for the QE
+// (3) ocall to get the quote, and check that it carries our report
+#[no_mangle]
+pub extern "C" fn enclave_get_pck_bound_quote(
+    p_quote: *mut u8,
+    maxlen: u32,
+    p_quote_len: &mut u32) -> sgx_status_t {
+    // (1)
+    let report_data = match pck_bound_report_data() {
+        Ok(r) => r,
+        Err(e) => return e,
+    };
+
+    // (2)
+    let qe_target = match quote::qe_get_target_info() {
+        Ok(r) => r,
+        Err(e) => return e,
+    };
+    let report = match rsgx_create_report(&qe_target, &report_data) {
+        Ok(r) => r,
+        Err(x) => {
+            println!("rsgx_create_report failed! {:?}", x);
+            return x;
+        }
+    };
+
+    // (3)
+    let quote = match quote::qe_get_quote(&report) {
+        Ok(r) => r,
+        Err(e) => return e,
+    };
+    if quote.len() > maxlen as usize {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    let out = unsafe { slice::from_raw_parts_mut(p_quote, quote.len()) };
+    out.copy_from_slice(&quote);
+    *p_quote_len = quote.len() as u32;
+    sgx_status_t::SGX_SUCCESS
+}
+
+// Verifies a quote with the Quote Verification Library of the host and
+// checks the QvE report inside the enclave, see quote::verify_quote.
+#[no_mangle]
+pub extern "C" fn enclave_verify_quote(
+    p_quote: *const u8,
+    quote_size: u32,
+    p_qv_result: &mut sgx_ql_qv_result_t) -> sgx_status_t {
+    let quote = unsafe { slice::from_raw_parts(p_quote, quote_size as usize) };
+    match quote::verify_quote(quote) {
+        Ok(result) => {
+            *p_qv_result = result;
+            sgx_status_t::SGX_SUCCESS
+        }
+        Err(e) => e,
+    }
+}
@@ -0,0 +1,98 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use std::prelude::v1::*;
+use std::str;
+use std::time::SystemTime;
+use std::untrusted::time::SystemTimeEx;
+
+use base64;
+use sgx_types::*;
+use webpki;
+
+// Intel SGX Provisioning Certification Root CA, DER encoded.
+// Downloaded by the top level Makefile from
+// https://certificates.trustedservices.intel.com/Intel_SGX_Provisioning_Certification_RootCA.cer
+pub const INTEL_SGX_ROOT_CA: &[u8] =
+    include_bytes!("../Intel_SGX_Provisioning_Certification_RootCA.cer");
+
+// PCK certificates and the PCK Platform/Processor CA are all signed with
+// ECDSA P-256 + SHA-256.
+type SignatureAlgorithms = &'static [&'static webpki::SignatureAlgorithm];
+static SUPPORTED_SIG_ALGS: SignatureAlgorithms = &[&webpki::ECDSA_P256_SHA256];
+
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+/// Split a concatenation of PEM certificates into DER blobs, preserving order.
+pub fn pem_chain_to_der(pem: &[u8]) -> Result<Vec<Vec<u8>>, sgx_status_t> {
+    let pem = str::from_utf8(pem).map_err(|_| sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+
+    let mut chain = Vec::new();
+    let mut rest = pem;
+    while let Some(begin) = rest.find(PEM_BEGIN) {
+        let body = &rest[begin + PEM_BEGIN.len()..];
+        let end = body
+            .find(PEM_END)
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        let b64: String = body[..end].chars().filter(|c| !c.is_whitespace()).collect();
+        let der = base64::decode(&b64).map_err(|_| sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        chain.push(der);
+        rest = &body[end + PEM_END.len()..];
+    }
+
+    if chain.is_empty() {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    Ok(chain)
+}
+
+/// Verify that `chain[0]` (the PCK certificate) chains up to the Intel SGX
+/// Root CA embedded in this enclave. The remaining entries are treated as
+/// intermediates; the root shipped by the PCCS is ignored as a trust anchor.
+pub fn verify_pck_cert_chain(chain: &[Vec<u8>]) -> Result<(), sgx_status_t> {
+    let (leaf, intermediates) = chain
+        .split_first()
+        .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+
+    let pck_cert = webpki::EndEntityCert::from(leaf).map_err(|e| {
+        println!("Bad PCK certificate: {:?}", e);
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    })?;
+
+    let root = webpki::trust_anchor_util::cert_der_as_trust_anchor(INTEL_SGX_ROOT_CA)
+        .map_err(|_| sgx_status_t::SGX_ERROR_UNEXPECTED)?;
+    let anchors = [root];
+
+    let intermediates: Vec<&[u8]> = intermediates.iter().map(|c| c.as_slice()).collect();
+    let now = webpki::Time::try_from(SystemTime::now())
+        .map_err(|_| sgx_status_t::SGX_ERROR_UNEXPECTED)?;
+
+    // PCK certificates carry no EKU extension, which webpki accepts for
+    // any usage. The client-cert path is used since no DNS name is involved.
+    pck_cert
+        .verify_is_valid_tls_client_cert(
+            SUPPORTED_SIG_ALGS,
+            &webpki::TLSClientTrustAnchors(&anchors),
+            &intermediates,
+            now,
+        )
+        .map_err(|e| {
+            println!("PCK certificate chain verification failed: {:?}", e);
+            sgx_status_t::SGX_ERROR_INVALID_SIGNATURE
+        })
+}
@@ -0,0 +1,186 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use std::prelude::v1::*;
+use std::mem;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::untrusted::time::SystemTimeEx;
+
+use sgx_dcap::DcapQuote;
+use sgx_trts::trts::rsgx_read_rand;
+use sgx_types::*;
+
+extern "C" {
+    pub fn ocall_qe_get_target_info(
+        ret_val: *mut sgx_quote3_error_t,
+        p_target_info: *mut sgx_target_info_t,
+    ) -> sgx_status_t;
+    pub fn ocall_qe_get_quote_size(
+        ret_val: *mut sgx_quote3_error_t,
+        p_quote_size: *mut u32,
+    ) -> sgx_status_t;
+    pub fn ocall_qe_get_quote(
+        ret_val: *mut sgx_quote3_error_t,
+        p_report: *const sgx_report_t,
+        quote_size: u32,
+        p_quote: *mut u8,
+    ) -> sgx_status_t;
+    pub fn ocall_qv_verify_quote(
+        ret_val: *mut sgx_quote3_error_t,
+        p_quote: *const u8,
+        quote_size: u32,
+        expiration_check_date: time_t,
+        p_collateral_expiration_status: *mut u32,
+        p_quote_verification_result: *mut sgx_ql_qv_result_t,
+        p_qve_report_info: *mut sgx_ql_qe_report_info_t,
+        supplemental_data_size: u32,
+        p_supplemental_data: *mut u8,
+    ) -> sgx_status_t;
+}
+
+// An ECDSA quote with a PCK certificate chain is around 5 KB.
+const QUOTE_MAX_LEN: u32 = 16384;
+
+// The lowest QvE ISVSVN we accept. The latest value is published in the
+// QvE Identity of the Intel PCS.
+const QVE_ISVSVN_THRESHOLD: sgx_isv_svn_t = 3;
+
+fn check(res: sgx_status_t, rt: sgx_quote3_error_t) -> Result<(), sgx_status_t> {
+    if res != sgx_status_t::SGX_SUCCESS {
+        return Err(res);
+    }
+    if rt != sgx_quote3_error_t::SGX_QL_SUCCESS {
+        println!("DCAP library returned {:?}", rt);
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+    Ok(())
+}
+
+pub fn qe_get_target_info() -> Result<sgx_target_info_t, sgx_status_t> {
+    let mut rt = sgx_quote3_error_t::SGX_QL_ERROR_UNEXPECTED;
+    let mut ti = sgx_target_info_t::default();
+    let res = unsafe { ocall_qe_get_target_info(&mut rt as *mut _, &mut ti as *mut _) };
+    check(res, rt)?;
+    Ok(ti)
+}
+
+// Workflow:
+// (1) ocall to get the quote size and the quote of `report` from the QE
+// (2) parse the quote and check that it carries our own report body, so
+//     the untrusted side cannot hand back a quote of another enclave
+pub fn qe_get_quote(report: &sgx_report_t) -> Result<Vec<u8>, sgx_status_t> {
+    // (1)
+    let mut rt = sgx_quote3_error_t::SGX_QL_ERROR_UNEXPECTED;
+    let mut quote_size: u32 = 0;
+    let res = unsafe { ocall_qe_get_quote_size(&mut rt as *mut _, &mut quote_size as *mut u32) };
+    check(res, rt)?;
+    // quote_size comes from the untrusted side
+    if quote_size == 0 || quote_size > QUOTE_MAX_LEN {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    let mut quote: Vec<u8> = vec![0; quote_size as usize];
+    let res = unsafe {
+        ocall_qe_get_quote(&mut rt as *mut _, report as *const _, quote_size, quote.as_mut_ptr())
+    };
+    check(res, rt)?;
+
+    // (2)
+    let parsed = match DcapQuote::parse(&quote) {
+        Ok(q) => q,
+        Err(e) => {
+            println!("Malformed quote: {}", e);
+            return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+        }
+    };
+    let body = parsed.report_body();
+    if body.mr_enclave.m != report.body.mr_enclave.m
+        || body.mr_signer.m != report.body.mr_signer.m
+        || body.report_data.d[..] != report.body.report_data.d[..]
+    {
+        println!("Quote does not match our report!");
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+
+    Ok(quote)
+}
+
+// Workflow:
+// (1) ocall to sgx_qv_verify_quote, asking the QvE for a report targeting
+//     this enclave, bound to a fresh nonce
+// (2) verify the QvE report and identity inside the enclave, so that the
+//     verification result cannot be forged by the untrusted side
+pub fn verify_quote(quote: &[u8]) -> Result<sgx_ql_qv_result_t, sgx_status_t> {
+    // (1)
+    let mut qve_report_info = sgx_ql_qe_report_info_t::default();
+    rsgx_read_rand(&mut qve_report_info.nonce.rand)?;
+    let mut self_target = sgx_target_info_t::default();
+    let ret = unsafe { sgx_self_target(&mut self_target as *mut _) };
+    if ret != sgx_status_t::SGX_SUCCESS {
+        return Err(ret);
+    }
+    qve_report_info.app_enclave_target_info = self_target;
+    let nonce = qve_report_info.nonce.rand;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| sgx_status_t::SGX_ERROR_UNEXPECTED)?
+        .as_secs() as time_t;
+    let mut collateral_expiration_status: u32 = 1;
+    let mut qv_result = sgx_ql_qv_result_t::SGX_QL_QV_RESULT_UNSPECIFIED;
+    let mut supplemental = vec![0_u8; mem::size_of::<sgx_ql_qv_supplemental_t>()];
+    let mut rt = sgx_quote3_error_t::SGX_QL_ERROR_UNEXPECTED;
+    let res = unsafe {
+        ocall_qv_verify_quote(
+            &mut rt as *mut _,
+            quote.as_ptr(),
+            quote.len() as u32,
+            now,
+            &mut collateral_expiration_status as *mut u32,
+            &mut qv_result as *mut _,
+            &mut qve_report_info as *mut _,
+            supplemental.len() as u32,
+            supplemental.as_mut_ptr(),
+        )
+    };
+    check(res, rt)?;
+
+    // (2)
+    if qve_report_info.nonce.rand != nonce {
+        println!("QvE report info has been replaced!");
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+    let rt = unsafe {
+        sgx_tvl_verify_qve_report_and_identity(
+            quote.as_ptr(),
+            quote.len() as u32,
+            &qve_report_info as *const _,
+            now,
+            collateral_expiration_status,
+            qv_result,
+            supplemental.as_ptr(),
+            supplemental.len() as u32,
+            QVE_ISVSVN_THRESHOLD,
+        )
+    };
+    check(sgx_status_t::SGX_SUCCESS, rt)?;
+
+    if collateral_expiration_status != 0 {
+        println!("Verification collateral has expired");
+    }
+    Ok(qv_result)
+}
@@ -0,0 +1,146 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! A plain HTTP stand-in for the PCCS `pckcert` endpoint, so that the
+//! PCCS client can be exercised in CI without a PCCS or SGX hardware.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+
+pub const PCK_CERT_PATH: &str = "/sgx/certification/v3/pckcert";
+
+/// The canned response for one platform.
+#[derive(Clone, Debug)]
+pub struct MockPlatform {
+    /// Hex encoded QE_ID the platform is registered under.
+    pub qe_id: String,
+    pub pck_cert: String,
+    pub issuer_chain: String,
+    pub tcbm: String,
+    pub fmspc: String,
+}
+
+// (name, expected hex length)
+const PCKCERT_PARAMS: &[(&str, usize)] = &[
+    ("encrypted_ppid", 768),
+    ("cpusvn", 32),
+    ("pcesvn", 4),
+    ("pceid", 4),
+    ("qeid", 32),
+];
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter_map(|kv| {
+            let mut it = kv.splitn(2, '=');
+            Some((it.next()?.to_owned(), it.next()?.to_owned()))
+        })
+        .collect()
+}
+
+fn write_response(
+    stream: &mut TcpStream,
+    code: u16,
+    reason: &str,
+    headers: &[(&str, String)],
+    body: &str,
+) -> io::Result<()> {
+    write!(stream, "HTTP/1.1 {} {}\r\n", code, reason)?;
+    for (name, value) in headers {
+        write!(stream, "{}: {}\r\n", name, value)?;
+    }
+    write!(
+        stream,
+        "Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn handle(mut stream: TcpStream, platforms: &[MockPlatform]) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // drain the request headers, the mock does not need them
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line == "\r\n" {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let target = parts.next().unwrap_or("");
+    let (path, query) = match target.find('?') {
+        Some(i) => (&target[..i], &target[i + 1..]),
+        None => (target, ""),
+    };
+
+    if method != "GET" || path != PCK_CERT_PATH {
+        return write_response(&mut stream, 404, "Not Found", &[], "");
+    }
+
+    let params = parse_query(query);
+    let valid = PCKCERT_PARAMS.iter().all(|(name, len)| {
+        params
+            .get(*name)
+            .map(|v| v.len() == *len && v.bytes().all(|b| b.is_ascii_hexdigit()))
+            .unwrap_or(false)
+    });
+    if !valid {
+        return write_response(&mut stream, 400, "Bad Request", &[], "");
+    }
+
+    let qe_id = params["qeid"].to_ascii_lowercase();
+    match platforms.iter().find(|p| p.qe_id.to_ascii_lowercase() == qe_id) {
+        Some(p) => {
+            let headers = [
+                (
+                    "SGX-PCK-Certificate-Issuer-Chain",
+                    utf8_percent_encode(&p.issuer_chain, NON_ALPHANUMERIC).to_string(),
+                ),
+                ("SGX-TCBm", p.tcbm.clone()),
+                ("SGX-FMSPC", p.fmspc.clone()),
+                ("SGX-PCK-Certificate-CA-Type", "processor".to_owned()),
+                ("Content-Type", "application/x-pem-file".to_owned()),
+            ];
+            write_response(&mut stream, 200, "OK", &headers, &p.pck_cert)
+        }
+        None => write_response(&mut stream, 461, "Platform Not Found", &[], ""),
+    }
+}
+
+/// Serve `platforms` on `listener` until the process exits.
+pub fn serve(listener: TcpListener, platforms: Vec<MockPlatform>) {
+    let platforms = Arc::new(platforms);
+    for stream in listener.incoming().flatten() {
+        let platforms = platforms.clone();
+        thread::spawn(move || {
+            if let Err(e) = handle(stream, &platforms) {
+                eprintln!("mock-pccs: {}", e);
+            }
+        });
+    }
+}
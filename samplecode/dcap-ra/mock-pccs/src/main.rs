@@ -0,0 +1,51 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+// Usage: mock-pccs <listen addr> <qe_id hex> <pck cert pem> <issuer chain pem> <tcbm> <fmspc>
+//
+// e.g. mock-pccs 127.0.0.1:8081 $QE_ID pck.pem chain.pem $TCBM 00906ea10000
+// and run the app with PCCS_URL=http://127.0.0.1:8081
+
+use std::env;
+use std::fs;
+use std::net::TcpListener;
+use std::process;
+
+use mock_pccs::MockPlatform;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 7 {
+        eprintln!(
+            "usage: {} <listen addr> <qe_id> <pck cert pem> <issuer chain pem> <tcbm> <fmspc>",
+            args[0]
+        );
+        process::exit(1);
+    }
+
+    let platform = MockPlatform {
+        qe_id: args[2].clone(),
+        pck_cert: fs::read_to_string(&args[3]).expect("cannot read the PCK certificate"),
+        issuer_chain: fs::read_to_string(&args[4]).expect("cannot read the issuer chain"),
+        tcbm: args[5].clone(),
+        fmspc: args[6].clone(),
+    };
+
+    let listener = TcpListener::bind(&args[1]).expect("cannot bind listen address");
+    println!("mock-pccs listening on {}", args[1]);
+    mock_pccs::serve(listener, vec![platform]);
+}
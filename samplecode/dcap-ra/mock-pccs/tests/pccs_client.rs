@@ -0,0 +1,89 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+// Runs the app's PCCS client against the mock server. Needs neither SGX
+// hardware nor a real PCCS.
+
+#[path = "../../app/src/pccs_client.rs"]
+#[allow(dead_code)]
+mod pccs_client;
+
+use std::net::TcpListener;
+use std::thread;
+
+use mock_pccs::MockPlatform;
+use pccs_client::{PccsClient, PccsError, PckPlatformInfo};
+
+// Not real certificates: the client only transports them, the enclave
+// verifies them.
+const PCK_CERT: &str = "-----BEGIN CERTIFICATE-----\nTU9DSyBQQ0sgQ0VSVA==\n-----END CERTIFICATE-----\n";
+const ISSUER_CHAIN: &str = "-----BEGIN CERTIFICATE-----\nTU9DSyBQQ0sgQ0E=\n-----END CERTIFICATE-----\n\
+                            -----BEGIN CERTIFICATE-----\nTU9DSyBST09UIENB\n-----END CERTIFICATE-----\n";
+const QE_ID: [u8; 16] = [0x5a; 16];
+
+fn start_mock() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let platform = MockPlatform {
+        qe_id: QE_ID.iter().map(|b| format!("{:02x}", b)).collect(),
+        pck_cert: PCK_CERT.to_owned(),
+        issuer_chain: ISSUER_CHAIN.to_owned(),
+        tcbm: "0c0c0303ffff010000000000000000000a00".to_owned(),
+        fmspc: "00906ea10000".to_owned(),
+    };
+    thread::spawn(move || mock_pccs::serve(listener, vec![platform]));
+    url
+}
+
+fn platform() -> PckPlatformInfo {
+    PckPlatformInfo {
+        pce_id: [0, 0],
+        cpu_svn: [0x0c; 16],
+        pce_svn: [0x0a, 0],
+    }
+}
+
+#[test]
+fn fetch_pck_cert() {
+    let client = PccsClient::new(&start_mock(), platform(), false).unwrap();
+    let cert = client.fetch_pck_cert(&QE_ID, &[0x11; 384]).unwrap();
+
+    assert_eq!(cert.cert, PCK_CERT);
+    assert_eq!(cert.issuer_chain, ISSUER_CHAIN);
+    assert_eq!(cert.fmspc, "00906ea10000");
+    assert_eq!(cert.ca_type.as_deref(), Some("processor"));
+    assert_eq!(cert.to_pem_chain().matches("BEGIN CERTIFICATE").count(), 3);
+    assert!(cert.to_pem_chain().starts_with(PCK_CERT));
+}
+
+#[test]
+fn fetch_pck_cert_unknown_platform() {
+    let client = PccsClient::new(&start_mock(), platform(), false).unwrap();
+    match client.fetch_pck_cert(&[0x00; 16], &[0x11; 384]) {
+        Err(PccsError::Status(461, _)) => {}
+        r => panic!("unexpected result {:?}", r),
+    }
+}
+
+#[test]
+fn fetch_pck_cert_invalid_parameter() {
+    let client = PccsClient::new(&start_mock(), platform(), false).unwrap();
+    match client.fetch_pck_cert(&QE_ID, &[0x11; 16]) {
+        Err(PccsError::InvalidParameter("enc_ppid")) => {}
+        r => panic!("unexpected result {:?}", r),
+    }
+}
@@ -18,13 +18,59 @@
 extern crate sgx_types;
 extern crate sgx_urts;
 use sgx_types::*;
+use sgx_types::marker::ContiguousMemory;
 use sgx_urts::SgxEnclave;
+use sgx_urts::channel::SgxChannelReader;
+use std::thread;
 
 static ENCLAVE_FILE: &'static str = "enclave.signed.so";
 
 extern {
     fn say_something(eid: sgx_enclave_id_t, retval: *mut sgx_status_t,
                      some_string: *const u8, len: usize) -> sgx_status_t;
+    fn select_all(eid: sgx_enclave_id_t, retval: *mut sgx_status_t,
+                  chan: *mut u8, chan_len: usize) -> sgx_status_t;
+}
+
+// Must match the Row of the enclave.
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct Row {
+    key: [u8; 4],
+    value: [u8; 32],
+}
+
+unsafe impl ContiguousMemory for Row {}
+
+// Runs select_all in a second thread and drains its results through a
+// channel of 64 rows.
+fn stream_rows(eid: sgx_enclave_id_t) -> Result<Vec<Row>, sgx_status_t> {
+    let mut reader = SgxChannelReader::<Row>::new(64);
+    let chan = reader.as_mut_ptr() as usize;
+    let chan_len = reader.byte_len();
+
+    let ecall = thread::spawn(move || {
+        let mut retval = sgx_status_t::SGX_SUCCESS;
+        let result = unsafe { select_all(eid, &mut retval, chan as *mut u8, chan_len) };
+        match result {
+            sgx_status_t::SGX_SUCCESS => retval,
+            _ => result,
+        }
+    });
+
+    // The enclave never closes the channel if the ECALL fails early.
+    let mut rows = Vec::new();
+    while !reader.is_closed() && !ecall.is_finished() {
+        rows.extend(reader.read_available());
+        thread::yield_now();
+    }
+    let status = ecall.join().unwrap();
+    rows.extend(reader.read_available());
+
+    match status {
+        sgx_status_t::SGX_SUCCESS => Ok(rows),
+        e => Err(e),
+    }
 }
 
 fn init_enclave() -> SgxResult<SgxEnclave> {
@@ -75,5 +121,18 @@ fn main() {
 
     println!("[+] say_something success...");
 
+    match stream_rows(enclave.geteid()) {
+        Ok(rows) => {
+            println!("[+] select_all streamed {} rows", rows.len());
+            let first = rows.first().map(|r| u32::from_be_bytes(r.key));
+            let last = rows.last().map(|r| u32::from_be_bytes(r.key));
+            println!("[+] keys {:?} ..= {:?}", first, last);
+        },
+        Err(x) => {
+            println!("[-] ECALL Enclave Failed {}!", x.as_str());
+            return;
+        }
+    }
+
     enclave.destroy();
 }
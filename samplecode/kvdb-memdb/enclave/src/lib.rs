@@ -30,7 +30,9 @@ extern crate kvdb;
 extern crate kvdb_memorydb;
 
 use sgx_types::*;
+use sgx_types::marker::ContiguousMemory;
 use std::string::String;
+use std::sync::SgxChannel;
 use std::vec::Vec;
 use std::io::{self, Write};
 use std::slice;
@@ -83,4 +85,51 @@ pub extern "C" fn say_something(some_string: *const u8, some_len: usize) -> sgx_
 
 
     sgx_status_t::SGX_SUCCESS
-}
\ No newline at end of file
+}
+pub const ROW_COUNT: u32 = 10_000;
+
+/// A row of the table streamed by `select_all`. The host declares the
+/// same layout.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct Row {
+    pub key: [u8; 4],
+    pub value: [u8; 32],
+}
+
+unsafe impl ContiguousMemory for Row {}
+
+/// Fills a table with `ROW_COUNT` rows and streams all of them to the host
+/// through the `SgxChannel` at `chan`, without collecting the result set in
+/// the enclave.
+#[no_mangle]
+pub extern "C" fn select_all(chan: *mut u8, chan_len: usize) -> sgx_status_t {
+    let mut chan = match unsafe { SgxChannel::<Row>::from_raw_parts(chan, chan_len) } {
+        Ok(chan) => chan,
+        Err(e) => return e,
+    };
+
+    let db = kvdb_memorydb::create(0);
+    let mut batch = DBTransaction::new();
+    for i in 0..ROW_COUNT {
+        let mut value = [0_u8; 32];
+        value[..4].copy_from_slice(&i.to_le_bytes());
+        batch.put(None, &i.to_be_bytes(), &value);
+    }
+    if db.write(batch).is_err() {
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    // SELECT * FROM table
+    for (key, value) in db.iter(None) {
+        let mut row = Row { key: [0; 4], value: [0; 32] };
+        row.key.copy_from_slice(&key);
+        row.value.copy_from_slice(&value);
+        if let Err(e) = chan.send(&row) {
+            return e;
+        }
+    }
+    println!("select_all streamed {} rows", chan.sent());
+
+    sgx_status_t::SGX_SUCCESS
+}
@@ -0,0 +1,178 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+extern crate sgx_types;
+extern crate sgx_urts;
+use sgx_types::*;
+use sgx_urts::SgxEnclave;
+use std::ptr;
+use std::slice;
+use std::sync::Mutex;
+
+static ENCLAVE_FILE: &'static str = "enclave.signed.so";
+
+extern {
+    fn la_session_request(eid: sgx_enclave_id_t, retval: *mut sgx_status_t,
+                          src_enclave_id: sgx_enclave_id_t,
+                          dh_msg1: *mut sgx_dh_msg1_t) -> sgx_status_t;
+    fn la_exchange_report(eid: sgx_enclave_id_t, retval: *mut sgx_status_t,
+                          src_enclave_id: sgx_enclave_id_t,
+                          self_enclave_id: sgx_enclave_id_t,
+                          dh_msg2: *const sgx_dh_msg2_t,
+                          dh_msg3: *mut sgx_dh_msg3_t) -> sgx_status_t;
+    fn la_connect(eid: sgx_enclave_id_t, retval: *mut sgx_status_t,
+                  self_enclave_id: sgx_enclave_id_t,
+                  peer_enclave_id: sgx_enclave_id_t) -> sgx_status_t;
+    fn la_send_message(eid: sgx_enclave_id_t, retval: *mut sgx_status_t,
+                       message: *const u8, len: usize) -> sgx_status_t;
+    fn la_echo_message(eid: sgx_enclave_id_t, retval: *mut sgx_status_t) -> sgx_status_t;
+    fn la_recv_message(eid: sgx_enclave_id_t, retval: *mut sgx_status_t) -> sgx_status_t;
+}
+
+// Frames in flight between the enclaves: (source, destination, frame).
+// The app only relays them, it can not read or forge them.
+static FRAMES: Mutex<Vec<(sgx_enclave_id_t, sgx_enclave_id_t, Vec<u8>)>> = Mutex::new(Vec::new());
+
+fn check(result: sgx_status_t, retval: sgx_status_t) -> SgxError {
+    match (result, retval) {
+        (sgx_status_t::SGX_SUCCESS, sgx_status_t::SGX_SUCCESS) => Ok(()),
+        (sgx_status_t::SGX_SUCCESS, err) => Err(err),
+        (err, _) => Err(err),
+    }
+}
+
+fn status(result: SgxError) -> sgx_status_t {
+    match result {
+        Ok(()) => sgx_status_t::SGX_SUCCESS,
+        Err(e) => e,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn session_request_ocall(src_enclave_id: sgx_enclave_id_t,
+                                        dest_enclave_id: sgx_enclave_id_t,
+                                        dh_msg1: *mut sgx_dh_msg1_t) -> sgx_status_t {
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let result = unsafe {
+        la_session_request(dest_enclave_id, &mut retval, src_enclave_id, dh_msg1)
+    };
+    status(check(result, retval))
+}
+
+#[no_mangle]
+pub extern "C" fn exchange_report_ocall(src_enclave_id: sgx_enclave_id_t,
+                                        dest_enclave_id: sgx_enclave_id_t,
+                                        dh_msg2: *const sgx_dh_msg2_t,
+                                        dh_msg3: *mut sgx_dh_msg3_t) -> sgx_status_t {
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let result = unsafe {
+        la_exchange_report(dest_enclave_id, &mut retval,
+                           src_enclave_id, dest_enclave_id, dh_msg2, dh_msg3)
+    };
+    status(check(result, retval))
+}
+
+#[no_mangle]
+pub extern "C" fn send_frame_ocall(src_enclave_id: sgx_enclave_id_t,
+                                   dest_enclave_id: sgx_enclave_id_t,
+                                   frame: *const u8,
+                                   frame_len: usize) -> sgx_status_t {
+    let frame = unsafe { slice::from_raw_parts(frame, frame_len) };
+    FRAMES.lock().unwrap().push((src_enclave_id, dest_enclave_id, frame.to_vec()));
+    sgx_status_t::SGX_SUCCESS
+}
+
+#[no_mangle]
+pub extern "C" fn recv_frame_ocall(src_enclave_id: sgx_enclave_id_t,
+                                   dest_enclave_id: sgx_enclave_id_t,
+                                   frame_buf: *mut u8,
+                                   frame_buf_len: usize,
+                                   frame_len: *mut usize) -> sgx_status_t {
+    let mut frames = FRAMES.lock().unwrap();
+    let pos = match frames.iter().position(|f| f.0 == src_enclave_id && f.1 == dest_enclave_id) {
+        Some(pos) => pos,
+        None => return sgx_status_t::SGX_ERROR_INVALID_STATE,
+    };
+    if frames[pos].2.len() > frame_buf_len {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    let (_, _, frame) = frames.remove(pos);
+    unsafe {
+        ptr::copy_nonoverlapping(frame.as_ptr(), frame_buf, frame.len());
+        *frame_len = frame.len();
+    }
+    sgx_status_t::SGX_SUCCESS
+}
+
+fn init_enclave() -> SgxResult<SgxEnclave> {
+    let mut launch_token: sgx_launch_token_t = [0; 1024];
+    let mut launch_token_updated: i32 = 0;
+    // call sgx_create_enclave to initialize an enclave instance
+    // Debug Support: set 2nd parameter to 1
+    let debug = 1;
+    let mut misc_attr = sgx_misc_attribute_t {secs_attr: sgx_attributes_t { flags:0, xfrm:0}, misc_select:0};
+    SgxEnclave::create(ENCLAVE_FILE,
+                       debug,
+                       &mut launch_token,
+                       &mut launch_token_updated,
+                       &mut misc_attr)
+}
+
+fn run(initiator: &SgxEnclave, responder: &SgxEnclave) -> SgxError {
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+
+    // the handshake runs through session_request_ocall and exchange_report_ocall
+    let result = unsafe {
+        la_connect(initiator.geteid(), &mut retval, initiator.geteid(), responder.geteid())
+    };
+    check(result, retval)?;
+
+    let message = b"hello from the initiator";
+    let result = unsafe {
+        la_send_message(initiator.geteid(), &mut retval, message.as_ptr(), message.len())
+    };
+    check(result, retval)?;
+    if let Some(frame) = FRAMES.lock().unwrap().first() {
+        println!("[+] relaying {} encrypted bytes", frame.2.len());
+    }
+
+    let result = unsafe { la_echo_message(responder.geteid(), &mut retval) };
+    check(result, retval)?;
+    let result = unsafe { la_recv_message(initiator.geteid(), &mut retval) };
+    check(result, retval)
+}
+
+fn main() {
+    // two instances of the same enclave
+    let (initiator, responder) = match (init_enclave(), init_enclave()) {
+        (Ok(initiator), Ok(responder)) => {
+            println!("[+] Init Enclaves Successful {} {}!", initiator.geteid(), responder.geteid());
+            (initiator, responder)
+        },
+        (Err(x), _) | (_, Err(x)) => {
+            println!("[-] Init Enclave Failed {}!", x.as_str());
+            return;
+        },
+    };
+
+    match run(&initiator, &responder) {
+        Ok(()) => println!("[+] local attestation channel success..."),
+        Err(x) => println!("[-] local attestation channel failed {}!", x.as_str()),
+    }
+
+    initiator.destroy();
+    responder.destroy();
+}
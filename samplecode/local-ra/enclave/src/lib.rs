@@ -0,0 +1,247 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+#![crate_name = "localraenclave"]
+#![crate_type = "staticlib"]
+
+#![cfg_attr(not(target_env = "sgx"), no_std)]
+#![cfg_attr(target_env = "sgx", feature(rustc_private))]
+
+extern crate sgx_types;
+extern crate sgx_tse;
+extern crate sgx_tdh;
+#[cfg(not(target_env = "sgx"))]
+#[macro_use]
+extern crate sgx_tstd as std;
+
+use sgx_types::*;
+use sgx_tdh::{SgxDhMsg1, SgxDhMsg2, SgxDhMsg3, SgxDhResponder};
+use sgx_tdh::channel::{LocalChannel, LocalConnector, LocalTransport, LOCAL_CHANNEL_FRAME_OVERHEAD};
+use sgx_tse::rsgx_self_report;
+use std::mem;
+use std::slice;
+use std::str;
+use std::sync::SgxMutex;
+use std::vec::Vec;
+
+// largest message the app is expected to pass through the channel
+const MAX_MESSAGE_SIZE: usize = 4096;
+
+extern "C" {
+    fn session_request_ocall(ret: *mut sgx_status_t,
+                             src_enclave_id: sgx_enclave_id_t,
+                             dest_enclave_id: sgx_enclave_id_t,
+                             dh_msg1: *mut sgx_dh_msg1_t) -> sgx_status_t;
+    fn exchange_report_ocall(ret: *mut sgx_status_t,
+                             src_enclave_id: sgx_enclave_id_t,
+                             dest_enclave_id: sgx_enclave_id_t,
+                             dh_msg2: *const sgx_dh_msg2_t,
+                             dh_msg3: *mut sgx_dh_msg3_t) -> sgx_status_t;
+    fn send_frame_ocall(ret: *mut sgx_status_t,
+                        src_enclave_id: sgx_enclave_id_t,
+                        dest_enclave_id: sgx_enclave_id_t,
+                        frame: *const u8,
+                        frame_len: usize) -> sgx_status_t;
+    fn recv_frame_ocall(ret: *mut sgx_status_t,
+                        src_enclave_id: sgx_enclave_id_t,
+                        dest_enclave_id: sgx_enclave_id_t,
+                        frame_buf: *mut u8,
+                        frame_buf_len: usize,
+                        frame_len: *mut usize) -> sgx_status_t;
+}
+
+fn check(result: sgx_status_t, retval: sgx_status_t) -> SgxError {
+    match (result, retval) {
+        (sgx_status_t::SGX_SUCCESS, sgx_status_t::SGX_SUCCESS) => Ok(()),
+        (sgx_status_t::SGX_SUCCESS, err) => Err(err),
+        (err, _) => Err(err),
+    }
+}
+
+fn status(result: SgxError) -> sgx_status_t {
+    match result {
+        Ok(()) => sgx_status_t::SGX_SUCCESS,
+        Err(e) => e,
+    }
+}
+
+// The app relays the messages of the handshake and the channel frames
+// between the two enclaves.
+struct OcallTransport {
+    self_enclave_id: sgx_enclave_id_t,
+}
+
+impl LocalTransport for OcallTransport {
+    fn send_frame(&mut self, peer: sgx_enclave_id_t, frame: &[u8]) -> SgxError {
+        let mut retval = sgx_status_t::SGX_SUCCESS;
+        let result = unsafe {
+            send_frame_ocall(&mut retval, self.self_enclave_id, peer, frame.as_ptr(), frame.len())
+        };
+        check(result, retval)
+    }
+
+    fn recv_frame(&mut self, peer: sgx_enclave_id_t) -> SgxResult<Vec<u8>> {
+        let mut retval = sgx_status_t::SGX_SUCCESS;
+        let mut frame = vec![0_u8; MAX_MESSAGE_SIZE + LOCAL_CHANNEL_FRAME_OVERHEAD];
+        let mut frame_len = 0_usize;
+        let result = unsafe {
+            recv_frame_ocall(&mut retval, peer, self.self_enclave_id,
+                             frame.as_mut_ptr(), frame.len(), &mut frame_len)
+        };
+        check(result, retval)?;
+        if frame_len > frame.len() {
+            return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+        }
+        frame.truncate(frame_len);
+        Ok(frame)
+    }
+}
+
+impl LocalConnector for OcallTransport {
+    fn session_request(&mut self, peer: sgx_enclave_id_t, msg1: &mut SgxDhMsg1) -> SgxError {
+        let mut retval = sgx_status_t::SGX_SUCCESS;
+        let result = unsafe {
+            session_request_ocall(&mut retval, self.self_enclave_id, peer, msg1)
+        };
+        check(result, retval)
+    }
+
+    fn exchange_report(&mut self, peer: sgx_enclave_id_t, msg2: &SgxDhMsg2) -> SgxResult<SgxDhMsg3> {
+        let mut retval = sgx_status_t::SGX_SUCCESS;
+        let mut dh_msg3 = sgx_dh_msg3_t::default();
+        let result = unsafe {
+            exchange_report_ocall(&mut retval, self.self_enclave_id, peer, msg2, &mut dh_msg3)
+        };
+        check(result, retval)?;
+
+        // the EDL passes a message 3 without additional properties
+        if dh_msg3.msg3_body.additional_prop_length != 0 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        unsafe {
+            SgxDhMsg3::from_raw_dh_msg3_t(&mut dh_msg3, mem::size_of::<sgx_dh_msg3_t>() as u32)
+        }.ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    }
+}
+
+// responder side: the session in progress
+static RESPONDER: SgxMutex<Option<SgxDhResponder>> = SgxMutex::new(None);
+static CHANNEL: SgxMutex<Option<LocalChannel<OcallTransport>>> = SgxMutex::new(None);
+
+// Both ends run the same enclave: the peer must have the same MRENCLAVE and
+// the same debug attribute.
+fn verify_peer(peer: &sgx_dh_session_enclave_identity_t) -> SgxError {
+    let report = rsgx_self_report();
+    if peer.mr_enclave() != report.body.mr_enclave() || peer.mr_signer() != report.body.mr_signer() {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_SIGNATURE);
+    }
+    if (peer.attributes.flags & SGX_FLAGS_DEBUG) != (report.body.attributes.flags & SGX_FLAGS_DEBUG) {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_ATTRIBUTE);
+    }
+    Ok(())
+}
+
+fn with_channel<F>(f: F) -> sgx_status_t
+    where F: FnOnce(&mut LocalChannel<OcallTransport>) -> SgxError {
+    match CHANNEL.lock().unwrap().as_mut() {
+        Some(channel) => status(f(channel)),
+        None => sgx_status_t::SGX_ERROR_INVALID_STATE,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn la_session_request(src_enclave_id: sgx_enclave_id_t,
+                                     dh_msg1: *mut sgx_dh_msg1_t) -> sgx_status_t {
+    let mut responder = SgxDhResponder::init_session();
+    let result = responder.gen_msg1(unsafe { &mut *dh_msg1 });
+    if result.is_ok() {
+        println!("session request from enclave {}", src_enclave_id);
+        *RESPONDER.lock().unwrap() = Some(responder);
+    }
+    status(result)
+}
+
+#[no_mangle]
+pub extern "C" fn la_exchange_report(src_enclave_id: sgx_enclave_id_t,
+                                     self_enclave_id: sgx_enclave_id_t,
+                                     dh_msg2: *const sgx_dh_msg2_t,
+                                     dh_msg3: *mut sgx_dh_msg3_t) -> sgx_status_t {
+    let mut responder = match RESPONDER.lock().unwrap().take() {
+        Some(responder) => responder,
+        None => return sgx_status_t::SGX_ERROR_INVALID_STATE,
+    };
+
+    let transport = OcallTransport { self_enclave_id: self_enclave_id };
+    let mut msg3 = SgxDhMsg3::new();
+    let result = LocalChannel::accept(src_enclave_id, transport, &mut responder,
+                                      unsafe { &*dh_msg2 }, &mut msg3)
+        .and_then(|channel| {
+            verify_peer(channel.peer_identity())?;
+            let msg3_len = msg3.calc_raw_sealed_data_size();
+            if msg3_len as usize != mem::size_of::<sgx_dh_msg3_t>() {
+                return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+            }
+            unsafe { msg3.to_raw_dh_msg3_t(dh_msg3, msg3_len) }
+                .ok_or(sgx_status_t::SGX_ERROR_UNEXPECTED)?;
+            println!("[{}] channel accepted from enclave {}", self_enclave_id, src_enclave_id);
+            *CHANNEL.lock().unwrap() = Some(channel);
+            Ok(())
+        });
+    status(result)
+}
+
+#[no_mangle]
+pub extern "C" fn la_connect(self_enclave_id: sgx_enclave_id_t,
+                             peer_enclave_id: sgx_enclave_id_t) -> sgx_status_t {
+    let transport = OcallTransport { self_enclave_id: self_enclave_id };
+    let result = LocalChannel::connect(peer_enclave_id, transport).and_then(|channel| {
+        verify_peer(channel.peer_identity())?;
+        println!("[{}] channel established with enclave {}", self_enclave_id, peer_enclave_id);
+        *CHANNEL.lock().unwrap() = Some(channel);
+        Ok(())
+    });
+    status(result)
+}
+
+#[no_mangle]
+pub extern "C" fn la_send_message(message: *const u8, len: usize) -> sgx_status_t {
+    if len > MAX_MESSAGE_SIZE {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    let message = unsafe { slice::from_raw_parts(message, len) };
+    with_channel(|channel| channel.send(message))
+}
+
+#[no_mangle]
+pub extern "C" fn la_echo_message() -> sgx_status_t {
+    with_channel(|channel| {
+        let message = channel.recv()?;
+        println!("received {:?}", str::from_utf8(&message).unwrap_or("<binary>"));
+        let mut reply = b"echo: ".to_vec();
+        reply.extend_from_slice(&message);
+        reply.truncate(MAX_MESSAGE_SIZE);
+        channel.send(&reply)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn la_recv_message() -> sgx_status_t {
+    with_channel(|channel| {
+        let message = channel.recv()?;
+        println!("received {:?}", str::from_utf8(&message).unwrap_or("<binary>"));
+        Ok(())
+    })
+}
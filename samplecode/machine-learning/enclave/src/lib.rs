@@ -33,6 +33,7 @@ use std::untrusted::time::SystemTimeEx;
 
 extern crate rusty_machine;
 extern crate sgx_rand as rand;
+extern crate sgx_tcrypto;
 extern crate serde;
 extern crate serde_json;
 
@@ -43,6 +44,7 @@ use rusty_machine::learning::UnSupModel;
 use rand::thread_rng;
 use rand::distributions::IndependentSample;
 use rand::distributions::normal::Normal;
+use rand::distributions::Beta;
 use rand::{random, Closed01};
 
 use rusty_machine::learning::nnet::{NeuralNet, BCECriterion};
@@ -52,6 +54,14 @@ use rusty_machine::learning::optim::grad_desc::StochasticGD;
 
 use rusty_machine::learning::SupModel;
 
+use sgx_tcrypto::blake3::Blake3Hasher;
+use sgx_tcrypto::rsgx_sha256_slice;
+
+// The size of the buffer hashed by the digest benchmark, and how many times
+// it is hashed. The buffer has to fit in the enclave heap.
+const DIGEST_BENCH_SIZE: usize = 8 * 1024 * 1024;
+const DIGEST_BENCH_ROUNDS: usize = 8;
+
 fn generate_data(centroids: &Matrix<f64>,
                  points_per_centroid: usize,
                  noise: f64)
@@ -84,16 +94,62 @@ fn generate_data(centroids: &Matrix<f64>,
                 raw_cluster_data)
 }
 
+// Draws `count` centroids with `dims` coordinates in [-1, 1], each
+// coordinate from a Beta(2, 2) distribution rescaled to that interval, so
+// they tend towards the middle. Draws again until every pair of centroids
+// is at least `min_distance` apart.
+fn generate_centroids(count: usize, dims: usize, min_distance: f64) -> Matrix<f64> {
+    let mut rng = thread_rng();
+    let beta = Beta::new(2.0, 2.0).unwrap();
+
+    loop {
+        let coords: Vec<f64> = (0..count * dims)
+            .map(|_| 2.0 * beta.ind_sample(&mut rng) - 1.0)
+            .collect();
+        let far_apart = (0..count).all(|i| {
+            (i + 1..count).all(|j| {
+                let a = &coords[i * dims..(i + 1) * dims];
+                let b = &coords[j * dims..(j + 1) * dims];
+                let d2: f64 = a.iter().zip(b.iter()).map(|(x, y)| (x - y) * (x - y)).sum();
+                d2.sqrt() >= min_distance
+            })
+        });
+        if far_apart {
+            return Matrix::new(count, dims, coords);
+        }
+    }
+}
+
 #[no_mangle]
 pub extern "C"
 fn sample_main() -> sgx_status_t {
     kmeans_sample();
+    digest_benchmark();
     nn_sample();
     iris_sample();
 
     sgx_status_t::SGX_SUCCESS
 }
 
+// Compares the time the two digests take on a model-sized buffer.
+fn digest_benchmark() {
+    println!("Model digest benchmark:");
+    let data = vec![0x5a_u8; DIGEST_BENCH_SIZE];
+    let total_mib = DIGEST_BENCH_SIZE * DIGEST_BENCH_ROUNDS / (1024 * 1024);
+
+    let now = SystemTime::now();
+    for _ in 0..DIGEST_BENCH_ROUNDS {
+        let _ = rsgx_sha256_slice(&data).unwrap();
+    }
+    println!("SHA-256 of {} MiB: {:?}", total_mib, now.elapsed().unwrap());
+
+    let now = SystemTime::now();
+    for _ in 0..DIGEST_BENCH_ROUNDS {
+        let _ = Blake3Hasher::hash(&data);
+    }
+    println!("BLAKE3 of {} MiB: {:?}", total_mib, now.elapsed().unwrap());
+}
+
 fn iris_sample() {
     println!("IRIS classification sample:");
     // Set the layer sizes - from input to output
@@ -366,8 +422,9 @@ fn kmeans_sample() {
 
     println!("Generating {0} samples from each centroids:",
              SAMPLES_PER_CENTROID);
-    // Choose two cluster centers, at (-0.5, -0.5) and (0, 0.5).
-    let centroids = Matrix::new(2, 2, vec![-0.5, -0.5, 0.0, 0.5]);
+    // Choose two cluster centers in [-1, 1] x [-1, 1], far enough apart for
+    // the clusters to be told apart.
+    let centroids = generate_centroids(2, 2, 0.8);
     println!("{}", centroids);
 
     // Generate some data randomly around the centroids
@@ -381,9 +438,12 @@ fn kmeans_sample() {
     // Our train function returns a Result<(), E>
     model.train(&samples).unwrap();
 
-    // Serialize the model to string
+    // Serialize the model to string, and record its digest so that the
+    // model can be checked before it is used again
     let model_json = serde_json::to_string(&model).unwrap();
     println!("serialized model = {}", model_json);
+    let model_digest = Blake3Hasher::hash(model_json.as_bytes());
+    println!("model digest (BLAKE3) = {:02x?}", model_digest);
 
     let centroids = model.centroids().as_ref().unwrap();
     println!("Model Centroids:\n{:.3}", centroids);
@@ -396,6 +456,11 @@ fn kmeans_sample() {
     println!("Samples closest to first centroid: {}", first.len());
     println!("Samples closest to second centroid: {}", second.len());
 
+    let digest = Blake3Hasher::hash(model_json.as_bytes());
+    if digest != model_digest {
+        println!("serialized model has been modified, refusing to load it");
+        return;
+    }
     let model_recovered : KMeansClassifier<KPlusPlus> = serde_json::from_str(&model_json).unwrap();
     println!("deserialized model = {:?}", model_recovered);
 
@@ -27,7 +27,7 @@ extern crate sgx_tstd as std;
 
 use std::collections;
 use std::untrusted::fs;
-use std::net::{self, SocketAddr};
+use std::net::{self, IpAddr, Ipv4Addr, SocketAddr};
 use std::str;
 use std::io;
 use std::string::String;
@@ -311,29 +311,25 @@ fn make_config(cert: &str) -> Arc<rustls::ClientConfig> {
     Arc::new(config)
 }
 
+// The addresses the client may connect to. The host name is resolved by
+// the untrusted host, which could otherwise send the client anywhere.
+const ALLOWED_ADDRESSES: &[IpAddr] = &[IpAddr::V4(Ipv4Addr::LOCALHOST)];
+
 // TODO: um, well, it turns out that openssl s_client/s_server
 // that we use for testing doesn't do ipv6.  So we can't actually
 // test ipv6 and hence kill this.
 fn lookup_ipv4(host: &str, port: u16) -> SocketAddr {
-    use std::net::ToSocketAddrs;
-
-    let addrs = (host, port).to_socket_addrs().unwrap();
-    for addr in addrs {
-        if let SocketAddr::V4(_) = addr {
-            return addr;
-        }
-    }
-
-    unreachable!("Cannot lookup address");
+    let addr = net::resolve_ipv4(host, port).expect("Cannot lookup address");
+    assert!(ALLOWED_ADDRESSES.contains(&addr.ip()), "{} is not an allowed address", addr);
+    addr
 }
 
 #[no_mangle]
 pub extern "C" fn run_client() {
     let port = 8443;
     let hostname = "localhost";
-    let ip = "127.0.0.1";
     let cert = "./ca.cert";
-    let addr = lookup_ipv4(ip, port);
+    let addr = lookup_ipv4(hostname, port);
     let flag_http = true;
 
     let config = make_config(cert);
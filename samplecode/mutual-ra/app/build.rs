@@ -28,4 +28,9 @@ fn main () {
     println!("cargo:rustc-link-search=native={}/lib64", sdk_dir);
     println!("cargo:rustc-link-lib=dylib=sgx_urts");
     println!("cargo:rustc-link-lib=dylib=sgx_uae_service");
+
+    if env::var("CARGO_FEATURE_DCAP").is_ok() {
+        println!("cargo:rustc-link-lib=dylib=sgx_dcap_ql");
+        println!("cargo:rustc-link-lib=dylib=sgx_dcap_quoteverify");
+    }
 }
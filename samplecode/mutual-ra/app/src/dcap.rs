@@ -0,0 +1,71 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+// The ocalls of Enclave_dcap.edl. They forward the enclave's requests to
+// the DCAP Quote Library and Quote Verification Library; the enclave
+// checks what they return.
+
+use sgx_types::*;
+use std::ptr;
+
+#[no_mangle]
+pub extern "C"
+fn ocall_qe_get_target_info(p_target_info: *mut sgx_target_info_t) -> sgx_quote3_error_t {
+    println!("Entering ocall_qe_get_target_info");
+    unsafe { sgx_qe_get_target_info(p_target_info) }
+}
+
+#[no_mangle]
+pub extern "C"
+fn ocall_qe_get_quote_size(p_quote_size: *mut u32) -> sgx_quote3_error_t {
+    unsafe { sgx_qe_get_quote_size(p_quote_size) }
+}
+
+#[no_mangle]
+pub extern "C"
+fn ocall_qe_get_quote(p_report: *const sgx_report_t,
+                      quote_size: u32,
+                      p_quote: *mut u8) -> sgx_quote3_error_t {
+    println!("Entering ocall_qe_get_quote");
+    unsafe { sgx_qe_get_quote(p_report, quote_size, p_quote) }
+}
+
+#[no_mangle]
+pub extern "C"
+fn ocall_qv_verify_quote(p_quote: *const u8,
+                         quote_size: u32,
+                         expiration_check_date: time_t,
+                         p_collateral_expiration_status: *mut u32,
+                         p_quote_verification_result: *mut sgx_ql_qv_result_t,
+                         p_qve_report_info: *mut sgx_ql_qe_report_info_t,
+                         supplemental_data_size: u32,
+                         p_supplemental_data: *mut u8) -> sgx_quote3_error_t {
+    println!("Entering ocall_qv_verify_quote");
+    // collateral is fetched from the PCCS configured in
+    // /etc/sgx_default_qcnl.conf
+    unsafe {
+        sgx_qv_verify_quote(p_quote,
+                            quote_size,
+                            ptr::null(),
+                            expiration_check_date,
+                            p_collateral_expiration_status,
+                            p_quote_verification_result,
+                            p_qve_report_info,
+                            supplemental_data_size,
+                            p_supplemental_data)
+    }
+}
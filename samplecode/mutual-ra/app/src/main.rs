@@ -28,6 +28,9 @@ use std::env;
 use std::net::{TcpListener, TcpStream, SocketAddr};
 use std::str;
 
+#[cfg(feature = "dcap")]
+mod dcap;
+
 const BUFFER_SIZE: usize = 1024;
 
 static ENCLAVE_FILE: &'static str = "enclave.signed.so";
@@ -49,26 +52,11 @@ fn ocall_sgx_init_quote(ret_ti: *mut sgx_target_info_t,
 }
 
 
-pub fn lookup_ipv4(host: &str, port: u16) -> SocketAddr {
-    use std::net::ToSocketAddrs;
-
-    let addrs = (host, port).to_socket_addrs().unwrap();
-    for addr in addrs {
-        if let SocketAddr::V4(_) = addr {
-            return addr;
-        }
-    }
-
-    unreachable!("Cannot lookup address");
-}
-
-
 #[no_mangle]
 pub extern "C"
-fn ocall_get_ias_socket(ret_fd : *mut c_int) -> sgx_status_t {
-    let port = 443;
-    let hostname = "api.trustedservices.intel.com";
-    let addr = lookup_ipv4(hostname, port);
+fn ocall_get_ias_socket(ip : *const [u8; 4], port : u16, ret_fd : *mut c_int) -> sgx_status_t {
+    // The enclave resolves IAS itself; connect to the address it chose.
+    let addr = SocketAddr::from((unsafe { *ip }, port));
     let sock = TcpStream::connect(&addr).expect("[-] Connect tls server failed!");
 
     unsafe {*ret_fd = sock.into_raw_fd();}
@@ -0,0 +1,38 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use std::prelude::v1::*;
+use sgx_ra_tls::Collateral;
+use sgx_types::*;
+
+/// A remote attestation scheme that binds a TLS public key to the enclave.
+///
+/// The quote returned by `get_quote` is embedded in an RA-TLS certificate
+/// (see `cert::gen_ra_tls_cert`). The peer checks that the quote commits to
+/// the key of that certificate and hands it to `verify_quote`. Both sides
+/// must be built with the same backend.
+pub trait AttestationProvider: Send + Sync {
+    /// Returns a quote of this enclave carrying `report_data`.
+    fn get_quote(&self, report_data: &sgx_report_data_t) -> Result<Vec<u8>, sgx_status_t>;
+
+    /// Checks the quote of a peer certificate, with the collateral the
+    /// certificate carries, if any.
+    ///
+    /// Returns `SGX_ERROR_UPDATE_NEEDED` if the peer is genuine but its
+    /// platform is out of date.
+    fn verify_quote(&self, quote: &[u8], collateral: &Collateral) -> Result<(), sgx_status_t>;
+}
@@ -0,0 +1,236 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use std::prelude::v1::*;
+use std::log::Level;
+use std::mem;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::untrusted::time::SystemTimeEx;
+
+use sgx_dcap::DcapQuote;
+use sgx_ra_tls::Collateral;
+use sgx_trts::trts::rsgx_read_rand;
+use sgx_tse::rsgx_create_report;
+use sgx_types::*;
+
+use attestation::AttestationProvider;
+use itertools::Itertools;
+
+extern "C" {
+    pub fn ocall_qe_get_target_info(
+        ret_val: *mut sgx_quote3_error_t,
+        p_target_info: *mut sgx_target_info_t,
+    ) -> sgx_status_t;
+    pub fn ocall_qe_get_quote_size(
+        ret_val: *mut sgx_quote3_error_t,
+        p_quote_size: *mut u32,
+    ) -> sgx_status_t;
+    pub fn ocall_qe_get_quote(
+        ret_val: *mut sgx_quote3_error_t,
+        p_report: *const sgx_report_t,
+        quote_size: u32,
+        p_quote: *mut u8,
+    ) -> sgx_status_t;
+    pub fn ocall_qv_verify_quote(
+        ret_val: *mut sgx_quote3_error_t,
+        p_quote: *const u8,
+        quote_size: u32,
+        expiration_check_date: time_t,
+        p_collateral_expiration_status: *mut u32,
+        p_quote_verification_result: *mut sgx_ql_qv_result_t,
+        p_qve_report_info: *mut sgx_ql_qe_report_info_t,
+        supplemental_data_size: u32,
+        p_supplemental_data: *mut u8,
+    ) -> sgx_status_t;
+}
+
+// An ECDSA quote with a PCK certificate chain is around 5 KB.
+const QUOTE_MAX_LEN: u32 = 16384;
+
+// The lowest QvE ISVSVN we accept. The latest value is published in the
+// QvE Identity of the Intel PCS.
+const QVE_ISVSVN_THRESHOLD: sgx_isv_svn_t = 3;
+
+fn check(res: sgx_status_t, rt: sgx_quote3_error_t) -> Result<(), sgx_status_t> {
+    if res != sgx_status_t::SGX_SUCCESS {
+        return Err(res);
+    }
+    if rt != sgx_quote3_error_t::SGX_QL_SUCCESS {
+        enclave_log!(Level::Error, "DCAP library returned {:?}", rt);
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+    Ok(())
+}
+
+// Workflow:
+// (1) ocall to get the QE target info and create a report for the QE
+// (2) ocall to get the quote size and the quote from the QE
+// (3) parse the quote and check that it carries our own report body, so
+//     the untrusted side cannot hand back a quote of another enclave
+fn get_quote(report_data: &sgx_report_data_t) -> Result<Vec<u8>, sgx_status_t> {
+    // (1)
+    let mut rt = sgx_quote3_error_t::SGX_QL_ERROR_UNEXPECTED;
+    let mut ti = sgx_target_info_t::default();
+    let res = unsafe { ocall_qe_get_target_info(&mut rt as *mut _, &mut ti as *mut _) };
+    check(res, rt)?;
+    let report = rsgx_create_report(&ti, report_data)?;
+
+    // (2)
+    let mut quote_size: u32 = 0;
+    let res = unsafe { ocall_qe_get_quote_size(&mut rt as *mut _, &mut quote_size as *mut u32) };
+    check(res, rt)?;
+    // quote_size comes from the untrusted side
+    if quote_size == 0 || quote_size > QUOTE_MAX_LEN {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    let mut quote: Vec<u8> = vec![0; quote_size as usize];
+    let res = unsafe {
+        ocall_qe_get_quote(&mut rt as *mut _, &report as *const _, quote_size, quote.as_mut_ptr())
+    };
+    check(res, rt)?;
+
+    // (3)
+    let parsed = match DcapQuote::parse(&quote) {
+        Ok(q) => q,
+        Err(e) => {
+            enclave_log!(Level::Error, "Malformed quote: {}", e);
+            return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+        }
+    };
+    let body = parsed.report_body();
+    if body.mr_enclave.m != report.body.mr_enclave.m
+        || body.mr_signer.m != report.body.mr_signer.m
+        || body.report_data.d[..] != report.body.report_data.d[..]
+    {
+        enclave_log!(Level::Error, "Quote does not match our report!");
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+
+    Ok(quote)
+}
+
+// Workflow:
+// (1) ocall to sgx_qv_verify_quote, asking the QvE for a report targeting
+//     this enclave, bound to a fresh nonce
+// (2) verify the QvE report and identity inside the enclave, so that the
+//     verification result cannot be forged by the untrusted side
+fn verify_quote(quote: &[u8]) -> Result<sgx_ql_qv_result_t, sgx_status_t> {
+    // (1)
+    let mut qve_report_info = sgx_ql_qe_report_info_t::default();
+    rsgx_read_rand(&mut qve_report_info.nonce.rand)?;
+    let mut self_target = sgx_target_info_t::default();
+    let ret = unsafe { sgx_self_target(&mut self_target as *mut _) };
+    if ret != sgx_status_t::SGX_SUCCESS {
+        return Err(ret);
+    }
+    qve_report_info.app_enclave_target_info = self_target;
+    let nonce = qve_report_info.nonce.rand;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| sgx_status_t::SGX_ERROR_UNEXPECTED)?
+        .as_secs() as time_t;
+    let mut collateral_expiration_status: u32 = 1;
+    let mut qv_result = sgx_ql_qv_result_t::SGX_QL_QV_RESULT_UNSPECIFIED;
+    let mut supplemental = vec![0_u8; mem::size_of::<sgx_ql_qv_supplemental_t>()];
+    let mut rt = sgx_quote3_error_t::SGX_QL_ERROR_UNEXPECTED;
+    let res = unsafe {
+        ocall_qv_verify_quote(
+            &mut rt as *mut _,
+            quote.as_ptr(),
+            quote.len() as u32,
+            now,
+            &mut collateral_expiration_status as *mut u32,
+            &mut qv_result as *mut _,
+            &mut qve_report_info as *mut _,
+            supplemental.len() as u32,
+            supplemental.as_mut_ptr(),
+        )
+    };
+    check(res, rt)?;
+
+    // (2)
+    if qve_report_info.nonce.rand != nonce {
+        enclave_log!(Level::Error, "QvE report info has been replaced!");
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+    let rt = unsafe {
+        sgx_tvl_verify_qve_report_and_identity(
+            quote.as_ptr(),
+            quote.len() as u32,
+            &qve_report_info as *const _,
+            now,
+            collateral_expiration_status,
+            qv_result,
+            supplemental.as_ptr(),
+            supplemental.len() as u32,
+            QVE_ISVSVN_THRESHOLD,
+        )
+    };
+    check(sgx_status_t::SGX_SUCCESS, rt)?;
+
+    if collateral_expiration_status != 0 {
+        enclave_log!(Level::Error, "Verification collateral has expired");
+    }
+    Ok(qv_result)
+}
+
+/// ECDSA attestation with the Intel SGX DCAP libraries, without a round trip
+/// to Intel. The collateral is fetched by the quote provider library of the
+/// host, so the certificates do not carry any.
+pub struct DcapProvider;
+
+impl DcapProvider {
+    pub fn new() -> DcapProvider {
+        DcapProvider
+    }
+}
+
+impl AttestationProvider for DcapProvider {
+    fn get_quote(&self, report_data: &sgx_report_data_t) -> Result<Vec<u8>, sgx_status_t> {
+        get_quote(report_data)
+    }
+
+    fn verify_quote(&self, quote: &[u8], _collateral: &Collateral) -> Result<(), sgx_status_t> {
+        let parsed = match DcapQuote::parse(quote) {
+            Ok(q) => q,
+            Err(e) => {
+                enclave_log!(Level::Error, "Malformed quote: {}", e);
+                return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+            }
+        };
+        let body = parsed.report_body();
+        enclave_log!(Level::Debug, "sgx quote version = {}", parsed.version());
+        enclave_log!(Level::Debug, "sgx quote mr_enclave = {:02x}", body.mr_enclave.m.iter().format(""));
+        enclave_log!(Level::Debug, "sgx quote mr_signer = {:02x}", body.mr_signer.m.iter().format(""));
+
+        let qv_result = verify_quote(quote)?;
+        enclave_log!(Level::Info, "Quote verification result: {}", qv_result);
+        match qv_result {
+            sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OK => Ok(()),
+            sgx_ql_qv_result_t::SGX_QL_QV_RESULT_CONFIG_NEEDED
+            | sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OUT_OF_DATE
+            | sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OUT_OF_DATE_CONFIG_NEEDED
+            | sgx_ql_qv_result_t::SGX_QL_QV_RESULT_SW_HARDENING_NEEDED
+            | sgx_ql_qv_result_t::SGX_QL_QV_RESULT_CONFIG_AND_SW_HARDENING_NEEDED => {
+                Err(sgx_status_t::SGX_ERROR_UPDATE_NEEDED)
+            }
+            _ => Err(sgx_status_t::SGX_ERROR_UNEXPECTED),
+        }
+    }
+}
@@ -0,0 +1,618 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use std::prelude::v1::*;
+use std::log::Level;
+use std::sync::OnceLock;
+use std::net::{resolve_ipv4, IpFilter, SocketAddr, TcpStream};
+use std::io::{BufReader, Read, ReadExt};
+use std::ptr;
+use std::str;
+use std::mem;
+use std::time::*;
+use std::untrusted::time::SystemTimeEx;
+use std::untrusted::fs;
+
+use sgx_types::*;
+use sgx_tse::*;
+use sgx_tcrypto::*;
+use sgx_tcrypto::x509::Certificate;
+use sgx_rand::*;
+use sgx_ra_tls::Collateral;
+
+use attestation::AttestationProvider;
+use hex;
+use https::{HttpsClient, HttpsResponse};
+use rustls;
+use webpki;
+use webpki_roots;
+use base64;
+use serde_json;
+use serde_json::Value;
+use chrono::prelude::*;
+use itertools::Itertools;
+
+pub const DEV_HOSTNAME:&'static str = "api.trustedservices.intel.com";
+pub const SIGRL_SUFFIX:&'static str = "/sgx/dev/attestation/v3/sigrl/";
+pub const REPORT_SUFFIX:&'static str = "/sgx/dev/attestation/v3/report";
+
+extern "C" {
+    #[allow(dead_code)]
+    pub fn ocall_get_update_info (ret_val: *mut sgx_status_t,
+                                  platformBlob: * const sgx_platform_info_t,
+                                  enclaveTrusted: i32,
+                                  update_info: * mut sgx_update_info_bit_t) -> sgx_status_t;
+}
+
+extern "C" {
+    pub fn ocall_sgx_init_quote ( ret_val : *mut sgx_status_t,
+                  ret_ti  : *mut sgx_target_info_t,
+                  ret_gid : *mut sgx_epid_group_id_t) -> sgx_status_t;
+    pub fn ocall_get_ias_socket ( ret_val : *mut sgx_status_t,
+                  ip      : *const [u8; 4],
+                  port    : u16,
+                  ret_fd  : *mut i32) -> sgx_status_t;
+    pub fn ocall_get_quote (ret_val            : *mut sgx_status_t,
+                p_sigrl            : *const u8,
+                sigrl_len          : u32,
+                p_report           : *const sgx_report_t,
+                quote_type         : sgx_quote_sign_type_t,
+                p_spid             : *const sgx_spid_t,
+                p_nonce            : *const sgx_quote_nonce_t,
+                p_qe_report        : *mut sgx_report_t,
+                p_quote            : *mut u8,
+                maxlen             : u32,
+                p_quote_len        : *mut u32) -> sgx_status_t;
+}
+
+
+fn ias_status_message(code: u16) -> &'static str {
+    match code {
+        200 => "OK Operation Successful",
+        401 => "Unauthorized Failed to authenticate or authorize request.",
+        404 => "Not Found GID does not refer to a valid EPID group ID.",
+        500 => "Internal error occurred",
+        503 => "Service is currently not able to process the request (due to
+            a temporary overloading or maintenance). This is a
+            temporary state – the same request can be repeated after
+            some time. ",
+        _ => "Unknown error occured",
+    }
+}
+
+fn parse_response_attn_report(resp : &HttpsResponse) -> (String, String, String){
+    enclave_log!(Level::Debug, "parse_response_attn_report");
+    enclave_log!(Level::Debug, "{}", ias_status_message(resp.status_code()));
+
+    let sig = resp.header("X-IASReport-Signature").unwrap_or("").to_string();
+    let cert = resp.header("X-IASReport-Signing-Certificate").unwrap_or("").to_string();
+
+    // The PEM encoded signing cert, followed by the CA cert
+    let sig_cert = percent_decode(cert);
+
+    let attn_report = str::from_utf8(resp.body()).unwrap().to_string();
+    enclave_log!(Level::Debug, "Attestation report: {}", attn_report);
+
+    (attn_report, sig, sig_cert)
+}
+
+fn percent_decode(orig: String) -> String {
+    let v:Vec<&str> = orig.split("%").collect();
+    let mut ret = String::new();
+    ret.push_str(v[0]);
+    if v.len() > 1 {
+        for s in v[1..].iter() {
+            ret.push(u8::from_str_radix(&s[0..2], 16).unwrap() as char);
+            ret.push_str(&s[2..]);
+        }
+    }
+    ret
+}
+
+fn parse_response_sigrl(resp : &HttpsResponse) -> Vec<u8> {
+    enclave_log!(Level::Debug, "parse_response_sigrl");
+    enclave_log!(Level::Debug, "{}", ias_status_message(resp.status_code()));
+
+    let resp_body = resp.body();
+    if resp_body.is_empty() {
+        return Vec::new();
+    }
+    enclave_log!(Level::Debug, "Base64-encoded SigRL: {:?}", resp_body);
+    base64::decode(str::from_utf8(resp_body).unwrap()).unwrap()
+}
+
+// The address of IAS, pinned by the first attestation. The enclave resolves
+// IAS itself with resolve_ipv4 once and from then on only connects to that
+// address: the host connects to exactly that address (see ias_socket), and
+// the peer address of the socket is checked against IAS_FILTER before the
+// enclave sends anything over it. A host that later answers with another
+// address, or connects somewhere else, is refused; the server itself is
+// authenticated by TLS.
+static IAS_ADDR: OnceLock<SocketAddr> = OnceLock::new();
+static IAS_FILTER: OnceLock<IpFilter> = OnceLock::new();
+
+fn ias_addr() -> Result<SocketAddr, sgx_status_t> {
+    if let Some(addr) = IAS_ADDR.get() {
+        return Ok(*addr);
+    }
+    let addr = resolve_ipv4(DEV_HOSTNAME, 443).map_err(|e| {
+        enclave_log!(Level::Error, "Cannot resolve {}: {}", DEV_HOSTNAME, e);
+        e
+    })?;
+    Ok(*IAS_ADDR.get_or_init(|| addr))
+}
+
+fn ias_filter() -> Result<&'static IpFilter, sgx_status_t> {
+    if let Some(filter) = IAS_FILTER.get() {
+        return Ok(filter);
+    }
+    let addr = ias_addr()?;
+    let filter = IpFilter::new()
+        .allow(DEV_HOSTNAME, &format!("{}/32", addr.ip()), Some(addr.port()))
+        .map_err(|_| sgx_status_t::SGX_ERROR_UNEXPECTED)?;
+    Ok(IAS_FILTER.get_or_init(|| filter))
+}
+
+pub fn make_ias_client() -> HttpsClient {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+
+    HttpsClient::with_root_store(roots)
+        .header("Ocp-Apim-Subscription-Key", &get_ias_api_key())
+        .ip_filter(ias_filter().expect("IAS is resolved by ias_socket"))
+}
+
+pub fn get_sigrl_from_intel(fd : c_int, gid : u32) -> Vec<u8> {
+    enclave_log!(Level::Debug, "get_sigrl_from_intel fd = {:?}", fd);
+    let client = make_ias_client();
+    let url = format!("https://{}{}{:08x}", DEV_HOSTNAME, SIGRL_SUFFIX, gid);
+    enclave_log!(Level::Debug, "GET {}", url);
+
+    let sock = TcpStream::new(fd).unwrap();
+    let resp = match client.get_with_socket(sock, &url) {
+        Ok(resp) => resp,
+        Err(e) => {
+            enclave_log!(Level::Error, "get_sigrl_from_intel: {}", e);
+            panic!("haha");
+        }
+    };
+    enclave_log!(Level::Debug, "response complete");
+
+    parse_response_sigrl(&resp)
+}
+
+// TODO: support pse
+pub fn get_report_from_intel(fd : c_int, quote : Vec<u8>) -> (String, String, String) {
+    enclave_log!(Level::Debug, "get_report_from_intel fd = {:?}", fd);
+    let client = make_ias_client();
+    let encoded_quote = base64::encode(&quote[..]);
+    let encoded_json = format!("{{\"isvEnclaveQuote\":\"{}\"}}\r\n", encoded_quote);
+    let url = format!("https://{}{}", DEV_HOSTNAME, REPORT_SUFFIX);
+    enclave_log!(Level::Debug, "POST {}", url);
+
+    let sock = TcpStream::new(fd).unwrap();
+    let resp = client.post_with_socket(sock, &url, "application/json", encoded_json.as_bytes()).unwrap();
+    enclave_log!(Level::Debug, "response complete");
+
+    parse_response_attn_report(&resp)
+}
+
+#[allow(const_err)]
+pub fn get_quote(report_data: &sgx_report_data_t, sign_type: sgx_quote_sign_type_t) -> Result<Vec<u8>, sgx_status_t> {
+    // Workflow:
+    // (1) ocall to get the target_info structure (ti) and epid group id (eg)
+    // (1.5) get sigrl
+    // (2) call sgx_create_report with ti+data, produce an sgx_report_t
+    // (3) ocall to sgx_get_quote to generate (*mut sgx-quote_t, uint32_t)
+
+    // (1) get ti + eg
+    let mut ti : sgx_target_info_t = sgx_target_info_t::default();
+    let mut eg : sgx_epid_group_id_t = sgx_epid_group_id_t::default();
+    let mut rt : sgx_status_t = sgx_status_t::SGX_ERROR_UNEXPECTED;
+
+    let res = unsafe {
+        ocall_sgx_init_quote(&mut rt as *mut sgx_status_t,
+                             &mut ti as *mut sgx_target_info_t,
+                             &mut eg as *mut sgx_epid_group_id_t)
+    };
+
+    enclave_log!(Level::Debug, "eg = {:?}", eg);
+
+    if res != sgx_status_t::SGX_SUCCESS {
+        return Err(res);
+    }
+
+    if rt != sgx_status_t::SGX_SUCCESS {
+        return Err(rt);
+    }
+
+    let eg_num = (&eg[..]).read_u32_le().expect("the EPID group id is 4 bytes");
+
+    // (1.5) get sigrl
+    let ias_sock = ias_socket()?;
+
+    //println!("Got ias_sock = {}", ias_sock);
+
+    // Now sigrl_vec is the revocation list, a vec<u8>
+    let sigrl_vec : Vec<u8> = get_sigrl_from_intel(ias_sock, eg_num);
+
+    // (2) Generate the report
+    let rep = match rsgx_create_report(&ti, report_data) {
+        Ok(r) =>{
+            enclave_log!(Level::Debug, "Report creation => success {:?}", r.body.mr_signer.m);
+            Some(r)
+        },
+        Err(e) =>{
+            enclave_log!(Level::Error, "Report creation => failed {:?}", e);
+            None
+        },
+    };
+
+    let mut quote_nonce = sgx_quote_nonce_t { rand : [0;16] };
+    let mut os_rng = os::SgxRng::new().unwrap();
+    os_rng.fill_bytes(&mut quote_nonce.rand);
+    enclave_log!(Level::Debug, "rand finished");
+    let mut qe_report = sgx_report_t::default();
+    const RET_QUOTE_BUF_LEN : u32 = 2048;
+    let mut return_quote_buf : [u8; RET_QUOTE_BUF_LEN as usize] = [0;RET_QUOTE_BUF_LEN as usize];
+    let mut quote_len : u32 = 0;
+
+    // (3) Generate the quote
+    // Args:
+    //       1. sigrl: ptr + len
+    //       2. report: ptr 432bytes
+    //       3. linkable: u32, unlinkable=0, linkable=1
+    //       4. spid: sgx_spid_t ptr 16bytes
+    //       5. sgx_quote_nonce_t ptr 16bytes
+    //       6. p_sig_rl + sigrl size ( same to sigrl)
+    //       7. [out]p_qe_report need further check
+    //       8. [out]p_quote
+    //       9. quote_size
+    let (p_sigrl, sigrl_len) =
+        if sigrl_vec.len() == 0 {
+            (ptr::null(), 0)
+        } else {
+            (sigrl_vec.as_ptr(), sigrl_vec.len() as u32)
+        };
+    let p_report = (&rep.unwrap()) as * const sgx_report_t;
+    let quote_type = sign_type;
+
+    let spid : sgx_spid_t = load_spid("spid.txt");
+
+    let p_spid = &spid as *const sgx_spid_t;
+    let p_nonce = &quote_nonce as * const sgx_quote_nonce_t;
+    let p_qe_report = &mut qe_report as *mut sgx_report_t;
+    let p_quote = return_quote_buf.as_mut_ptr();
+    let maxlen = RET_QUOTE_BUF_LEN;
+    let p_quote_len = &mut quote_len as *mut u32;
+
+    let result = unsafe {
+        ocall_get_quote(&mut rt as *mut sgx_status_t,
+                p_sigrl,
+                sigrl_len,
+                p_report,
+                quote_type,
+                p_spid,
+                p_nonce,
+                p_qe_report,
+                p_quote,
+                maxlen,
+                p_quote_len)
+    };
+
+    if result != sgx_status_t::SGX_SUCCESS {
+        return Err(result);
+    }
+
+    if rt != sgx_status_t::SGX_SUCCESS {
+        enclave_log!(Level::Error, "ocall_get_quote returned {}", rt);
+        return Err(rt);
+    }
+
+    // Added 09-28-2018
+    // Perform a check on qe_report to verify if the qe_report is valid
+    match rsgx_verify_report(&qe_report) {
+        Ok(()) => enclave_log!(Level::Debug, "rsgx_verify_report passed!"),
+        Err(x) => {
+            enclave_log!(Level::Error, "rsgx_verify_report failed with {:?}", x);
+            return Err(x);
+        },
+    }
+
+    // Check if the qe_report is produced on the same platform
+    if ti.mr_enclave.m != qe_report.body.mr_enclave.m ||
+       ti.attributes.flags != qe_report.body.attributes.flags ||
+       ti.attributes.xfrm  != qe_report.body.attributes.xfrm {
+        enclave_log!(Level::Error, "qe_report does not match current target_info!");
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+
+    enclave_log!(Level::Debug, "qe_report check passed");
+
+    // Debug
+    // for i in 0..quote_len {
+    //     print!("{:02X}", unsafe {*p_quote.offset(i as isize)});
+    // }
+    // println!("");
+
+    // Check qe_report to defend against replay attack
+    // The purpose of p_qe_report is for the ISV enclave to confirm the QUOTE
+    // it received is not modified by the untrusted SW stack, and not a replay.
+    // The implementation in QE is to generate a REPORT targeting the ISV
+    // enclave (target info from p_report) , with the lower 32Bytes in
+    // report.data = SHA256(p_nonce||p_quote). The ISV enclave can verify the
+    // p_qe_report and report.data to confirm the QUOTE has not be modified and
+    // is not a replay. It is optional.
+
+    let mut rhs_vec : Vec<u8> = quote_nonce.rand.to_vec();
+    rhs_vec.extend(&return_quote_buf[..quote_len as usize]);
+    let rhs_hash = rsgx_sha256_slice(&rhs_vec[..]).unwrap();
+    let lhs_hash = &qe_report.body.report_data.d[..32];
+
+    enclave_log!(Level::Debug, "rhs hash = {:02X}", rhs_hash.iter().format(""));
+    enclave_log!(Level::Debug, "report hs= {:02X}", lhs_hash.iter().format(""));
+
+    if rhs_hash != lhs_hash {
+        enclave_log!(Level::Error, "Quote is tampered!");
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+
+    Ok(return_quote_buf[..quote_len as usize].to_vec())
+}
+
+// Has IAS verify a quote and returns its attestation report, the signature
+// and the signing certificate.
+fn get_report(quote: &[u8]) -> Result<(String, String, String), sgx_status_t> {
+    let ias_sock = ias_socket()?;
+    Ok(get_report_from_intel(ias_sock, quote.to_vec()))
+}
+
+// Has the host connect to the pinned IAS address, so the address that is
+// checked is the one connected to.
+fn ias_socket() -> Result<c_int, sgx_status_t> {
+    let addr = ias_addr()?;
+    if let Err(e) = ias_filter()?.check(DEV_HOSTNAME, &addr) {
+        enclave_log!(Level::Error, "{}", e);
+        return Err(sgx_status_t::SGX_ERROR_NETWORK_FAILURE);
+    }
+    let ip = match addr {
+        SocketAddr::V4(v4) => v4.ip().octets(),
+        SocketAddr::V6(_) => return Err(sgx_status_t::SGX_ERROR_UNEXPECTED),
+    };
+
+    let mut rt : sgx_status_t = sgx_status_t::SGX_ERROR_UNEXPECTED;
+    let mut ias_sock : i32 = 0;
+    let res = unsafe {
+        ocall_get_ias_socket(&mut rt as *mut sgx_status_t,
+                             &ip as *const [u8; 4],
+                             addr.port(),
+                             &mut ias_sock as *mut i32)
+    };
+
+    if res != sgx_status_t::SGX_SUCCESS {
+        return Err(res);
+    }
+
+    if rt != sgx_status_t::SGX_SUCCESS {
+        return Err(rt);
+    }
+
+    Ok(ias_sock)
+}
+
+fn load_spid(filename: &str) -> sgx_spid_t {
+    let mut spidfile = fs::File::open(filename).expect("cannot open spid file");
+    let mut contents = String::new();
+    spidfile.read_to_string(&mut contents).expect("cannot read the spid file");
+
+    hex::decode_spid(&contents)
+}
+
+fn get_ias_api_key() -> String {
+    let mut keyfile = fs::File::open("key.txt").expect("cannot open ias key file");
+    let mut key = String::new();
+    keyfile.read_to_string(&mut key).expect("cannot read the ias key file");
+
+    key.trim_end().to_owned()
+}
+
+type SignatureAlgorithms = &'static [&'static webpki::SignatureAlgorithm];
+static SUPPORTED_SIG_ALGS: SignatureAlgorithms = &[
+    &webpki::ECDSA_P256_SHA256,
+    &webpki::ECDSA_P256_SHA384,
+    &webpki::ECDSA_P384_SHA256,
+    &webpki::ECDSA_P384_SHA384,
+    &webpki::RSA_PSS_2048_8192_SHA256_LEGACY_KEY,
+    &webpki::RSA_PSS_2048_8192_SHA384_LEGACY_KEY,
+    &webpki::RSA_PSS_2048_8192_SHA512_LEGACY_KEY,
+    &webpki::RSA_PKCS1_2048_8192_SHA256,
+    &webpki::RSA_PKCS1_2048_8192_SHA384,
+    &webpki::RSA_PKCS1_2048_8192_SHA512,
+    &webpki::RSA_PKCS1_3072_8192_SHA384,
+];
+
+pub const IAS_REPORT_CA:&[u8] = include_bytes!("../AttestationReportSigningCACert.pem");
+
+/// EPID attestation through the Intel Attestation Service. The certificate
+/// carries the EPID quote, which the verifier has IAS check, as in Gramine.
+pub struct EpidProvider {
+    sign_type: sgx_quote_sign_type_t,
+}
+
+impl EpidProvider {
+    pub fn new(sign_type: sgx_quote_sign_type_t) -> EpidProvider {
+        EpidProvider { sign_type: sign_type }
+    }
+}
+
+impl AttestationProvider for EpidProvider {
+    fn get_quote(&self, report_data: &sgx_report_data_t) -> Result<Vec<u8>, sgx_status_t> {
+        get_quote(report_data, self.sign_type)
+    }
+
+    fn verify_quote(&self, quote: &[u8], _collateral: &Collateral) -> Result<(), sgx_status_t> {
+        let (attn_report, sig, sig_cert) = get_report(quote)?;
+        let attn_report_raw = attn_report.as_bytes();
+        let sig = base64::decode(&sig).unwrap();
+        let sig_cert = Certificate::from_pem(&sig_cert).map_err(|e| {
+            enclave_log!(Level::Error, "Malformed IAS signing certificate: {}", e);
+            e
+        })?;
+        enclave_log!(Level::Debug, "IAS signing certificate: {:?}", sig_cert);
+        let sig_cert = webpki::EndEntityCert::from(sig_cert.as_der()).expect("Bad DER");
+
+        // Verify if the signing cert is issued by Intel CA
+        let ias_ca = str::from_utf8(IAS_REPORT_CA)
+            .map_err(|_| sgx_status_t::SGX_ERROR_UNEXPECTED)
+            .and_then(Certificate::from_pem)
+            .expect("Bad IAS CA");
+
+        let mut ca_reader = BufReader::new(&IAS_REPORT_CA[..]);
+
+        let mut root_store = rustls::RootCertStore::empty();
+        root_store.add_pem_file(&mut ca_reader).expect("Failed to add CA");
+
+        let trust_anchors: Vec<webpki::TrustAnchor> = root_store
+            .roots
+            .iter()
+            .map(|cert| cert.to_trust_anchor())
+            .collect();
+
+        let mut chain:Vec<&[u8]> = Vec::new();
+        chain.push(ias_ca.as_der());
+
+        let now_func = webpki::Time::try_from(SystemTime::now());
+
+        match sig_cert.verify_is_valid_tls_server_cert(
+            SUPPORTED_SIG_ALGS,
+            &webpki::TLSServerTrustAnchors(&trust_anchors),
+            &chain,
+            now_func.unwrap()) {
+            Ok(_) => enclave_log!(Level::Debug, "Cert is good"),
+            Err(e) => enclave_log!(Level::Error, "Cert verification error {:?}", e),
+        }
+
+        // Verify the signature against the signing cert
+        match sig_cert.verify_signature(
+            &webpki::RSA_PKCS1_2048_8192_SHA256,
+            &attn_report_raw,
+            &sig) {
+            Ok(_) => enclave_log!(Level::Debug, "Signature good"),
+            Err(e) => {
+                enclave_log!(Level::Error, "Signature verification error {:?}", e);
+                panic!();
+            },
+        }
+
+        // Verify attestation report
+        // 1. Check timestamp is within 24H (90day is recommended by Intel)
+        let attn_report: Value = serde_json::from_slice(attn_report_raw).unwrap();
+        if let Value::String(time) = &attn_report["timestamp"] {
+            let time_fixed = time.clone() + "+0000";
+            let ts = DateTime::parse_from_str(&time_fixed, "%Y-%m-%dT%H:%M:%S%.f%z").unwrap().timestamp();
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+            enclave_log!(Level::Debug, "Time diff = {}", now - ts);
+        } else {
+            enclave_log!(Level::Error, "Failed to fetch timestamp from attestation report");
+            return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+        }
+
+        // 2. Verify quote status (mandatory field)
+        if let Value::String(quote_status) = &attn_report["isvEnclaveQuoteStatus"] {
+            enclave_log!(Level::Debug, "isvEnclaveQuoteStatus = {}", quote_status);
+            match quote_status.as_ref() {
+                "OK" => (),
+                "GROUP_OUT_OF_DATE" | "GROUP_REVOKED" | "CONFIGURATION_NEEDED" => {
+                    // Verify platformInfoBlob for further info if status not OK
+                    if let Value::String(pib) = &attn_report["platformInfoBlob"] {
+                        let mut platform_info = Vec::new();
+
+                        // the TLV Header (4 bytes/8 hexes) should be skipped
+                        let n = (pib.len() - 8)/2;
+                        for i in 0..n {
+                            platform_info.push(u8::from_str_radix(&pib[(i*2+8)..(i*2+10)], 16).unwrap());
+                        }
+
+                        // Optionally, a signed Platform Info Blob Type-Length-Value (TLV)will be generated and included
+                        // in the report (as defined in Platform Info Blobsection).The SP involved in the remote attestation
+                        // process shouldforward Platform Info Blob, excluding the TLV header, to ISV SGX application running
+                        // on the client platform that is being attested. The ISV SGX application can then process the Platform
+                        // Info Blob using SGX SDK API sgx_report_attestation_status().
+
+                        // let mut update_info = sgx_update_info_bit_t::default();
+                        // let mut rt : sgx_status_t = sgx_status_t::SGX_ERROR_UNEXPECTED;
+                        // let res = unsafe{
+                        //     ocall_get_update_info(&mut rt as *mut sgx_status_t,
+                        //                           platform_info.as_slice().as_ptr() as * const sgx_platform_info_t,
+                        //                           1,
+                        //                           &mut update_info as * mut sgx_update_info_bit_t)
+                        // };
+                        // if res != sgx_status_t::SGX_SUCCESS {
+                        //     println!("res={:?}", res);
+                        //     return Err(res);
+                        // }
+
+                        // if rt != sgx_status_t::SGX_SUCCESS {
+                        //     println!("rt={:?}", rt);
+                        //     // Borrow of packed field is unsafe in future Rust releases
+                        //     unsafe{
+                        //         println!("update_info.pswUpdate: {}", update_info.pswUpdate);
+                        //         println!("update_info.csmeFwUpdate: {}", update_info.csmeFwUpdate);
+                        //         println!("update_info.ucodeUpdate: {}", update_info.ucodeUpdate);
+                        //     }
+                        //     return Err(rt);
+                        // }
+                    } else {
+                        enclave_log!(Level::Error, "Failed to fetch platformInfoBlob from attestation report");
+                        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+                    }
+                }
+                _ => return Err(sgx_status_t::SGX_ERROR_UNEXPECTED),
+            }
+        } else {
+            enclave_log!(Level::Error, "Failed to fetch isvEnclaveQuoteStatus from attestation report");
+            return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+        }
+
+        // 3. Verify quote body
+        if let Value::String(quote_raw) = &attn_report["isvEnclaveQuoteBody"] {
+            let quote_body = base64::decode(&quote_raw).unwrap();
+            enclave_log!(Level::Debug, "Quote = {:?}", quote_body);
+
+            // IAS reports the quote without its signature: it must be the
+            // one of the certificate, whose report data commits to its key
+            if quote_body.len() != mem::size_of::<sgx_quote_t>() - 4 ||
+               quote.len() < mem::size_of::<sgx_quote_t>() ||
+               quote_body[..] != quote[..quote_body.len()] {
+                enclave_log!(Level::Error, "Attestation report is not about the quote of the certificate!");
+                return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+            }
+            let sgx_quote: sgx_quote_t = unsafe{ptr::read_unaligned(quote.as_ptr() as *const _)};
+            let report_body = sgx_quote.report_body;
+            enclave_log!(Level::Debug, "sgx quote version = {}", { sgx_quote.version });
+            enclave_log!(Level::Debug, "sgx quote signature type = {}", { sgx_quote.sign_type });
+            enclave_log!(Level::Debug, "sgx quote mr_enclave = {:02x}", report_body.mr_enclave.m.iter().format(""));
+            enclave_log!(Level::Debug, "sgx quote mr_signer = {:02x}", report_body.mr_signer.m.iter().format(""));
+            enclave_log!(Level::Info, "Mutual RA done!");
+        } else {
+            enclave_log!(Level::Error, "Failed to fetch isvEnclaveQuoteBody from attestation report");
+            return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+        }
+
+        Ok(())
+    }
+}
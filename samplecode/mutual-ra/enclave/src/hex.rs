@@ -1,6 +1,7 @@
 // hex encoder and decoder used by rust-protobuf unittests
 
 use std::prelude::v1::*;
+use std::log::Level;
 use std::char;
 use sgx_types::*;
 
@@ -18,7 +19,7 @@ pub fn decode_spid(hex: &str) -> sgx_spid_t {
     let hex = hex.trim();
 
     if hex.len() < 16 * 2 {
-        println!("Input spid file len ({}) is incorrect!", hex.len());
+        enclave_log!(Level::Error, "Input spid file len ({}) is incorrect!", hex.len());
         return spid;
     }
 
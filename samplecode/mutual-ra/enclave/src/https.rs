@@ -0,0 +1,323 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! A minimal HTTPS/1.1 client for talking to attestation services.
+//!
+//! Each request opens a fresh TLS session with `Connection: close`. The
+//! response is read through a `BufReader`, so header parsing does not cost
+//! a TLS record read per byte.
+
+use std::prelude::v1::*;
+use std::error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpFilter, Shutdown, TcpStream};
+use std::sync::Arc;
+
+const MAX_HEADERS: usize = 32;
+const MAX_LINE_LEN: usize = 8192;
+
+#[derive(Debug)]
+pub enum HttpsError {
+    /// The URL is not of the form `https://host[:port][/path]`.
+    InvalidUrl,
+    /// The CA certificate could not be parsed.
+    InvalidCertificate,
+    /// Connecting, the TLS handshake or the transfer failed.
+    Io(io::Error),
+    /// The server did not send a well-formed HTTP/1.1 response.
+    InvalidResponse,
+}
+
+impl fmt::Display for HttpsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HttpsError::InvalidUrl => f.write_str("invalid https URL"),
+            HttpsError::InvalidCertificate => f.write_str("invalid CA certificate"),
+            HttpsError::Io(ref e) => write!(f, "https transfer failed: {}", e),
+            HttpsError::InvalidResponse => f.write_str("malformed HTTP response"),
+        }
+    }
+}
+
+impl error::Error for HttpsError {}
+
+impl From<io::Error> for HttpsError {
+    fn from(e: io::Error) -> HttpsError {
+        HttpsError::Io(e)
+    }
+}
+
+pub struct HttpsResponse {
+    status_code: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl HttpsResponse {
+    pub fn status_code(&self) -> u16 {
+        self.status_code
+    }
+
+    /// Returns the value of the first header called `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|&&(ref n, _)| n.eq_ignore_ascii_case(name))
+            .map(|&(_, ref v)| v.as_str())
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+}
+
+pub struct HttpsClient {
+    config: Arc<rustls::ClientConfig>,
+    headers: Vec<(String, String)>,
+    ip_filter: Option<&'static IpFilter>,
+}
+
+impl HttpsClient {
+    /// Creates a client that trusts servers certified by `ca_cert_der`.
+    pub fn new(ca_cert_der: &[u8]) -> Result<HttpsClient, HttpsError> {
+        let mut roots = rustls::RootCertStore::empty();
+        roots
+            .add(&rustls::Certificate(ca_cert_der.to_vec()))
+            .map_err(|_| HttpsError::InvalidCertificate)?;
+        Ok(HttpsClient::with_root_store(roots))
+    }
+
+    /// Creates a client that trusts the CAs in `roots`.
+    pub fn with_root_store(roots: rustls::RootCertStore) -> HttpsClient {
+        let mut config = rustls::ClientConfig::new();
+        config.root_store = roots;
+        HttpsClient {
+            config: Arc::new(config),
+            headers: Vec::new(),
+            ip_filter: None,
+        }
+    }
+
+    /// Adds a header that is sent with every request, such as an API key.
+    pub fn header(mut self, name: &str, value: &str) -> HttpsClient {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Only talks to peers allowed by `filter`. Sockets passed to the
+    /// `*_with_socket` methods are checked by their peer address.
+    pub fn ip_filter(mut self, filter: &'static IpFilter) -> HttpsClient {
+        self.ip_filter = Some(filter);
+        self
+    }
+
+    pub fn get(&self, url: &str) -> Result<HttpsResponse, HttpsError> {
+        let url = Url::parse(url)?;
+        let sock = self.connect(&url)?;
+        self.send(sock, "GET", &url, None)
+    }
+
+    pub fn post(&self, url: &str, content_type: &str, body: &[u8]) -> Result<HttpsResponse, HttpsError> {
+        let url = Url::parse(url)?;
+        let sock = self.connect(&url)?;
+        self.send(sock, "POST", &url, Some((content_type, body)))
+    }
+
+    /// Like `get`, but over `sock`, which is already connected to the host
+    /// of `url`.
+    pub fn get_with_socket(&self, sock: TcpStream, url: &str) -> Result<HttpsResponse, HttpsError> {
+        let url = Url::parse(url)?;
+        self.send(sock, "GET", &url, None)
+    }
+
+    /// Like `post`, but over `sock`, which is already connected to the host
+    /// of `url`.
+    pub fn post_with_socket(
+        &self,
+        sock: TcpStream,
+        url: &str,
+        content_type: &str,
+        body: &[u8],
+    ) -> Result<HttpsResponse, HttpsError> {
+        let url = Url::parse(url)?;
+        self.send(sock, "POST", &url, Some((content_type, body)))
+    }
+
+    fn connect(&self, url: &Url) -> Result<TcpStream, HttpsError> {
+        let sock = match self.ip_filter {
+            Some(filter) => filter.connect(url.host, url.port)?,
+            None => TcpStream::connect((url.host, url.port))?,
+        };
+        Ok(sock)
+    }
+
+    fn send(
+        &self,
+        mut sock: TcpStream,
+        method: &str,
+        url: &Url,
+        body: Option<(&str, &[u8])>,
+    ) -> Result<HttpsResponse, HttpsError> {
+        if let Some(filter) = self.ip_filter {
+            filter.check(url.host, &sock.peer_addr()?).map_err(io::Error::from)?;
+        }
+
+        let mut req = format!("{} {} HTTP/1.1\r\nHost: {}\r\n", method, url.path, url.authority);
+        for &(ref name, ref value) in self.headers.iter() {
+            req.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        if let Some((content_type, body)) = body {
+            req.push_str(&format!("Content-Type: {}\r\nContent-Length: {}\r\n", content_type, body.len()));
+        }
+        req.push_str("Connection: close\r\n\r\n");
+        let mut req = req.into_bytes();
+        if let Some((_, body)) = body {
+            req.extend_from_slice(body);
+        }
+
+        let dns_name = webpki::DNSNameRef::try_from_ascii_str(url.host).map_err(|_| HttpsError::InvalidUrl)?;
+        let mut sess = rustls::ClientSession::new(&self.config, dns_name);
+        let mut tls = rustls::Stream::new(&mut sess, &mut sock);
+        tls.write_all(&req)?;
+        tls.flush()?;
+        // Half-close to signal the end of the request (Connection: close).
+        tls.sock.shutdown(Shutdown::Write)?;
+
+        read_response(&mut BufReader::new(tls))
+    }
+}
+
+struct Url<'a> {
+    host: &'a str,
+    port: u16,
+    // host[:port], as sent in the Host header
+    authority: &'a str,
+    path: &'a str,
+}
+
+impl<'a> Url<'a> {
+    fn parse(url: &'a str) -> Result<Url<'a>, HttpsError> {
+        if url.len() < 8 || !url[..8].eq_ignore_ascii_case("https://") {
+            return Err(HttpsError::InvalidUrl);
+        }
+        let rest = &url[8..];
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rfind(':') {
+            Some(i) => {
+                let port = authority[i + 1..].parse().map_err(|_| HttpsError::InvalidUrl)?;
+                (&authority[..i], port)
+            }
+            None => (authority, 443),
+        };
+        if host.is_empty() {
+            return Err(HttpsError::InvalidUrl);
+        }
+        Ok(Url { host: host, port: port, authority: authority, path: path })
+    }
+}
+
+fn read_response<R: BufRead>(reader: &mut R) -> Result<HttpsResponse, HttpsError> {
+    let status_line = read_line(reader)?;
+    let mut parts = status_line.splitn(3, ' ');
+    match parts.next() {
+        Some(version) if version.starts_with("HTTP/1.") => {}
+        _ => return Err(HttpsError::InvalidResponse),
+    }
+    let status_code = parts
+        .next()
+        .and_then(|code| code.parse().ok())
+        .ok_or(HttpsError::InvalidResponse)?;
+
+    let mut response = HttpsResponse {
+        status_code: status_code,
+        headers: Vec::new(),
+        body: Vec::new(),
+    };
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            break;
+        }
+        if response.headers.len() == MAX_HEADERS {
+            return Err(HttpsError::InvalidResponse);
+        }
+        let colon = line.find(':').ok_or(HttpsError::InvalidResponse)?;
+        let name = line[..colon].trim();
+        if name.is_empty() {
+            return Err(HttpsError::InvalidResponse);
+        }
+        response.headers.push((name.to_string(), line[colon + 1..].trim().to_string()));
+    }
+
+    let chunked = response
+        .header("Transfer-Encoding")
+        .map_or(false, |te| te.eq_ignore_ascii_case("chunked"));
+    response.body = if chunked {
+        read_chunked(reader)?
+    } else {
+        match response.header("Content-Length") {
+            Some(len) => {
+                let len: usize = len.parse().map_err(|_| HttpsError::InvalidResponse)?;
+                let mut body = vec![0; len];
+                reader.read_exact(&mut body)?;
+                body
+            }
+            None => {
+                let mut body = Vec::new();
+                reader.read_to_end(&mut body)?;
+                body
+            }
+        }
+    };
+    Ok(response)
+}
+
+fn read_chunked<R: BufRead>(reader: &mut R) -> Result<Vec<u8>, HttpsError> {
+    let mut body = Vec::new();
+    loop {
+        let line = read_line(reader)?;
+        // Chunk extensions after ';' are ignored.
+        let size = line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size, 16).map_err(|_| HttpsError::InvalidResponse)?;
+        if size == 0 {
+            // Skip the trailer section.
+            while !read_line(reader)?.is_empty() {}
+            return Ok(body);
+        }
+        let start = body.len();
+        body.resize(start.checked_add(size).ok_or(HttpsError::InvalidResponse)?, 0);
+        reader.read_exact(&mut body[start..])?;
+        if !read_line(reader)?.is_empty() {
+            return Err(HttpsError::InvalidResponse);
+        }
+    }
+}
+
+// Reads a CRLF-terminated line, without the CRLF.
+fn read_line<R: BufRead>(reader: &mut R) -> Result<String, HttpsError> {
+    let mut line = Vec::new();
+    reader.by_ref().take(MAX_LINE_LEN as u64).read_until(b'\n', &mut line)?;
+    if !line.ends_with(b"\r\n") {
+        return Err(HttpsError::InvalidResponse);
+    }
+    line.truncate(line.len() - 2);
+    String::from_utf8(line).map_err(|_| HttpsError::InvalidResponse)
+}
@@ -0,0 +1,108 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use std::io::{self, Read, Write};
+use sgx_rand::{Rng, SgxRng};
+use sgx_tcrypto::ecc::{X25519KeyPair, X25519PublicKey, X25519_KEY_SIZE};
+use sgx_tcrypto::kdf::Hkdf;
+use sgx_tcrypto::sha::Sha256;
+use sgx_tcrypto::util::Zeroize;
+use sgx_types::*;
+
+pub const SESSION_KEY_LEN: usize = 32;
+
+/// Which end of the TLS session this enclave is.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Client,
+    Server,
+}
+
+/// A key shared by the two attested enclaves, erased when dropped.
+pub struct SessionKey([u8; SESSION_KEY_LEN]);
+
+impl SessionKey {
+    pub fn as_bytes(&self) -> &[u8; SESSION_KEY_LEN] {
+        &self.0
+    }
+
+    /// A short identifier of the key, derived from it with HKDF, which
+    /// both sides can log to show that they agree.
+    pub fn id(&self) -> SgxResult<[u8; 8]> {
+        let mut id = [0_u8; 8];
+        Hkdf::<Sha256>::extract(None, &self.0)?.expand(b"mutual-ra x25519 key id", &mut id)?;
+        Ok(id)
+    }
+}
+
+impl Drop for SessionKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Agrees on a `SessionKey` with X25519 over an established TLS session.
+///
+/// Both enclaves generate an ephemeral X25519 key pair and exchange the
+/// public keys through `stream`. The server sends first, and the client
+/// only sends once it has received the server's key, so the client's key
+/// is never read together with an earlier message. The shared secret is
+/// expanded with HKDF-SHA256, salted with the fingerprints of both attested
+/// certificates, which binds the key to the two attested enclaves.
+///
+/// Call this only after the peer's certificate has been checked against
+/// the attested one.
+pub fn agree<S: Read + Write>(
+    stream: &mut S,
+    role: Role,
+    own_fingerprint: &[u8; 32],
+    peer_fingerprint: &[u8; 32],
+) -> io::Result<SessionKey> {
+    let mut rng = SgxRng::new()?;
+    let key_pair = X25519KeyPair::generate_with_rng(|scalar| {
+        rng.fill_bytes(scalar);
+        Ok(())
+    })?;
+
+    let mut peer = [0_u8; X25519_KEY_SIZE];
+    if role == Role::Server {
+        stream.write_all(key_pair.public_key().as_bytes())?;
+        stream.read_exact(&mut peer)?;
+    } else {
+        stream.read_exact(&mut peer)?;
+        stream.write_all(key_pair.public_key().as_bytes())?;
+    }
+    stream.flush()?;
+
+    let shared = key_pair.diffie_hellman(&X25519PublicKey::from_bytes(peer));
+    if !shared.was_contributory() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "peer sent a low order X25519 key"));
+    }
+
+    let (client, server) = match role {
+        Role::Client => (own_fingerprint, peer_fingerprint),
+        Role::Server => (peer_fingerprint, own_fingerprint),
+    };
+    let mut salt = [0_u8; 64];
+    salt[..32].copy_from_slice(client);
+    salt[32..].copy_from_slice(server);
+
+    let mut key = SessionKey([0_u8; SESSION_KEY_LEN]);
+    Hkdf::<Sha256>::extract(Some(&salt), shared.as_bytes())?
+        .expand(b"mutual-ra x25519 session key", &mut key.0)?;
+    Ok(key)
+}
@@ -0,0 +1,68 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use std::prelude::v1::*;
+use std::sync::SgxMutex;
+use sgx_tcrypto::rsgx_sha256_slice;
+
+/// Access to the certificates the peer presented during the handshake,
+/// for pinning them after the handshake has completed.
+pub trait PeerCertificates {
+    /// DER encoding of each certificate in the peer's chain, leaf first.
+    /// `None` before the handshake has completed.
+    fn peer_certificates(&self) -> Option<Vec<Vec<u8>>>;
+
+    /// SHA-256 of the DER encoding of the peer's leaf certificate.
+    fn peer_certificate_fingerprint(&self) -> Option<[u8; 32]> {
+        let certs = self.peer_certificates()?;
+        certs.first().and_then(|leaf| rsgx_sha256_slice(leaf).ok())
+    }
+}
+
+impl<S: rustls::Session> PeerCertificates for S {
+    fn peer_certificates(&self) -> Option<Vec<Vec<u8>>> {
+        self.get_peer_certificates()
+            .map(|certs| certs.into_iter().map(|cert| cert.0).collect())
+    }
+}
+
+/// Remembers the fingerprint of the certificate whose attestation report
+/// the verifier has checked, to compare it against the certificate the
+/// session ends up with.
+pub struct AttestedFingerprint(SgxMutex<Option<[u8; 32]>>);
+
+impl AttestedFingerprint {
+    pub fn new() -> AttestedFingerprint {
+        AttestedFingerprint(SgxMutex::new(None))
+    }
+
+    pub fn set(&self, cert_der: &[u8]) {
+        *self.0.lock().unwrap() = rsgx_sha256_slice(cert_der).ok();
+    }
+
+    pub fn get(&self) -> Option<[u8; 32]> {
+        *self.0.lock().unwrap()
+    }
+
+    /// Checks that the session's peer presented the attested certificate.
+    pub fn matches<S: PeerCertificates>(&self, sess: &S) -> bool {
+        match (self.get(), sess.peer_certificate_fingerprint()) {
+            (Some(expected), Some(actual)) => expected == actual,
+            _ => false,
+        }
+    }
+}
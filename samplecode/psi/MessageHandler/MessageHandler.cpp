@@ -50,6 +50,12 @@ void MessageHandler::start() {
         return;
     }
 
+    ret = self_test_search(this->enclave->getID(), &status);
+    if ((SGX_SUCCESS != ret) || (SGX_SUCCESS != status)) {
+        Log("Error, oblivious search self test fail", log::error);
+        return;
+    }
+
     Log("Call initEnclave success");
     this->nm->startService();
 }
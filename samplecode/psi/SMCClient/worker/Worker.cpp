@@ -770,8 +770,20 @@ int PSIWorker::sp_psi_intersect(Messages::MessagePsiIntersect msg) {
     //     Log("[PSI] Intersect result: %s", this->data_map[hash_str]);
     // }
 
+    // the flags are followed by the false-positive rate, a little-endian double
+    if (data_size < (int)sizeof(double)) {
+        Log("[PSI] Intersect result too short, %d", data_size);
+        SafeFree(data);
+        SafeFree(dec_data);
+        return -1;
+    }
+
+    int flag_size = data_size - sizeof(double);
+    double false_positive_rate = 0;
+    memcpy(&false_positive_rate, &dec_data[flag_size], sizeof(double));
+
     int hash_cnt = 0;
-    for (int i = 0; i < data_size && i < this->hash_vector.size(); i++) {
+    for (int i = 0; i < flag_size && i < this->hash_vector.size(); i++) {
         if (dec_data[i]) {
             hash_cnt++;
             string hash_str = this->hash_vector[i];
@@ -780,6 +792,7 @@ int PSIWorker::sp_psi_intersect(Messages::MessagePsiIntersect msg) {
     }
 
     Log("[PSI] Intersect result count: %d", hash_cnt);
+    Log("[PSI] Intersect false positive rate: %g", false_positive_rate);
 
     SafeFree(data);
     SafeFree(dec_data);
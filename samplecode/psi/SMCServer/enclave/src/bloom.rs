@@ -0,0 +1,121 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use sgx_types::*;
+use sgx_tcrypto::hmac::Hmac;
+use sgx_tcrypto::sha::Sha256;
+use sgx_rand::{Rng, thread_rng};
+use std::cmp;
+use std::f64::consts::LN_2;
+use std::vec::Vec;
+
+/// A counting Bloom filter over `SGX_HASH_SIZE`-byte hashes.
+///
+/// The counters an element maps to are derived from HMAC-SHA256 under a
+/// random key drawn when the filter is created, so the clients can neither
+/// predict them nor craft elements that collide. Counters are 8 bits wide
+/// and saturate instead of wrapping.
+pub struct BloomFilter {
+    counters: Vec<u8>,
+    hashes: u32,
+    inserted: usize,
+    key: [u8; SGX_HASH_SIZE],
+}
+
+impl BloomFilter {
+
+    /// Creates an empty filter for `capacity` elements whose
+    /// false-positive probability stays below `false_positive_rate` as
+    /// long as no more than `capacity` elements are inserted.
+    ///
+    /// For n elements and a rate p the filter uses the optimal
+    /// m = -n ln(p) / ln(2)^2 counters and k = (m / n) ln(2) hash
+    /// functions. Panics if `false_positive_rate` is not between 0 and 1.
+    pub fn new(capacity: usize, false_positive_rate: f64) -> Self {
+
+        assert!(false_positive_rate > 0.0 && false_positive_rate < 1.0,
+                "false positive rate must be between 0 and 1");
+
+        let n = cmp::max(capacity, 1) as f64;
+        let size = (-n * false_positive_rate.ln() / (LN_2 * LN_2)).ceil();
+        let hashes = (size / n * LN_2).round().max(1.0);
+
+        let mut key = [0_u8; SGX_HASH_SIZE];
+        thread_rng().fill_bytes(&mut key);
+
+        BloomFilter {
+            counters: vec![0; size as usize],
+            hashes: hashes as u32,
+            inserted: 0,
+            key,
+        }
+    }
+
+    /// Adds `element` to the filter.
+    pub fn insert(&mut self, element: &[u8; SGX_HASH_SIZE]) {
+
+        let (h1, h2) = self.digest(element);
+        for i in 0..self.hashes {
+            let pos = self.position(h1, h2, i);
+            self.counters[pos] = self.counters[pos].saturating_add(1);
+        }
+        self.inserted += 1;
+    }
+
+    /// Returns false if `element` was certainly not inserted and true if
+    /// it probably was. All k counters are read even after a zero one, so
+    /// every test does the same work.
+    pub fn test(&self, element: &[u8; SGX_HASH_SIZE]) -> bool {
+
+        let (h1, h2) = self.digest(element);
+        let mut present = true;
+        for i in 0..self.hashes {
+            present &= self.counters[self.position(h1, h2, i)] != 0;
+        }
+        present
+    }
+
+    /// Returns the probability that `test` reports an element that was
+    /// never inserted, (1 - e^(-kn/m))^k for the n elements inserted so
+    /// far.
+    pub fn false_positive_rate(&self) -> f64 {
+
+        let k = self.hashes as f64;
+        let fill = -k * self.inserted as f64 / self.counters.len() as f64;
+        (1.0 - fill.exp()).powf(k)
+    }
+
+    // Splits the keyed hash of `element` into the two hashes of the
+    // double hashing scheme h1 + i * h2, which derives all k positions
+    // from one HMAC.
+    fn digest(&self, element: &[u8; SGX_HASH_SIZE]) -> (u64, u64) {
+
+        let tag = Hmac::<Sha256>::mac(&self.key, element)
+            .expect("HMAC-SHA256 failed")
+            .into_bytes();
+        let mut h1 = [0_u8; 8];
+        let mut h2 = [0_u8; 8];
+        h1.copy_from_slice(&tag[..8]);
+        h2.copy_from_slice(&tag[8..16]);
+        (u64::from_le_bytes(h1), u64::from_le_bytes(h2))
+    }
+
+    fn position(&self, h1: u64, h2: u64, i: u32) -> usize {
+        let pos = (h1 as u128 + i as u128 * h2 as u128) % self.counters.len() as u128;
+        pos as usize
+    }
+}
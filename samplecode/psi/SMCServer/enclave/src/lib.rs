@@ -20,7 +20,6 @@
 
 #![cfg_attr(not(target_env = "sgx"), no_std)]
 #![cfg_attr(target_env = "sgx", feature(rustc_private))]
-#![feature(asm)]
 #![allow(dead_code)]
 #![allow(unused_variables)]
 
@@ -37,13 +36,17 @@ extern crate sgx_rand;
 use sgx_types::*;
 use sgx_trts::memeq::ConsttimeMemEq;
 use sgx_tcrypto::*;
+use sgx_tcrypto::util::{ct_eq, ct_eq_usize, ct_lt_usize, ct_select, ct_select_usize};
 use sgx_tkey_exchange::*;
-use sgx_rand::{Rng, StdRng};
+use sgx_rand::{Rng, StdRng, weighted_sample};
 use std::slice;
 use std::vec::Vec;
-use std::cell::RefCell;
-use std::sync::atomic::{AtomicPtr, Ordering};
-use std::boxed::Box;
+use std::sync::{OnceLock, SgxMutex};
+
+#[cfg(feature = "bloom")]
+mod bloom;
+#[cfg(feature = "bloom")]
+use bloom::BloomFilter;
 
 const G_SP_PUB_KEY: sgx_ec256_public_t = sgx_ec256_public_t {
     gx : [0x72, 0x12, 0x8a, 0x7a, 0x17, 0x52, 0x6e, 0xbf,
@@ -60,6 +63,11 @@ const SGX_SALT_SIZE: usize = 32;
 const CLIENT_MAX_NUMBER: usize = 2;
 const HASH_DATA_FINISH: u32 = 1;
 const RESULT_FINISH: u32 = 2;
+// get_result appends the false-positive rate of the result, as a
+// little-endian f64, to the intersection flags
+const FALSE_POSITIVE_RATE_SIZE: usize = 8;
+#[cfg(feature = "bloom")]
+const BLOOM_FALSE_POSITIVE_RATE: f64 = 1e-6;
 
 #[derive(Clone, Default)]
 struct SetIntersection {
@@ -72,6 +80,7 @@ struct SetIntersection {
 struct HashDataBuffer {
     hashdata: Vec<[u8; SGX_HASH_SIZE]>,
     result: Vec<u8>,
+    false_positive_rate: f64,
     state: u32,
 }
 
@@ -81,16 +90,11 @@ impl SetIntersection {
     }
 }
 
-static GLOBAL_HASH_BUFFER: AtomicPtr<()> = AtomicPtr::new(0 as * mut ());
+static GLOBAL_HASH_BUFFER: OnceLock<SgxMutex<SetIntersection>> = OnceLock::new();
 
-fn get_ref_hash_buffer() -> Option<&'static RefCell<SetIntersection>>
+fn get_ref_hash_buffer() -> Option<&'static SgxMutex<SetIntersection>>
 {
-    let ptr = GLOBAL_HASH_BUFFER.load(Ordering::Relaxed) as * mut RefCell<SetIntersection>;
-    if ptr.is_null() {
-        None
-    } else {
-        Some(unsafe { &* ptr })
-    }
+    GLOBAL_HASH_BUFFER.get()
 }
 
 
@@ -105,9 +109,8 @@ fn initialize() -> sgx_status_t {
     };
     rand.fill_bytes(&mut data.salt);
 
-    let data_box = Box::new(RefCell::<SetIntersection>::new(data));
-    let ptr = Box::into_raw(data_box);
-    GLOBAL_HASH_BUFFER.store(ptr as *mut (), Ordering::Relaxed);
+    let buffer = GLOBAL_HASH_BUFFER.get_or_init(|| SgxMutex::new(SetIntersection::new()));
+    *buffer.lock().unwrap() = data;
 
     sgx_status_t::SGX_SUCCESS
 }
@@ -116,11 +119,9 @@ fn initialize() -> sgx_status_t {
 pub extern "C"
 fn uninitialize() {
 
-    let ptr = GLOBAL_HASH_BUFFER.swap(0 as * mut (), Ordering::Relaxed) as * mut RefCell<SetIntersection>;
-    if ptr.is_null() {
-       return;
+    if let Some(buffer) = get_ref_hash_buffer() {
+        *buffer.lock().unwrap() = SetIntersection::new();
     }
-    let _ = unsafe { Box::from_raw(ptr) };
 }
 
 
@@ -196,7 +197,7 @@ fn verify_secret_data(context: sgx_ra_context_t,
                       salt_mac: &mut [u8; SGX_MAC_SIZE],
                       id: &mut u32) -> sgx_status_t {
 
-    let mut data = get_ref_hash_buffer().unwrap().borrow_mut();
+    let mut data = get_ref_hash_buffer().unwrap().lock().unwrap();
     if data.number < CLIENT_MAX_NUMBER as u32 {
         data.number +=1;
     } else {
@@ -296,7 +297,7 @@ fn add_hash_data(id: u32,
         Err(x) => return x,
     };
 
-    let mut intersection = get_ref_hash_buffer().unwrap().borrow_mut();
+    let mut intersection = get_ref_hash_buffer().unwrap().lock().unwrap();
     let buffer = &mut intersection.data[id as usize - 1].hashdata;
 
     for i in 0_usize..(hash_size/SGX_HASH_SIZE) {
@@ -323,7 +324,7 @@ fn get_result_size(id: u32, len: &mut usize) -> sgx_status_t {
         0
     };
 
-    let mut intersection = get_ref_hash_buffer().unwrap().borrow_mut();
+    let mut intersection = get_ref_hash_buffer().unwrap().lock().unwrap();
 
     if intersection.data[cid].state == 0 {
         intersection.data[cid].state = HASH_DATA_FINISH;
@@ -339,18 +340,30 @@ fn get_result_size(id: u32, len: &mut usize) -> sgx_status_t {
         let mut v_cid: Vec<u8> = vec![0; intersection.data[cid].hashdata.len()];
         let mut v_other: Vec<u8> = vec![0; intersection.data[other].hashdata.len()];
 
-        oget_intersection(&intersection.data[cid].hashdata,
-                          &intersection.data[other].hashdata,
-                          &mut v_cid,
-                          &mut v_other);
+        #[cfg(not(feature = "bloom"))]
+        let (rate_cid, rate_other) = {
+            oget_intersection(&intersection.data[cid].hashdata,
+                              &intersection.data[other].hashdata,
+                              &mut v_cid,
+                              &mut v_other);
+            (0.0, 0.0)
+        };
+        #[cfg(feature = "bloom")]
+        let (rate_cid, rate_other) =
+            bloom_intersection(&intersection.data[cid].hashdata,
+                               &intersection.data[other].hashdata,
+                               &mut v_cid,
+                               &mut v_other);
 
         intersection.data[cid].result = v_cid;
         intersection.data[other].result = v_other;
+        intersection.data[cid].false_positive_rate = rate_cid;
+        intersection.data[other].false_positive_rate = rate_other;
         intersection.data[cid].state = RESULT_FINISH;
         intersection.data[other].state = RESULT_FINISH;
-        intersection.data[cid].result.len()
+        intersection.data[cid].result.len() + FALSE_POSITIVE_RATE_SIZE
     } else if (state1 == RESULT_FINISH) && (state2 == RESULT_FINISH) {
-        intersection.data[cid].result.len()
+        intersection.data[cid].result.len() + FALSE_POSITIVE_RATE_SIZE
     } else {
         return sgx_status_t::SGX_ERROR_UNEXPECTED;
     };
@@ -383,7 +396,7 @@ fn get_result(id: u32,
         Err(x) => return x,
     };
 
-    let mut intersection = get_ref_hash_buffer().unwrap().borrow_mut();
+    let mut intersection = get_ref_hash_buffer().unwrap().lock().unwrap();
 
     let state1 = intersection.data[cid].state;
     let state2 = intersection.data[other].state;
@@ -391,35 +404,35 @@ fn get_result(id: u32,
         return sgx_status_t::SGX_ERROR_INVALID_STATE;
     }
 
-    let len = intersection.data[cid].result.len();
-    if len > 0 {
-        if result_size != len {
-            return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
-        }
+    let mut payload = intersection.data[cid].result.clone();
+    payload.extend_from_slice(&intersection.data[cid].false_positive_rate.to_le_bytes());
+    if result_size != payload.len() {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
 
-        let result_slice = unsafe {
-            slice::from_raw_parts_mut(result, result_size)
-        };
+    let result_slice = unsafe {
+        slice::from_raw_parts_mut(result, result_size)
+    };
 
-        let iv = [0; SGX_AESGCM_IV_SIZE];
-        let aad:[u8; 0] = [0; 0];
-        let ret = rsgx_rijndael128GCM_encrypt(&sk_key,
-                                              intersection.data[cid].result.as_slice(),
-                                              &iv,
-                                              &aad,
-                                              result_slice,
-                                              result_mac);
-        match ret {
-            Ok(()) => {},
-            Err(x) => return x,
-        };
-    }
+    let iv = [0; SGX_AESGCM_IV_SIZE];
+    let aad:[u8; 0] = [0; 0];
+    let ret = rsgx_rijndael128GCM_encrypt(&sk_key,
+                                          payload.as_slice(),
+                                          &iv,
+                                          &aad,
+                                          result_slice,
+                                          result_mac);
+    match ret {
+        Ok(()) => {},
+        Err(x) => return x,
+    };
 
     intersection.number -= 1;
     if intersection.number == 0 {
         for i in 0..CLIENT_MAX_NUMBER {
             intersection.data[i].hashdata = Vec::new();
             intersection.data[i].result = Vec::new();
+            intersection.data[i].false_positive_rate = 0.0;
             intersection.data[i].state = 0;
         }
     }
@@ -427,164 +440,236 @@ fn get_result(id: u32,
     sgx_status_t::SGX_SUCCESS
 }
 
-fn oget_intersection(a: &Vec<[u8; SGX_HASH_SIZE]>, b: &Vec<[u8; SGX_HASH_SIZE]>, v1: &mut Vec<u8>, v2: &mut Vec<u8>) {
+/// Returns a random subset of up to `sample_size` elements of the
+/// intersection, in the same layout as the flags of `get_result`: one
+/// byte per hash the client sent, set to 1 if that element was picked.
+/// The false-positive rate is not appended.
+///
+/// Every hash is weighted by its intersection flag, so only elements of
+/// the intersection can be picked, each with the same probability. Must
+/// be called after `get_result_size` and before `get_result`, which
+/// releases the data. The mask is sealed under a fresh random IV so it
+/// never shares one with `get_result`.
+#[no_mangle]
+pub extern "C"
+fn get_sample_result(id: u32,
+                     context: sgx_ra_context_t,
+                     sample_size: usize,
+                     result: * mut u8,
+                     result_size: usize,
+                     result_iv: &mut [u8; SGX_AESGCM_IV_SIZE],
+                     result_mac: &mut [u8; SGX_MAC_SIZE]) -> sgx_status_t {
 
-    let n = a.len();
-    for i in 0..n {
-        let ret = obinary_search(b, &a[i], v2);
-        let miss = oequal(usize::max_value(), ret as usize);
-        v1[i] = omov(miss as isize, 0, 1) as u8;
+    if (id == 0) || (id > CLIENT_MAX_NUMBER as u32) {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
     }
-}
 
-fn obinary_search(b: &Vec<[u8; SGX_HASH_SIZE]>, target: &[u8; SGX_HASH_SIZE], v2: &mut Vec<u8>) -> isize {
-
-    let mut lo: isize = 0;
-    let mut hi: isize = b.len() as isize - 1;
-    let mut ret: isize = -1;
+    let cid: usize = id as usize - 1;
+    let other = if cid == 0 {
+        CLIENT_MAX_NUMBER - 1
+    } else {
+        0
+    };
 
-    while lo <= hi {
-        let mid = lo + (hi - lo) / 2;
-        let hit = eq(&b[mid as usize], target);
-        ret = omov(hit, mid, ret);
-        v2[mid as usize] = omov(hit, 1, v2[mid as usize] as isize) as u8;
-        let be = le(&b[mid as usize], target);
-        lo = omov(be, mid + 1, lo);
-        hi = omov(be, hi, mid - 1);
-    }
-    ret
-}
+    let sk_key: sgx_ec_key_128bit_t = match rsgx_ra_get_keys(context, sgx_ra_key_type_t::SGX_RA_KEY_SK) {
+        Ok(key) => key,
+        Err(x) => return x,
+    };
 
-fn eq(a: &[u8; SGX_HASH_SIZE], b: &[u8; SGX_HASH_SIZE]) -> isize {
+    let intersection = get_ref_hash_buffer().unwrap().lock().unwrap();
 
-    let mut ret: isize = 1;
-    for i in 0..SGX_HASH_SIZE {
-        let hit = oequal(a[i] as usize, b[i] as usize);
-        ret = omov(hit as isize, ret, 0);
+    let state1 = intersection.data[cid].state;
+    let state2 = intersection.data[other].state;
+    if (state1 != RESULT_FINISH) || (state2 != RESULT_FINISH) {
+        return sgx_status_t::SGX_ERROR_INVALID_STATE;
     }
-    ret
-}
 
-fn le(a: &[u8; SGX_HASH_SIZE], b: &[u8; SGX_HASH_SIZE]) -> isize {
+    let flags = &intersection.data[cid].result;
+    if result_size != flags.len() {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    if result_size == 0 {
+        return sgx_status_t::SGX_SUCCESS;
+    }
 
-    let mut ret: isize = 0;
-    for i in 0..SGX_HASH_SIZE {
+    let mut rand = match StdRng::new() {
+        Ok(rng) => rng,
+        Err(_) => { return sgx_status_t::SGX_ERROR_UNEXPECTED; },
+    };
+    // sample_size comes from the host, never ask for more than there are
+    let sample_size = sample_size.min(flags.len());
+    let picked = match weighted_sample(&mut rand, 0..flags.len(), |&i| flags[i] as f64, sample_size) {
+        Ok(picked) => picked,
+        Err(_) => { return sgx_status_t::SGX_ERROR_UNEXPECTED; },
+    };
 
-        let hit = oequal(a[i] as usize, b[i] as usize);
-        let be = ob(a[i] as usize, b[i] as usize);
-        let cmp = omov(hit as isize, 0, omov(be as isize, -1, 1));
-        ret = omov(ret, ret, cmp)
+    let mut mask: Vec<u8> = vec![0; flags.len()];
+    for i in picked {
+        mask[i] = 1;
     }
-    (ret <= 0) as isize
-}
 
-fn ge(a: &[u8; SGX_HASH_SIZE], b: &[u8; SGX_HASH_SIZE]) -> isize {
+    let result_slice = unsafe {
+        slice::from_raw_parts_mut(result, result_size)
+    };
 
-    let mut ret: isize = 0;
-    for i in 0..SGX_HASH_SIZE {
+    rand.fill_bytes(&mut result_iv[..]);
+    let aad:[u8; 0] = [0; 0];
+    let ret = rsgx_rijndael128GCM_encrypt(&sk_key,
+                                          mask.as_slice(),
+                                          result_iv,
+                                          &aad,
+                                          result_slice,
+                                          result_mac);
+    match ret {
+        Ok(()) => {},
+        Err(x) => return x,
+    };
 
-        let hit = oequal(a[i] as usize, b[i] as usize);
-        let ae = oa(a[i] as usize, b[i] as usize);
-        let cmp = omov(hit as isize, 0, omov(ae as isize, 1, -1));
-        ret = omov(ret, ret, cmp)
-    }
-    (ret >= 0) as isize
+    sgx_status_t::SGX_SUCCESS
 }
 
-fn oequal(x: usize, y: usize) -> bool {
+#[cfg(not(feature = "bloom"))]
+fn oget_intersection(a: &Vec<[u8; SGX_HASH_SIZE]>, b: &Vec<[u8; SGX_HASH_SIZE]>, v1: &mut Vec<u8>, v2: &mut Vec<u8>) {
 
-    let ret: bool;
-    unsafe {
-        asm!(
-            "cmp %rcx, %rdx \n\t
-             sete %al \n\t"
-            : "={al}"(ret)
-            : "{rcx}"(x), "{rdx}" (y)
-            : "rcx", "rdx"
-            : "volatile"
-        );
+    let rounds = search_rounds(b.len());
+    let mut comparisons: usize = 0;
+    let n = a.len();
+    for i in 0..n {
+        let ret = obinary_search(b, &a[i], v2, rounds, &mut comparisons);
+        let miss = ct_eq_usize(usize::max_value(), ret as usize);
+        v1[i] = ct_select(miss, 0, 1);
     }
-    ret
 }
 
-fn obe(x: usize, y: usize) -> bool {
+// Computes the intersection flags with a Bloom filter of each set instead
+// of a binary search, in O(n + m) instead of O(n log m). Elements of the
+// other set's filter can be falsely flagged; the returned pair holds the
+// false-positive rates of v1 and v2.
+#[cfg(feature = "bloom")]
+fn bloom_intersection(a: &Vec<[u8; SGX_HASH_SIZE]>, b: &Vec<[u8; SGX_HASH_SIZE]>, v1: &mut Vec<u8>, v2: &mut Vec<u8>) -> (f64, f64) {
 
-    let ret: bool;
-    unsafe {
-        asm!(
-            "cmp %rdx, %rcx \n\t
-             setbe %al \n\t"
-            : "={al}"(ret)
-            : "{rcx}"(x), "{rdx}" (y)
-            : "rcx", "rdx"
-            : "volatile"
-        );
+    let mut filter_a = BloomFilter::new(a.len(), BLOOM_FALSE_POSITIVE_RATE);
+    for hash in a.iter() {
+        filter_a.insert(hash);
     }
-    ret
+    let mut filter_b = BloomFilter::new(b.len(), BLOOM_FALSE_POSITIVE_RATE);
+    for hash in b.iter() {
+        filter_b.insert(hash);
+    }
+
+    for i in 0..a.len() {
+        v1[i] = filter_b.test(&a[i]) as u8;
+    }
+    for i in 0..b.len() {
+        v2[i] = filter_a.test(&b[i]) as u8;
+    }
+    (filter_b.false_positive_rate(), filter_a.false_positive_rate())
 }
 
-fn ob(x: usize, y: usize) -> bool {
+// The number of probes a binary search needs to find any element of a
+// sorted set of `len` elements: floor(log2(len)) + 1, or 0 for an empty set.
+fn search_rounds(len: usize) -> usize {
+    (usize::BITS - len.leading_zeros()) as usize
+}
 
-    let ret: bool;
-    unsafe {
-        asm!(
-            "cmp %rdx, %rcx \n\t
-             setb %al \n\t"
-            : "={al}"(ret)
-            : "{rcx}"(x), "{rdx}" (y)
-            : "rcx", "rdx"
-            : "volatile"
-        );
+// Searches the sorted set `b` for `target` and marks the hit in `v2`.
+//
+// The search always runs `rounds` probes, each with one `eq` and one `le`
+// comparison, so the number of iterations does not depend on whether or
+// where the target is found. Once the range is empty the remaining probes
+// are dummies: they compare against a clamped index and `omov` discards
+// their results. `comparisons` is incremented by the comparisons themselves,
+// so `self_test_search` sees any comparison that is skipped.
+// See SECURITY.md for the memory access pattern, which still leaks.
+fn obinary_search(b: &Vec<[u8; SGX_HASH_SIZE]>,
+                  target: &[u8; SGX_HASH_SIZE],
+                  v2: &mut Vec<u8>,
+                  rounds: usize,
+                  comparisons: &mut usize) -> isize {
+
+    let len = b.len() as isize;
+    let mut lo: isize = 0;
+    let mut hi: isize = len - 1;
+    let mut ret: isize = -1;
+
+    for _ in 0..rounds {
+        // lo <= hi, with lo >= 0 and hi >= -1
+        let active = ct_lt_usize(lo as usize, (hi + 1) as usize);
+        let mid = lo + (hi - lo) / 2;
+        // an empty range leaves mid == lo, which can be len
+        let mid = omov(ct_lt_usize(mid as usize, len as usize), mid, len - 1);
+        let hit = active & counted(comparisons, ct_eq(&b[mid as usize], target));
+        ret = omov(hit, mid, ret);
+        v2[mid as usize] = ct_select(hit, 1, v2[mid as usize]);
+        let be = counted(comparisons, le(&b[mid as usize], target));
+        lo = omov(active, omov(be, mid + 1, lo), lo);
+        hi = omov(active, omov(be, hi, mid - 1), hi);
     }
     ret
 }
 
-fn oae(x: usize, y: usize) -> bool {
+// Passes through the result of a comparison, counting it. The comparison is
+// an argument, so it only counts when it has actually been evaluated.
+fn counted(comparisons: &mut usize, result: bool) -> bool {
+    *comparisons += 1;
+    result
+}
+
+/// Checks `obinary_search` on sorted sets of several sizes: every search,
+/// hit or miss, must find the right element and run the same number of
+/// comparisons. The server runs it once at startup.
+#[no_mangle]
+pub extern "C"
+fn self_test_search() -> sgx_status_t {
+
+    // the sets hold the odd numbers 1, 3, ..., 2 * len - 1 in their first
+    // two bytes, so every even number from 0 to 2 * len is a miss
+    let hash = |value: usize| {
+        let mut h = [0x5a_u8; SGX_HASH_SIZE];
+        h[..2].copy_from_slice(&(value as u16).to_be_bytes());
+        h
+    };
 
-    let ret: bool;
-    unsafe {
-        asm!(
-            "cmp %rdx, %rcx \n\t
-             setae %al \n\t"
-            : "={al}"(ret)
-            : "{rcx}"(x), "{rdx}" (y)
-            : "rcx", "rdx"
-            : "volatile"
-        );
+    for &len in [1_usize, 2, 3, 7, 8, 9, 64, 100].iter() {
+        let b: Vec<[u8; SGX_HASH_SIZE]> = (0..len).map(|i| hash(2 * i + 1)).collect();
+        let rounds = search_rounds(len);
+
+        for value in 0..=2 * len {
+            let mut v2: Vec<u8> = vec![0; len];
+            let mut comparisons: usize = 0;
+            let ret = obinary_search(&b, &hash(value), &mut v2, rounds, &mut comparisons);
+
+            let expected = if value % 2 == 1 { value as isize / 2 } else { -1 };
+            let marked = v2.iter().enumerate().all(|(i, &m)| (m == 1) == (i as isize == expected));
+            if ret != expected || !marked || comparisons != 2 * rounds {
+                println!("oblivious search self test failed: len {}, value {}, comparisons {}",
+                         len, value, comparisons);
+                return sgx_status_t::SGX_ERROR_UNEXPECTED;
+            }
+        }
     }
-    ret
+    sgx_status_t::SGX_SUCCESS
 }
 
-fn oa(x: usize, y: usize) -> bool {
+// Whether `a <= b`, comparing every byte.
+fn le(a: &[u8; SGX_HASH_SIZE], b: &[u8; SGX_HASH_SIZE]) -> bool {
 
-    let ret: bool;
-    unsafe {
-        asm!(
-            "cmp %rdx, %rcx \n\t
-             seta %al \n\t"
-            : "={al}"(ret)
-            : "{rcx}"(x), "{rdx}" (y)
-            : "rcx", "rdx"
-            : "volatile"
-        );
+    // -1, 0 or 1 at the first differing byte
+    let mut ret: isize = 0;
+    for i in 0..SGX_HASH_SIZE {
+        let (x, y) = (a[i] as usize, b[i] as usize);
+        let cmp = omov(ct_eq_usize(x, y), 0, omov(ct_lt_usize(x, y), -1, 1));
+        ret = omov(ct_eq_usize(ret as usize, 0), cmp, ret);
     }
-    ret
+    !ct_eq_usize(ret as usize, 1)
 }
 
-fn omov(flag: isize, x: isize, y: isize) -> isize {
-
-    let ret: isize;
-    unsafe {
-        asm!(
-            "xor %rcx, %rcx \n\t
-             mov $1, %rcx \n\t
-             test %rcx, %rcx \n\t
-             cmovz %rdx, %rax \n\t"
-            : "={rax}"(ret)
-            : "r"(flag), "{rax}" (x), "{rdx}" (y)
-            : "rax", "rcx", "rdx"
-            : "volatile"
-        );
-    }
-    ret
+// Whether `a >= b`, comparing every byte.
+fn ge(a: &[u8; SGX_HASH_SIZE], b: &[u8; SGX_HASH_SIZE]) -> bool {
+    le(b, a)
+}
+
+// `x` if `flag` is set and `y` otherwise, without branching.
+fn omov(flag: bool, x: isize, y: isize) -> isize {
+    ct_select_usize(flag, x as usize, y as usize) as isize
 }
@@ -0,0 +1,76 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+#![crate_name = "randcompatenclave"]
+#![crate_type = "staticlib"]
+
+#![cfg_attr(not(target_env = "sgx"), no_std)]
+#![cfg_attr(target_env = "sgx", feature(rustc_private))]
+
+extern crate sgx_types;
+#[cfg(not(target_env = "sgx"))]
+#[macro_use]
+extern crate sgx_tstd as std;
+extern crate sgx_rand;
+extern crate rand;
+extern crate rand_chacha;
+extern crate rand_core;
+
+use sgx_types::*;
+use std::vec::Vec;
+
+use rand::seq::SliceRandom;
+use rand::Rng as _;
+use rand_chacha::ChaCha20Rng;
+use rand_core::{RngCore, SeedableRng};
+use sgx_rand::compat::{RngAdapter, RngCoreAdapter, SgxRngAdapter};
+use sgx_rand::distributions::{IndependentSample, Normal};
+use sgx_rand::PcgRng;
+
+#[no_mangle]
+pub extern "C" fn rand_compat_demo() -> sgx_status_t {
+    // Seed a rand_chacha generator from the SGX random number generator.
+    let sgx_rng = match SgxRngAdapter::new() {
+        Ok(rng) => rng,
+        Err(_) => return sgx_status_t::SGX_ERROR_UNEXPECTED,
+    };
+    let mut chacha = match ChaCha20Rng::from_rng(sgx_rng) {
+        Ok(rng) => rng,
+        Err(_) => return sgx_status_t::SGX_ERROR_UNEXPECTED,
+    };
+
+    // Any rand 0.8 API now works inside the enclave.
+    let dice: Vec<u8> = (0..10).map(|_| chacha.gen_range(1..=6)).collect();
+    println!("[enclave] ten dice rolls: {:?}", dice);
+    let mut deck: Vec<u8> = (1..=13).collect();
+    deck.shuffle(&mut chacha);
+    println!("[enclave] shuffled deck: {:?}", deck);
+
+    // sgx_rand generators can be handed to rand_core consumers, and seeded
+    // through rand_core::SeedableRng.
+    let mut pcg = RngCoreAdapter::<PcgRng>::seed_from_u64(42);
+    let mut again = RngCoreAdapter::<PcgRng>::seed_from_u64(42);
+    assert_eq!(pcg.next_u64(), again.next_u64());
+    println!("[enclave] PcgRng via rand_core: {}", pcg.gen::<u32>());
+
+    // And sgx_rand distributions can draw from rand_core generators.
+    let normal = Normal::new(100.0, 15.0);
+    let mut chacha = RngAdapter::new(chacha);
+    let samples: Vec<f64> = (0..5).map(|_| normal.ind_sample(&mut chacha)).collect();
+    println!("[enclave] normal samples: {:?}", samples);
+
+    sgx_status_t::SGX_SUCCESS
+}
@@ -0,0 +1,117 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+extern crate sgx_types;
+extern crate sgx_urts;
+use sgx_types::*;
+use sgx_urts::SgxEnclave;
+
+mod migration;
+use migration::{check, MigrationHelper};
+
+static ENCLAVE_V1_FILE: &'static str = "enclave_v1.signed.so";
+static ENCLAVE_V2_FILE: &'static str = "enclave_v2.signed.so";
+
+extern {
+    fn seal_secret(eid: sgx_enclave_id_t, retval: *mut sgx_status_t,
+                   secret: *const u8, secret_len: usize,
+                   sealed_buf: *mut u8, sealed_buf_len: usize,
+                   sealed_len: *mut u32) -> sgx_status_t;
+    fn unseal_secret(eid: sgx_enclave_id_t, retval: *mut sgx_status_t,
+                     sealed: *const u8, sealed_len: usize) -> sgx_status_t;
+}
+
+fn init_enclave(file: &str) -> SgxResult<SgxEnclave> {
+    let mut launch_token: sgx_launch_token_t = [0; 1024];
+    let mut launch_token_updated: i32 = 0;
+    // call sgx_create_enclave to initialize an enclave instance
+    // Debug Support: set 2nd parameter to 1
+    let debug = 1;
+    let mut misc_attr = sgx_misc_attribute_t {secs_attr: sgx_attributes_t { flags:0, xfrm:0}, misc_select:0};
+    SgxEnclave::create(file,
+                       debug,
+                       &mut launch_token,
+                       &mut launch_token_updated,
+                       &mut misc_attr)
+}
+
+fn seal(enclave: &SgxEnclave, secret: &[u8]) -> SgxResult<Vec<u8>> {
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let mut sealed = vec![0_u8; 1024];
+    let mut sealed_len = 0_u32;
+    let result = unsafe {
+        seal_secret(enclave.geteid(), &mut retval,
+                    secret.as_ptr(), secret.len(),
+                    sealed.as_mut_ptr(), sealed.len(), &mut sealed_len)
+    };
+    check(result, retval)?;
+    sealed.truncate(sealed_len as usize);
+    Ok(sealed)
+}
+
+fn unseal(enclave: &SgxEnclave, sealed: &[u8]) -> SgxError {
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let result = unsafe {
+        unseal_secret(enclave.geteid(), &mut retval, sealed.as_ptr(), sealed.len())
+    };
+    check(result, retval)
+}
+
+fn main() {
+    let (v1, v2) = match (init_enclave(ENCLAVE_V1_FILE), init_enclave(ENCLAVE_V2_FILE)) {
+        (Ok(v1), Ok(v2)) => {
+            println!("[+] Init Enclaves Successful {} {}!", v1.geteid(), v2.geteid());
+            (v1, v2)
+        },
+        (Err(x), _) | (_, Err(x)) => {
+            println!("[-] Init Enclave Failed {}!", x.as_str());
+            return;
+        },
+    };
+
+    let sealed = match seal(&v1, b"the v1 secret") {
+        Ok(sealed) => sealed,
+        Err(x) => {
+            println!("[-] seal_secret in v1 failed {}!", x.as_str());
+            return;
+        }
+    };
+    println!("[+] v1 sealed {} bytes", sealed.len());
+
+    // bound to the MRENCLAVE of v1
+    match unseal(&v2, &sealed) {
+        Ok(()) => println!("[-] v2 unsealed the data of v1!"),
+        Err(x) => println!("[+] v2 cannot unseal the data of v1: {}", x.as_str()),
+    }
+
+    let migrated = match MigrationHelper::new(&v1, &v2).migrate(&sealed) {
+        Ok(migrated) => migrated,
+        Err(x) => {
+            println!("[-] Migration failed {}!", x.as_str());
+            return;
+        }
+    };
+    println!("[+] migrated {} bytes from v1 to v2", migrated.len());
+
+    match unseal(&v2, &migrated) {
+        Ok(()) => println!("[+] v2 unsealed the migrated data"),
+        Err(x) => println!("[-] v2 failed to unseal the migrated data {}!", x.as_str()),
+    }
+
+    v1.destroy();
+    v2.destroy();
+}
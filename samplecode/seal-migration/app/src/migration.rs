@@ -0,0 +1,122 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use sgx_types::*;
+use sgx_urts::SgxEnclave;
+
+extern {
+    fn migration_session_request(eid: sgx_enclave_id_t, retval: *mut sgx_status_t,
+                                 dh_msg1: *mut sgx_dh_msg1_t) -> sgx_status_t;
+    fn migration_accept_report(eid: sgx_enclave_id_t, retval: *mut sgx_status_t,
+                               dh_msg2: *const sgx_dh_msg2_t,
+                               dh_msg3: *mut sgx_dh_msg3_t,
+                               nonce: *mut u8) -> sgx_status_t;
+    fn migration_import(eid: sgx_enclave_id_t, retval: *mut sgx_status_t,
+                        blob: *const u8, blob_len: usize,
+                        sealed_buf: *mut u8, sealed_buf_len: usize,
+                        sealed_len: *mut u32) -> sgx_status_t;
+    fn migration_exchange_report(eid: sgx_enclave_id_t, retval: *mut sgx_status_t,
+                                 dh_msg1: *const sgx_dh_msg1_t,
+                                 dh_msg2: *mut sgx_dh_msg2_t) -> sgx_status_t;
+    fn migration_export(eid: sgx_enclave_id_t, retval: *mut sgx_status_t,
+                        dh_msg3: *const sgx_dh_msg3_t,
+                        nonce: *const u8,
+                        sealed: *const u8, sealed_len: usize,
+                        blob_buf: *mut u8, blob_buf_len: usize,
+                        blob_len: *mut u32) -> sgx_status_t;
+}
+
+const MIGRATION_NONCE_SIZE: usize = 16;
+// nonce, IV, MAC and the length of the additional text
+const MIGRATION_HEADER_SIZE: usize = MIGRATION_NONCE_SIZE + 12 + 16 + 4;
+
+pub fn check(result: sgx_status_t, retval: sgx_status_t) -> SgxError {
+    match (result, retval) {
+        (sgx_status_t::SGX_SUCCESS, sgx_status_t::SGX_SUCCESS) => Ok(()),
+        (sgx_status_t::SGX_SUCCESS, err) => Err(err),
+        (err, _) => Err(err),
+    }
+}
+
+/// Moves data sealed with the MRENCLAVE policy from one version of an
+/// enclave to another.
+///
+/// The two enclaves establish a local attestation session and check that
+/// they are versions of the same enclave. The new enclave derives a
+/// single-use migration key from the session key and a fresh nonce; the old
+/// enclave unseals the data and encrypts it under that key, and the new
+/// enclave decrypts it and seals it under its own identity. The untrusted
+/// side only passes messages and never sees the data.
+pub struct MigrationHelper<'a> {
+    old: &'a SgxEnclave,
+    new: &'a SgxEnclave,
+}
+
+impl<'a> MigrationHelper<'a> {
+    pub fn new(old: &'a SgxEnclave, new: &'a SgxEnclave) -> MigrationHelper<'a> {
+        MigrationHelper { old: old, new: new }
+    }
+
+    /// Returns `sealed`, sealed by the old enclave, sealed by the new one.
+    pub fn migrate(&self, sealed: &[u8]) -> SgxResult<Vec<u8>> {
+        let mut retval = sgx_status_t::SGX_SUCCESS;
+        let mut dh_msg1 = sgx_dh_msg1_t::default();
+        let mut dh_msg2 = sgx_dh_msg2_t::default();
+        let mut dh_msg3 = sgx_dh_msg3_t::default();
+        let mut nonce = [0_u8; MIGRATION_NONCE_SIZE];
+
+        // local attestation, the new enclave responds
+        let result = unsafe {
+            migration_session_request(self.new.geteid(), &mut retval, &mut dh_msg1)
+        };
+        check(result, retval)?;
+        let result = unsafe {
+            migration_exchange_report(self.old.geteid(), &mut retval, &dh_msg1, &mut dh_msg2)
+        };
+        check(result, retval)?;
+        let result = unsafe {
+            migration_accept_report(self.new.geteid(), &mut retval,
+                                    &dh_msg2, &mut dh_msg3, nonce.as_mut_ptr())
+        };
+        check(result, retval)?;
+
+        // the blob carries the plain text and additional text of the sealed
+        // data, under a header of its own
+        let mut blob = vec![0_u8; sealed.len() + MIGRATION_HEADER_SIZE];
+        let mut blob_len = 0_u32;
+        let result = unsafe {
+            migration_export(self.old.geteid(), &mut retval,
+                             &dh_msg3, nonce.as_ptr(),
+                             sealed.as_ptr(), sealed.len(),
+                             blob.as_mut_ptr(), blob.len(), &mut blob_len)
+        };
+        check(result, retval)?;
+        blob.truncate(blob_len as usize);
+
+        // sealed by the new enclave, the data takes as much space as before
+        let mut migrated = vec![0_u8; sealed.len()];
+        let mut migrated_len = 0_u32;
+        let result = unsafe {
+            migration_import(self.new.geteid(), &mut retval,
+                             blob.as_ptr(), blob.len(),
+                             migrated.as_mut_ptr(), migrated.len(), &mut migrated_len)
+        };
+        check(result, retval)?;
+        migrated.truncate(migrated_len as usize);
+        Ok(migrated)
+    }
+}
@@ -0,0 +1,245 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+#![crate_name = "sealmigrationenclave"]
+#![crate_type = "staticlib"]
+
+#![cfg_attr(not(target_env = "sgx"), no_std)]
+#![cfg_attr(target_env = "sgx", feature(rustc_private))]
+
+extern crate sgx_types;
+extern crate sgx_trts;
+extern crate sgx_tse;
+extern crate sgx_tseal;
+extern crate sgx_tdh;
+#[cfg(not(target_env = "sgx"))]
+#[macro_use]
+extern crate sgx_tstd as std;
+
+use sgx_types::*;
+use sgx_tdh::{SgxDhInitiator, SgxDhMsg3, SgxDhResponder};
+use sgx_tse::rsgx_self_report;
+use sgx_tseal::{KeyPolicy, SgxSealedData};
+use sgx_tseal::migration::{MigrationKey, MIGRATION_NONCE_SIZE};
+use std::mem;
+use std::slice;
+use std::str;
+use std::sync::SgxMutex;
+
+#[cfg(not(feature = "v2"))]
+const ENCLAVE_VERSION: u32 = 1;
+#[cfg(feature = "v2")]
+const ENCLAVE_VERSION: u32 = 2;
+
+// sending side: the local attestation session in progress
+static INITIATOR: SgxMutex<Option<SgxDhInitiator>> = SgxMutex::new(None);
+// receiving side: the session in progress, then the key of the next migration
+static RESPONDER: SgxMutex<Option<SgxDhResponder>> = SgxMutex::new(None);
+static MIGRATION_KEY: SgxMutex<Option<MigrationKey>> = SgxMutex::new(None);
+
+fn sealed_from_raw<'a>(sealed: *const u8, sealed_len: usize) -> SgxResult<SgxSealedData<'a, [u8]>> {
+    if sealed_len > u32::MAX as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    unsafe {
+        SgxSealedData::<[u8]>::from_raw_sealed_data_t(sealed as *mut sgx_sealed_data_t, sealed_len as u32)
+    }.ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+}
+
+fn sealed_to_raw(sealed_data: &SgxSealedData<[u8]>,
+                 sealed_buf: *mut u8,
+                 sealed_buf_len: usize,
+                 sealed_len: *mut u32) -> SgxError {
+    let size = SgxSealedData::<[u8]>::calc_raw_sealed_data_size(
+        sealed_data.get_add_mac_txt_len(), sealed_data.get_encrypt_txt_len());
+    if size as usize > sealed_buf_len {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    unsafe {
+        sealed_data.to_raw_sealed_data_t(sealed_buf as *mut sgx_sealed_data_t, size)
+            .ok_or(sgx_status_t::SGX_ERROR_UNEXPECTED)?;
+        *sealed_len = size;
+    }
+    Ok(())
+}
+
+// Data may only move between versions of this enclave: same signer and
+// product, and never to an older version or out of a debug enclave into a
+// production one.
+fn verify_peer(peer: &sgx_dh_session_enclave_identity_t, peer_is_newer: bool) -> SgxError {
+    let report = rsgx_self_report();
+    if peer.mr_signer() != report.body.mr_signer()
+        || peer.isv_prod_id != report.body.isv_prod_id {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_SIGNATURE);
+    }
+    if (peer_is_newer && peer.isv_svn < report.body.isv_svn)
+        || (!peer_is_newer && peer.isv_svn > report.body.isv_svn) {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_ISVSVN);
+    }
+    if (peer.attributes.flags & SGX_FLAGS_DEBUG) != (report.body.attributes.flags & SGX_FLAGS_DEBUG) {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_ATTRIBUTE);
+    }
+    Ok(())
+}
+
+fn status(result: SgxError) -> sgx_status_t {
+    match result {
+        Ok(()) => sgx_status_t::SGX_SUCCESS,
+        Err(e) => e,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn seal_secret(secret: *const u8,
+                              secret_len: usize,
+                              sealed_buf: *mut u8,
+                              sealed_buf_len: usize,
+                              sealed_len: *mut u32) -> sgx_status_t {
+    let secret = unsafe { slice::from_raw_parts(secret, secret_len) };
+    let aad = format!("sealed by v{}", ENCLAVE_VERSION);
+    status(SgxSealedData::<[u8]>::seal_with_policy(aad.as_bytes(), secret, KeyPolicy::MrEnclave)
+        .and_then(|sealed_data| sealed_to_raw(&sealed_data, sealed_buf, sealed_buf_len, sealed_len)))
+}
+
+#[no_mangle]
+pub extern "C" fn unseal_secret(sealed: *const u8, sealed_len: usize) -> sgx_status_t {
+    let result = sealed_from_raw(sealed, sealed_len).and_then(|sealed_data| {
+        let unsealed_data = sealed_data.unseal_data()?;
+        println!("[v{}] unsealed {:?} ({})",
+                 ENCLAVE_VERSION,
+                 str::from_utf8(unsealed_data.get_decrypt_txt()).unwrap_or("<binary>"),
+                 str::from_utf8(unsealed_data.get_additional_txt()).unwrap_or("<binary>"));
+        Ok(())
+    });
+    status(result)
+}
+
+#[no_mangle]
+pub extern "C" fn migration_session_request(dh_msg1: *mut sgx_dh_msg1_t) -> sgx_status_t {
+    let mut responder = SgxDhResponder::init_session();
+    let result = responder.gen_msg1(unsafe { &mut *dh_msg1 });
+    if result.is_ok() {
+        *RESPONDER.lock().unwrap() = Some(responder);
+    }
+    status(result)
+}
+
+#[no_mangle]
+pub extern "C" fn migration_accept_report(dh_msg2: *const sgx_dh_msg2_t,
+                                          dh_msg3: *mut sgx_dh_msg3_t,
+                                          nonce: *mut [u8; MIGRATION_NONCE_SIZE]) -> sgx_status_t {
+    let mut responder = match RESPONDER.lock().unwrap().take() {
+        Some(responder) => responder,
+        None => return sgx_status_t::SGX_ERROR_INVALID_STATE,
+    };
+
+    let mut msg3 = SgxDhMsg3::new();
+    let mut aek = sgx_key_128bit_t::default();
+    let mut initiator_identity = sgx_dh_session_enclave_identity_t::default();
+    let result = responder.proc_msg2(unsafe { &*dh_msg2 }, &mut msg3, &mut aek, &mut initiator_identity)
+        .and_then(|_| verify_peer(&initiator_identity, false))
+        .and_then(|_| {
+            let msg3_len = msg3.calc_raw_sealed_data_size();
+            if msg3_len as usize != mem::size_of::<sgx_dh_msg3_t>() {
+                return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+            }
+            unsafe { msg3.to_raw_dh_msg3_t(dh_msg3, msg3_len) }
+                .ok_or(sgx_status_t::SGX_ERROR_UNEXPECTED)?;
+            // replaces the key of any migration that did not complete
+            let key = MigrationKey::generate(&aek)?;
+            unsafe { *nonce = *key.nonce() };
+            *MIGRATION_KEY.lock().unwrap() = Some(key);
+            Ok(())
+        });
+    status(result)
+}
+
+#[no_mangle]
+pub extern "C" fn migration_import(blob: *const u8,
+                                   blob_len: usize,
+                                   sealed_buf: *mut u8,
+                                   sealed_buf_len: usize,
+                                   sealed_len: *mut u32) -> sgx_status_t {
+    // the key is used once, whether the import succeeds or not
+    let key = match MIGRATION_KEY.lock().unwrap().take() {
+        Some(key) => key,
+        None => return sgx_status_t::SGX_ERROR_INVALID_STATE,
+    };
+    let blob = unsafe { slice::from_raw_parts(blob, blob_len) };
+    let result = key.import_sealed::<[u8]>(blob, KeyPolicy::MrEnclave)
+        .and_then(|sealed_data| sealed_to_raw(&sealed_data, sealed_buf, sealed_buf_len, sealed_len));
+    if result.is_ok() {
+        println!("[v{}] imported migrated data", ENCLAVE_VERSION);
+    }
+    status(result)
+}
+
+#[no_mangle]
+pub extern "C" fn migration_exchange_report(dh_msg1: *const sgx_dh_msg1_t,
+                                            dh_msg2: *mut sgx_dh_msg2_t) -> sgx_status_t {
+    let mut initiator = SgxDhInitiator::init_session();
+    let result = initiator.proc_msg1(unsafe { &*dh_msg1 }, unsafe { &mut *dh_msg2 });
+    if result.is_ok() {
+        *INITIATOR.lock().unwrap() = Some(initiator);
+    }
+    status(result)
+}
+
+#[no_mangle]
+pub extern "C" fn migration_export(dh_msg3: *mut sgx_dh_msg3_t,
+                                   nonce: *const [u8; MIGRATION_NONCE_SIZE],
+                                   sealed: *const u8,
+                                   sealed_len: usize,
+                                   blob_buf: *mut u8,
+                                   blob_buf_len: usize,
+                                   blob_len: *mut u32) -> sgx_status_t {
+    let mut initiator = match INITIATOR.lock().unwrap().take() {
+        Some(initiator) => initiator,
+        None => return sgx_status_t::SGX_ERROR_INVALID_STATE,
+    };
+
+    // the EDL passes a message 3 without additional properties
+    if unsafe { (*dh_msg3).msg3_body.additional_prop_length } != 0 {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    let msg3 = match unsafe {
+        SgxDhMsg3::from_raw_dh_msg3_t(dh_msg3, mem::size_of::<sgx_dh_msg3_t>() as u32)
+    } {
+        Some(msg3) => msg3,
+        None => return sgx_status_t::SGX_ERROR_INVALID_PARAMETER,
+    };
+
+    let mut aek = sgx_key_128bit_t::default();
+    let mut responder_identity = sgx_dh_session_enclave_identity_t::default();
+    let result = initiator.proc_msg3(&msg3, &mut aek, &mut responder_identity)
+        .and_then(|_| verify_peer(&responder_identity, true))
+        .and_then(|_| {
+            let key = MigrationKey::with_nonce(&aek, unsafe { &*nonce })?;
+            let sealed_data = sealed_from_raw(sealed, sealed_len)?;
+            let blob = key.export_sealed(&sealed_data)?;
+            if blob.len() > blob_buf_len {
+                return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+            }
+            unsafe {
+                slice::from_raw_parts_mut(blob_buf, blob.len()).copy_from_slice(&blob);
+                *blob_len = blob.len() as u32;
+            }
+            println!("[v{}] exported sealed data for migration", ENCLAVE_VERSION);
+            Ok(())
+        });
+    status(result)
+}
@@ -30,14 +30,27 @@ use std::ffi::CString;
 use std::net::SocketAddr;
 use std::str;
 use std::io::{self, Write};
+use std::env;
+use std::thread;
+use std::time::{Duration, Instant};
 
 const BUFFER_SIZE: usize = 1024;
 
+// One thread per TCS of the enclave, see Enclave.config.xml.
+const BENCH_THREADS: usize = 4;
+const BENCH_ITERATIONS: usize = 1_000_000;
+
+// MAX_CONNECTIONS and ACQUIRE_TIMEOUT of the enclave.
+const POOL_SIZE: usize = 64;
+const POOL_TIMEOUT: Duration = Duration::from_secs(3);
+const INVALID_SESSION: usize = 0xFFFF_FFFF_FFFF_FFFF;
+
 static ENCLAVE_FILE: &'static str = "enclave.signed.so";
 
 extern {
     fn tls_client_new(eid: sgx_enclave_id_t, retval: *mut usize,
-                     fd: c_int, hostname: *const c_char, cert: *const c_char) -> sgx_status_t;
+                     fd: c_int, hostname: *const c_char, cert: *const c_char,
+                     protocols: *const *const c_char, protocol_count: usize) -> sgx_status_t;
     fn tls_client_read(eid: sgx_enclave_id_t, retval: *mut c_int,
                      session_id: usize, buf: *mut c_void, cnt: c_int) -> sgx_status_t;
     fn tls_client_write(eid: sgx_enclave_id_t, retval: *mut c_int,
@@ -48,6 +61,12 @@ extern {
                      session_id: usize) -> sgx_status_t;
     fn tls_client_close(eid: sgx_enclave_id_t,
                      session_id: usize) -> sgx_status_t;
+    fn tls_client_get_negotiated_protocol(eid: sgx_enclave_id_t, retval: *mut c_int,
+                     session_id: usize, buf: *mut c_char, buflen: usize) -> sgx_status_t;
+    fn tls_client_reserve(eid: sgx_enclave_id_t, retval: *mut usize) -> sgx_status_t;
+    fn tls_client_bench_contexts(eid: sgx_enclave_id_t, retval: *mut usize,
+                     iterations: usize) -> sgx_status_t;
+    fn tls_client_test_session_cache(eid: sgx_enclave_id_t, retval: *mut c_int) -> sgx_status_t;
 }
 
 fn init_enclave() -> SgxResult<SgxEnclave> {
@@ -107,20 +126,29 @@ impl TlsClient {
 }
 
 impl TlsClient {
-    fn new(enclave_id: sgx_enclave_id_t, sock: TcpStream, hostname: &str, cert: &str) -> Option<TlsClient> {
+    fn new(enclave_id: sgx_enclave_id_t, sock: TcpStream, hostname: &str, cert: &str,
+           protocols: &[&str]) -> Option<TlsClient> {
 
-        println!("[+] TlsClient new {} {}", hostname, cert);
+        println!("[+] TlsClient new {} {} {:?}", hostname, cert, protocols);
 
         let mut tlsclient_id: usize = 0xFFFF_FFFF_FFFF_FFFF;
         let c_host = CString::new(hostname.to_string()).unwrap();
         let c_cert = CString::new(cert.to_string()).unwrap();
+        let c_protocols: Vec<CString> = protocols.iter()
+            .map(|p| CString::new(p.to_string()).unwrap())
+            .collect();
+        let protocol_ptrs: Vec<*const c_char> = c_protocols.iter()
+            .map(|p| p.as_ptr())
+            .collect();
 
         let retval = unsafe {
             tls_client_new(enclave_id,
                            &mut tlsclient_id,
                            sock.as_raw_fd(),
                            c_host.as_ptr() as *const c_char,
-                           c_cert.as_ptr() as *const c_char)
+                           c_cert.as_ptr() as *const c_char,
+                           protocol_ptrs.as_ptr(),
+                           protocol_ptrs.len())
         };
 
         if retval != sgx_status_t::SGX_SUCCESS {
@@ -153,6 +181,28 @@ impl TlsClient {
         }
     }
 
+    /// The protocol the server selected through ALPN, if any.
+    fn negotiated_protocol(&self) -> Option<String> {
+        let mut retval = -1;
+        let mut buf = [0_u8; 255];
+        let result = unsafe {
+            tls_client_get_negotiated_protocol(self.enclave_id,
+                                               &mut retval,
+                                               self.tlsclient_id,
+                                               buf.as_mut_ptr() as *mut c_char,
+                                               buf.len())
+        };
+
+        if result != sgx_status_t::SGX_SUCCESS {
+            println!("[-] ECALL Enclave [tls_client_get_negotiated_protocol] Failed {}!", result);
+            return None;
+        }
+        if retval <= 0 {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&buf[..retval as usize]).into_owned())
+    }
+
     fn read_tls(&self, buf: &mut [u8]) -> isize {
         let mut retval = -1;
         let result = unsafe {
@@ -319,6 +369,106 @@ fn lookup_ipv4(host: &str, port: u16) -> SocketAddr {
     unreachable!("Cannot lookup address");
 }
 
+/// Time concurrent session lookups in the enclave. Build the enclave with
+/// `make CONTEXTS_LOCK=spin` to measure the SpinRwLock instead of the
+/// default SgxRwLock.
+fn bench_contexts(enclave_id: sgx_enclave_id_t) {
+    let start = Instant::now();
+    let threads: Vec<_> = (0..BENCH_THREADS)
+        .map(|_| thread::spawn(move || {
+            let mut found: usize = 0;
+            let result = unsafe {
+                tls_client_bench_contexts(enclave_id, &mut found, BENCH_ITERATIONS)
+            };
+            if result != sgx_status_t::SGX_SUCCESS {
+                println!("[-] ECALL Enclave [tls_client_bench_contexts] Failed {}!", result);
+            }
+            found
+        }))
+        .collect();
+    let found: usize = threads.into_iter().map(|t| t.join().unwrap()).sum();
+    let elapsed = start.elapsed();
+
+    let lookups = BENCH_THREADS * BENCH_ITERATIONS;
+    println!("[+] {} of {} session lookups succeeded on {} threads in {:?} ({} ns per lookup)",
+             found, lookups, BENCH_THREADS, elapsed,
+             elapsed.as_nanos() / lookups as u128);
+}
+
+fn reserve_session(enclave_id: sgx_enclave_id_t) -> usize {
+    let mut session_id: usize = INVALID_SESSION;
+    let result = unsafe { tls_client_reserve(enclave_id, &mut session_id) };
+    if result != sgx_status_t::SGX_SUCCESS {
+        println!("[-] ECALL Enclave [tls_client_reserve] Failed {}!", result);
+        return INVALID_SESSION;
+    }
+    session_id
+}
+
+fn close_session(enclave_id: sgx_enclave_id_t, session_id: usize) {
+    let result = unsafe { tls_client_close(enclave_id, session_id) };
+    if result != sgx_status_t::SGX_SUCCESS {
+        println!("[-] ECALL Enclave [tls_client_close] Failed {}!", result);
+    }
+}
+
+/// Hold 65 sessions at once. The 65th has to wait until another thread
+/// closes one, and a 66th times out while the pool stays full.
+fn test_pool(enclave_id: sgx_enclave_id_t) -> bool {
+    let mut sessions: Vec<usize> = (0..POOL_SIZE).map(|_| reserve_session(enclave_id)).collect();
+    if sessions.contains(&INVALID_SESSION) {
+        println!("[-] Could not reserve {} sessions", POOL_SIZE);
+        return false;
+    }
+
+    let delay = Duration::from_secs(1);
+    let closed = sessions.remove(0);
+    let closer = thread::spawn(move || {
+        thread::sleep(delay);
+        close_session(enclave_id, closed);
+    });
+    let start = Instant::now();
+    let reused = reserve_session(enclave_id);
+    let waited = start.elapsed();
+    closer.join().unwrap();
+    let mut passed = reused == closed && waited >= delay;
+    println!("[{}] Session {} was reused after {:?}",
+             if passed { "+" } else { "-" }, reused, waited);
+    if reused != INVALID_SESSION {
+        sessions.push(reused);
+    }
+
+    let start = Instant::now();
+    let extra = reserve_session(enclave_id);
+    let waited = start.elapsed();
+    if extra == INVALID_SESSION && waited >= POOL_TIMEOUT {
+        println!("[+] A full pool timed out after {:?}", waited);
+    } else {
+        println!("[-] A full pool returned session {} after {:?}", extra, waited);
+        sessions.push(extra);
+        passed = false;
+    }
+
+    for session_id in sessions {
+        close_session(enclave_id, session_id);
+    }
+    passed
+}
+
+/// Seal a session cache to a file, load it back, then check that a
+/// tampered file is discarded.
+fn test_session_cache(enclave_id: sgx_enclave_id_t) -> bool {
+    let mut retval: c_int = -1;
+    let result = unsafe { tls_client_test_session_cache(enclave_id, &mut retval) };
+    if result != sgx_status_t::SGX_SUCCESS {
+        println!("[-] ECALL Enclave [tls_client_test_session_cache] Failed {}!", result);
+        return false;
+    }
+    println!("[{}] Session cache round trip and tamper check",
+             if retval == 0 { "+" } else { "-" });
+    retval == 0
+}
+
 fn main() {
 
     let enclave = match init_enclave() {
@@ -332,6 +482,30 @@ fn main() {
         },
     };
 
+    if env::args().any(|arg| arg == "--bench-contexts") {
+        bench_contexts(enclave.geteid());
+        enclave.destroy();
+        return;
+    }
+
+    if env::args().any(|arg| arg == "--test-session-cache") {
+        let passed = test_session_cache(enclave.geteid());
+        enclave.destroy();
+        if !passed {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if env::args().any(|arg| arg == "--test-pool") {
+        let passed = test_pool(enclave.geteid());
+        enclave.destroy();
+        if !passed {
+            std::process::exit(1);
+        }
+        return;
+    }
+
     println!("[+] Test tlsclient in enclave, start!");
 
     let port = 8443;
@@ -343,7 +517,8 @@ fn main() {
     let tlsclient = TlsClient::new(enclave.geteid(),
                                    sock,
                                    hostname,
-                                   cert);
+                                   cert,
+                                   &["http/1.1"]);
 
     if tlsclient.is_some() {
         println!("[+] Tlsclient new success!");
@@ -365,6 +540,9 @@ fn main() {
             poll.poll(&mut events, None).unwrap();
             for ev in events.iter() {
                 if !tlsclient.ready(&mut poll, &ev) {
+                    if let Some(protocol) = tlsclient.negotiated_protocol() {
+                        println!("[+] ALPN protocol: {}", protocol);
+                    }
                     tlsclient.close();
                     break 'outer ;
                 }
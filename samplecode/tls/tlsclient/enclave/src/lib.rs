@@ -23,6 +23,7 @@
 
 extern crate sgx_types;
 extern crate sgx_trts;
+extern crate sgx_tseal;
 #[cfg(not(target_env = "sgx"))]
 #[macro_use]
 extern crate sgx_tstd as std;
@@ -30,12 +31,14 @@ extern crate sgx_tstd as std;
 #[macro_use]
 extern crate lazy_static;
 
-use sgx_trts::trts::{rsgx_lfence, rsgx_sfence};
+use sgx_trts::trts::{rsgx_raw_is_outside_enclave, rsgx_lfence, rsgx_sfence};
 
 use sgx_types::*;
-use std::collections;
+use sgx_tseal::SgxSealedData;
 
 use std::untrusted::fs;
+use std::untrusted::time::InstantEx;
+use std::io;
 use std::io::BufReader;
 
 use std::ffi::CStr;
@@ -44,35 +47,168 @@ use std::os::raw::c_char;
 use std::string::String;
 use std::vec::Vec;
 use std::boxed::Box;
-use std::io::{Read, Write};
+use std::io::{Read, TeeReader, Write};
+use std::log::Level;
+use std::sgxfs::{OpenOptions, SgxFile};
+use std::mem;
+use std::ptr;
 use std::slice;
-use std::sync::{Arc, SgxMutex, SgxRwLock};
+use std::sync::{Arc, SgxCondvar, SgxMutex};
+#[cfg(not(feature = "spin_contexts"))]
+use std::sync::SgxRwLock as ContextsLock;
+#[cfg(feature = "spin_contexts")]
+use std::sync::SpinRwLock as ContextsLock;
 use std::net::TcpStream;
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicUsize, AtomicPtr, Ordering};
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::time::{Duration, Instant};
 
 extern crate webpki;
 extern crate rustls;
-use rustls::Session;
+use rustls::{ProtocolVersion, Session, StoresClientSessions};
 
 pub struct TlsClient {
     socket: TcpStream,
     tls_session:  rustls::ClientSession,
+    audit_log: Option<SgxFile>,
 }
 
-static GLOBAL_CONTEXT_COUNT: AtomicUsize = AtomicUsize::new(0);
+/// Every plaintext byte received from a server is appended to this sealed
+/// file, so a session can be replayed for forensic analysis.
+const AUDIT_LOG: &str = "tlsclient_audit.log";
+
+/// TLS sessions are resumed across runs from this sealed file, see
+/// `SealedPersistCache`.
+const SESSION_CACHE_FILE: &str = "tlsclient_sessions.sealed";
+
+/// The number of sessions the enclave holds at once. Session ids are the
+/// slot indices `0..MAX_CONNECTIONS`.
+const MAX_CONNECTIONS: usize = 64;
+/// How long `tls_client_new` waits for a session to be closed when all
+/// slots are taken.
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(3);
 
 lazy_static! {
-    static ref GLOBAL_CONTEXTS: SgxRwLock<HashMap<usize, AtomicPtr<TlsClient>>> = {
-        SgxRwLock::new(HashMap::new())
-    };
+    static ref GLOBAL_CONTEXTS: TlsConnectionPool = TlsConnectionPool::new(ACQUIRE_TIMEOUT);
+    static ref SESSION_CACHE: Arc<SealedPersistCache> = Arc::new(SealedPersistCache::new(SESSION_CACHE_FILE));
+}
+
+/// A fixed-size ring of session slots.
+///
+/// A slot is `None` when free. An acquired slot holds a null pointer until
+/// its TlsClient is inserted.
+struct TlsConnectionPool {
+    // Build with the `spin_contexts` feature to guard the slots with a
+    // SpinRwLock instead of an SgxRwLock, see `tls_client_bench_contexts`.
+    slots: ContextsLock<Vec<Option<AtomicPtr<TlsClient>>>>,
+    // The slot the next search for a free one starts at, so that a closed
+    // session id is not handed out again right away.
+    next: SgxMutex<usize>,
+    released: SgxCondvar,
+    timeout: Duration,
+}
+
+impl TlsConnectionPool {
+    fn new(timeout: Duration) -> TlsConnectionPool {
+        TlsConnectionPool {
+            slots: ContextsLock::new((0..MAX_CONNECTIONS).map(|_| None).collect()),
+            next: SgxMutex::new(0),
+            released: SgxCondvar::new(),
+            timeout,
+        }
+    }
+
+    /// Take the first free slot at or after the ring position. If every
+    /// slot is in use, wait up to the pool timeout for one to be released.
+    fn acquire(&self) -> Option<usize> {
+        let deadline = Instant::now() + self.timeout;
+        // `next` stays locked between searching and waiting, so a release
+        // cannot slip in unnoticed.
+        let mut next = self.next.lock().ok()?;
+        loop {
+            if let Some(id) = self.take_free(*next) {
+                *next = (id + 1) % MAX_CONNECTIONS;
+                return Some(id);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                enclave_log!(Level::Error, "No free session slot after {:?}", self.timeout);
+                return None;
+            }
+            next = self.released.wait_timeout(next, deadline - now).ok()?.0;
+        }
+    }
+
+    fn take_free(&self, start: usize) -> Option<usize> {
+        let mut slots = match self.slots.write() {
+            Ok(slots) => slots,
+            Err(x) => {
+                enclave_log!(Level::Error, "Locking global contexts failed! {:?}", x);
+                return None;
+            },
+        };
+        let id = (start..MAX_CONNECTIONS).chain(0..start).find(|&id| slots[id].is_none())?;
+        slots[id] = Some(AtomicPtr::new(ptr::null_mut()));
+        Some(id)
+    }
+
+    /// Store the session of an acquired slot.
+    fn insert(&self, id: usize, session: *mut TlsClient) {
+        if let Ok(slots) = self.slots.read() {
+            if let Some(Some(slot)) = slots.get(id) {
+                slot.store(session, Ordering::SeqCst);
+            }
+        }
+    }
+
+    /// The session of an acquired slot, null if none has been inserted.
+    fn get(&self, id: usize) -> Option<*mut TlsClient> {
+        match self.slots.read() {
+            Ok(slots) => match slots.get(id) {
+                Some(Some(slot)) => Some(slot.load(Ordering::SeqCst)),
+                _ => {
+                    enclave_log!(Level::Error, "Global contexts cannot find session id = {}", id);
+                    None
+                }
+            },
+            Err(x) => {
+                enclave_log!(Level::Error, "Locking global contexts failed on get_session! {:?}", x);
+                None
+            },
+        }
+    }
+
+    /// Mark a slot free, drop its session and wake one waiting `acquire`.
+    fn release(&self, id: usize) {
+        let session = match self.slots.write() {
+            Ok(mut slots) => match slots.get_mut(id).and_then(Option::take) {
+                Some(slot) => slot.into_inner(),
+                None => return,
+            },
+            Err(_) => return,
+        };
+        if !session.is_null() {
+            let _ = unsafe { Box::<TlsClient>::from_raw(session) };
+        }
+
+        let _next = self.next.lock();
+        self.released.notify_one();
+    }
 }
 
 impl TlsClient {
     fn new(fd: c_int, hostname: &str, cfg: Arc<rustls::ClientConfig>) -> TlsClient {
         TlsClient {
             socket: TcpStream::new(fd).unwrap(),
-            tls_session: rustls::ClientSession::new(&cfg, webpki::DNSNameRef::try_from_ascii_str(hostname).unwrap())
+            tls_session: rustls::ClientSession::new(&cfg, webpki::DNSNameRef::try_from_ascii_str(hostname).unwrap()),
+            audit_log: match OpenOptions::new().append(true).open(AUDIT_LOG) {
+                Ok(f) => Some(f),
+                Err(e) => {
+                    enclave_log!(Level::Error, "Cannot open audit log: {:?}", e);
+                    None
+                }
+            },
         }
     }
 
@@ -81,13 +217,13 @@ impl TlsClient {
         // is broken.
         let rc = self.tls_session.read_tls(&mut self.socket);
         if rc.is_err() {
-            println!("TLS read error: {:?}", rc);
+            enclave_log!(Level::Error, "TLS read error: {:?}", rc);
             return -1;
         }
 
         // If we're ready but there's no data: EOF.
         if rc.unwrap() == 0 {
-            println!("EOF");
+            enclave_log!(Level::Info, "EOF");
             return -1;
         }
 
@@ -96,21 +232,25 @@ impl TlsClient {
         // TLS protocol problems and are fatal.
         let processed = self.tls_session.process_new_packets();
         if processed.is_err() {
-            println!("TLS error: {:?}", processed.unwrap_err());
+            enclave_log!(Level::Error, "TLS error: {:?}", processed.unwrap_err());
             return -1;
         }
 
         // Having read some TLS data, and processed any new messages,
         // we might have new plaintext as a result.
         //
-        // Read it and then write it to stdout.
-        let rc = self.tls_session.read_to_end(plaintext);
+        // Read it and then write it to stdout. A copy goes to the audit log;
+        // failing to write it does not interrupt the session.
+        let rc = match self.audit_log {
+            Some(ref mut log) => TeeReader::new(&mut self.tls_session, log).read_to_end(plaintext),
+            None => self.tls_session.read_to_end(plaintext),
+        };
 
         // If that fails, the peer might have started a clean TLS-level
         // session closure.
         if rc.is_err() {
             let err = rc.unwrap_err();
-            println!("Plaintext read error: {:?}", err);
+            enclave_log!(Level::Error, "Plaintext read error: {:?}", err);
             return -1;
         }
         plaintext.len() as c_int
@@ -130,84 +270,64 @@ impl TlsClient {
 }
 
 /// This is an example cache for client session data.
-/// It optionally dumps cached data to a file, but otherwise
-/// is just in-memory.
-///
-/// Note that the contents of such a file are extremely sensitive.
-/// Don't write this stuff to disk in production code.
+/// It is just in-memory, see `SealedPersistCache` for
+/// keeping it across runs.
 struct PersistCache {
-    cache: SgxMutex<collections::HashMap<Vec<u8>, Vec<u8>>>,
-    filename: Option<String>,
+    cache: SgxMutex<HashMap<Vec<u8>, Vec<u8>>>,
 }
 
 impl PersistCache {
-    /// Make a new cache.  If filename is Some, load the cache
-    /// from it and flush changes back to that file.
-    fn new(filename: &Option<String>) -> PersistCache {
-        let cache = PersistCache {
-            cache: SgxMutex::new(collections::HashMap::new()),
-            filename: filename.clone(),
-        };
-        if cache.filename.is_some() {
-            cache.load();
+    /// Make a new, empty cache.
+    fn new() -> PersistCache {
+        PersistCache {
+            cache: SgxMutex::new(HashMap::new()),
         }
-        cache
     }
 
-    /// If we have a filename, save the cache contents to it.
-    fn save(&self) {
+    /// Serialize the cache contents.
+    fn encode(&self) -> Vec<u8> {
         use rustls::internal::msgs::codec::Codec;
         use rustls::internal::msgs::base::PayloadU16;
 
-        if self.filename.is_none() {
-            return;
-        }
-
-        let mut file = fs::File::create(self.filename.as_ref().unwrap())
-            .expect("cannot open cache file");
-
+        let mut data = Vec::new();
         for (key, val) in self.cache.lock().unwrap().iter() {
-            let mut item = Vec::new();
-            let key_pl = PayloadU16::new(key.clone());
-            let val_pl = PayloadU16::new(val.clone());
-            key_pl.encode(&mut item);
-            val_pl.encode(&mut item);
-            file.write_all(&item).unwrap();
+            PayloadU16::new(key.clone()).encode(&mut data);
+            PayloadU16::new(val.clone()).encode(&mut data);
         }
+        data
     }
 
-    /// We have a filename, so replace the cache contents from it.
-    fn load(&self) {
+    /// Replace the cache contents with those serialized in `data`.
+    /// The cache is left empty if `data` is malformed.
+    fn decode(&self, data: &[u8]) {
         use rustls::internal::msgs::codec::{Codec, Reader};
         use rustls::internal::msgs::base::PayloadU16;
 
-        let mut file = match fs::File::open(self.filename.as_ref().unwrap()) {
-            Ok(f) => f,
-            Err(_) => return,
-        };
-        let mut data = Vec::new();
-        file.read_to_end(&mut data).unwrap();
-
         let mut cache = self.cache.lock()
             .unwrap();
         cache.clear();
-        let mut rd = Reader::init(&data);
+        let mut rd = Reader::init(data);
 
         while rd.any_left() {
-            let key_pl = PayloadU16::read(&mut rd).unwrap();
-            let val_pl = PayloadU16::read(&mut rd).unwrap();
-            cache.insert(key_pl.0, val_pl.0);
+            match (PayloadU16::read(&mut rd), PayloadU16::read(&mut rd)) {
+                (Some(key_pl), Some(val_pl)) => {
+                    cache.insert(key_pl.0, val_pl.0);
+                }
+                _ => {
+                    cache.clear();
+                    return;
+                }
+            }
         }
     }
 }
 
 impl rustls::StoresClientSessions for PersistCache {
-    /// put: insert into in-memory cache, and perhaps persist to disk.
+    /// put: insert into in-memory cache
     fn put(&self, key: Vec<u8>, value: Vec<u8>) -> bool {
         self.cache.lock()
             .unwrap()
             .insert(key, value);
-        self.save();
         true
     }
 
@@ -219,9 +339,94 @@ impl rustls::StoresClientSessions for PersistCache {
     }
 }
 
+/// A `PersistCache` kept in an untrusted file.
+///
+/// The file is unsealed with `sgx_unseal_data` once, when the cache
+/// is created, and `get` is answered from memory. The whole cache,
+/// keys and values, is sealed to this enclave with `sgx_seal_data`
+/// on every `put`. A file that is missing or does not unseal,
+/// because the host replaced or modified it, is silently discarded,
+/// so the host can at worst force a full handshake.
+struct SealedPersistCache {
+    cache: PersistCache,
+    filename: String,
+    // Held from inserting to writing the file, so that concurrent puts
+    // cannot write their snapshots in the wrong order.
+    save_lock: SgxMutex<()>,
+}
+
+impl SealedPersistCache {
+    /// Make a new cache backed by `filename`, loading it if it exists.
+    fn new(filename: &str) -> SealedPersistCache {
+        let cache = SealedPersistCache {
+            cache: PersistCache::new(),
+            filename: filename.to_owned(),
+            save_lock: SgxMutex::new(()),
+        };
+        cache.load();
+        cache
+    }
+
+    /// Seal the cache contents and write them to the file.
+    fn save(&self) -> io::Result<()> {
+        let data = self.cache.encode();
+        let sealed = SgxSealedData::<[u8]>::seal_data(&[], &data)?;
+        let size = SgxSealedData::<[u8]>::calc_raw_sealed_data_size(
+            sealed.get_add_mac_txt_len(), sealed.get_encrypt_txt_len());
+        let mut raw = vec![0_u8; size as usize];
+        unsafe {
+            sealed.to_raw_sealed_data_t(raw.as_mut_ptr() as *mut sgx_sealed_data_t, size)
+        }.ok_or(sgx_status_t::SGX_ERROR_UNEXPECTED)?;
+        fs::write(&self.filename, &raw)
+    }
+
+    /// Replace the cache contents with the unsealed file, or empty
+    /// the cache if the file cannot be read or unsealed.
+    fn load(&self) {
+        let data = self.unseal().unwrap_or_default();
+        self.cache.decode(&data);
+    }
+
+    /// Read the file and unseal it.
+    fn unseal(&self) -> io::Result<Vec<u8>> {
+        let mut raw = fs::read(&self.filename)?;
+        if raw.len() > u32::MAX as usize {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER.into());
+        }
+        let sealed = unsafe {
+            SgxSealedData::<[u8]>::from_raw_sealed_data_t(
+                raw.as_mut_ptr() as *mut sgx_sealed_data_t, raw.len() as u32)
+        }.ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        let unsealed = sealed.unseal_data()?;
+        Ok(unsealed.get_decrypt_txt().to_vec())
+    }
+}
+
+impl rustls::StoresClientSessions for SealedPersistCache {
+    /// put: insert into the cache and seal it to disk.
+    fn put(&self, key: Vec<u8>, value: Vec<u8>) -> bool {
+        let _saving = self.save_lock.lock().unwrap();
+        self.cache.put(key, value);
+        if let Err(e) = self.save() {
+            enclave_log!(Level::Error, "Cannot save session cache: {}", e);
+        }
+        true
+    }
+
+    /// get: from the in-memory cache, loaded when it was created.
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.cache.get(key)
+    }
+}
+
 /// Build a `ClientConfig` from our arguments
-fn make_config(cert: &str) -> Arc<rustls::ClientConfig> {
+///
+/// TLS 1.3 is preferred, TLS 1.2 is still offered for older servers.
+/// `protocols` are offered through ALPN in order of preference, no ALPN
+/// extension is sent if it is empty.
+fn make_config(cert: &str, protocols: &[String]) -> Arc<rustls::ClientConfig> {
     let mut config = rustls::ClientConfig::new();
+    config.versions = vec![ProtocolVersion::TLSv1_3, ProtocolVersion::TLSv1_2];
 
     let certfile = fs::File::open(cert).expect("Cannot open CA file");
     let mut reader = BufReader::new(certfile);
@@ -229,64 +434,78 @@ fn make_config(cert: &str) -> Arc<rustls::ClientConfig> {
         .add_pem_file(&mut reader)
         .unwrap();
 
-    let cache = Option::None;
-    let persist = Arc::new(PersistCache::new(&cache));
-    config.set_persistence(persist);
+    config.set_persistence(SESSION_CACHE.clone());
+    config.alpn_protocols = protocols.iter().map(|p| p.as_bytes().to_vec()).collect();
 
     Arc::new(config)
 }
 
+/// ALPN protocol names are 1 to 255 bytes long.
+const MAX_PROTOCOL_NAME_LEN: usize = 255;
+
+/// Copy a NUL terminated protocol name out of untrusted memory, checking
+/// every byte before reading it.
+fn copy_protocol_name(name: * const c_char) -> Option<String> {
+    if name.is_null() {
+        return None;
+    }
+
+    let mut bytes = Vec::new();
+    loop {
+        let p = unsafe { (name as * const u8).add(bytes.len()) };
+        if !rsgx_raw_is_outside_enclave(p, 1) {
+            return None;
+        }
+        rsgx_lfence();
+        match unsafe { *p } {
+            0 => break,
+            b if bytes.len() < MAX_PROTOCOL_NAME_LEN => bytes.push(b),
+            _ => return None,
+        }
+    }
+    if bytes.is_empty() {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Copy the array of ALPN protocol names passed to `tls_client_new`.
+/// A null array means no ALPN.
+fn copy_protocols(protocols: * const * const c_char, protocol_count: usize) -> Option<Vec<String>> {
+    if protocols.is_null() {
+        return Some(Vec::new());
+    }
+
+    let size = protocol_count.checked_mul(mem::size_of::<* const c_char>())?;
+    if !rsgx_raw_is_outside_enclave(protocols as * const u8, size) {
+        return None;
+    }
+    rsgx_lfence();
+    let names = unsafe { slice::from_raw_parts(protocols, protocol_count) }.to_vec();
+    names.into_iter().map(copy_protocol_name).collect()
+}
+
 struct Sessions;
 
 impl Sessions {
     fn new_session(svr_ptr : *mut TlsClient) -> Option<usize> {
-        match GLOBAL_CONTEXTS.write() {
-            Ok(mut gctxts) => {
-                let curr_id = GLOBAL_CONTEXT_COUNT.fetch_add(1, Ordering::Relaxed);
-                gctxts.insert(curr_id, AtomicPtr::new(svr_ptr));
-                Some(curr_id)
-            },
-            Err(x) => {
-                println!("Locking global context SgxRwLock failed! {:?}", x);
-                None
-            },
-        }
+        let curr_id = GLOBAL_CONTEXTS.acquire()?;
+        GLOBAL_CONTEXTS.insert(curr_id, svr_ptr);
+        Some(curr_id)
     }
 
     fn get_session(sess_id: size_t) -> Option<*mut TlsClient> {
-        match GLOBAL_CONTEXTS.read() {
-            Ok(gctxts) => {
-                match gctxts.get(&sess_id) {
-                    Some(s) => {
-                        Some(s.load(Ordering::SeqCst))
-                    },
-                    None => {
-                        println!("Global contexts cannot find session id = {}", sess_id);
-                        None
-                    }
-                }
-            },
-            Err(x) => {
-                println!("Locking global context SgxRwLock failed on get_session! {:?}", x);
-                None
-            },
-        }
+        GLOBAL_CONTEXTS.get(sess_id).filter(|p| !p.is_null())
     }
 
     fn remove_session(sess_id: size_t) {
-        if let Ok(mut gctxts) = GLOBAL_CONTEXTS.write() {
-            if let Some(session_ptr) = gctxts.get(&sess_id) {
-                let session_ptr = session_ptr.load(Ordering::SeqCst);
-                let session = unsafe { &mut *session_ptr };
-                let _ = unsafe { Box::<TlsClient>::from_raw(session as *mut _) };
-                let _ = gctxts.remove(&sess_id);
-            }
-        }
+        GLOBAL_CONTEXTS.release(sess_id)
     }
 }
 
 #[no_mangle]
-pub extern "C" fn tls_client_new(fd: c_int, hostname: * const c_char, cert: * const c_char) -> usize {
+pub extern "C" fn tls_client_new(fd: c_int, hostname: * const c_char, cert: * const c_char,
+                                 protocols: * const * const c_char, protocol_count: usize) -> usize {
     if hostname.is_null() || cert.is_null() {
         return 0xFFFF_FFFF_FFFF_FFFF;
     }
@@ -295,7 +514,13 @@ pub extern "C" fn tls_client_new(fd: c_int, hostname: * const c_char, cert: * co
     if certfile.is_err() {
         return 0xFFFF_FFFF_FFFF_FFFF;
     }
-    let config = make_config(certfile.unwrap());
+    let protocols = match copy_protocols(protocols, protocol_count) {
+        Some(p) => p,
+        None => {
+            return 0xFFFF_FFFF_FFFF_FFFF;
+        }
+    };
+    let config = make_config(certfile.unwrap(), &protocols);
     let name = unsafe { CStr::from_ptr(hostname).to_str() };
     let name = match name {
         Ok(n) => n,
@@ -305,6 +530,22 @@ pub extern "C" fn tls_client_new(fd: c_int, hostname: * const c_char, cert: * co
     };
     let p: *mut TlsClient = Box::into_raw(Box::new(TlsClient::new(fd, name, config)));
     match Sessions::new_session(p) {
+        Some(s) => s,
+        None => {
+            let _ = unsafe { Box::<TlsClient>::from_raw(p) };
+            0xFFFF_FFFF_FFFF_FFFF
+        },
+    }
+}
+
+/// Acquire a session slot without a connection, blocking like
+/// `tls_client_new` when the pool is exhausted. Release it with
+/// `tls_client_close`.
+///
+/// The application uses this to check that the pool blocks and times out.
+#[no_mangle]
+pub extern "C" fn tls_client_reserve() -> usize {
+    match GLOBAL_CONTEXTS.acquire() {
         Some(s) => s,
         None => 0xFFFF_FFFF_FFFF_FFFF,
     }
@@ -379,3 +620,90 @@ pub extern "C" fn tls_client_wants_write(session_id: usize)  -> c_int {
 pub extern "C" fn tls_client_close(session_id: usize) {
     Sessions::remove_session(session_id)
 }
+
+/// Copy the protocol agreed on through ALPN into `buf`.
+///
+/// Returns the length of the protocol name, 0 if no protocol has been
+/// negotiated (yet) and -1 if the session does not exist or `buf` is too
+/// small.
+#[no_mangle]
+pub extern "C" fn tls_client_get_negotiated_protocol(session_id: usize, buf: * mut c_char, buflen: usize) -> c_int {
+    if let Some(session_ptr) = Sessions::get_session(session_id) {
+        let session = unsafe { &mut *session_ptr };
+        let protocol = match session.tls_session.get_alpn_protocol() {
+            Some(p) => p,
+            None => return 0,
+        };
+        if buf.is_null() || buflen < protocol.len() {
+            return -1;
+        }
+
+        rsgx_sfence();
+        let raw_buf = unsafe { slice::from_raw_parts_mut(buf as * mut u8, protocol.len()) };
+        raw_buf.copy_from_slice(protocol);
+        protocol.len() as c_int
+    } else { -1 }
+}
+
+/// Look up a placeholder session `iterations` times, the way every other
+/// ECALL does, and return the number of successful lookups.
+///
+/// The application times concurrent calls to compare the lock guarding
+/// `GLOBAL_CONTEXTS` with and without the `spin_contexts` feature.
+#[no_mangle]
+pub extern "C" fn tls_client_bench_contexts(iterations: usize) -> usize {
+    // An acquired slot without a TlsClient serves as the placeholder.
+    let session_id = match GLOBAL_CONTEXTS.acquire() {
+        Some(id) => id,
+        None => return 0,
+    };
+    let found = (0..iterations)
+        .filter(|_| GLOBAL_CONTEXTS.get(session_id).is_some())
+        .count();
+    GLOBAL_CONTEXTS.release(session_id);
+    found
+}
+
+/// Check that `SealedPersistCache` keeps its entries across instances
+/// and discards its file once the file has been tampered with.
+///
+/// Returns 0 if it does. The application runs this with
+/// `--test-session-cache`.
+#[no_mangle]
+pub extern "C" fn tls_client_test_session_cache() -> c_int {
+    const FILENAME: &str = "tlsclient_sessions_test.sealed";
+
+    let _ = fs::remove_file(FILENAME);
+    let result = check_session_cache(FILENAME);
+    let _ = fs::remove_file(FILENAME);
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            enclave_log!(Level::Error, "Session cache check failed: {}", e);
+            -1
+        }
+    }
+}
+
+fn check_session_cache(filename: &str) -> io::Result<()> {
+    fn check(ok: bool, what: &str) -> io::Result<()> {
+        if ok { Ok(()) } else { Err(io::Error::new(io::ErrorKind::Other, what)) }
+    }
+
+    let cache = SealedPersistCache::new(filename);
+    check(cache.get(b"server").is_none(), "a new cache is not empty")?;
+    cache.put(b"server".to_vec(), b"session".to_vec());
+    check(cache.get(b"server") == Some(b"session".to_vec()), "put is not visible to get")?;
+
+    // a second instance loads what the first one sealed
+    let reloaded = SealedPersistCache::new(filename);
+    check(reloaded.get(b"server") == Some(b"session".to_vec()), "the sealed file did not round trip")?;
+
+    // flip a bit of the sealed ciphertext, which is at the end of the file
+    let mut raw = fs::read(filename)?;
+    let last = raw.len() - 1;
+    raw[last] ^= 1;
+    fs::write(filename, &raw)?;
+    let tampered = SealedPersistCache::new(filename);
+    check(tampered.get(b"server").is_none(), "a tampered file was not discarded")
+}
@@ -32,9 +32,14 @@ use std::net;
 use std::str;
 use std::io::{self, Read, Write};
 use std::collections::HashMap;
+use std::fs;
+use std::time::{Duration, SystemTime};
 
 const BUFFER_SIZE: usize = 1024;
 
+// How often to check whether the certificate files have been replaced.
+const RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
 static ENCLAVE_FILE: &'static str = "enclave.signed.so";
 
 extern {
@@ -52,6 +57,8 @@ extern {
                      session_id: size_t) -> sgx_status_t;
     fn tls_server_send_close(edi: sgx_enclave_id_t,
                      session_id: size_t) -> sgx_status_t;
+    fn tls_server_reload_cert(eid: sgx_enclave_id_t, retval: *mut c_int,
+                     cert: *const c_char, key: *const c_char) -> sgx_status_t;
 }
 
 fn init_enclave() -> SgxResult<SgxEnclave> {
@@ -95,6 +102,15 @@ struct TlsServer {
     mode: ServerMode,
     connections: HashMap<mio::Token, Connection>,
     next_id: usize,
+    cert_modified: Option<SystemTime>,
+}
+
+fn last_modified(files: &[&CString]) -> Option<SystemTime> {
+    files.iter()
+        .map(|f| fs::metadata(f.to_str().ok()?).and_then(|m| m.modified()).ok())
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .max()
 }
 
 impl TlsServer {
@@ -102,6 +118,7 @@ impl TlsServer {
 
         println!("[+] TlsServer new {:?} {:?}", cert, key);
 
+        let cert_modified = last_modified(&[&cert, &key]);
         TlsServer {
             enclave_id: enclave_id,
             server: server,
@@ -109,8 +126,35 @@ impl TlsServer {
             key: key,
             mode: mode,
             connections: HashMap::new(),
-            next_id: 2
+            next_id: 2,
+            cert_modified: cert_modified,
+        }
+    }
+
+    /// Hand the certificate files to the enclave again if they have changed
+    /// since they were last loaded.
+    fn reload_if_changed(&mut self) {
+        let modified = last_modified(&[&self.cert, &self.key]);
+        if modified.is_none() || modified == self.cert_modified {
+            return;
+        }
+
+        let mut retval: c_int = -1;
+        let result = unsafe {
+            tls_server_reload_cert(self.enclave_id,
+                                   &mut retval,
+                                   self.cert.as_bytes_with_nul().as_ptr() as * const c_char,
+                                   self.key.as_bytes_with_nul().as_ptr() as * const c_char)
+        };
+        if result != sgx_status_t::SGX_SUCCESS {
+            println!("[-] ECALL Enclave [tls_server_reload_cert] Failed {}!", result);
+        } else if retval != 0 {
+            // Before the first connection, the enclave loads the files anyway.
+            println!("[-] Certificate not reloaded");
+        } else {
+            println!("[+] Certificate reloaded");
         }
+        self.cert_modified = modified;
     }
 
     fn accept(&mut self, poll: &mut mio::Poll) -> bool {
@@ -562,8 +606,9 @@ fn main() {
     let mut events = mio::Events::with_capacity(256);
 
     'outer: loop {
-        poll.poll(&mut events, None)
+        poll.poll(&mut events, Some(RELOAD_INTERVAL))
             .unwrap();
+        tlsserv.reload_if_changed();
 
         for event in events.iter() {
             match event.token() {
@@ -40,29 +40,56 @@ use std::ffi::CStr;
 use std::os::raw::c_char;
 
 use std::vec::Vec;
-use std::boxed::Box;
 use std::io::{Read, Write};
+use std::log::Level;
 use std::slice;
-use std::sync::{Arc, SgxRwLock};
+use std::sync::{watch, Arc, SgxArc, SgxMutex, SgxRwLock};
 use std::net::TcpStream;
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicUsize, AtomicPtr, Ordering};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 extern crate webpki;
 extern crate rustls;
-use rustls::{Session, NoClientAuth};
+use rustls::{ClientHello, NoClientAuth, ProtocolVersion, ResolvesServerCert, Session};
+use rustls::sign::{self, CertifiedKey};
 
 pub struct TlsServer {
     socket: TcpStream,
     tls_session: rustls::ServerSession,
 }
 
+/// A session, shared by the session map and the ECALLs using it.
+type SharedSession = SgxArc<SgxMutex<TlsServer>>;
+
 static GLOBAL_CONTEXT_COUNT: AtomicUsize = AtomicUsize::new(0);
 
 lazy_static! {
-    static ref GLOBAL_CONTEXTS: SgxRwLock<HashMap<usize, AtomicPtr<TlsServer>>> = {
+    static ref GLOBAL_CONTEXTS: SgxRwLock<HashMap<usize, SharedSession>> = {
         SgxRwLock::new(HashMap::new())
     };
+    // Set up by the first `tls_server_new`, updated by `tls_server_reload_cert`.
+    static ref CERTIFICATES: SgxMutex<Option<CertificateWatch>> = SgxMutex::new(None);
+}
+
+/// The certificate chain and private key the server presents.
+#[derive(Clone)]
+struct CertificateChain(Arc<CertifiedKey>);
+
+struct CertificateWatch {
+    sender: watch::Sender<CertificateChain>,
+    // Cloned for every new session. Holding it keeps the channel open while
+    // no session is active, so a reload is never lost.
+    receiver: watch::Receiver<CertificateChain>,
+}
+
+/// Resolves a handshake to the certificate chain that is current when it
+/// happens, so a reload also covers sessions created before it.
+struct CertificateResolver(watch::Receiver<CertificateChain>);
+
+impl ResolvesServerCert for CertificateResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<CertifiedKey> {
+        Some((*self.0.current().0).clone())
+    }
 }
 
 impl TlsServer {
@@ -78,13 +105,13 @@ impl TlsServer {
         // is broken.
         let rc = self.tls_session.read_tls(&mut self.socket);
         if rc.is_err() {
-            println!("TLS read error: {:?}", rc);
+            enclave_log!(Level::Error, "TLS read error: {:?}", rc);
             return -1;
         }
 
         // If we're ready but there's no data: EOF.
         if rc.unwrap() == 0 {
-            println!("EOF");
+            enclave_log!(Level::Info, "EOF");
             return -1;
         }
 
@@ -93,7 +120,7 @@ impl TlsServer {
         // TLS protocol problems and are fatal.
         let processed = self.tls_session.process_new_packets();
         if processed.is_err() {
-            println!("TLS error: {:?}", processed.unwrap_err());
+            enclave_log!(Level::Error, "TLS error: {:?}", processed.unwrap_err());
             return -1;
         }
         return 0;
@@ -110,7 +137,7 @@ impl TlsServer {
         // session closure.
         if rc.is_err() {
             let err = rc.unwrap_err();
-            println!("Plaintext read error: {:?}", err);
+            enclave_log!(Level::Error, "Plaintext read error: {:?}", err);
             return -1;
         }
         plaintext.len() as c_int
@@ -161,13 +188,34 @@ fn load_private_key(filename: &str) -> rustls::PrivateKey {
     }
 }
 
-fn make_config(cert: &str, key: &str) -> Arc<rustls::ServerConfig> {
-
-    let mut config = rustls::ServerConfig::new(NoClientAuth::new());
-
+fn load_certificate_chain(cert: &str, key: &str) -> Option<CertificateChain> {
     let certs = load_certs(cert);
     let privkey = load_private_key(key);
-    config.set_single_cert_with_ocsp_and_sct(certs, privkey, vec![], vec![]).unwrap();
+    match sign::any_supported_type(&privkey) {
+        Ok(signing_key) => Some(CertificateChain(Arc::new(CertifiedKey::new(certs, Arc::new(signing_key))))),
+        Err(_) => {
+            enclave_log!(Level::Error, "Unsupported private key in {}", key);
+            None
+        },
+    }
+}
+
+/// Subscribe to the current certificate chain, loading it from `cert` and
+/// `key` if this is the first session.
+fn subscribe_certificates(cert: &str, key: &str) -> Option<watch::Receiver<CertificateChain>> {
+    let mut certificates = CERTIFICATES.lock().ok()?;
+    if certificates.is_none() {
+        let (sender, receiver) = watch::channel(load_certificate_chain(cert, key)?);
+        *certificates = Some(CertificateWatch { sender, receiver });
+    }
+    certificates.as_ref().map(|c| c.receiver.clone())
+}
+
+fn make_config(certificates: watch::Receiver<CertificateChain>) -> Arc<rustls::ServerConfig> {
+    let mut config = rustls::ServerConfig::new(NoClientAuth::new());
+    config.versions = vec![ProtocolVersion::TLSv1_3, ProtocolVersion::TLSv1_2];
+    config.cert_resolver = Arc::new(CertificateResolver(certificates));
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
 
     Arc::new(config)
 }
@@ -175,48 +223,50 @@ fn make_config(cert: &str, key: &str) -> Arc<rustls::ServerConfig> {
 struct Sessions;
 
 impl Sessions {
-    fn new_session(svr_ptr : *mut TlsServer) -> Option<usize> {
+    /// Register `session` and return its id. A session that is already
+    /// registered keeps the id it has.
+    fn new_session(session: SharedSession) -> Option<usize> {
         match GLOBAL_CONTEXTS.write() {
             Ok(mut gctxts) => {
+                if let Some((&id, _)) = gctxts.iter().find(|(_, s)| SgxArc::ptr_eq(s, &session)) {
+                    return Some(id);
+                }
                 let curr_id = GLOBAL_CONTEXT_COUNT.fetch_add(1, Ordering::Relaxed);
-                gctxts.insert(curr_id, AtomicPtr::new(svr_ptr));
+                gctxts.insert(curr_id, session);
                 Some(curr_id)
             },
             Err(x) => {
-                println!("Locking global context SgxRwLock failed! {:?}", x);
+                enclave_log!(Level::Error, "Locking global context SgxRwLock failed! {:?}", x);
                 None
             },
         }
     }
 
-    fn get_session(sess_id: size_t) -> Option<*mut TlsServer> {
+    fn get_session(sess_id: size_t) -> Option<SharedSession> {
         match GLOBAL_CONTEXTS.read() {
             Ok(gctxts) => {
                 match gctxts.get(&sess_id) {
                     Some(s) => {
-                        Some(s.load(Ordering::SeqCst))
+                        Some(s.clone())
                     },
                     None => {
-                        println!("Global contexts cannot find session id = {}", sess_id);
+                        enclave_log!(Level::Error, "Global contexts cannot find session id = {}", sess_id);
                         None
                     }
                 }
             },
             Err(x) => {
-                println!("Locking global context SgxRwLock failed on get_session! {:?}", x);
+                enclave_log!(Level::Error, "Locking global context SgxRwLock failed on get_session! {:?}", x);
                 None
             },
         }
     }
 
+    /// Unregister a session. It is dropped once the last ECALL still
+    /// using it returns.
     fn remove_session(sess_id: size_t) {
         if let Ok(mut gctxts) = GLOBAL_CONTEXTS.write() {
-            if let Some(session_ptr) = gctxts.get(&sess_id) {
-                let session_ptr = session_ptr.load(Ordering::SeqCst);
-                let session = unsafe { &mut *session_ptr };
-                let _ = unsafe { Box::<TlsServer>::from_raw(session as *mut _) };
-                let _ = gctxts.remove(&sess_id);
-            }
+            let _ = gctxts.remove(&sess_id);
         }
     }
 }
@@ -235,10 +285,14 @@ pub extern "C" fn tls_server_new(fd: c_int, cert: * const c_char, key: * const c
     if keyfile.is_err() {
         return 0xFFFF_FFFF_FFFF_FFFF;
     }
-    let config = make_config(certfile.unwrap(), keyfile.unwrap());
+    let certificates = match subscribe_certificates(certfile.unwrap(), keyfile.unwrap()) {
+        Some(c) => c,
+        None => return 0xFFFF_FFFF_FFFF_FFFF,
+    };
+    let config = make_config(certificates);
 
-    let p: *mut TlsServer = Box::into_raw(Box::new(TlsServer::new(fd, config)));
-    match Sessions::new_session(p) {
+    let session = SgxArc::new(SgxMutex::new(TlsServer::new(fd, config)));
+    match Sessions::new_session(session) {
         Some(s) => s,
         None => 0xFFFF_FFFF_FFFF_FFFF,
     }
@@ -246,8 +300,8 @@ pub extern "C" fn tls_server_new(fd: c_int, cert: * const c_char, key: * const c
 
 #[no_mangle]
 pub extern "C" fn tls_server_read(session_id: size_t, buf: * mut c_char, cnt: c_int) -> c_int {
-    if let Some(session_ptr) = Sessions::get_session(session_id) {
-        let session = unsafe { &mut *(session_ptr) };
+    if let Some(session) = Sessions::get_session(session_id) {
+        let mut session = session.lock().unwrap();
         if buf.is_null() || cnt == 0 {
             // just read_tls
             session.do_read()
@@ -274,8 +328,8 @@ pub extern "C" fn tls_server_read(session_id: size_t, buf: * mut c_char, cnt: c_
 
 #[no_mangle]
 pub extern "C" fn tls_server_write(session_id: usize, buf: * const c_char, cnt: c_int)  -> c_int {
-    if let Some(session_ptr) = Sessions::get_session(session_id) {
-        let session = unsafe { &mut *(session_ptr) };
+    if let Some(session) = Sessions::get_session(session_id) {
+        let mut session = session.lock().unwrap();
 
         // no buffer, just write_tls.
         if buf.is_null() || cnt == 0 {
@@ -295,8 +349,8 @@ pub extern "C" fn tls_server_write(session_id: usize, buf: * const c_char, cnt:
 
 #[no_mangle]
 pub extern "C" fn tls_server_wants_read(session_id: usize) -> c_int {
-    if let Some(session_ptr) = Sessions::get_session(session_id) {
-        let session = unsafe { &mut *(session_ptr) };
+    if let Some(session) = Sessions::get_session(session_id) {
+        let session = session.lock().unwrap();
         let result = session.tls_session.wants_read() as c_int;
         result
     } else { -1 }
@@ -304,8 +358,8 @@ pub extern "C" fn tls_server_wants_read(session_id: usize) -> c_int {
 
 #[no_mangle]
 pub extern "C" fn tls_server_wants_write(session_id: usize)  -> c_int {
-    if let Some(session_ptr) = Sessions::get_session(session_id) {
-        let session = unsafe { &mut *(session_ptr) };
+    if let Some(session) = Sessions::get_session(session_id) {
+        let session = session.lock().unwrap();
         let result = session.tls_session.wants_write() as c_int;
         result
     } else { -1 }
@@ -318,8 +372,42 @@ pub extern "C" fn tls_server_close(session_id: usize) {
 
 #[no_mangle]
 pub extern "C" fn tls_server_send_close(session_id: usize) {
-    if let Some(session_ptr) = Sessions::get_session(session_id) {
-        let session = unsafe { &mut *session_ptr };
+    if let Some(session) = Sessions::get_session(session_id) {
+        let mut session = session.lock().unwrap();
         session.tls_session.send_close_notify();
     }
 }
+
+/// Load a new certificate chain and key and hand them to every session,
+/// without restarting the enclave.
+///
+/// Returns 0 on success and -1 if the files cannot be used or no session
+/// has been created yet.
+#[no_mangle]
+pub extern "C" fn tls_server_reload_cert(cert: * const c_char, key: * const c_char) -> c_int {
+    if key.is_null() || cert.is_null() {
+        return -1;
+    }
+
+    let certfile = unsafe { CStr::from_ptr(cert).to_str() };
+    let keyfile = unsafe { CStr::from_ptr(key).to_str() };
+    let (certfile, keyfile) = match (certfile, keyfile) {
+        (Ok(c), Ok(k)) => (c, k),
+        _ => return -1,
+    };
+    let chain = match load_certificate_chain(certfile, keyfile) {
+        Some(c) => c,
+        None => return -1,
+    };
+
+    match CERTIFICATES.lock() {
+        Ok(certificates) => match certificates.as_ref().map(|c| c.sender.send(chain)) {
+            Some(Ok(())) => {
+                enclave_log!(Level::Info, "Reloaded certificate chain from {}", certfile);
+                0
+            },
+            _ => -1,
+        },
+        Err(_) => -1,
+    }
+}
@@ -0,0 +1,111 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+extern crate sgx_types;
+extern crate sgx_urts;
+use sgx_types::*;
+use sgx_urts::SgxEnclave;
+
+use std::net::UdpSocket;
+use std::thread;
+use std::time::Duration;
+
+static ENCLAVE_FILE: &'static str = "enclave.signed.so";
+
+// The enclave sends this many datagrams, see MESSAGES in the enclave.
+const ECHO_COUNT: usize = 3;
+
+extern {
+    fn udp_echo_client(eid: sgx_enclave_id_t, retval: *mut sgx_status_t,
+                       port: u16) -> sgx_status_t;
+}
+
+fn init_enclave() -> SgxResult<SgxEnclave> {
+    let mut launch_token: sgx_launch_token_t = [0; 1024];
+    let mut launch_token_updated: i32 = 0;
+    // call sgx_create_enclave to initialize an enclave instance
+    // Debug Support: set 2nd parameter to 1
+    let debug = 1;
+    let mut misc_attr = sgx_misc_attribute_t {secs_attr: sgx_attributes_t { flags:0, xfrm:0}, misc_select:0};
+    SgxEnclave::create(ENCLAVE_FILE,
+                       debug,
+                       &mut launch_token,
+                       &mut launch_token_updated,
+                       &mut misc_attr)
+}
+
+/// Echo ECHO_COUNT datagrams back to their sender, or give up after five
+/// seconds without traffic.
+fn echo_server(socket: UdpSocket) {
+    let mut buf = [0_u8; 1500];
+    for _ in 0..ECHO_COUNT {
+        match socket.recv_from(&mut buf) {
+            Ok((len, peer)) => {
+                println!("[+] Echo server received {} bytes from {}", len, peer);
+                if let Err(e) = socket.send_to(&buf[..len], peer) {
+                    println!("[-] Echo server send failed: {}", e);
+                    return;
+                }
+            },
+            Err(e) => {
+                println!("[-] Echo server receive failed: {}", e);
+                return;
+            },
+        }
+    }
+}
+
+fn main() {
+    let enclave = match init_enclave() {
+        Ok(r) => {
+            println!("[+] Init Enclave Successful {}!", r.geteid());
+            r
+        },
+        Err(x) => {
+            println!("[-] Init Enclave Failed {}!", x.as_str());
+            return;
+        },
+    };
+
+    let socket = UdpSocket::bind("127.0.0.1:0").expect("[-] Bind echo server failed!");
+    socket.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let port = socket.local_addr().unwrap().port();
+    println!("[+] Echo server listening on port {}", port);
+    let server = thread::spawn(move || echo_server(socket));
+
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let result = unsafe {
+        udp_echo_client(enclave.geteid(), &mut retval, port)
+    };
+    server.join().unwrap();
+
+    match result {
+        sgx_status_t::SGX_SUCCESS => {},
+        _ => {
+            println!("[-] ECALL Enclave Failed {}!", result.as_str());
+            return;
+        }
+    }
+    match retval {
+        sgx_status_t::SGX_SUCCESS => {},
+        _ => {
+            println!("[-] udp_echo_client failed {}!", retval.as_str());
+            return;
+        }
+    }
+    println!("[+] udp_echo_client success...");
+    enclave.destroy();
+}
@@ -0,0 +1,92 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+#![crate_name = "udpsampleenclave"]
+#![crate_type = "staticlib"]
+
+#![cfg_attr(not(target_env = "sgx"), no_std)]
+#![cfg_attr(target_env = "sgx", feature(rustc_private))]
+
+extern crate sgx_types;
+#[cfg(not(target_env = "sgx"))]
+#[macro_use]
+extern crate sgx_tstd as std;
+
+use sgx_types::*;
+use std::io::{self, ErrorKind};
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::str;
+use std::time::Duration;
+
+const MESSAGES: [&str; 3] = [
+    "Hello from the enclave!",
+    "This datagram went through the connected socket.",
+    "Goodbye!",
+];
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[no_mangle]
+pub extern "C" fn udp_echo_client(port: u16) -> sgx_status_t {
+    match echo_client(port) {
+        Ok(()) => sgx_status_t::SGX_SUCCESS,
+        Err(e) => {
+            println!("[-] UDP echo client failed: {}", e);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+fn echo_client(port: u16) -> io::Result<()> {
+    let server = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let socket = UdpSocket::bind("127.0.0.1:0")?;
+    socket.set_read_timeout(Some(TIMEOUT))?;
+    socket.set_write_timeout(Some(TIMEOUT))?;
+    println!("[+] Enclave bound UDP socket {}", socket.local_addr()?);
+
+    let mut buf = [0_u8; 1500];
+
+    // Unconnected mode: every datagram names its destination and every
+    // reply reports its source.
+    socket.send_to(MESSAGES[0].as_bytes(), server)?;
+    let (len, from) = socket.recv_from(&mut buf)?;
+    if from != server {
+        return Err(io::Error::new(ErrorKind::Other, "reply from an unexpected address"));
+    }
+    check_echo(&buf[..len], MESSAGES[0])?;
+
+    // Connected mode: the OS drops datagrams that do not come from the
+    // server. Both the address and the payload pass through the untrusted
+    // host, so a real protocol must authenticate its messages itself.
+    socket.connect(server)?;
+    for message in &MESSAGES[1..] {
+        socket.send(message.as_bytes())?;
+        let len = socket.recv(&mut buf)?;
+        check_echo(&buf[..len], message)?;
+    }
+
+    Ok(())
+}
+
+fn check_echo(reply: &[u8], expected: &str) -> io::Result<()> {
+    match str::from_utf8(reply) {
+        Ok(reply) if reply == expected => {
+            println!("[+] Enclave received echo: {}", reply);
+            Ok(())
+        }
+        _ => Err(io::Error::new(ErrorKind::InvalidData, "echo does not match")),
+    }
+}
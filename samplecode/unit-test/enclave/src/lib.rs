@@ -38,6 +38,8 @@ extern crate sgx_alloc;
 extern crate sgx_rand;
 extern crate sgx_trts;
 extern crate sgx_tseal;
+extern crate sgx_dcap;
+extern crate sgx_ra_tls;
 #[macro_use]
 extern crate memoffset;
 extern crate sgx_serialize;
@@ -46,6 +48,8 @@ pub use sgx_serialize::*;
 extern crate sgx_serialize_derive;
 extern crate sgx_libc;
 extern crate sgx_signal;
+extern crate rustls;
+extern crate webpki;
 
 pub use sgx_serialize::*;
 use sgx_tunittest::*;
@@ -59,6 +63,9 @@ mod utils;
 mod test_crypto;
 use test_crypto::*;
 
+mod test_chacha20poly1305;
+use test_chacha20poly1305::*;
+
 mod test_assert;
 use test_assert::*;
 
@@ -68,6 +75,12 @@ use test_rts::*;
 mod test_seal;
 use test_seal::*;
 
+mod test_dcap;
+use test_dcap::*;
+
+mod test_ra_tls;
+use test_ra_tls::*;
+
 mod test_rand;
 use test_rand::*;
 
@@ -98,6 +111,23 @@ use test_thread::*;
 mod test_mpsc;
 use test_mpsc::*;
 
+mod test_io;
+use test_io::*;
+
+mod test_fmt;
+use test_fmt::*;
+mod test_string;
+use test_string::*;
+
+mod test_collections;
+use test_collections::*;
+
+mod test_sync;
+use test_sync::*;
+
+mod test_net;
+use test_net::*;
+
 mod test_alignbox;
 use test_alignbox::*;
 
@@ -114,14 +144,56 @@ use test_fp::*;
 
 #[no_mangle]
 pub extern "C" fn test_main_entrance() -> size_t {
+    record_panics();
+
     rsgx_unit_tests!(
         // tcrypto
-        test_rsgx_sha256_slice,
-        test_rsgx_sha256_handle,
+        rsgx_parameterized_tests!(
+            test_rsgx_sha256_slice,
+            ["abc", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"],
+            ["abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+             "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"],
+            ["abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+             "cf5b16a778af8380036ce59e7b0492370b249b11e8f07a51afac45037afee9d1"],
+        ),
+        rsgx_parameterized_tests!(
+            test_rsgx_sha256_handle,
+            ["abc", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"],
+            ["abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+             "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"],
+            ["abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+             "cf5b16a778af8380036ce59e7b0492370b249b11e8f07a51afac45037afee9d1"],
+        ),
+        test_pbkdf2,
+        test_hmac,
+        test_digest_empty,
+        test_hkdf,
+        test_pbkdf2_kdf,
+        test_rsa_pss,
+        test_ecdsa_batch_verify,
+        test_ctr_drbg,
+        test_x25519,
+        test_ed25519,
+        test_ec_key_pkcs8,
+        test_shake_xof,
+        test_sha3,
+        test_blake2,
+        test_blake3,
+        test_argon2,
+        test_ecies,
+        test_threshold_ecdsa,
+        test_aes_gcm_siv,
+        test_aes_siv,
+        test_aes_256_gcm,
+        test_chacha20poly1305,
+        test_chacha20poly1305_params,
+        test_x509,
         // assert
         foo_panic,
         foo_should,
         foo_assert,
+        test_panic_hook,
+        test_unit_test_timeout,
         // rts::veh
         test_register_first_exception_handler,
         test_register_last_exception_handler,
@@ -138,6 +210,8 @@ pub extern "C" fn test_main_entrance() -> size_t {
         test_raw_is_outside_enclave,
         // rts::macros
         test_global_ctors_object,
+        // rts::stack
+        test_stack_probe,
         // rts::error
         test_error,
         // rts::libc
@@ -152,15 +226,33 @@ pub extern "C" fn test_main_entrance() -> size_t {
         // tseal
         test_seal_unseal,
         test_number_sealing, // Thanks to @silvanegli
+        test_streaming_seal_unseal,
         test_array_sealing,  // Thanks to @silvanegli
         test_mac_aadata_slice,
         test_mac_aadata_number,
+        // dcap
+        test_dcap_quote_parse,
+        test_dcap_quote_unsupported,
+        // ra_tls
+        test_ra_tls_cert,
+        test_ra_tls_cert_errors,
         // rand
         test_rand_os_sgxrng,
+        test_rand_fill_bytes_verified,
         test_rand_distributions,
         test_rand_isaac_isaacrng,
         test_rand_chacharng,
+        test_rand_pcgrng,
+        test_rand_zeroize,
         test_rand_reseeding,
+        test_rand_reseed_strategy,
+        test_rand_hypergeometric,
+        test_rand_exponential_truncated,
+        test_rand_student_t,
+        test_rand_beta,
+        test_rand_weighted,
+        test_rand_gen_strings,
+        test_rand_gen_secure_token,
         // serialize
         test_serialize_base,
         test_serialize_struct,
@@ -171,6 +263,48 @@ pub extern "C" fn test_main_entrance() -> size_t {
         test_fs,
         // std::fs untrusted mode
         test_fs_untrusted_fs_feature_enabled,
+        // std::net
+        test_net_tcp_shutdown_write,
+        test_net_socketpair,
+        test_net_socketpair_tls,
+        test_net_socketpair_tls13,
+        test_net_socketpair_tls_alpn,
+        test_net_ip_filter,
+        // std::sync
+        test_sync_mutex_lock_or_init,
+        test_sync_rwlock_optimistic_read,
+        test_sync_rwlock_optimistic_read_throughput,
+        test_sync_mutex_try_lock_for,
+        test_sync_condvar_broadcast_if,
+        test_sync_arc_try_unwrap,
+        test_sync_mutex_into_inner_poisoned,
+        test_sync_fair_mutex,
+        test_sync_fair_mutex_fairness,
+        test_sync_spin_mutex,
+        test_sync_spin_rwlock,
+        test_sync_mpsc_queue,
+        test_sync_mpsc_queue_drop,
+        test_sync_mpsc_queue_ping_pong,
+        test_sync_watch,
+        test_sync_watch_broadcast,
+        // std::collections
+        test_collections_bloom_filter,
+        test_collections_secure_map,
+        test_collections_secure_map_trace,
+        test_collections_index_set,
+        test_collections_timed_cache,
+        // std::io
+        test_io_encrypting_writer,
+        test_io_read_write_ext,
+        test_io_read_tls_record_header,
+        test_io_chunked_writer,
+        test_io_bufreader_fill_buf_checked,
+        test_io_tee,
+        test_io_error_sgx_status,
+        // std::fmt
+        test_fmt_display_buffer,
+        // std::string
+        test_string_constant_time,
         // std::time
         test_std_time,
         // rand
@@ -178,11 +312,18 @@ pub extern "C" fn test_main_entrance() -> size_t {
         // types
         check_metadata_size,
         check_version,
+        check_quote4_size,
+        test_quote4_parse,
+        test_quote4_parse_errors,
+        test_td_report,
+        test_measurement_types,
         // env
         test_env_vars_os,
         test_env_self_exe_path,
         test_env_current_dir,
         test_env_home_dir,
+        test_env_args_override,
+        test_env_enclave_measurement,
         //path
         test_path_stat_is_correct_on_is_dir,
         test_path_fileinfo_false_when_checking_is_file_on_a_directory,
@@ -230,6 +371,7 @@ pub extern "C" fn test_main_entrance() -> size_t {
         test_thread_size_of_option_thread_id,
         test_thread_id_equal,
         test_thread_id_not_equal,
+        test_thread_joinset,
         //test mpsc
         test_mpsc_smoke,
         test_mpsc_drop_full,
@@ -15,8 +15,26 @@
 // specific language governing permissions and limitations
 // under the License..
 
+use sgx_tunittest::*;
+use std::boxed::Box;
+use std::panic;
+use std::string::String;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::SgxMutex;
+use std::thread;
+use std::time::Duration;
 use std::vec::Vec;
 
+// the panics of the tests, recorded by the hook of record_panics
+static PANICS: SgxMutex<Vec<String>> = SgxMutex::new(Vec::new());
+
+pub fn record_panics() {
+    panic::set_hook(Box::new(|info| {
+        let mut panics = PANICS.lock().unwrap_or_else(|e| e.into_inner());
+        panics.push(format!("{}", info));
+    }));
+}
+
 pub fn foo_panic() {
     let v: Vec<u32> = vec![1];
     v[0];
@@ -35,3 +53,46 @@ pub fn foo_assert() {
     should_panic!(assert!(false));
     should_panic!(panic!());
 }
+
+#[allow(unreachable_code)]
+pub fn test_panic_hook() {
+    let before = PANICS.lock().unwrap().len();
+    should_panic!(panic!("recorded by the hook"));
+    let panics = PANICS.lock().unwrap();
+    assert_eq!(panics.len(), before + 1);
+    assert!(panics[before].contains("recorded by the hook"));
+    assert!(panics[before].contains("test_assert.rs"));
+}
+
+// set when spin_until_timeout unwinds
+static SPIN_DROPPED: AtomicBool = AtomicBool::new(false);
+
+struct SpinGuard;
+
+impl Drop for SpinGuard {
+    fn drop(&mut self) {
+        SPIN_DROPPED.store(true, Ordering::SeqCst);
+    }
+}
+
+fn spin_until_timeout() {
+    let _guard = SpinGuard;
+    loop {
+        rsgx_unit_test_check_timeout();
+        thread::yield_now();
+    }
+}
+
+pub fn test_unit_test_timeout() {
+    let failed = rsgx_unit_tests!(timeout_ms = 100, foo_assert, spin_until_timeout);
+    assert_eq!(failed, 1);
+
+    // the timed out test panics at its next check and drops its guard
+    for _ in 0..100 {
+        if SPIN_DROPPED.load(Ordering::SeqCst) {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(SPIN_DROPPED.load(Ordering::SeqCst));
+}
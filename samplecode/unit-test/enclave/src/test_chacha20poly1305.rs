@@ -0,0 +1,136 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use sgx_tcrypto::chacha20poly1305::*;
+use sgx_types::*;
+use std::vec::Vec;
+use utils::*;
+
+// (key, nonce, plaintext, aad, ciphertext || tag), the AEAD examples of
+// RFC 8439 sections 2.8.2 and A.5
+static CHACHA20_POLY1305_TEST_VEC: &'static [(&'static str, &'static str, &'static str, &'static str, &'static str)] = &[
+    (
+        "808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f",
+        "070000004041424344454647",
+        "4c616469657320616e642047656e746c656d656e206f662074686520636c6173\
+         73206f66202739393a204966204920636f756c64206f6666657220796f75206f\
+         6e6c79206f6e652074697020666f7220746865206675747572652c2073756e73\
+         637265656e20776f756c642062652069742e",
+        "50515253c0c1c2c3c4c5c6c7",
+        "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d6\
+         3dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b36\
+         92ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc\
+         3ff4def08e4b7a9de576d26586cec64b61161ae10b594f09e26a7e902ecbd060\
+         0691",
+    ),
+    (
+        "1c9240a5eb55d38af333888604f6b5f0473917c1402b80099dca5cbc207075c0",
+        "000000000102030405060708",
+        "496e7465726e65742d4472616674732061726520647261667420646f63756d65\
+         6e74732076616c696420666f722061206d6178696d756d206f6620736978206d\
+         6f6e74687320616e64206d617920626520757064617465642c207265706c6163\
+         65642c206f72206f62736f6c65746564206279206f7468657220646f63756d65\
+         6e747320617420616e792074696d652e20497420697320696e617070726f7072\
+         6961746520746f2075736520496e7465726e65742d4472616674732061732072\
+         65666572656e6365206d6174657269616c206f7220746f206369746520746865\
+         6d206f74686572207468616e206173202fe2809c776f726b20696e2070726f67\
+         726573732e2fe2809d",
+        "f33388860000000000004e91",
+        "64a0861575861af460f062c79be643bd5e805cfd345cf389f108670ac76c8cb2\
+         4c6cfc18755d43eea09ee94e382d26b0bdb7b73c321b0100d4f03b7f355894cf\
+         332f830e710b97ce98c8a84abd0b948114ad176e008d33bd60f982b1ff37c855\
+         9797a06ef4f0ef61c186324e2b3506383606907b6a7c02b0f9f6157b53c867e4\
+         b9166c767b804d46a59b5216cde7a4e99040c5a40433225ee282a1b0a06c523e\
+         af4534d7f83fa1155b0047718cbc546a0d072b04b3564eea1b422273f548271a\
+         0bb2316053fa76991955ebd63159434ecebb4e466dae5a1073a6727627097a10\
+         49e617d91d361094fa68f0ff77987130305beaba2eda04df997b714d6c6f2c29\
+         a6ad5cb4022b02709beead9d67890cbb22392336fea1851f38",
+    ),
+];
+
+pub fn test_chacha20poly1305() {
+    for &(key, nonce, plaintext, aad, sealed) in CHACHA20_POLY1305_TEST_VEC.iter() {
+        let mut k = [0_u8; CHACHA20_POLY1305_KEY_SIZE];
+        k.copy_from_slice(&hex_to_bytes(key));
+        let mut n = [0_u8; CHACHA20_POLY1305_NONCE_SIZE];
+        n.copy_from_slice(&hex_to_bytes(nonce));
+        let plaintext = hex_to_bytes(plaintext);
+        let aad = hex_to_bytes(aad);
+        let aead = ChaCha20Poly1305::new(&k);
+
+        let mut out = vec![0_u8; plaintext.len() + CHACHA20_POLY1305_TAG_SIZE];
+        assert_eq!(aead.seal(&n, &plaintext, &aad, &mut out).unwrap(), out.len());
+        assert_eq!(hex_to_bytes(sealed), out);
+
+        let mut opened = vec![0_u8; plaintext.len()];
+        assert_eq!(aead.open(&n, &out, &aad, &mut opened).unwrap(), plaintext.len());
+        assert_eq!(plaintext, opened);
+
+        // any change to the ciphertext, tag, aad or nonce is detected, and
+        // nothing is released
+        let mut untouched = vec![0_u8; plaintext.len()];
+        out[0] ^= 1;
+        assert_eq!(
+            aead.open(&n, &out, &aad, &mut untouched),
+            Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH)
+        );
+        out[0] ^= 1;
+        let last = out.len() - 1;
+        out[last] ^= 1;
+        assert!(aead.open(&n, &out, &aad, &mut untouched).is_err());
+        out[last] ^= 1;
+        assert!(aead.open(&n, &out, b"other", &mut untouched).is_err());
+        n[0] ^= 1;
+        assert!(aead.open(&n, &out, &aad, &mut untouched).is_err());
+        assert!(untouched.iter().all(|&b| b == 0));
+    }
+}
+
+pub fn test_chacha20poly1305_params() {
+    let aead = ChaCha20Poly1305::new(&[7; CHACHA20_POLY1305_KEY_SIZE]);
+    let nonce = [0_u8; CHACHA20_POLY1305_NONCE_SIZE];
+
+    // an empty message still carries a tag
+    let mut tag = [0_u8; CHACHA20_POLY1305_TAG_SIZE];
+    assert_eq!(aead.seal(&nonce, b"", b"aad", &mut tag).unwrap(), CHACHA20_POLY1305_TAG_SIZE);
+    assert_eq!(aead.open(&nonce, &tag, b"aad", &mut []).unwrap(), 0);
+
+    // buffers that are too short are rejected
+    let mut short = [0_u8; 22];
+    assert_eq!(
+        aead.seal(&nonce, b"message", b"", &mut short),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+    assert_eq!(
+        aead.open(&nonce, &[0_u8; 15], b"", &mut short),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+    let mut sealed = [0_u8; 23];
+    aead.seal(&nonce, b"message", b"", &mut sealed).unwrap();
+    assert_eq!(
+        aead.open(&nonce, &sealed, b"", &mut [0_u8; 6]),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+
+    // the keystream continues across 64 byte blocks
+    let long: Vec<u8> = (0..200).map(|i| i as u8).collect();
+    let mut sealed = vec![0_u8; long.len() + CHACHA20_POLY1305_TAG_SIZE];
+    aead.seal(&nonce, &long, b"", &mut sealed).unwrap();
+    let mut prefix = vec![0_u8; 64 + CHACHA20_POLY1305_TAG_SIZE];
+    aead.seal(&nonce, &long[..64], b"", &mut prefix).unwrap();
+    assert_eq!(&sealed[..64], &prefix[..64]);
+}
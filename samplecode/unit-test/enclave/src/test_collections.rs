@@ -0,0 +1,263 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use std::collections::{BloomFilter, HashMap, HashSet, IndexSet, SecureMap, TimedCache};
+use std::panic;
+use std::string::{String, ToString};
+use std::time::{Duration, Instant};
+use std::untrusted::time::InstantEx;
+use std::vec::Vec;
+
+pub fn test_collections_bloom_filter() {
+    // 1000 items at a target false positive rate of 1%:
+    // BITS = -1000 * ln(0.01) / ln(2)^2 = 9586, K = 9586 / 1000 * ln(2) = 7
+    const N: u64 = 1000;
+    let mut filter = BloomFilter::<9586, 7>::new();
+    assert!(filter.is_empty());
+    assert_eq!(filter.false_positive_rate(), 0.0);
+
+    for i in 0..N {
+        filter.insert(&i);
+    }
+    for i in 0..N {
+        assert!(filter.contains(&i));
+    }
+
+    let estimated = filter.false_positive_rate();
+    assert!(estimated > 0.005 && estimated < 0.015, "{}", estimated);
+    let trials = 100_000;
+    let false_positives = (N..N + trials).filter(|i| filter.contains(i)).count();
+    let rate = false_positives as f64 / trials as f64;
+    assert!(rate < 0.015, "{}", rate);
+
+    // Same seed, same hash functions.
+    let mut odd = BloomFilter::<9586, 7>::with_seed(filter.seed());
+    let mut even = BloomFilter::<9586, 7>::with_seed(filter.seed());
+    for i in 0..N {
+        if i % 2 == 0 {
+            even.insert(&i);
+        } else {
+            odd.insert(&i);
+        }
+    }
+    even.merge(&odd);
+    assert!(even == filter);
+
+    should_panic!(BloomFilter::<9586, 7>::with_seed([1; 16])
+        .merge(&BloomFilter::<9586, 7>::with_seed([0; 16])));
+
+    filter.clear();
+    assert!(filter.is_empty());
+    assert!(!filter.contains(&0_u64));
+}
+
+pub fn test_collections_secure_map() {
+    let mut map = SecureMap::with_capacity(3);
+    assert!(map.is_empty());
+    assert_eq!(map.capacity(), 3);
+    assert_eq!(map.insert("alice", 1), None);
+    assert_eq!(map.insert("bob", 2), None);
+    assert_eq!(map.insert("alice", 3), Some(1));
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get(&"alice"), Some(3));
+    assert!(map.contains_key(&"bob"));
+    assert!(!map.contains_key(&"carol"));
+    assert_eq!(map.remove(&"alice"), Some(3));
+    assert_eq!(map.remove(&"alice"), None);
+    assert_eq!(map.len(), 1);
+
+    // against a HashMap, with enough entries for recursive position maps
+    for &capacity in &[1_usize, 64, 65, 2000] {
+        let mut map = SecureMap::with_capacity(capacity);
+        let mut reference = HashMap::new();
+        let mut x = capacity as u64;
+        for step in 0..capacity * 6 {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let key = (x >> 33) % (capacity as u64 * 2);
+            match (x >> 20) % 3 {
+                0 if reference.len() < capacity || reference.contains_key(&key) => {
+                    assert_eq!(map.insert(key, step), reference.insert(key, step));
+                }
+                1 => assert_eq!(map.get(&key), reference.get(&key).cloned()),
+                _ => assert_eq!(map.remove(&key), reference.remove(&key)),
+            }
+            assert_eq!(map.len(), reference.len());
+        }
+        for (key, value) in reference.iter() {
+            assert_eq!(map.get(key), Some(*value));
+        }
+    }
+
+    // a full map rejects a new key whichever of its buckets is emptier,
+    // and is left unchanged
+    for _ in 0..16 {
+        let mut full = SecureMap::with_capacity(2);
+        full.insert(1, ());
+        full.insert(2, ());
+        assert_eq!(full.insert(2, ()), Some(()));
+        for key in 3..7 {
+            let rejected = panic::catch_unwind(panic::AssertUnwindSafe(|| full.insert(key, ())));
+            assert!(rejected.is_err());
+            assert_eq!(full.len(), 2);
+            assert!(!full.contains_key(&key));
+        }
+    }
+}
+
+pub fn test_collections_secure_map_trace() {
+    fn trace<F: FnOnce(&mut SecureMap<String, u32>)>(map: &mut SecureMap<String, u32>, f: F) -> Vec<(usize, usize)> {
+        map.start_trace();
+        f(map);
+        map.take_trace()
+    }
+    fn levels(trace: &[(usize, usize)]) -> Vec<usize> {
+        trace.iter().map(|&(level, _)| level).collect()
+    }
+
+    let mut map = SecureMap::with_capacity(256);
+    for i in 0..200 {
+        map.insert(i.to_string(), i);
+    }
+    map.insert("alice".to_string(), 1);
+    map.insert("bob".to_string(), 2);
+
+    // every operation reads the same number of paths at the same levels,
+    // whether the key is present or not
+    let alice = trace(&mut map, |m| assert_eq!(m.get(&"alice".to_string()), Some(1)));
+    let bob = trace(&mut map, |m| assert_eq!(m.get(&"bob".to_string()), Some(2)));
+    let carol = trace(&mut map, |m| assert_eq!(m.get(&"carol".to_string()), None));
+    assert_eq!(levels(&alice), levels(&bob));
+    assert_eq!(levels(&alice), levels(&carol));
+    let update = trace(&mut map, |m| assert_eq!(m.insert("alice".to_string(), 3), Some(1)));
+    let insert = trace(&mut map, |m| assert_eq!(m.insert("carol".to_string(), 4), None));
+    assert_eq!(levels(&update), levels(&insert));
+    let remove = trace(&mut map, |m| assert_eq!(m.remove(&"carol".to_string()), Some(4)));
+    assert_eq!(levels(&remove), levels(&alice));
+
+    // and the paths are uniformly distributed whatever the key
+    const BINS: usize = 16;
+    const ROUNDS: usize = 2000;
+    let mut histograms = [[0_u32; BINS]; 2];
+    for (histogram, key) in histograms.iter_mut().zip(["alice", "bob"].iter()) {
+        let key = key.to_string();
+        for _ in 0..ROUNDS {
+            for (level, leaf) in trace(&mut map, |m| assert!(m.get(&key).is_some())) {
+                if level == 0 {
+                    histogram[leaf % BINS] += 1;
+                }
+            }
+        }
+    }
+    let expected = (2 * ROUNDS / BINS) as f64;
+    for histogram in histograms.iter() {
+        let chi_squared: f64 = histogram
+            .iter()
+            .map(|&n| (n as f64 - expected).powi(2) / expected)
+            .sum();
+        // 15 degrees of freedom, p = 0.001
+        assert!(chi_squared < 37.7, "chi-squared {}", chi_squared);
+    }
+    // two-sample test between the keys
+    let chi_squared: f64 = (0..BINS)
+        .map(|i| {
+            let (a, b) = (histograms[0][i] as f64, histograms[1][i] as f64);
+            (a - b).powi(2) / (a + b)
+        })
+        .sum();
+    assert!(chi_squared < 37.7, "chi-squared {}", chi_squared);
+}
+
+pub fn test_collections_index_set() {
+    let mut set = IndexSet::new();
+    assert!(set.insert("b"));
+    assert!(set.insert("a"));
+    assert!(!set.insert("b"));
+    assert!(set.insert("c"));
+    assert_eq!(set.iter().cloned().collect::<Vec<_>>(), ["b", "a", "c"]);
+    assert!(set.remove(&"a"));
+    assert!(!set.remove(&"a"));
+    assert!(!set.contains(&"a"));
+    assert_eq!(set.get_index(1), Some(&"c"));
+    assert_eq!(set.len(), 2);
+
+    // Intersect two 10,000 element inputs with duplicates, keeping the
+    // order in which items first appear in the left input.
+    let left: Vec<u32> = (0..10_000).map(|i| (i * 7919) % 6_000).collect();
+    let right: Vec<u32> = (0..10_000).map(|i| (i * 104_729) % 9_000 + 3_000).collect();
+    let left_set: IndexSet<u32> = left.iter().cloned().collect();
+    let right_set: IndexSet<u32> = right.iter().cloned().collect();
+    let intersection: IndexSet<u32> = left_set
+        .iter()
+        .filter(|x| right_set.contains(x))
+        .cloned()
+        .collect();
+
+    let right_hash: HashSet<u32> = right.iter().cloned().collect();
+    let mut expected = Vec::new();
+    let mut seen = HashSet::new();
+    for x in left.iter() {
+        if right_hash.contains(x) && seen.insert(*x) {
+            expected.push(*x);
+        }
+    }
+    assert_eq!(left_set.len(), 6_000);
+    assert!(!expected.is_empty());
+    assert_eq!(intersection.iter().cloned().collect::<Vec<_>>(), expected);
+    for (i, x) in expected.iter().enumerate() {
+        assert_eq!(intersection.get_index(i), Some(x));
+    }
+}
+
+pub fn test_collections_timed_cache() {
+    fn spin(d: Duration) {
+        let start = Instant::now();
+        while start.elapsed() < d {}
+    }
+
+    let mut cache = TimedCache::new(Duration::from_millis(100));
+    assert_eq!(cache.ttl(), Duration::from_millis(100));
+    assert!(cache.is_empty());
+    assert_eq!(cache.insert("session".to_string(), 1), None);
+    assert_eq!(cache.get("session"), Some(&1));
+    assert!(cache.contains_key("session"));
+    assert_eq!(cache.get("unknown"), None);
+    assert_eq!(*cache.get_or_insert_with("session".to_string(), || 2), 1);
+
+    spin(Duration::from_millis(200));
+    assert_eq!(cache.get("session"), None);
+    assert!(!cache.contains_key("session"));
+    // expired entries stay until evicted
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.remove_expired(), 1);
+    assert!(cache.is_empty());
+
+    // an expired entry is replaced, and an unexpired one is kept
+    cache.insert("a".to_string(), 1);
+    spin(Duration::from_millis(200));
+    assert_eq!(*cache.get_or_insert_with("a".to_string(), || 2), 2);
+    assert_eq!(*cache.get_or_insert_with("a".to_string(), || 3), 2);
+
+    // insert returns the previous value only if it has not expired
+    assert_eq!(cache.insert("a".to_string(), 4), Some(2));
+    cache.insert("b".to_string(), 5);
+    spin(Duration::from_millis(200));
+    assert_eq!(cache.insert("a".to_string(), 6), None);
+    assert_eq!(cache.remove("b"), None);
+    assert_eq!(cache.remove_expired(), 0);
+    assert_eq!(cache.remove("a"), Some(6));
+    assert!(cache.is_empty());
+}
@@ -15,40 +15,1826 @@
 // specific language governing permissions and limitations
 // under the License..
 
+use sgx_tcrypto::pbkdf2::*;
 use sgx_tcrypto::*;
-use std::string::String;
+use sgx_tseal::SgxSealedData;
+use sgx_types::*;
+use std::vec::Vec;
+use std::time::Instant;
+use std::untrusted::time::InstantEx;
 use utils::*;
 
-static HASH_TEST_VEC: &'static [&'static str] = &[
-    &"abc",
-    &"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
-    &"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+pub fn test_rsgx_sha256_slice(input: &str, expected: &str) {
+    let hash = rsgx_sha256_slice(input.as_bytes()).unwrap();
+    assert_eq!(hex_to_bytes(expected), hash);
+}
+
+pub fn test_rsgx_sha256_handle(input: &str, expected: &str) {
+    let shah = SgxShaHandle::new();
+    shah.init().unwrap();
+    shah.update_slice(input.as_bytes()).unwrap();
+    let hash = shah.get_hash().unwrap();
+    shah.close().unwrap();
+    assert_eq!(hex_to_bytes(expected), hash);
+}
+
+// (password, salt, iterations, SHA-256 result, SHA-384 result), the
+// RFC 6070 inputs with HMAC-SHA256/384 in place of HMAC-SHA1
+static PBKDF2_TEST_VEC: &'static [(&'static [u8], &'static [u8], u32, &'static str, &'static str)] = &[
+    (
+        b"password",
+        b"salt",
+        1,
+        "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b",
+        "c0e14f06e49e32d73f9f52ddf1d0c5c7191609233631dadd76a567db42b78676",
+    ),
+    (
+        b"password",
+        b"salt",
+        2,
+        "ae4d0c95af6b46d32d0adff928f06dd02a303f8ef3c251dfd6e2d85a95474c43",
+        "54f775c6d790f21930459162fc535dbf04a939185127016a04176a0730c6f1f4",
+    ),
+    (
+        b"password",
+        b"salt",
+        4096,
+        "c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a",
+        "559726be38db125bc85ed7895f6e3cf574c7a01c080c3447db1e8a76764deb3c",
+    ),
+    (
+        b"passwordPASSWORDpassword",
+        b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+        4096,
+        "348c89dbcbd32b2f32d814b8116e84cf2b17347ebc1800181c4e2a1fb8dd53e1c635518c7dac47e9",
+        "819143ad66df9a552559b9e131c52ae6c5c1b0eed18f4d283b8c5c9eaeb92b392c147cc2d2869d58",
+    ),
+    (
+        b"pass\0word",
+        b"sa\0lt",
+        4096,
+        "89b69d0516f829893c696226650a8687",
+        "a3f00ac8657e095f8e0823d232fc60b3",
+    ),
 ];
 
-static HASH_SHA256_TRUTH: &'static [&'static str] = &[
-    &"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
-    &"248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
-    &"cf5b16a778af8380036ce59e7b0492370b249b11e8f07a51afac45037afee9d1",
+pub fn test_pbkdf2() {
+    for &(password, salt, iterations, sha256, sha384) in PBKDF2_TEST_VEC {
+        let mut out = vec![0_u8; sha256.len() / 2];
+        Pbkdf2Sha256::derive_unchecked(password, salt, iterations, &mut out).unwrap();
+        assert_eq!(hex_to_bytes(sha256), out);
+        Pbkdf2Sha384::derive_unchecked(password, salt, iterations, &mut out).unwrap();
+        assert_eq!(hex_to_bytes(sha384), out);
+    }
+
+    let mut out = [0_u8; 32];
+    assert_eq!(
+        Pbkdf2Sha256::derive(b"password", b"salt", PBKDF2_MIN_ITERATIONS - 1, &mut out),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+    assert_eq!(
+        Pbkdf2Sha384::derive(b"password", b"salt", 4096, &mut out),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+    assert_eq!(
+        Pbkdf2Sha256::derive_unchecked(b"password", b"salt", 1, &mut [0_u8; 65]),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+    assert_eq!(
+        Pbkdf2Sha256::derive_unchecked(b"password", b"salt", 0, &mut out),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+
+    let start = Instant::now();
+    Pbkdf2Sha256::derive(b"password", b"salt", PBKDF2_MIN_ITERATIONS, &mut out).unwrap();
+    assert!(start.elapsed().as_millis() >= 1);
+}
+
+// (key, data, HMAC-SHA256, HMAC-SHA384, HMAC-SHA512), test cases 1-4, 6 and 7
+// of RFC 4231
+static HMAC_TEST_VEC: &'static [(&'static str, &'static str, &'static str, &'static str, &'static str)] = &[
+    (
+        "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b",
+        "4869205468657265",
+        "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+        "afd03944d84895626b0825f4ab46907f15f9dadbe4101ec682aa034c7cebc59c\
+         faea9ea9076ede7f4af152e8b2fa9cb6",
+        "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cde\
+         daa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854",
+    ),
+    (
+        "4a656665",
+        "7768617420646f2079612077616e7420666f72206e6f7468696e673f",
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+        "af45d2e376484031617f78d2b58a6b1b9c7ef464f5a01b47e42ec3736322445e\
+         8e2240ca5e69e2c78b3239ecfab21649",
+        "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+         9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+    ),
+    (
+        "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+        "dddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddd\
+         dddddddddddddddddddddddddddddddddddd",
+        "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+        "88062608d3e6ad8a0aa2ace014c8a86f0aa635d947ac9febe83ef4e55966144b\
+         2a5ab39dc13814b94e3ab6e101a34f27",
+        "fa73b0089d56a284efb0f0756c890be9b1b5dbdd8ee81a3655f83e33b2279d39\
+         bf3e848279a722c806b485a47e67c807b946a337bee8942674278859e13292fb",
+    ),
+    (
+        "0102030405060708090a0b0c0d0e0f10111213141516171819",
+        "cdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcd\
+         cdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcd",
+        "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b",
+        "3e8a69b7783c25851933ab6290af6ca77a9981480850009cc5577c6e1f573b4e\
+         6801dd23c4a7d679ccf8a386c674cffb",
+        "b0ba465637458c6990e5a8c5f61d4af7e576d97ff94b872de76f8050361ee3db\
+         a91ca5c11aa25eb4d679275cc5788063a5f19741120c4f2de2adebeb10a298dd",
+    ),
+    (
+        "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
+         aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
+         aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
+         aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
+         aaaaaa",
+        "54657374205573696e67204c6172676572205468616e20426c6f636b2d53697a\
+         65204b6579202d2048617368204b6579204669727374",
+        "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+        "4ece084485813e9088d2c63a041bc5b44f9ef1012a2b588f3cd11f05033ac4c6\
+         0c2ef6ab4030fe8296248df163f44952",
+        "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f352\
+         6b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598",
+    ),
+    (
+        "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
+         aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
+         aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
+         aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
+         aaaaaa",
+        "5468697320697320612074657374207573696e672061206c6172676572207468\
+         616e20626c6f636b2d73697a65206b657920616e642061206c61726765722074\
+         68616e20626c6f636b2d73697a6520646174612e20546865206b6579206e6565\
+         647320746f20626520686173686564206265666f7265206265696e6720757365\
+         642062792074686520484d414320616c676f726974686d2e",
+        "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2",
+        "6617178e941f020d351e2f254e8fd32c602420feb0b8fb9adccebb82461e99c5\
+         a678cc31e799176d3860e6110c46523e",
+        "e37b6a775dc87dbaa4dfa9f96e5e3ffddebd71f8867289865df5a32d20cdc944\
+         b6022cac3c4982b10d5eeb55c3e4de15134676fb6de0446065c97440fa8c6a58",
+    ),
 ];
+pub fn test_hmac() {
+    use sgx_tcrypto::hmac::*;
+
+    for &(key, data, sha256, sha384, sha512) in HMAC_TEST_VEC.iter() {
+        let key = hex_to_bytes(key);
+        let data = hex_to_bytes(data);
 
-pub fn test_rsgx_sha256_slice() {
-    let test_size = HASH_TEST_VEC.len();
-    for i in 0..test_size {
-        let input_str = String::from(HASH_TEST_VEC[i]);
-        let hash = rsgx_sha256_slice(input_str.as_bytes()).unwrap();
-        assert_eq!(hex_to_bytes(HASH_SHA256_TRUTH[i]), hash);
+        let tag = Hmac::<Sha256>::mac(&key, &data).unwrap();
+        assert_eq!(hex_to_bytes(sha256), &tag.as_bytes()[..]);
+        let tag = Hmac::<Sha384>::mac(&key, &data).unwrap();
+        assert_eq!(hex_to_bytes(sha384), &tag.as_bytes()[..]);
+        assert!(tag.verify(&hex_to_bytes(sha384)));
+        let tag = Hmac::<Sha512>::mac(&key, &data).unwrap();
+        assert_eq!(hex_to_bytes(sha512), &tag.as_bytes()[..]);
+
+        // feeding the data in pieces gives the same tag
+        let mut hmac = Hmac::<Sha384>::new(&key).unwrap();
+        for chunk in data.chunks(7) {
+            hmac.update(chunk).unwrap();
+        }
+        let streamed = hmac.finalize().unwrap();
+        assert_eq!(streamed, Hmac::<Sha384>::mac(&key, &data).unwrap());
     }
+
+    // HMAC-SHA256 agrees with the SDK implementation
+    let key: sgx_hmac_256bit_key_t = [7; SGX_HMAC256_KEY_SIZE];
+    let tag = Hmac::<Sha256>::mac(&key, b"message").unwrap();
+    assert_eq!(rsgx_hmac_sha256_slice(&key, b"message").unwrap(), tag.into_bytes());
+
+    let tag = Hmac::<Sha384>::mac(b"key", b"message").unwrap();
+    let mut other = tag.into_bytes();
+    other[47] ^= 1;
+    assert!(!tag.verify(&other));
+    assert!(!tag.verify(&tag.as_bytes()[..32]));
+    assert!(tag != Hmac::<Sha384>::mac(b"key", b"messagf").unwrap());
+
+    // an empty message is valid input
+    let tag = Hmac::<Sha256>::mac(b"key", b"").unwrap();
+    assert_eq!(
+        hex_to_bytes("5d5d139563c95b5967b9bd9a8c9b233a9dedb45072794cd232dc1b74832607d0"),
+        &tag.as_bytes()[..]
+    );
+    let tag = Hmac::<Sha384>::mac(b"key", b"").unwrap();
+    assert_eq!(
+        hex_to_bytes(
+            "99f44bb4e73c9d0ef26533596c8d8a32a5f8c10a9b997d30d89a7e35ba1ccf20\
+             0b985f72431202b891fe350da410e43f"
+        ),
+        &tag.as_bytes()[..]
+    );
+
+    // HMAC-SHA1, test cases 1 and 2 of RFC 2202
+    let tag = Hmac::<Sha1>::mac(&[0x0b; 20], b"Hi There").unwrap();
+    assert_eq!(hex_to_bytes("b617318655057264e28bc0b6fb378c8ef146be00"), &tag.as_bytes()[..]);
+    let tag = Hmac::<Sha1>::mac(b"Jefe", b"what do ya want for nothing?").unwrap();
+    assert_eq!(hex_to_bytes("effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"), &tag.as_bytes()[..]);
+    let tag = Hmac::<Sha1>::mac(b"key", b"").unwrap();
+    assert_eq!(hex_to_bytes("f42bb0eeb018ebbd4597ae7213711ec60760843f"), &tag.as_bytes()[..]);
+}
+
+pub fn test_digest_empty() {
+    use sgx_tcrypto::hmac::*;
+
+    let mut sha256 = Sha256::new().unwrap();
+    sha256.update(&[]).unwrap();
+    assert_eq!(
+        hex_to_bytes("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+        &sha256.finalize().unwrap()[..]
+    );
+
+    let mut sha384 = Sha384::new().unwrap();
+    sha384.update(&[]).unwrap();
+    assert_eq!(
+        hex_to_bytes(
+            "38b060a751ac96384cd9327eb1b1e36a21fdb71114be07434c0cc7bf63f6e1da\
+             274edebfe76f65fbd51ad2f14898b95b"
+        ),
+        &sha384.finalize().unwrap()[..]
+    );
+
+    let mut sha1 = Sha1::new().unwrap();
+    sha1.update(&[]).unwrap();
+    assert_eq!(
+        hex_to_bytes("da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+        &sha1.finalize().unwrap()[..]
+    );
+
+    // empty updates between non-empty ones change nothing
+    let mut sha256 = Sha256::new().unwrap();
+    sha256.update(b"ab").unwrap();
+    sha256.update(&[]).unwrap();
+    sha256.update(b"c").unwrap();
+    assert_eq!(rsgx_sha256_slice(&b"abc"[..]).unwrap(), sha256.finalize().unwrap());
+}
+
+// (ikm, salt, info, PRK, OKM) for HKDF-SHA256, test cases 1-3 of RFC 5869
+static HKDF_SHA256_TEST_VEC: &'static [(&'static str, &'static str, &'static str, &'static str, &'static str)] = &[
+    (
+        "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b",
+        "000102030405060708090a0b0c",
+        "f0f1f2f3f4f5f6f7f8f9",
+        "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5",
+        "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf\
+         34007208d5b887185865",
+    ),
+    (
+        "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f\
+         202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f\
+         404142434445464748494a4b4c4d4e4f",
+        "606162636465666768696a6b6c6d6e6f707172737475767778797a7b7c7d7e7f\
+         808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f\
+         a0a1a2a3a4a5a6a7a8a9aaabacadaeaf",
+        "b0b1b2b3b4b5b6b7b8b9babbbcbdbebfc0c1c2c3c4c5c6c7c8c9cacbcccdcecf\
+         d0d1d2d3d4d5d6d7d8d9dadbdcdddedfe0e1e2e3e4e5e6e7e8e9eaebecedeeef\
+         f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff",
+        "06a6b88c5853361a06104c9ceb35b45cef760014904671014a193f40c15fc244",
+        "b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c\
+         59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71\
+         cc30c58179ec3e87c14c01d5c1f3434f1d87",
+    ),
+    (
+        "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b",
+        "",
+        "",
+        "19ef24a32c717b167f33a91d6f648bdf96596776afdb6377ac434c1c293ccb04",
+        "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d\
+         9d201395faa4b61a96c8",
+    ),
+];
+pub fn test_hkdf() {
+    use sgx_tcrypto::hmac::*;
+    use sgx_tcrypto::kdf::*;
+
+    for &(ikm, salt, info, prk, okm) in HKDF_SHA256_TEST_VEC.iter() {
+        let ikm = hex_to_bytes(ikm);
+        let salt = hex_to_bytes(salt);
+        let info = hex_to_bytes(info);
+        let derived = Hkdf::<Sha256>::extract(Some(&salt), &ikm).unwrap();
+        assert_eq!(hex_to_bytes(prk), derived.as_bytes());
+
+        let mut out = vec![0_u8; okm.len() / 2];
+        derived.expand(&info, &mut out).unwrap();
+        assert_eq!(hex_to_bytes(okm), out);
+
+        let from_prk = HkdfPrk::<Sha256>::from_prk(&hex_to_bytes(prk)).unwrap();
+        let mut again = vec![0_u8; out.len()];
+        from_prk.expand(&info, &mut again).unwrap();
+        assert_eq!(out, again);
+    }
+
+    // an empty salt is the same as no salt
+    let ikm = [0x0b_u8; 22];
+    let a = Hkdf::<Sha256>::extract(None, &ikm).unwrap();
+    let b = Hkdf::<Sha256>::extract(Some(&[]), &ikm).unwrap();
+    assert_eq!(a.as_bytes(), b.as_bytes());
+
+    // the inputs of RFC 5869 test case 1 with SHA-384 and SHA-512
+    let salt = hex_to_bytes("000102030405060708090a0b0c");
+    let info = hex_to_bytes("f0f1f2f3f4f5f6f7f8f9");
+    let mut out = [0_u8; 42];
+    Hkdf::<Sha384>::extract(Some(&salt), &ikm).unwrap().expand(&info, &mut out).unwrap();
+    assert_eq!(
+        hex_to_bytes("9b5097a86038b805309076a44b3a9f38063e25b516dcbf369f394cfab43685f7\
+             48b6457763e4f0204fc5"),
+        &out[..]
+    );
+    Hkdf::<Sha512>::extract(Some(&salt), &ikm).unwrap().expand(&info, &mut out).unwrap();
+    assert_eq!(
+        hex_to_bytes("832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9fc7fe92c14815793\
+             38da362cb8d9f925d7cb"),
+        &out[..]
+    );
+
+    // at most 255 blocks can be derived
+    let mut out = vec![0_u8; 255 * 32];
+    a.expand(b"", &mut out).unwrap();
+    out.push(0);
+    assert_eq!(a.expand(b"", &mut out), Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER));
+    assert_eq!(
+        HkdfPrk::<Sha256>::from_prk(&[0_u8; 31]).err(),
+        Some(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+}
+
+// (password, salt, iterations, DK) for PBKDF2-HMAC-SHA1, RFC 6070 without
+// the 16777216 iteration case
+static PBKDF2_SHA1_TEST_VEC: &'static [(&'static [u8], &'static [u8], u32, &'static str)] = &[
+    (b"password", b"salt", 1, "0c60c80f961f0e71f3a9b524af6012062fe037a6"),
+    (b"password", b"salt", 2, "ea6c014dc72d6f8ccd1ed92ace1d41f0d8de8957"),
+    (b"password", b"salt", 4096, "4b007901b765489abead49d926f721d065a429c1"),
+    (
+        b"passwordPASSWORDpassword",
+        b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+        4096,
+        "3d2eec4fe41c849b80c8d83662c0e44a8b291a964cf2f07038",
+    ),
+    (b"pass\0word", b"sa\0lt", 4096, "56fa6aa75548099dcc37d7f03425e0c3"),
+];
+
+// SHA-512 results for the RFC 6070 inputs of PBKDF2_TEST_VEC, in order
+static PBKDF2_SHA512_TEST_VEC: &'static [&'static str] = &[
+    "867f70cf1ade02cff3752599a3a53dc4af34c7a669815ae5d513554e1c8cf252",
+    "e1d9c16aa681708a45f5c7c4e215ceb66e011a2e9f0040713f18aefdb866d53c",
+    "d197b1b33db0143e018b12f3d1d1479e6cdebdcc97c5c0f87f6902e072f457b5",
+    "8c0511f4c6e597c6ac6315d8f0362e225f3c501495ba23b868c005174dc4ee71115b59f9e60cd953",
+    "9d9e9c4cd21fe4be24d5b8244c759665",
+];
+
+pub fn test_pbkdf2_kdf() {
+    use sgx_tcrypto::hmac::{Sha1, Sha256, Sha512};
+    use sgx_tcrypto::kdf::*;
+    use sgx_tcrypto::util::ct_eq;
+
+    for (&(password, salt, iterations, sha256, _), &sha512) in
+        PBKDF2_TEST_VEC.iter().zip(PBKDF2_SHA512_TEST_VEC.iter())
+    {
+        let mut out = vec![0_u8; sha256.len() / 2];
+        Pbkdf2::<Sha256>::derive_unchecked(password, salt, iterations, &mut out).unwrap();
+        assert!(ct_eq(&hex_to_bytes(sha256), &out));
+        Pbkdf2::<Sha512>::derive_unchecked(password, salt, iterations, &mut out).unwrap();
+        assert!(ct_eq(&hex_to_bytes(sha512), &out));
+    }
+    for &(password, salt, iterations, expected) in PBKDF2_SHA1_TEST_VEC.iter() {
+        let mut out = vec![0_u8; expected.len() / 2];
+        Pbkdf2::<Sha1>::derive_unchecked(password, salt, iterations, &mut out).unwrap();
+        assert!(ct_eq(&hex_to_bytes(expected), &out));
+    }
+
+    let mut out = [0_u8; 64];
+    assert_eq!(
+        Pbkdf2::<Sha512>::derive(b"password", b"salt", PBKDF2_MIN_ITERATIONS - 1, &mut out),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+    Pbkdf2::<Sha512>::derive_unchecked(b"password", b"salt", 10_000, &mut out).unwrap();
+    assert_eq!(
+        hex_to_bytes("72629a41b076e588fba8c71ca37fadc9acdc8e7321b9cb4ea55fd0bf9fe8ed72\
+             def92b4c7dff5242a0254945b945394ce4d6008e947bdc7593085cd1e2f6a375"),
+        &out[..]
+    );
+    assert_eq!(
+        Pbkdf2::<Sha512>::derive_unchecked(b"password", b"salt", 1, &mut [0_u8; 65]),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+
+    assert!(ct_eq(b"", b""));
+    assert!(ct_eq(b"secret", b"secret"));
+    assert!(!ct_eq(b"secret", b"secreT"));
+    assert!(!ct_eq(b"secret", b"secret\0"));
+}
+
+// A 2048-bit modulus (little-endian) with public exponent 65537, and an
+// RSASSA-PSS SHA-256 signature over PSS_TEST_MSG made with another library
+static PSS_TEST_MODULUS: &'static str = "8b61c4810e78e8e758ae9b73ae1d6aed7a146d2e88e584a411d144a291c9b043\
+     b253044256ead51a8c67eb3426ac7b3a09c849bb7012ea892b59a64bbe51a3bc\
+     fc566812594e6aa7e70599e2fbb879c2b6cf14dc613e49c0a021c80177336027\
+     3c96a640acc4966294a454778337ab986447adaf197983fe568e38c62a46bb8e\
+     113f225ff4d02c79641999feb8ed903d804419a4076102ac75aeb32c3f5e15c8\
+     d86dc636e31c9fea1bbe90f4e523341ab0ba601400e4d79ad5b8873c4e62df00\
+     7c8f1345ce2ba6a6f4eb8baf929ddf318ddd93d93ecaa94f980c0b80c3303e73\
+     3058afecad89b96c44c9053d9c680e4419e28bfe464378fdc4f4b0e98b3886c1";
+static PSS_TEST_MSG: &'static [u8] = b"RSASSA-PSS with SHA-256";
+static PSS_TEST_SIGNATURE: &'static str = "756cc43e6502b45fc7a9837cd81a7124ac3e862d66f61124e89fa8409cbcaee5\
+     f8a958b7cd1a1915a1977fdf150eb563b534e32867115d7666e94305c54800e6\
+     f2358e44a430c4b548e1c4d0dfaa0e3f849f4ec5a4da88c4c747cce649bc8e68\
+     b99f0adb78992a9fbfe91027d8e6c79ed7cc3d5dd087aac62da0b482dce82de2\
+     8a79dd2e3947bef79ec17fd164106a7e3bcaaf312ba12a9cf648cb457868908c\
+     5417c30953140e8b3c98a3f8fc07a9ecea1156b1ed58175a29c6c64da6d86a0c\
+     f63c4ca3a76b1fae74839f1ae50133d62ae4dd2cc32f8d3c86484d12df31cb51\
+     871a6f65d9bac9dba5e7c6d95a7b171a6188c486bdd01b0f7c4ae42ff7e4dfa6";
+
+pub fn test_rsa_pss() {
+    use sgx_tcrypto::rsa::*;
+    use std::convert::TryFrom;
+
+    let public = Rsa2048PssPublicKey::new(&hex_to_bytes(PSS_TEST_MODULUS), &[0x01, 0x00, 0x01]).unwrap();
+    let mut bytes = [0_u8; RSA2048_KEY_SIZE];
+    bytes.copy_from_slice(&hex_to_bytes(PSS_TEST_SIGNATURE));
+    let sig = RsaPssSignature::from_bytes(bytes);
+    RsaPssSignature::verify(&public, PSS_TEST_MSG, &sig).unwrap();
+    assert_eq!(
+        RsaPssSignature::verify(&public, b"RSASSA-PSS with SHA-384", &sig),
+        Err(sgx_status_t::SGX_ERROR_INVALID_SIGNATURE)
+    );
+    bytes[100] ^= 0x01;
+    assert_eq!(
+        RsaPssSignature::verify(&public, PSS_TEST_MSG, &RsaPssSignature::from_bytes(bytes)),
+        Err(sgx_status_t::SGX_ERROR_INVALID_SIGNATURE)
+    );
+
+    let mut n = vec![0_u8; SGX_RSA3072_KEY_SIZE];
+    let mut d = vec![0_u8; SGX_RSA3072_PRI_EXP_SIZE];
+    let mut e = vec![1_u8, 0, 1, 0];
+    let mut p = vec![0_u8; SGX_RSA3072_KEY_SIZE / 2];
+    let mut q = vec![0_u8; SGX_RSA3072_KEY_SIZE / 2];
+    let mut dmp1 = vec![0_u8; SGX_RSA3072_KEY_SIZE / 2];
+    let mut dmq1 = vec![0_u8; SGX_RSA3072_KEY_SIZE / 2];
+    let mut iqmp = vec![0_u8; SGX_RSA3072_KEY_SIZE / 2];
+    rsgx_create_rsa_key_pair(
+        SGX_RSA3072_KEY_SIZE as i32,
+        SGX_RSA3072_PUB_EXP_SIZE as i32,
+        &mut n,
+        &mut d,
+        &mut e,
+        &mut p,
+        &mut q,
+        &mut dmp1,
+        &mut dmq1,
+        &mut iqmp,
+    )
+    .unwrap();
+
+    let mut key = sgx_rsa3072_key_t::default();
+    key.modulus.copy_from_slice(&n);
+    key.d.copy_from_slice(&d);
+    key.e.copy_from_slice(&e);
+    let key = Rsa3072PssKey::try_from(&key).unwrap();
+    let public = key.public_key();
+
+    let msg = b"message signed inside the enclave";
+    let a = key.sign(msg).unwrap();
+    let b = key.sign(msg).unwrap();
+    // the salt is random
+    assert_ne!(a, b);
+    RsaPssSignature::verify(&public, msg, &a).unwrap();
+    RsaPssSignature::verify(&public, msg, &b).unwrap();
+    assert_eq!(
+        RsaPssSignature::verify(&public, &msg[1..], &a),
+        Err(sgx_status_t::SGX_ERROR_INVALID_SIGNATURE)
+    );
+
+    // a signature must be smaller than the modulus
+    let too_big = RsaPssSignature::from_bytes([0xff_u8; SGX_RSA3072_KEY_SIZE]);
+    assert_eq!(
+        RsaPssSignature::verify(&public, msg, &too_big),
+        Err(sgx_status_t::SGX_ERROR_INVALID_SIGNATURE)
+    );
+
+    // an even modulus is rejected
+    n[0] &= 0xfe;
+    assert_eq!(Rsa3072PssKey::new(&n, &d, &e).err(), Some(sgx_status_t::SGX_ERROR_INVALID_PARAMETER));
 }
 
-pub fn test_rsgx_sha256_handle() {
-    let test_size = HASH_TEST_VEC.len();
-    for i in 0..test_size {
-        let input_str = String::from(HASH_TEST_VEC[i]);
-        let shah = SgxShaHandle::new();
-        shah.init().unwrap();
-        shah.update_slice(input_str.as_bytes()).unwrap();
-        let hash = shah.get_hash().unwrap();
-        shah.close().unwrap();
-        assert_eq!(hex_to_bytes(HASH_SHA256_TRUTH[i]), hash);
+pub fn test_ecdsa_batch_verify() {
+    let ecc = SgxEccHandle::new();
+    ecc.open().unwrap();
+
+    let keys: Vec<_> = (0..10).map(|_| ecc.create_key_pair().unwrap()).collect();
+    let msgs: Vec<Vec<u8>> = (0..1000_u32)
+        .map(|i| i.to_le_bytes().iter().cycle().take(i as usize % 64 + 1).cloned().collect())
+        .collect();
+    let mut batch: Vec<(sgx_ec256_public_t, &[u8], sgx_ec256_signature_t)> = msgs
+        .iter()
+        .enumerate()
+        .map(|(i, msg)| {
+            let (private, public) = &keys[i % keys.len()];
+            let signature = ecc.ecdsa_sign_slice(msg.as_slice(), private).unwrap();
+            (*public, msg.as_slice(), signature)
+        })
+        .collect();
+
+    let per_item = |batch: &[(sgx_ec256_public_t, &[u8], sgx_ec256_signature_t)]| {
+        batch
+            .iter()
+            .all(|(public, msg, signature)| ecc.ecdsa_verify_slice(msg, public, signature).unwrap())
+    };
+
+    assert!(ecc.use_naive_batch());
+    assert!(per_item(&batch));
+    assert_eq!(ecc.ecdsa_batch_verify_slice(&batch).unwrap(), true);
+    assert_eq!(ecc.ecdsa_batch_verify_slice(&[]).unwrap(), true);
+
+    // a signature made with the wrong key
+    batch[500].0 = keys[0].1;
+    batch[500].2 = ecc.ecdsa_sign_slice(batch[500].1, &keys[1].0).unwrap();
+    assert!(!per_item(&batch));
+    assert_eq!(ecc.ecdsa_batch_verify_slice(&batch).unwrap(), false);
+
+    // both paths agree, on the valid prefix and wherever the invalid
+    // signature sits
+    let both = |batch: &[(sgx_ec256_public_t, &[u8], sgx_ec256_signature_t)]| {
+        ecc.set_use_naive_batch(true);
+        let naive = ecc.ecdsa_batch_verify_slice(batch).unwrap();
+        ecc.set_use_naive_batch(false);
+        assert!(!ecc.use_naive_batch());
+        let batched = ecc.ecdsa_batch_verify_slice(batch).unwrap();
+        assert_eq!(naive, batched);
+        assert_eq!(naive, per_item(batch));
+        naive
+    };
+    assert!(!both(&batch));
+    assert!(both(&batch[..500]));
+    assert!(both(&batch[501..]));
+    assert!(both(&[]));
+    for i in [0, 1, 499, 501, 998, 999] {
+        assert!(!both(&batch[i.min(500)..i.max(500) + 1]));
+    }
+
+    ecc.close().unwrap();
+    assert_eq!(
+        ecc.ecdsa_batch_verify_slice(&batch[..1]).unwrap_err(),
+        sgx_status_t::SGX_ERROR_INVALID_STATE
+    );
+}
+
+pub fn test_ctr_drbg() {
+    use sgx_rand::Rng;
+    use sgx_tcrypto::prng::*;
+
+    // CAVP CTR_DRBG.rsp, [AES-256 no df], PredictionResistance = False,
+    // no reseed, COUNT = 0
+    let entropy = hex_to_bytes(
+        "df5d73faa468649edda33b5cca79b0b05600419ccb7a879ddfec9db32ee494e5531b51de16a30f769262474c73bec010",
+    );
+    let mut drbg = CtrDrbg::instantiate(&entropy).unwrap();
+    let mut out = [0_u8; 64];
+    drbg.generate(&mut out, &[]).unwrap();
+    drbg.generate(&mut out, &[]).unwrap();
+    assert_eq!(
+        hex_to_bytes(
+            "d1c07cd95af8a7f11012c84ce48bb8cb87189e99d40fccb1771c619bdf82ab22\
+             80b1dc2f2581f39164f7ac0c510494b3a43c41b7db17514c87b107ae793e01c5"
+        ),
+        out.to_vec()
+    );
+
+    // personalization string, reseed and additional input
+    let seed_material: Vec<u8> = (0..48_u8).zip(100..148_u8).map(|(e, p)| e ^ p).collect();
+    let mut drbg = CtrDrbg::instantiate(&seed_material).unwrap();
+    drbg.reseed(&(200..248_u8).collect::<Vec<u8>>(), b"reseed").unwrap();
+    drbg.generate(&mut out, b"first").unwrap();
+    drbg.generate(&mut out, b"second").unwrap();
+    assert_eq!(
+        hex_to_bytes(
+            "c3a32777e7331590b69954616b459fd9a1f5fa55926d039c508f2bf7593bd4fd\
+             cf4e7e751dd9d1d8c83259e54e6d88f0c0aa0377c0fba779dbb5d3408deaa90b"
+        ),
+        out.to_vec()
+    );
+
+    assert_eq!(
+        CtrDrbg::instantiate(&entropy[..47]).unwrap_err(),
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+    assert_eq!(
+        drbg.reseed(&entropy[..32], &[]),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+    assert_eq!(
+        drbg.reseed(&entropy, &[0_u8; 49]),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+    let mut big = vec![0_u8; CTR_DRBG_MAX_REQUEST_LEN + 1];
+    assert_eq!(drbg.generate(&mut big, &[]), Err(DrbgError::RequestTooLarge));
+    assert_eq!(
+        drbg.generate(&mut out, &[0_u8; 49]),
+        Err(DrbgError::AdditionalInputTooLong)
+    );
+    drbg.generate(&mut big[1..], &[0_u8; 48]).unwrap();
+
+    // Rng splits requests over the per-request limit
+    drbg.fill_bytes(&mut big);
+    assert!(big.iter().any(|b| *b != 0));
+    assert_ne!(drbg.next_u64(), drbg.next_u64());
+    drbg.uninstantiate();
+}
+
+pub fn test_x25519() {
+    use sgx_tcrypto::ecc::*;
+    use sgx_tcrypto::util::Zeroize;
+
+    fn key(hex: &str) -> [u8; X25519_KEY_SIZE] {
+        let mut key = [0_u8; X25519_KEY_SIZE];
+        key.copy_from_slice(&hex_to_bytes(hex));
+        key
+    }
+
+    // RFC 7748, section 5.2
+    let vectors = [
+        (
+            "a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4",
+            "e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c",
+            "c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552",
+        ),
+        (
+            "4b66e9d4d1b4673c5ad22691957d6af5c11b6421e0ea01d42ca4169e7918ba0d",
+            "e5210f12786811d3f4b7959d0538ae2c31dbe7106fc03c3efc4cd549c715a493",
+            "95cbde9476e8907d7aade45cb4b873f88b595a68799fa152e6f8f7647aac7957",
+        ),
+    ];
+    for &(scalar, u, output) in vectors.iter() {
+        let pair = X25519KeyPair::from_scalar(&key(scalar)).unwrap();
+        let shared = pair.diffie_hellman(&X25519PublicKey::from_bytes(key(u)));
+        assert_eq!(shared.as_bytes(), &key(output));
+    }
+
+    let mut k = [0_u8; X25519_KEY_SIZE];
+    k[0] = 9;
+    let mut u = k;
+    for i in 0..1000 {
+        let next = *X25519KeyPair::from_scalar(&k).unwrap().diffie_hellman(&u.into()).as_bytes();
+        u = k;
+        k = next;
+        if i == 0 {
+            assert_eq!(k, key("422c8e7a6227d7bca1350b3e2bb7279f7897b87bb6854b783c60e80311ae3079"));
+        }
+    }
+    assert_eq!(k, key("684cf59ba83309552800ef566f2f4d3c1c3887c49360e3875f2eb94d99532c51"));
+
+    // RFC 7748, section 6.1
+    let alice = X25519KeyPair::from_scalar(&key(
+        "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a",
+    ))
+    .unwrap();
+    let bob = X25519KeyPair::from_scalar(&key(
+        "5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb",
+    ))
+    .unwrap();
+    assert_eq!(
+        alice.public_key().as_bytes(),
+        &key("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a")
+    );
+    assert_eq!(
+        bob.public_key().as_bytes(),
+        &key("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f")
+    );
+    let shared = key("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742");
+    assert_eq!(alice.diffie_hellman(bob.public_key()).as_bytes(), &shared);
+    assert_eq!(bob.diffie_hellman(alice.public_key()).as_bytes(), &shared);
+
+    // the scalar comes from the caller's generator
+    let from_rng = X25519KeyPair::generate_with_rng(|scalar| {
+        scalar.copy_from_slice(&key(
+            "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a",
+        ));
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(from_rng.public_key(), alice.public_key());
+    let mut secret = from_rng.diffie_hellman(bob.public_key());
+    assert_eq!(secret.as_bytes(), &shared);
+    secret.zeroize();
+    assert_eq!(secret.as_bytes(), &[0_u8; X25519_KEY_SIZE]);
+    assert_eq!(
+        X25519KeyPair::generate_with_rng(|_| Err(sgx_status_t::SGX_ERROR_UNEXPECTED)).unwrap_err(),
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    );
+    assert_eq!(
+        X25519KeyPair::generate_with_rng(|_| Ok(())).unwrap_err(),
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+
+    let alice = X25519KeyPair::generate().unwrap();
+    let bob = X25519KeyPair::generate().unwrap();
+    assert_ne!(alice.public_key(), bob.public_key());
+    let shared = alice.diffie_hellman(bob.public_key());
+    assert!(shared.was_contributory());
+    assert_eq!(shared.as_bytes(), bob.diffie_hellman(alice.public_key()).as_bytes());
+
+    let zero = X25519PublicKey::from_bytes([0_u8; X25519_KEY_SIZE]);
+    assert!(!alice.diffie_hellman(&zero).was_contributory());
+    assert_eq!(
+        X25519KeyPair::from_scalar(&[0_u8; X25519_KEY_SIZE]).unwrap_err(),
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+}
+
+pub fn test_ed25519() {
+    use sgx_tcrypto::ecc::*;
+
+    // RFC 8032, section 7.1: TEST 1, 2, 3 and SHA(abc)
+    let vectors = [
+        (
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            "",
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+             5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+        ),
+        (
+            "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+            "72",
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+             085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+        ),
+        (
+            "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+            "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+            "af82",
+            "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac\
+             18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a",
+        ),
+        (
+            "833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42",
+            "ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf",
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+             2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+            "dc2a4459e7369633a52b1bf277839a00201009a3efbf3ecb69bea2186c26b589\
+             09351fc9ac90b3ecfdfbc7c66431e0303dca179c138ac17ad9bef1177331a704",
+        ),
+    ];
+    for &(seed, public, message, signature) in vectors.iter() {
+        let mut s = [0_u8; ED25519_KEY_SIZE];
+        s.copy_from_slice(&hex_to_bytes(seed));
+        let pair = Ed25519KeyPair::from_seed(&s).unwrap();
+        assert_eq!(pair.verify_key().as_bytes().to_vec(), hex_to_bytes(public));
+
+        let message = hex_to_bytes(message);
+        let sig = pair.sign(&message).unwrap();
+        assert_eq!(sig.as_bytes().to_vec(), hex_to_bytes(signature));
+
+        let mut p = [0_u8; ED25519_KEY_SIZE];
+        p.copy_from_slice(&hex_to_bytes(public));
+        let verify_key = Ed25519VerifyKey::from_bytes(&p).unwrap();
+        assert!(verify_key.verify(&message, &sig).unwrap());
+
+        let mut other = message.clone();
+        other.push(0);
+        assert!(!verify_key.verify(&other, &sig).unwrap());
+        let mut bad = *sig.as_bytes();
+        bad[0] ^= 1;
+        assert!(!verify_key.verify(&message, &bad.into()).unwrap());
+        let mut bad = *sig.as_bytes();
+        bad[63] ^= 0x80;
+        assert!(!verify_key.verify(&message, &bad.into()).unwrap());
+    }
+
+    let pair = Ed25519KeyPair::generate().unwrap();
+    let sig = pair.sign(b"message").unwrap();
+    assert_eq!(sig, pair.sign(b"message").unwrap());
+    assert!(pair.verify_key().verify(b"message", &sig).unwrap());
+    assert_ne!(pair.verify_key(), Ed25519KeyPair::generate().unwrap().verify_key());
+
+    // S + L encodes the same scalar as S, but is not canonical
+    let l = hex_to_bytes("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010");
+    let mut malleated = *sig.as_bytes();
+    let mut carry = 0_u16;
+    for i in 0..32 {
+        let v = u16::from(malleated[32 + i]) + u16::from(l[i]) + carry;
+        malleated[32 + i] = v as u8;
+        carry = v >> 8;
+    }
+    assert!(!pair.verify_key().verify(b"message", &malleated.into()).unwrap());
+
+    // y = p is not canonical, and y = 2 is not on the curve
+    let mut p = [0xff_u8; ED25519_KEY_SIZE];
+    p[0] = 0xed;
+    p[31] = 0x7f;
+    assert_eq!(
+        Ed25519VerifyKey::from_bytes(&p).unwrap_err(),
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+    let mut two = [0_u8; ED25519_KEY_SIZE];
+    two[0] = 2;
+    assert!(Ed25519VerifyKey::from_bytes(&two).is_err());
+}
+
+pub fn test_ec_key_pkcs8() {
+    use sgx_tcrypto::ecc::*;
+
+    // RFC 6979, appendix A.2.5, encoded by OpenSSL
+    let der = hex_to_bytes(
+        "308187020100301306072a8648ce3d020106082a8648ce3d030107046d306b0201010420c9afa9d8\
+         45ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721a1440342000460fed4ba255a\
+         9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb67903fe1008b8bc99a41ae9e95628\
+         bc64f2f1b20c2d7e9f5177a3c294d4462299",
+    );
+    let pair = EcKeyPair::from_pkcs8_der(&der).unwrap();
+    let mut gx = hex_to_bytes("60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6");
+    gx.reverse();
+    assert_eq!(&pair.public_key().gx[..], &gx[..]);
+    assert_eq!(&pair.to_pkcs8_der()[..], &der[..]);
+
+    let mut corrupted = der.clone();
+    let len = corrupted.len();
+    corrupted[len - 1] ^= 1;
+    assert_eq!(EcKeyPair::from_pkcs8_der(&corrupted).unwrap_err(), Pkcs8Error::InvalidKey);
+    assert_eq!(EcKeyPair::from_pkcs8_der(&der[..len - 1]).unwrap_err(), Pkcs8Error::InvalidEncoding);
+
+    // persist a fresh key the way an enclave would
+    let pair = EcKeyPair::generate().unwrap();
+    let exported = pair.to_pkcs8_der();
+    let aad: [u8; 0] = [];
+    let sealed = SgxSealedData::<[u8]>::seal_data(&aad, &exported).unwrap();
+    let unsealed = sealed.unseal_data().unwrap();
+    let restored = EcKeyPair::from_pkcs8_der(unsealed.get_decrypt_txt()).unwrap();
+    assert_eq!(restored.private_key().r, pair.private_key().r);
+
+    let msg = b"persisted signing key";
+    let signature = restored.sign_slice(&msg[..]).unwrap();
+    let ecc_handle = SgxEccHandle::new();
+    ecc_handle.open().unwrap();
+    assert!(ecc_handle.ecdsa_verify_slice(&msg[..], pair.public_key(), &signature).unwrap());
+}
+
+pub fn test_shake_xof() {
+    use sgx_tcrypto::sha::ShakeXof;
+
+    // FIPS 202 example values: the empty message, and 200 bytes of 0xa3
+    let mut xof = ShakeXof::new_shake128();
+    let mut out = [0_u8; 32];
+    xof.squeeze(&mut out).unwrap();
+    assert_eq!(
+        hex_to_bytes("7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26"),
+        out.to_vec()
+    );
+
+    let mut xof = ShakeXof::new_shake256();
+    let mut out = [0_u8; 64];
+    xof.squeeze(&mut out).unwrap();
+    assert_eq!(
+        hex_to_bytes(
+            "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f\
+             d75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be"
+        ),
+        out.to_vec()
+    );
+
+    let msg = [0xa3_u8; 200];
+    let mut xof = ShakeXof::new_shake128();
+    xof.update(&msg).unwrap();
+    let mut out = [0_u8; 32];
+    xof.squeeze(&mut out).unwrap();
+    assert_eq!(
+        hex_to_bytes("131ab8d2b594946b9c81333f9bb6e0ce75c3b93104fa3469d3917457385da037"),
+        out.to_vec()
+    );
+
+    // the same input absorbed in pieces, and the output squeezed in pieces
+    // that cross the 136-byte rate boundary, continue one stream
+    let mut whole = ShakeXof::new_shake256();
+    whole.update(&msg).unwrap();
+    let mut expected = [0_u8; 300];
+    whole.squeeze(&mut expected).unwrap();
+    assert_eq!(
+        hex_to_bytes("cd8a920ed141aa0407a22d59288652e9d9f1a7ee0c1e7c1ca699424da84a904d"),
+        expected[..32].to_vec()
+    );
+
+    let mut split = ShakeXof::new_shake256();
+    split.update(&msg[..1]).unwrap();
+    split.update(&msg[1..150]).unwrap();
+    split.update(&msg[150..]).unwrap();
+    let mut out = [0_u8; 300];
+    split.squeeze(&mut out[..16]).unwrap();
+    split.squeeze(&mut out[16..200]).unwrap();
+    split.squeeze(&mut out[200..]).unwrap();
+    assert_eq!(&expected[..], &out[..]);
+
+    assert_eq!(split.update(b"more"), Err(sgx_status_t::SGX_ERROR_INVALID_STATE));
+}
+
+// (message, SHA3-256, SHA3-512, SHAKE128 32 bytes, SHAKE256 64 bytes), the
+// FIPS 202 example values for the empty message, "abc" and 200 bytes of 0xa3
+static SHA3_TEST_VEC: &'static [(&'static [u8], &'static str, &'static str, &'static str, &'static str)] = &[
+    (
+        b"",
+        "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a",
+        "a69f73cca23a9ac5c8b567dc185a756e97c982164fe25859e0d1dcc1475c80a6\
+         15b2123af1f5f94c11e3e9402c3ac558f500199d95b6d3e301758586281dcd26",
+        "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26",
+        "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f\
+         d75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be",
+    ),
+    (
+        b"abc",
+        "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+        "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e\
+         10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0",
+        "5881092dd818bf5cf8a3ddb793fbcba74097d5c526a6d35f97b83351940f2cc8",
+        "483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739\
+         d5a15bef186a5386c75744c0527e1faa9f8726e462a12a4feb06bd8801e751e4",
+    ),
+    (
+        &[0xa3; 200],
+        "79f38adec5c20307a98ef76e8324afbfd46cfd81b22e3973c65fa1bd9de31787",
+        "e76dfad22084a8b1467fcf2ffa58361bec7628edf5f3fdc0e4805dc48caeeca8\
+         1b7c13c30adf52a3659584739a2df46be589c51ca1a4a8416df6545a1ce8ba00",
+        "131ab8d2b594946b9c81333f9bb6e0ce75c3b93104fa3469d3917457385da037",
+        "cd8a920ed141aa0407a22d59288652e9d9f1a7ee0c1e7c1ca699424da84a904d\
+         2d700caae7396ece96604440577da4f3aa22aeb8857f961c4cd8e06f0ae6610b",
+    ),
+];
+
+pub fn test_sha3() {
+    use sgx_tcrypto::hmac::{Digest, Hmac};
+    use sgx_tcrypto::sha3::*;
+
+    for &(msg, sha3_256, sha3_512, shake128, shake256) in SHA3_TEST_VEC.iter() {
+        assert_eq!(hex_to_bytes(sha3_256), Sha3_256::hash(msg).unwrap());
+        assert_eq!(hex_to_bytes(sha3_512), &Sha3_512::hash(msg).unwrap()[..]);
+
+        // the same input absorbed in pieces that cross the rate boundary
+        let mut hash = Sha3_256::new().unwrap();
+        for chunk in msg.chunks(7) {
+            hash.update(chunk).unwrap();
+        }
+        assert_eq!(hex_to_bytes(sha3_256), hash.finalize().unwrap());
+
+        let mut xof = Shake128::new();
+        xof.update(msg).unwrap();
+        let mut out = vec![0_u8; 32];
+        xof.finalize_xof(&mut out).unwrap();
+        assert_eq!(hex_to_bytes(shake128), out);
+
+        let mut xof = Shake256::new();
+        xof.update(msg).unwrap();
+        let mut out = vec![0_u8; 64];
+        xof.finalize_xof(&mut out).unwrap();
+        assert_eq!(hex_to_bytes(shake256), out);
+    }
+
+    // HMAC-SHA3-256 with a 32 byte key, from the NIST HMAC examples
+    let key: Vec<u8> = (0..32).collect();
+    let mac = Hmac::<Sha3_256>::mac(&key, b"Sample message for keylen<blocklen").unwrap();
+    assert_eq!(
+        hex_to_bytes("4fe8e202c4f058e8dddc23d8c34e467343e23555e24fc2f025d598f558f67205"),
+        mac.as_bytes()
+    );
+}
+
+pub fn test_blake2() {
+    use sgx_tcrypto::blake2::*;
+
+    // RFC 7693 appendices A and B
+    assert_eq!(
+        hex_to_bytes(
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+             7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        ),
+        Blake2b::digest(64, b"abc").unwrap().as_bytes()
+    );
+    assert_eq!(
+        hex_to_bytes("508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982"),
+        Blake2s::digest(32, b"abc").unwrap().as_bytes()
+    );
+    // a shorter output is not a truncation of the longer one
+    assert_eq!(
+        hex_to_bytes("bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319"),
+        Blake2b::digest(32, b"abc").unwrap().as_bytes()
+    );
+
+    // keyed known-answer values of the BLAKE2 reference implementation,
+    // with the key 00 01 02 ... and the message 00 01 02 ... fe
+    let key: Vec<u8> = (0..BLAKE2B_MAX_SIZE as u8).collect();
+    let msg: Vec<u8> = (0..255).collect();
+    let mut mac = Blake2b::new(Some(&key), BLAKE2B_MAX_SIZE).unwrap();
+    for chunk in msg.chunks(100) {
+        mac.update(chunk).unwrap();
+    }
+    let tag = mac.finalize().unwrap();
+    assert!(tag.verify(&hex_to_bytes(
+        "142709d62e28fcccd0af97fad0f8465b971e82201dc51070faa0372aa43e9248\
+         4be1c1e73ba10906d5d1853db6a4106e0a7bf9800d373d6dee2d46d62ef2a461"
+    )));
+    assert!(!tag.verify(&[0_u8; BLAKE2B_MAX_SIZE]));
+
+    let mut mac = Blake2s::new(Some(&key[..BLAKE2S_MAX_SIZE]), BLAKE2S_MAX_SIZE).unwrap();
+    mac.update(&msg).unwrap();
+    assert!(mac.finalize().unwrap().verify(&hex_to_bytes(
+        "3fb735061abc519dfe979e54c1ee5bfad0a9d858b3315bad34bde999efd724dd"
+    )));
+
+    // an empty message with a key is still one block
+    let mac = Blake2s::new(Some(&key[..BLAKE2S_MAX_SIZE]), BLAKE2S_MAX_SIZE).unwrap();
+    assert_eq!(
+        hex_to_bytes("48a8997da407876b3d79c0d92325ad3b89cbb754d86ab71aee047ad345fd2c49"),
+        mac.finalize().unwrap().as_bytes()
+    );
+
+    assert_eq!(
+        Blake2b::new(Some(&[0_u8; 65]), 32).err(),
+        Some(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+    assert_eq!(Blake2b::new(None, 0).err(), Some(sgx_status_t::SGX_ERROR_INVALID_PARAMETER));
+    assert_eq!(Blake2s::new(None, 33).err(), Some(sgx_status_t::SGX_ERROR_INVALID_PARAMETER));
+}
+
+pub fn test_blake3() {
+    use sgx_tcrypto::blake3::*;
+
+    assert_eq!(
+        hex_to_bytes("af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"),
+        Blake3Hasher::hash(&[])
+    );
+    assert_eq!(
+        hex_to_bytes("6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"),
+        Blake3Hasher::hash(b"abc")
+    );
+
+    // inputs of the official test vectors, the bytes 0, 1, ..., 250, 0, 1, ...
+    let input = |len: usize| -> Vec<u8> { (0..len).map(|i| (i % 251) as u8).collect() };
+    for (len, hash) in [
+        (1, "2d3adedff11b61f14c886e35afa036736dcd87a74d27b5c1510225d0f592e213"),
+        (1023, "10108970eeda3eb932baac1428c7a2163b0e924c9a9e25b35bba72b28f70bd11"),
+        (1024, "42214739f095a406f3fc83deb889744ac00df831c10daa55189b5d121c855af7"),
+        (1025, "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444"),
+        (8193, "bab6c09cb8ce8cf459261398d2e7aef35700bf488116ceb94a36d0f5f1b7bc3b"),
+        (102400, "bc3e3d41a1146b069abffad3c0d44860cf664390afce4d9661f7902e7943e085"),
+    ] {
+        let data = input(len);
+        assert_eq!(hex_to_bytes(hash), Blake3Hasher::hash(&data));
+        // the same in uneven pieces
+        let mut hasher = Blake3Hasher::new();
+        for piece in data.chunks(97) {
+            hasher.update(piece);
+        }
+        assert_eq!(hex_to_bytes(hash), hasher.finalize());
+    }
+
+    // extendable output, read in uneven pieces
+    let mut hasher = Blake3Hasher::new();
+    hasher.update(&input(2049));
+    let mut reader = hasher.finalize_xof();
+    let mut out = [0_u8; 131];
+    reader.fill(&mut out[..7]);
+    reader.fill(&mut out[7..70]);
+    reader.fill(&mut out[70..]);
+    assert_eq!(
+        hex_to_bytes(
+            "5f4d72f40d7a5f82b15ca2b2e44b1de3c2ef86c426c95c1af0b687952256303096de31d71d74103403822a2e0bc1eb193e\
+             7aecc9643a76b7bbc0c9f9c52e8783aae98764ca468962b5c2ec92f0c74eb5448d519713e09413719431c802f948dd5d90\
+             425a4ecdadece9eb178d80f26efccae630734dff63340285adec2aed3b51073ad3"
+        ),
+        out.to_vec()
+    );
+    assert_eq!(out[..BLAKE3_OUT_LEN], hasher.finalize());
+
+    // finalize does not consume the state, and reset keeps the mode
+    hasher.update(b"more");
+    assert_ne!(out[..BLAKE3_OUT_LEN], hasher.finalize());
+    hasher.reset();
+    hasher.update(&input(2049));
+    assert_eq!(out[..BLAKE3_OUT_LEN], hasher.finalize());
+
+    let key = *b"whats the Elephant juicy key, ok";
+    let context = "BLAKE3 2019-12-27 16:29:52 test vectors context";
+    assert_eq!(
+        hex_to_bytes("fcdf1a4d28247e25524c42d519535359c5ef6a8e1b091e04814e291c61835e76"),
+        Blake3Hasher::keyed_hash(&key, &[])
+    );
+    let mut mac = Blake3Hasher::new_keyed(&key);
+    mac.update(&input(1025));
+    assert_eq!(
+        hex_to_bytes("243a186081cea49cfe47f064852d4c353c245900bd2e656ecd30987368058922"),
+        mac.finalize()
+    );
+    assert_eq!(
+        hex_to_bytes("2cc39783c223154fea8dfb7c1b1660f2ac2dcbd1c1de8277b0b0dd39b7e50d7d"),
+        Blake3Hasher::derive_key(context, &[])
+    );
+    assert_eq!(
+        hex_to_bytes("effaa245f065fbf82ac186839a249707c3bddf6d3fdda22d1b95a3c970379bcb"),
+        Blake3Hasher::derive_key(context, &input(1025))
+    );
+}
+
+// (key, nonce, plaintext, aad, ciphertext || tag), from RFC 8452 appendix C.1
+static AES_GCM_SIV_TEST_VEC: &'static [(&'static str, &'static str, &'static str, &'static str, &'static str)] = &[
+    (
+        "01000000000000000000000000000000",
+        "030000000000000000000000",
+        "",
+        "",
+        "dc20e2d83f25705bb49e439eca56de25",
+    ),
+    (
+        "01000000000000000000000000000000",
+        "030000000000000000000000",
+        "0100000000000000",
+        "",
+        "b5d839330ac7b786578782fff6013b815b287c22493a364c",
+    ),
+    (
+        "01000000000000000000000000000000",
+        "030000000000000000000000",
+        "010000000000000000000000",
+        "01",
+        "b9d653159914e8698e508b2f87767f7519314812740d55942ac0fb83",
+    ),
+    (
+        "01000000000000000000000000000000",
+        "030000000000000000000000",
+        "020000000000000000000000000000000300000000000000000000000000000004000000000000000000000000000000",
+        "01",
+        "50c8303ea93925d64090d07bd109dfd9515a5a33431019c17d93465999a8b005\
+         3201d723120a8562b838cdff25bf9d1e6a8cc3865f76897c2e4b245cf31c51f2",
+    ),
+];
+
+pub fn test_argon2() {
+    use sgx_tcrypto::kdf::*;
+
+    // RFC 9106 section 5.3, with a secret and associated data
+    let params = Argon2Params {
+        memory_kib: 32,
+        iterations: 3,
+        parallelism: 4,
+    };
+    let hash = Argon2::hash_password_keyed(&[1_u8; 32], &[2_u8; 16], &[3_u8; 8], &[4_u8; 12], params).unwrap();
+    assert_eq!(
+        hex_to_bytes("0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659"),
+        hash.as_bytes()
+    );
+    assert!(hash.verify_keyed(&[1_u8; 32], &[3_u8; 8], &[4_u8; 12]));
+    assert!(!hash.verify_keyed(&[1_u8; 32], &[3_u8; 8], &[]));
+    assert!(!hash.verify(&[1_u8; 32]));
+
+    // PHC strings of the Argon2 reference implementation
+    for phc in [
+        "$argon2id$v=19$m=256,t=2,p=1$c29tZXNhbHQ$nf65EOgLrQMR/uIPnA4rEsF5h7TKyQwu9U1bMCHGi/4",
+        "$argon2id$v=19$m=256,t=2,p=2$c29tZXNhbHQ$bQk8UB/VmZZF4Oo79iDXuL5/0ttZwg2f/5U52iv1cDc",
+        "$argon2id$v=19$m=65536,t=2,p=1$c29tZXNhbHQ$CTFhFdXPJO1aFaMaO6Mm5c8y7cJHAph8ArZWb2GRPPc",
+    ] {
+        let hash = Argon2Hash::from_phc_str(phc).unwrap();
+        assert_eq!(hash.salt(), b"somesalt");
+        assert!(hash.verify(b"password"));
+        assert!(!hash.verify(b"Password"));
+        assert_eq!(hash.to_phc_string(), phc);
+    }
+    let params = Argon2Params {
+        memory_kib: 256,
+        iterations: 2,
+        parallelism: 2,
+    };
+    let hash = Argon2::hash_password(b"password", b"somesalt", params).unwrap();
+    assert_eq!(
+        hex_to_bytes("6d093c501fd5999645e0ea3bf620d7b8be7fd2db59c20d9fff9539da2bf57037"),
+        hash.as_bytes()
+    );
+    assert_eq!(hash.params(), params);
+
+    // the salt is too short, a parameter is out of range, or the memory
+    // exceeds half of the heap
+    assert!(Argon2::hash_password(b"password", b"salt", params).is_err());
+    for params in [
+        Argon2Params { memory_kib: 15, ..params },
+        Argon2Params { iterations: 0, ..params },
+        Argon2Params { parallelism: 0, ..params },
+        Argon2Params { memory_kib: u32::MAX, ..params },
+    ] {
+        assert_eq!(
+            Argon2::hash_password(b"password", b"somesalt", params).unwrap_err(),
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+        );
+    }
+    for phc in [
+        "$argon2i$v=19$m=256,t=2,p=1$c29tZXNhbHQ$nf65EOgLrQMR/uIPnA4rEsF5h7TKyQwu9U1bMCHGi/4",
+        "$argon2id$v=16$m=256,t=2,p=1$c29tZXNhbHQ$nf65EOgLrQMR/uIPnA4rEsF5h7TKyQwu9U1bMCHGi/4",
+        "$argon2id$v=19$m=256,t=2$c29tZXNhbHQ$nf65EOgLrQMR/uIPnA4rEsF5h7TKyQwu9U1bMCHGi/4",
+        "$argon2id$v=19$m=0256,t=2,p=1$c29tZXNhbHQ$nf65EOgLrQMR/uIPnA4rEsF5h7TKyQwu9U1bMCHGi/4",
+        "$argon2id$v=19$m=256,t=2,p=1$c29tZXNhbHQ=$nf65EOgLrQMR/uIPnA4rEsF5h7TKyQwu9U1bMCHGi/4",
+        "$argon2id$v=19$m=256,t=2,p=1$c29tZXNhbHQ$nf65EOgLrQMR/uIPnA4rEsF5h7TKyQwu9U1bMCHGi/5",
+        "$argon2id$v=19$m=256,t=2,p=1$c29tZXNhbHQ$nf65EOgLrQMR/uIPnA4rEsF5h7TKyQwu9U1bMCHG",
+    ] {
+        assert!(Argon2Hash::from_phc_str(phc).is_err());
+    }
+}
+
+pub fn test_ecies() {
+    use sgx_tcrypto::ecc::EcKeyPair;
+    use sgx_tcrypto::ecies::*;
+
+    let recipient = EcKeyPair::generate().unwrap();
+    let plaintext = b"attested secret for the peer enclave";
+    let aad = b"session 1";
+
+    let msg = Ecies::encrypt(recipient.public_key(), plaintext, aad).unwrap();
+    assert_eq!(msg.ciphertext().len(), plaintext.len());
+    assert_ne!(msg.ciphertext(), &plaintext[..]);
+    assert_eq!(Ecies::decrypt(&recipient, &msg, aad).unwrap(), plaintext.to_vec());
+
+    // every message uses a new ephemeral key and IV
+    let other = Ecies::encrypt(recipient.public_key(), plaintext, aad).unwrap();
+    assert_ne!(other.ephemeral_public().gx, msg.ephemeral_public().gx);
+    assert_ne!(other.ciphertext(), msg.ciphertext());
+
+    let empty = Ecies::encrypt(recipient.public_key(), &[], &[]).unwrap();
+    assert!(Ecies::decrypt(&recipient, &empty, &[]).unwrap().is_empty());
+
+    // a message built outside the enclave with a fixed ephemeral key pins
+    // the key derivation
+    let mut private = sgx_ec256_private_t::default();
+    private.r.copy_from_slice(&hex_to_bytes(
+        "201f1e1d1c1b1a191817161514131211100f0e0d0c0b0a090807060504030201",
+    ));
+    let fixed = EcKeyPair::from_private_key(&private).unwrap();
+    let known = EciesCiphertext::from_slice(&hex_to_bytes(
+        "9ebb12831f029457e320954a98fd7ad7cfcdd4e0db4d4ff851b2b1bf4601141f\
+         2b897ce496509c448a9f5ff91177fb635266a9500bcc3ddf0477fab1085a99ec\
+         000102030405060708090a0b70b81092f6e2edd743a56e294272d3f3ed003c44\
+         c22628b3823fb8da26efbe338de541ede21c367d1bc3bbba4e7207091fbb8fb7",
+    ))
+    .unwrap();
+    assert_eq!(Ecies::decrypt(&fixed, &known, aad).unwrap(), plaintext.to_vec());
+
+    // serialization round trip
+    let bytes = msg.to_vec();
+    assert_eq!(bytes.len(), ECIES_OVERHEAD + plaintext.len());
+    let parsed = EciesCiphertext::from_slice(&bytes).unwrap();
+    assert_eq!(Ecies::decrypt(&recipient, &parsed, aad).unwrap(), plaintext.to_vec());
+    assert_eq!(
+        EciesCiphertext::from_slice(&bytes[..ECIES_OVERHEAD - 1]).unwrap_err(),
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+
+    // wrong aad, wrong recipient and modified fields are rejected
+    assert_eq!(
+        Ecies::decrypt(&recipient, &msg, b"session 2").unwrap_err(),
+        sgx_status_t::SGX_ERROR_MAC_MISMATCH
+    );
+    let stranger = EcKeyPair::generate().unwrap();
+    assert_eq!(
+        Ecies::decrypt(&stranger, &msg, aad).unwrap_err(),
+        sgx_status_t::SGX_ERROR_MAC_MISMATCH
+    );
+    for offset in [2 * 32, 2 * 32 + 12, bytes.len() - 1] {
+        let mut tampered = bytes.clone();
+        tampered[offset] ^= 1;
+        let tampered = EciesCiphertext::from_slice(&tampered).unwrap();
+        assert_eq!(
+            Ecies::decrypt(&recipient, &tampered, aad).unwrap_err(),
+            sgx_status_t::SGX_ERROR_MAC_MISMATCH
+        );
+    }
+
+    // an ephemeral key that is not on the curve
+    let mut invalid = bytes;
+    invalid[0] ^= 1;
+    let invalid = EciesCiphertext::from_slice(&invalid).unwrap();
+    assert_eq!(
+        Ecies::decrypt(&recipient, &invalid, aad).unwrap_err(),
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+}
+
+pub fn test_threshold_ecdsa() {
+    use sgx_tcrypto::threshold::*;
+
+    // 2-of-3, any two parties sign
+    let (mut shares, public) = ThresholdEcdsa::keygen(3, 2, 4).unwrap();
+    for share in shares.iter() {
+        assert!(public.verify_share(share));
+    }
+    let message = b"threshold signed message";
+    let handle = SgxEccHandle::new();
+    handle.open().unwrap();
+    for (presignature, &(a, b)) in [(0, 1), (0, 2), (2, 1)].iter().enumerate() {
+        let pa = ThresholdEcdsa::partial_sign(&mut shares[a], presignature, message).unwrap();
+        let pb = ThresholdEcdsa::partial_sign(&mut shares[b], presignature, message).unwrap();
+        let signature = ThresholdEcdsa::combine(&public, &[pa, pb]).unwrap();
+        assert!(handle.ecdsa_verify_slice(message, public.public_key(), &signature).unwrap());
+        assert!(!handle.ecdsa_verify_slice(b"other message", public.public_key(), &signature).unwrap());
+
+        // too few partials, a repeated party, and a modified partial
+        assert_eq!(
+            ThresholdEcdsa::combine(&public, &[pa]).err(),
+            Some(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+        );
+        assert_eq!(
+            ThresholdEcdsa::combine(&public, &[pa, pa]).err(),
+            Some(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+        );
+        let mut bytes = pb.to_bytes();
+        bytes[THRESHOLD_PARTIAL_SIGNATURE_SIZE - 1] ^= 1;
+        let modified = PartialSignature::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            ThresholdEcdsa::combine(&public, &[pa, modified]).err(),
+            Some(sgx_status_t::SGX_ERROR_INVALID_SIGNATURE)
+        );
+    }
+
+    // a presignature signs only once
+    assert_eq!(
+        ThresholdEcdsa::partial_sign(&mut shares[0], 0, message).unwrap_err(),
+        sgx_status_t::SGX_ERROR_INVALID_STATE
+    );
+    assert_eq!(
+        ThresholdEcdsa::partial_sign(&mut shares[0], 4, message).unwrap_err(),
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+
+    // serialized shares keep track of the used presignatures
+    let bytes = shares[1].to_vec();
+    let mut restored = ThresholdShare::from_slice(&bytes).unwrap();
+    assert_eq!(restored.to_vec(), bytes);
+    assert!(restored.is_presignature_used(0));
+    assert!(restored.is_presignature_used(2));
+    assert!(!restored.is_presignature_used(3));
+    let restored_public = ThresholdPublicKey::from_slice(&public.to_vec()).unwrap();
+    assert!(restored_public.verify_share(&restored));
+
+    let p1 = ThresholdEcdsa::partial_sign(&mut restored, 3, message).unwrap();
+    let p0 = ThresholdEcdsa::partial_sign(&mut shares[0], 3, message).unwrap();
+    let p2 = ThresholdEcdsa::partial_sign(&mut shares[2], 3, b"other message").unwrap();
+    assert_eq!(
+        ThresholdEcdsa::combine(&restored_public, &[p1, p0, p2]).err(),
+        Some(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+    let signature = ThresholdEcdsa::combine(&restored_public, &[p1, p0]).unwrap();
+    assert!(handle.ecdsa_verify_slice(message, public.public_key(), &signature).unwrap());
+
+    // a share of another key
+    let (others, _) = ThresholdEcdsa::keygen(3, 2, 1).unwrap();
+    assert!(!public.verify_share(&others[0]));
+
+    // two disjoint sets of signers are impossible
+    assert!(ThresholdEcdsa::keygen(5, 3, 1).is_ok());
+    for (parties, threshold, presignatures) in
+        [(3, 1, 1), (2, 3, 1), (3, 2, 0), (4, 2, 1), (6, 3, 1), (256, 2, 1), (256, 200, 1)]
+    {
+        assert_eq!(
+            ThresholdEcdsa::keygen(parties, threshold, presignatures).err(),
+            Some(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+        );
+    }
+}
+
+pub fn test_aes_gcm_siv() {
+    use sgx_tcrypto::aes::*;
+
+    for &(key, nonce, plaintext, aad, sealed) in AES_GCM_SIV_TEST_VEC.iter() {
+        let mut k: sgx_key_128bit_t = [0; 16];
+        k.copy_from_slice(&hex_to_bytes(key));
+        let mut n = [0_u8; AES_GCM_SIV_NONCE_SIZE];
+        n.copy_from_slice(&hex_to_bytes(nonce));
+        let plaintext = hex_to_bytes(plaintext);
+        let aad = hex_to_bytes(aad);
+        let siv = AesGcmSiv128::new(&k);
+
+        let mut out = vec![0_u8; plaintext.len() + AES_GCM_SIV_TAG_SIZE];
+        assert_eq!(siv.seal(&n, &plaintext, &aad, &mut out).unwrap(), out.len());
+        assert_eq!(hex_to_bytes(sealed), out);
+
+        let mut opened = vec![0_u8; plaintext.len()];
+        assert_eq!(siv.open(&n, &out, &aad, &mut opened).unwrap(), plaintext.len());
+        assert_eq!(plaintext, opened);
+
+        // any change to the ciphertext, tag, aad or nonce is detected, and
+        // nothing is released
+        let last = out.len() - 1;
+        out[last] ^= 1;
+        assert_eq!(
+            siv.open(&n, &out, &aad, &mut opened),
+            Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH)
+        );
+        assert!(opened.iter().all(|&b| b == 0));
+        out[last] ^= 1;
+        assert!(siv.open(&n, &out, b"other", &mut opened).is_err());
+        n[0] ^= 1;
+        assert!(siv.open(&n, &out, &aad, &mut opened).is_err());
+    }
+
+    // a repeated nonce only reveals equal messages
+    let siv = AesGcmSiv128::new(&[7; 16]);
+    let nonce = [0_u8; AES_GCM_SIV_NONCE_SIZE];
+    let mut a = [0_u8; 23];
+    let mut b = [0_u8; 23];
+    siv.seal(&nonce, b"message", b"", &mut a[..]).unwrap();
+    siv.seal(&nonce, b"message", b"", &mut b[..]).unwrap();
+    assert_eq!(a, b);
+    siv.seal(&nonce, b"messagf", b"", &mut b[..]).unwrap();
+    assert!(a[..7] != b[..7] && a[7..] != b[7..]);
+
+    // buffers that are too short are rejected
+    let mut short = [0_u8; 22];
+    assert_eq!(
+        siv.seal(&nonce, b"message", b"", &mut short),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+    assert!(siv.open(&nonce, &[0_u8; 15], b"", &mut short).is_err());
+}
+
+pub fn test_aes_siv() {
+    use sgx_tcrypto::aes::*;
+
+    // RFC 5297 appendix A.1, deterministic authenticated encryption
+    let mut key = [0_u8; AES_SIV_KEY_SIZE];
+    key.copy_from_slice(&hex_to_bytes(
+        "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff",
+    ));
+    let siv = AesSiv::new(&key);
+    let aad = hex_to_bytes("101112131415161718191a1b1c1d1e1f2021222324252627");
+    let plaintext = hex_to_bytes("112233445566778899aabbccddee");
+    let mut sealed = vec![0_u8; plaintext.len() + AES_SIV_TAG_SIZE];
+    assert_eq!(siv.seal(&plaintext, &[&aad], &mut sealed).unwrap(), sealed.len());
+    assert_eq!(
+        hex_to_bytes("85632d07c6e8f37f950acd320a2ecc9340c02b9690c4dc04daef7f6afe5c"),
+        sealed
+    );
+    let mut opened = vec![0_u8; plaintext.len()];
+    assert_eq!(siv.open(&sealed, &[&aad], &mut opened).unwrap(), plaintext.len());
+    assert_eq!(plaintext, opened);
+
+    // RFC 5297 appendix A.2, nonce-based, with the nonce as the last string
+    key.copy_from_slice(&hex_to_bytes(
+        "7f7e7d7c7b7a79787776757473727170404142434445464748494a4b4c4d4e4f",
+    ));
+    let siv = AesSiv::new(&key);
+    let ad1 = hex_to_bytes(
+        "00112233445566778899aabbccddeeffdeaddadadeaddadaffeeddccbbaa99887766554433221100",
+    );
+    let ad2 = hex_to_bytes("102030405060708090a0");
+    let nonce = hex_to_bytes("09f911029d74e35bd84156c5635688c0");
+    let plaintext = hex_to_bytes(
+        "7468697320697320736f6d6520706c61696e7465787420746f20656e6372797074207573696e67205349562d414553",
+    );
+    let mut sealed = vec![0_u8; plaintext.len() + AES_SIV_TAG_SIZE];
+    siv.seal(&plaintext, &[&ad1, &ad2, &nonce], &mut sealed).unwrap();
+    assert_eq!(
+        hex_to_bytes(
+            "7bdb6e3b432667eb06f4d14bff2fbd0fcb900f2fddbe404326601965c889bf17\
+             dba77ceb094fa663b7a3f748ba8af829ea64ad544a272e9c485b62a3fd5c0d"
+        ),
+        sealed
+    );
+    let mut opened = vec![0_u8; plaintext.len()];
+    siv.open(&sealed, &[&ad1, &ad2, &nonce], &mut opened).unwrap();
+    assert_eq!(plaintext, opened);
+
+    // the strings are authenticated separately and in order
+    assert_eq!(
+        siv.open(&sealed, &[&ad2, &ad1, &nonce], &mut opened),
+        Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH)
+    );
+    assert!(opened.iter().all(|&b| b == 0));
+    assert!(siv.open(&sealed, &[&ad1, &ad2], &mut opened).is_err());
+    sealed[20] ^= 1;
+    assert!(siv.open(&sealed, &[&ad1, &ad2, &nonce], &mut opened).is_err());
+
+    // equal inputs give equal outputs, and an empty plaintext is allowed
+    let mut a = [0_u8; AES_SIV_TAG_SIZE];
+    let mut b = [0_u8; AES_SIV_TAG_SIZE];
+    siv.seal(b"", &[b"record 1"], &mut a).unwrap();
+    siv.seal(b"", &[b"record 1"], &mut b).unwrap();
+    assert_eq!(a, b);
+    assert_eq!(siv.open(&a, &[b"record 1"], &mut []).unwrap(), 0);
+    siv.seal(b"", &[b"record 2"], &mut b).unwrap();
+    assert_ne!(a, b);
+
+    assert_eq!(
+        siv.seal(b"x", &[], &mut a),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+}
+
+// (key, iv, plaintext, aad, ciphertext, tag), test cases 13-16 of the GCM
+// specification by McGrew and Viega, also used by NIST
+static AES_256_GCM_TEST_VEC: &'static [(&'static str, &'static str, &'static str, &'static str, &'static str, &'static str)] = &[
+    (
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "000000000000000000000000",
+        "",
+        "",
+        "",
+        "530f8afbc74536b9a963b4f1c4cb738b",
+    ),
+    (
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "000000000000000000000000",
+        "00000000000000000000000000000000",
+        "",
+        "cea7403d4d606b6e074ec5d3baf39d18",
+        "d0d1c8a799996bf0265b98b5d48ab919",
+    ),
+    (
+        "feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308",
+        "cafebabefacedbaddecaf888",
+        "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+         1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b391aafd255",
+        "",
+        "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa\
+         8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662898015ad",
+        "b094dac5d93471bdec1a502270e3cc6c",
+    ),
+    (
+        "feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308",
+        "cafebabefacedbaddecaf888",
+        "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+         1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+        "feedfacedeadbeeffeedfacedeadbeefabaddad2",
+        "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa\
+         8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662",
+        "76fc6ece0f4e1768cddf8853bb2d551b",
+    ),
+];
+
+pub fn test_aes_256_gcm() {
+    use sgx_tcrypto::aes::*;
+
+    for &(key, iv, plaintext, aad, ciphertext, tag) in AES_256_GCM_TEST_VEC.iter() {
+        let mut k: sgx_aes_gcm_256bit_key_t = [0; 32];
+        k.copy_from_slice(&hex_to_bytes(key));
+        let iv = hex_to_bytes(iv);
+        let plaintext = hex_to_bytes(plaintext);
+        let aad = hex_to_bytes(aad);
+        let gcm = Aes256Gcm::new(&k);
+
+        let mut out = vec![0_u8; plaintext.len()];
+        let mut mac = [0_u8; AES_256_GCM_TAG_SIZE];
+        gcm.encrypt(&plaintext, &iv, &aad, &mut out, &mut mac).unwrap();
+        assert_eq!(hex_to_bytes(ciphertext), out);
+        assert_eq!(hex_to_bytes(tag), mac);
+
+        let mut opened = vec![0_u8; plaintext.len()];
+        gcm.decrypt(&out, &iv, &aad, &mac, &mut opened).unwrap();
+        assert_eq!(plaintext, opened);
+
+        // the same message passed in uneven pieces
+        let mut encryptor = gcm.encryptor(&iv, &aad).unwrap();
+        let mut streamed = vec![0_u8; plaintext.len()];
+        for (src, dst) in plaintext.chunks(7).zip(streamed.chunks_mut(7)) {
+            encryptor.update(src, dst).unwrap();
+        }
+        assert_eq!(out, streamed);
+        assert_eq!(encryptor.get_mac(), mac);
+
+        let mut decryptor = gcm.decryptor(&iv, &aad).unwrap();
+        for (src, dst) in out.chunks(23).zip(opened.chunks_mut(23)) {
+            decryptor.update(src, dst).unwrap();
+        }
+        decryptor.verify(&mac).unwrap();
+        assert_eq!(plaintext, opened);
+
+        // a changed tag, ciphertext or aad is detected before decrypting
+        let mut bad_mac = mac;
+        bad_mac[0] ^= 1;
+        let mut untouched = vec![0_u8; plaintext.len()];
+        assert_eq!(
+            gcm.decrypt(&out, &iv, &aad, &bad_mac, &mut untouched),
+            Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH)
+        );
+        assert!(untouched.iter().all(|&b| b == 0));
+        assert!(gcm.decrypt(&out, &iv, b"other", &mac, &mut untouched).is_err());
+        let decryptor = gcm.decryptor(&iv, &aad).unwrap();
+        assert_eq!(decryptor.verify(&bad_mac), Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+    }
+
+    let gcm = Aes256Gcm::new(&[7; 32]);
+    let mut mac = [0_u8; AES_256_GCM_TAG_SIZE];
+    assert_eq!(
+        gcm.encrypt(b"message", &[0; 16], b"", &mut [0; 7], &mut mac),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+    assert_eq!(
+        gcm.encrypt(b"message", &[0; AES_256_GCM_IV_SIZE], b"", &mut [0; 6], &mut mac),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+}
+
+// openssl req -x509 -newkey rsa:2048 -subj "/CN=RSA Test Root/O=Teaclave" -set_serial 0x1001
+static X509_RSA_ROOT: &'static str = "
+-----BEGIN CERTIFICATE-----
+MIIDJTCCAg2gAwIBAgICEAEwDQYJKoZIhvcNAQELBQAwKzEWMBQGA1UEAwwNUlNB
+IFRlc3QgUm9vdDERMA8GA1UECgwIVGVhY2xhdmUwHhcNMjYxMDE1MTgwMjE5WhcN
+MzYxMDEyMTgwMjE5WjArMRYwFAYDVQQDDA1SU0EgVGVzdCBSb290MREwDwYDVQQK
+DAhUZWFjbGF2ZTCCASIwDQYJKoZIhvcNAQEBBQADggEPADCCAQoCggEBAK8xZpEN
+93sWlj7NwZvh/3BKc0eyS6UQAU7w/1N9B7BxAWgd8lQRIMeki5UDrw5R6ITXSGeL
+DGL8tC+xX4zDRgtIsGNsvf6FwJu6xBWN+E0mjBuuET4zJ2BYMFuRfaKXJBnQStio
+irUxN+rthJQz11RqOvHvudZeye8A6vMbGhzxiyAyXnhR63YI3pz9fjZJkiWcwLNT
+XHupzMJB4gIm88Nd7ouOIbDc1wW2X9U+Hr3eTN8jcizheSgEu6L4etyj/XBjbBVF
+zfcjFYWRF9dERqCDS+eutorOcQ4f1a5vBkrQogCOkmjXS92O9ZWBYJ+hdczzSe+2
+CNlMuJSHn5ToMTUCAwEAAaNTMFEwHQYDVR0OBBYEFN1ZyDULfPECYBCHGSRtIwnb
+l5mzMB8GA1UdIwQYMBaAFN1ZyDULfPECYBCHGSRtIwnbl5mzMA8GA1UdEwEB/wQF
+MAMBAf8wDQYJKoZIhvcNAQELBQADggEBAKNGuyeFkEL30BAV7BV2LEE5GIfkniPa
++nx1xzpzH7qv7PWjtjOVWpqWygE8x+dUb16KzlcPrBoJ8AfnvGr2FKgXy0N+Ceno
+UnPBYjWjmWjOTVu3RAmsWeMactTi/7vHujK2VqM4GCDSkknB4JF7lItK+51HU/Km
+5MnAQx4+yQo8EZytf4KjQ3124/UwaB19O3mPldLcI05y++WDJWFedVnlqWd82Mh+
+K3AfCuaAdOEvd/8psbJt+nnIVJO+ORwp3dfrIarA/cnMryKYkR/+57irPx6szckx
+Rbb9y+KYjWfqy0OTymmfu6UzA9QmfzT52MyAHR1yDkq+D6RbUrq3O/k=
+-----END CERTIFICATE-----
+";
+
+// openssl req -x509 -new -subj "/CN=EC Test CA/O=Teaclave" -set_serial 0x2002, prime256v1
+// -addext "basicConstraints=critical,CA:TRUE" -addext "keyUsage=critical,keyCertSign"
+static X509_EC_CA: &'static str = "
+-----BEGIN CERTIFICATE-----
+MIIBozCCAUmgAwIBAgICIAIwCgYIKoZIzj0EAwIwKDETMBEGA1UEAwwKRUMgVGVz
+dCBDQTERMA8GA1UECgwIVGVhY2xhdmUwHhcNMjYxMDE1MTgwMjE5WhcNMzYxMDEy
+MTgwMjE5WjAoMRMwEQYDVQQDDApFQyBUZXN0IENBMREwDwYDVQQKDAhUZWFjbGF2
+ZTBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABEeZAuNEZfyvpXLlm9CvtcgiVhb8
+wsjQxqjkOaw1bYePrxFplMS5uZSR5VuOBEt/F8ZiDNyeKg3/JSyjMZ7yzzOjYzBh
+MB0GA1UdDgQWBBT984RR8fKMIUo3m5GphtsCtNuA3zAfBgNVHSMEGDAWgBT984RR
+8fKMIUo3m5GphtsCtNuA3zAPBgNVHRMBAf8EBTADAQH/MA4GA1UdDwEB/wQEAwIC
+BDAKBggqhkjOPQQDAgNIADBFAiB3zYO42RhM4iv4CAPSRaYnkHfRhdu6cnbq3s8w
+Gb14jgIhAKSznyu7iVe950+f3HRAXqFtL0IcLKI9VEGMdjk3fo3F
+-----END CERTIFICATE-----
+";
+
+// signed by X509_EC_CA with the extensions basicConstraints=CA:FALSE,
+// 1.2.840.113741.1337.6=DER:01:02:03:04 and subjectAltName=DNS:localhost
+static X509_EC_LEAF: &'static str = "
+-----BEGIN CERTIFICATE-----
+MIIBpTCCAUugAwIBAgICMAMwCgYIKoZIzj0EAwIwKDETMBEGA1UEAwwKRUMgVGVz
+dCBDQTERMA8GA1UECgwIVGVhY2xhdmUwHhcNMjYxMDE1MTgwMjE5WhcNMzYxMDEy
+MTgwMjE5WjAXMRUwEwYDVQQDDAxFQyBUZXN0IExlYWYwWTATBgcqhkjOPQIBBggq
+hkjOPQMBBwNCAATu2EYjdkGyjl6zlRNLcmplFOzYW67Vi2eXwS/7csz/CdhspkC6
+BcrZCa3UZvU2AmgkZp1M6ia0qIwOblFQ9hXgo3YwdDAJBgNVHRMEAjAAMBEGCSqG
+SIb4TYo5BgQEAQIDBDAUBgNVHREEDTALgglsb2NhbGhvc3QwHQYDVR0OBBYEFLjf
+Kt8+DGWft58r41Zn90OFtUhIMB8GA1UdIwQYMBaAFP3zhFHx8owhSjebkamG2wK0
+24DfMAoGCCqGSM49BAMCA0gAMEUCIQCXtG6P+uOrOOOHHJC+vPGXjuMwy9GligIm
+XpZ+LSnr+wIgVhjqz4zHXQDSliLkogWb3vWgwLvy5b24tuUvXo9cNSI=
+-----END CERTIFICATE-----
+";
+
+// openssl req -x509 -newkey rsa:3072 -sha256 -subj "/CN=RSA-3072 Test CA/O=Teaclave" -set_serial 0x4004
+// -addext "basicConstraints=critical,CA:TRUE" -addext "keyUsage=critical,keyCertSign"
+static X509_RSA3072_CA: &'static str = "
+-----BEGIN CERTIFICATE-----
+MIIEOzCCAqOgAwIBAgICQAQwDQYJKoZIhvcNAQELBQAwLjEZMBcGA1UEAwwQUlNB
+LTMwNzIgVGVzdCBDQTERMA8GA1UECgwIVGVhY2xhdmUwHhcNMjYxMDE1MjA0ODUx
+WhcNMzYxMDEyMjA0ODUxWjAuMRkwFwYDVQQDDBBSU0EtMzA3MiBUZXN0IENBMREw
+DwYDVQQKDAhUZWFjbGF2ZTCCAaIwDQYJKoZIhvcNAQEBBQADggGPADCCAYoCggGB
+AMEQFHeEYoTycEuGdfj+V/g9jCizV+I5fTos5vGUn7Ci6IotxgQjR0e0ikpqSYAq
+Q1dnp9gbost0hgg8nfYjWTic7g87uLqfjG+7cDINDWY5e/gg8zvvWBYQC7l1Lewz
+ZRVwa6UzJrXgjx3I1FldCZ75JhZ8Gr3XadaSWBjll03AH2aMw29kLHbi2ncIGypS
+OvoiFwQWfFPFP6RpZ2KDCGSXLFlhbzPPPu20cXIW2cOI6tfn6Xnrb7Hh3uqxc3lH
+Oz9tsehxxdJaSFXro+c4vbmI8pApFN1wSpwHXfU6oS5sj6shr++BJzmNuimgpiiO
+3y/Zqp3l/kleDwAEZak2BjUhEpbwfqRXCxmkxzlyqzzloUB7dNxuRDzgJzDCmkps
+24dvx+kklEayI1obsH4FHalyeU/eYrPcfjAWPWFXxXINTjoGRLFFXzYNq7ZdeBRc
+B85rvNHbcdZbTArf4vKqvS88V9YfAF/ZSgCzE7u7evjY8lKMmW0DbgC/XPtG1DIW
+zwIDAQABo2MwYTAdBgNVHQ4EFgQUWMLp+JxOVt+AL3DERlNWI9j1roIwHwYDVR0j
+BBgwFoAUWMLp+JxOVt+AL3DERlNWI9j1roIwDwYDVR0TAQH/BAUwAwEB/zAOBgNV
+HQ8BAf8EBAMCAgQwDQYJKoZIhvcNAQELBQADggGBAFSIfrKq2JaS1Eg+8WE7krKO
+hU5e2n+vvTH5Hbq9bxlHG08fi7B3FCetN1n3ZjTcBQ4/udfBwJ8YQrJA6FSLCClK
+YFDpb/zz/pTm/R6Yl3/b3PfwUx5dmQDhmONGD9TmZaMaqTbIZzduj0qkrSNQyVli
+/C2I382iJhUV8YEbOJT6QKx1lqM2XdJiMXHT83uaDu/OG2qykOPh9seBeM4vhlzG
+toXZjHXSr8f1jIgdtE+9XgLemCyKQaA6MB010b6htCVm6GJp+A/BE5rEkHX/Vu3x
+Wg1oc5eUu5sfjmWHfsnWvzkBXFIPmBupLWGsPa3OeObZHvLEDvmnPsE4ntSHtvGC
+jfTLxUe7Hnrf1qQcccFnnJEqvVFhIegBCXD7vdwuUcFiZnNVQGJmYxfBD3MSoIVl
++O7Y9RRfvwq2THZWO4NAp1g45lmovyAg5GhxpA5kGKOdNL2tllOYSzGEM1XdXcsZ
+xQkTAhMAqwccSMwEwKBTgJ9kriDnyv+5xjc23DoH7Q==
+-----END CERTIFICATE-----
+";
+
+// a prime256v1 key signed by X509_RSA3072_CA with sha256WithRSAEncryption
+static X509_RSA3072_SIGNED_LEAF: &'static str = "
+-----BEGIN CERTIFICATE-----
+MIICuzCCASOgAwIBAgICUAUwDQYJKoZIhvcNAQELBQAwLjEZMBcGA1UEAwwQUlNB
+LTMwNzIgVGVzdCBDQTERMA8GA1UECgwIVGVhY2xhdmUwHhcNMjYxMDE1MjA0ODUx
+WhcNMzYxMDEyMjA0ODUxWjAaMRgwFgYDVQQDDA9SU0EgU2lnbmVkIExlYWYwWTAT
+BgcqhkjOPQIBBggqhkjOPQMBBwNCAAROjgGVt9/zeOD/AisRpCqf2U1blM4itvm9
+74i5H/k/LIqpqONL1bjDcdaslWILI1BdXBn3n7/pYFvMA1RwcfQso0IwQDAdBgNV
+HQ4EFgQU5QoTewhj6EF2HII8gTlyXGXgsJQwHwYDVR0jBBgwFoAUWMLp+JxOVt+A
+L3DERlNWI9j1roIwDQYJKoZIhvcNAQELBQADggGBADfo1WHkkSdO81b3GQMU/w3a
+RHXJpG3/ddB4bWczuDod0KRo4XtL8yUqNwPgRXRV2sTxiM3924iyI21beMbvj04W
+grhfKlkgQJpJPq4PaMfu7RM5tfbGRVKXVkw59otlD8Yh5xQQ8wDGUUxc+uEVNkIu
+6cJMccLhWnaYOf2+w7EF2JM6uMdMENOsP7pvzgU50R9a3gzSPPUmOD3JtB6VkSft
+13pXmhGorHY3u6zdMgGpJnhYJ5mBKage6y3+3FCu1v1i89J7ceALlu9rFFnarYHE
+exECqWtOsfAoUK6hSZWyistc9/OCmBnd1QHVjaM80fdK7lHHuTKMlQAvlrY47GeP
+Dbpq6SkhEnqEF7lIJhauSFiv49EoFQk/ceDH0KdIKehrGOVY9KhOxLPhSY5Fk8zu
+pSjjxkk/7pHDYW9B3pe+VeDb+yoNNlkXzazOvK2HAVww93FC0QKln6LHjJJhFARA
+uZVqu2ZKHQEE59POc7wFNJJwjio7LpdcWkC0Ai434Q==
+-----END CERTIFICATE-----
+";
+
+pub fn test_x509() {
+    use sgx_tcrypto::x509::Certificate;
+
+    // RSA, self-signed
+    let root = Certificate::from_pem(X509_RSA_ROOT).unwrap();
+    assert_eq!(root.version(), 3);
+    assert_eq!(root.serial_number(), &[0x10, 0x01]);
+    assert_eq!(root.subject().common_name(), Some("RSA Test Root"));
+    assert!(root.is_self_issued());
+    assert!(root.signature_algorithm().oid() == "1.2.840.113549.1.1.11");
+    assert!(root.public_key().algorithm().oid() == "1.2.840.113549.1.1.1");
+    assert!(root.public_key().ec256_public_key().is_err());
+    assert_eq!(root.not_before().as_str(), "261015180219Z");
+    assert_eq!(root.not_before().unix_time(), 1792087339);
+    assert_eq!(root.not_after().unix_time(), 2107447339);
+    let basic_constraints = root.extension("2.5.29.19").unwrap();
+    assert!(basic_constraints.critical());
+    assert_eq!(basic_constraints.value(), &[0x30, 0x03, 0x01, 0x01, 0xff]);
+    assert_eq!(
+        root.verify_signature(&root.public_key()),
+        Err(sgx_status_t::SGX_ERROR_FEATURE_NOT_SUPPORTED)
+    );
+
+    // EC, self-signed CA
+    let ca = Certificate::from_pem(X509_EC_CA).unwrap();
+    assert_eq!(ca.subject().common_name(), Some("EC Test CA"));
+    assert!(ca.is_self_issued());
+    assert!(ca.signature_algorithm().oid() == "1.2.840.10045.4.3.2");
+    assert!(ca.public_key().ec256_public_key().is_ok());
+    assert_eq!(ca.extensions().count(), 4);
+    assert!(ca.extension("2.5.29.15").unwrap().critical());
+    assert_eq!(ca.verify_signature(&ca.public_key()), Ok(true));
+
+    // EC, signed by the CA, with an RA-TLS quote extension
+    let leaf = Certificate::from_pem(X509_EC_LEAF).unwrap();
+    assert_eq!(leaf.subject().common_name(), Some("EC Test Leaf"));
+    assert!(!leaf.is_self_issued());
+    assert_eq!(leaf.issuer(), ca.subject());
+    let quote = leaf
+        .extensions()
+        .find(|ext| ext.oid() == "1.2.840.113741.1337.6")
+        .unwrap();
+    assert!(!quote.critical());
+    assert_eq!(quote.value(), &[1, 2, 3, 4]);
+    assert!(leaf.extension("1.2.840.113741.1337.7").is_none());
+    assert_eq!(leaf.verify_signature(&ca.public_key()), Ok(true));
+    assert_eq!(leaf.verify_signature(&leaf.public_key()), Ok(false));
+    assert_eq!(
+        leaf.verify_signature(&root.public_key()),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+
+    // RSA-3072, self-signed CA and an EC leaf it signed
+    let rsa_ca = Certificate::from_pem(X509_RSA3072_CA).unwrap();
+    assert_eq!(rsa_ca.subject().common_name(), Some("RSA-3072 Test CA"));
+    assert!(rsa_ca.signature_algorithm().oid() == "1.2.840.113549.1.1.11");
+    assert!(rsa_ca.public_key().rsa3072_public_key().is_ok());
+    assert_eq!(
+        root.public_key().rsa3072_public_key().err(),
+        Some(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+    assert_eq!(rsa_ca.verify_signature(&rsa_ca.public_key()), Ok(true));
+    assert_eq!(rsa_ca.verify_signature(&ca.public_key()), Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER));
+    let rsa_leaf = Certificate::from_pem(X509_RSA3072_SIGNED_LEAF).unwrap();
+    assert_eq!(rsa_leaf.issuer(), rsa_ca.subject());
+    assert!(rsa_leaf.public_key().ec256_public_key().is_ok());
+    assert_eq!(rsa_leaf.verify_signature(&rsa_ca.public_key()), Ok(true));
+    assert_eq!(rsa_ca.verify_signature(&rsa_leaf.public_key()), Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER));
+    // a 2048 bit issuer key is not supported
+    assert_eq!(
+        rsa_leaf.verify_signature(&root.public_key()),
+        Err(sgx_status_t::SGX_ERROR_FEATURE_NOT_SUPPORTED)
+    );
+    let mut tampered = rsa_leaf.as_der().to_vec();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 1;
+    let tampered = Certificate::from_der(&tampered).unwrap();
+    assert_eq!(tampered.verify_signature(&rsa_ca.public_key()), Ok(false));
+
+    // DER, borrowed
+    let der = leaf.as_der().to_vec();
+    let borrowed = Certificate::from_der(&der).unwrap();
+    assert_eq!(borrowed.tbs_certificate(), leaf.tbs_certificate());
+    assert_eq!(borrowed.public_key(), leaf.public_key());
+
+    // truncated, trailing or altered encodings
+    assert!(Certificate::from_der(&der[..der.len() - 1]).is_err());
+    let mut trailing = der.clone();
+    trailing.push(0);
+    assert!(Certificate::from_der(&trailing).is_err());
+    let mut tampered = der.clone();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 1;
+    if let Ok(tampered) = Certificate::from_der(&tampered) {
+        assert_ne!(tampered.verify_signature(&ca.public_key()), Ok(true));
     }
+    assert!(Certificate::from_pem("-----BEGIN CERTIFICATE-----\nMIIB*\n-----END CERTIFICATE-----").is_err());
+    assert!(Certificate::from_pem(&X509_EC_LEAF.replace("CERTIFICATE", "PUBLIC KEY")).is_err());
 }
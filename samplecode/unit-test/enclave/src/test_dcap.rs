@@ -0,0 +1,120 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use sgx_dcap::*;
+use std::vec::Vec;
+
+// Builds a quote of the given version with recognizable contents.
+fn build_quote(version: u16, chain: &[u8]) -> Vec<u8> {
+    let mut quote = Vec::new();
+    // header: version, attestation key type, reserved or TEE type,
+    // QE SVN, PCE SVN, QE vendor id, user data
+    quote.extend_from_slice(&version.to_le_bytes());
+    quote.extend_from_slice(&ATT_KEY_TYPE_ECDSA_P256.to_le_bytes());
+    quote.extend_from_slice(&0_u32.to_le_bytes());
+    quote.extend_from_slice(&7_u16.to_le_bytes());
+    quote.extend_from_slice(&11_u16.to_le_bytes());
+    quote.extend_from_slice(&[0xaa; 16]);
+    quote.extend_from_slice(&[0xbb; 20]);
+    // report body with mr_enclave at offset 64 and report_data at 320
+    let mut body = [0_u8; 384];
+    body[64..96].copy_from_slice(&[0x11; 32]);
+    body[320..].copy_from_slice(&[0x22; 64]);
+    quote.extend_from_slice(&body);
+
+    let mut qe = Vec::new();
+    let mut qe_report = [0_u8; 384];
+    qe_report[64..96].copy_from_slice(&[0x33; 32]);
+    qe.extend_from_slice(&qe_report);
+    qe.extend_from_slice(&[0x44; 64]);
+    qe.extend_from_slice(&3_u16.to_le_bytes());
+    qe.extend_from_slice(b"abc");
+    qe.extend_from_slice(&CERT_TYPE_PCK_CERT_CHAIN.to_le_bytes());
+    qe.extend_from_slice(&(chain.len() as u32).to_le_bytes());
+    qe.extend_from_slice(chain);
+
+    let mut sig = Vec::new();
+    sig.extend_from_slice(&[0x55; 64]);
+    sig.extend_from_slice(&[0x66; 64]);
+    if version == QUOTE_VERSION_4 {
+        sig.extend_from_slice(&CERT_TYPE_QE_REPORT.to_le_bytes());
+        sig.extend_from_slice(&(qe.len() as u32).to_le_bytes());
+    }
+    sig.extend_from_slice(&qe);
+    quote.extend_from_slice(&(sig.len() as u32).to_le_bytes());
+    quote.extend_from_slice(&sig);
+    quote
+}
+
+pub fn test_dcap_quote_parse() {
+    let chain = b"-----BEGIN CERTIFICATE-----";
+    for &version in [QUOTE_VERSION_3, QUOTE_VERSION_4].iter() {
+        let bytes = build_quote(version, chain);
+        let quote = DcapQuote::parse(&bytes).unwrap();
+        assert_eq!(quote.version(), version);
+        assert_eq!(quote.header().user_data, [0xbb; 20]);
+        assert_eq!(quote.report_body().mr_enclave.m, [0x11; 32]);
+        assert_eq!(quote.report_body().report_data.d, [0x22; 64]);
+        assert_eq!(quote.signed_data(), &bytes[..432]);
+        assert_eq!(quote.signature(), &[0x55; 64][..]);
+        assert_eq!(quote.attestation_key(), &[0x66; 64][..]);
+        assert_eq!(quote.qe_report_body().mr_enclave.m, [0x33; 32]);
+        assert_eq!(quote.qe_report().len(), 384);
+        assert_eq!(quote.qe_report_signature(), &[0x44; 64][..]);
+        assert_eq!(quote.qe_auth_data(), b"abc");
+        assert_eq!(quote.pck_cert_chain(), Some(&chain[..]));
+
+        // every truncation is detected
+        for len in 0..bytes.len() {
+            assert!(DcapQuote::parse(&bytes[..len]).is_err());
+        }
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert_eq!(DcapQuote::parse(&longer).unwrap_err(), QuoteError::TrailingData);
+    }
+}
+
+pub fn test_dcap_quote_unsupported() {
+    let bytes = build_quote(QUOTE_VERSION_3, b"");
+
+    let mut epid = bytes.clone();
+    epid[0] = 2;
+    assert_eq!(DcapQuote::parse(&epid).unwrap_err(), QuoteError::UnsupportedVersion(2));
+
+    let mut p384 = bytes.clone();
+    p384[2] = 3;
+    assert_eq!(
+        DcapQuote::parse(&p384).unwrap_err(),
+        QuoteError::UnsupportedAttestationKeyType(3)
+    );
+
+    let mut tdx = build_quote(QUOTE_VERSION_4, b"");
+    tdx[4..8].copy_from_slice(&TEE_TYPE_TDX.to_le_bytes());
+    assert_eq!(
+        DcapQuote::parse(&tdx).unwrap_err(),
+        QuoteError::UnsupportedTeeType(TEE_TYPE_TDX)
+    );
+
+    // a version 4 quote must wrap the QE report in certification data
+    let mut v4 = build_quote(QUOTE_VERSION_4, b"");
+    v4[436 + 128] = CERT_TYPE_PCK_CERT_CHAIN as u8;
+    assert_eq!(
+        DcapQuote::parse(&v4).unwrap_err(),
+        QuoteError::InvalidCertificationData
+    );
+
+}
@@ -1,5 +1,8 @@
 use std::env::*;
 use std::path::Path;
+use std::string::{String, ToString};
+use std::thread;
+use std::vec::Vec;
 
 pub fn test_env_vars_os() {
     let p = vars_os();
@@ -24,3 +27,46 @@ pub fn test_env_home_dir() {
     let dir = home_dir();
     println!("{:?}", dir.unwrap());
 }
+
+pub fn test_env_args_override() {
+    reset_args();
+    assert_eq!(args().collect::<Vec<String>>(), ["enclave"]);
+
+    let sweep = vec!["enclave".to_string(), "--lr=0.01".to_string(), "--epochs=20".to_string()];
+    override_args(sweep.clone());
+    let mut a = args();
+    assert_eq!(a.len(), 3);
+    assert_eq!(a.next_back(), Some("--epochs=20".to_string()));
+    assert_eq!(args().collect::<Vec<String>>(), sweep);
+
+    // the override is local to the thread
+    let other = thread::spawn(|| args().collect::<Vec<String>>()).join().unwrap();
+    assert_eq!(other, ["enclave"]);
+
+    let custom = Args::from_vec(vec!["a".to_string(), "b".to_string()]);
+    assert_eq!(custom.collect::<Vec<String>>(), ["a", "b"]);
+
+    reset_args();
+    assert_eq!(args().count(), 1);
+}
+
+pub fn test_env_enclave_measurement() {
+    use std::os::sgx::EnclaveMeasurement;
+
+    let measurement = EnclaveMeasurement::current();
+    assert!(measurement.mrenclave().iter().any(|&b| b != 0));
+    assert!(measurement.mrsigner().iter().any(|&b| b != 0));
+
+    // the report is created once and cached
+    let again = EnclaveMeasurement::current();
+    assert!(std::ptr::eq(measurement, again));
+    assert_eq!(measurement.mrenclave(), again.mrenclave());
+    assert_eq!(measurement.mrsigner(), again.mrsigner());
+
+    // every thread sees the same enclave
+    let mrenclave = thread::spawn(|| EnclaveMeasurement::current().mrenclave())
+        .join()
+        .unwrap();
+    assert_eq!(measurement.mrenclave(), mrenclave);
+    println!("{:?}", measurement);
+}
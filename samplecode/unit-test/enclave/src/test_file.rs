@@ -23,66 +23,29 @@ use std::untrusted::fs::remove_file;
 use std::untrusted::fs::File;
 
 pub fn test_sgxfs() {
-    let mut write_data: [u8; 16] = [0; 16];
-    let mut read_data: [u8; 16] = [0; 16];
-    let write_size;
-    let read_size;
-    {
-        let mut rand = StdRng::new().unwrap();
-        rand.fill_bytes(&mut write_data);
-
-        let opt = SgxFile::create("sgx_file");
-        assert_eq!(opt.is_ok(), true);
-        let mut file = opt.unwrap();
-
-        let result = file.write(&write_data);
-        assert_eq!(result.is_ok(), true);
-        write_size = result.unwrap();
-    }
-
-    {
-        let opt = SgxFile::open("sgx_file");
-        assert_eq!(opt.is_ok(), true);
-        let mut file = opt.unwrap();
-
-        let result = file.read(&mut read_data);
-        assert_eq!(result.is_ok(), true);
-        read_size = result.unwrap();
-    }
-
-    let result = sgxfs::remove("sgx_file");
-    assert_eq!(result.is_ok(), true);
-
-    assert_eq!(write_data, read_data);
-    assert_eq!(write_size, read_size);
-
-    {
-        let opt = SgxFile::open("/");
-        assert_eq!(opt.is_err(), true);
-        let opt = SgxFile::open(".");
-        assert_eq!(opt.is_err(), true);
-        let opt = SgxFile::open("..");
-        assert_eq!(opt.is_err(), true);
-        let opt = SgxFile::open("?");
-        assert_eq!(opt.is_err(), true);
+    let mut data = [0_u8; 16];
+    StdRng::new().unwrap().fill_bytes(&mut data);
+
+    sgxfs::write("sgx_file", &data).unwrap();
+    sgxfs::append("sgx_file", b"tail").unwrap();
+    assert_eq!(sgxfs::read("sgx_file").unwrap(), [&data[..], b"tail"].concat());
+    sgxfs::remove("sgx_file").unwrap();
+
+    let key = [0x5a_u8; 16];
+    sgxfs::write_with("sgx_file_key", Some(&key), "Hello, ").unwrap();
+    sgxfs::append_with("sgx_file_key", Some(&key), "world!").unwrap();
+    assert_eq!(sgxfs::read_to_string_with("sgx_file_key", Some(&key)).unwrap(), "Hello, world!");
+    assert!(sgxfs::read_with("sgx_file_key", Some(&[0_u8; 16])).is_err());
+    assert!(sgxfs::read_to_string("sgx_file_key").is_err());
+    sgxfs::remove("sgx_file_key").unwrap();
+
+    for path in &["/", ".", "..", "?"] {
+        assert!(SgxFile::open(path).is_err());
     }
     #[cfg(feature = "hw_test")]
-    {
-        let opt1 = SgxFile::open("/dev/isgx");
-        let opt2 = SgxFile::open("/dev/sgx/enclave");
-        assert_eq!(opt1.is_ok() || opt2.is_ok(), true);
-    }
-    {
-        let opt = SgxFile::create("/");
-        assert_eq!(opt.is_err(), true);
-    }
-    {
-        let opt = SgxFile::create("/proc/100");
-        assert_eq!(opt.is_err(), true);
-        let opt = SgxFile::create(".");
-        assert_eq!(opt.is_err(), true);
-        let opt = SgxFile::create("..");
-        assert_eq!(opt.is_err(), true);
+    assert!(SgxFile::open("/dev/isgx").is_ok() || SgxFile::open("/dev/sgx/enclave").is_ok());
+    for path in &["/", "/proc/100", ".", ".."] {
+        assert!(SgxFile::create(path).is_err());
     }
 }
 
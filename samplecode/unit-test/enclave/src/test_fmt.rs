@@ -0,0 +1,67 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use std::fmt::{self, DisplayBuffer, Write};
+use std::string::ToString;
+
+struct Report {
+    id: u32,
+    detail: [u8; 190],
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "report {:04}: ", self.id)?;
+        for b in self.detail.iter() {
+            f.write_char(*b as char)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn test_fmt_display_buffer() {
+    let report = Report { id: 7, detail: [b'x'; 190] };
+
+    let buf = DisplayBuffer::<100>::from(&report as &dyn fmt::Display);
+    assert!(buf.is_truncated());
+    assert_eq!(buf.len(), 100);
+    assert!(buf.as_str().starts_with("report 0007: xxx"));
+    assert!(buf.as_str().ends_with("x..."));
+
+    let mut large = DisplayBuffer::<256>::new();
+    write!(large, "{}", report).unwrap();
+    assert!(!large.is_truncated());
+    assert_eq!(large.len(), 203);
+    assert_eq!(&large.as_str()[..97], &buf.as_str()[..97]);
+
+    // multi-byte characters are never split
+    let mut buf = DisplayBuffer::<8>::new();
+    write!(buf, "{}", "ab\u{00e9}\u{00e9}\u{00e9}\u{00e9}").unwrap();
+    assert_eq!(buf.as_str(), "ab\u{00e9}...");
+    write!(buf, "more").unwrap();
+    assert_eq!(buf.as_str(), "ab\u{00e9}...");
+
+    buf.clear();
+    assert!(buf.is_empty());
+    write!(buf, "{:>6}", 42).unwrap();
+    assert_eq!(buf.to_string(), "    42");
+    assert_eq!(format!("{:?}", buf), "\"    42\"");
+
+    let mut tiny = DisplayBuffer::<2>::new();
+    write!(tiny, "abc").unwrap();
+    assert_eq!(tiny.as_str(), "..");
+}
@@ -0,0 +1,436 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use sgx_rand::{Rng, SgxRng};
+use sgx_tcrypto::aead::{StreamDecryptor, StreamEncryptor};
+use sgx_tcrypto::aes::AesGcm128;
+use std::io::{
+    self, BufRead, BufReader, ChunkedReader, ChunkedWriter, Cursor, Error, ErrorKind, FramedReader, FramedWriter,
+    Read, ReadExt, Seek, SeekFrom, TeeReader, TeeWriter, Write, WriteExt,
+};
+use sgx_types::sgx_status_t;
+use std::convert::TryFrom;
+use std::string::String;
+use std::vec::Vec;
+
+const CHUNK_SIZE: usize = 4096;
+// Precedes the first chunk.
+const STREAM_ID_LEN: usize = 16;
+// len: u32 | last: u8 | nonce: 12 | ciphertext | tag: 16
+const CHUNK_OVERHEAD: usize = 4 + 1 + 12 + 16;
+
+// A writer encrypting in chunks of CHUNK_SIZE bytes with
+// sgx_tcrypto::aead, the way an enclave would put the two together.
+fn encrypting_writer<W: Write>(
+    inner: W,
+    key: &[u8],
+) -> FramedWriter<W, impl FnMut(&[u8], bool) -> io::Result<Vec<u8>>> {
+    let mut encryptor = StreamEncryptor::<AesGcm128>::new(key, CHUNK_SIZE).unwrap();
+    FramedWriter::new(inner, CHUNK_SIZE, move |chunk, last| {
+        encryptor.seal_chunk(chunk, last).map_err(Error::from_sgx_error)
+    })
+}
+
+fn decrypting_reader<R: Read>(
+    inner: R,
+    key: &[u8],
+) -> FramedReader<R, impl FnMut(&mut R) -> io::Result<Option<Vec<u8>>>> {
+    // Every error of the decryptor means that the stream is corrupt.
+    fn invalid(status: sgx_status_t) -> Error {
+        Error::new(ErrorKind::InvalidData, status)
+    }
+
+    let mut decryptor = StreamDecryptor::<AesGcm128>::new(key, CHUNK_SIZE).unwrap();
+    FramedReader::new(inner, move |inner: &mut R| {
+        if decryptor.is_finished() {
+            return Ok(None);
+        }
+        // A stream that ends before its final chunk fails here with
+        // UnexpectedEof.
+        let mut header = vec![0_u8; decryptor.header_len()];
+        inner.read_exact(&mut header)?;
+        let mut body = vec![0_u8; decryptor.parse_header(&header).map_err(invalid)?];
+        inner.read_exact(&mut body)?;
+        decryptor.open_chunk(&body).map(Some).map_err(invalid)
+    })
+}
+
+fn encrypt(key: &[u8], data: &[u8], write_sizes: &[usize]) -> Vec<u8> {
+    let mut writer = encrypting_writer(Vec::new(), key);
+    let mut pos = 0;
+    for &n in write_sizes.iter().cycle() {
+        if pos == data.len() {
+            break;
+        }
+        let end = std::cmp::min(pos + n, data.len());
+        writer.write_all(&data[pos..end]).unwrap();
+        pos = end;
+    }
+    writer.finish().unwrap()
+}
+
+fn decrypt(key: &[u8], sealed: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut reader = decrypting_reader(sealed, key);
+    let mut plain = Vec::new();
+    reader.read_to_end(&mut plain)?;
+    Ok(plain)
+}
+
+pub fn test_io_encrypting_writer() {
+    let mut rng = SgxRng::new().unwrap();
+    let mut key = [0_u8; 16];
+    rng.fill_bytes(&mut key);
+    let mut data = vec![0_u8; 1024 * 1024];
+    rng.fill_bytes(&mut data);
+
+    // Odd write sizes so that writes straddle chunk boundaries.
+    let sealed = encrypt(&key, &data, &[1, 4095, 4097, 10000, 7]);
+    let chunks = data.len() / CHUNK_SIZE + 1;
+    assert_eq!(sealed.len(), STREAM_ID_LEN + data.len() + chunks * CHUNK_OVERHEAD);
+    // Every chunk but the empty final one is full.
+    let mut pos = STREAM_ID_LEN;
+    for i in 0..chunks {
+        let mut len = [0_u8; 4];
+        len.copy_from_slice(&sealed[pos..pos + 4]);
+        let len = u32::from_le_bytes(len) as usize;
+        let last = i == chunks - 1;
+        assert_eq!(sealed[pos + 4], last as u8);
+        assert_eq!(len, if last { 12 + 16 } else { 12 + CHUNK_SIZE + 16 });
+        pos += 5 + len;
+    }
+    assert_eq!(pos, sealed.len());
+    assert_eq!(decrypt(&key, &sealed).unwrap(), data);
+
+    // A flush emits a short chunk, which reads back transparently.
+    let mut writer = encrypting_writer(Vec::new(), &key);
+    writer.write_all(b"hello ").unwrap();
+    writer.flush().unwrap();
+    writer.write_all(b"enclave").unwrap();
+    let sealed_short = writer.finish().unwrap();
+    assert_eq!(decrypt(&key, &sealed_short).unwrap(), b"hello enclave");
+
+    // Wrong key.
+    let mut wrong_key = key;
+    wrong_key[0] ^= 1;
+    assert_eq!(
+        decrypt(&wrong_key, &sealed).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    // Tampered ciphertext.
+    let mut tampered = sealed.clone();
+    tampered[STREAM_ID_LEN + 5 + 12 + 100] ^= 1;
+    assert_eq!(
+        decrypt(&key, &tampered).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    // Swapped chunks.
+    let chunk_len = 5 + 12 + CHUNK_SIZE + 16;
+    let mut swapped = sealed.clone();
+    swapped[STREAM_ID_LEN..STREAM_ID_LEN + 2 * chunk_len].rotate_left(chunk_len);
+    assert_eq!(
+        decrypt(&key, &swapped).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    // Truncated at a chunk boundary.
+    assert_eq!(
+        decrypt(&key, &sealed[..STREAM_ID_LEN + chunk_len]).unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+    // Tampered stream id.
+    let mut tampered = sealed.clone();
+    tampered[0] ^= 1;
+    assert_eq!(
+        decrypt(&key, &tampered).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    // A chunk spliced in from another stream under the same key, at the
+    // same position.
+    let other = encrypt(&key, &data, &[CHUNK_SIZE]);
+    assert_ne!(other[..STREAM_ID_LEN], sealed[..STREAM_ID_LEN]);
+    let second = STREAM_ID_LEN + chunk_len..STREAM_ID_LEN + 2 * chunk_len;
+    let mut spliced = sealed.clone();
+    spliced[second.clone()].copy_from_slice(&other[second]);
+    assert_eq!(
+        decrypt(&key, &spliced).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    // The spliced stream still decrypts up to the foreign chunk.
+    let mut reader = decrypting_reader(&spliced[..], &key);
+    let mut first = vec![0_u8; CHUNK_SIZE];
+    reader.read_exact(&mut first).unwrap();
+    assert_eq!(first, data[..CHUNK_SIZE]);
+    assert_eq!(
+        reader.read(&mut first).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+}
+
+pub fn test_io_read_write_ext() {
+    let mut buf = Vec::new();
+    buf.write_u8(0xfe).unwrap();
+    buf.write_i8(-2).unwrap();
+    buf.write_u16_be(0x0102).unwrap();
+    buf.write_u32_be(0x0102_0304).unwrap();
+    buf.write_u64_be(0x0102_0304_0506_0708).unwrap();
+    buf.write_i16_be(-0x0102).unwrap();
+    buf.write_i32_be(-0x0102_0304).unwrap();
+    buf.write_i64_be(i64::MIN).unwrap();
+    buf.write_u16_le(0x0102).unwrap();
+    buf.write_u32_le(0x0102_0304).unwrap();
+    buf.write_u64_le(0x0102_0304_0506_0708).unwrap();
+    buf.write_i16_le(-0x0102).unwrap();
+    buf.write_i32_le(-0x0102_0304).unwrap();
+    buf.write_i64_le(i64::MAX).unwrap();
+    assert_eq!(buf.len(), 2 * (1 + 2 + 4 + 8) + 2 + 4 + 8 + 2 + 4 + 8);
+    assert_eq!(&buf[2..8], &[1, 2, 1, 2, 3, 4]);
+    assert_eq!(&buf[30..36], &[2, 1, 4, 3, 2, 1]);
+
+    let mut rdr = Cursor::new(buf);
+    assert_eq!(rdr.read_u8().unwrap(), 0xfe);
+    assert_eq!(rdr.read_i8().unwrap(), -2);
+    assert_eq!(rdr.read_u16_be().unwrap(), 0x0102);
+    assert_eq!(rdr.read_u32_be().unwrap(), 0x0102_0304);
+    assert_eq!(rdr.read_u64_be().unwrap(), 0x0102_0304_0506_0708);
+    assert_eq!(rdr.read_i16_be().unwrap(), -0x0102);
+    assert_eq!(rdr.read_i32_be().unwrap(), -0x0102_0304);
+    assert_eq!(rdr.read_i64_be().unwrap(), i64::MIN);
+    assert_eq!(rdr.read_u16_le().unwrap(), 0x0102);
+    assert_eq!(rdr.read_u32_le().unwrap(), 0x0102_0304);
+    assert_eq!(rdr.read_u64_le().unwrap(), 0x0102_0304_0506_0708);
+    assert_eq!(rdr.read_i16_le().unwrap(), -0x0102);
+    assert_eq!(rdr.read_i32_le().unwrap(), -0x0102_0304);
+    assert_eq!(rdr.read_i64_le().unwrap(), i64::MAX);
+    assert_eq!(rdr.read_u8().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+
+    // a truncated integer is an error too
+    let mut rdr = Cursor::new([0_u8; 3]);
+    assert_eq!(rdr.read_u32_le().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+}
+
+pub fn test_io_read_tls_record_header() {
+    // TLS 1.2 handshake record carrying the start of a ClientHello
+    let record: &[u8] = &[
+        0x16, 0x03, 0x03, 0x00, 0x2f, 0x01, 0x00, 0x00, 0x2b, 0x03, 0x03,
+    ];
+    let mut rdr = Cursor::new(record);
+
+    let content_type = rdr.read_u8().unwrap();
+    let version = rdr.read_u16_be().unwrap();
+    let length = rdr.read_u16_be().unwrap();
+    assert_eq!(content_type, 22);
+    assert_eq!(version, 0x0303);
+    assert_eq!(length, 47);
+
+    // handshake header: type u8, length u24
+    assert_eq!(rdr.read_u8().unwrap(), 1);
+    let hs_len = (rdr.read_u8().unwrap() as u32) << 16 | rdr.read_u16_be().unwrap() as u32;
+    assert_eq!(hs_len, length as u32 - 4);
+    assert_eq!(rdr.read_u16_be().unwrap(), version);
+
+    // and back
+    let mut header = Vec::new();
+    header.write_u8(content_type).unwrap();
+    header.write_u16_be(version).unwrap();
+    header.write_u16_be(length).unwrap();
+    assert_eq!(header, &record[..5]);
+}
+
+pub fn test_io_chunked_writer() {
+    let mut writer = ChunkedWriter::new(Vec::new(), 64, 0xa5);
+    writer.write_all(&[0x42]).unwrap();
+    assert!(writer.get_ref().is_empty());
+    writer.flush().unwrap();
+    let out = writer.get_ref().clone();
+    assert_eq!(out.len(), 64);
+    assert_eq!(&out[..5], &[0, 0, 0, 1, 0x42]);
+    assert!(out[5..].iter().all(|b| *b == 0xa5));
+
+    let mut reader = ChunkedReader::new(&out[..], 64, 0xa5);
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data).unwrap();
+    assert_eq!(data, [0x42]);
+
+    // only whole chunks reach the underlying writer
+    let message: Vec<u8> = (0..200_u8).map(|i| i % 100 + 1).collect();
+    writer.write_all(&message[..150]).unwrap();
+    assert_eq!(writer.get_ref().len(), 64 * 3);
+    writer.flush().unwrap();
+    writer.write_all(&message[150..]).unwrap();
+    let out = writer.into_inner().unwrap();
+    assert_eq!(out.len(), 64 * 5);
+
+    let mut reader = ChunkedReader::new(&out[..], 64, 0xa5);
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data).unwrap();
+    assert_eq!(data[0], 0x42);
+    assert_eq!(&data[1..], &message[..]);
+
+    let mut reader = ChunkedReader::new(&out[..100], 64, 0xa5);
+    let mut data = Vec::new();
+    assert_eq!(reader.read_to_end(&mut data).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+
+    // data ending in the pad byte, or consisting of it, is not truncated
+    for message in [&[0x42, 0xa5, 0xa5][..], &[0xa5; 60][..], &[0xa5; 61][..]] {
+        let mut writer = ChunkedWriter::new(Vec::new(), 64, 0xa5);
+        writer.write_all(message).unwrap();
+        let out = writer.into_inner().unwrap();
+        let mut data = Vec::new();
+        ChunkedReader::new(&out[..], 64, 0xa5).read_to_end(&mut data).unwrap();
+        assert_eq!(data, message);
+    }
+
+    // a chunk claiming more data than fits in it
+    let mut out = vec![0xa5; 64];
+    out[..4].copy_from_slice(&61_u32.to_be_bytes());
+    let mut reader = ChunkedReader::new(&out[..], 64, 0xa5);
+    let mut data = Vec::new();
+    assert_eq!(reader.read_to_end(&mut data).unwrap_err().kind(), ErrorKind::InvalidData);
+}
+
+pub fn test_io_bufreader_fill_buf_checked() {
+    let mut reader = BufReader::with_capacity(4, Cursor::new(b"HTTP/1.1 200 OK\r\nA: b\r\n\r\nbody".to_vec()));
+    assert_eq!(reader.capacity(), 4);
+    assert_eq!(reader.fill_buf_checked().unwrap(), b"HTTP");
+
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    assert_eq!(line, "HTTP/1.1 200 OK\r\n");
+
+    // seeking discards the buffer and repositions the inner reader
+    assert_eq!(reader.fill_buf().unwrap(), b"A: b");
+    reader.seek(SeekFrom::Start(9)).unwrap();
+    assert!(reader.buffer().is_empty());
+    let lines: Vec<String> = reader.lines().map(|l| l.unwrap()).collect();
+    assert_eq!(lines, ["200 OK", "A: b", "", "body"]);
+
+    let mut reader = BufReader::new(&b"ab"[..]);
+    assert_eq!(reader.fill_buf_checked().unwrap(), b"ab");
+    reader.consume(2);
+    assert_eq!(reader.fill_buf_checked().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    assert!(reader.fill_buf().unwrap().is_empty());
+}
+
+// Accepts `limit` bytes, then fails every write.
+struct FailingWriter {
+    written: Vec<u8>,
+    limit: usize,
+}
+
+impl Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.written.len() + buf.len() > self.limit {
+            return Err(std::io::Error::new(ErrorKind::Other, "audit log full"));
+        }
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+pub fn test_io_tee() {
+    let mut data = vec![0_u8; 1024];
+    SgxRng::new().unwrap().fill_bytes(&mut data);
+
+    let mut log = Vec::new();
+    let mut received = Vec::new();
+    {
+        let mut reader = TeeReader::new(&data[..], &mut log);
+        let mut buf = [0_u8; 100];
+        loop {
+            let n = reader.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            received.extend_from_slice(&buf[..n]);
+        }
+        assert!(reader.tee_error().is_none());
+    }
+    assert_eq!(received, data);
+    assert_eq!(log, data);
+
+    // a failing tee does not interrupt reading
+    let mut reader = TeeReader::new(&data[..], FailingWriter { written: Vec::new(), limit: 512 });
+    let mut buf = [0_u8; 256];
+    let mut received = Vec::new();
+    reader.read_to_end(&mut received).unwrap();
+    assert_eq!(received, data);
+    assert_eq!(reader.tee_error().unwrap().kind(), ErrorKind::Other);
+    assert_eq!(reader.read(&mut buf).unwrap(), 0);
+    let (_, tee) = reader.into_inner();
+    assert_eq!(&tee.written[..], &data[..tee.written.len()]);
+
+    let mut writer = TeeWriter::new(Vec::new(), Vec::new());
+    writer.write_all(&data).unwrap();
+    writer.flush().unwrap();
+    let (primary, secondary) = writer.into_inner();
+    assert_eq!(primary, data);
+    assert_eq!(secondary, data);
+
+    // errors from the secondary writer are recorded, not returned
+    let mut writer = TeeWriter::new(Vec::new(), FailingWriter { written: Vec::new(), limit: 300 });
+    for chunk in data.chunks(200) {
+        writer.write_all(chunk).unwrap();
+    }
+    assert_eq!(writer.secondary_error().unwrap().kind(), ErrorKind::Other);
+    assert_eq!(writer.get_ref(), &data);
+    assert_eq!(&writer.secondary_ref().written[..], &data[..200]);
+
+    // errors from the primary writer are returned
+    let mut writer = TeeWriter::new(FailingWriter { written: Vec::new(), limit: 300 }, Vec::new());
+    assert_eq!(writer.write_all(&data).unwrap_err().kind(), ErrorKind::Other);
+    let (primary, secondary) = writer.into_inner();
+    assert_eq!(primary.written, secondary);
+}
+
+pub fn test_io_error_sgx_status() {
+    fn ecall_like(status: sgx_status_t) -> std::io::Result<()> {
+        Err(status)?;
+        Ok(())
+    }
+
+    let err = ecall_like(sgx_status_t::SGX_ERROR_INVALID_PARAMETER).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert_eq!(err.raw_sgx_error(), Some(sgx_status_t::SGX_ERROR_INVALID_PARAMETER));
+
+    let kinds = [
+        (sgx_status_t::SGX_ERROR_UNEXPECTED, ErrorKind::Other),
+        (sgx_status_t::SGX_ERROR_OUT_OF_MEMORY, ErrorKind::OutOfMemory),
+        (sgx_status_t::SGX_ERROR_MAC_MISMATCH, ErrorKind::InvalidData),
+        (sgx_status_t::SGX_ERROR_BUSY, ErrorKind::ResourceBusy),
+        (sgx_status_t::SGX_ERROR_ENCLAVE_LOST, ErrorKind::SgxError),
+    ];
+    for &(status, kind) in kinds.iter() {
+        let err = Error::from(status);
+        assert_eq!(err.kind(), kind);
+        // the status itself survives the round trip
+        assert_eq!(sgx_status_t::try_from(err).unwrap(), status);
+    }
+
+    assert_eq!(
+        sgx_status_t::try_from(Error::from(ErrorKind::InvalidInput)).unwrap(),
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    );
+    assert_eq!(
+        sgx_status_t::try_from(Error::new(ErrorKind::OutOfMemory, "no space")).unwrap(),
+        sgx_status_t::SGX_ERROR_OUT_OF_MEMORY
+    );
+    let err = sgx_status_t::try_from(Error::from(ErrorKind::NotFound)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
@@ -0,0 +1,236 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use rustls::internal::pemfile;
+use rustls::{ProtocolVersion, Session};
+use std::io::{BufReader, ErrorKind, Read, Write};
+use std::net::{socketpair, IpFilter, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::string::ToString;
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::vec::Vec;
+
+// The certificate of the TLS sample server, valid for "localhost".
+const SERVER_CERT_CHAIN: &[u8] = include_bytes!("../../../tls/tlsserver/bin/end.fullchain");
+const SERVER_KEY: &[u8] = include_bytes!("../../../tls/tlsserver/bin/end.rsa");
+
+pub fn test_net_tcp_shutdown_write() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        // Only returns once the client has half-closed the connection.
+        let mut request = Vec::new();
+        stream.read_to_end(&mut request).unwrap();
+        stream.write_all(b"response").unwrap();
+        request
+    });
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"request").unwrap();
+    stream.shutdown(Shutdown::Write).unwrap();
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).unwrap();
+    assert_eq!(response, b"response");
+    assert_eq!(server.join().unwrap(), b"request");
+}
+
+pub fn test_net_socketpair() {
+    let (mut a, mut b) = socketpair().unwrap();
+    a.write_all(b"ping").unwrap();
+    let mut buf = [0_u8; 4];
+    b.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"ping");
+
+    b.write_all(b"pong").unwrap();
+    b.shutdown(Shutdown::Write).unwrap();
+    let mut reply = Vec::new();
+    a.read_to_end(&mut reply).unwrap();
+    assert_eq!(reply, b"pong");
+
+    // not a TCP socket
+    assert!(a.peer_addr().is_err());
+}
+
+// Accepts exactly the expected certificate, so that the test does not
+// depend on the expiry date of the sample certificate.
+struct PinnedServerCert(rustls::Certificate);
+
+impl rustls::ServerCertVerifier for PinnedServerCert {
+    fn verify_server_cert(
+        &self,
+        _roots: &rustls::RootCertStore,
+        presented_certs: &[rustls::Certificate],
+        _dns_name: webpki::DNSNameRef,
+        _ocsp_response: &[u8],
+    ) -> Result<rustls::ServerCertVerified, rustls::TLSError> {
+        match presented_certs.first() {
+            Some(cert) if *cert == self.0 => Ok(rustls::ServerCertVerified::assertion()),
+            _ => Err(rustls::TLSError::General("unexpected server certificate".to_string())),
+        }
+    }
+}
+
+// A server offering both TLS 1.3 and TLS 1.2 and the ALPN protocols
+// `server_alpn`, and a client offering `client_versions` and `client_alpn`.
+fn tls_configs(
+    client_versions: &[ProtocolVersion],
+    server_alpn: &[&[u8]],
+    client_alpn: &[&[u8]],
+) -> (rustls::ServerConfig, rustls::ClientConfig) {
+    let certs = pemfile::certs(&mut BufReader::new(SERVER_CERT_CHAIN)).unwrap();
+    let key = pemfile::rsa_private_keys(&mut BufReader::new(SERVER_KEY))
+        .unwrap()
+        .remove(0);
+    let pinned = PinnedServerCert(certs[0].clone());
+
+    let mut server_config = rustls::ServerConfig::new(rustls::NoClientAuth::new());
+    server_config.versions = vec![ProtocolVersion::TLSv1_3, ProtocolVersion::TLSv1_2];
+    server_config
+        .set_single_cert_with_ocsp_and_sct(certs, key, Vec::new(), Vec::new())
+        .unwrap();
+    server_config.alpn_protocols = server_alpn.iter().map(|p| p.to_vec()).collect();
+    let mut client_config = rustls::ClientConfig::new();
+    client_config.versions = client_versions.to_vec();
+    client_config
+        .dangerous()
+        .set_certificate_verifier(Arc::new(pinned));
+    client_config.alpn_protocols = client_alpn.iter().map(|p| p.to_vec()).collect();
+    (server_config, client_config)
+}
+
+// Exchanges a ping and a pong over a socketpair and returns both ends of
+// the connection.
+fn tls_ping_pong(
+    server_config: rustls::ServerConfig,
+    client_config: rustls::ClientConfig,
+) -> (rustls::ClientSession, rustls::ServerSession) {
+    let (mut client_sock, mut server_sock) = socketpair().unwrap();
+
+    let server = thread::spawn(move || {
+        let mut sess = rustls::ServerSession::new(&Arc::new(server_config));
+        let mut request = [0_u8; 4];
+        {
+            let mut tls = rustls::Stream::new(&mut sess, &mut server_sock);
+            tls.read_exact(&mut request).unwrap();
+            tls.write_all(b"pong").unwrap();
+        }
+        assert_eq!(&request, b"ping");
+        sess
+    });
+
+    let dns_name = webpki::DNSNameRef::try_from_ascii_str("localhost").unwrap();
+    let mut sess = rustls::ClientSession::new(&Arc::new(client_config), dns_name);
+    let mut response = [0_u8; 4];
+    {
+        let mut tls = rustls::Stream::new(&mut sess, &mut client_sock);
+        tls.write_all(b"ping").unwrap();
+        tls.read_exact(&mut response).unwrap();
+    }
+    assert_eq!(&response, b"pong");
+
+    (sess, server.join().unwrap())
+}
+
+pub fn test_net_socketpair_tls() {
+    let (server_config, client_config) = tls_configs(&[ProtocolVersion::TLSv1_2], &[], &[]);
+    let (client, server) = tls_ping_pong(server_config, client_config);
+    assert_eq!(client.get_protocol_version(), Some(ProtocolVersion::TLSv1_2));
+    assert_eq!(server.get_protocol_version(), Some(ProtocolVersion::TLSv1_2));
+    assert_eq!(client.get_alpn_protocol(), None);
+}
+
+pub fn test_net_socketpair_tls13() {
+    let versions = [ProtocolVersion::TLSv1_3, ProtocolVersion::TLSv1_2];
+    let (server_config, client_config) = tls_configs(&versions, &[], &[]);
+    let (client, server) = tls_ping_pong(server_config, client_config);
+    assert_eq!(client.get_protocol_version(), Some(ProtocolVersion::TLSv1_3));
+    assert_eq!(server.get_protocol_version(), Some(ProtocolVersion::TLSv1_3));
+}
+
+pub fn test_net_socketpair_tls_alpn() {
+    let tls13 = [ProtocolVersion::TLSv1_3];
+    let server_alpn: &[&[u8]] = &[b"h2", b"http/1.1"];
+
+    // the client only speaks HTTP/1.1, although the server prefers h2
+    let (server_config, client_config) = tls_configs(&tls13, server_alpn, &[b"http/1.1"]);
+    let (client, server) = tls_ping_pong(server_config, client_config);
+    assert_eq!(client.get_alpn_protocol(), Some(&b"http/1.1"[..]));
+    assert_eq!(server.get_alpn_protocol(), Some(&b"http/1.1"[..]));
+
+    let (server_config, client_config) =
+        tls_configs(&tls13, server_alpn, &[b"h2", b"http/1.1"]);
+    let (client, _) = tls_ping_pong(server_config, client_config);
+    assert_eq!(client.get_alpn_protocol(), Some(&b"h2"[..]));
+
+    // without ALPN on the client, none is negotiated
+    let (server_config, client_config) = tls_configs(&tls13, server_alpn, &[]);
+    let (client, server) = tls_ping_pong(server_config, client_config);
+    assert_eq!(client.get_alpn_protocol(), None);
+    assert_eq!(server.get_alpn_protocol(), None);
+}
+
+pub fn test_net_ip_filter() {
+    static FILTER: OnceLock<IpFilter> = OnceLock::new();
+    let filter = FILTER.get_or_init(|| {
+        IpFilter::new()
+            .allow("*.example.com", "203.0.113.0/24", Some(443))
+            .unwrap()
+            .allow("db.internal", "10.1.0.0/16", None)
+            .unwrap()
+            .allow("DB.Internal", "2001:db8::/32", Some(5432))
+            .unwrap()
+    });
+    let addr = |s: &str| s.parse::<SocketAddr>().unwrap();
+
+    assert!(filter.check("api.example.com", &addr("203.0.113.7:443")).is_ok());
+    assert!(filter.check("a.b.EXAMPLE.com.", &addr("203.0.113.255:443")).is_ok());
+    assert!(filter.check("db.internal", &addr("10.1.200.3:1")).is_ok());
+    assert!(filter.check("db.internal", &addr("[::ffff:10.1.0.1]:5432")).is_ok());
+    assert!(filter.check("db.internal", &addr("[2001:db8::1]:5432")).is_ok());
+
+    // outside the allowlist: address, port or host name
+    let violation = filter.check("api.example.com", &addr("198.51.100.7:443")).unwrap_err();
+    assert_eq!(violation.hostname(), "api.example.com");
+    assert_eq!(violation.addr(), addr("198.51.100.7:443"));
+    assert!(filter.check("api.example.com", &addr("203.0.112.255:443")).is_err());
+    assert!(filter.check("api.example.com", &addr("203.0.113.7:80")).is_err());
+    assert!(filter.check("example.com", &addr("203.0.113.7:443")).is_err());
+    assert!(filter.check("evilexample.com", &addr("203.0.113.7:443")).is_err());
+    assert!(filter.check("db.internal", &addr("10.2.0.1:5432")).is_err());
+    assert!(filter.check("db.internal", &addr("[2001:db9::1]:5432")).is_err());
+    assert!(filter.check("db.internal", &addr("[2001:db8::1]:22")).is_err());
+    assert!(IpFilter::new().check("localhost", &addr("127.0.0.1:80")).is_err());
+
+    for &(pattern, range) in [("", "10.0.0.0/8"), ("*.", "10.0.0.0/8"), ("a.*.com", "10.0.0.0/8"),
+        ("x", "10.0.0.0/33"), ("x", "::/129"), ("x", "10.0.0/8"), ("x", "host")].iter()
+    {
+        assert_eq!(IpFilter::new().allow(pattern, range, None).unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+
+    // connect only reaches allowed addresses
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let denied = IpFilter::new().allow("localhost", "10.0.0.0/8", None).unwrap();
+    assert_eq!(denied.connect("127.0.0.1", port).unwrap_err().kind(), ErrorKind::PermissionDenied);
+    let allowed = IpFilter::new().allow("127.0.0.1", "127.0.0.0/8", Some(port)).unwrap();
+    let stream = allowed.connect("127.0.0.1", port).unwrap();
+    assert_eq!(stream.peer_addr().unwrap(), addr(&format!("127.0.0.1:{}", port)));
+    listener.accept().unwrap();
+}
@@ -0,0 +1,122 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use sgx_dcap::QuoteError;
+use sgx_ra_tls::*;
+use sgx_tcrypto::ecc::EcKeyPair;
+use sgx_types::*;
+use std::vec::Vec;
+
+// Builds a version 3 ECDSA quote carrying `report_data`.
+fn build_dcap_quote(report_data: &sgx_report_data_t) -> Vec<u8> {
+    let mut quote = Vec::new();
+    quote.extend_from_slice(&3_u16.to_le_bytes());
+    quote.extend_from_slice(&2_u16.to_le_bytes());
+    quote.extend_from_slice(&[0; 44]);
+    let mut body = [0_u8; 384];
+    body[320..].copy_from_slice(&report_data.d);
+    quote.extend_from_slice(&body);
+
+    let chain = b"-----BEGIN CERTIFICATE-----";
+    let mut sig = Vec::new();
+    sig.extend_from_slice(&[0x55; 128]);
+    sig.extend_from_slice(&[0; 384]);
+    sig.extend_from_slice(&[0x44; 64]);
+    sig.extend_from_slice(&0_u16.to_le_bytes());
+    sig.extend_from_slice(&5_u16.to_le_bytes());
+    sig.extend_from_slice(&(chain.len() as u32).to_le_bytes());
+    sig.extend_from_slice(chain);
+    quote.extend_from_slice(&(sig.len() as u32).to_le_bytes());
+    quote.extend_from_slice(&sig);
+    quote
+}
+
+// Builds a version 2 EPID quote carrying `report_data`.
+fn build_epid_quote(report_data: &sgx_report_data_t) -> Vec<u8> {
+    let mut quote = Vec::new();
+    quote.extend_from_slice(&2_u16.to_le_bytes());
+    quote.extend_from_slice(&[0; 46]);
+    let mut body = [0_u8; 384];
+    body[320..].copy_from_slice(&report_data.d);
+    quote.extend_from_slice(&body);
+    quote.extend_from_slice(&8_u32.to_le_bytes());
+    quote.extend_from_slice(&[0x77; 8]);
+    quote
+}
+
+pub fn test_ra_tls_cert() {
+    let key = EcKeyPair::generate().unwrap();
+    let mut report_data = RaTlsCert::report_data(key.public_key()).unwrap();
+    assert!(report_data.d[32..].iter().all(|&b| b == 0));
+    report_data.d[63] = 0x99;
+
+    let collateral = Collateral {
+        tcb_info: b"{\"tcbInfo\":{}}\0",
+        tcb_info_issuer_chain: b"-----BEGIN CERTIFICATE-----\0",
+        root_ca_crl: &[0x30; 300],
+        ..Collateral::default()
+    };
+    for quote in [build_dcap_quote(&report_data), build_epid_quote(&report_data)].iter() {
+        let cert = RaTlsCert::generate(&key, quote, &collateral).unwrap();
+        let verified = RaTlsCert::verify(cert.as_bytes()).unwrap();
+        assert_eq!(verified.quote(), &quote[..]);
+        assert_eq!(verified.version(), quote[0] as u16);
+        assert_eq!(verified.report_data().d, report_data.d);
+        assert_eq!(verified.public_key().gx, key.public_key().gx);
+        assert_eq!(verified.public_key().gy, key.public_key().gy);
+        assert_eq!(verified.collateral().tcb_info, collateral.tcb_info);
+        assert_eq!(verified.collateral().root_ca_crl, collateral.root_ca_crl);
+        assert_eq!(
+            verified.collateral().qe_identity_issuer_chain,
+            collateral.tcb_info_issuer_chain
+        );
+        assert!(verified.collateral().pck_crl.is_empty());
+    }
+}
+
+pub fn test_ra_tls_cert_errors() {
+    let key = EcKeyPair::generate().unwrap();
+    let other = EcKeyPair::generate().unwrap();
+    let report_data = RaTlsCert::report_data(key.public_key()).unwrap();
+    let quote = build_dcap_quote(&report_data);
+
+    assert_eq!(
+        RaTlsCert::generate(&other, &quote, &Collateral::default()).unwrap_err(),
+        RaTlsError::KeyMismatch
+    );
+    assert_eq!(
+        RaTlsCert::generate(&key, &quote[..quote.len() - 1], &Collateral::default()).unwrap_err(),
+        RaTlsError::InvalidQuote(QuoteError::TooShort)
+    );
+
+    let cert = RaTlsCert::generate(&key, &quote, &Collateral::default())
+        .unwrap()
+        .into_vec();
+    assert_eq!(
+        RaTlsCert::verify(&cert[..cert.len() - 1]).unwrap_err(),
+        RaTlsError::InvalidCertificate
+    );
+    let mut trailing = cert.clone();
+    trailing.push(0);
+    assert_eq!(RaTlsCert::verify(&trailing).unwrap_err(), RaTlsError::InvalidCertificate);
+
+    // a byte of the report body inside the quote extension
+    let mut tampered = cert.clone();
+    let offset = cert.windows(quote.len()).position(|w| w == &quote[..]).unwrap();
+    tampered[offset + 100] ^= 1;
+    assert_eq!(RaTlsCert::verify(&tampered).unwrap_err(), RaTlsError::InvalidSignature);
+}
@@ -16,6 +16,8 @@
 // under the License..
 
 use sgx_rand::*;
+use std::string::String;
+use std::vec::Vec;
 
 // pub use os::SgxRng
 pub fn test_rand_os_sgxrng() {
@@ -36,6 +38,34 @@ pub fn test_rand_os_sgxrng() {
     assert_ne!(cmp, true);
 }
 
+// An entropy source that is stuck at a constant value.
+struct StuckRng(u8);
+
+impl Rng for StuckRng {
+    fn next_u32(&mut self) -> u32 {
+        u32::from(self.0) * 0x0101_0101
+    }
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for b in dest.iter_mut() {
+            *b = self.0;
+        }
+    }
+}
+
+pub fn test_rand_fill_bytes_verified() {
+    let mut os_rng = SgxRng::new().unwrap();
+    let mut key = [0_u8; 32];
+    os_rng.fill_bytes_verified(&mut key).unwrap();
+    assert!(key.iter().any(|b| *b != 0));
+    os_rng.fill_bytes_fast(&mut key);
+    assert!(os_rng.fill_bytes_verified(&mut []).is_ok());
+
+    let mut large = vec![0_u8; 1000];
+    assert_eq!(fill_bytes_verified(&mut StuckRng(0), &mut large), Err(EntropyDegradedError));
+    assert_eq!(fill_bytes_verified(&mut StuckRng(0xa5), &mut key), Err(EntropyDegradedError));
+    assert!(fill_bytes_verified(&mut XorShiftRng::new_unseeded(), &mut large).is_ok());
+}
+
 // pub mod distribution
 // Too hard to test
 pub fn test_rand_distributions() {
@@ -127,7 +157,7 @@ pub fn test_rand_distributions() {
         f.ind_sample(&mut rng);
     }
 
-    let mut t = StudentT::new(11.0);
+    let mut t = StudentT::new(11.0).unwrap();
     for _ in 0..1000 {
         t.sample(&mut rng);
         t.ind_sample(&mut rng);
@@ -718,6 +748,113 @@ pub fn test_rand_chacharng() {
 }
 
 // reseeding.rs
+pub fn test_rand_pcgrng() {
+    // the first outputs of the reference pcg32-demo, seeded with (42, 54)
+    let mut pcg = PcgRng::from_seed([42, 54]);
+    let outputs: Vec<u32> = (0..6).map(|_| pcg.next_u32()).collect();
+    assert_eq!(outputs, [0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e]);
+
+    // reseeding restarts the stream
+    pcg.reseed([42, 54]);
+    assert_eq!(pcg.next_u32(), 0xa15c02b7);
+
+    // new_unseeded uses the reference PCG32_INITIALIZER
+    let mut unseeded = PcgRng::new_unseeded();
+    assert_eq!(unseeded.next_u32(), 0x152ca78d);
+    assert_eq!(unseeded.next_u32(), 0x027c6003);
+
+    // different streams from the same initial state diverge
+    let mut a = PcgRng::from_seed([1, 1]);
+    let mut b = PcgRng::from_seed([1, 2]);
+    assert!((0..8).any(|_| a.next_u32() != b.next_u32()));
+
+    should_panic!(PcgRng::from_seed([0, 0]));
+    should_panic!(PcgRng::new_unseeded().reseed([0, 0]));
+
+    let mut seeded: PcgRng = os::SgxRng::new().unwrap().gen();
+    let mut other: PcgRng = os::SgxRng::new().unwrap().gen();
+    assert!((0..8).any(|_| seeded.next_u32() != other.next_u32()));
+
+    // chi-squared test of the high and low bytes over 256 buckets; the
+    // critical value for 255 degrees of freedom at p = 0.001 is 330.52
+    const EXPECTED: usize = 1000;
+    let mut high = [0_usize; 256];
+    let mut low = [0_usize; 256];
+    let mut pcg = PcgRng::from_seed([42, 54]);
+    for _ in 0..256 * EXPECTED {
+        let x = pcg.next_u32();
+        high[(x >> 24) as usize] += 1;
+        low[(x & 0xff) as usize] += 1;
+    }
+    let chi_squared = |counts: &[usize]| {
+        counts
+            .iter()
+            .map(|&n| {
+                let d = n as f64 - EXPECTED as f64;
+                d * d / EXPECTED as f64
+            })
+            .sum::<f64>()
+    };
+    assert!(chi_squared(&high) < 330.52);
+    assert!(chi_squared(&low) < 330.52);
+}
+
+pub fn test_rand_zeroize() {
+    use sgx_rand::zeroize::{ZeroingArray, ZeroingVec};
+    use std::mem::ManuallyDrop;
+    use std::ptr;
+
+    // Dropping in place keeps the storage alive, so it can be inspected.
+    let mut seed = ManuallyDrop::new(ZeroingArray::new([0xa5_u8; 32]));
+    let storage = seed.as_ptr();
+    unsafe { ptr::drop_in_place(&mut *seed) };
+    let bytes = unsafe { std::slice::from_raw_parts(storage, 32) };
+    assert!(bytes.iter().all(|&b| b == 0));
+
+    let mut seed = ZeroingVec::from_vec(vec![0x5a_u64; 16]);
+    seed.zeroize();
+    assert_eq!(seed.len(), 16);
+    assert!(seed.iter().all(|&w| w == 0));
+    assert_eq!(format!("{:?}", seed), "ZeroingVec { len: 16 }");
+
+    // Seeding through the wrappers matches seeding with the raw values.
+    let words = [1_u32, 2, 3, 4, 5, 6, 7, 8];
+    let mut expected = IsaacRng::from_seed(&words[..]);
+    let mut rng = IsaacRng::from_seed(&ZeroingArray::new(words));
+    assert_eq!(rng.next_u32(), expected.next_u32());
+    rng.reseed(&ZeroingArray::new(words));
+    expected.reseed(&words[..]);
+    assert_eq!(rng.next_u32(), expected.next_u32());
+
+    let mut expected = ChaChaRng::from_seed(&words[..]);
+    let mut rng = ChaChaRng::from_seed(&ZeroingVec::from_vec(words.to_vec()));
+    assert_eq!(rng.next_u32(), expected.next_u32());
+
+    let mut expected = Isaac64Rng::from_seed(&[9_u64, 8, 7][..]);
+    let mut rng = Isaac64Rng::from_seed(&ZeroingArray::new([9_u64, 8, 7]));
+    assert_eq!(rng.next_u64(), expected.next_u64());
+
+    let mut expected: StdRng = SeedableRng::from_seed(&[1_usize, 2][..]);
+    let seed = ZeroingArray::new([1_usize, 2]);
+    let mut rng: StdRng = SeedableRng::from_seed(&seed[..]);
+    assert_eq!(rng.next_u64(), expected.next_u64());
+
+    let mut expected = PcgRng::from_seed([42, 54]);
+    let mut rng = PcgRng::from_seed(ZeroingArray::new([42_u64, 54]));
+    assert_eq!(rng.next_u32(), expected.next_u32());
+    rng.reseed(ZeroingArray::new([42_u64, 54]));
+    assert_eq!(rng.next_u32(), 0xa15c02b7);
+
+    let mut sgx_rng = os::SgxRng::new().unwrap();
+    let seed: ZeroingArray<u32, 4> = ZeroingArray::from_rng(&mut sgx_rng);
+    let words = [seed[0], seed[1], seed[2], seed[3]];
+    let mut a = XorShiftRng::from_seed(ZeroingArray::new(words));
+    let mut b = XorShiftRng::from_seed(words);
+    assert_eq!(a.next_u32(), b.next_u32());
+    let seed: ZeroingVec<u32> = ZeroingVec::from_rng(&mut sgx_rng, 256);
+    let _ = IsaacRng::from_seed(&seed);
+}
+
 pub fn test_rand_reseeding() {
     use std::prelude::v1::*;
 
@@ -792,5 +929,421 @@ pub fn test_rand_reseeding() {
     assert!(sum / v.len() as f64 != 0.0);
 }
 
+pub fn test_rand_reseed_strategy() {
+    use sgx_rand::reseeding::{ReseedStrategy, Reseeder, ReseedingRng};
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::thread;
+    use std::time::Duration;
+
+    struct CountingReseeder(Rc<Cell<u32>>);
+
+    impl Reseeder<XorShiftRng> for CountingReseeder {
+        fn reseed(&mut self, _rng: &mut XorShiftRng) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    fn counting(strategy: ReseedStrategy) -> (ReseedingRng<XorShiftRng, CountingReseeder>, Rc<Cell<u32>>) {
+        let count = Rc::new(Cell::new(0));
+        let rng = ReseedingRng::new(XorShiftRng::new_unseeded(), strategy, CountingReseeder(count.clone()));
+        (rng, count)
+    }
+
+    // a threshold of 32 bytes is crossed every 8 u32s
+    let (mut rng, count) = counting(ReseedStrategy::ByteThreshold(32));
+    for _ in 0..80 {
+        rng.next_u32();
+    }
+    assert_eq!(count.get(), 9);
+    let mut buf = [0_u8; 100];
+    rng.fill_bytes(&mut buf);
+    rng.fill_bytes(&mut buf);
+    assert_eq!(count.get(), 11);
+
+    // a u64 converts into a byte threshold
+    assert_eq!(ReseedStrategy::from(400), ReseedStrategy::ByteThreshold(400));
+
+    // calls are counted regardless of their size
+    let (mut rng, count) = counting(ReseedStrategy::GenerationCount(10));
+    for _ in 0..50 {
+        rng.next_u64();
+    }
+    assert_eq!(count.get(), 4);
+    let mut buf = [0_u8; 4096];
+    for _ in 0..10 {
+        rng.fill_bytes(&mut buf);
+    }
+    assert_eq!(count.get(), 5);
+
+    let (mut rng, count) = counting(ReseedStrategy::TimedInterval(Duration::from_millis(50)));
+    for _ in 0..1000 {
+        rng.next_u32();
+    }
+    assert_eq!(count.get(), 0);
+    thread::sleep(Duration::from_millis(60));
+    rng.next_u32();
+    assert_eq!(count.get(), 1);
+    rng.next_u32();
+    assert_eq!(count.get(), 1);
+
+    // whichever condition is met first triggers the reseed
+    let (mut rng, count) = counting(ReseedStrategy::Combined(vec![
+        ReseedStrategy::ByteThreshold(64),
+        ReseedStrategy::GenerationCount(4),
+    ]));
+    for _ in 0..40 {
+        rng.next_u32();
+    }
+    assert_eq!(count.get(), 9);
+    for _ in 0..8 {
+        rng.fill_bytes(&mut buf[..64]);
+    }
+    assert_eq!(count.get(), 17);
+
+    let (mut rng, count) = counting(ReseedStrategy::Combined(Vec::new()));
+    for _ in 0..1000 {
+        rng.next_u64();
+    }
+    assert_eq!(count.get(), 0);
+}
+
+pub fn test_rand_hypergeometric() {
+    use sgx_rand::distributions::{HyperGeometric, HyperGeometricError, IndependentSample};
+
+    assert_eq!(HyperGeometric::new(10, 11, 5).unwrap_err(), HyperGeometricError::TooManySuccesses);
+    assert_eq!(HyperGeometric::new(10, 5, 11).unwrap_err(), HyperGeometricError::TooManyDraws);
+
+    let mut rng = thread_rng();
+
+    // hearts in a five card hand
+    let pmf = [
+        0.22153361344537814,
+        0.41141956782713085,
+        0.2742797118847539,
+        0.08154261704681873,
+        0.010729291716686675,
+        0.0004951980792316927,
+    ];
+    let hearts = HyperGeometric::new(52, 13, 5).unwrap();
+    let n = 100_000;
+    let mut counts = [0_u32; 6];
+    for _ in 0..n {
+        counts[hearts.ind_sample(&mut rng) as usize] += 1;
+    }
+    for (count, p) in counts.iter().zip(pmf.iter()) {
+        assert!((*count as f64 / n as f64 - p).abs() < 0.01);
+    }
+
+    let params: &[(u64, u64, u64)] = &[
+        (52, 13, 5),
+        (20, 15, 12),
+        (1000, 400, 300),
+        (100_000, 30_000, 50_000),
+        (1_000_000, 999_000, 1000),
+    ];
+    for &(population, successes, draws) in params {
+        let dist = HyperGeometric::new(population, successes, draws).unwrap();
+        let lo = (successes + draws).saturating_sub(population);
+        let hi = successes.min(draws);
+        let mut sum = 0_f64;
+        for _ in 0..n {
+            let v = dist.ind_sample(&mut rng);
+            assert!(lo <= v && v <= hi);
+            sum += v as f64;
+        }
+        let mean = draws as f64 * successes as f64 / population as f64;
+        assert!((sum / n as f64 - mean).abs() < mean * 0.01);
+    }
+
+    let all = HyperGeometric::new(10, 10, 10).unwrap();
+    assert_eq!(all.ind_sample(&mut rng), 10);
+    let none = HyperGeometric::new(10, 0, 10).unwrap();
+    assert_eq!(none.ind_sample(&mut rng), 0);
+}
+
+pub fn test_rand_exponential_truncated() {
+    use sgx_rand::distributions::{ExponentialTruncated, ExponentialTruncatedError, IndependentSample};
+
+    assert_eq!(ExponentialTruncated::new(0.0, 1.0).unwrap_err(), ExponentialTruncatedError::InvalidLambda);
+    assert_eq!(ExponentialTruncated::new(std::f64::NAN, 1.0).unwrap_err(), ExponentialTruncatedError::InvalidLambda);
+    assert_eq!(ExponentialTruncated::new(1.0, -1.0).unwrap_err(), ExponentialTruncatedError::InvalidUpper);
+    assert_eq!(ExponentialTruncated::new(1.0, std::f64::NAN).unwrap_err(), ExponentialTruncatedError::InvalidUpper);
+
+    let mut rng = thread_rng();
+    let n = 100_000;
+    for &(lambda, upper) in [(1.0_f64, 2.0_f64), (0.1, 5.0), (50.0, 1e-3)].iter() {
+        let dist = ExponentialTruncated::new(lambda, upper).unwrap();
+        let mean = 1.0 / lambda - upper * (-lambda * upper).exp() / (1.0 - (-lambda * upper).exp());
+        assert!((dist.mean() - mean).abs() < 1e-9 * mean);
+
+        let mut sum = 0.0;
+        for _ in 0..n {
+            let v = dist.ind_sample(&mut rng);
+            assert!(v >= 0.0 && v <= upper);
+            sum += v;
+        }
+        assert!((sum / n as f64 - mean).abs() < 0.01 * mean);
+    }
+
+    let unbounded = ExponentialTruncated::new(2.0, std::f64::INFINITY).unwrap();
+    assert_eq!(unbounded.mean(), 0.5);
+}
+
+pub fn test_rand_student_t() {
+    use sgx_rand::distributions::{IndependentSample, StudentT, TDistError};
+    use std::f64::consts::PI;
+
+    assert_eq!(StudentT::new(0.0).unwrap_err(), TDistError::InvalidDegreesOfFreedom);
+    assert_eq!(StudentT::new(-1.0).unwrap_err(), TDistError::InvalidDegreesOfFreedom);
+    assert_eq!(StudentT::new(std::f64::NAN).unwrap_err(), TDistError::InvalidDegreesOfFreedom);
+    assert_eq!(StudentT::new(std::f64::INFINITY).unwrap_err(), TDistError::InvalidDegreesOfFreedom);
+
+    let mut rng = thread_rng();
+    let n = 100_000;
+
+    // df = 1 is the Cauchy distribution: F(x) = 1/2 + atan(x) / pi, with
+    // quartiles at -1 and 1.
+    let cauchy = StudentT::new(1.0).unwrap();
+    for &x in [-100.0_f64, -3.0, -1.0, -0.25, 0.0, 0.5, 1.0, 7.0, 1e4].iter() {
+        assert!((cauchy.cdf(x) - (0.5 + x.atan() / PI)).abs() < 1e-12);
+    }
+    let mut samples: Vec<f64> = (0..n).map(|_| cauchy.ind_sample(&mut rng)).collect();
+    samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert!(samples[n / 2].abs() < 0.05);
+    assert!((samples[n / 4] + 1.0).abs() < 0.05);
+    assert!((samples[3 * n / 4] - 1.0).abs() < 0.05);
+
+    assert_eq!(cauchy.cdf(std::f64::INFINITY), 1.0);
+    assert_eq!(cauchy.cdf(std::f64::NEG_INFINITY), 0.0);
+    assert!(cauchy.cdf(std::f64::NAN).is_nan());
+
+    // Reference values of the t(3) and t(10) distribution functions.
+    assert!((StudentT::new(3.0).unwrap().cdf(2.0) - 0.930_337_015_720_6).abs() < 1e-10);
+    assert!((StudentT::new(10.0).unwrap().cdf(-1.5) - 0.082_253_663_222_7).abs() < 1e-10);
+
+    // For large df, t approaches the standard normal distribution.
+    for &df in [30.0_f64, 100.0, 1000.0].iter() {
+        let t = StudentT::new(df).unwrap();
+        let tol = 0.15 / df;
+        assert!((t.cdf(0.0) - 0.5).abs() < 1e-12);
+        assert!((t.cdf(1.0) - 0.841_344_746_068_543).abs() < tol);
+        assert!((t.cdf(-1.959_963_984_540_054) - 0.025).abs() < tol);
+
+        let inside = (0..n)
+            .filter(|_| t.ind_sample(&mut rng).abs() < 1.959_963_984_540_054)
+            .count();
+        assert!((inside as f64 / n as f64 - 0.95).abs() < 0.015);
+    }
+
+    // For df > 2, the mean is 0 and the variance is df / (df - 2).
+    for &df in [5.0_f64, 10.0, 40.0].iter() {
+        let t = StudentT::new(df).unwrap();
+        let mut sum = 0.0;
+        let mut sum_sq = 0.0;
+        for _ in 0..n {
+            let v = t.ind_sample(&mut rng);
+            sum += v;
+            sum_sq += v * v;
+        }
+        let mean = sum / n as f64;
+        let var = sum_sq / n as f64 - mean * mean;
+        let expected = df / (df - 2.0);
+        assert!(mean.abs() < 0.03);
+        assert!((var - expected).abs() < 0.1 * expected);
+    }
+}
+
+pub fn test_rand_beta() {
+    use sgx_rand::distributions::{Beta, BetaError, IndependentSample};
+
+    assert_eq!(Beta::new(0.0, 1.0).unwrap_err(), BetaError::InvalidAlpha);
+    assert_eq!(Beta::new(-1.0, 1.0).unwrap_err(), BetaError::InvalidAlpha);
+    assert_eq!(Beta::new(f64::NAN, 1.0).unwrap_err(), BetaError::InvalidAlpha);
+    assert_eq!(Beta::new(1.0, 0.0).unwrap_err(), BetaError::InvalidBeta);
+    assert_eq!(Beta::new(1.0, f64::INFINITY).unwrap_err(), BetaError::InvalidBeta);
+
+    let mut rng = os::SgxRng::new().unwrap();
+    let n = 100_000;
+
+    // The mean is a / (a + b) and the variance a * b / ((a + b)^2 (a + b + 1)).
+    // Shapes below 1 exercise the small shape path of Gamma.
+    for &(a, b) in [(2.0_f64, 5.0_f64), (0.5, 0.5), (0.3, 3.0), (20.0, 20.0)].iter() {
+        let beta = Beta::new(a, b).unwrap();
+        let mut sum = 0.0;
+        let mut sum_sq = 0.0;
+        for _ in 0..n {
+            let v = beta.ind_sample(&mut rng);
+            assert!(v >= 0.0 && v <= 1.0);
+            sum += v;
+            sum_sq += v * v;
+        }
+        let mean = sum / n as f64;
+        let var = sum_sq / n as f64 - mean * mean;
+        let expected_mean = a / (a + b);
+        let expected_var = a * b / ((a + b) * (a + b) * (a + b + 1.0));
+        assert!((mean - expected_mean).abs() < 0.01);
+        assert!((var - expected_var).abs() < 0.05 * expected_var);
+    }
+
+    // Tiny shapes put almost all the mass at 0 and 1, where both gamma
+    // variates can underflow; samples stay in range and balanced.
+    let beta = Beta::new(1e-3, 1e-3).unwrap();
+    let mut sum = 0.0;
+    for _ in 0..n {
+        let v = beta.ind_sample(&mut rng);
+        assert!(v >= 0.0 && v <= 1.0);
+        sum += v;
+    }
+    assert!((sum / n as f64 - 0.5).abs() < 0.02);
+}
+
+pub fn test_rand_weighted() {
+    use sgx_rand::distributions::{FloatWeightedChoice, IndependentSample, WeightedError};
+
+    assert_eq!(FloatWeightedChoice::<u32>::new(&[]).unwrap_err(), WeightedError::NoItem);
+    assert_eq!(FloatWeightedChoice::new(&[(0, 1.0), (1, -1.0)]).unwrap_err(), WeightedError::InvalidWeight);
+    assert_eq!(FloatWeightedChoice::new(&[(0, f64::NAN)]).unwrap_err(), WeightedError::InvalidWeight);
+    assert_eq!(FloatWeightedChoice::new(&[(0, f64::INFINITY)]).unwrap_err(), WeightedError::InvalidWeight);
+    assert_eq!(FloatWeightedChoice::new(&[(0, 0.0), (1, 0.0)]).unwrap_err(), WeightedError::AllWeightsZero);
+    assert_eq!(FloatWeightedChoice::new(&[(0, f64::MAX), (1, f64::MAX)]).unwrap_err(), WeightedError::TooLargeTotal);
+
+    let mut rng = os::SgxRng::new().unwrap();
+    let n = 70_000;
+
+    // zero weights at either end and in the middle are never chosen
+    let wc = FloatWeightedChoice::new(&[(0_usize, 0.0), (1, 0.5), (2, 0.0), (3, 1.0), (4, 0.25), (5, 0.0)]).unwrap();
+    assert_eq!(wc.total_weight(), 1.75);
+    let mut counts = [0_usize; 6];
+    for _ in 0..n {
+        counts[wc.ind_sample(&mut rng)] += 1;
+    }
+    assert_eq!(counts[0] + counts[2] + counts[5], 0);
+    for &(i, expected) in [(1, 20_000), (3, 40_000), (4, 10_000)].iter() {
+        assert!((counts[i] as isize - expected).abs() < 1_000);
+    }
+
+    assert_eq!(weighted_sample(&mut rng, 0..10, |_| -1.0, 3).unwrap_err(), WeightedError::InvalidWeight);
+    assert_eq!(weighted_sample(&mut rng, 0..10, |_| f64::NAN, 3).unwrap_err(), WeightedError::InvalidWeight);
+    assert!(weighted_sample(&mut rng, 0..10, |_| 1.0, 0).unwrap().is_empty());
+    // asking for more than there are does not reserve memory for them
+    assert_eq!(weighted_sample(&mut rng, 0..10, |_| 1.0, usize::MAX).unwrap().len(), 10);
+
+    // only elements with a positive weight are returned, each at most once
+    let mut v = weighted_sample(&mut rng, 0..100, |&x| (x % 2) as f64, 80).unwrap();
+    v.sort();
+    assert_eq!(v, (0..100).filter(|x| x % 2 == 1).collect::<Vec<_>>());
+
+    let mut v = weighted_sample(&mut rng, 0..100, |&x| x as f64, 10).unwrap();
+    assert_eq!(v.len(), 10);
+    v.sort();
+    v.dedup();
+    assert_eq!(v.len(), 10);
+    assert!(!v.contains(&0));
+
+    // a single draw picks an element in proportion to its weight
+    let mut hits = 0;
+    for _ in 0..n {
+        if weighted_sample(&mut rng, [1.0, 3.0].iter(), |&&x| x, 1).unwrap() == [&3.0] {
+            hits += 1;
+        }
+    }
+    assert!((hits as f64 / n as f64 - 0.75).abs() < 0.01);
+}
+
+pub fn test_rand_gen_strings() {
+    let mut rng = os::SgxRng::new().unwrap();
+
+    let s = rng.gen_alphabetic_string(64);
+    assert_eq!(s.len(), 64);
+    assert!(s.bytes().all(|c| c.is_ascii_alphabetic()));
+    let s = rng.gen_alphanumeric_string(64);
+    assert_eq!(s.len(), 64);
+    assert!(s.bytes().all(|c| c.is_ascii_alphanumeric()));
+    let s = rng.gen_urlsafe_string(64);
+    assert_eq!(s.len(), 64);
+    assert!(s
+        .bytes()
+        .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'));
+    if cfg!(debug_assertions) {
+        should_panic!(os::SgxRng::new().unwrap().gen_alphabetic_string(0));
+    }
+
+    // Chi-squared test on the first character of 10,000 strings. The bounds
+    // are far beyond the 99.99% quantile for 51, 61 and 63 degrees of freedom.
+    fn chi_squared(strings: &[String], charset: &[u8]) -> f64 {
+        let mut counts = [0usize; 128];
+        for s in strings {
+            counts[s.as_bytes()[0] as usize] += 1;
+        }
+        let expected = strings.len() as f64 / charset.len() as f64;
+        charset
+            .iter()
+            .map(|&c| {
+                let d = counts[c as usize] as f64 - expected;
+                d * d / expected
+            })
+            .sum()
+    }
+    const ALPHA: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+    const DIGITS: &[u8] = b"0123456789";
+    let alnum: Vec<u8> = ALPHA.iter().chain(DIGITS).cloned().collect();
+    let urlsafe: Vec<u8> = alnum.iter().chain(b"-_").cloned().collect();
+
+    let samples: Vec<String> = (0..10000).map(|_| rng.gen_alphabetic_string(8)).collect();
+    assert!(chi_squared(&samples, ALPHA) < 110.0);
+    let samples: Vec<String> = (0..10000).map(|_| rng.gen_alphanumeric_string(8)).collect();
+    assert!(chi_squared(&samples, &alnum) < 125.0);
+    let samples: Vec<String> = (0..10000).map(|_| rng.gen_urlsafe_string(8)).collect();
+    assert!(chi_squared(&samples, &urlsafe) < 128.0);
+}
+
 // No need for testing others
 // Already included in the above tests
+
+pub fn test_rand_gen_secure_token() {
+    let mut rng = os::SgxRng::new().unwrap();
+
+    assert_eq!(rng.gen_secure_token(8).len(), 1);
+    assert_eq!(rng.gen_secure_token(256).len(), 32);
+    should_panic!(os::SgxRng::new().unwrap().gen_secure_token(0));
+    should_panic!(os::SgxRng::new().unwrap().gen_secure_token(127));
+
+    let mut tokens = Vec::new();
+    for _ in 0..1000 {
+        let s = rng.gen_secure_token_string(128);
+        assert_eq!(s.len(), 22);
+        assert!(s
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'));
+        // the last character only carries 2 bits
+        assert!(b"AQgw".contains(&s.as_bytes()[21]));
+        tokens.push(s);
+    }
+    tokens.sort();
+    tokens.dedup();
+    assert_eq!(tokens.len(), 1000);
+    assert_eq!(rng.gen_secure_token_string(8).len(), 2);
+    assert_eq!(rng.gen_secure_token_string(16).len(), 3);
+    assert_eq!(rng.gen_secure_token_string(24).len(), 4);
+
+    let mut uuids = Vec::new();
+    let mut ones = [0_usize; 128];
+    for _ in 0..1000 {
+        let uuid = rng.gen_uuid_v4();
+        // version 4, variant 10x (RFC 4122)
+        assert_eq!(uuid[6] >> 4, 4);
+        assert_eq!(uuid[8] >> 6, 0b10);
+        for bit in 0..128 {
+            ones[bit] += (uuid[bit / 8] >> (7 - bit % 8) & 1) as usize;
+        }
+        uuids.push(uuid);
+    }
+    uuids.sort();
+    uuids.dedup();
+    assert_eq!(uuids.len(), 1000);
+    // every bit outside the version and variant fields varies
+    for bit in (0..128).filter(|b| !(48..52).contains(b) && !(64..66).contains(b)) {
+        assert!(ones[bit] > 400 && ones[bit] < 600);
+    }
+}
@@ -25,6 +25,7 @@ use sgx_trts::enclave::*;
 use sgx_trts::error;
 use sgx_trts::libc;
 use sgx_trts::memchr;
+use sgx_trts::stack::*;
 use sgx_trts::trts::*;
 use sgx_trts::veh::*;
 
@@ -200,6 +201,18 @@ pub fn test_global_ctors_object() {
     assert_eq!(VARNAME(), ());
 }
 
+pub fn test_stack_probe() {
+    let remaining = remaining_stack();
+    assert!(remaining > 0);
+    assert!(probe_stack(0));
+    assert!(probe_stack(remaining / 2));
+    assert!(!probe_stack(remaining + 0x1000));
+    assert!(!probe_stack(usize::MAX));
+
+    sgx_check_stack!(remaining / 2);
+    should_panic!(sgx_check_stack!(usize::MAX));
+}
+
 // oom
 // I don't think we can test oom
 
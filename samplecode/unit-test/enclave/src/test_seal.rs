@@ -19,7 +19,9 @@ use sgx_rand::*;
 use sgx_tseal::*;
 use sgx_types::marker::*;
 use sgx_types::*;
+use std::io::{Read, Write};
 use std::prelude::v1::*;
+use std::untrusted::fs::{remove_file, File};
 
 fn to_sealed_log<T: Copy + ContiguousMemory>(
     sealed_data: &SgxSealedData<T>,
@@ -59,18 +61,25 @@ pub fn test_seal_unseal() {
 
     let aad: [u8; 0] = [0_u8; 0];
     let sealed_data = SgxSealedData::<RandData>::seal_data(&aad, &data).unwrap();
+    assert_eq!(sealed_data.key_policy(), KeyPolicy::MrSigner);
 
-    let mut sealed_log_arr: [u8; 2048] = [0; 2048];
-    let sealed_log = sealed_log_arr.as_mut_ptr();
-    let sealed_log_size: u32 = 2048;
-    let opt = to_sealed_log(&sealed_data, sealed_log, sealed_log_size);
-    assert_eq!(opt.is_some(), true);
-
-    let sealed_data = from_sealed_log::<RandData>(sealed_log, sealed_log_size).unwrap();
-    let unsealed_data = sealed_data.unseal_data().unwrap();
-    let udata = unsealed_data.get_decrypt_txt();
-    assert_eq!(data.key, udata.key);
-    assert_eq!(data.rand, udata.rand);
+    for policy in [KeyPolicy::MrEnclave, KeyPolicy::MrSigner].iter() {
+        let sealed_data =
+            SgxSealedData::<RandData>::seal_with_policy(&aad, &data, *policy).unwrap();
+
+        let mut sealed_log_arr: [u8; 2048] = [0; 2048];
+        let sealed_log = sealed_log_arr.as_mut_ptr();
+        let sealed_log_size: u32 = 2048;
+        let opt = to_sealed_log(&sealed_data, sealed_log, sealed_log_size);
+        assert_eq!(opt.is_some(), true);
+
+        let sealed_data = from_sealed_log::<RandData>(sealed_log, sealed_log_size).unwrap();
+        assert_eq!(sealed_data.key_policy(), *policy);
+        let unsealed_data = sealed_data.unseal_data().unwrap();
+        let udata = unsealed_data.get_decrypt_txt();
+        assert_eq!(data.key, udata.key);
+        assert_eq!(data.rand, udata.rand);
+    }
 }
 
 pub fn test_number_sealing() {
@@ -113,3 +122,101 @@ pub fn test_mac_aadata_slice() {
     let inner_slice = unsafe { slice::from_raw_parts(inner as *mut u8, 10) };
     assert_eq!(inner_slice, aad_data);
 }
+
+pub fn test_streaming_seal_unseal() {
+    const CHUNK_SIZE: usize = 4096;
+    let path = "streaming_seal.bin";
+    let aad = b"streaming seal";
+
+    let mut data = vec![0_u8; 10 * 1024 * 1024];
+    let mut rand = StdRng::new().unwrap();
+    rand.fill_bytes(&mut data);
+
+    // seal to an untrusted file, and keep a copy for the tampering checks
+    let mut sealed = Vec::new();
+    {
+        let mut file = File::create(path).unwrap();
+        let mut seal = StreamingSeal::new(KeyPolicy::MrEnclave, aad).unwrap();
+        file.write_all(seal.header()).unwrap();
+        sealed.extend_from_slice(seal.header());
+        for chunk in data.chunks(CHUNK_SIZE) {
+            let encrypt = seal.update(chunk).unwrap();
+            assert_eq!(encrypt.len(), chunk.len());
+            file.write_all(&encrypt).unwrap();
+            sealed.extend_from_slice(&encrypt);
+        }
+        let tag = seal.finalize().unwrap();
+        assert_eq!(tag.len(), STREAM_TAG_SIZE);
+        file.write_all(&tag).unwrap();
+        sealed.extend_from_slice(&tag);
+    }
+
+    // unseal from the file
+    let mut file = File::open(path).unwrap();
+    let mut header = vec![0_u8; STREAM_HEADER_FIXED_SIZE];
+    file.read_exact(&mut header).unwrap();
+    let header_len = StreamingUnseal::header_len(&header).unwrap();
+    assert_eq!(header_len, STREAM_HEADER_FIXED_SIZE + aad.len());
+    header.resize(header_len, 0);
+    file.read_exact(&mut header[STREAM_HEADER_FIXED_SIZE..]).unwrap();
+
+    let mut unseal = StreamingUnseal::new(&header).unwrap();
+    assert_eq!(unseal.key_policy(), KeyPolicy::MrEnclave);
+    assert_eq!(unseal.get_additional_txt(), &aad[..]);
+    let mut unsealed = Vec::with_capacity(data.len());
+    let mut chunk = [0_u8; CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut chunk).unwrap();
+        if n == 0 {
+            break;
+        }
+        unsealed.extend_from_slice(&unseal.update(&chunk[..n]).unwrap());
+    }
+    assert!(unseal.finalize().is_ok());
+    assert!(unsealed == data);
+    drop(file);
+    remove_file(path).unwrap();
+
+    let unseal_all = |sealed: &[u8]| -> SgxError {
+        let header_len = StreamingUnseal::header_len(sealed).unwrap();
+        let mut unseal = StreamingUnseal::new(&sealed[..header_len])?;
+        for chunk in sealed[header_len..].chunks(CHUNK_SIZE) {
+            unseal.update(chunk)?;
+        }
+        unseal.finalize()
+    };
+    assert!(unseal_all(&sealed).is_ok());
+
+    let mut tampered = sealed.clone();
+    tampered[header_len + data.len() / 2] ^= 1;
+    assert_eq!(unseal_all(&tampered), Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+
+    let mut tampered = sealed.clone();
+    tampered[header_len - 1] ^= 1;
+    assert_eq!(unseal_all(&tampered), Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+
+    let truncated = &sealed[..sealed.len() - CHUNK_SIZE];
+    assert_eq!(unseal_all(truncated), Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+
+    // update hands out unauthenticated data: a tampered or truncated stream
+    // decrypts without error, and only finalize rejects it
+    let unseal_updates = |sealed: &[u8]| -> (Vec<u8>, SgxError) {
+        let mut unseal = StreamingUnseal::new(&sealed[..header_len]).unwrap();
+        let mut unsealed = Vec::new();
+        for chunk in sealed[header_len..].chunks(CHUNK_SIZE) {
+            unsealed.extend_from_slice(&unseal.update(chunk).unwrap());
+        }
+        (unsealed, unseal.finalize())
+    };
+    let mut tampered = sealed.clone();
+    tampered[header_len + 100] ^= 1;
+    let (unsealed, result) = unseal_updates(&tampered);
+    assert_eq!(result, Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+    assert_eq!(unsealed.len(), data.len());
+    assert_eq!(unsealed[100], data[100] ^ 1);
+    for cut in [1, STREAM_TAG_SIZE, STREAM_TAG_SIZE + 1, CHUNK_SIZE + 7] {
+        let (unsealed, result) = unseal_updates(&sealed[..sealed.len() - cut]);
+        assert_eq!(result, Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+        assert!(unsealed.len() < data.len());
+    }
+}
@@ -0,0 +1,67 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use std::string::{ConstantTimeString, String, ToString};
+use std::time::{Duration, Instant};
+use std::untrusted::time::InstantEx;
+
+fn time_comparisons(a: &ConstantTimeString, b: &ConstantTimeString, rounds: usize) -> Duration {
+    let mut equal = 0;
+    let start = Instant::now();
+    for _ in 0..rounds {
+        if a == b {
+            equal += 1;
+        }
+    }
+    let elapsed = start.elapsed();
+    assert!(equal == 0 || equal == rounds);
+    elapsed
+}
+
+pub fn test_string_constant_time() {
+    let secret = ConstantTimeString::new("correct horse battery staple".to_string());
+    assert_eq!(secret, ConstantTimeString::from(String::from("correct horse battery staple")));
+    assert!(secret != ConstantTimeString::new("correct horse battery stapler".to_string()));
+    assert!(secret != ConstantTimeString::new("Correct horse battery staple".to_string()));
+    assert_eq!(secret.expose_secret(), "correct horse battery staple");
+    assert_eq!(format!("{}", secret), "<redacted>");
+    assert_eq!(format!("{:?}", secret), "<redacted>");
+
+    // A mismatch in the first byte must take as long as a full match.
+    let token: String = (0..1024).map(|i| (b'a' + (i % 26) as u8) as char).collect();
+    let mut early = token.clone().into_bytes();
+    early[0] = b'#';
+    let token = ConstantTimeString::new(token);
+    let same = token.clone();
+    let early = ConstantTimeString::new(String::from_utf8(early).unwrap());
+
+    // The fastest of several runs filters out interrupts and AEXs.
+    let rounds = 100_000;
+    let mut t_same = Duration::MAX;
+    let mut t_early = Duration::MAX;
+    for _ in 0..5 {
+        t_same = t_same.min(time_comparisons(&token, &same, rounds));
+        t_early = t_early.min(time_comparisons(&token, &early, rounds));
+    }
+    let (fast, slow) = if t_same < t_early { (t_same, t_early) } else { (t_early, t_same) };
+    assert!(
+        (slow - fast) * 20 < slow,
+        "timing differs: {:?} for equal, {:?} for different strings",
+        t_same,
+        t_early
+    );
+}
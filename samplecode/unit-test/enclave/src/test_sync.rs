@@ -0,0 +1,722 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{mpsc, watch};
+use std::sync::{
+    Arc, IntrusiveBox, IntrusiveNode, SgxArc, SgxCondvar, SgxFairMutex, SgxMpscQueue, SgxMutex,
+    SgxRwLock, SpinMutex, SpinRwLock, TryLockError,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+use std::untrusted::time::InstantEx;
+use std::vec::Vec;
+
+pub fn test_sync_mutex_lock_or_init() {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    static SINGLETON: SgxMutex<Option<Vec<u32>>> = SgxMutex::new(None);
+
+    let barrier = Arc::new(std::sync::Barrier::new(16));
+    let threads: Vec<_> = (0..16)
+        .map(|i| {
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                let mut value = SINGLETON
+                    .lock_or_init(|| {
+                        CALLS.fetch_add(1, Ordering::SeqCst);
+                        thread::sleep(std::time::Duration::from_millis(10));
+                        vec![42]
+                    })
+                    .unwrap();
+                value.push(i);
+                value[0]
+            })
+        })
+        .collect();
+
+    for t in threads {
+        assert_eq!(t.join().unwrap(), 42);
+    }
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+
+    let value = SINGLETON.lock_or_init(|| unreachable!()).unwrap();
+    assert_eq!(value.len(), 17);
+    let mut pushed = value[1..].to_vec();
+    pushed.sort_unstable();
+    assert_eq!(pushed, (0..16).collect::<Vec<u32>>());
+}
+
+pub fn test_sync_rwlock_optimistic_read() {
+    // every write keeps all words equal, so a torn read shows up as a mismatch
+    let lock = Arc::new(SgxRwLock::new([0_u64; 8]));
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let writer = {
+        let lock = lock.clone();
+        let stop = stop.clone();
+        thread::spawn(move || {
+            let mut n = 0;
+            while !stop.load(Ordering::Relaxed) {
+                n += 1;
+                let mut data = lock.write().unwrap();
+                for word in data.iter_mut() {
+                    *word = n;
+                }
+            }
+            n
+        })
+    };
+
+    let readers: Vec<_> = (0..8)
+        .map(|_| {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                let mut last = 0;
+                for _ in 0..20000 {
+                    let data = lock.optimistic_read(|data| Some(*data));
+                    assert!(data.iter().all(|w| *w == data[0]), "torn read: {:?}", data);
+                    assert!(data[0] >= last);
+                    last = data[0];
+                }
+            })
+        })
+        .collect();
+
+    for r in readers {
+        r.join().unwrap();
+    }
+    stop.store(true, Ordering::Relaxed);
+    let n = writer.join().unwrap();
+    assert_eq!(lock.optimistic_read(|data| Some(data[7])), n);
+
+    // `None` asks for a retry
+    let tries = AtomicUsize::new(0);
+    let value = lock.optimistic_read(|data| {
+        if tries.fetch_add(1, Ordering::Relaxed) < 100 {
+            None
+        } else {
+            Some(data[0])
+        }
+    });
+    assert_eq!(value, n);
+    assert_eq!(tries.load(Ordering::Relaxed), 101);
+
+    // a writer holding the lock forces a retry: `f` only ever sees the
+    // value left once the write is done, never the one it replaces
+    let (locked_tx, locked_rx) = mpsc::channel();
+    let writer = {
+        let lock = lock.clone();
+        thread::spawn(move || {
+            let mut data = lock.write().unwrap();
+            locked_tx.send(()).unwrap();
+            thread::sleep(Duration::from_millis(50));
+            for word in data.iter_mut() {
+                *word = n + 1;
+            }
+        })
+    };
+    locked_rx.recv().unwrap();
+    let calls = AtomicUsize::new(0);
+    let data = lock.optimistic_read(|data| {
+        calls.fetch_add(1, Ordering::Relaxed);
+        Some(*data)
+    });
+    writer.join().unwrap();
+    assert_eq!(data, [n + 1; 8]);
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+}
+
+pub fn test_sync_rwlock_optimistic_read_throughput() {
+    const READERS: usize = 16;
+    const DURATION: Duration = Duration::from_millis(500);
+
+    fn run(optimistic: bool) -> usize {
+        let lock = Arc::new(SgxRwLock::new((0_u64, 0_u64)));
+        let stop = Arc::new(AtomicBool::new(false));
+        let reads = Arc::new(AtomicUsize::new(0));
+
+        let writer = {
+            let lock = lock.clone();
+            let stop = stop.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let mut data = lock.write().unwrap();
+                    data.0 += 1;
+                    data.1 = data.0 * 2;
+                    drop(data);
+                    thread::sleep(Duration::from_millis(1));
+                }
+            })
+        };
+
+        let readers: Vec<_> = (0..READERS)
+            .map(|_| {
+                let lock = lock.clone();
+                let stop = stop.clone();
+                let reads = reads.clone();
+                thread::spawn(move || {
+                    let mut n = 0;
+                    while !stop.load(Ordering::Relaxed) {
+                        let (a, b) = if optimistic {
+                            lock.optimistic_read(|data| Some(*data))
+                        } else {
+                            *lock.read().unwrap()
+                        };
+                        assert_eq!(b, a * 2);
+                        n += 1;
+                    }
+                    reads.fetch_add(n, Ordering::Relaxed);
+                })
+            })
+            .collect();
+
+        let start = Instant::now();
+        while start.elapsed() < DURATION {
+            thread::sleep(Duration::from_millis(10));
+        }
+        stop.store(true, Ordering::Relaxed);
+        for r in readers {
+            r.join().unwrap();
+        }
+        writer.join().unwrap();
+        reads.load(Ordering::Relaxed)
+    }
+
+    let locked = run(false);
+    let optimistic = run(true);
+    assert!(optimistic > locked);
+}
+
+pub fn test_sync_mutex_try_lock_for() {
+    let mutex = Arc::new(SgxMutex::new(0));
+    let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+
+    let holder = {
+        let mutex = mutex.clone();
+        thread::spawn(move || {
+            let mut guard = mutex.lock().unwrap();
+            locked_tx.send(()).unwrap();
+            thread::sleep(Duration::from_millis(200));
+            *guard += 1;
+        })
+    };
+    locked_rx.recv().unwrap();
+
+    let start = Instant::now();
+    assert!(mutex.try_lock_for(Duration::from_millis(100)).is_err());
+    assert!(start.elapsed() >= Duration::from_millis(100));
+
+    let guard = mutex.try_lock_for(Duration::from_millis(400)).unwrap();
+    assert_eq!(*guard, 1);
+    assert!(start.elapsed() < Duration::from_millis(400));
+    drop(guard);
+    holder.join().unwrap();
+
+    let deadline = Instant::now() + Duration::from_millis(10);
+    assert!(mutex.try_lock_until(deadline).is_ok());
+    let _guard = mutex.lock().unwrap();
+    assert!(mutex.try_lock_until(Instant::now()).is_err());
+}
+
+pub fn test_sync_condvar_broadcast_if() {
+    let pair = Arc::new((SgxMutex::new(0), SgxCondvar::new()));
+
+    let waiters: Vec<_> = (0..4)
+        .map(|_| {
+            let pair = pair.clone();
+            thread::spawn(move || {
+                let (lock, cvar) = &*pair;
+                let mut wakeups = 0;
+                let mut count = lock.lock().unwrap();
+                while *count < 10 {
+                    count = cvar.wait(count).unwrap();
+                    wakeups += 1;
+                }
+                (*count, wakeups)
+            })
+        })
+        .collect();
+
+    let (lock, cvar) = &*pair;
+    for i in 1..=10 {
+        thread::sleep(Duration::from_millis(5));
+        let mut count = lock.lock().unwrap();
+        *count += 1;
+        assert_eq!(cvar.broadcast_if(&count, |count| *count == 10), i == 10);
+    }
+
+    for waiter in waiters {
+        let (count, wakeups) = waiter.join().unwrap();
+        assert_eq!(count, 10);
+        // woken up by the last increment only, unless it came too late to wait
+        assert!(wakeups <= 1);
+    }
+
+    let count = cvar.wait_while(lock.lock().unwrap(), |count| *count < 10).unwrap();
+    assert!(!cvar.broadcast_if(&count, |count| *count > 10));
+}
+
+pub fn test_sync_arc_try_unwrap() {
+    let a = SgxArc::new(vec![1_u32, 2, 3]);
+    let b = SgxArc::clone(&a);
+    assert_eq!(SgxArc::strong_count(&a), 2);
+    assert!(SgxArc::ptr_eq(&a, &b));
+    assert!(!SgxArc::ptr_eq(&a, &SgxArc::new(vec![1_u32, 2, 3])));
+
+    // a second reference is still alive
+    let a = SgxArc::try_unwrap(a).unwrap_err();
+    assert_eq!(SgxArc::strong_count(&a), 2);
+
+    // weak references do not count
+    let weak = Arc::downgrade(SgxArc::as_arc(&b));
+    drop(b);
+    assert_eq!(SgxArc::strong_count(&a), 1);
+    let mut a = a;
+    unsafe { SgxArc::get_mut_unchecked(&mut a).push(4) };
+    assert_eq!(SgxArc::try_unwrap(a).unwrap(), vec![1, 2, 3, 4]);
+    assert!(weak.upgrade().is_none());
+}
+
+pub fn test_sync_mutex_into_inner_poisoned() {
+    let m = SgxMutex::new(vec![1_u32]);
+    assert_eq!(m.into_inner().unwrap(), [1]);
+
+    let m = Arc::new(SgxMutex::new(vec![1_u32]));
+    let m2 = Arc::clone(&m);
+    let r = thread::spawn(move || {
+        let mut data = m2.lock().unwrap();
+        data.push(2);
+        panic!("poison the mutex");
+    })
+    .join();
+    assert!(r.is_err());
+    assert!(m.is_poisoned());
+
+    let m = Arc::try_unwrap(m).unwrap();
+    let data = m.into_inner().unwrap_err().into_inner();
+    assert_eq!(data, [1, 2]);
+}
+
+pub fn test_sync_fair_mutex() {
+    let m = SgxFairMutex::new(vec![1_u32]);
+    {
+        let mut guard = m.try_lock().unwrap();
+        guard.push(2);
+        assert!(matches!(m.try_lock(), Err(TryLockError::WouldBlock)));
+        assert_eq!(format!("{:?}", m), "SgxFairMutex { data: <locked>, poisoned: false, .. }");
+    }
+    assert_eq!(*m.lock().unwrap(), [1, 2]);
+
+    let m = Arc::new(m);
+    let m2 = Arc::clone(&m);
+    let r = thread::spawn(move || {
+        let _guard = m2.lock().unwrap();
+        panic!("poison the mutex");
+    })
+    .join();
+    assert!(r.is_err());
+    assert!(m.is_poisoned());
+    assert_eq!(*m.lock().unwrap_err().into_inner(), [1, 2]);
+    m.clear_poison();
+    assert_eq!(Arc::try_unwrap(m).unwrap().into_inner().unwrap(), [1, 2]);
+}
+
+pub fn test_sync_fair_mutex_fairness() {
+    const THREADS: usize = 16;
+    const ROUNDS: usize = 1000;
+
+    // THREADS threads race to increment a shared counter THREADS * ROUNDS
+    // times in total, each counting how often it won. Counting starts once
+    // every thread has held the lock, so a head start from being spawned
+    // first does not count.
+    let lock = Arc::new(SgxFairMutex::new(0_usize));
+    let ready = Arc::new(AtomicUsize::new(0));
+    let barrier = Arc::new(std::sync::Barrier::new(THREADS));
+    let threads: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let lock = lock.clone();
+            let barrier = barrier.clone();
+            let ready = ready.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                let mut won = 0_usize;
+                let mut first = true;
+                loop {
+                    let mut total = lock.lock().unwrap();
+                    if first {
+                        ready.fetch_add(1, Ordering::SeqCst);
+                        first = false;
+                    }
+                    if *total == THREADS * ROUNDS {
+                        return won;
+                    }
+                    if ready.load(Ordering::SeqCst) == THREADS {
+                        *total += 1;
+                        won += 1;
+                    }
+                }
+            })
+        })
+        .collect();
+    let counts: Vec<usize> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+    assert_eq!(counts.iter().sum::<usize>(), THREADS * ROUNDS);
+
+    // every thread gets within 20% of the others' share
+    let max = *counts.iter().max().unwrap();
+    let min = *counts.iter().min().unwrap();
+    assert!(max * 5 <= min * 6);
+}
+
+pub fn test_sync_spin_mutex() {
+    const THREADS: usize = 8;
+    const ROUNDS: usize = 10000;
+
+    let m = SpinMutex::new(0_usize);
+    {
+        let _guard = m.try_lock().unwrap();
+        assert!(matches!(m.try_lock(), Err(TryLockError::WouldBlock)));
+        assert_eq!(format!("{:?}", m), "SpinMutex { data: <locked>, poisoned: false, .. }");
+    }
+
+    let m = Arc::new(m);
+    let threads: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let m = Arc::clone(&m);
+            thread::spawn(move || {
+                for _ in 0..ROUNDS {
+                    *m.lock().unwrap() += 1;
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(*m.lock().unwrap(), THREADS * ROUNDS);
+
+    let m2 = Arc::clone(&m);
+    let r = thread::spawn(move || {
+        let _guard = m2.lock().unwrap();
+        panic!("poison the mutex");
+    })
+    .join();
+    assert!(r.is_err());
+    assert!(m.is_poisoned());
+    m.clear_poison();
+    assert_eq!(Arc::try_unwrap(m).unwrap().into_inner().unwrap(), THREADS * ROUNDS);
+}
+
+pub fn test_sync_spin_rwlock() {
+    const THREADS: usize = 8;
+    const ROUNDS: usize = 10000;
+
+    let lock = SpinRwLock::new(vec![0_usize; 2]);
+    {
+        let r1 = lock.try_read().unwrap();
+        let r2 = lock.read().unwrap();
+        assert_eq!(*r1, *r2);
+        assert!(matches!(lock.try_write(), Err(TryLockError::WouldBlock)));
+    }
+    {
+        let _w = lock.write().unwrap();
+        assert!(matches!(lock.try_read(), Err(TryLockError::WouldBlock)));
+        assert_eq!(format!("{:?}", lock), "SpinRwLock { data: <locked>, poisoned: false, .. }");
+    }
+
+    // Writers keep both entries equal, readers must never see them differ.
+    let lock = Arc::new(lock);
+    let threads: Vec<_> = (0..THREADS)
+        .map(|i| {
+            let lock = Arc::clone(&lock);
+            thread::spawn(move || {
+                for _ in 0..ROUNDS {
+                    if i % 2 == 0 {
+                        let mut w = lock.write().unwrap();
+                        w[0] += 1;
+                        w[1] += 1;
+                    } else {
+                        let r = lock.read().unwrap();
+                        assert_eq!(r[0], r[1]);
+                    }
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(*lock.read().unwrap(), [THREADS / 2 * ROUNDS; 2]);
+
+    let lock2 = Arc::clone(&lock);
+    let r = thread::spawn(move || {
+        let _r = lock2.read().unwrap();
+        panic!("readers do not poison the lock");
+    })
+    .join();
+    assert!(r.is_err());
+    assert!(!lock.is_poisoned());
+    assert!(lock.try_write().is_ok());
+}
+
+struct Message {
+    next: AtomicPtr<Message>,
+    producer: usize,
+    seq: usize,
+}
+
+unsafe impl IntrusiveNode for Message {
+    fn next(&self) -> &AtomicPtr<Message> {
+        &self.next
+    }
+}
+
+impl Message {
+    fn new(producer: usize, seq: usize) -> IntrusiveBox<Message> {
+        IntrusiveBox::new(Message {
+            next: AtomicPtr::default(),
+            producer: producer,
+            seq: seq,
+        })
+    }
+}
+
+pub fn test_sync_mpsc_queue() {
+    const PRODUCERS: usize = 4;
+    const MESSAGES: usize = 10_000;
+
+    let queue = SgxMpscQueue::new();
+    assert!(queue.is_empty());
+    assert!(queue.pop().is_none());
+    queue.push_box(Message::new(0, 1));
+    queue.push_box(Message::new(0, 2));
+    assert!(!queue.is_empty());
+    assert_eq!(queue.pop_box().unwrap().seq, 1);
+    let last = queue.pop_box().unwrap();
+    assert_eq!(last.seq, 2);
+    assert!(queue.pop_box().is_none());
+    // a popped node can be pushed again
+    queue.push_box(last);
+    assert_eq!(IntrusiveBox::into_inner(queue.pop_box().unwrap()).seq, 2);
+    assert!(queue.is_empty());
+
+    let queue = Arc::new(queue);
+    let producers: Vec<_> = (0..PRODUCERS)
+        .map(|p| {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                for seq in 0..MESSAGES {
+                    queue.push_box(Message::new(p, seq));
+                }
+            })
+        })
+        .collect();
+
+    // messages from each producer arrive in order
+    let mut expected = [0_usize; PRODUCERS];
+    let mut received = 0;
+    while received < PRODUCERS * MESSAGES {
+        match queue.pop_box() {
+            Some(msg) => {
+                assert_eq!(msg.seq, expected[msg.producer]);
+                expected[msg.producer] += 1;
+                received += 1;
+            }
+            None => thread::yield_now(),
+        }
+    }
+    for p in producers {
+        p.join().unwrap();
+    }
+    assert!(queue.pop().is_none());
+    assert!(expected.iter().all(|&n| n == MESSAGES));
+}
+
+pub fn test_sync_mpsc_queue_drop() {
+    struct Tracked {
+        next: AtomicPtr<Tracked>,
+        drops: Arc<AtomicUsize>,
+    }
+
+    unsafe impl IntrusiveNode for Tracked {
+        fn next(&self) -> &AtomicPtr<Tracked> {
+            &self.next
+        }
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.drops.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let drops = Arc::new(AtomicUsize::new(0));
+    let tracked = || {
+        IntrusiveBox::new(Tracked {
+            next: AtomicPtr::default(),
+            drops: drops.clone(),
+        })
+    };
+
+    let queue = SgxMpscQueue::new();
+    for _ in 0..3 {
+        queue.push_box(tracked());
+    }
+    unsafe { queue.push(IntrusiveBox::into_raw(tracked())) };
+    drop(queue.pop_box());
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+
+    // the remaining nodes are freed with the queue
+    drop(queue);
+    assert_eq!(drops.load(Ordering::SeqCst), 4);
+}
+
+pub fn test_sync_mpsc_queue_ping_pong() {
+    const ROUNDS: usize = 1_000_000;
+
+    fn queue_ping_pong() -> Duration {
+        let ping = Arc::new(SgxMpscQueue::new());
+        let pong = Arc::new(SgxMpscQueue::new());
+        let echo = {
+            let ping = ping.clone();
+            let pong = pong.clone();
+            thread::spawn(move || {
+                let mut seen = Vec::with_capacity(ROUNDS);
+                for _ in 0..ROUNDS {
+                    let msg = loop {
+                        if let Some(msg) = ping.pop_box() {
+                            break msg;
+                        }
+                    };
+                    seen.push((msg.producer, msg.seq));
+                    pong.push_box(msg);
+                }
+                seen
+            })
+        };
+
+        let start = Instant::now();
+        let mut msg = Message::new(0, 0);
+        let mut returned = Vec::with_capacity(ROUNDS);
+        for seq in 0..ROUNDS {
+            msg.seq = seq;
+            ping.push_box(msg);
+            msg = loop {
+                if let Some(msg) = pong.pop_box() {
+                    break msg;
+                }
+            };
+            returned.push((msg.producer, msg.seq));
+        }
+        let elapsed = start.elapsed();
+        let seen = echo.join().unwrap();
+
+        let expected: Vec<_> = (0..ROUNDS).map(|seq| (0, seq)).collect();
+        assert_eq!(seen, expected);
+        assert_eq!(returned, expected);
+        assert!(ping.is_empty() && pong.is_empty());
+        elapsed
+    }
+
+    fn channel_ping_pong() -> Duration {
+        let (ping_tx, ping_rx) = mpsc::channel::<usize>();
+        let (pong_tx, pong_rx) = mpsc::channel::<usize>();
+        let echo = thread::spawn(move || {
+            let mut seen = Vec::with_capacity(ROUNDS);
+            for _ in 0..ROUNDS {
+                let seq = ping_rx.recv().unwrap();
+                seen.push(seq);
+                pong_tx.send(seq).unwrap();
+            }
+            seen
+        });
+
+        let start = Instant::now();
+        let mut returned = Vec::with_capacity(ROUNDS);
+        for seq in 0..ROUNDS {
+            ping_tx.send(seq).unwrap();
+            returned.push(pong_rx.recv().unwrap());
+        }
+        let elapsed = start.elapsed();
+        let seen = echo.join().unwrap();
+
+        let expected: Vec<_> = (0..ROUNDS).collect();
+        assert_eq!(seen, expected);
+        assert_eq!(returned, expected);
+        elapsed
+    }
+
+    let queue = queue_ping_pong();
+    let channel = channel_ping_pong();
+    assert!(queue < channel);
+}
+
+pub fn test_sync_watch() {
+    let (tx, mut rx) = watch::channel(1);
+    assert_eq!(rx.try_recv(), None);
+    assert_eq!(rx.current(), 1);
+
+    // Only the latest value is kept.
+    tx.send(2).unwrap();
+    tx.send(3).unwrap();
+    assert_eq!(rx.try_recv(), Some(3));
+    assert_eq!(rx.try_recv(), None);
+
+    let mut rx2 = rx.clone();
+    assert_eq!(tx.receiver_count(), 2);
+    tx.send(4).unwrap();
+    assert_eq!(rx.recv(), Ok(4));
+    drop(rx);
+    assert_eq!(tx.receiver_count(), 1);
+
+    // The last value is still delivered once the sender is gone.
+    tx.send(5).unwrap();
+    drop(tx);
+    assert_eq!(rx2.recv(), Ok(5));
+    assert_eq!(rx2.recv(), Err(watch::RecvError));
+
+    let (tx, rx) = watch::channel(0);
+    drop(rx);
+    assert_eq!(tx.send(1), Err(watch::SendError(1)));
+}
+
+pub fn test_sync_watch_broadcast() {
+    const THREADS: usize = 8;
+
+    let (tx, rx) = watch::channel(0_usize);
+    let barrier = Arc::new(std::sync::Barrier::new(THREADS + 1));
+    let threads: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let mut rx = rx.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                rx.recv().unwrap()
+            })
+        })
+        .collect();
+    drop(rx);
+
+    barrier.wait();
+    // Give every receiver time to block in recv.
+    thread::sleep(Duration::from_millis(50));
+    tx.send(42).unwrap();
+    for t in threads {
+        assert_eq!(t.join().unwrap(), 42);
+    }
+    assert_eq!(tx.receiver_count(), 0);
+}
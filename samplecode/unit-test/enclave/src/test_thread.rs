@@ -9,9 +9,13 @@ use std::sync::mpsc::{channel, Sender};
 use std::thread;
 use std::thread::sleep;
 use std::thread::Builder;
+use std::thread::JoinSet;
 use std::thread::ThreadId;
 use std::time::Duration;
 use std::u32;
+use std::vec::Vec;
+
+use sgx_rand::{thread_rng, Rng};
 
 pub fn test_thread_unnamed_thread() {
     thread::spawn(move || {
@@ -241,3 +245,38 @@ pub fn test_thread_id_not_equal() {
     let spawned_id = thread::spawn(|| thread::current().id()).join().unwrap();
     assert!(thread::current().id() != spawned_id);
 }
+
+pub fn test_thread_joinset() {
+    let mut rng = thread_rng();
+    let mut set = JoinSet::new();
+    for _ in 0..20 {
+        let ms: u64 = rng.gen_range(1, 101);
+        set.spawn(move || {
+            sleep(Duration::from_millis(ms));
+            ms
+        });
+    }
+    assert_eq!(set.len(), 20);
+
+    let mut finished = Vec::new();
+    while let Some(res) = set.join_next() {
+        finished.push(res.unwrap());
+    }
+    assert!(set.is_empty());
+    assert!(set.join_next().is_none());
+    assert_eq!(finished.len(), 20);
+    // Threads come back in completion order, i.e. by sleep time. Allow some
+    // slack for threads whose sleep times are only a few ms apart.
+    for w in finished.windows(2) {
+        assert!(w[1] + 10 >= w[0], "{:?}", finished);
+    }
+
+    let results = set
+        .spawn(|| 1)
+        .spawn(|| panic!("joinset"))
+        .spawn(|| 3)
+        .join_all();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results.iter().filter(|r| r.is_err()).count(), 1);
+    assert_eq!(results.into_iter().filter_map(|r| r.ok()).sum::<u64>(), 4);
+}
@@ -1,7 +1,9 @@
 use sgx_types::metadata::*;
 use sgx_types::*;
 
+use core::convert::TryFrom;
 use core::mem;
+use std::vec::Vec;
 
 pub fn check_metadata_size() {
     assert_eq!(mem::size_of::<layout_group_t>(), 32);
@@ -31,3 +33,146 @@ pub fn check_version() {
         MINOR_VERSION as u64
     );
 }
+
+pub fn check_quote4_size() {
+    assert_eq!(mem::size_of::<sgx_quote4_header_t>(), 48);
+    assert_eq!(mem::size_of::<sgx_report2_body_t>(), 584);
+    assert_eq!(mem::size_of::<sgx_quote4_t>(), 636);
+    assert_eq!(mem::size_of::<sgx_tdx_module_t>(), 104);
+    assert_eq!(mem::size_of::<tee_tcb_info_t>(), 239);
+    assert_eq!(mem::size_of::<tee_info_t>(), 512);
+    assert_eq!(mem::size_of::<TdReport>(), TDX_REPORT_SIZE);
+}
+
+// Builds a TDX quote with recognizable contents.
+fn build_quote4(chain: &[u8]) -> Vec<u8> {
+    let mut quote = Vec::new();
+    // header: version, attestation key type, TEE type, reserved,
+    // QE vendor id, user data
+    quote.extend_from_slice(&4_u16.to_le_bytes());
+    quote.extend_from_slice(&2_u16.to_le_bytes());
+    quote.extend_from_slice(&TEE_TYPE_TDX.to_le_bytes());
+    quote.extend_from_slice(&0_u32.to_le_bytes());
+    quote.extend_from_slice(&[0xaa; 16]);
+    quote.extend_from_slice(&[0xbb; 20]);
+    // TD report body: mr_seam at 16, td_attributes at 120, mr_td at 136,
+    // report_data at 520
+    let mut body = [0_u8; 584];
+    body[16..64].copy_from_slice(&[0x11; 48]);
+    body[120] = 0x01;
+    body[123] = 0x10;
+    body[136..184].copy_from_slice(&[0x22; 48]);
+    body[520..].copy_from_slice(&[0x33; 64]);
+    quote.extend_from_slice(&body);
+
+    let mut qe = Vec::new();
+    let mut qe_report = [0_u8; 384];
+    qe_report[64..96].copy_from_slice(&[0x44; 32]);
+    qe.extend_from_slice(&qe_report);
+    qe.extend_from_slice(&[0x55; 64]);
+    qe.extend_from_slice(&3_u16.to_le_bytes());
+    qe.extend_from_slice(b"abc");
+    qe.extend_from_slice(&(sgx_ql_cert_key_type_t::PCK_CERT_CHAIN as u16).to_le_bytes());
+    qe.extend_from_slice(&(chain.len() as u32).to_le_bytes());
+    qe.extend_from_slice(chain);
+
+    let mut sig = Vec::new();
+    sig.extend_from_slice(&[0x66; 64]);
+    sig.extend_from_slice(&[0x77; 64]);
+    sig.extend_from_slice(&(sgx_ql_cert_key_type_t::ECDSA_SIG_AUX_DATA as u16).to_le_bytes());
+    sig.extend_from_slice(&(qe.len() as u32).to_le_bytes());
+    sig.extend_from_slice(&qe);
+    quote.extend_from_slice(&(sig.len() as u32).to_le_bytes());
+    quote.extend_from_slice(&sig);
+    quote
+}
+
+pub fn test_quote4_parse() {
+    let chain = b"-----BEGIN CERTIFICATE-----";
+    let bytes = build_quote4(chain);
+    let quote = Quote4::parse(&bytes).unwrap();
+    assert_eq!(quote.version(), 4);
+    assert_eq!(quote.tee_type(), TEE_TYPE_TDX);
+    assert_eq!(quote.header().user_data, [0xbb; 20]);
+    assert_eq!(quote.report_body().mr_td.m, [0x22; 48]);
+    assert_eq!(quote.report_body().report_data.d, [0x33; 64]);
+    assert_eq!(quote.tdx_module().mr_seam.m, [0x11; 48]);
+    assert_eq!(
+        quote.td_attributes(),
+        sgx_tdx_attributes_t::DEBUG | sgx_tdx_attributes_t::SEPT_VE_DISABLE
+    );
+    assert_eq!(quote.signed_data(), &bytes[..632]);
+    assert_eq!(quote.signature(), &[0x66; 64][..]);
+    assert_eq!(quote.attestation_key(), &[0x77; 64][..]);
+    assert_eq!(quote.qe_report_body().mr_enclave.m, [0x44; 32]);
+    assert_eq!(quote.qe_report_signature(), &[0x55; 64][..]);
+    assert_eq!(quote.qe_auth_data(), b"abc");
+    assert_eq!(quote.pck_cert_chain(), Some(&chain[..]));
+}
+
+pub fn test_quote4_parse_errors() {
+    let bytes = build_quote4(b"chain");
+    assert_eq!(Quote4::parse(&bytes[..635]).unwrap_err(), QuoteParseError::TooShort);
+    assert_eq!(Quote4::parse(&bytes[..bytes.len() - 1]).unwrap_err(), QuoteParseError::TooShort);
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(Quote4::parse(&trailing).unwrap_err(), QuoteParseError::TrailingData);
+
+    let mut v3 = bytes.clone();
+    v3[0] = 3;
+    assert_eq!(Quote4::parse(&v3).unwrap_err(), QuoteParseError::UnsupportedVersion(3));
+
+    let mut sgx = bytes.clone();
+    sgx[4..8].copy_from_slice(&TEE_TYPE_SGX.to_le_bytes());
+    assert_eq!(Quote4::parse(&sgx).unwrap_err(), QuoteParseError::UnsupportedTeeType(TEE_TYPE_SGX));
+
+    let mut p384 = bytes.clone();
+    p384[2] = 3;
+    assert_eq!(
+        Quote4::parse(&p384).unwrap_err(),
+        QuoteParseError::UnsupportedAttestationKeyType(3)
+    );
+
+    // the outer certification data must be the QE report
+    let mut cert_type = bytes.clone();
+    cert_type[636 + 128] = 5;
+    assert_eq!(
+        Quote4::parse(&cert_type).unwrap_err(),
+        QuoteParseError::InvalidCertificationData
+    );
+}
+
+pub fn test_td_report() {
+    let mut report = tdx_report_t::default();
+    // report_data at 128, mr_seam at 256 + 24, td attributes at 512
+    report.d[128..192].copy_from_slice(&[0x11; 64]);
+    report.d[280..328].copy_from_slice(&[0x22; 48]);
+    report.d[512] = 0x01;
+    report.d[519] = 0x80;
+    let td = TdReport::from(&report);
+    assert_eq!(td.report_data().d, [0x11; 64]);
+    assert_eq!(td.tdx_module().mr_seam.m, [0x22; 48]);
+    assert_eq!(
+        td.td_attributes(),
+        sgx_tdx_attributes_t::DEBUG | sgx_tdx_attributes_t::PERFMON
+    );
+}
+
+pub fn test_measurement_types() {
+    let mut body = sgx_report_body_t::default();
+    body.mr_enclave.m = [0x11; 32];
+    body.mr_signer.m = [0xab; 32];
+
+    assert_eq!(body.mr_enclave(), MrEnclave([0x11; 32]));
+    assert_eq!(body.mr_signer(), MrSigner([0xab; 32]));
+    assert_eq!(sgx_measurement_t::from(body.mr_enclave()).m, [0x11; 32]);
+    assert_eq!(format!("{}", MrSigner::from(body.mr_signer)), "ab".repeat(32));
+
+    assert_eq!(MrEnclave::try_from(&[0x11; 32][..]), Ok(body.mr_enclave()));
+    assert_eq!(
+        MrEnclave::try_from(&[0x11; 31][..]),
+        Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    );
+    assert!(MrSigner::try_from(&[0_u8; 33][..]).is_err());
+}
@@ -0,0 +1,99 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Runs a WASI command module in the enclave:
+//!
+//!     wasi <module.wasm> [stdin-file]
+//!
+//! The module gets the content of `stdin-file` (or nothing) as standard
+//! input and exits with the exit code of the module.
+
+extern crate sgx_types;
+extern crate sgx_urts;
+
+use sgx_types::*;
+use sgx_urts::SgxEnclave;
+
+use std::env;
+use std::fs;
+use std::process;
+
+static ENCLAVE_FILE: &'static str = "enclave.signed.so";
+
+extern {
+    fn sgxwasm_run_wasi(eid: sgx_enclave_id_t, retval: *mut sgx_status_t,
+                        wasm: *const u8, wasm_len: usize,
+                        stdin_buf: *const u8, stdin_len: usize,
+                        exit_code: *mut i32) -> sgx_status_t;
+}
+
+fn init_enclave() -> SgxResult<SgxEnclave> {
+    let mut launch_token: sgx_launch_token_t = [0; 1024];
+    let mut launch_token_updated: i32 = 0;
+    // call sgx_create_enclave to initialize an enclave instance
+    // Debug Support: set 2nd parameter to 1
+    let debug = 1;
+    let mut misc_attr = sgx_misc_attribute_t {secs_attr: sgx_attributes_t { flags:0, xfrm:0}, misc_select:0};
+    SgxEnclave::create(ENCLAVE_FILE,
+                       debug,
+                       &mut launch_token,
+                       &mut launch_token_updated,
+                       &mut misc_attr)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 || args.len() > 3 {
+        println!("Usage: {} <module.wasm> [stdin-file]", args[0]);
+        process::exit(2);
+    }
+
+    let wasm = fs::read(&args[1]).expect("[-] Cannot read the WASI module");
+    let stdin = match args.get(2) {
+        Some(path) => fs::read(path).expect("[-] Cannot read the stdin file"),
+        None => Vec::new(),
+    };
+
+    let enclave = match init_enclave() {
+        Ok(r) => r,
+        Err(x) => {
+            println!("[-] Init Enclave Failed {}!", x.as_str());
+            process::exit(1);
+        },
+    };
+
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let mut exit_code = 0;
+    let result = unsafe {
+        sgxwasm_run_wasi(enclave.geteid(),
+                         &mut retval,
+                         wasm.as_ptr(),
+                         wasm.len(),
+                         stdin.as_ptr(),
+                         stdin.len(),
+                         &mut exit_code)
+    };
+    enclave.destroy();
+
+    match (result, retval) {
+        (sgx_status_t::SGX_SUCCESS, sgx_status_t::SGX_SUCCESS) => process::exit(exit_code),
+        (sgx_status_t::SGX_SUCCESS, e) | (e, _) => {
+            println!("[-] ECALL Enclave [sgxwasm_run_wasi] Failed {}!", e.as_str());
+            process::exit(1);
+        }
+    }
+}
@@ -68,6 +68,9 @@ pub enum SgxWasmAction {
         name: Option<String>,
         as_name: String,
     },
+    SetBudget {
+        instructions: u64,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -527,6 +530,56 @@ fn run_a_wast(enclave   : &SgxEnclave,
     Ok(())
 }
 
+fn sgx_enclave_wasm_set_budget(instructions : u64,
+                               enclave      : &SgxEnclave) -> Result<(), String> {
+    let req = SgxWasmAction::SetBudget { instructions : instructions };
+    match sgx_enclave_wasm_invoke(serde_json::to_string(&req).unwrap(),
+                                  MAXOUTPUT,
+                                  enclave) {
+        (_, sgx_status_t::SGX_SUCCESS) => Ok(()),
+        (_, x) => Err(format!("SetBudget failed: {}", x.as_str())),
+    }
+}
+
+// Runs a module with an infinite loop under an execution budget: the
+// invocation must be stopped, and the next one gets a full budget again.
+fn run_budget_test(enclave : &SgxEnclave) -> Result<(), String> {
+    const SPIN: &str = r#"
+        (module
+            (func (export "spin") (loop br 0))
+            (func (export "answer") (result i32) i32.const 42))
+    "#;
+
+    sgx_enclave_wasm_init(enclave)?;
+    sgx_enclave_wasm_set_budget(100_000, enclave)?;
+    let wasm = wabt::wat2wasm(SPIN).map_err(|e| e.to_string())?;
+    sgx_enclave_wasm_load_module(wasm, &None, enclave)?;
+
+    let invoke = |field: &str| {
+        let req = SgxWasmAction::Invoke {
+            module : None,
+            field  : field.to_string(),
+            args   : vec![],
+        };
+        sgx_enclave_wasm_invoke(serde_json::to_string(&req).unwrap(), MAXOUTPUT, enclave)
+    };
+
+    match invoke("answer") {
+        (Ok(Some(BoundaryValue::I32(42))), sgx_status_t::SGX_SUCCESS) => {},
+        (r, x) => return Err(format!("answer returned {:?} {}", r, x.as_str())),
+    }
+    match invoke("spin") {
+        (_, sgx_status_t::SGX_ERROR_WASM_BUDGET_EXCEEDED) => {},
+        (r, x) => return Err(format!("spin returned {:?} {}", r, x.as_str())),
+    }
+    match invoke("answer") {
+        (Ok(Some(BoundaryValue::I32(42))), sgx_status_t::SGX_SUCCESS) => {},
+        (r, x) => return Err(format!("answer returned {:?} {}", r, x.as_str())),
+    }
+
+    sgx_enclave_wasm_set_budget(0, enclave)
+}
+
 fn main() {
 
     let enclave = match init_enclave() {
@@ -620,6 +673,9 @@ fn main() {
         run_a_wast(&enclave, wfile).unwrap();
     }
 
+    println!("======================= testing execution budget =====================");
+    run_budget_test(&enclave).unwrap();
+
     enclave.destroy();
     println!("[+] run_wasm success...");
 
@@ -0,0 +1,294 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Execution budgets: a bound on the instructions a module may execute.
+//!
+//! wasmi cannot count instructions itself, so modules are instrumented
+//! when they are loaded: every function and every loop starts with a call
+//! to the host function `sgxwasm.gas`, passing the number of instructions
+//! up to the next metering point. `MeteredExternals` charges these calls
+//! (and every other host call) to the budget and traps once it is used
+//! up. The count is an upper bound: an instruction is charged when its
+//! function or loop iteration starts, even if a branch skips it.
+
+use std::prelude::v1::*;
+use std::mem;
+
+use parity_wasm::elements::{self, External, FunctionType, ImportEntry, ImportSection,
+                            Instruction, Internal, Section, Type, TypeSection, ValueType};
+
+use wasmi::{Externals, FuncInstance, FuncRef, RuntimeArgs, RuntimeValue, Signature, Trap,
+            TrapKind};
+
+/// Module and field name of the metering import added to modules.
+pub const BUDGET_MODULE_NAME: &str = "sgxwasm";
+pub const GAS_FUNC_NAME: &str = "gas";
+
+/// Host function index of `sgxwasm.gas`, beyond those of `SpecModule`
+/// and `WasiModule`.
+pub const GAS_FUNC_INDEX: usize = 0x200;
+
+/// The number of instructions a single invocation may execute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExecutionBudget {
+    max_instructions: u64,
+    remaining: u64,
+}
+
+impl ExecutionBudget {
+    pub fn new(max_instructions: u64) -> ExecutionBudget {
+        ExecutionBudget {
+            max_instructions: max_instructions,
+            remaining: max_instructions,
+        }
+    }
+
+    pub fn max_instructions(&self) -> u64 {
+        self.max_instructions
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Restores the full budget, for the next invocation.
+    pub fn refill(&mut self) {
+        self.remaining = self.max_instructions;
+    }
+
+    /// Charges `instructions`, returns false if they exceed what is left.
+    /// The budget is then exhausted.
+    fn charge(&mut self, instructions: u64) -> bool {
+        match self.remaining.checked_sub(instructions) {
+            Some(remaining) => {
+                self.remaining = remaining;
+                true
+            }
+            None => {
+                self.remaining = 0;
+                false
+            }
+        }
+    }
+}
+
+/// `Externals` that charge host calls to an execution budget before
+/// passing them on.
+pub struct MeteredExternals<'a, E: 'a + Externals> {
+    inner: &'a mut E,
+    budget: Option<&'a mut ExecutionBudget>,
+}
+
+impl<'a, E: Externals> MeteredExternals<'a, E> {
+    /// Without a budget, calls are passed on uncharged.
+    pub fn new(inner: &'a mut E, budget: Option<&'a mut ExecutionBudget>) -> Self {
+        MeteredExternals { inner: inner, budget: budget }
+    }
+}
+
+impl<'a, E: Externals> Externals for MeteredExternals<'a, E> {
+    fn invoke_index(
+        &mut self,
+        index: usize,
+        args: RuntimeArgs,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let cost = if index == GAS_FUNC_INDEX {
+            let instructions: u32 = args.nth_checked(0)?;
+            instructions as u64
+        } else {
+            1
+        };
+
+        if let Some(ref mut budget) = self.budget {
+            if !budget.charge(cost) {
+                return Err(Trap::new(TrapKind::Unreachable));
+            }
+        }
+
+        if index == GAS_FUNC_INDEX {
+            Ok(None)
+        } else {
+            self.inner.invoke_index(index, args)
+        }
+    }
+}
+
+/// Resolves `sgxwasm.gas`.
+pub fn resolve_gas_func(field_name: &str) -> Option<FuncRef> {
+    if field_name != GAS_FUNC_NAME {
+        return None;
+    }
+    const PARAMS: &[wasmi::ValueType] = &[wasmi::ValueType::I32];
+    let signature = Signature::new(PARAMS, None);
+    Some(FuncInstance::alloc_host(signature, GAS_FUNC_INDEX))
+}
+
+/// Adds the import of `sgxwasm.gas` to `module` and calls it at the start
+/// of every function and every loop.
+pub fn inject_gas_counter(mut module: elements::Module) -> Result<elements::Module, String> {
+    if module.import_section().map_or(false, |imports| {
+        imports.entries().iter().any(|entry| entry.module() == BUDGET_MODULE_NAME)
+    }) {
+        return Err(format!("module imports from reserved module {}", BUDGET_MODULE_NAME));
+    }
+
+    // the type (i32) -> ()
+    let gas_type = FunctionType::new(vec![ValueType::I32], vec![]);
+    let type_index = match module.type_section_mut() {
+        Some(types) => {
+            types.types_mut().push(Type::Function(gas_type));
+            types.types().len() - 1
+        }
+        None => {
+            let position = section_position(&module, 1);
+            module.sections_mut().insert(
+                position,
+                Section::Type(TypeSection::with_types(vec![Type::Function(gas_type)])),
+            );
+            0
+        }
+    };
+
+    // Appended after all other imports, the new function takes the first
+    // index after the imported functions; defined functions move up by one.
+    let gas_index = module.import_count(elements::ImportCountType::Function) as u32;
+    let import = ImportEntry::new(
+        BUDGET_MODULE_NAME.to_string(),
+        GAS_FUNC_NAME.to_string(),
+        External::Function(type_index as u32),
+    );
+    match module.import_section_mut() {
+        Some(imports) => imports.entries_mut().push(import),
+        None => {
+            let position = section_position(&module, 2);
+            module.sections_mut().insert(position, Section::Import(ImportSection::with_entries(vec![import])));
+        }
+    }
+
+    let shift = |index: u32| if index >= gas_index { index + 1 } else { index };
+    if let Some(code) = module.code_section_mut() {
+        for body in code.bodies_mut() {
+            let instructions = body.code_mut().elements_mut();
+            for instruction in instructions.iter_mut() {
+                if let Instruction::Call(ref mut index) = *instruction {
+                    *index = shift(*index);
+                }
+            }
+            let metered = meter(mem::replace(instructions, Vec::new()), gas_index);
+            *instructions = metered;
+        }
+    }
+    if let Some(exports) = module.export_section_mut() {
+        for entry in exports.entries_mut() {
+            if let Internal::Function(ref mut index) = *entry.internal_mut() {
+                *index = shift(*index);
+            }
+        }
+    }
+    if let Some(elements) = module.elements_section_mut() {
+        for segment in elements.entries_mut() {
+            for index in segment.members_mut() {
+                *index = shift(*index);
+            }
+        }
+    }
+    if let Some(start) = module.start_section() {
+        module.set_start_section(shift(start));
+    }
+    // the function names no longer match their indices
+    module.sections_mut().retain(|section| match *section {
+        Section::Name(_) => false,
+        Section::Custom(ref custom) => custom.name() != "name",
+        _ => true,
+    });
+
+    Ok(module)
+}
+
+// Where a section with the given id goes: after the sections with a
+// lower id.
+fn section_position(module: &elements::Module, id: u8) -> usize {
+    module.sections()
+        .iter()
+        .position(|section| section_id(section).map_or(false, |other| other > id))
+        .unwrap_or_else(|| module.sections().len())
+}
+
+fn section_id(section: &Section) -> Option<u8> {
+    Some(match *section {
+        Section::Type(_) => 1,
+        Section::Import(_) => 2,
+        Section::Function(_) => 3,
+        Section::Table(_) => 4,
+        Section::Memory(_) => 5,
+        Section::Global(_) => 6,
+        Section::Export(_) => 7,
+        Section::Start(_) => 8,
+        Section::Element(_) => 9,
+        Section::Code(_) => 10,
+        Section::Data(_) => 11,
+        _ => return None,
+    })
+}
+
+// Inserts a call of the gas function at the start of the function body and
+// of every loop, charging the instructions up to the next metering point.
+fn meter(instructions: Vec<Instruction>, gas_index: u32) -> Vec<Instruction> {
+    // Metered regions: the function body and the loops. `costs[r]` are the
+    // instructions of region r outside its nested loops.
+    let mut costs = vec![0_u32];
+    let mut loop_starts = Vec::new();
+    // for each open block: whether it is a loop, and the enclosing region
+    let mut blocks: Vec<(bool, usize)> = Vec::new();
+    let mut region = 0;
+
+    for (i, instruction) in instructions.iter().enumerate() {
+        costs[region] = costs[region].saturating_add(1);
+        match *instruction {
+            Instruction::Loop(_) => {
+                blocks.push((true, region));
+                region = costs.len();
+                costs.push(0);
+                loop_starts.push(i + 1);
+            }
+            Instruction::Block(_) | Instruction::If(_) => blocks.push((false, region)),
+            Instruction::End => {
+                if let Some((_, outer)) = blocks.pop() {
+                    region = outer;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let gas = |cost: u32| vec![Instruction::I32Const(cost as i32), Instruction::Call(gas_index)];
+    let mut metered = Vec::with_capacity(instructions.len() + 2 * costs.len());
+    metered.extend(gas(costs[0]));
+    let mut next_loop = 0;
+    for (i, instruction) in instructions.into_iter().enumerate() {
+        while next_loop < loop_starts.len() && loop_starts[next_loop] == i {
+            metered.extend(gas(costs[next_loop + 1]));
+            next_loop += 1;
+        }
+        metered.push(instruction);
+    }
+    metered
+}
@@ -25,6 +25,8 @@ extern crate sgx_types;
 #[cfg(not(target_env = "sgx"))]
 #[macro_use]
 extern crate sgx_tstd as std;
+extern crate sgx_rand;
+extern crate parity_wasm;
 extern crate wasmi;
 extern crate wabt;
 
@@ -62,6 +64,11 @@ use wasmi::{ModuleInstance,
 use wabt::script;
 use wabt::script::{Value};
 
+mod budget;
+pub use budget::{ExecutionBudget, MeteredExternals, BUDGET_MODULE_NAME};
+mod wasi;
+pub use wasi::{ProcExit, WasiConfig, WasiModule, WASI_MODULE_NAME};
+
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
@@ -88,6 +95,11 @@ pub enum SgxWasmAction {
         name: Option<String>,
         as_name: String,
     },
+    /// Limits each invocation to `instructions`, 0 removes the limit.
+    /// Only modules loaded afterwards are metered.
+    SetBudget {
+        instructions: u64,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -135,6 +147,7 @@ pub struct SpecModule {
     global_i32: GlobalRef,
     global_f32: GlobalRef,
     global_f64: GlobalRef,
+    wasi: Option<WasiModule>,
 }
 
 impl SpecModule {
@@ -145,8 +158,15 @@ impl SpecModule {
             global_i32: GlobalInstance::alloc(RuntimeValue::I32(666), false),
             global_f32: GlobalInstance::alloc(RuntimeValue::F32(666.0.into()), false),
             global_f64: GlobalInstance::alloc(RuntimeValue::F64(666.0.into()), false),
+            wasi: None,
         }
     }
+
+    /// The WASI host functions, if the driver was created with a
+    /// `WasiConfig`.
+    pub fn wasi(&mut self) -> Option<&mut WasiModule> {
+        self.wasi.as_mut()
+    }
 }
 
 pub fn spec_to_runtime_value(value: Value) -> RuntimeValue {
@@ -192,6 +212,9 @@ impl Externals for SpecModule {
                 println!("print: {:?}", args);
                 Ok(None)
             }
+            _ if index >= wasi::WASI_FUNC_BASE && self.wasi.is_some() => {
+                self.wasi.as_mut().unwrap().invoke_index(index, args)
+            }
             _ => panic!("SpecModule doesn't provide function at index {}", index),
         }
     }
@@ -278,6 +301,7 @@ pub struct SpecDriver {
     spec_module: SpecModule,
     instances: HashMap<String, ModuleRef>,
     last_module: Option<ModuleRef>,
+    budget: Option<ExecutionBudget>,
 }
 
 impl SpecDriver {
@@ -286,13 +310,40 @@ impl SpecDriver {
             spec_module: SpecModule::new(),
             instances: HashMap::new(),
             last_module: None,
+            budget: None,
         }
     }
 
+    /// A driver that also resolves the `wasi_snapshot_preview1` imports.
+    pub fn with_wasi(config: WasiConfig) -> SpecDriver {
+        let mut driver = SpecDriver::new();
+        driver.spec_module.wasi = Some(WasiModule::new(config));
+        driver
+    }
+
     pub fn spec_module(&mut self) -> &mut SpecModule {
         &mut self.spec_module
     }
 
+    /// Sets the execution budget of each invocation. Modules are metered
+    /// if a budget is set when they are loaded.
+    pub fn set_budget(&mut self, budget: Option<ExecutionBudget>) {
+        self.budget = budget;
+    }
+
+    pub fn budget(&self) -> Option<&ExecutionBudget> {
+        self.budget.as_ref()
+    }
+
+    /// The externals for a new invocation: the spec module, charging a
+    /// refilled budget if there is one.
+    pub fn externals(&mut self) -> MeteredExternals<SpecModule> {
+        if let Some(ref mut budget) = self.budget {
+            budget.refill();
+        }
+        MeteredExternals::new(&mut self.spec_module, self.budget.as_mut())
+    }
+
     pub fn add_module(&mut self, name: Option<String>, module: ModuleRef) {
         self.last_module = Some(module.clone());
         if let Some(name) = name {
@@ -335,6 +386,12 @@ impl ImportResolver for SpecDriver {
     ) -> Result<FuncRef, InterpreterError> {
         if module_name == "spectest" {
             self.spec_module.resolve_func(field_name, func_type)
+        } else if module_name == WASI_MODULE_NAME && self.spec_module.wasi.is_some() {
+            self.spec_module.wasi.as_ref().unwrap().resolve_func(field_name, func_type)
+        } else if module_name == BUDGET_MODULE_NAME {
+            budget::resolve_gas_func(field_name).ok_or_else(|| {
+                InterpreterError::Instantiation(format!("Unknown host func import {}", field_name))
+            })
         } else {
             self.module(module_name)?
                 .resolve_func(field_name, func_type)
@@ -388,6 +445,18 @@ pub fn try_load_module(wasm: &[u8]) -> Result<Module, Error> {
     Module::from_buffer(wasm).map_err(|e| Error::Load(e.to_string()))
 }
 
+/// Loads a module, instrumented for metering if `spec_driver` has an
+/// execution budget.
+pub fn prepare_module(wasm: &[u8], spec_driver: &SpecDriver) -> Result<Module, Error> {
+    if spec_driver.budget().is_none() {
+        return try_load_module(wasm);
+    }
+    let module = parity_wasm::elements::deserialize_buffer(wasm)
+        .map_err(|e| Error::Load(e.to_string()))?;
+    let module = budget::inject_gas_counter(module).map_err(Error::Load)?;
+    Module::from_parity_wasm_module(module).map_err(|e| Error::Load(e.to_string()))
+}
+
 pub fn try_load(wasm: &[u8], spec_driver: &mut SpecDriver) -> Result<(), Error> {
     let module = try_load_module(wasm)?;
     let instance = ModuleInstance::new(&module, &ImportsBuilder::default())?;
@@ -398,10 +467,16 @@ pub fn try_load(wasm: &[u8], spec_driver: &mut SpecDriver) -> Result<(), Error>
 }
 
 pub fn load_module(wasm: &[u8], name: &Option<String>, spec_driver: &mut SpecDriver) -> Result<ModuleRef, Error> {
-    let module = try_load_module(wasm)?;
-    let instance = ModuleInstance::new(&module, spec_driver)
-        .map_err(|e| Error::Load(e.to_string()))?
-        .run_start(spec_driver.spec_module())
+    let module = prepare_module(wasm, spec_driver)?;
+    let not_started = ModuleInstance::new(&module, spec_driver)
+        .map_err(|e| Error::Load(e.to_string()))?;
+    // WASI functions access the memory of the module, also in its start
+    // function.
+    if let Some(wasi) = spec_driver.spec_module().wasi() {
+        wasi.bind_memory(not_started.not_started_instance());
+    }
+    let instance = not_started
+        .run_start(&mut spec_driver.externals())
         .map_err(|trap| Error::Start(trap))?;
 
     let module_name = name.clone();
@@ -0,0 +1,382 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! A small subset of WASI (`wasi_snapshot_preview1`), enough to run a
+//! C hello-world built with wasi-sdk.
+//!
+//! There is no file system: descriptors 0, 1 and 2 are the only open
+//! ones. Standard input is an in-memory buffer given in `WasiConfig`,
+//! standard output and error go to the enclave's stdout and stderr.
+//! Every pointer a module passes is checked against the bounds of its
+//! linear memory before it is read or written.
+
+use std::prelude::v1::*;
+use std::fmt;
+use std::io::{self, Write};
+use std::time::UNIX_EPOCH;
+use std::time::SystemTime;
+use std::untrusted::time::SystemTimeEx;
+
+use sgx_rand::{Rng, SgxRng};
+
+use wasmi::memory_units::Bytes;
+use wasmi::{Error as InterpreterError,
+            Externals,
+            FuncInstance,
+            FuncRef,
+            HostError,
+            MemoryRef,
+            ModuleImportResolver,
+            ModuleRef,
+            RuntimeArgs,
+            RuntimeValue,
+            Signature,
+            Trap,
+            TrapKind,
+            ValueType,
+};
+
+/// Module name of the WASI imports.
+pub const WASI_MODULE_NAME: &str = "wasi_snapshot_preview1";
+
+/// Host function indices of WASI start here, so that they do not clash
+/// with the ones of `SpecModule`.
+pub const WASI_FUNC_BASE: usize = 0x100;
+
+const ARGS_GET: usize = WASI_FUNC_BASE;
+const ARGS_SIZES_GET: usize = WASI_FUNC_BASE + 1;
+const ENVIRON_GET: usize = WASI_FUNC_BASE + 2;
+const ENVIRON_SIZES_GET: usize = WASI_FUNC_BASE + 3;
+const CLOCK_TIME_GET: usize = WASI_FUNC_BASE + 4;
+const FD_CLOSE: usize = WASI_FUNC_BASE + 5;
+const FD_FDSTAT_GET: usize = WASI_FUNC_BASE + 6;
+const FD_READ: usize = WASI_FUNC_BASE + 7;
+const FD_SEEK: usize = WASI_FUNC_BASE + 8;
+const FD_WRITE: usize = WASI_FUNC_BASE + 9;
+const PROC_EXIT: usize = WASI_FUNC_BASE + 10;
+const RANDOM_GET: usize = WASI_FUNC_BASE + 11;
+
+// errno values of wasi_snapshot_preview1
+const ERRNO_SUCCESS: i32 = 0;
+const ERRNO_BADF: i32 = 8;
+const ERRNO_FAULT: i32 = 21;
+const ERRNO_INVAL: i32 = 28;
+const ERRNO_IO: i32 = 29;
+const ERRNO_SPIPE: i32 = 70;
+
+const CLOCKID_REALTIME: u32 = 0;
+const CLOCKID_MONOTONIC: u32 = 1;
+
+const FILETYPE_CHARACTER_DEVICE: u8 = 2;
+const RIGHTS_FD_READ: u64 = 1 << 1;
+const RIGHTS_FD_WRITE: u64 = 1 << 6;
+
+const STDIN: u32 = 0;
+const STDOUT: u32 = 1;
+const STDERR: u32 = 2;
+
+/// Configuration of the WASI host functions.
+#[derive(Clone, Debug, Default)]
+pub struct WasiConfig {
+    /// Data a module reads from descriptor 0.
+    pub stdin: Vec<u8>,
+}
+
+/// The trap raised by `proc_exit`, which ends the execution of a module.
+#[derive(Debug)]
+pub struct ProcExit(pub i32);
+
+impl fmt::Display for ProcExit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "proc_exit({})", self.0)
+    }
+}
+
+impl HostError for ProcExit {}
+
+/// The WASI host functions of a module.
+///
+/// They work on the exported `memory` of the module, which has to be
+/// bound with `bind_memory` before the module runs.
+pub struct WasiModule {
+    stdin: Vec<u8>,
+    stdin_pos: usize,
+    memory: Option<MemoryRef>,
+    exit_code: Option<i32>,
+}
+
+impl WasiModule {
+    pub fn new(config: WasiConfig) -> WasiModule {
+        WasiModule {
+            stdin: config.stdin,
+            stdin_pos: 0,
+            memory: None,
+            exit_code: None,
+        }
+    }
+
+    /// Binds the exported memory of `instance`, if it has one.
+    pub fn bind_memory(&mut self, instance: &ModuleRef) {
+        self.memory = instance
+            .export_by_name("memory")
+            .and_then(|export| export.as_memory().cloned());
+    }
+
+    /// The code passed to `proc_exit`, if the module called it.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    fn memory(&self) -> Result<&MemoryRef, i32> {
+        self.memory.as_ref().ok_or(ERRNO_FAULT)
+    }
+
+    // Checks that `len` bytes at `ptr` lie within the linear memory.
+    fn check_bounds(&self, ptr: u32, len: usize) -> Result<(), i32> {
+        let size: Bytes = self.memory()?.current_size().into();
+        match (ptr as usize).checked_add(len) {
+            Some(end) if end <= size.0 => Ok(()),
+            _ => Err(ERRNO_FAULT),
+        }
+    }
+
+    fn read_bytes(&self, ptr: u32, len: usize) -> Result<Vec<u8>, i32> {
+        self.check_bounds(ptr, len)?;
+        self.memory()?.get(ptr, len).map_err(|_| ERRNO_FAULT)
+    }
+
+    fn write_bytes(&self, ptr: u32, bytes: &[u8]) -> Result<(), i32> {
+        self.check_bounds(ptr, bytes.len())?;
+        self.memory()?.set(ptr, bytes).map_err(|_| ERRNO_FAULT)
+    }
+
+    fn read_u32(&self, ptr: u32) -> Result<u32, i32> {
+        let bytes = self.read_bytes(ptr, 4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn write_u32(&self, ptr: u32, value: u32) -> Result<(), i32> {
+        self.write_bytes(ptr, &value.to_le_bytes())
+    }
+
+    fn write_u64(&self, ptr: u32, value: u64) -> Result<(), i32> {
+        self.write_bytes(ptr, &value.to_le_bytes())
+    }
+
+    // Reads an array of `ciovec`/`iovec`: pairs of a buffer pointer and a
+    // buffer length.
+    fn read_iovecs(&self, iovs: u32, iovs_len: u32) -> Result<Vec<(u32, u32)>, i32> {
+        let mut iovecs = Vec::new();
+        for i in 0..iovs_len {
+            let iov = i.checked_mul(8)
+                .and_then(|offset| iovs.checked_add(offset))
+                .ok_or(ERRNO_FAULT)?;
+            let buf = self.read_u32(iov)?;
+            let buf_len = self.read_u32(iov + 4)?;
+            self.check_bounds(buf, buf_len as usize)?;
+            iovecs.push((buf, buf_len));
+        }
+        Ok(iovecs)
+    }
+
+    // No arguments and no environment variables: both counts and the
+    // buffer sizes are zero.
+    fn sizes_get(&self, count_ptr: u32, buf_size_ptr: u32) -> Result<(), i32> {
+        self.write_u32(count_ptr, 0)?;
+        self.write_u32(buf_size_ptr, 0)
+    }
+
+    fn clock_time_get(&self, clock_id: u32, time_ptr: u32) -> Result<(), i32> {
+        // Both clocks come from the untrusted system time: the host can
+        // make them jump or run backwards.
+        match clock_id {
+            CLOCKID_REALTIME | CLOCKID_MONOTONIC => (),
+            _ => return Err(ERRNO_INVAL),
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| ERRNO_IO)?;
+        let nanos = now.as_secs()
+            .checked_mul(1_000_000_000)
+            .and_then(|n| n.checked_add(now.subsec_nanos() as u64))
+            .ok_or(ERRNO_IO)?;
+        self.write_u64(time_ptr, nanos)
+    }
+
+    fn fd_fdstat_get(&self, fd: u32, stat_ptr: u32) -> Result<(), i32> {
+        let rights = match fd {
+            STDIN => RIGHTS_FD_READ,
+            STDOUT | STDERR => RIGHTS_FD_WRITE,
+            _ => return Err(ERRNO_BADF),
+        };
+        // struct fdstat { filetype: u8, flags: u16, rights_base: u64,
+        //                 rights_inheriting: u64 }
+        let mut stat = [0_u8; 24];
+        stat[0] = FILETYPE_CHARACTER_DEVICE;
+        stat[8..16].copy_from_slice(&rights.to_le_bytes());
+        self.write_bytes(stat_ptr, &stat)
+    }
+
+    fn fd_read(&mut self, fd: u32, iovs: u32, iovs_len: u32, nread_ptr: u32) -> Result<(), i32> {
+        if fd != STDIN {
+            return Err(ERRNO_BADF);
+        }
+        let mut nread = 0_u32;
+        for (buf, buf_len) in self.read_iovecs(iovs, iovs_len)? {
+            let rest = &self.stdin[self.stdin_pos..];
+            let n = rest.len().min(buf_len as usize);
+            self.write_bytes(buf, &rest[..n])?;
+            self.stdin_pos += n;
+            nread += n as u32;
+        }
+        self.write_u32(nread_ptr, nread)
+    }
+
+    fn fd_write(&self, fd: u32, iovs: u32, iovs_len: u32, nwritten_ptr: u32) -> Result<(), i32> {
+        let mut out: Box<dyn Write> = match fd {
+            STDOUT => Box::new(io::stdout()),
+            STDERR => Box::new(io::stderr()),
+            _ => return Err(ERRNO_BADF),
+        };
+        let iovecs = self.read_iovecs(iovs, iovs_len)?;
+        // Like writev, reject a total that does not fit before writing anything.
+        let nwritten = iovecs.iter()
+            .try_fold(0_u32, |total, &(_, buf_len)| total.checked_add(buf_len))
+            .ok_or(ERRNO_INVAL)?;
+        for (buf, buf_len) in iovecs {
+            let data = self.read_bytes(buf, buf_len as usize)?;
+            out.write_all(&data).map_err(|_| ERRNO_IO)?;
+        }
+        self.write_u32(nwritten_ptr, nwritten)
+    }
+
+    fn random_get(&self, buf: u32, buf_len: u32) -> Result<(), i32> {
+        self.check_bounds(buf, buf_len as usize)?;
+        let mut rng = SgxRng::new().map_err(|_| ERRNO_IO)?;
+        let mut bytes = vec![0_u8; buf_len as usize];
+        rng.fill_bytes(&mut bytes);
+        self.write_bytes(buf, &bytes)
+    }
+
+    fn signature(index: usize) -> Signature {
+        use wasmi::ValueType::{I32, I64};
+        let (params, return_type): (&'static [ValueType], _) = match index {
+            ARGS_GET | ARGS_SIZES_GET | ENVIRON_GET | ENVIRON_SIZES_GET => (&[I32, I32], Some(I32)),
+            CLOCK_TIME_GET => (&[I32, I64, I32], Some(I32)),
+            FD_CLOSE => (&[I32], Some(I32)),
+            FD_FDSTAT_GET => (&[I32, I32], Some(I32)),
+            FD_READ | FD_WRITE => (&[I32, I32, I32, I32], Some(I32)),
+            FD_SEEK => (&[I32, I64, I32, I32], Some(I32)),
+            PROC_EXIT => (&[I32], None),
+            RANDOM_GET => (&[I32, I32], Some(I32)),
+            _ => unreachable!(),
+        };
+        Signature::new(params, return_type)
+    }
+}
+
+fn errno(result: Result<(), i32>) -> RuntimeValue {
+    RuntimeValue::I32(match result {
+        Ok(()) => ERRNO_SUCCESS,
+        Err(errno) => errno,
+    })
+}
+
+impl Externals for WasiModule {
+    fn invoke_index(
+        &mut self,
+        index: usize,
+        args: RuntimeArgs,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let result = match index {
+            ARGS_SIZES_GET | ENVIRON_SIZES_GET => {
+                self.sizes_get(args.nth_checked(0)?, args.nth_checked(1)?)
+            }
+            // nothing to copy
+            ARGS_GET | ENVIRON_GET => Ok(()),
+            CLOCK_TIME_GET => {
+                let _precision: u64 = args.nth_checked(1)?;
+                self.clock_time_get(args.nth_checked(0)?, args.nth_checked(2)?)
+            }
+            FD_CLOSE => {
+                let fd: u32 = args.nth_checked(0)?;
+                if fd <= STDERR { Ok(()) } else { Err(ERRNO_BADF) }
+            }
+            FD_FDSTAT_GET => self.fd_fdstat_get(args.nth_checked(0)?, args.nth_checked(1)?),
+            FD_READ => self.fd_read(args.nth_checked(0)?,
+                                    args.nth_checked(1)?,
+                                    args.nth_checked(2)?,
+                                    args.nth_checked(3)?),
+            FD_SEEK => {
+                let fd: u32 = args.nth_checked(0)?;
+                if fd <= STDERR { Err(ERRNO_SPIPE) } else { Err(ERRNO_BADF) }
+            }
+            FD_WRITE => self.fd_write(args.nth_checked(0)?,
+                                      args.nth_checked(1)?,
+                                      args.nth_checked(2)?,
+                                      args.nth_checked(3)?),
+            PROC_EXIT => {
+                let code: i32 = args.nth_checked(0)?;
+                self.exit_code = Some(code);
+                return Err(Trap::new(TrapKind::Host(Box::new(ProcExit(code)))));
+            }
+            RANDOM_GET => self.random_get(args.nth_checked(0)?, args.nth_checked(1)?),
+            _ => panic!("WasiModule doesn't provide function at index {}", index),
+        };
+        Ok(Some(errno(result)))
+    }
+}
+
+impl ModuleImportResolver for WasiModule {
+    fn resolve_func(
+        &self,
+        field_name: &str,
+        func_type: &Signature,
+    ) -> Result<FuncRef, InterpreterError> {
+        let index = match field_name {
+            "args_get" => ARGS_GET,
+            "args_sizes_get" => ARGS_SIZES_GET,
+            "environ_get" => ENVIRON_GET,
+            "environ_sizes_get" => ENVIRON_SIZES_GET,
+            "clock_time_get" => CLOCK_TIME_GET,
+            "fd_close" => FD_CLOSE,
+            "fd_fdstat_get" => FD_FDSTAT_GET,
+            "fd_read" => FD_READ,
+            "fd_seek" => FD_SEEK,
+            "fd_write" => FD_WRITE,
+            "proc_exit" => PROC_EXIT,
+            "random_get" => RANDOM_GET,
+            _ => {
+                return Err(InterpreterError::Instantiation(format!(
+                    "Unsupported WASI function {}",
+                    field_name
+                )));
+            }
+        };
+
+        let signature = WasiModule::signature(index);
+        if func_type.params() != signature.params()
+            || func_type.return_type() != signature.return_type() {
+            return Err(InterpreterError::Instantiation(format!(
+                "WASI function {} has signature {:?}, expected {:?}",
+                field_name, func_type, signature
+            )));
+        }
+
+        Ok(FuncInstance::alloc_host(signature, index))
+    }
+}
@@ -36,7 +36,7 @@ use std::ptr;
 extern crate wasmi;
 extern crate sgxwasm;
 
-use sgxwasm::{SpecDriver, boundary_value_to_runtime_value, result_covert};
+use sgxwasm::{ExecutionBudget, SpecDriver, WasiConfig, boundary_value_to_runtime_value, result_covert};
 
 use sgx_types::*;
 use std::slice;
@@ -46,6 +46,11 @@ use wasmi::{ModuleInstance, ImportsBuilder, RuntimeValue, Error as InterpreterEr
 extern crate serde;
 extern crate serde_json;
 
+// Stack the interpreter is guaranteed to start with. Deeply nested wasm
+// calls (and host calls re-entering the interpreter) recurse on the enclave
+// stack, whose StackMaxSize is 1 MB in Enclave.config.xml.
+const WASM_MIN_STACK: usize = 0x40000;
+
 lazy_static!{
     static ref SPECDRIVER: SgxMutex<SpecDriver> = SgxMutex::new(SpecDriver::new());
 }
@@ -60,10 +65,27 @@ fn sgxwasm_init() -> sgx_status_t {
 
 fn wasm_invoke(module : Option<String>, field : String, args : Vec<RuntimeValue>)
               -> Result<Option<RuntimeValue>, InterpreterError> {
+    sgx_check_stack!(WASM_MIN_STACK);
     let mut program = SPECDRIVER.lock().unwrap();
     let module = program.module_or_last(module.as_ref().map(|x| x.as_ref()))
                         .expect(&format!("Expected program to have loaded module {:?}", module));
-    module.invoke_export(&field, &args, program.spec_module())
+    module.invoke_export(&field, &args, &mut program.externals())
+}
+
+// Whether the last invocation used up the execution budget.
+fn wasm_budget_exhausted() -> bool {
+    let program = SPECDRIVER.lock().unwrap();
+    program.budget().map_or(false, |budget| budget.is_exhausted())
+}
+
+fn wasm_set_budget(instructions: u64) {
+    let mut program = SPECDRIVER.lock().unwrap();
+    let budget = if instructions == 0 {
+        None
+    } else {
+        Some(ExecutionBudget::new(instructions))
+    };
+    program.set_budget(budget);
 }
 
 fn wasm_get(module : Option<String>, field : String)
@@ -115,10 +137,11 @@ fn wasm_try_load(wasm: Vec<u8>) -> Result<(), InterpreterError> {
 fn wasm_load_module(name: Option<String>, module: Vec<u8>)
                     -> Result<(), InterpreterError> {
     let ref mut spec_driver = SPECDRIVER.lock().unwrap();
-    let module = try_load_module(&module[..])?;
+    let module = sgxwasm::prepare_module(&module[..], spec_driver)
+        .map_err(|e| InterpreterError::Instantiation(format!("Module::from_buffer error {:?}", e)))?;
     let instance = ModuleInstance::new(&module, &**spec_driver)
         .map_err(|e| InterpreterError::Instantiation(format!("ModuleInstance::new error on {:?}", e)))?
-        .run_start(spec_driver.spec_module())
+        .run_start(&mut spec_driver.externals())
         .map_err(|trap| InterpreterError::Instantiation(format!("ModuleInstance::run_start error on {:?}", trap)))?;
 
     spec_driver.add_module(name, instance.clone());
@@ -155,6 +178,11 @@ fn sgxwasm_run_action(req_bin : *const u8, req_length: usize,
                 Ok(_) => {
                     return_status = sgx_status_t::SGX_SUCCESS;
                 },
+                // The module was stopped, the host may tear down the
+                // enclave or go on with a new invocation.
+                Err(_) if wasm_budget_exhausted() => {
+                    return_status = sgx_status_t::SGX_ERROR_WASM_BUDGET_EXCEEDED;
+                },
                 Err(_) => {
                     return_status = sgx_status_t::SGX_ERROR_WASM_INTERPRETER_ERROR;
                }
@@ -208,6 +236,12 @@ fn sgxwasm_run_action(req_bin : *const u8, req_length: usize,
                     return_status = sgx_status_t::SGX_ERROR_WASM_REGISTER_ERROR;
                 }
             }
+        },
+        sgxwasm::SgxWasmAction::SetBudget{instructions} => {
+            wasm_set_budget(instructions);
+            let r: Result<(), InterpreterError> = Ok(());
+            response = serde_json::to_string(&r).unwrap();
+            return_status = sgx_status_t::SGX_SUCCESS;
         }
     }
 
@@ -227,3 +261,43 @@ fn sgxwasm_run_action(req_bin : *const u8, req_length: usize,
     }
 }
 
+/// Runs the `_start` function of a WASI command module, with `stdin_buf` as its
+/// standard input, and stores its exit code.
+#[no_mangle]
+pub extern "C"
+fn sgxwasm_run_wasi(wasm: *const u8, wasm_len: usize,
+                    stdin_buf: *const u8, stdin_len: usize,
+                    exit_code: *mut i32) -> sgx_status_t {
+    sgx_check_stack!(WASM_MIN_STACK);
+
+    let wasm = unsafe { slice::from_raw_parts(wasm, wasm_len) };
+    let stdin = if stdin_len == 0 {
+        Vec::new()
+    } else {
+        unsafe { slice::from_raw_parts(stdin_buf, stdin_len) }.to_vec()
+    };
+
+    // A driver of its own: the WASI state must not leak into the modules
+    // of the spec tests, nor from one run into the next.
+    let mut driver = SpecDriver::with_wasi(WasiConfig { stdin: stdin });
+    let instance = match sgxwasm::load_module(wasm, &None, &mut driver) {
+        Ok(instance) => instance,
+        Err(e) => {
+            println!("Cannot load WASI module: {:?}", e);
+            return sgx_status_t::SGX_ERROR_WASM_LOAD_MODULE_ERROR;
+        }
+    };
+
+    let result = instance.invoke_export("_start", &[], &mut driver.externals());
+    // proc_exit ends the module with a trap, its exit code is what counts.
+    let code = match (result, driver.spec_module().wasi().and_then(|w| w.exit_code())) {
+        (_, Some(code)) => code,
+        (Ok(_), None) => 0,
+        (Err(e), None) => {
+            println!("WASI module failed: {:?}", e);
+            return sgx_status_t::SGX_ERROR_WASM_INTERPRETER_ERROR;
+        }
+    };
+    unsafe { *exit_code = code; }
+    sgx_status_t::SGX_SUCCESS
+}
@@ -16,6 +16,8 @@ use std::thread;
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::{env, fs};
 
+pub mod reproducible;
+
 /// A helper macro to `unwrap` a result except also print out details like:
 ///
 /// * The file/line of the panic
@@ -169,7 +171,7 @@ pub fn rerun_if_changed_anything_in_dir(dir: &Path, filter: &[&str]) {
         .read_dir()
         .unwrap()
         .map(|e| e.unwrap())
-        .filter(|e| !is_in_filter_list(&*e.file_name(), filter))
+        .filter(|e| !is_in_filter_list(&e.file_name(), filter))
         .collect::<Vec<_>>();
     while let Some(entry) = stack.pop() {
         let path = entry.path();
@@ -178,7 +180,7 @@ pub fn rerun_if_changed_anything_in_dir(dir: &Path, filter: &[&str]) {
                 path.read_dir()
                     .unwrap()
                     .map(|e| e.unwrap())
-                    .filter(|e| !is_in_filter_list(&*e.file_name(), filter)),
+                    .filter(|e| !is_in_filter_list(&e.file_name(), filter)),
             );
         } else {
             println!("cargo:rerun-if-changed={}", path.display());
@@ -0,0 +1,233 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Helpers for build scripts of enclaves that have to be reproducible, i.e.
+//! whose MRENCLAVE must be the same for everyone building the same source.
+//!
+//! A build script can warn about settings that break this, and embed the
+//! expected measurement of an enclave for a hardcoded attestation policy:
+//!
+//! ```no_run
+//! use std::path::{Path, PathBuf};
+//! use std::{env, fs};
+//! use sgx_build_helper::reproducible::{check_reproducible_env, measure_mrenclave};
+//!
+//! check_reproducible_env();
+//! let mrenclave = measure_mrenclave(
+//!     Path::new("/opt/intel/sgxsdk/bin/x64/sgx_sign"),
+//!     Path::new("../bin/enclave.so"),
+//! );
+//! let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("mrenclave.rs");
+//! fs::write(out, format!("pub const MRENCLAVE: [u8; 32] = {:?};\n", mrenclave)).unwrap();
+//! ```
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::{run_suppressed, t};
+
+// `sgx_sign gendata` writes the SIGSTRUCT header (128 bytes) followed by
+// its body, in which the enclave hash starts at offset 60.
+const SIGDATA_ENCLAVE_HASH: usize = 128 + 60;
+
+/// Emits a `cargo:warning=` for every setting found that makes the enclave
+/// binary, and so its MRENCLAVE, depend on more than the source:
+///
+/// * no `rust-toolchain.toml` or `rust-toolchain` file pinning the Rust
+///   toolchain to a version or a dated nightly,
+/// * symbols, with their build paths, left in the binary because the
+///   rustflags lack `-Zstrip=symbols`,
+/// * no `SOURCE_DATE_EPOCH` to replace the build time that C compilers
+///   embed through `__DATE__` and `__TIME__`,
+/// * `ld.gold` as the linker, rather than `lld` or GNU `ld`.
+pub fn check_reproducible_env() {
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    let manifest_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default());
+    match find_toolchain_file(&manifest_dir) {
+        Some(file) => {
+            println!("cargo:rerun-if-changed={}", file.display());
+            let contents = fs::read_to_string(&file).unwrap_or_default();
+            if !toolchain_is_pinned(&contents) {
+                warn(&format!(
+                    "{} does not pin a Rust version or a dated nightly",
+                    file.display()
+                ));
+            }
+        }
+        None => warn("no rust-toolchain.toml pins the Rust toolchain"),
+    }
+
+    let flags = rustflags();
+    if !has_codegen_flag(&flags, "strip=symbols") {
+        warn("symbols are not stripped, build with RUSTFLAGS=-Zstrip=symbols");
+    }
+
+    if env::var_os("SOURCE_DATE_EPOCH").is_none() {
+        warn("SOURCE_DATE_EPOCH is not set, C code may embed the build time");
+    }
+
+    if let Some(version) = linker_version(&flags) {
+        if version.contains("GNU gold") {
+            warn(&format!(
+                "the linker is ld.gold ({}), link with lld or GNU ld instead",
+                version
+            ));
+        }
+    }
+}
+
+/// Returns the MRENCLAVE of the enclave in `enclave_so`, as computed by
+/// `sgx_sign gendata` at `sgxsign_path`.
+///
+/// The enclave does not need to be signed. Exits the build script if
+/// `sgx_sign` fails.
+pub fn measure_mrenclave(sgxsign_path: &Path, enclave_so: &Path) -> [u8; 32] {
+    let out_dir = env::var_os("OUT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir);
+    let stem = enclave_so.file_stem().unwrap_or_default().to_string_lossy();
+    let sigdata = out_dir.join(format!("{}.sigdata", stem));
+
+    run_suppressed(
+        Command::new(sgxsign_path)
+            .arg("gendata")
+            .arg("-enclave")
+            .arg(enclave_so)
+            .arg("-out")
+            .arg(&sigdata),
+    );
+    let data = t!(fs::read(&sigdata), sigdata);
+    match enclave_hash(&data) {
+        Some(hash) => hash,
+        None => panic!(
+            "{} is too short for signing data ({} bytes)",
+            sigdata.display(),
+            data.len()
+        ),
+    }
+}
+
+fn enclave_hash(sigdata: &[u8]) -> Option<[u8; 32]> {
+    let hash = sigdata.get(SIGDATA_ENCLAVE_HASH..SIGDATA_ENCLAVE_HASH + 32)?;
+    let mut mrenclave = [0_u8; 32];
+    mrenclave.copy_from_slice(hash);
+    Some(mrenclave)
+}
+
+fn find_toolchain_file(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .flat_map(|d| [d.join("rust-toolchain.toml"), d.join("rust-toolchain")])
+        .find(|f| f.is_file())
+}
+
+// Accepts both the TOML format and the legacy one-line format.
+fn toolchain_is_pinned(contents: &str) -> bool {
+    let channel = contents
+        .lines()
+        .map(str::trim)
+        .find_map(|l| l.strip_prefix("channel"))
+        .map(|l| {
+            l.trim_start()
+                .trim_start_matches('=')
+                .trim()
+                .trim_matches('"')
+        })
+        .unwrap_or_else(|| contents.trim());
+    !channel.is_empty() && !matches!(channel, "stable" | "beta" | "nightly")
+}
+
+fn rustflags() -> Vec<String> {
+    if let Ok(flags) = env::var("CARGO_ENCODED_RUSTFLAGS") {
+        flags
+            .split('\x1f')
+            .filter(|f| !f.is_empty())
+            .map(String::from)
+            .collect()
+    } else {
+        env::var("RUSTFLAGS")
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(String::from)
+            .collect()
+    }
+}
+
+// Matches `-Zflag`, `-Cflag` and the same with a space after `-Z` or `-C`.
+fn has_codegen_flag(flags: &[String], flag: &str) -> bool {
+    flags.iter().enumerate().any(|(i, f)| {
+        f.strip_prefix("-Z").or_else(|| f.strip_prefix("-C")) == Some(flag)
+            || (f == flag && i > 0 && matches!(flags[i - 1].as_str(), "-Z" | "-C"))
+    })
+}
+
+// The first line of `--version` of the linker picked by `-fuse-ld=`, `LD`
+// or else `ld`.
+fn linker_version(flags: &[String]) -> Option<String> {
+    let linker = flags
+        .iter()
+        .find_map(|f| f.split("-fuse-ld=").nth(1))
+        .map(|l| format!("ld.{}", l))
+        .or_else(|| env::var("LD").ok())
+        .unwrap_or_else(|| "ld".to_string());
+    let output = Command::new(linker).arg("--version").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout.lines().next().map(String::from)
+}
+
+fn warn(message: &str) {
+    println!("cargo:warning=reproducible build: {}", message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toolchain_pinning() {
+        assert!(toolchain_is_pinned("nightly-2022-10-22\n"));
+        assert!(toolchain_is_pinned("[toolchain]\nchannel = \"1.65.0\"\n"));
+        assert!(!toolchain_is_pinned("nightly\n"));
+        assert!(!toolchain_is_pinned("[toolchain]\nchannel = \"stable\"\n"));
+        assert!(!toolchain_is_pinned(""));
+    }
+
+    #[test]
+    fn strip_flag() {
+        let flags = |s: &str| s.split_whitespace().map(String::from).collect::<Vec<_>>();
+        assert!(has_codegen_flag(&flags("-Zstrip=symbols"), "strip=symbols"));
+        assert!(has_codegen_flag(
+            &flags("-C opt-level=3 -C strip=symbols"),
+            "strip=symbols"
+        ));
+        assert!(!has_codegen_flag(
+            &flags("-Zstrip=debuginfo"),
+            "strip=symbols"
+        ));
+        assert!(!has_codegen_flag(&flags("strip=symbols"), "strip=symbols"));
+    }
+
+    #[test]
+    fn sigdata_enclave_hash() {
+        let mut sigdata = [0_u8; 256];
+        sigdata[188..220].copy_from_slice(&[0xab; 32]);
+        assert_eq!(enclave_hash(&sigdata), Some([0xab; 32]));
+        assert_eq!(enclave_hash(&sigdata[..219]), None);
+    }
+}
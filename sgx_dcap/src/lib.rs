@@ -0,0 +1,46 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! # Intel(R) SGX DCAP Quote Parsing
+//!
+//! The library parses the ECDSA quotes produced by the Quoting Enclave of
+//! the Intel SGX Data Center Attestation Primitives (DCAP): version 3, and
+//! version 4 with an SGX report body.
+//!
+//! `DcapQuote::parse` checks the layout of a quote and gives typed access
+//! to its parts: the header, the ISV enclave report body, the ECDSA
+//! signature and attestation key, the QE report and its signature, the QE
+//! authentication data and the certification data, usually the PCK
+//! certificate chain.
+//!
+//! Parsing does not verify any signature. A quote must be verified, for
+//! example with `sgx_qv_verify_quote` of the Intel DCAP Quote Verification
+//! Library, before its contents are trusted.
+//!
+//! The library neither allocates nor depends on the SGX runtime, so the
+//! same code can be used inside an enclave and in the untrusted application.
+
+#![no_std]
+#![cfg_attr(
+    all(target_env = "sgx", target_vendor = "mesalock"),
+    feature(rustc_private)
+)]
+
+extern crate sgx_types;
+
+mod quote;
+pub use self::quote::*;
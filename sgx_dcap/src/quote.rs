@@ -0,0 +1,304 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use core::fmt;
+use core::mem;
+use core::ptr;
+use sgx_types::*;
+
+pub const QUOTE_VERSION_3: u16 = 3;
+pub const QUOTE_VERSION_4: u16 = 4;
+
+/// The attestation key type of ECDSA-256-with-P-256 quotes.
+pub const ATT_KEY_TYPE_ECDSA_P256: u16 = 2;
+
+/// The TEE types of version 4 quotes.
+pub use sgx_types::{TEE_TYPE_SGX, TEE_TYPE_TDX};
+
+/// Certification data: PCK certificate chain, PEM encoded.
+pub const CERT_TYPE_PCK_CERT_CHAIN: u16 = 5;
+/// Certification data: QE report certification data (version 4 quotes).
+pub const CERT_TYPE_QE_REPORT: u16 = 6;
+
+const HEADER_SIZE: usize = mem::size_of::<sgx_quote_header_t>();
+const REPORT_BODY_SIZE: usize = mem::size_of::<sgx_report_body_t>();
+const SIGNATURE_SIZE: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuoteError {
+    /// The quote ends before a structure it announces.
+    TooShort,
+    /// The quote has bytes past the structures it announces.
+    TrailingData,
+    UnsupportedVersion(u16),
+    UnsupportedAttestationKeyType(u16),
+    UnsupportedTeeType(u32),
+    /// A version 4 quote does not carry QE report certification data.
+    InvalidCertificationData,
+}
+
+impl fmt::Display for QuoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            QuoteError::TooShort => f.write_str("quote is truncated"),
+            QuoteError::TrailingData => f.write_str("quote has trailing data"),
+            QuoteError::UnsupportedVersion(v) => write!(f, "unsupported quote version {}", v),
+            QuoteError::UnsupportedAttestationKeyType(t) => {
+                write!(f, "unsupported attestation key type {}", t)
+            }
+            QuoteError::UnsupportedTeeType(t) => write!(f, "unsupported TEE type {:#x}", t),
+            QuoteError::InvalidCertificationData => {
+                f.write_str("quote has no QE report certification data")
+            }
+        }
+    }
+}
+
+///
+/// Certification data of a quote: a type and the data of that type.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct CertificationData<'a> {
+    pub cert_type: u16,
+    pub data: &'a [u8],
+}
+
+///
+/// A parsed ECDSA quote, borrowing the quote bytes.
+///
+/// Version 3 quotes and version 4 quotes of SGX enclaves are supported.
+/// They share the same header and report body and only differ in how the
+/// QE report is stored in the signature data. Quotes of TDX trust domains
+/// carry a different report body and are parsed by `sgx_types::Quote4`.
+///
+#[derive(Clone, Copy)]
+pub struct DcapQuote<'a> {
+    header: sgx_quote_header_t,
+    report_body: sgx_report_body_t,
+    signed_data: &'a [u8],
+    signature: &'a [u8],
+    attestation_key: &'a [u8],
+    qe_report_body: sgx_report_body_t,
+    qe_report: &'a [u8],
+    qe_report_signature: &'a [u8],
+    qe_auth_data: &'a [u8],
+    certification_data: CertificationData<'a>,
+}
+
+impl<'a> DcapQuote<'a> {
+    ///
+    /// parse checks the layout of `bytes` and splits it into its parts.
+    ///
+    /// The signature data must be exactly as long as the header announces
+    /// and every nested structure must fit in it. No signature is checked.
+    ///
+    pub fn parse(bytes: &'a [u8]) -> Result<DcapQuote<'a>, QuoteError> {
+        let mut reader = Reader(bytes);
+        let header_bytes = reader.take(HEADER_SIZE)?;
+        let header: sgx_quote_header_t = read_struct(header_bytes);
+        match header.version {
+            QUOTE_VERSION_3 => (),
+            QUOTE_VERSION_4 => {
+                // the version 3 field att_key_data_0 holds the TEE type
+                let tee_type = header.att_key_data_0;
+                if tee_type != TEE_TYPE_SGX {
+                    return Err(QuoteError::UnsupportedTeeType(tee_type));
+                }
+            }
+            version => return Err(QuoteError::UnsupportedVersion(version)),
+        }
+        if header.att_key_type != ATT_KEY_TYPE_ECDSA_P256 {
+            return Err(QuoteError::UnsupportedAttestationKeyType(header.att_key_type));
+        }
+        let report_body = read_struct(reader.take(REPORT_BODY_SIZE)?);
+        let signed_data = &bytes[..HEADER_SIZE + REPORT_BODY_SIZE];
+
+        let signature_data_len = reader.u32()? as usize;
+        let mut sig = Reader(reader.take(signature_data_len)?);
+        reader.finish()?;
+
+        let signature = sig.take(SIGNATURE_SIZE)?;
+        let attestation_key = sig.take(SIGNATURE_SIZE)?;
+        // version 4 wraps the QE report into certification data of its own
+        let mut qe = if header.version == QUOTE_VERSION_4 {
+            let outer = sig.certification_data()?;
+            sig.finish()?;
+            if outer.cert_type != CERT_TYPE_QE_REPORT {
+                return Err(QuoteError::InvalidCertificationData);
+            }
+            Reader(outer.data)
+        } else {
+            sig
+        };
+        let qe_report = qe.take(REPORT_BODY_SIZE)?;
+        let qe_report_signature = qe.take(SIGNATURE_SIZE)?;
+        let auth_len = qe.u16()? as usize;
+        let qe_auth_data = qe.take(auth_len)?;
+        let certification_data = qe.certification_data()?;
+        qe.finish()?;
+
+        Ok(DcapQuote {
+            header,
+            report_body,
+            signed_data,
+            signature,
+            attestation_key,
+            qe_report_body: read_struct(qe_report),
+            qe_report,
+            qe_report_signature,
+            qe_auth_data,
+            certification_data,
+        })
+    }
+
+    pub fn header(&self) -> &sgx_quote_header_t {
+        &self.header
+    }
+
+    pub fn version(&self) -> u16 {
+        self.header.version
+    }
+
+    ///
+    /// report_body returns the report body of the attested enclave.
+    ///
+    pub fn report_body(&self) -> &sgx_report_body_t {
+        &self.report_body
+    }
+
+    ///
+    /// signed_data returns the header and report body, the bytes covered
+    /// by `signature`.
+    ///
+    pub fn signed_data(&self) -> &'a [u8] {
+        self.signed_data
+    }
+
+    ///
+    /// signature returns the ECDSA signature over `signed_data`, made with
+    /// the attestation key: r followed by s, big endian.
+    ///
+    pub fn signature(&self) -> &'a [u8] {
+        self.signature
+    }
+
+    ///
+    /// attestation_key returns the public attestation key of the QE: x
+    /// followed by y, big endian.
+    ///
+    pub fn attestation_key(&self) -> &'a [u8] {
+        self.attestation_key
+    }
+
+    pub fn qe_report_body(&self) -> &sgx_report_body_t {
+        &self.qe_report_body
+    }
+
+    ///
+    /// qe_report returns the raw QE report body, the bytes covered by
+    /// `qe_report_signature`.
+    ///
+    pub fn qe_report(&self) -> &'a [u8] {
+        self.qe_report
+    }
+
+    ///
+    /// qe_report_signature returns the signature of the PCK over
+    /// `qe_report`.
+    ///
+    pub fn qe_report_signature(&self) -> &'a [u8] {
+        self.qe_report_signature
+    }
+
+    pub fn qe_auth_data(&self) -> &'a [u8] {
+        self.qe_auth_data
+    }
+
+    pub fn certification_data(&self) -> CertificationData<'a> {
+        self.certification_data
+    }
+
+    ///
+    /// pck_cert_chain returns the PEM encoded PCK certificate chain, if
+    /// that is what the certification data holds.
+    ///
+    pub fn pck_cert_chain(&self) -> Option<&'a [u8]> {
+        match self.certification_data.cert_type {
+            CERT_TYPE_PCK_CERT_CHAIN => Some(self.certification_data.data),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for DcapQuote<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DcapQuote")
+            .field("version", &self.version())
+            .field("mr_enclave", &self.report_body.mr_enclave.m)
+            .field("mr_signer", &self.report_body.mr_signer.m)
+            .field("cert_type", &self.certification_data.cert_type)
+            .finish_non_exhaustive()
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], QuoteError> {
+        if self.0.len() < len {
+            return Err(QuoteError::TooShort);
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u16(&mut self) -> Result<u16, QuoteError> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, QuoteError> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    // sgx_ql_certification_data_t: type, size and data
+    fn certification_data(&mut self) -> Result<CertificationData<'a>, QuoteError> {
+        let cert_type = self.u16()?;
+        let len = self.u32()? as usize;
+        Ok(CertificationData {
+            cert_type,
+            data: self.take(len)?,
+        })
+    }
+
+    fn finish(&self) -> Result<(), QuoteError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(QuoteError::TrailingData)
+        }
+    }
+}
+
+// The quote structures only consist of integers and byte arrays, so any
+// bytes of the right length are a valid value.
+fn read_struct<T: Copy>(bytes: &[u8]) -> T {
+    assert_eq!(bytes.len(), mem::size_of::<T>());
+    unsafe { ptr::read_unaligned(bytes.as_ptr() as *const T) }
+}
@@ -596,6 +596,13 @@ extern "C" {
     ) -> sgx_status_t;
     pub fn u_freeaddrinfo_ocall(res: *mut addrinfo) -> sgx_status_t;
     pub fn u_gai_strerror_ocall(result: *mut *const c_char, errcode: c_int) -> sgx_status_t;
+    pub fn u_dns_resolve_ocall(
+        result: *mut c_int,
+        errno: *mut c_int,
+        hostname: *const c_char,
+        port: u16,
+        addr: *mut [u8; 16],
+    ) -> sgx_status_t;
     // async io
     pub fn u_poll_ocall(
         result: *mut c_int,
@@ -2633,6 +2640,12 @@ pub unsafe fn socket(domain: c_int, ty: c_int, protocol: c_int) -> c_int {
         set_errno(ESGX);
         result = -1;
     }
+
+    // The host must return either -1 or a descriptor.
+    if result < -1 {
+        set_errno(ESGX);
+        result = -1;
+    }
     result
 }
 
@@ -2830,6 +2843,12 @@ pub unsafe fn send(sockfd: c_int, buf: *const c_void, len: size_t, flags: c_int)
         result = -1;
     }
 
+    // The host must not report more bytes than the buffer holds.
+    if result < -1 || (result >= 0 && result as size_t > len) {
+        set_errno(ESGX);
+        result = -1;
+    }
+
     if len <= MAX_OCALL_ALLOC_SIZE {
         sgx_ocfree();
     } else {
@@ -2889,6 +2908,12 @@ pub unsafe fn sendto(
         result = -1;
     }
 
+    // The host must not report more bytes than the buffer holds.
+    if result < -1 || (result >= 0 && result as size_t > len) {
+        set_errno(ESGX);
+        result = -1;
+    }
+
     if len <= MAX_OCALL_ALLOC_SIZE {
         sgx_ocfree();
     } else {
@@ -3040,6 +3065,12 @@ pub unsafe fn recv(sockfd: c_int, buf: *mut c_void, len: size_t, flags: c_int) -
         result = -1;
     }
 
+    // The host must not report more bytes than the buffer holds.
+    if result < -1 || (result >= 0 && result as size_t > len) {
+        set_errno(ESGX);
+        result = -1;
+    }
+
     if result != -1 {
         ptr::copy_nonoverlapping(tmp_buf as *const u8, buf as *mut u8, len);
     }
@@ -3105,6 +3136,13 @@ pub unsafe fn recvfrom(
         result = -1;
     }
 
+    // The host must not report more bytes than the buffer holds, nor an
+    // address longer than the one it was given room for.
+    if result < -1 || (result >= 0 && result as size_t > len) || len_out > len_in {
+        set_errno(ESGX);
+        result = -1;
+    }
+
     if result != -1 {
         ptr::copy_nonoverlapping(tmp_buf as *const u8, buf as *mut u8, len);
     }
@@ -3114,7 +3152,7 @@ pub unsafe fn recvfrom(
         free(tmp_buf);
     }
 
-    if !addrlen.is_null() {
+    if result != -1 && !addrlen.is_null() {
         *addrlen = len_out;
     }
     result
@@ -3577,6 +3615,33 @@ pub unsafe fn gai_strerror(errcode: c_int) -> *const c_char {
     result
 }
 
+// Resolves `hostname` to an IPv4 address on the host, returned as the
+// IPv4-mapped IPv6 address `::ffff:a.b.c.d`. Returns 0 or an EAI_* error.
+pub unsafe fn dns_resolve(hostname: *const c_char, port: u16, addr: &mut [u8; 16]) -> c_int {
+    let mut result: c_int = 0;
+    let mut error: c_int = 0;
+    let mut ret_addr = [0_u8; 16];
+    let status = u_dns_resolve_ocall(
+        &mut result as *mut c_int,
+        &mut error as *mut c_int,
+        hostname,
+        port,
+        &mut ret_addr as *mut [u8; 16],
+    );
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == 0 {
+            *addr = ret_addr;
+        } else if result == EAI_SYSTEM {
+            set_errno(error);
+        }
+    } else {
+        set_errno(ESGX);
+        result = EAI_SYSTEM;
+    }
+    result
+}
+
 pub unsafe fn poll(fds: *mut pollfd, nfds: nfds_t, timeout: c_int) -> c_int {
     let mut result: c_int = 0;
     let mut error: c_int = 0;
@@ -0,0 +1,661 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use crate::der::{self, Der};
+use alloc::vec::Vec;
+use core::fmt;
+use core::ptr;
+use sgx_dcap::{DcapQuote, QuoteError};
+use sgx_tcrypto::ecc::EcKeyPair;
+use sgx_tcrypto::{rsgx_sha256_slice, SgxEccHandle};
+use sgx_types::*;
+
+/// The extension holding the quote.
+pub const RA_TLS_QUOTE_OID: &str = "1.2.840.113741.1337.6";
+
+// 1.2.840.113741.1337.x
+const OID_INTEL_RA_TLS_PREFIX: [u8; 8] = [0x2a, 0x86, 0x48, 0x86, 0xf8, 0x4d, 0x8a, 0x39];
+const OID_QUOTE: u8 = 6;
+const OID_PCK_CRL_ISSUER_CHAIN: u8 = 8;
+const OID_TCB_INFO: u8 = 9;
+const OID_TCB_INFO_ISSUER_CHAIN: u8 = 10;
+const OID_QE_IDENTITY: u8 = 11;
+const OID_ROOT_CA_CRL: u8 = 12;
+const OID_PCK_CRL: u8 = 13;
+
+// id-ecPublicKey (1.2.840.10045.2.1)
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+// prime256v1 (1.2.840.10045.3.1.7)
+const OID_PRIME256V1: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+// ecdsa-with-SHA256 (1.2.840.10045.4.3.2)
+const OID_ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+// commonName (2.5.4.3)
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+
+const COMMON_NAME: &[u8] = b"RATLS";
+// The certificate is as valid as its quote, so it never expires (RFC 5280,
+// section 4.1.2.5).
+const NOT_BEFORE: &[u8] = b"010101000000Z";
+const NOT_AFTER: &[u8] = b"99991231235959Z";
+
+const QUOTE_VERSION_EPID: [u16; 2] = [1, 2];
+const EPID_QUOTE_SIZE: usize = core::mem::size_of::<sgx_quote_t>();
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RaTlsError {
+    /// A function of the Intel SGX cryptography library failed.
+    Crypto(sgx_status_t),
+    /// The certificate is not a DER encoded X.509 version 3 certificate.
+    InvalidCertificate,
+    /// The key is not a P-256 key, or the signature not ECDSA with SHA-256.
+    UnsupportedAlgorithm,
+    /// The certificate has a critical extension other than the RA-TLS ones.
+    UnsupportedExtension,
+    /// The self-signature of the certificate does not verify.
+    InvalidSignature,
+    /// The certificate has no quote extension.
+    MissingQuote,
+    InvalidQuote(QuoteError),
+    /// The report data of the quote does not commit to the certificate key.
+    KeyMismatch,
+}
+
+impl fmt::Display for RaTlsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RaTlsError::Crypto(status) => write!(f, "cryptography library error: {}", status),
+            RaTlsError::InvalidCertificate => f.write_str("malformed certificate"),
+            RaTlsError::UnsupportedAlgorithm => {
+                f.write_str("the certificate is not signed with ECDSA P-256 and SHA-256")
+            }
+            RaTlsError::UnsupportedExtension => {
+                f.write_str("the certificate has an unsupported critical extension")
+            }
+            RaTlsError::InvalidSignature => f.write_str("invalid certificate signature"),
+            RaTlsError::MissingQuote => f.write_str("the certificate has no quote"),
+            RaTlsError::InvalidQuote(e) => write!(f, "invalid quote: {}", e),
+            RaTlsError::KeyMismatch => {
+                f.write_str("the quote is not bound to the certificate key")
+            }
+        }
+    }
+}
+
+impl From<sgx_status_t> for RaTlsError {
+    fn from(status: sgx_status_t) -> RaTlsError {
+        RaTlsError::Crypto(status)
+    }
+}
+
+impl From<QuoteParseError> for RaTlsError {
+    fn from(e: QuoteParseError) -> RaTlsError {
+        RaTlsError::InvalidQuote(match e {
+            QuoteParseError::TooShort => QuoteError::TooShort,
+            QuoteParseError::TrailingData => QuoteError::TrailingData,
+            QuoteParseError::UnsupportedVersion(v) => QuoteError::UnsupportedVersion(v),
+            QuoteParseError::UnsupportedAttestationKeyType(t) => {
+                QuoteError::UnsupportedAttestationKeyType(t)
+            }
+            QuoteParseError::UnsupportedTeeType(t) => QuoteError::UnsupportedTeeType(t),
+            QuoteParseError::InvalidCertificationData => QuoteError::InvalidCertificationData,
+        })
+    }
+}
+
+///
+/// Quote verification collateral, as returned by
+/// `sgx_ql_get_quote_verification_collateral`: PEM certificate chains and
+/// CRLs, and the JSON TCB info and QE identity. Every field is optional.
+///
+/// The QE identity issuer chain has no RA-TLS extension and is not
+/// embedded. Intel signs the TCB info and the QE identity with the same
+/// key, so `RaTlsCert::verify` returns the TCB info issuer chain for both.
+///
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Collateral<'a> {
+    pub pck_crl_issuer_chain: &'a [u8],
+    pub root_ca_crl: &'a [u8],
+    pub pck_crl: &'a [u8],
+    pub tcb_info_issuer_chain: &'a [u8],
+    pub tcb_info: &'a [u8],
+    pub qe_identity_issuer_chain: &'a [u8],
+    pub qe_identity: &'a [u8],
+}
+
+impl<'a> Collateral<'a> {
+    fn fields(&self) -> [(u8, &'a [u8]); 6] {
+        [
+            (OID_PCK_CRL_ISSUER_CHAIN, self.pck_crl_issuer_chain),
+            (OID_TCB_INFO, self.tcb_info),
+            (OID_TCB_INFO_ISSUER_CHAIN, self.tcb_info_issuer_chain),
+            (OID_QE_IDENTITY, self.qe_identity),
+            (OID_ROOT_CA_CRL, self.root_ca_crl),
+            (OID_PCK_CRL, self.pck_crl),
+        ]
+    }
+
+    fn field_mut(&mut self, oid: u8) -> Option<&mut &'a [u8]> {
+        match oid {
+            OID_PCK_CRL_ISSUER_CHAIN => Some(&mut self.pck_crl_issuer_chain),
+            OID_TCB_INFO => Some(&mut self.tcb_info),
+            OID_TCB_INFO_ISSUER_CHAIN => Some(&mut self.tcb_info_issuer_chain),
+            OID_QE_IDENTITY => Some(&mut self.qe_identity),
+            OID_ROOT_CA_CRL => Some(&mut self.root_ca_crl),
+            OID_PCK_CRL => Some(&mut self.pck_crl),
+            _ => None,
+        }
+    }
+}
+
+/// A DER encoded X.509 certificate.
+#[derive(Clone, PartialEq, Eq)]
+pub struct DerCertificate(Vec<u8>);
+
+impl DerCertificate {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl AsRef<[u8]> for DerCertificate {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for DerCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DerCertificate").field(&self.0.len()).finish()
+    }
+}
+
+///
+/// The contents of an RA-TLS certificate whose self-signature and key
+/// binding `RaTlsCert::verify` has checked, borrowing the certificate.
+///
+/// The quote itself has not been verified.
+///
+#[derive(Clone, Copy)]
+pub struct VerifiedQuote<'a> {
+    quote: &'a [u8],
+    collateral: Collateral<'a>,
+    public_key: sgx_ec256_public_t,
+    report_data: sgx_report_data_t,
+}
+
+impl<'a> VerifiedQuote<'a> {
+    /// The raw quote, to be parsed with `sgx_dcap::DcapQuote` or
+    /// `sgx_types::Quote4` and verified.
+    pub fn quote(&self) -> &'a [u8] {
+        self.quote
+    }
+
+    pub fn version(&self) -> u16 {
+        u16::from_le_bytes([self.quote[0], self.quote[1]])
+    }
+
+    pub fn collateral(&self) -> &Collateral<'a> {
+        &self.collateral
+    }
+
+    /// The key of the certificate, which the peer uses in the handshake.
+    pub fn public_key(&self) -> &sgx_ec256_public_t {
+        &self.public_key
+    }
+
+    /// The report data of the quote. The first 32 bytes commit to the key;
+    /// the rest is whatever the enclave put there.
+    pub fn report_data(&self) -> &sgx_report_data_t {
+        &self.report_data
+    }
+}
+
+impl fmt::Debug for VerifiedQuote<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VerifiedQuote")
+            .field("version", &self.version())
+            .field("report_data", &&self.report_data.d[..])
+            .field("collateral", &self.collateral)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Generation and verification of RA-TLS certificates.
+pub struct RaTlsCert;
+
+impl RaTlsCert {
+    ///
+    /// report_data returns the report data an enclave must put in the quote
+    /// of a certificate for `public_key`: the SHA-256 hash of its
+    /// `SubjectPublicKeyInfo`, followed by zeros.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    pub fn report_data(public_key: &sgx_ec256_public_t) -> Result<sgx_report_data_t, RaTlsError> {
+        let hash = rsgx_sha256_slice(&subject_public_key_info(public_key))?;
+        let mut report_data = sgx_report_data_t::default();
+        report_data.d[..hash.len()].copy_from_slice(&hash);
+        Ok(report_data)
+    }
+
+    ///
+    /// generate creates a certificate for `key_pair`, self-signed with it,
+    /// that carries `quote` and `collateral`.
+    ///
+    /// # Parameters
+    ///
+    /// **key_pair**
+    ///
+    /// The TLS key of the enclave.
+    ///
+    /// **quote**
+    ///
+    /// An EPID or ECDSA quote of the enclave whose report data starts with
+    /// `RaTlsCert::report_data(key_pair.public_key())`.
+    ///
+    /// **collateral**
+    ///
+    /// The collateral to embed for a verifier without access to the Intel
+    /// PCS. Empty fields are left out.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Errors
+    ///
+    /// **RaTlsError::InvalidQuote**
+    ///
+    /// The quote is malformed.
+    ///
+    /// **RaTlsError::KeyMismatch**
+    ///
+    /// The report data of the quote does not commit to the key.
+    ///
+    /// **RaTlsError::Crypto**
+    ///
+    /// Hashing or signing failed.
+    ///
+    pub fn generate(
+        key_pair: &EcKeyPair,
+        quote: &[u8],
+        collateral: &Collateral<'_>,
+    ) -> Result<DerCertificate, RaTlsError> {
+        let spki = subject_public_key_info(key_pair.public_key());
+        check_binding(&spki, &quote_report_data(quote)?)?;
+
+        let signature_algorithm = der::element(
+            der::TAG_SEQUENCE,
+            &[&der::element(der::TAG_OID, &[OID_ECDSA_WITH_SHA256])],
+        );
+        let name = der::element(
+            der::TAG_SEQUENCE,
+            &[&der::element(
+                der::TAG_SET,
+                &[&der::element(
+                    der::TAG_SEQUENCE,
+                    &[
+                        &der::element(der::TAG_OID, &[OID_COMMON_NAME]),
+                        &der::element(der::TAG_UTF8_STRING, &[COMMON_NAME]),
+                    ],
+                )],
+            )],
+        );
+        let validity = der::element(
+            der::TAG_SEQUENCE,
+            &[
+                &der::element(der::TAG_UTC_TIME, &[NOT_BEFORE]),
+                &der::element(der::TAG_GENERALIZED_TIME, &[NOT_AFTER]),
+            ],
+        );
+
+        let mut extensions = extension(OID_QUOTE, quote);
+        for (oid, value) in collateral.fields() {
+            if !value.is_empty() {
+                extensions.extend_from_slice(&extension(oid, value));
+            }
+        }
+        let extensions = der::element(
+            der::TAG_EXTENSIONS,
+            &[&der::element(der::TAG_SEQUENCE, &[&extensions])],
+        );
+
+        let tbs = der::element(
+            der::TAG_SEQUENCE,
+            &[
+                // version 3
+                &der::element(der::TAG_VERSION, &[&der::unsigned_integer(&[2])]),
+                &der::unsigned_integer(&[1]),
+                &signature_algorithm,
+                &name,
+                &validity,
+                &name,
+                &spki,
+                &extensions,
+            ],
+        );
+
+        let signature = key_pair.sign_slice(&tbs)?;
+        let (r, s) = signature_to_be(&signature);
+        let signature = der::element(
+            der::TAG_SEQUENCE,
+            &[&der::unsigned_integer(&r), &der::unsigned_integer(&s)],
+        );
+
+        Ok(DerCertificate(der::element(
+            der::TAG_SEQUENCE,
+            &[
+                &tbs,
+                &signature_algorithm,
+                &der::element(der::TAG_BIT_STRING, &[&[0], &signature]),
+            ],
+        )))
+    }
+
+    ///
+    /// verify parses an RA-TLS certificate, checks its self-signature and
+    /// that the report data of its quote commits to its key.
+    ///
+    /// The validity period is not checked: freshness is a property of the
+    /// quote and its collateral.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Errors
+    ///
+    /// **RaTlsError::InvalidCertificate**
+    ///
+    /// The certificate is malformed, or has an RA-TLS extension twice.
+    ///
+    /// **RaTlsError::UnsupportedAlgorithm**
+    ///
+    /// The key is not a P-256 key, or the signature not ECDSA with SHA-256.
+    ///
+    /// **RaTlsError::UnsupportedExtension**
+    ///
+    /// The certificate has an unknown critical extension.
+    ///
+    /// **RaTlsError::InvalidSignature**
+    ///
+    /// The certificate is not signed with its own key.
+    ///
+    /// **RaTlsError::MissingQuote**
+    ///
+    /// The certificate has no quote.
+    ///
+    /// **RaTlsError::InvalidQuote**
+    ///
+    /// The quote is malformed.
+    ///
+    /// **RaTlsError::KeyMismatch**
+    ///
+    /// The report data of the quote does not commit to the key.
+    ///
+    /// **RaTlsError::Crypto**
+    ///
+    /// Hashing or verifying the signature failed.
+    ///
+    pub fn verify(cert: &[u8]) -> Result<VerifiedQuote<'_>, RaTlsError> {
+        let mut outer = Der(cert);
+        let mut certificate = Der(outer.read(der::TAG_SEQUENCE)?);
+        outer.finish()?;
+        let tbs = certificate.read_element(der::TAG_SEQUENCE)?;
+        let signature_algorithm = certificate.read(der::TAG_SEQUENCE)?;
+        let signature = certificate.read(der::TAG_BIT_STRING)?;
+        certificate.finish()?;
+
+        let mut algorithm = Der(signature_algorithm);
+        if algorithm.read(der::TAG_OID)? != OID_ECDSA_WITH_SHA256 {
+            return Err(RaTlsError::UnsupportedAlgorithm);
+        }
+        algorithm.finish()?;
+
+        let mut fields = Der(Der(tbs).read(der::TAG_SEQUENCE)?);
+        let mut version = Der(fields.read(der::TAG_VERSION)?);
+        if version.read(der::TAG_INTEGER)? != [2] {
+            return Err(RaTlsError::InvalidCertificate);
+        }
+        version.finish()?;
+        // serial number
+        fields.read(der::TAG_INTEGER)?;
+        if fields.read(der::TAG_SEQUENCE)? != signature_algorithm {
+            return Err(RaTlsError::InvalidCertificate);
+        }
+        // issuer, validity and subject
+        fields.read(der::TAG_SEQUENCE)?;
+        fields.read(der::TAG_SEQUENCE)?;
+        fields.read(der::TAG_SEQUENCE)?;
+        let spki = fields.read_element(der::TAG_SEQUENCE)?;
+        // issuerUniqueID and subjectUniqueID
+        for tag in [0x81, 0x82] {
+            if fields.peek() == Some(tag) {
+                fields.read(tag)?;
+            }
+        }
+        let mut extensions = Der(fields.read(der::TAG_EXTENSIONS)?);
+        fields.finish()?;
+        let mut list = Der(extensions.read(der::TAG_SEQUENCE)?);
+        extensions.finish()?;
+
+        let public_key = parse_subject_public_key_info(spki)?;
+        let signature = parse_signature(signature)?;
+        let handle = SgxEccHandle::new();
+        handle.open()?;
+        if !handle.ecdsa_verify_slice(tbs, &public_key, &signature)? {
+            return Err(RaTlsError::InvalidSignature);
+        }
+
+        let mut quote = None;
+        let mut collateral = Collateral::default();
+        while !list.is_empty() {
+            let mut extension = Der(list.read(der::TAG_SEQUENCE)?);
+            let oid = extension.read(der::TAG_OID)?;
+            let critical = if extension.peek() == Some(der::TAG_BOOLEAN) {
+                extension.read(der::TAG_BOOLEAN)? != [0]
+            } else {
+                false
+            };
+            let value = extension.read(der::TAG_OCTET_STRING)?;
+            extension.finish()?;
+
+            let arc = match oid.split_last() {
+                Some((&arc, prefix)) if prefix == OID_INTEL_RA_TLS_PREFIX => Some(arc),
+                _ => None,
+            };
+            if arc == Some(OID_QUOTE) {
+                if quote.replace(value).is_some() {
+                    return Err(RaTlsError::InvalidCertificate);
+                }
+                continue;
+            }
+            match arc.and_then(|arc| collateral.field_mut(arc)) {
+                Some(field) if field.is_empty() => *field = value,
+                Some(_) => return Err(RaTlsError::InvalidCertificate),
+                None if critical => return Err(RaTlsError::UnsupportedExtension),
+                None => {}
+            }
+        }
+        collateral.qe_identity_issuer_chain = collateral.tcb_info_issuer_chain;
+
+        let quote = quote.ok_or(RaTlsError::MissingQuote)?;
+        let report_data = quote_report_data(quote)?;
+        check_binding(spki, &report_data)?;
+
+        Ok(VerifiedQuote {
+            quote,
+            collateral,
+            public_key,
+            report_data,
+        })
+    }
+}
+
+// Encodes the extension `1.2.840.113741.1337.oid`.
+fn extension(oid: u8, value: &[u8]) -> Vec<u8> {
+    der::element(
+        der::TAG_SEQUENCE,
+        &[
+            &der::element(der::TAG_OID, &[&OID_INTEL_RA_TLS_PREFIX, &[oid]]),
+            &der::element(der::TAG_OCTET_STRING, &[value]),
+        ],
+    )
+}
+
+fn subject_public_key_info(public_key: &sgx_ec256_public_t) -> Vec<u8> {
+    let mut point = Vec::with_capacity(2 + 2 * SGX_ECP256_KEY_SIZE);
+    // no unused bits, uncompressed point
+    point.extend_from_slice(&[0, 0x04]);
+    point.extend(public_key.gx.iter().rev());
+    point.extend(public_key.gy.iter().rev());
+    der::element(
+        der::TAG_SEQUENCE,
+        &[
+            &der::element(
+                der::TAG_SEQUENCE,
+                &[
+                    &der::element(der::TAG_OID, &[OID_EC_PUBLIC_KEY]),
+                    &der::element(der::TAG_OID, &[OID_PRIME256V1]),
+                ],
+            ),
+            &der::element(der::TAG_BIT_STRING, &[&point]),
+        ],
+    )
+}
+
+fn parse_subject_public_key_info(spki: &[u8]) -> Result<sgx_ec256_public_t, RaTlsError> {
+    let mut outer = Der(spki);
+    let mut info = Der(outer.read(der::TAG_SEQUENCE)?);
+    outer.finish()?;
+    let mut algorithm = Der(info.read(der::TAG_SEQUENCE)?);
+    if algorithm.read(der::TAG_OID)? != OID_EC_PUBLIC_KEY {
+        return Err(RaTlsError::UnsupportedAlgorithm);
+    }
+    if algorithm.peek() != Some(der::TAG_OID) || algorithm.read(der::TAG_OID)? != OID_PRIME256V1 {
+        return Err(RaTlsError::UnsupportedAlgorithm);
+    }
+    algorithm.finish()?;
+    let point = info.read(der::TAG_BIT_STRING)?;
+    info.finish()?;
+
+    match point {
+        [0, 0x04, xy @ ..] if xy.len() == 2 * SGX_ECP256_KEY_SIZE => {
+            let (x, y) = xy.split_at(SGX_ECP256_KEY_SIZE);
+            let mut public_key = sgx_ec256_public_t::default();
+            copy_reversed(&mut public_key.gx, x);
+            copy_reversed(&mut public_key.gy, y);
+            Ok(public_key)
+        }
+        _ => Err(RaTlsError::UnsupportedAlgorithm),
+    }
+}
+
+// Returns r and s, big-endian.
+fn signature_to_be(
+    signature: &sgx_ec256_signature_t,
+) -> ([u8; SGX_ECP256_KEY_SIZE], [u8; SGX_ECP256_KEY_SIZE]) {
+    let to_be = |words: &[u32; SGX_NISTP_ECP256_KEY_SIZE]| {
+        let mut be = [0_u8; SGX_ECP256_KEY_SIZE];
+        for (chunk, word) in be.chunks_exact_mut(4).zip(words.iter().rev()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        be
+    };
+    (to_be(&signature.x), to_be(&signature.y))
+}
+
+fn parse_signature(bits: &[u8]) -> Result<sgx_ec256_signature_t, RaTlsError> {
+    let signature = match bits {
+        [0, signature @ ..] => signature,
+        _ => return Err(RaTlsError::InvalidCertificate),
+    };
+    let mut outer = Der(signature);
+    let mut values = Der(outer.read(der::TAG_SEQUENCE)?);
+    outer.finish()?;
+    let mut r = [0_u8; SGX_ECP256_KEY_SIZE];
+    let mut s = [0_u8; SGX_ECP256_KEY_SIZE];
+    der::read_unsigned_integer(values.read(der::TAG_INTEGER)?, &mut r)?;
+    der::read_unsigned_integer(values.read(der::TAG_INTEGER)?, &mut s)?;
+    values.finish()?;
+
+    let from_be = |be: &[u8; SGX_ECP256_KEY_SIZE]| {
+        let mut words = [0_u32; SGX_NISTP_ECP256_KEY_SIZE];
+        for (word, chunk) in words.iter_mut().rev().zip(be.chunks_exact(4)) {
+            *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        words
+    };
+    Ok(sgx_ec256_signature_t {
+        x: from_be(&r),
+        y: from_be(&s),
+    })
+}
+
+// Returns the report data of an EPID quote (version 1 or 2), an ECDSA
+// quote of an enclave (version 3 or 4) or of a TDX trust domain (version 4).
+fn quote_report_data(quote: &[u8]) -> Result<sgx_report_data_t, RaTlsError> {
+    if quote.len() < 8 {
+        return Err(RaTlsError::InvalidQuote(QuoteError::TooShort));
+    }
+    let version = u16::from_le_bytes([quote[0], quote[1]]);
+    let tee_type = u32::from_le_bytes([quote[4], quote[5], quote[6], quote[7]]);
+
+    if QUOTE_VERSION_EPID.contains(&version) {
+        if quote.len() < EPID_QUOTE_SIZE {
+            return Err(RaTlsError::InvalidQuote(QuoteError::TooShort));
+        }
+        let header: sgx_quote_t = unsafe { ptr::read_unaligned(quote.as_ptr() as *const _) };
+        let signature_len = header.signature_len as usize;
+        match (quote.len() - EPID_QUOTE_SIZE).cmp(&signature_len) {
+            core::cmp::Ordering::Less => Err(RaTlsError::InvalidQuote(QuoteError::TooShort)),
+            core::cmp::Ordering::Greater => {
+                Err(RaTlsError::InvalidQuote(QuoteError::TrailingData))
+            }
+            core::cmp::Ordering::Equal => Ok({ header.report_body }.report_data),
+        }
+    } else if version == 4 && tee_type == TEE_TYPE_TDX {
+        let quote = Quote4::parse(quote)?;
+        Ok(sgx_report_data_t {
+            d: quote.report_body().report_data.d,
+        })
+    } else {
+        let quote = DcapQuote::parse(quote).map_err(RaTlsError::InvalidQuote)?;
+        Ok(quote.report_body().report_data)
+    }
+}
+
+fn check_binding(spki: &[u8], report_data: &sgx_report_data_t) -> Result<(), RaTlsError> {
+    let hash = rsgx_sha256_slice(spki)?;
+    if report_data.d[..hash.len()] == hash[..] {
+        Ok(())
+    } else {
+        Err(RaTlsError::KeyMismatch)
+    }
+}
+
+fn copy_reversed(dst: &mut [u8], src: &[u8]) {
+    for (d, s) in dst.iter_mut().zip(src.iter().rev()) {
+        *d = *s;
+    }
+}
@@ -0,0 +1,158 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! The subset of DER needed for RA-TLS certificates.
+
+use crate::RaTlsError;
+use alloc::vec::Vec;
+
+pub const TAG_BOOLEAN: u8 = 0x01;
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_BIT_STRING: u8 = 0x03;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_OID: u8 = 0x06;
+pub const TAG_UTF8_STRING: u8 = 0x0c;
+pub const TAG_UTC_TIME: u8 = 0x17;
+pub const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub const TAG_SEQUENCE: u8 = 0x30;
+pub const TAG_SET: u8 = 0x31;
+pub const TAG_VERSION: u8 = 0xa0;
+pub const TAG_EXTENSIONS: u8 = 0xa3;
+
+// A reader of the DER elements in a byte string.
+pub struct Der<'a>(pub &'a [u8]);
+
+impl<'a> Der<'a> {
+    pub fn peek(&self) -> Option<u8> {
+        self.0.first().copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // Reads an element with the given tag and returns its contents.
+    pub fn read(&mut self, tag: u8) -> Result<&'a [u8], RaTlsError> {
+        let (header, len) = self.header(tag)?;
+        let contents = &self.0[header..header + len];
+        self.0 = &self.0[header + len..];
+        Ok(contents)
+    }
+
+    // Reads an element with the given tag and returns its encoding.
+    pub fn read_element(&mut self, tag: u8) -> Result<&'a [u8], RaTlsError> {
+        let (header, len) = self.header(tag)?;
+        let (element, rest) = self.0.split_at(header + len);
+        self.0 = rest;
+        Ok(element)
+    }
+
+    pub fn finish(&self) -> Result<(), RaTlsError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(RaTlsError::InvalidCertificate)
+        }
+    }
+
+    // Returns the size of the tag and length, and the length, accepting
+    // only the minimal length encodings of DER.
+    fn header(&self, tag: u8) -> Result<(usize, usize), RaTlsError> {
+        let input = self.0;
+        if input.len() < 2 || input[0] != tag {
+            return Err(RaTlsError::InvalidCertificate);
+        }
+        let (len, header) = match input[1] {
+            n if n < 0x80 => (n as usize, 2),
+            n @ 0x81..=0x84 => {
+                let octets = (n & 0x7f) as usize;
+                if input.len() < 2 + octets || input[2] == 0 {
+                    return Err(RaTlsError::InvalidCertificate);
+                }
+                let len = input[2..2 + octets]
+                    .iter()
+                    .fold(0_usize, |len, &b| len << 8 | b as usize);
+                if len < 0x80 {
+                    return Err(RaTlsError::InvalidCertificate);
+                }
+                (len, 2 + octets)
+            }
+            _ => return Err(RaTlsError::InvalidCertificate),
+        };
+        if input.len() - header < len {
+            return Err(RaTlsError::InvalidCertificate);
+        }
+        Ok((header, len))
+    }
+}
+
+// Appends an element with the given tag and contents.
+pub fn write(out: &mut Vec<u8>, tag: u8, contents: &[u8]) {
+    out.push(tag);
+    let len = contents.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let octets = ((usize::BITS - len.leading_zeros() + 7) / 8) as usize;
+        out.push(0x80 | octets as u8);
+        out.extend_from_slice(&len.to_be_bytes()[core::mem::size_of::<usize>() - octets..]);
+    }
+    out.extend_from_slice(contents);
+}
+
+// Encodes an element whose contents are the concatenation of `parts`.
+pub fn element(tag: u8, parts: &[&[u8]]) -> Vec<u8> {
+    let contents = parts.concat();
+    let mut out = Vec::with_capacity(contents.len() + 6);
+    write(&mut out, tag, &contents);
+    out
+}
+
+// Encodes a big-endian unsigned integer as an INTEGER.
+pub fn unsigned_integer(be: &[u8]) -> Vec<u8> {
+    let start = be.iter().position(|&b| b != 0).unwrap_or(be.len() - 1);
+    let be = &be[start..];
+    if be[0] & 0x80 != 0 {
+        element(TAG_INTEGER, &[&[0], be])
+    } else {
+        element(TAG_INTEGER, &[be])
+    }
+}
+
+// Decodes the contents of a non-negative INTEGER into `out`, big-endian
+// and left-padded with zeros.
+pub fn read_unsigned_integer(contents: &[u8], out: &mut [u8]) -> Result<(), RaTlsError> {
+    let value = match contents {
+        [] => return Err(RaTlsError::InvalidCertificate),
+        [0, rest @ ..] if !rest.is_empty() => {
+            if rest[0] & 0x80 == 0 {
+                // not minimal
+                return Err(RaTlsError::InvalidCertificate);
+            }
+            rest
+        }
+        [b, ..] if b & 0x80 != 0 => return Err(RaTlsError::InvalidCertificate),
+        _ => contents,
+    };
+    if value.len() > out.len() {
+        return Err(RaTlsError::InvalidCertificate);
+    }
+    let pad = out.len() - value.len();
+    out[..pad].iter_mut().for_each(|b| *b = 0);
+    out[pad..].copy_from_slice(value);
+    Ok(())
+}
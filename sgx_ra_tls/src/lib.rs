@@ -0,0 +1,58 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! # RA-TLS Certificates
+//!
+//! RA-TLS binds a TLS key to an enclave: the enclave generates a key pair,
+//! obtains a quote whose report data commits to the public key, and puts
+//! the quote into an extension of a self-signed X.509 certificate. A peer
+//! that checks the quote during the TLS handshake knows it talks to the
+//! enclave, without any certificate authority.
+//!
+//! `RaTlsCert::generate` creates such a certificate for a NIST P-256 key
+//! and `RaTlsCert::verify` parses one and checks its self-signature and the
+//! binding between key and quote. The format is the one of Gramine and
+//! Occlum:
+//!
+//! * the raw quote is the value of the extension `1.2.840.113741.1337.6`;
+//! * the first 32 bytes of the report data are the SHA-256 hash of the
+//!   DER encoded `SubjectPublicKeyInfo` of the certificate;
+//! * optional verification collateral is carried in the extensions
+//!   `1.2.840.113741.1337.8` to `1.2.840.113741.1337.13` of the Intel
+//!   RA-TLS reference implementation.
+//!
+//! Verifying a certificate does not verify the quote. Its signature, TCB
+//! status and enclave identity must still be checked, for example with
+//! `sgx_qv_verify_quote` of the Intel DCAP Quote Verification Library or
+//! by the Intel Attestation Service for EPID quotes.
+
+#![no_std]
+#![cfg_attr(
+    all(target_env = "sgx", target_vendor = "mesalock"),
+    feature(rustc_private)
+)]
+
+extern crate alloc;
+
+extern crate sgx_dcap;
+extern crate sgx_tcrypto;
+extern crate sgx_types;
+
+mod cert;
+pub use self::cert::*;
+
+mod der;
@@ -0,0 +1,188 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Adapters between `sgx_rand` and the `rand_core` 0.6 traits.
+//!
+//! Crates of the `rand` 0.8 ecosystem, such as `rand`, `rand_chacha` or
+//! most cryptography crates, take generators implementing
+//! `rand_core::RngCore` and `rand_core::SeedableRng`. This module, enabled
+//! by the `rand_core` feature, lets them be used inside an enclave:
+//!
+//! * `SgxRngAdapter` is a `RngCore + CryptoRng` backed by the SGX random
+//!   number generator, the entropy source to pass to such crates.
+//! * `RngCoreAdapter` exposes any `sgx_rand::Rng` as a `RngCore`, and any
+//!   generator that implements `Rand` as a `rand_core::SeedableRng`.
+//! * `RngAdapter` goes the other way, so the distributions of this crate
+//!   can draw from a `rand_core` generator.
+//!
+//! ```ignore
+//! use rand_chacha::ChaCha20Rng;
+//! use rand_core::SeedableRng;
+//! use sgx_rand::compat::SgxRngAdapter;
+//!
+//! let mut rng = ChaCha20Rng::from_rng(SgxRngAdapter::new().unwrap()).unwrap();
+//! ```
+
+use std::io;
+use std::num::NonZeroU32;
+use rand_core::{CryptoRng, Error, RngCore};
+use sgx_trts::trts::rsgx_read_rand;
+use crate::{ChaChaRng, Rand, Rng, SeedableRng, SgxRng};
+
+/// A `rand_core` generator backed by the SGX random number generator.
+///
+/// `try_fill_bytes` reports a failure of `sgx_read_rand` as an error whose
+/// code is `Error::CUSTOM_START` plus the `sgx_status_t` value; the other
+/// methods panic, like `SgxRng`.
+#[derive(Debug)]
+pub struct SgxRngAdapter(SgxRng);
+
+impl SgxRngAdapter {
+    pub fn new() -> io::Result<SgxRngAdapter> {
+        SgxRng::new().map(SgxRngAdapter)
+    }
+}
+
+impl From<SgxRng> for SgxRngAdapter {
+    fn from(rng: SgxRng) -> SgxRngAdapter {
+        SgxRngAdapter(rng)
+    }
+}
+
+impl RngCore for SgxRngAdapter {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        rsgx_read_rand(dest).map_err(|status| {
+            // sgx_status_t values are far below 2^30, so this cannot overflow
+            let code = Error::CUSTOM_START + status as u32;
+            Error::from(NonZeroU32::new(code).unwrap())
+        })
+    }
+}
+
+impl CryptoRng for SgxRngAdapter {}
+
+/// Exposes an `sgx_rand::Rng` as a `rand_core::RngCore`.
+///
+/// The adapter is a `CryptoRng` only if the wrapped generator is `SgxRng`;
+/// use `SgxRngAdapter` for that.
+#[derive(Clone, Debug)]
+pub struct RngCoreAdapter<R> {
+    rng: R,
+}
+
+impl<R: Rng> RngCoreAdapter<R> {
+    pub fn new(rng: R) -> RngCoreAdapter<R> {
+        RngCoreAdapter { rng }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.rng
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    pub fn into_inner(self) -> R {
+        self.rng
+    }
+}
+
+impl<R: Rng> RngCore for RngCoreAdapter<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl<R: Rng + Rand> rand_core::SeedableRng for RngCoreAdapter<R> {
+    type Seed = [u8; 32];
+
+    /// Creates the generator with `Rand::rand` from a `ChaChaRng` keyed
+    /// with `seed`, so equal seeds give equal generators.
+    fn from_seed(seed: [u8; 32]) -> RngCoreAdapter<R> {
+        let mut key = [0_u32; 8];
+        for (k, chunk) in key.iter_mut().zip(seed.chunks(4)) {
+            *k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        let mut chacha = ChaChaRng::from_seed(&key[..]);
+        RngCoreAdapter { rng: R::rand(&mut chacha) }
+    }
+}
+
+/// Exposes a `rand_core::RngCore` as an `sgx_rand::Rng`, e.g. to sample
+/// the distributions of this crate with a `rand_chacha` generator.
+#[derive(Clone, Debug)]
+pub struct RngAdapter<R> {
+    rng: R,
+}
+
+impl<R: RngCore> RngAdapter<R> {
+    pub fn new(rng: R) -> RngAdapter<R> {
+        RngAdapter { rng }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.rng
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    pub fn into_inner(self) -> R {
+        self.rng
+    }
+}
+
+impl<R: RngCore> Rng for RngAdapter<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+}
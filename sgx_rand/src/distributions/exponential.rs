@@ -17,6 +17,9 @@
 
 //! The exponential distribution.
 
+use std::error;
+use std::fmt;
+
 use crate::{Rng, Rand};
 use crate::distributions::{ziggurat, ziggurat_tables, Sample, IndependentSample};
 
@@ -103,3 +106,92 @@ impl IndependentSample<f64> for Exp {
         n * self.lambda_inverse
     }
 }
+
+/// The exponential distribution `Exp(lambda)` truncated to `[0, upper]`.
+///
+/// This distribution has density function: `f(x) = lambda *
+/// exp(-lambda * x) / (1 - exp(-lambda * upper))` for `0 <= x <= upper`,
+/// and mean `1/lambda - upper * exp(-lambda * upper) / (1 - exp(-lambda *
+/// upper))`. It is useful for modelling durations which are cut off, e.g.
+/// by a timeout.
+///
+/// Samples are drawn by inverting the distribution function, so every
+/// sample costs one uniform variate.
+///
+/// # Example
+///
+/// ```rust
+/// use sgx_rand::distributions::{ExponentialTruncated, IndependentSample};
+///
+/// // service times with a mean of 10ms, cut off by a 50ms timeout
+/// let service = ExponentialTruncated::new(0.1, 50.0).unwrap();
+/// let v = service.ind_sample(&mut sgx_rand::thread_rng());
+/// println!("request served in {}ms", v);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ExponentialTruncated {
+    lambda_inverse: f64,
+    upper: f64,
+    /// `1 - exp(-lambda * upper)`, the probability mass of `Exp(lambda)`
+    /// below `upper`.
+    mass: f64,
+}
+
+/// Error returned by `ExponentialTruncated::new` for invalid parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExponentialTruncatedError {
+    /// `lambda` is not a positive finite number.
+    InvalidLambda,
+    /// `upper` is not a positive number.
+    InvalidUpper,
+}
+
+impl fmt::Display for ExponentialTruncatedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            ExponentialTruncatedError::InvalidLambda => "lambda is not positive and finite",
+            ExponentialTruncatedError::InvalidUpper => "upper bound is not positive",
+        })
+    }
+}
+
+impl error::Error for ExponentialTruncatedError {}
+
+impl ExponentialTruncated {
+    /// Construct a new `ExponentialTruncated` with rate `lambda` and
+    /// support `[0, upper]`. An infinite `upper` gives `Exp(lambda)`.
+    pub fn new(lambda: f64, upper: f64) -> Result<ExponentialTruncated, ExponentialTruncatedError> {
+        if !(lambda > 0.0 && lambda.is_finite()) {
+            return Err(ExponentialTruncatedError::InvalidLambda);
+        }
+        if upper.is_nan() || upper <= 0.0 {
+            return Err(ExponentialTruncatedError::InvalidUpper);
+        }
+        Ok(ExponentialTruncated {
+            lambda_inverse: 1.0 / lambda,
+            upper: upper,
+            mass: -(-lambda * upper).exp_m1(),
+        })
+    }
+
+    /// The mean of the distribution.
+    pub fn mean(&self) -> f64 {
+        if self.upper.is_infinite() {
+            return self.lambda_inverse;
+        }
+        self.lambda_inverse - self.upper * (-self.upper / self.lambda_inverse).exp() / self.mass
+    }
+}
+
+impl Sample<f64> for ExponentialTruncated {
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> f64 { self.ind_sample(rng) }
+}
+impl IndependentSample<f64> for ExponentialTruncated {
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> f64 {
+        // F^-1(u) = -ln(1 - u * mass) / lambda
+        let u = rng.gen::<f64>();
+        let x = -(-u * self.mass).ln_1p() * self.lambda_inverse;
+        // guard against rounding past the bound
+        if x > self.upper { self.upper } else { x }
+    }
+}
@@ -20,6 +20,7 @@
 use self::GammaRepr::*;
 use self::ChiSquaredRepr::*;
 
+use std::{error, f64, fmt};
 use crate::{Rng, Open01};
 use super::normal::StandardNormal;
 use super::{IndependentSample, Sample, Exp};
@@ -295,12 +296,17 @@ impl IndependentSample<f64> for FisherF {
 /// The Student t distribution, `t(nu)`, where `nu` is the degrees of
 /// freedom.
 ///
+/// Samples are drawn as the ratio of a standard normal variate to the
+/// square root of an independent chi-squared variate divided by `nu`. For
+/// `nu = 1` this is the Cauchy distribution, and as `nu` grows it
+/// approaches the standard normal distribution.
+///
 /// # Example
 ///
 /// ```rust
 /// use sgx_rand::distributions::{StudentT, IndependentSample};
 ///
-/// let t = StudentT::new(11.0);
+/// let t = StudentT::new(11.0).unwrap();
 /// let v = t.ind_sample(&mut sgx_rand::thread_rng());
 /// println!("{} is from a t(11) distribution", v)
 /// ```
@@ -310,15 +316,51 @@ pub struct StudentT {
     dof: f64
 }
 
+/// Error type returned from `StudentT::new`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TDistError {
+    /// The degrees of freedom are not a finite number greater than zero.
+    InvalidDegreesOfFreedom,
+}
+
+impl fmt::Display for TDistError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            TDistError::InvalidDegreesOfFreedom => "degrees of freedom must be finite and greater than zero",
+        })
+    }
+}
+
+impl error::Error for TDistError {}
+
 impl StudentT {
     /// Create a new Student t distribution with `n` degrees of
-    /// freedom. Panics if `n <= 0`.
-    pub fn new(n: f64) -> StudentT {
-        assert!(n > 0.0, "StudentT::new called with `n <= 0`");
-        StudentT {
+    /// freedom. Fails if `n <= 0` or `n` is not finite.
+    pub fn new(n: f64) -> Result<StudentT, TDistError> {
+        if n.is_nan() || n.is_infinite() || n <= 0.0 {
+            return Err(TDistError::InvalidDegreesOfFreedom);
+        }
+        Ok(StudentT {
             chi: ChiSquared::new(n),
             dof: n
+        })
+    }
+
+    /// The cumulative distribution function, `P(T <= x)`.
+    ///
+    /// This is evaluated through the regularized incomplete beta function,
+    /// `P(T <= x) = 1 - I(nu / (nu + x^2); nu / 2, 1 / 2) / 2` for
+    /// `x >= 0`, using its continued fraction expansion. The result is
+    /// accurate to about 1e-10.
+    pub fn cdf(&self, x: f64) -> f64 {
+        if x.is_nan() {
+            return x;
+        }
+        if x.is_infinite() {
+            return if x > 0.0 { 1.0 } else { 0.0 };
         }
+        let tail = 0.5 * inc_beta(0.5 * self.dof, 0.5, self.dof / (self.dof + x * x));
+        if x > 0.0 { 1.0 - tail } else { tail }
     }
 }
 impl Sample<f64> for StudentT {
@@ -330,3 +372,159 @@ impl IndependentSample<f64> for StudentT {
         norm * (self.dof / self.chi.ind_sample(rng)).sqrt()
     }
 }
+
+/// The Beta distribution `Beta(alpha, beta)`, over the interval `[0, 1]`.
+///
+/// The density function of this distribution is
+///
+/// ```text
+/// f(x) = x^(α - 1) * (1 - x)^(β - 1) / B(α, β)
+/// ```
+///
+/// where `B` is the Beta function and `α` and `β` are strictly positive.
+/// Samples are drawn as `X / (X + Y)` for independent `X ~ Gamma(α, 1)`
+/// and `Y ~ Gamma(β, 1)`.
+///
+/// # Example
+///
+/// ```rust
+/// use sgx_rand::distributions::{Beta, IndependentSample};
+///
+/// let beta = Beta::new(2.0, 5.0).unwrap();
+/// let v = beta.ind_sample(&mut sgx_rand::thread_rng());
+/// println!("{} is from a Beta(2, 5) distribution", v)
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Beta {
+    gamma_alpha: Gamma,
+    gamma_beta: Gamma,
+    // alpha / (alpha + beta), the limit of the distribution as both shapes
+    // go to zero
+    mean: f64,
+}
+
+/// Error type returned from `Beta::new`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BetaError {
+    /// `alpha` is not a finite number greater than zero.
+    InvalidAlpha,
+    /// `beta` is not a finite number greater than zero.
+    InvalidBeta,
+}
+
+impl fmt::Display for BetaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            BetaError::InvalidAlpha => "alpha must be finite and greater than zero",
+            BetaError::InvalidBeta => "beta must be finite and greater than zero",
+        })
+    }
+}
+
+impl error::Error for BetaError {}
+
+impl Beta {
+    /// Create a new Beta distribution with shape parameters `alpha` and
+    /// `beta`. Fails if either is `<= 0` or not finite.
+    pub fn new(alpha: f64, beta: f64) -> Result<Beta, BetaError> {
+        if !alpha.is_finite() || alpha <= 0.0 {
+            return Err(BetaError::InvalidAlpha);
+        }
+        if !beta.is_finite() || beta <= 0.0 {
+            return Err(BetaError::InvalidBeta);
+        }
+        Ok(Beta {
+            gamma_alpha: Gamma::new(alpha, 1.0),
+            gamma_beta: Gamma::new(beta, 1.0),
+            mean: alpha / (alpha + beta),
+        })
+    }
+}
+impl Sample<f64> for Beta {
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> f64 { self.ind_sample(rng) }
+}
+impl IndependentSample<f64> for Beta {
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> f64 {
+        let x = self.gamma_alpha.ind_sample(rng);
+        let y = self.gamma_beta.ind_sample(rng);
+        if x + y > 0.0 {
+            x / (x + y)
+        } else {
+            // With tiny shapes both variates can underflow to zero; the
+            // distribution is then concentrated at 0 and 1.
+            if rng.gen::<f64>() < self.mean { 1.0 } else { 0.0 }
+        }
+    }
+}
+
+// ln Γ(x) for x > 0, using the Lanczos approximation with g = 7.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |acc, (i, c)| acc + c / (x + (i + 1) as f64));
+    0.5 * (2.0 * f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+// The regularized incomplete beta function I(x; a, b).
+fn inc_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    // The continued fraction converges quickly for x < (a + 1) / (a + b + 2);
+    // otherwise use the symmetry I(x; a, b) = 1 - I(1 - x; b, a).
+    if x < (a + 1.0) / (a + b + 2.0) {
+        ln_front.exp() * beta_cont_frac(a, b, x) / a
+    } else {
+        1.0 - ln_front.exp() * beta_cont_frac(b, a, 1.0 - x) / b
+    }
+}
+
+// The continued fraction of the incomplete beta function, evaluated with
+// the modified Lentz method.
+fn beta_cont_frac(a: f64, b: f64, x: f64) -> f64 {
+    const MAX_ITERATIONS: usize = 300;
+    const EPSILON: f64 = 1e-15;
+    const TINY: f64 = 1e-300;
+
+    let not_tiny = |v: f64| if v.abs() < TINY { TINY } else { v };
+    let mut c = 1.0;
+    let mut d = 1.0 / not_tiny(1.0 - (a + b) * x / (a + 1.0));
+    let mut h = d;
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        // even step
+        let aa = m * (b - m) * x / ((a - 1.0 + m2) * (a + m2));
+        d = 1.0 / not_tiny(1.0 + aa * d);
+        c = not_tiny(1.0 + aa / c);
+        h *= d * c;
+        // odd step
+        let aa = -(a + m) * (a + b + m) * x / ((a + m2) * (a + 1.0 + m2));
+        d = 1.0 / not_tiny(1.0 + aa * d);
+        c = not_tiny(1.0 + aa / c);
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    h
+}
@@ -0,0 +1,272 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! The hypergeometric distribution.
+
+use std::error;
+use std::fmt;
+
+use crate::{Rng, Open01};
+use crate::distributions::{Sample, IndependentSample};
+
+/// The hypergeometric distribution `HyperGeometric(N, K, n)`.
+///
+/// This is the distribution of the number of successes when drawing `n`
+/// items without replacement from a population of `N` items of which `K`
+/// are successes. The probability mass function is
+///
+/// ```text
+/// f(k) = C(K, k) * C(N - K, n - k) / C(N, n)
+/// ```
+///
+/// where `C(a, b)` is the binomial coefficient.
+///
+/// When the mean is small, samples are drawn by inversion, searching
+/// upwards from the smallest possible value as described by Fishman
+/// (1979)[1]. Otherwise the ratio-of-uniforms rejection method HRUA of
+/// Stadlober (1989)[2] is used, whose time does not depend on the
+/// parameters.
+///
+/// # Example
+///
+/// ```rust
+/// use sgx_rand::distributions::{IndependentSample, HyperGeometric};
+///
+/// // 5 cards drawn from a deck of 52 with 13 hearts
+/// let hearts = HyperGeometric::new(52, 13, 5).unwrap();
+/// let v = hearts.ind_sample(&mut sgx_rand::thread_rng());
+/// println!("{} hearts in the hand", v);
+/// ```
+///
+/// [1]: George S. Fishman. 1979. "Sampling from the Poisson, Binomial and
+/// Hypergeometric Distributions" *Computing* 22, 4 (December 1979), 365-378.
+///
+/// [2]: Ernst Stadlober. 1989. "Sampling from Poisson, binomial and
+/// hypergeometric distributions: ratio of uniforms as a simple and fast
+/// alternative" *Bericht 303, Mathematisch-Statistische Sektion,
+/// Forschungsgesellschaft Joanneum*, Graz.
+#[derive(Clone, Copy, Debug)]
+pub struct HyperGeometric {
+    population: u64,
+    successes: u64,
+    draws: u64,
+    // Sampling is done with at most half of the population drawn and at
+    // most half of it successes; these say how to map the result back.
+    flip_successes: bool,
+    flip_draws: bool,
+    repr: HyperGeometricRepr,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum HyperGeometricRepr {
+    Inverse {
+        // P(X = min)
+        p_min: f64,
+        min: u64,
+        max: u64,
+    },
+    RatioOfUniforms {
+        a: f64,
+        h: f64,
+        mode_log_p: f64,
+        bound: f64,
+    },
+}
+
+/// Error returned by `HyperGeometric::new` for invalid parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HyperGeometricError {
+    /// `successes > population`.
+    TooManySuccesses,
+    /// `draws > population`.
+    TooManyDraws,
+}
+
+impl fmt::Display for HyperGeometricError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            HyperGeometricError::TooManySuccesses => "more successes than the population",
+            HyperGeometricError::TooManyDraws => "more draws than the population",
+        })
+    }
+}
+
+impl error::Error for HyperGeometricError {}
+
+// Means below this are sampled by inversion, which takes time linear in
+// the mean.
+const MAX_INVERSION_MEAN: f64 = 10.0;
+
+impl HyperGeometric {
+    /// Construct an object representing the `HyperGeometric(population,
+    /// successes, draws)` distribution.
+    pub fn new(population: u64, successes: u64, draws: u64) -> Result<HyperGeometric, HyperGeometricError> {
+        if successes > population {
+            return Err(HyperGeometricError::TooManySuccesses);
+        }
+        if draws > population {
+            return Err(HyperGeometricError::TooManyDraws);
+        }
+
+        let flip_successes = successes > population / 2;
+        let flip_draws = draws > population / 2;
+        let k = if flip_successes { population - successes } else { successes };
+        let n = if flip_draws { population - draws } else { draws };
+
+        let nf = population as f64;
+        let mean = n as f64 * k as f64 / nf;
+        let repr = if population == 0 || mean < MAX_INVERSION_MEAN {
+            let min = (n + k).saturating_sub(population);
+            let max = n.min(k);
+            HyperGeometricRepr::Inverse {
+                p_min: log_pmf(population, k, n, min).exp(),
+                min: min,
+                max: max,
+            }
+        } else {
+            let p = k as f64 / nf;
+            let q = 1.0 - p;
+            let a = mean + 0.5;
+            let s = ((nf - n as f64) * n as f64 * p * q / (nf - 1.0) + 0.5).sqrt();
+            // 2 * sqrt(2 / e) and 3 - 2 * sqrt(3 / e)
+            let h = 1.7155277699214135 * s + 0.8989161620588988;
+            let mode = ((n as f64 + 1.0) * (k as f64 + 1.0) / (nf + 2.0)).floor();
+            HyperGeometricRepr::RatioOfUniforms {
+                a: a,
+                h: h,
+                mode_log_p: log_pmf(population, k, n, mode as u64),
+                bound: (n.min(k) as f64 + 1.0).min((a + 16.0 * s).floor()),
+            }
+        };
+
+        Ok(HyperGeometric {
+            population: population,
+            successes: successes,
+            draws: draws,
+            flip_successes: flip_successes,
+            flip_draws: flip_draws,
+            repr: repr,
+        })
+    }
+
+    /// The mean of the distribution, `draws * successes / population`.
+    pub fn mean(&self) -> f64 {
+        if self.population == 0 {
+            0.0
+        } else {
+            self.draws as f64 * self.successes as f64 / self.population as f64
+        }
+    }
+
+    fn reduced(&self) -> (u64, u64) {
+        let k = if self.flip_successes { self.population - self.successes } else { self.successes };
+        let n = if self.flip_draws { self.population - self.draws } else { self.draws };
+        (k, n)
+    }
+}
+
+impl Sample<u64> for HyperGeometric {
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> u64 { self.ind_sample(rng) }
+}
+
+impl IndependentSample<u64> for HyperGeometric {
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> u64 {
+        let (k, n) = self.reduced();
+        let population = self.population;
+
+        let x = match self.repr {
+            HyperGeometricRepr::Inverse { p_min, min, max } => loop {
+                let mut u = rng.gen::<f64>();
+                let mut p = p_min;
+                let mut x = min;
+                while u > p && x < max {
+                    u -= p;
+                    // f(x + 1) / f(x)
+                    p *= (k - x) as f64 * (n - x) as f64
+                        / ((x + 1) as f64 * (population - k - n + x + 1) as f64);
+                    x += 1;
+                }
+                // rounding can leave some probability mass beyond `max`
+                if u <= p {
+                    break x;
+                }
+            },
+            HyperGeometricRepr::RatioOfUniforms { a, h, mode_log_p, bound } => loop {
+                let Open01(u) = rng.gen::<Open01<f64>>();
+                let v = rng.gen::<f64>();
+                let w = a + h * (v - 0.5) / u;
+                if w < 0.0 || w >= bound {
+                    continue;
+                }
+                let x = w.floor();
+                // ln(f(x) / f(mode))
+                let t = log_pmf(population, k, n, x as u64) - mode_log_p;
+                // squeezes around 2 * ln(u) <= t
+                if u * (4.0 - u) - 3.0 <= t {
+                    break x as u64;
+                }
+                if u * (u - t) >= 1.0 {
+                    continue;
+                }
+                if 2.0 * u.ln() <= t {
+                    break x as u64;
+                }
+            },
+        };
+
+        let x = if self.flip_successes { n - x } else { x };
+        if self.flip_draws { self.successes - x } else { x }
+    }
+}
+
+// ln f(x) for HyperGeometric(population, successes, draws)
+fn log_pmf(population: u64, successes: u64, draws: u64, x: u64) -> f64 {
+    ln_choose(successes, x) + ln_choose(population - successes, draws - x) - ln_choose(population, draws)
+}
+
+fn ln_choose(n: u64, k: u64) -> f64 {
+    ln_factorial(n) - ln_factorial(k) - ln_factorial(n - k)
+}
+
+// ln(n!) with Stirling's series, exact to double precision for all `n`
+fn ln_factorial(n: u64) -> f64 {
+    const COEFFS: [f64; 10] = [
+        8.333333333333333e-02, -2.777777777777778e-03,
+        7.936507936507937e-04, -5.952380952380952e-04,
+        8.417508417508418e-04, -1.917526917526918e-03,
+        6.410256410256410e-03, -2.955065359477124e-02,
+        1.796443723688307e-01, -1.39243221690590e+00,
+    ];
+
+    if n < 2 {
+        return 0.0;
+    }
+    // ln Γ(x) with x = n + 1, shifted up to at least 7 for the series
+    let x = n as f64 + 1.0;
+    let shift = if x < 7.0 { (7.0 - x).floor() } else { 0.0 };
+    let x0 = x + shift;
+    let x2 = 1.0 / (x0 * x0);
+    let series = COEFFS.iter().rev().fold(0.0, |acc, c| acc * x2 + c);
+    let mut ln_gamma = series / x0 + 0.5 * (2.0 * std::f64::consts::PI).ln()
+        + (x0 - 0.5) * x0.ln() - x0;
+    let mut y = x0;
+    for _ in 0..shift as u64 {
+        y -= 1.0;
+        ln_gamma -= y.ln();
+    }
+    ln_gamma
+}
@@ -29,14 +29,18 @@ use std::marker;
 use crate::{Rng, Rand};
 
 pub use self::range::Range;
-pub use self::gamma::{Gamma, ChiSquared, FisherF, StudentT};
+pub use self::gamma::{Gamma, ChiSquared, FisherF, StudentT, TDistError, Beta, BetaError};
 pub use self::normal::{Normal, LogNormal};
-pub use self::exponential::Exp;
+pub use self::exponential::{Exp, ExponentialTruncated, ExponentialTruncatedError};
+pub use self::hypergeometric::{HyperGeometric, HyperGeometricError};
+pub use self::weighted::{FloatWeightedChoice, WeightedError};
 
 pub mod range;
 pub mod gamma;
 pub mod normal;
 pub mod exponential;
+pub mod hypergeometric;
+pub mod weighted;
 
 /// Types that can be used to create a random instance of `Support`.
 pub trait Sample<Support> {
@@ -0,0 +1,145 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Selection from collections with floating point weights.
+
+use std::error;
+use std::fmt;
+use std::vec::Vec;
+
+use crate::Rng;
+use crate::distributions::{Sample, IndependentSample};
+
+/// Error type returned from `FloatWeightedChoice::new` and
+/// `weighted_sample`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WeightedError {
+    /// No items were given.
+    NoItem,
+    /// A weight is negative, infinite or NaN.
+    InvalidWeight,
+    /// All weights are zero.
+    AllWeightsZero,
+    /// The sum of the weights is not finite.
+    TooLargeTotal,
+}
+
+impl fmt::Display for WeightedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            WeightedError::NoItem => "no items to choose from",
+            WeightedError::InvalidWeight => "weights must be finite and non-negative",
+            WeightedError::AllWeightsZero => "at least one weight must be greater than zero",
+            WeightedError::TooLargeTotal => "the total weight must be finite",
+        })
+    }
+}
+
+impl error::Error for WeightedError {}
+
+/// A distribution that selects from a finite collection of items with
+/// `f64` weights.
+///
+/// This is the counterpart of `WeightedChoice` for fractional weights,
+/// such as importance weights or probabilities. It owns a copy of the
+/// items and validates the weights up front instead of panicking.
+/// An item is chosen with probability proportional to its weight; items
+/// with weight zero are never chosen.
+///
+/// Construction computes the cumulative weights once, so each sample
+/// costs a single `f64` and a binary search, O(log n).
+///
+/// # Example
+///
+/// ```rust
+/// use sgx_rand::distributions::{FloatWeightedChoice, IndependentSample};
+///
+/// let wc = FloatWeightedChoice::new(&[('a', 0.5), ('b', 1.0), ('c', 0.25)]).unwrap();
+/// let mut rng = sgx_rand::thread_rng();
+/// for _ in 0..7 {
+///      // on average prints 'a' twice, 'b' 4 times and 'c' once.
+///      println!("{}", wc.ind_sample(&mut rng));
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct FloatWeightedChoice<T> {
+    items: Vec<T>,
+    // cumulative[i] is the sum of the weights of items[..=i]
+    cumulative: Vec<f64>,
+    // index of the last item with a positive weight, used when rounding
+    // puts the sampled weight at the very end of the range
+    last: usize,
+}
+
+impl<T: Clone> FloatWeightedChoice<T> {
+    /// Create a new `FloatWeightedChoice` from `(item, weight)` pairs.
+    ///
+    /// Fails if `items` is empty, if any weight is negative or not
+    /// finite, if all weights are zero, or if their sum is not finite.
+    pub fn new(items: &[(T, f64)]) -> Result<FloatWeightedChoice<T>, WeightedError> {
+        if items.is_empty() {
+            return Err(WeightedError::NoItem);
+        }
+
+        let mut running_total = 0.0_f64;
+        let mut last = 0;
+        let mut cumulative = Vec::with_capacity(items.len());
+        for (i, &(_, weight)) in items.iter().enumerate() {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(WeightedError::InvalidWeight);
+            }
+            if weight > 0.0 {
+                last = i;
+            }
+            running_total += weight;
+            cumulative.push(running_total);
+        }
+        if running_total == 0.0 {
+            return Err(WeightedError::AllWeightsZero);
+        }
+        if !running_total.is_finite() {
+            return Err(WeightedError::TooLargeTotal);
+        }
+
+        Ok(FloatWeightedChoice {
+            items: items.iter().map(|&(ref item, _)| item.clone()).collect(),
+            cumulative,
+            last,
+        })
+    }
+
+    /// The sum of all weights.
+    pub fn total_weight(&self) -> f64 {
+        self.cumulative[self.cumulative.len() - 1]
+    }
+}
+
+impl<T: Clone> Sample<T> for FloatWeightedChoice<T> {
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> T { self.ind_sample(rng) }
+}
+
+impl<T: Clone> IndependentSample<T> for FloatWeightedChoice<T> {
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> T {
+        // choose a weight in [0, total_weight) and find the first item
+        // whose cumulative weight is greater than it. Items with weight
+        // zero have the same cumulative weight as their predecessor, so
+        // they are always skipped.
+        let sample_weight = rng.gen::<f64>() * self.total_weight();
+        let idx = self.cumulative.partition_point(|&w| w <= sample_weight);
+        self.items[idx.min(self.last)].clone()
+    }
+}
@@ -0,0 +1,50 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! `Rng` for the NIST SP 800-90A DRBGs of `sgx_tcrypto`.
+
+use sgx_tcrypto::prng::{CtrDrbg, CTR_DRBG_MAX_REQUEST_LEN};
+use crate::Rng;
+
+/// Draws from `CtrDrbg::generate` without additional input, splitting
+/// large requests into several.
+///
+/// # Panics
+///
+/// Panics if the DRBG has to be reseeded; use `CtrDrbg::generate` directly
+/// to handle that case.
+impl Rng for CtrDrbg {
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0_u8; 4];
+        self.fill_bytes(&mut buf);
+        u32::from_le_bytes(buf)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0_u8; 8];
+        self.fill_bytes(&mut buf);
+        u64::from_le_bytes(buf)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(CTR_DRBG_MAX_REQUEST_LEN) {
+            if let Err(e) = self.generate(chunk, &[]) {
+                panic!("CtrDrbg::generate failed: {}", e);
+            }
+        }
+    }
+}
@@ -24,11 +24,13 @@
 
 extern crate sgx_types;
 extern crate sgx_trts;
+extern crate sgx_tcrypto;
 #[cfg(not(target_env = "sgx"))]
 #[macro_use]
 extern crate sgx_tstd as std;
 
 use std::boxed::Box;
+use std::string::String;
 use std::vec::Vec;
 use std::cell::RefCell;
 use std::marker;
@@ -36,27 +38,35 @@ use std::mem;
 use std::io;
 use std::rc::Rc;
 use std::num::Wrapping as w;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
 
-pub use os::SgxRng;
+pub use os::{fill_bytes_verified, EntropyDegradedError, SgxRng};
 
 pub use isaac::{IsaacRng, Isaac64Rng};
 pub use chacha::ChaChaRng;
+pub use pcg::PcgRng;
 
 #[cfg(target_pointer_width = "32")]
 use IsaacRng as IsaacWordRng;
 #[cfg(target_pointer_width = "64")]
 use Isaac64Rng as IsaacWordRng;
 
-use distributions::{Range, IndependentSample};
+use distributions::{Range, IndependentSample, WeightedError};
 use distributions::range::SampleRange;
 
 pub mod distributions;
 pub mod isaac;
 pub mod chacha;
+pub mod pcg;
+#[cfg(feature = "rand_core")]
+pub mod compat;
+pub mod zeroize;
 pub mod reseeding;
 mod rand_impls;
 pub mod os;
 pub mod read;
+mod drbg;
 
 #[allow(bad_style)]
 type w64 = w<u64>;
@@ -314,6 +324,99 @@ pub trait Rng {
         AsciiGenerator { rng: self }
     }
 
+    /// Return a random string of `len` characters from the set A-Z,a-z.
+    ///
+    /// Use an `SgxRng` when the string needs to be unpredictable.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sgx_rand::{Rng, SgxRng};
+    ///
+    /// let mut rng = SgxRng::new().unwrap();
+    /// println!("{}", rng.gen_alphabetic_string(10));
+    /// ```
+    fn gen_alphabetic_string(&mut self, len: usize) -> String where Self: Sized {
+        gen_string(self, &GEN_ASCII_STR_CHARSET[..52], len)
+    }
+
+    /// Return a random string of `len` characters from the set A-Z,a-z,0-9.
+    ///
+    /// Use an `SgxRng` when the string needs to be unpredictable.
+    fn gen_alphanumeric_string(&mut self, len: usize) -> String where Self: Sized {
+        gen_string(self, GEN_ASCII_STR_CHARSET, len)
+    }
+
+    /// Return a random string of `len` characters from the base64url
+    /// alphabet A-Z,a-z,0-9,`-`,`_`, suitable for tokens in URLs and
+    /// headers.
+    ///
+    /// Use an `SgxRng` when the string needs to be unpredictable.
+    fn gen_urlsafe_string(&mut self, len: usize) -> String where Self: Sized {
+        gen_string(self, GEN_URLSAFE_STR_CHARSET, len)
+    }
+
+    /// Return a random token of `bits` bits, e.g. for a session ID or a
+    /// bearer token.
+    ///
+    /// The token is read with a single `fill_bytes` call, so it carries as
+    /// much entropy as the generator provides. Use an `SgxRng`, or another
+    /// cryptographically secure generator, for tokens that must not be
+    /// guessable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is zero or not a multiple of 8.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sgx_rand::{Rng, SgxRng};
+    ///
+    /// let mut rng = SgxRng::new().unwrap();
+    /// assert_eq!(rng.gen_secure_token(128).len(), 16);
+    /// ```
+    fn gen_secure_token(&mut self, bits: usize) -> Vec<u8> where Self: Sized {
+        assert!(bits > 0 && bits % 8 == 0, "token size must be a positive multiple of 8 bits");
+        let mut token = vec![0_u8; bits / 8];
+        self.fill_bytes(&mut token);
+        token
+    }
+
+    /// Return a random token of `bits` bits as unpadded base64url text,
+    /// which can be used in URLs, cookies and headers as is.
+    ///
+    /// A 128-bit token is 22 characters long.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is zero or not a multiple of 8.
+    fn gen_secure_token_string(&mut self, bits: usize) -> String where Self: Sized {
+        encode_base64url(&self.gen_secure_token(bits))
+    }
+
+    /// Return a random RFC 4122 version 4 UUID.
+    ///
+    /// 122 of the 128 bits are random; the remaining bits hold the version
+    /// (4) and the variant (`10`).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sgx_rand::{Rng, SgxRng};
+    ///
+    /// let uuid = SgxRng::new().unwrap().gen_uuid_v4();
+    /// assert_eq!(uuid[6] >> 4, 4);
+    /// assert_eq!(uuid[8] >> 6, 0b10);
+    /// ```
+    fn gen_uuid_v4(&mut self) -> [u8; 16] where Self: Sized {
+        let mut uuid = [0_u8; 16];
+        self.fill_bytes(&mut uuid);
+        uuid[6] = (uuid[6] & 0x0f) | 0x40;
+        uuid[8] = (uuid[8] & 0x3f) | 0x80;
+        uuid
+    }
+
     /// Return a random element from `values`.
     ///
     /// Return `None` if `values` is empty.
@@ -440,6 +543,34 @@ impl<'a, T: Rand, R: Rng> Iterator for Generator<'a, T, R> {
     }
 }
 
+const GEN_ASCII_STR_CHARSET: &[u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZ\
+      abcdefghijklmnopqrstuvwxyz\
+      0123456789";
+
+const GEN_URLSAFE_STR_CHARSET: &[u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZ\
+      abcdefghijklmnopqrstuvwxyz\
+      0123456789-_";
+
+fn gen_string<R: Rng>(rng: &mut R, charset: &[u8], len: usize) -> String {
+    debug_assert!(len > 0, "random string length must not be zero");
+    (0..len).map(|_| *rng.choose(charset).unwrap() as char).collect()
+}
+
+// Encodes `data` as base64url without padding (RFC 4648, section 5).
+fn encode_base64url(data: &[u8]) -> String {
+    let mut out = String::with_capacity((data.len() * 4 + 2) / 3);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..chunk.len() + 1 {
+            out.push(GEN_URLSAFE_STR_CHARSET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
 /// Iterator which will continuously generate random ascii characters.
 ///
 /// This iterator is created via the [`gen_ascii_chars`] method on [`Rng`].
@@ -455,10 +586,6 @@ impl<'a, R: Rng> Iterator for AsciiGenerator<'a, R> {
     type Item = char;
 
     fn next(&mut self) -> Option<char> {
-        const GEN_ASCII_STR_CHARSET: &'static [u8] =
-            b"ABCDEFGHIJKLMNOPQRSTUVWXYZ\
-              abcdefghijklmnopqrstuvwxyz\
-              0123456789";
         Some(*self.rng.choose(GEN_ASCII_STR_CHARSET).unwrap() as char)
     }
 }
@@ -657,11 +784,11 @@ impl<'a> SeedableRng<&'a [usize]> for StdRng {
     fn reseed(&mut self, seed: &'a [usize]) {
         // the internal RNG can just be seeded from the above
         // randomness.
-        self.rng.reseed(unsafe {mem::transmute(seed)})
+        SeedableRng::<&'a [_]>::reseed(&mut self.rng, unsafe {mem::transmute(seed)})
     }
 
     fn from_seed(seed: &'a [usize]) -> StdRng {
-        StdRng { rng: SeedableRng::from_seed(unsafe {mem::transmute(seed)}) }
+        StdRng { rng: SeedableRng::<&'a [_]>::from_seed(unsafe {mem::transmute(seed)}) }
     }
 }
 
@@ -820,3 +947,82 @@ pub fn sample<T, I, R>(rng: &mut R, iterable: I, amount: usize) -> Vec<T>
     }
     reservoir
 }
+
+/// Randomly sample up to `amount` elements from a finite iterator, where
+/// the chance of each element being chosen is proportional to the weight
+/// returned for it by `weight`.
+/// The order of elements in the sample is not random.
+///
+/// This is the weighted reservoir algorithm A-Res of Efraimidis and
+/// Spirakis (2006)[1]: every element gets the key `u^(1/w)` for a uniform
+/// `u`, and the `amount` elements with the largest keys are kept. Elements
+/// with weight zero are never chosen, so fewer than `amount` elements are
+/// returned if there are not enough with a positive weight.
+///
+/// Fails if any weight is negative or not finite.
+///
+/// # Example
+///
+/// ```rust
+/// use sgx_rand::{thread_rng, weighted_sample};
+///
+/// let mut rng = thread_rng();
+/// // larger numbers are more likely to be chosen
+/// let sample = weighted_sample(&mut rng, 1..100, |&x| x as f64, 5).unwrap();
+/// println!("{:?}", sample);
+/// ```
+///
+/// [1]: Pavlos S. Efraimidis and Paul G. Spirakis. 2006. "Weighted random
+/// sampling with a reservoir" *Information Processing Letters* 97, 5
+/// (March 2006), 181-185.
+pub fn weighted_sample<T, I, W, R>(rng: &mut R,
+                                   iterable: I,
+                                   mut weight: W,
+                                   amount: usize) -> Result<Vec<T>, WeightedError>
+    where I: IntoIterator<Item=T>,
+          W: FnMut(&T) -> f64,
+          R: Rng,
+{
+    // a min-heap on the keys, so the element to evict is on top
+    struct Keyed<T> {
+        key: f64,
+        elem: T,
+    }
+    impl<T> PartialEq for Keyed<T> {
+        fn eq(&self, other: &Self) -> bool { self.cmp(other) == Ordering::Equal }
+    }
+    impl<T> Eq for Keyed<T> {}
+    impl<T> PartialOrd for Keyed<T> {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+    }
+    impl<T> Ord for Keyed<T> {
+        fn cmp(&self, other: &Self) -> Ordering { other.key.total_cmp(&self.key) }
+    }
+
+    // `amount` may be far larger than the input, only reserve what can be
+    // filled
+    let iter = iterable.into_iter();
+    let mut reservoir: BinaryHeap<Keyed<T>> =
+        BinaryHeap::with_capacity(amount.min(iter.size_hint().0));
+    for elem in iter {
+        let elem_weight = weight(&elem);
+        if !elem_weight.is_finite() || elem_weight < 0.0 {
+            return Err(WeightedError::InvalidWeight);
+        }
+        if elem_weight == 0.0 || amount == 0 {
+            continue;
+        }
+        // ln(u^(1/w)) = ln(u) / w orders the same way and does not
+        // underflow for small weights
+        let Open01(u) = rng.gen::<Open01<f64>>();
+        let key = u.ln() / elem_weight;
+        if reservoir.len() < amount {
+            reservoir.push(Keyed { key, elem });
+        } else if let Some(mut smallest) = reservoir.peek_mut() {
+            if key > smallest.key {
+                *smallest = Keyed { key, elem };
+            }
+        }
+    }
+    Ok(reservoir.into_iter().map(|keyed| keyed.elem).collect())
+}
@@ -18,7 +18,7 @@
 //! Interfaces to the operating system provided random number
 //! generators.
 
-use std::{io, mem, fmt};
+use std::{error, io, mem, fmt};
 use crate::Rng;
 
 /// A random number generator
@@ -29,6 +29,22 @@ impl SgxRng {
     pub fn new() -> io::Result<SgxRng> {
         imp::SgxRng::new().map(SgxRng)
     }
+
+    /// Fill `dest` with random data and check that the entropy source is
+    /// not stuck.
+    ///
+    /// See `fill_bytes_verified` for the check. It reads twice as much
+    /// entropy as `fill_bytes_fast`.
+    pub fn fill_bytes_verified(&mut self, dest: &mut [u8]) -> Result<(), EntropyDegradedError> {
+        fill_bytes_verified(self, dest)
+    }
+
+    /// Fill `dest` with random data without checking the entropy source.
+    ///
+    /// This is the same as `fill_bytes`.
+    pub fn fill_bytes_fast(&mut self, dest: &mut [u8]) {
+        self.fill_bytes(dest)
+    }
 }
 
 impl Rng for SgxRng {
@@ -43,6 +59,47 @@ impl fmt::Debug for SgxRng {
     }
 }
 
+/// The error returned when an entropy source repeats its output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntropyDegradedError;
+
+impl fmt::Display for EntropyDegradedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("the entropy source returned the same output twice")
+    }
+}
+
+impl error::Error for EntropyDegradedError {}
+
+/// Fill `dest` from `source`, then check that `source` is not stuck.
+///
+/// After filling `dest`, a second sample of the same size is read and XORed
+/// with it byte by byte. If the result is all zeros, the two samples were
+/// identical, as happens when the source always returns the same value
+/// (for example all zeros), and `EntropyDegradedError` is returned. The
+/// contents of `dest` must not be used in that case.
+///
+/// The check doubles the amount of entropy read. A healthy source fails it
+/// with probability 2<sup>-8n</sup> for an `n` byte `dest`, so it is only
+/// meaningful for buffers of at least 16 bytes, such as keys and nonces.
+/// An empty `dest` always passes.
+pub fn fill_bytes_verified<R: Rng + ?Sized>(source: &mut R, dest: &mut [u8]) -> Result<(), EntropyDegradedError> {
+    source.fill_bytes(dest);
+
+    let mut sample = [0_u8; 64];
+    let mut diff = 0_u8;
+    for chunk in dest.chunks(sample.len()) {
+        let sample = &mut sample[..chunk.len()];
+        source.fill_bytes(sample);
+        diff = chunk.iter().zip(sample.iter()).fold(diff, |acc, (a, b)| acc | (a ^ b));
+    }
+    if dest.is_empty() || diff != 0 {
+        Ok(())
+    } else {
+        Err(EntropyDegradedError)
+    }
+}
+
 fn next_u32(fill_buf: &mut dyn FnMut(&mut [u8])) -> u32 {
     let mut buf: [u8; 4] = [0; 4];
     fill_buf(&mut buf);
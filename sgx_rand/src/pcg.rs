@@ -0,0 +1,109 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! The PCG random number generator.
+
+use std::num::Wrapping as w;
+use crate::{Rand, Rng, SeedableRng, w64};
+
+const MULTIPLIER: w64 = w(6364136223846793005);
+
+/// A PCG-XSH-RR 64/32 random number generator [1].
+///
+/// The generator has 64 bits of state plus a 64 bit stream selector, and
+/// produces 32 bits per step by permuting the old state with a xorshift
+/// followed by a random rotation. It is small, fast and passes common
+/// statistical test suites, but it is not suitable for cryptographic
+/// purposes: its state can be recovered from a few outputs. Use `SgxRng`
+/// or `ChaChaRng` when the output must be unpredictable.
+///
+/// The seed is `[initstate, initseq]`, as in the reference `pcg32_srandom`:
+/// generators seeded with different `initseq` values produce independent
+/// streams.
+///
+/// [1]: M. E. O'Neill, [*PCG: A Family of Simple Fast Space-Efficient
+/// Statistically Good Algorithms for Random Number
+/// Generation*](https://www.pcg-random.org/paper.html)
+#[derive(Copy, Clone, Debug)]
+pub struct PcgRng {
+    state: w64,
+    inc: w64,
+}
+
+impl PcgRng {
+    /// Creates a new PcgRng instance which is not seeded.
+    ///
+    /// The initial values of this RNG are constants, so all generators created
+    /// by this function will yield the same stream of random numbers. It is
+    /// highly recommended that this is created through `SeedableRng` instead of
+    /// this function
+    pub fn new_unseeded() -> PcgRng {
+        PcgRng {
+            state: w(0x853c49e6748fea9b),
+            inc: w(0xda3e39cb94b95bdb),
+        }
+    }
+
+    #[inline]
+    fn step(&mut self) {
+        self.state = self.state * MULTIPLIER + self.inc;
+    }
+}
+
+impl Rng for PcgRng {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        let old = self.state.0;
+        self.step();
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+}
+
+impl SeedableRng<[u64; 2]> for PcgRng {
+    /// Reseed a PcgRng. This will panic if `seed` is entirely 0.
+    fn reseed(&mut self, seed: [u64; 2]) {
+        assert!(!seed.iter().all(|&x| x == 0),
+                "PcgRng.reseed called with an all zero seed.");
+
+        self.state = w(0);
+        self.inc = w((seed[1] << 1) | 1);
+        self.step();
+        self.state += w(seed[0]);
+        self.step();
+    }
+
+    /// Create a new PcgRng. This will panic if `seed` is entirely 0.
+    fn from_seed(seed: [u64; 2]) -> PcgRng {
+        assert!(!seed.iter().all(|&x| x == 0),
+                "PcgRng::from_seed called with an all zero seed.");
+
+        let mut rng = PcgRng { state: w(0), inc: w(0) };
+        rng.reseed(seed);
+        rng
+    }
+}
+
+impl Rand for PcgRng {
+    fn rand<R: Rng>(rng: &mut R) -> PcgRng {
+        let mut seed: [u64; 2] = [rng.gen(), rng.gen()];
+        while seed == [0, 0] {
+            seed = [rng.gen(), rng.gen()];
+        }
+        SeedableRng::from_seed(seed)
+    }
+}
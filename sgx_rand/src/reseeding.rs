@@ -16,9 +16,13 @@
 // under the License..
 
 //! A wrapper around another RNG that reseeds it after it
-//! generates a certain number of random bytes.
+//! generates a certain number of random bytes, after a certain
+//! number of calls or after some time.
 
 use std::default::Default;
+use std::time::{Duration, Instant};
+use std::untrusted::time::InstantEx;
+use std::vec::Vec;
 
 use crate::{Rng, SeedableRng};
 
@@ -26,13 +30,68 @@ use crate::{Rng, SeedableRng};
 /// before it is reseeded
 const DEFAULT_GENERATION_THRESHOLD: u64 = 32 * 1024;
 
-/// A wrapper around any RNG which reseeds the underlying RNG after it
-/// has generated a certain number of random bytes.
+/// When a `ReseedingRng` reseeds the underlying RNG.
+///
+/// A `u64` converts into `ByteThreshold`, so `ReseedingRng::new(rng, 1024,
+/// reseeder)` keeps working.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReseedStrategy {
+    /// Reseed after the given number of bytes has been generated.
+    ByteThreshold(u64),
+    /// Reseed after the given number of calls to `next_u32`, `next_u64`
+    /// or `fill_bytes`.
+    GenerationCount(u64),
+    /// Reseed when the given time has passed since the last reseed.
+    ///
+    /// The time is read from the untrusted host through an OCALL on every
+    /// generation, so this is only a best effort policy, e.g. for rotating
+    /// keys per epoch. Combine it with `ByteThreshold` when the amount of
+    /// output must be bounded.
+    TimedInterval(Duration),
+    /// Reseed as soon as any of the strategies asks for it. An empty list
+    /// never reseeds.
+    Combined(Vec<ReseedStrategy>),
+}
+
+impl ReseedStrategy {
+    fn is_timed(&self) -> bool {
+        match *self {
+            ReseedStrategy::TimedInterval(_) => true,
+            ReseedStrategy::Combined(ref strategies) => strategies.iter().any(|s| s.is_timed()),
+            _ => false,
+        }
+    }
+
+    fn is_due(&self, bytes_generated: u64, generations: u64, last_reseed: Option<&Instant>) -> bool {
+        match *self {
+            ReseedStrategy::ByteThreshold(threshold) => bytes_generated >= threshold,
+            ReseedStrategy::GenerationCount(count) => generations >= count,
+            ReseedStrategy::TimedInterval(interval) => {
+                last_reseed.map_or(false, |t| t.elapsed() >= interval)
+            }
+            ReseedStrategy::Combined(ref strategies) => strategies
+                .iter()
+                .any(|s| s.is_due(bytes_generated, generations, last_reseed)),
+        }
+    }
+}
+
+impl From<u64> for ReseedStrategy {
+    fn from(generation_threshold: u64) -> ReseedStrategy {
+        ReseedStrategy::ByteThreshold(generation_threshold)
+    }
+}
+
+/// A wrapper around any RNG which reseeds the underlying RNG according
+/// to a `ReseedStrategy`, by default after it has generated a certain
+/// number of random bytes.
 #[derive(Debug)]
 pub struct ReseedingRng<R, Rsdr> {
     rng: R,
-    generation_threshold: u64,
+    strategy: ReseedStrategy,
     bytes_generated: u64,
+    generations: u64,
+    last_reseed: Option<Instant>,
     /// Controls the behaviour when reseeding the RNG.
     pub reseeder: Rsdr,
 }
@@ -43,23 +102,40 @@ impl<R: Rng, Rsdr: Reseeder<R>> ReseedingRng<R, Rsdr> {
     /// # Arguments
     ///
     /// * `rng`: the random number generator to use.
-    /// * `generation_threshold`: the number of bytes of entropy at which to reseed the RNG.
+    /// * `strategy`: when to reseed the RNG, or the number of bytes of entropy
+    ///   at which to reseed it.
     /// * `reseeder`: the reseeding object to use.
-    pub fn new(rng: R, generation_threshold: u64, reseeder: Rsdr) -> ReseedingRng<R,Rsdr> {
+    pub fn new<S: Into<ReseedStrategy>>(rng: R, strategy: S, reseeder: Rsdr) -> ReseedingRng<R,Rsdr> {
+        let strategy = strategy.into();
+        let last_reseed = if strategy.is_timed() { Some(Instant::now()) } else { None };
         ReseedingRng {
             rng: rng,
-            generation_threshold: generation_threshold,
+            strategy: strategy,
             bytes_generated: 0,
+            generations: 0,
+            last_reseed: last_reseed,
             reseeder: reseeder
         }
     }
 
-    /// Reseed the internal RNG if the number of bytes that have been
-    /// generated exceed the threshold.
+    /// The strategy deciding when to reseed.
+    pub fn strategy(&self) -> &ReseedStrategy {
+        &self.strategy
+    }
+
+    /// Reseed the internal RNG if the strategy asks for it.
     pub fn reseed_if_necessary(&mut self) {
-        if self.bytes_generated >= self.generation_threshold {
+        if self.strategy.is_due(self.bytes_generated, self.generations, self.last_reseed.as_ref()) {
             self.reseeder.reseed(&mut self.rng);
-            self.bytes_generated = 0;
+            self.reset();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.bytes_generated = 0;
+        self.generations = 0;
+        if self.last_reseed.is_some() {
+            self.last_reseed = Some(Instant::now());
         }
     }
 }
@@ -69,18 +145,21 @@ impl<R: Rng, Rsdr: Reseeder<R>> Rng for ReseedingRng<R, Rsdr> {
     fn next_u32(&mut self) -> u32 {
         self.reseed_if_necessary();
         self.bytes_generated += 4;
+        self.generations += 1;
         self.rng.next_u32()
     }
 
     fn next_u64(&mut self) -> u64 {
         self.reseed_if_necessary();
         self.bytes_generated += 8;
+        self.generations += 1;
         self.rng.next_u64()
     }
 
     fn fill_bytes(&mut self, dest: &mut [u8]) {
         self.reseed_if_necessary();
         self.bytes_generated += dest.len() as u64;
+        self.generations += 1;
         self.rng.fill_bytes(dest)
     }
 }
@@ -90,7 +169,7 @@ impl<S, R: SeedableRng<S>, Rsdr: Reseeder<R> + Default>
     fn reseed(&mut self, (rsdr, seed): (Rsdr, S)) {
         self.rng.reseed(seed);
         self.reseeder = rsdr;
-        self.bytes_generated = 0;
+        self.reset();
     }
 
     /// Create a new `ReseedingRng` from the given reseeder and
@@ -98,8 +177,10 @@ impl<S, R: SeedableRng<S>, Rsdr: Reseeder<R> + Default>
     fn from_seed((rsdr, seed): (Rsdr, S)) -> ReseedingRng<R, Rsdr> {
         ReseedingRng {
             rng: SeedableRng::from_seed(seed),
-            generation_threshold: DEFAULT_GENERATION_THRESHOLD,
+            strategy: ReseedStrategy::ByteThreshold(DEFAULT_GENERATION_THRESHOLD),
             bytes_generated: 0,
+            generations: 0,
+            last_reseed: None,
             reseeder: rsdr
         }
     }
@@ -0,0 +1,202 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Containers that erase seed material when dropped.
+//!
+//! A seed left behind in enclave memory lets anyone who later reads that
+//! memory, e.g. through a disclosure bug, reconstruct the generator state.
+//! `ZeroingArray` and `ZeroingVec` overwrite their contents with volatile
+//! writes when they are dropped. Both dereference to a slice, so they can
+//! be passed to `SeedableRng::from_seed` directly:
+//!
+//! * `&ZeroingArray<T, N>` and `&ZeroingVec<T>` for generators seeded with
+//!   a slice, like `IsaacRng`, `Isaac64Rng` and `ChaChaRng`. For `StdRng`,
+//!   whose type is often only known from the binding, write `&seed[..]`.
+//! * `ZeroingArray<T, N>` by value for generators seeded with `[T; N]`,
+//!   like `XorShiftRng` and `PcgRng`. The array is moved into the
+//!   generator's own `from_seed`; that copy is not erased.
+//!
+//! ```rust
+//! use sgx_rand::{IsaacRng, Rng, SeedableRng, SgxRng};
+//! use sgx_rand::zeroize::ZeroingArray;
+//!
+//! let seed: ZeroingArray<u32, 256> = ZeroingArray::from_rng(&mut SgxRng::new().unwrap());
+//! let mut rng = IsaacRng::from_seed(&seed);
+//! drop(seed); // the seed is erased here
+//! println!("{}", rng.gen::<u32>());
+//! ```
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::sync::atomic::{compiler_fence, Ordering};
+use std::vec::Vec;
+use crate::{Rand, Rng, SeedableRng};
+
+// Overwrites `len` elements at `data` with `T::default()` in a way the
+// compiler cannot elide, even though the memory is about to be freed.
+unsafe fn volatile_clear<T: Copy + Default>(data: *mut T, len: usize) {
+    for i in 0..len {
+        ptr::write_volatile(data.add(i), T::default());
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+/// A fixed-size array that is zeroed when dropped.
+pub struct ZeroingArray<T: Copy + Default, const N: usize> {
+    data: [T; N],
+}
+
+impl<T: Copy + Default, const N: usize> ZeroingArray<T, N> {
+    pub fn new(data: [T; N]) -> ZeroingArray<T, N> {
+        ZeroingArray { data }
+    }
+
+    /// Creates an array filled with `T::default()`, i.e. zeros for integers.
+    pub fn zeroed() -> ZeroingArray<T, N> {
+        ZeroingArray { data: [T::default(); N] }
+    }
+
+    /// Creates an array filled with values generated by `rng`, without a
+    /// temporary copy.
+    pub fn from_rng<R: Rng>(rng: &mut R) -> ZeroingArray<T, N>
+    where
+        T: Rand,
+    {
+        let mut array = ZeroingArray::zeroed();
+        for v in array.data.iter_mut() {
+            *v = rng.gen();
+        }
+        array
+    }
+
+    /// Zeroes the contents now instead of on drop.
+    pub fn zeroize(&mut self) {
+        unsafe { volatile_clear(self.data.as_mut_ptr(), N) }
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Deref for ZeroingArray<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.data
+    }
+}
+
+impl<T: Copy + Default, const N: usize> DerefMut for ZeroingArray<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+}
+
+impl<T: Copy + Default, const N: usize> From<[T; N]> for ZeroingArray<T, N> {
+    fn from(data: [T; N]) -> ZeroingArray<T, N> {
+        ZeroingArray::new(data)
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Drop for ZeroingArray<T, N> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<T: Copy + Default, const N: usize> fmt::Debug for ZeroingArray<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ZeroingArray {{ len: {} }}", N)
+    }
+}
+
+/// A heap-allocated buffer that is zeroed, up to its full capacity, when
+/// dropped.
+///
+/// The buffer cannot grow: a reallocation would leave a copy of the
+/// contents behind in freed memory. Its length is fixed on creation.
+pub struct ZeroingVec<T: Copy + Default> {
+    data: Vec<T>,
+}
+
+impl<T: Copy + Default> ZeroingVec<T> {
+    /// Creates a buffer of `len` elements set to `T::default()`.
+    pub fn zeroed(len: usize) -> ZeroingVec<T> {
+        ZeroingVec { data: vec![T::default(); len] }
+    }
+
+    /// Takes ownership of `data`. Copies the vector may have left behind
+    /// when it grew are out of reach and are not erased.
+    pub fn from_vec(data: Vec<T>) -> ZeroingVec<T> {
+        ZeroingVec { data }
+    }
+
+    /// Creates a buffer of `len` elements generated by `rng`.
+    pub fn from_rng<R: Rng>(rng: &mut R, len: usize) -> ZeroingVec<T>
+    where
+        T: Rand,
+    {
+        let mut vec = ZeroingVec::zeroed(len);
+        for v in vec.data.iter_mut() {
+            *v = rng.gen();
+        }
+        vec
+    }
+
+    /// Zeroes the contents now instead of on drop. The length is unchanged.
+    pub fn zeroize(&mut self) {
+        unsafe { volatile_clear(self.data.as_mut_ptr(), self.data.capacity()) }
+    }
+}
+
+impl<T: Copy + Default> Deref for ZeroingVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.data
+    }
+}
+
+impl<T: Copy + Default> DerefMut for ZeroingVec<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+}
+
+impl<T: Copy + Default> Drop for ZeroingVec<T> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<T: Copy + Default> fmt::Debug for ZeroingVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ZeroingVec {{ len: {} }}", self.data.len())
+    }
+}
+
+impl<R, T, const N: usize> SeedableRng<ZeroingArray<T, N>> for R
+where
+    R: SeedableRng<[T; N]>,
+    T: Copy + Default,
+{
+    fn reseed(&mut self, seed: ZeroingArray<T, N>) {
+        self.reseed(seed.data)
+    }
+
+    fn from_seed(seed: ZeroingArray<T, N>) -> R {
+        R::from_seed(seed.data)
+    }
+}
@@ -0,0 +1,422 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//!
+//! Chunked authenticated encryption of byte streams
+//!
+//! An encrypted stream starts with a random stream id, followed by a
+//! sequence of chunks, each laid out as
+//!
+//! ```text
+//! | len: u32 LE | last: u8 | nonce | ciphertext | tag |
+//! ```
+//!
+//! where `len` covers `nonce | ciphertext | tag`. Every chunk is sealed with
+//! a fresh random nonce and `stream id | counter: u64 LE | last: u8` as
+//! additional data, so reordered, dropped or truncated chunks, and chunks
+//! moved over from another stream under the same key, fail to decrypt.
+//!
+//! `StreamEncryptor` and `StreamDecryptor` only produce and consume
+//! chunks; they do no I/O. In an enclave using sgx_tstd they are meant to
+//! be driven by `std::io::FramedWriter` and `std::io::FramedReader`.
+//!
+use crate::aes::AesGcm128;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use sgx_types::*;
+
+/// The length of the random id that precedes the first chunk.
+pub const STREAM_ID_LEN: usize = 16;
+
+/// The length of a chunk header, `len: u32 LE | last: u8`.
+pub const CHUNK_HEADER_LEN: usize = 5;
+
+///
+/// An authenticated cipher with associated data, as used by
+/// `StreamEncryptor` and `StreamDecryptor`.
+///
+pub trait AeadCipher: Sized {
+    /// Length of the key in bytes.
+    const KEY_LEN: usize;
+    /// Length of the nonce in bytes.
+    const NONCE_LEN: usize;
+    /// Length of the authentication tag in bytes.
+    const TAG_LEN: usize;
+
+    ///
+    /// new creates a cipher from `key`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `key` is not `KEY_LEN` bytes long.
+    ///
+    fn new(key: &[u8]) -> SgxResult<Self>;
+
+    ///
+    /// seal encrypts `plaintext` and appends the ciphertext followed by the
+    /// tag to `out`.
+    ///
+    fn seal(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8], out: &mut Vec<u8>) -> SgxError;
+
+    ///
+    /// open verifies and decrypts `ciphertext` (followed by the tag) and
+    /// appends the plaintext to `out`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The tag does not match. Nothing is appended to `out`.
+    ///
+    fn open(&self, nonce: &[u8], aad: &[u8], ciphertext: &[u8], out: &mut Vec<u8>) -> SgxError;
+}
+
+impl AeadCipher for AesGcm128 {
+    const KEY_LEN: usize = SGX_AESGCM_KEY_SIZE;
+    const NONCE_LEN: usize = SGX_AESGCM_IV_SIZE;
+    const TAG_LEN: usize = SGX_AESGCM_MAC_SIZE;
+
+    fn new(key: &[u8]) -> SgxResult<AesGcm128> {
+        let key: &sgx_aes_gcm_128bit_key_t = key
+            .try_into()
+            .map_err(|_| sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        Ok(AesGcm128::new(key))
+    }
+
+    fn seal(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8], out: &mut Vec<u8>) -> SgxError {
+        let start = out.len();
+        out.resize(start + plaintext.len(), 0);
+        let mut tag: sgx_aes_gcm_128bit_tag_t = [0; SGX_AESGCM_MAC_SIZE];
+        if let Err(status) = self.encrypt(plaintext, nonce, aad, &mut out[start..], &mut tag) {
+            out.truncate(start);
+            return Err(status);
+        }
+        out.extend_from_slice(&tag);
+        Ok(())
+    }
+
+    fn open(&self, nonce: &[u8], aad: &[u8], ciphertext: &[u8], out: &mut Vec<u8>) -> SgxError {
+        if ciphertext.len() < Self::TAG_LEN {
+            return Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH);
+        }
+        let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - Self::TAG_LEN);
+        let mut mac: sgx_aes_gcm_128bit_tag_t = [0; SGX_AESGCM_MAC_SIZE];
+        mac.copy_from_slice(tag);
+
+        let start = out.len();
+        out.resize(start + ciphertext.len(), 0);
+        let result = self.decrypt(ciphertext, nonce, aad, &mac, &mut out[start..]);
+        if result.is_err() {
+            out.truncate(start);
+        }
+        result
+    }
+}
+
+fn chunk_aad(stream_id: &[u8; STREAM_ID_LEN], counter: u64, last: bool) -> [u8; STREAM_ID_LEN + 9] {
+    let mut aad = [0_u8; STREAM_ID_LEN + 9];
+    aad[..STREAM_ID_LEN].copy_from_slice(stream_id);
+    aad[STREAM_ID_LEN..STREAM_ID_LEN + 8].copy_from_slice(&counter.to_le_bytes());
+    aad[STREAM_ID_LEN + 8] = last as u8;
+    aad
+}
+
+fn max_chunk_size<C: AeadCipher>() -> usize {
+    u32::MAX as usize - C::NONCE_LEN - C::TAG_LEN
+}
+
+fn check_chunk_size<C: AeadCipher>(chunk_size: usize) -> SgxError {
+    if chunk_size == 0 || chunk_size > max_chunk_size::<C>() {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    Ok(())
+}
+
+fn read_rand(buf: &mut [u8]) -> SgxError {
+    let ret = unsafe { sgx_read_rand(buf.as_mut_ptr(), buf.len()) };
+    match ret {
+        sgx_status_t::SGX_SUCCESS => Ok(()),
+        _ => Err(ret),
+    }
+}
+
+///
+/// Encrypts a stream chunk by chunk, in the format of this module.
+///
+pub struct StreamEncryptor<C: AeadCipher> {
+    cipher: C,
+    chunk_size: usize,
+    stream_id: [u8; STREAM_ID_LEN],
+    counter: u64,
+    finished: bool,
+}
+
+impl<C: AeadCipher> StreamEncryptor<C> {
+    ///
+    /// new creates an encryptor for chunks of at most `chunk_size` bytes of
+    /// plaintext.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `key` is not a valid key for `C`, or `chunk_size` is zero or does
+    /// not fit in a chunk header.
+    ///
+    pub fn new(key: &[u8], chunk_size: usize) -> SgxResult<StreamEncryptor<C>> {
+        check_chunk_size::<C>(chunk_size)?;
+        Ok(StreamEncryptor {
+            cipher: C::new(key)?,
+            chunk_size,
+            stream_id: [0; STREAM_ID_LEN],
+            counter: 0,
+            finished: false,
+        })
+    }
+
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    ///
+    /// seal_chunk encrypts the next chunk of the stream. The first chunk is
+    /// preceded by the stream id.
+    ///
+    /// # Parameters
+    ///
+    /// **plaintext**
+    ///
+    /// At most `chunk_size` bytes. Chunks may be shorter than `chunk_size`
+    /// and the final one may be empty.
+    ///
+    /// **last**
+    ///
+    /// Whether this is the final chunk, after which no more can be sealed.
+    /// A stream without a final chunk is rejected as truncated.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a, libsgx_trts.a
+    ///
+    /// # Return value
+    ///
+    /// The bytes to append to the encrypted stream.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `plaintext` is longer than `chunk_size`, or the final chunk has
+    /// already been sealed.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// Reading random numbers or the encryption failed.
+    ///
+    pub fn seal_chunk(&mut self, plaintext: &[u8], last: bool) -> SgxResult<Vec<u8>> {
+        if self.finished || plaintext.len() > self.chunk_size {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut nonce = vec![0_u8; C::NONCE_LEN];
+        read_rand(&mut nonce)?;
+
+        let body_len = C::NONCE_LEN + plaintext.len() + C::TAG_LEN;
+        let mut chunk = Vec::with_capacity(STREAM_ID_LEN + CHUNK_HEADER_LEN + body_len);
+        if self.counter == 0 {
+            read_rand(&mut self.stream_id)?;
+            chunk.extend_from_slice(&self.stream_id);
+        }
+        chunk.extend_from_slice(&(body_len as u32).to_le_bytes());
+        chunk.push(last as u8);
+        chunk.extend_from_slice(&nonce);
+        let aad = chunk_aad(&self.stream_id, self.counter, last);
+        self.cipher.seal(&nonce, &aad, plaintext, &mut chunk)?;
+
+        self.counter += 1;
+        self.finished = last;
+        Ok(chunk)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+impl<C: AeadCipher> fmt::Debug for StreamEncryptor<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamEncryptor")
+            .field("chunk_size", &self.chunk_size)
+            .field("counter", &self.counter)
+            .field("finished", &self.finished)
+            .finish_non_exhaustive()
+    }
+}
+
+///
+/// Decrypts a stream produced by `StreamEncryptor`, chunk by chunk.
+///
+/// For every chunk, read `header_len` bytes and pass them to
+/// `parse_header`, then read the number of bytes it returns and pass them
+/// to `open_chunk`. The stream is complete once `is_finished` returns true;
+/// if the input ends before that, it has been truncated.
+///
+pub struct StreamDecryptor<C: AeadCipher> {
+    cipher: C,
+    chunk_size: usize,
+    stream_id: [u8; STREAM_ID_LEN],
+    counter: u64,
+    // the body length and last flag of the chunk whose header has been parsed
+    pending: Option<(usize, bool)>,
+    finished: bool,
+}
+
+impl<C: AeadCipher> StreamDecryptor<C> {
+    ///
+    /// new creates a decryptor for chunks of at most `chunk_size` bytes of
+    /// plaintext.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `key` is not a valid key for `C`, or `chunk_size` is zero or does
+    /// not fit in a chunk header.
+    ///
+    pub fn new(key: &[u8], chunk_size: usize) -> SgxResult<StreamDecryptor<C>> {
+        check_chunk_size::<C>(chunk_size)?;
+        Ok(StreamDecryptor {
+            cipher: C::new(key)?,
+            chunk_size,
+            stream_id: [0; STREAM_ID_LEN],
+            counter: 0,
+            pending: None,
+            finished: false,
+        })
+    }
+
+    ///
+    /// header_len returns the number of bytes `parse_header` expects next:
+    /// the stream id and a chunk header before the first chunk, and a
+    /// chunk header after that.
+    ///
+    pub fn header_len(&self) -> usize {
+        if self.counter == 0 {
+            STREAM_ID_LEN + CHUNK_HEADER_LEN
+        } else {
+            CHUNK_HEADER_LEN
+        }
+    }
+
+    ///
+    /// parse_header checks the header of the next chunk.
+    ///
+    /// # Return value
+    ///
+    /// The length of the chunk body to pass to `open_chunk`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `header` is not `header_len` bytes long, the stream is finished, or
+    /// the header is malformed or announces a chunk larger than
+    /// `chunk_size`.
+    ///
+    pub fn parse_header(&mut self, header: &[u8]) -> SgxResult<usize> {
+        if self.finished || header.len() != self.header_len() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let header = if self.counter == 0 {
+            let (stream_id, header) = header.split_at(STREAM_ID_LEN);
+            self.stream_id.copy_from_slice(stream_id);
+            header
+        } else {
+            header
+        };
+
+        let mut len = [0_u8; 4];
+        len.copy_from_slice(&header[..4]);
+        let len = u32::from_le_bytes(len) as usize;
+        let last = match header[4] {
+            0 => false,
+            1 => true,
+            _ => return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        };
+        if len < C::NONCE_LEN + C::TAG_LEN || len - C::NONCE_LEN - C::TAG_LEN > self.chunk_size {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        self.pending = Some((len, last));
+        Ok(len)
+    }
+
+    ///
+    /// open_chunk verifies and decrypts the body of the chunk whose header
+    /// was passed to `parse_header`.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// The plaintext of the chunk.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// No header has been parsed, or `body` does not have the length it
+    /// announced.
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The chunk has been modified, reordered, or comes from another
+    /// stream.
+    ///
+    pub fn open_chunk(&mut self, body: &[u8]) -> SgxResult<Vec<u8>> {
+        let (len, last) = self.pending.take().ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        if body.len() != len {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let (nonce, ciphertext) = body.split_at(C::NONCE_LEN);
+
+        let mut plaintext = Vec::with_capacity(ciphertext.len() - C::TAG_LEN);
+        let aad = chunk_aad(&self.stream_id, self.counter, last);
+        self.cipher.open(nonce, &aad, ciphertext, &mut plaintext)?;
+        self.counter += 1;
+        self.finished = last;
+        Ok(plaintext)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+impl<C: AeadCipher> fmt::Debug for StreamDecryptor<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamDecryptor")
+            .field("chunk_size", &self.chunk_size)
+            .field("counter", &self.counter)
+            .field("finished", &self.finished)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,1279 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+//!
+//! AES-GCM-SIV nonce-misuse-resistant authenticated encryption, AES-SIV
+//! deterministic authenticated encryption, AES-128-GCM and AES-256-GCM
+//!
+use crate::crypto::{
+    rsgx_aes_ctr_decrypt, rsgx_aes_ctr_encrypt, rsgx_rijndael128GCM_decrypt,
+    rsgx_rijndael128GCM_encrypt, sgx_aes_ctr_128bit_ctr_t, SgxCmacHandle,
+};
+use crate::util::zeroize;
+use crate::util::ct_eq;
+use core::arch::x86_64::*;
+use core::fmt;
+use sgx_types::*;
+
+/// The length of an AES-GCM-SIV nonce.
+pub const AES_GCM_SIV_NONCE_SIZE: usize = 12;
+
+/// The length of an AES-GCM-SIV authentication tag, which `seal` appends to
+/// the ciphertext.
+pub const AES_GCM_SIV_TAG_SIZE: usize = 16;
+
+// Plaintext and additional data are limited to 2^36 bytes (RFC 8452, section 6).
+const MAX_INPUT_LEN: u64 = 1 << 36;
+
+const BLOCK_LEN: usize = 16;
+
+///
+/// AES-128-GCM-SIV as defined in RFC 8452.
+///
+/// Unlike AES-GCM, encrypting two messages with the same nonce only reveals
+/// whether they are equal; it does not leak the authentication key or the
+/// XOR of the plaintexts. This makes it a safer choice for sealing short
+/// messages when nonces may repeat, e.g. across enclave restarts.
+///
+/// Per-nonce encryption and authentication keys are derived from the key
+/// with AES, the tag is computed with POLYVAL over the plaintext, and the
+/// tag doubles as the initial counter of the AES-CTR encryption.
+///
+/// The SGX GCM functions cannot be used for this: their GHASH key is fixed
+/// to AES(K, 0) and their counter format differs. AES and POLYVAL are
+/// therefore computed with the AES-NI and PCLMULQDQ instructions, which
+/// every SGX capable processor supports. The key schedule is erased when
+/// the object is dropped.
+///
+pub struct AesGcmSiv128 {
+    keys: RoundKeys,
+}
+
+impl AesGcmSiv128 {
+    ///
+    /// new creates an AES-128-GCM-SIV instance.
+    ///
+    /// # Parameters
+    ///
+    /// **key**
+    ///
+    /// The 128 bit key-generating key.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    pub fn new(key: &sgx_key_128bit_t) -> AesGcmSiv128 {
+        AesGcmSiv128 { keys: expand_key(key) }
+    }
+
+    ///
+    /// seal encrypts and authenticates `plaintext`, and authenticates `aad`.
+    ///
+    /// # Parameters
+    ///
+    /// **nonce**
+    ///
+    /// The 96 bit nonce. It should be unique per message, but repeating it
+    /// only reveals whether two messages are identical.
+    ///
+    /// **plaintext**
+    ///
+    /// The data to encrypt, at most 2^36 bytes.
+    ///
+    /// **aad**
+    ///
+    /// Additional data to authenticate, at most 2^36 bytes.
+    ///
+    /// **sealed**
+    ///
+    /// Receives the ciphertext followed by the `AES_GCM_SIV_TAG_SIZE` byte
+    /// tag. It must hold at least `plaintext.len() + AES_GCM_SIV_TAG_SIZE`
+    /// bytes.
+    ///
+    /// # Return value
+    ///
+    /// The number of bytes written to `sealed`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// An input is too long, or `sealed` is too short.
+    ///
+    pub fn seal(
+        &self,
+        nonce: &[u8; AES_GCM_SIV_NONCE_SIZE],
+        plaintext: &[u8],
+        aad: &[u8],
+        sealed: &mut [u8],
+    ) -> SgxResult<usize> {
+        let sealed_len = plaintext
+            .len()
+            .checked_add(AES_GCM_SIV_TAG_SIZE)
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        if plaintext.len() as u64 > MAX_INPUT_LEN
+            || aad.len() as u64 > MAX_INPUT_LEN
+            || sealed.len() < sealed_len
+        {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut message_keys = self.derive_keys(nonce);
+        let tag = compute_tag(&message_keys, nonce, plaintext, aad);
+        let (ciphertext, tag_out) = sealed[..sealed_len].split_at_mut(plaintext.len());
+        ciphertext.copy_from_slice(plaintext);
+        ctr(&message_keys.enc_keys, &tag, ciphertext);
+        tag_out.copy_from_slice(&tag);
+        message_keys.zeroize();
+        Ok(sealed_len)
+    }
+
+    ///
+    /// open verifies and decrypts data produced by `seal`.
+    ///
+    /// # Parameters
+    ///
+    /// **nonce**
+    ///
+    /// The nonce passed to `seal`.
+    ///
+    /// **sealed**
+    ///
+    /// The ciphertext followed by the tag.
+    ///
+    /// **aad**
+    ///
+    /// The additional data passed to `seal`.
+    ///
+    /// **plaintext**
+    ///
+    /// Receives the plaintext. It must hold at least
+    /// `sealed.len() - AES_GCM_SIV_TAG_SIZE` bytes. If verification fails,
+    /// it is zeroed.
+    ///
+    /// # Return value
+    ///
+    /// The number of bytes written to `plaintext`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `sealed` is shorter than a tag, an input is too long, or `plaintext`
+    /// is too short.
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The tag does not match: the key, nonce, ciphertext or additional
+    /// data is wrong.
+    ///
+    pub fn open(
+        &self,
+        nonce: &[u8; AES_GCM_SIV_NONCE_SIZE],
+        sealed: &[u8],
+        aad: &[u8],
+        plaintext: &mut [u8],
+    ) -> SgxResult<usize> {
+        if sealed.len() < AES_GCM_SIV_TAG_SIZE {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let (ciphertext, tag) = sealed.split_at(sealed.len() - AES_GCM_SIV_TAG_SIZE);
+        if ciphertext.len() as u64 > MAX_INPUT_LEN
+            || aad.len() as u64 > MAX_INPUT_LEN
+            || plaintext.len() < ciphertext.len()
+        {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut message_keys = self.derive_keys(nonce);
+        let mut received_tag = [0_u8; BLOCK_LEN];
+        received_tag.copy_from_slice(tag);
+        let out = &mut plaintext[..ciphertext.len()];
+        out.copy_from_slice(ciphertext);
+        ctr(&message_keys.enc_keys, &received_tag, out);
+        let expected_tag = compute_tag(&message_keys, nonce, out, aad);
+        message_keys.zeroize();
+
+        let diff = expected_tag
+            .iter()
+            .zip(received_tag.iter())
+            .fold(0_u8, |acc, (a, b)| acc | (a ^ b));
+        if diff != 0 {
+            zeroize(out);
+            return Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH);
+        }
+        Ok(out.len())
+    }
+
+    // Derives the per-nonce keys (RFC 8452, section 4).
+    fn derive_keys(&self, nonce: &[u8; AES_GCM_SIV_NONCE_SIZE]) -> MessageKeys {
+        let mut derived = [0_u8; 4 * 8];
+        let mut block = [0_u8; BLOCK_LEN];
+        block[4..].copy_from_slice(nonce);
+        for (i, chunk) in derived.chunks_mut(8).enumerate() {
+            block[..4].copy_from_slice(&(i as u32).to_le_bytes());
+            let mut out = encrypt_block(&self.keys, &block);
+            chunk.copy_from_slice(&out[..8]);
+            zeroize(&mut out);
+        }
+
+        let mut auth_key = [0_u8; BLOCK_LEN];
+        let mut enc_key = [0_u8; BLOCK_LEN];
+        auth_key.copy_from_slice(&derived[..BLOCK_LEN]);
+        enc_key.copy_from_slice(&derived[BLOCK_LEN..]);
+        let keys = MessageKeys { auth_key, enc_keys: expand_key(&enc_key) };
+        zeroize(&mut derived);
+        zeroize(&mut enc_key);
+        keys
+    }
+}
+
+impl Drop for AesGcmSiv128 {
+    fn drop(&mut self) {
+        zeroize_keys(&mut self.keys);
+    }
+}
+
+impl fmt::Debug for AesGcmSiv128 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AesGcmSiv128").finish_non_exhaustive()
+    }
+}
+
+struct MessageKeys {
+    auth_key: [u8; BLOCK_LEN],
+    enc_keys: RoundKeys,
+}
+
+impl MessageKeys {
+    fn zeroize(&mut self) {
+        zeroize(&mut self.auth_key);
+        zeroize_keys(&mut self.enc_keys);
+    }
+}
+
+// The tag: AES of POLYVAL over the padded inputs and their bit lengths, XORed
+// with the nonce and with the top bit cleared.
+fn compute_tag(
+    keys: &MessageKeys,
+    nonce: &[u8; AES_GCM_SIV_NONCE_SIZE],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> [u8; BLOCK_LEN] {
+    let mut polyval = Polyval::new(&keys.auth_key);
+    polyval.update_padded(aad);
+    polyval.update_padded(plaintext);
+    let mut lengths = [0_u8; BLOCK_LEN];
+    lengths[..8].copy_from_slice(&(aad.len() as u64 * 8).to_le_bytes());
+    lengths[8..].copy_from_slice(&(plaintext.len() as u64 * 8).to_le_bytes());
+    polyval.update_block(&lengths);
+
+    let mut s = polyval.digest();
+    for (s, n) in s.iter_mut().zip(nonce.iter()) {
+        *s ^= *n;
+    }
+    s[15] &= 0x7f;
+    let tag = encrypt_block(&keys.enc_keys, &s);
+    zeroize(&mut s);
+    tag
+}
+
+// AES-CTR with the counter in the first 32 bits, little endian, starting
+// from the tag with the top bit set.
+fn ctr(keys: &RoundKeys, tag: &[u8; BLOCK_LEN], data: &mut [u8]) {
+    let mut counter = *tag;
+    counter[15] |= 0x80;
+    let mut n = u32::from_le_bytes([counter[0], counter[1], counter[2], counter[3]]);
+    for chunk in data.chunks_mut(BLOCK_LEN) {
+        counter[..4].copy_from_slice(&n.to_le_bytes());
+        let mut keystream = encrypt_block(keys, &counter);
+        chunk.iter_mut().zip(keystream.iter()).for_each(|(d, k)| *d ^= *k);
+        zeroize(&mut keystream);
+        n = n.wrapping_add(1);
+    }
+}
+
+/// The length of an AES-SIV key: the S2V key followed by the CTR key.
+pub const AES_SIV_KEY_SIZE: usize = 32;
+
+/// The length of the synthetic IV, which `seal` places in front of the
+/// ciphertext.
+pub const AES_SIV_TAG_SIZE: usize = 16;
+
+// S2V takes at most 127 strings, the last of them the plaintext (RFC 5297,
+// section 7).
+const AES_SIV_MAX_AAD_COUNT: usize = 126;
+
+///
+/// AES-SIV (AES-CMAC-SIV with 128 bit keys) as defined in RFC 5297.
+///
+/// Encryption is deterministic: the same plaintext and additional data
+/// always give the same ciphertext, and nothing else about the plaintext is
+/// revealed. This allows lookups and deduplication over encrypted records
+/// without any nonce management. Protocols that can supply a nonce pass it
+/// as the last additional data string.
+///
+/// The synthetic IV is computed with S2V over the CMAC functions of the
+/// Intel SGX SDK and serves as both the authentication tag and the initial
+/// counter of the SDK's AES-CTR encryption. The keys are erased when the
+/// object is dropped.
+///
+pub struct AesSiv {
+    mac_key: sgx_cmac_128bit_key_t,
+    enc_key: sgx_aes_ctr_128bit_key_t,
+}
+
+impl AesSiv {
+    ///
+    /// new creates an AES-SIV instance.
+    ///
+    /// # Parameters
+    ///
+    /// **key**
+    ///
+    /// The 256 bit key: the 128 bit S2V key followed by the 128 bit CTR key.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    pub fn new(key: &[u8; AES_SIV_KEY_SIZE]) -> AesSiv {
+        let mut siv = AesSiv {
+            mac_key: [0_u8; BLOCK_LEN],
+            enc_key: [0_u8; BLOCK_LEN],
+        };
+        siv.mac_key.copy_from_slice(&key[..BLOCK_LEN]);
+        siv.enc_key.copy_from_slice(&key[BLOCK_LEN..]);
+        siv
+    }
+
+    ///
+    /// seal encrypts and authenticates `plaintext`, and authenticates each
+    /// of the `aad` strings.
+    ///
+    /// # Parameters
+    ///
+    /// **plaintext**
+    ///
+    /// The data to encrypt.
+    ///
+    /// **aad**
+    ///
+    /// Up to 126 additional data strings to authenticate, in order. Each
+    /// may be empty.
+    ///
+    /// **sealed**
+    ///
+    /// Receives the `AES_SIV_TAG_SIZE` byte synthetic IV followed by the
+    /// ciphertext. It must hold at least
+    /// `plaintext.len() + AES_SIV_TAG_SIZE` bytes.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// The number of bytes written to `sealed`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// There are too many additional data strings, an input is too long,
+    /// or `sealed` is too short.
+    ///
+    /// **SGX_ERROR_OUT_OF_MEMORY**
+    ///
+    /// Not enough memory is available to complete this operation.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// An internal cryptography library failure occurred.
+    ///
+    pub fn seal(&self, plaintext: &[u8], aad: &[&[u8]], sealed: &mut [u8]) -> SgxResult<usize> {
+        let sealed_len = plaintext
+            .len()
+            .checked_add(AES_SIV_TAG_SIZE)
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        if aad.len() > AES_SIV_MAX_AAD_COUNT || sealed.len() < sealed_len {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let v = self.s2v(aad, plaintext)?;
+        let (iv, ciphertext) = sealed[..sealed_len].split_at_mut(AES_SIV_TAG_SIZE);
+        if !plaintext.is_empty() {
+            let mut counter = siv_counter(&v);
+            rsgx_aes_ctr_encrypt(&self.enc_key, plaintext, &mut counter, 128, ciphertext)?;
+        }
+        iv.copy_from_slice(&v);
+        Ok(sealed_len)
+    }
+
+    ///
+    /// open verifies and decrypts data produced by `seal`.
+    ///
+    /// # Parameters
+    ///
+    /// **sealed**
+    ///
+    /// The synthetic IV followed by the ciphertext.
+    ///
+    /// **aad**
+    ///
+    /// The additional data strings passed to `seal`.
+    ///
+    /// **plaintext**
+    ///
+    /// Receives the plaintext. It must hold at least
+    /// `sealed.len() - AES_SIV_TAG_SIZE` bytes. If verification fails, it
+    /// is zeroed.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// The number of bytes written to `plaintext`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `sealed` is shorter than the synthetic IV, there are too many
+    /// additional data strings, or `plaintext` is too short.
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The synthetic IV does not match: the key, ciphertext or additional
+    /// data is wrong.
+    ///
+    /// **SGX_ERROR_OUT_OF_MEMORY**
+    ///
+    /// Not enough memory is available to complete this operation.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// An internal cryptography library failure occurred.
+    ///
+    pub fn open(&self, sealed: &[u8], aad: &[&[u8]], plaintext: &mut [u8]) -> SgxResult<usize> {
+        if sealed.len() < AES_SIV_TAG_SIZE || aad.len() > AES_SIV_MAX_AAD_COUNT {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let (iv, ciphertext) = sealed.split_at(AES_SIV_TAG_SIZE);
+        if plaintext.len() < ciphertext.len() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let out = &mut plaintext[..ciphertext.len()];
+        if !ciphertext.is_empty() {
+            let mut v = [0_u8; BLOCK_LEN];
+            v.copy_from_slice(iv);
+            let mut counter = siv_counter(&v);
+            rsgx_aes_ctr_decrypt(&self.enc_key, ciphertext, &mut counter, 128, out)?;
+        }
+        let expected = match self.s2v(aad, out) {
+            Ok(v) => v,
+            Err(e) => {
+                zeroize(out);
+                return Err(e);
+            }
+        };
+        if !ct_eq(&expected, iv) {
+            zeroize(out);
+            return Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH);
+        }
+        Ok(out.len())
+    }
+
+    // S2V over the additional data strings followed by the plaintext (RFC
+    // 5297, section 2.4).
+    fn s2v(&self, aad: &[&[u8]], plaintext: &[u8]) -> SgxResult<[u8; BLOCK_LEN]> {
+        let mut d = self.cmac(&[&[0_u8; BLOCK_LEN]])?;
+        for s in aad {
+            let mac = self.cmac(&[s])?;
+            d = dbl(&d);
+            d.iter_mut().zip(mac.iter()).for_each(|(d, m)| *d ^= *m);
+        }
+
+        let result = if plaintext.len() >= BLOCK_LEN {
+            // the plaintext with D XORed into its last block
+            let (head, tail) = plaintext.split_at(plaintext.len() - BLOCK_LEN);
+            d.iter_mut().zip(tail.iter()).for_each(|(d, t)| *d ^= *t);
+            self.cmac(&[head, &d])
+        } else {
+            // dbl(D) XORed with the plaintext padded by 10*
+            d = dbl(&d);
+            d.iter_mut().zip(plaintext.iter()).for_each(|(d, p)| *d ^= *p);
+            d[plaintext.len()] ^= 0x80;
+            self.cmac(&[&d])
+        };
+        zeroize(&mut d);
+        result
+    }
+
+    // CMAC(mac_key, parts[0] || parts[1] || ...)
+    fn cmac(&self, parts: &[&[u8]]) -> SgxResult<[u8; BLOCK_LEN]> {
+        let handle = SgxCmacHandle::new();
+        handle.init(&self.mac_key)?;
+        for part in parts.iter().filter(|p| !p.is_empty()) {
+            handle.update_slice(part)?;
+        }
+        handle.get_hash()
+    }
+}
+
+impl Drop for AesSiv {
+    fn drop(&mut self) {
+        zeroize(&mut self.mac_key);
+        zeroize(&mut self.enc_key);
+    }
+}
+
+impl fmt::Debug for AesSiv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AesSiv").finish_non_exhaustive()
+    }
+}
+
+// Doubling in GF(2^128) with the polynomial x^128 + x^7 + x^2 + x + 1, on a
+// big-endian block.
+fn dbl(block: &[u8; BLOCK_LEN]) -> [u8; BLOCK_LEN] {
+    let mut out = [0_u8; BLOCK_LEN];
+    for i in 0..BLOCK_LEN - 1 {
+        out[i] = (block[i] << 1) | (block[i + 1] >> 7);
+    }
+    out[BLOCK_LEN - 1] = (block[BLOCK_LEN - 1] << 1) ^ (0x87 & 0_u8.wrapping_sub(block[0] >> 7));
+    out
+}
+
+// The initial counter: the synthetic IV with the top bits of its last two
+// 32-bit words cleared, so implementations may use 64-bit additions.
+fn siv_counter(v: &[u8; BLOCK_LEN]) -> sgx_aes_ctr_128bit_ctr_t {
+    let mut counter = *v;
+    counter[8] &= 0x7f;
+    counter[12] &= 0x7f;
+    counter
+}
+
+///
+/// AES-128-GCM with a key that is kept for the lifetime of the object.
+///
+/// This wraps `rsgx_rijndael128GCM_encrypt` and `rsgx_rijndael128GCM_decrypt`
+/// with the same parameter conventions, so that callers encrypting many
+/// messages under one key do not have to hold the raw key themselves. The
+/// key is erased when the object is dropped.
+///
+pub struct AesGcm128 {
+    key: sgx_aes_gcm_128bit_key_t,
+}
+
+impl AesGcm128 {
+    ///
+    /// new creates an AES-128-GCM instance.
+    ///
+    /// # Parameters
+    ///
+    /// **key**
+    ///
+    /// The 128 bit key.
+    ///
+    pub fn new(key: &sgx_aes_gcm_128bit_key_t) -> AesGcm128 {
+        AesGcm128 { key: *key }
+    }
+
+    ///
+    /// encrypt encrypts and authenticates `src`, and authenticates `aad`.
+    ///
+    /// See `rsgx_rijndael128GCM_encrypt` for the parameters and errors.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    pub fn encrypt(
+        &self,
+        src: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+        dst: &mut [u8],
+        mac: &mut sgx_aes_gcm_128bit_tag_t,
+    ) -> SgxError {
+        rsgx_rijndael128GCM_encrypt(&self.key, src, iv, aad, dst, mac)
+    }
+
+    ///
+    /// decrypt verifies and decrypts data produced by `encrypt`.
+    ///
+    /// See `rsgx_rijndael128GCM_decrypt` for the parameters and errors.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    pub fn decrypt(
+        &self,
+        src: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+        mac: &sgx_aes_gcm_128bit_tag_t,
+        dst: &mut [u8],
+    ) -> SgxError {
+        rsgx_rijndael128GCM_decrypt(&self.key, src, iv, aad, mac, dst)
+    }
+}
+
+impl Drop for AesGcm128 {
+    fn drop(&mut self) {
+        zeroize(&mut self.key);
+    }
+}
+
+impl fmt::Debug for AesGcm128 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AesGcm128").finish_non_exhaustive()
+    }
+}
+
+/// The length of an AES-256-GCM initialization vector.
+pub const AES_256_GCM_IV_SIZE: usize = SGX_AESGCM_IV_SIZE;
+
+/// The length of an AES-256-GCM authentication tag.
+pub const AES_256_GCM_TAG_SIZE: usize = SGX_AESGCM_MAC_SIZE;
+
+// Plaintext is limited to 2^39 - 256 bits, additional data to 2^61 - 1
+// bytes (NIST SP 800-38D, section 5.2.1.1).
+const GCM_MAX_TEXT_LEN: u64 = (1 << 36) - 32;
+const GCM_MAX_AAD_LEN: u64 = (1 << 61) - 1;
+
+///
+/// AES-256-GCM as defined in NIST SP 800-38D, with 96 bit IVs and 128 bit
+/// tags.
+///
+/// This is the 256 bit key counterpart of `rsgx_rijndael128GCM_encrypt` and
+/// `rsgx_rijndael128GCM_decrypt`, with the same parameter conventions. The
+/// Intel SGX SDK only provides 128 bit keys, so AES and GHASH are computed
+/// with the AES-NI and PCLMULQDQ instructions. GHASH is evaluated as
+/// POLYVAL on byte-reversed blocks (RFC 8452, appendix A).
+///
+/// Besides the one-shot `encrypt` and `decrypt`, `encryptor` and
+/// `decryptor` process a message in pieces, like `SgxAesHandle` does for
+/// 128 bit keys. The key schedule is erased when the object is dropped.
+///
+pub struct Aes256Gcm {
+    keys: RoundKeys256,
+    // GHASH key as a POLYVAL key: mulX_POLYVAL(ByteReverse(AES(K, 0)))
+    hash_key: [u8; BLOCK_LEN],
+}
+
+impl Aes256Gcm {
+    ///
+    /// new creates an AES-256-GCM instance.
+    ///
+    /// # Parameters
+    ///
+    /// **key**
+    ///
+    /// The 256 bit key.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    pub fn new(key: &sgx_aes_gcm_256bit_key_t) -> Aes256Gcm {
+        let keys = expand_key256(key);
+        let mut h = encrypt_block(&keys, &[0_u8; BLOCK_LEN]);
+        h.reverse();
+        let hash_key = mul_x_polyval(&h);
+        zeroize(&mut h);
+        Aes256Gcm { keys, hash_key }
+    }
+
+    ///
+    /// encrypt encrypts and authenticates `src`, and authenticates `aad`.
+    ///
+    /// # Parameters
+    ///
+    /// **src**
+    ///
+    /// The data to encrypt, at most 2^36 - 32 bytes.
+    ///
+    /// **iv**
+    ///
+    /// The initialization vector, `AES_256_GCM_IV_SIZE` bytes. It must never
+    /// be reused with the same key.
+    ///
+    /// **aad**
+    ///
+    /// Additional data to authenticate.
+    ///
+    /// **dst**
+    ///
+    /// Receives the ciphertext. It must be at least as long as `src`.
+    ///
+    /// **mac**
+    ///
+    /// Receives the authentication tag.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The IV has the wrong length, an input is too long, or `dst` is too
+    /// short.
+    ///
+    pub fn encrypt(
+        &self,
+        src: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+        dst: &mut [u8],
+        mac: &mut sgx_aes_gcm_128bit_tag_t,
+    ) -> SgxError {
+        let mut stream = self.encryptor(iv, aad)?;
+        stream.update(src, dst)?;
+        *mac = stream.get_mac();
+        Ok(())
+    }
+
+    ///
+    /// decrypt verifies and decrypts data produced by `encrypt`.
+    ///
+    /// The tag is checked before anything is written to `dst`.
+    ///
+    /// # Parameters
+    ///
+    /// **src**
+    ///
+    /// The ciphertext.
+    ///
+    /// **iv**
+    ///
+    /// The initialization vector passed to `encrypt`.
+    ///
+    /// **aad**
+    ///
+    /// The additional data passed to `encrypt`.
+    ///
+    /// **mac**
+    ///
+    /// The authentication tag produced by `encrypt`.
+    ///
+    /// **dst**
+    ///
+    /// Receives the plaintext. It must be at least as long as `src`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The IV has the wrong length, an input is too long, or `dst` is too
+    /// short.
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The tag does not match: the key, IV, ciphertext or additional data
+    /// is wrong.
+    ///
+    pub fn decrypt(
+        &self,
+        src: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+        mac: &sgx_aes_gcm_128bit_tag_t,
+        dst: &mut [u8],
+    ) -> SgxError {
+        let mut state = GcmState::new(self, iv, aad)?;
+        state.check_update(src.len(), dst.len())?;
+        state.hash(src);
+        if !tags_equal(&state.tag(), mac) {
+            return Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH);
+        }
+        let out = &mut dst[..src.len()];
+        out.copy_from_slice(src);
+        state.apply_keystream(out);
+        Ok(())
+    }
+
+    ///
+    /// encryptor starts encrypting a message that is passed in pieces.
+    ///
+    /// # Parameters
+    ///
+    /// **iv**
+    ///
+    /// The initialization vector, `AES_256_GCM_IV_SIZE` bytes. It must never
+    /// be reused with the same key.
+    ///
+    /// **aad**
+    ///
+    /// Additional data to authenticate.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The IV has the wrong length, or `aad` is too long.
+    ///
+    pub fn encryptor(&self, iv: &[u8], aad: &[u8]) -> SgxResult<Aes256GcmEncryptor<'_>> {
+        Ok(Aes256GcmEncryptor { state: GcmState::new(self, iv, aad)? })
+    }
+
+    ///
+    /// decryptor starts decrypting a message that is passed in pieces.
+    ///
+    /// Plaintext is released by `update` before the tag is checked by
+    /// `verify`; it must not be used before `verify` succeeds.
+    ///
+    /// # Parameters
+    ///
+    /// **iv**
+    ///
+    /// The initialization vector passed to `encrypt`.
+    ///
+    /// **aad**
+    ///
+    /// The additional data passed to `encrypt`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The IV has the wrong length, or `aad` is too long.
+    ///
+    pub fn decryptor(&self, iv: &[u8], aad: &[u8]) -> SgxResult<Aes256GcmDecryptor<'_>> {
+        Ok(Aes256GcmDecryptor { state: GcmState::new(self, iv, aad)? })
+    }
+}
+
+impl Drop for Aes256Gcm {
+    fn drop(&mut self) {
+        zeroize_keys(&mut self.keys);
+        zeroize(&mut self.hash_key);
+    }
+}
+
+impl fmt::Debug for Aes256Gcm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Aes256Gcm").finish_non_exhaustive()
+    }
+}
+
+///
+/// An AES-256-GCM encryption in progress, created by
+/// `Aes256Gcm::encryptor`.
+///
+pub struct Aes256GcmEncryptor<'a> {
+    state: GcmState<'a>,
+}
+
+impl<'a> Aes256GcmEncryptor<'a> {
+    ///
+    /// update encrypts the next part of the message.
+    ///
+    /// # Parameters
+    ///
+    /// **src**
+    ///
+    /// The next part of the plaintext, of any length.
+    ///
+    /// **dst**
+    ///
+    /// Receives the ciphertext. It must be at least as long as `src`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `dst` is too short, or the message has grown too long.
+    ///
+    pub fn update(&mut self, src: &[u8], dst: &mut [u8]) -> SgxError {
+        self.state.check_update(src.len(), dst.len())?;
+        let out = &mut dst[..src.len()];
+        out.copy_from_slice(src);
+        self.state.apply_keystream(out);
+        self.state.hash(out);
+        Ok(())
+    }
+
+    ///
+    /// get_mac finishes the encryption and returns the authentication tag.
+    ///
+    pub fn get_mac(mut self) -> sgx_aes_gcm_128bit_tag_t {
+        self.state.tag()
+    }
+}
+
+///
+/// An AES-256-GCM decryption in progress, created by
+/// `Aes256Gcm::decryptor`.
+///
+pub struct Aes256GcmDecryptor<'a> {
+    state: GcmState<'a>,
+}
+
+impl<'a> Aes256GcmDecryptor<'a> {
+    ///
+    /// update decrypts the next part of the message.
+    ///
+    /// The plaintext is not authenticated until `verify` succeeds.
+    ///
+    /// # Parameters
+    ///
+    /// **src**
+    ///
+    /// The next part of the ciphertext, of any length.
+    ///
+    /// **dst**
+    ///
+    /// Receives the plaintext. It must be at least as long as `src`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `dst` is too short, or the message has grown too long.
+    ///
+    pub fn update(&mut self, src: &[u8], dst: &mut [u8]) -> SgxError {
+        self.state.check_update(src.len(), dst.len())?;
+        self.state.hash(src);
+        let out = &mut dst[..src.len()];
+        out.copy_from_slice(src);
+        self.state.apply_keystream(out);
+        Ok(())
+    }
+
+    ///
+    /// verify finishes the decryption and checks the authentication tag.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The tag does not match. All plaintext returned by `update` must be
+    /// discarded.
+    ///
+    pub fn verify(mut self, mac: &sgx_aes_gcm_128bit_tag_t) -> SgxError {
+        if tags_equal(&self.state.tag(), mac) {
+            Ok(())
+        } else {
+            Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH)
+        }
+    }
+}
+
+// The state shared by one-shot and incremental GCM.
+struct GcmState<'a> {
+    keys: &'a RoundKeys256,
+    ghash: Polyval,
+    // the pre-counter block J0 = IV || 0^31 || 1
+    j0: [u8; BLOCK_LEN],
+    // the counter of the next keystream block
+    counter: u32,
+    keystream: [u8; BLOCK_LEN],
+    keystream_used: usize,
+    // ciphertext not yet hashed, less than a block
+    pending: [u8; BLOCK_LEN],
+    pending_len: usize,
+    aad_len: u64,
+    text_len: u64,
+}
+
+impl<'a> GcmState<'a> {
+    fn new(cipher: &'a Aes256Gcm, iv: &[u8], aad: &[u8]) -> SgxResult<GcmState<'a>> {
+        if iv.len() != AES_256_GCM_IV_SIZE || aad.len() as u64 > GCM_MAX_AAD_LEN {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let mut j0 = [0_u8; BLOCK_LEN];
+        j0[..AES_256_GCM_IV_SIZE].copy_from_slice(iv);
+        j0[15] = 1;
+
+        let mut state = GcmState {
+            keys: &cipher.keys,
+            ghash: Polyval::new(&cipher.hash_key),
+            j0,
+            counter: 2,
+            keystream: [0_u8; BLOCK_LEN],
+            keystream_used: BLOCK_LEN,
+            pending: [0_u8; BLOCK_LEN],
+            pending_len: 0,
+            aad_len: aad.len() as u64,
+            text_len: 0,
+        };
+        for chunk in aad.chunks(BLOCK_LEN) {
+            let mut block = [0_u8; BLOCK_LEN];
+            block[..chunk.len()].copy_from_slice(chunk);
+            state.ghash_block(&mut block);
+        }
+        Ok(state)
+    }
+
+    fn check_update(&mut self, src_len: usize, dst_len: usize) -> SgxError {
+        match self.text_len.checked_add(src_len as u64) {
+            Some(len) if len <= GCM_MAX_TEXT_LEN && dst_len >= src_len => {
+                self.text_len = len;
+                Ok(())
+            }
+            _ => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        }
+    }
+
+    // XORs the CTR keystream, continuing where the last call stopped.
+    fn apply_keystream(&mut self, data: &mut [u8]) {
+        for d in data.iter_mut() {
+            if self.keystream_used == BLOCK_LEN {
+                let mut block = self.j0;
+                block[12..].copy_from_slice(&self.counter.to_be_bytes());
+                self.keystream = encrypt_block(self.keys, &block);
+                self.counter = self.counter.wrapping_add(1);
+                self.keystream_used = 0;
+            }
+            *d ^= self.keystream[self.keystream_used];
+            self.keystream_used += 1;
+        }
+    }
+
+    // Feeds ciphertext to GHASH, buffering partial blocks.
+    fn hash(&mut self, data: &[u8]) {
+        for &b in data {
+            self.pending[self.pending_len] = b;
+            self.pending_len += 1;
+            if self.pending_len == BLOCK_LEN {
+                let mut block = self.pending;
+                self.ghash_block(&mut block);
+                self.pending_len = 0;
+            }
+        }
+    }
+
+    fn ghash_block(&mut self, block: &mut [u8; BLOCK_LEN]) {
+        block.reverse();
+        self.ghash.update_block(block);
+        zeroize(block);
+    }
+
+    fn tag(&mut self) -> sgx_aes_gcm_128bit_tag_t {
+        if self.pending_len > 0 {
+            let mut block = [0_u8; BLOCK_LEN];
+            block[..self.pending_len].copy_from_slice(&self.pending[..self.pending_len]);
+            self.ghash_block(&mut block);
+            self.pending_len = 0;
+        }
+        let mut lengths = [0_u8; BLOCK_LEN];
+        lengths[..8].copy_from_slice(&(self.aad_len * 8).to_be_bytes());
+        lengths[8..].copy_from_slice(&(self.text_len * 8).to_be_bytes());
+        self.ghash_block(&mut lengths);
+
+        let mut s = self.ghash.digest();
+        s.reverse();
+        let mut tag = encrypt_block(self.keys, &self.j0);
+        tag.iter_mut().zip(s.iter()).for_each(|(t, s)| *t ^= *s);
+        zeroize(&mut s);
+        tag
+    }
+}
+
+impl<'a> Drop for GcmState<'a> {
+    fn drop(&mut self) {
+        zeroize(&mut self.keystream);
+        zeroize(&mut self.pending);
+    }
+}
+
+// Multiplies by x in the POLYVAL field (RFC 8452, appendix A).
+fn mul_x_polyval(block: &[u8; BLOCK_LEN]) -> [u8; BLOCK_LEN] {
+    let v = u128::from_le_bytes(*block);
+    let carry = (v >> 127).wrapping_neg();
+    let v = (v << 1) ^ (carry & ((0xc2_u128 << 120) | 1));
+    v.to_le_bytes()
+}
+
+fn tags_equal(a: &[u8; BLOCK_LEN], b: &[u8; BLOCK_LEN]) -> bool {
+    a.iter().zip(b.iter()).fold(0_u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+// POLYVAL (RFC 8452, section 3) with a 128 bit carry-less multiplication.
+struct Polyval {
+    h: __m128i,
+    s: __m128i,
+}
+
+impl Polyval {
+    fn new(key: &[u8; BLOCK_LEN]) -> Polyval {
+        unsafe {
+            Polyval {
+                h: _mm_loadu_si128(key.as_ptr() as *const __m128i),
+                s: _mm_setzero_si128(),
+            }
+        }
+    }
+
+    fn update_block(&mut self, block: &[u8; BLOCK_LEN]) {
+        unsafe {
+            let x = _mm_loadu_si128(block.as_ptr() as *const __m128i);
+            self.s = clmul_polyval(_mm_xor_si128(self.s, x), self.h);
+        }
+    }
+
+    // Absorbs `data`, zero-padded to a multiple of the block length.
+    fn update_padded(&mut self, data: &[u8]) {
+        for chunk in data.chunks(BLOCK_LEN) {
+            let mut block = [0_u8; BLOCK_LEN];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.update_block(&block);
+            zeroize(&mut block);
+        }
+    }
+
+    fn digest(&self) -> [u8; BLOCK_LEN] {
+        let mut out = [0_u8; BLOCK_LEN];
+        unsafe { _mm_storeu_si128(out.as_mut_ptr() as *mut __m128i, self.s) };
+        out
+    }
+}
+
+impl Drop for Polyval {
+    fn drop(&mut self) {
+        unsafe {
+            core::ptr::write_volatile(&mut self.h, _mm_setzero_si128());
+            core::ptr::write_volatile(&mut self.s, _mm_setzero_si128());
+        }
+    }
+}
+
+// a * b * x^-128 in GF(2^128) modulo x^128 + x^127 + x^126 + x^121 + 1.
+#[target_feature(enable = "pclmulqdq,sse2")]
+unsafe fn clmul_polyval(a: __m128i, b: __m128i) -> __m128i {
+    // x^127 + x^126 + x^121 + 1, with the x^128 term implied
+    let poly = _mm_set_epi64x(0xc200_0000_0000_0000_u64 as i64, 1);
+
+    let lo = _mm_clmulepi64_si128(a, b, 0x00);
+    let hi = _mm_clmulepi64_si128(a, b, 0x11);
+    let mid = _mm_xor_si128(_mm_clmulepi64_si128(a, b, 0x10), _mm_clmulepi64_si128(a, b, 0x01));
+    let lo = _mm_xor_si128(lo, _mm_slli_si128(mid, 8));
+    let hi = _mm_xor_si128(hi, _mm_srli_si128(mid, 8));
+
+    // Two Montgomery reduction steps of 64 bits each.
+    let t = _mm_clmulepi64_si128(lo, poly, 0x10);
+    let lo = _mm_xor_si128(_mm_shuffle_epi32(lo, 0x4e), t);
+    let t = _mm_clmulepi64_si128(lo, poly, 0x10);
+    let lo = _mm_xor_si128(_mm_shuffle_epi32(lo, 0x4e), t);
+    _mm_xor_si128(hi, lo)
+}
+
+// The AES-128 round keys.
+type RoundKeys = [__m128i; 11];
+
+// The AES-256 round keys.
+type RoundKeys256 = [__m128i; 15];
+
+fn expand_key(key: &[u8; BLOCK_LEN]) -> RoundKeys {
+    unsafe { aesni_expand_key(key) }
+}
+
+fn expand_key256(key: &[u8; 2 * BLOCK_LEN]) -> RoundKeys256 {
+    unsafe { aesni_expand_key256(key) }
+}
+
+// Encrypts with AES-128 or AES-256, depending on the number of round keys.
+fn encrypt_block(keys: &[__m128i], block: &[u8; BLOCK_LEN]) -> [u8; BLOCK_LEN] {
+    unsafe { aesni_encrypt_block(keys, block) }
+}
+
+fn zeroize_keys(keys: &mut [__m128i]) {
+    for k in keys.iter_mut() {
+        unsafe { core::ptr::write_volatile(k, _mm_setzero_si128()) };
+    }
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn aesni_expand_key(key: &[u8; BLOCK_LEN]) -> RoundKeys {
+    let mut keys = [_mm_setzero_si128(); 11];
+    keys[0] = _mm_loadu_si128(key.as_ptr() as *const __m128i);
+
+    macro_rules! expand {
+        ($i:expr, $rcon:expr) => {
+            let t = _mm_shuffle_epi32(_mm_aeskeygenassist_si128(keys[$i - 1], $rcon), 0xff);
+            let mut a = keys[$i - 1];
+            a = _mm_xor_si128(a, _mm_slli_si128(a, 4));
+            a = _mm_xor_si128(a, _mm_slli_si128(a, 4));
+            a = _mm_xor_si128(a, _mm_slli_si128(a, 4));
+            keys[$i] = _mm_xor_si128(a, t);
+        };
+    }
+    expand!(1, 0x01);
+    expand!(2, 0x02);
+    expand!(3, 0x04);
+    expand!(4, 0x08);
+    expand!(5, 0x10);
+    expand!(6, 0x20);
+    expand!(7, 0x40);
+    expand!(8, 0x80);
+    expand!(9, 0x1b);
+    expand!(10, 0x36);
+    keys
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn aesni_expand_key256(key: &[u8; 2 * BLOCK_LEN]) -> RoundKeys256 {
+    let mut keys = [_mm_setzero_si128(); 15];
+    keys[0] = _mm_loadu_si128(key.as_ptr() as *const __m128i);
+    keys[1] = _mm_loadu_si128(key.as_ptr().add(BLOCK_LEN) as *const __m128i);
+
+    // Even round keys mix in RotWord(SubWord(w)) ^ rcon of the previous
+    // one, odd round keys only SubWord(w).
+    macro_rules! expand {
+        ($i:expr, $assist:expr, $shuffle:expr) => {
+            let t = _mm_shuffle_epi32(_mm_aeskeygenassist_si128(keys[$i - 1], $assist), $shuffle);
+            let mut a = keys[$i - 2];
+            a = _mm_xor_si128(a, _mm_slli_si128(a, 4));
+            a = _mm_xor_si128(a, _mm_slli_si128(a, 4));
+            a = _mm_xor_si128(a, _mm_slli_si128(a, 4));
+            keys[$i] = _mm_xor_si128(a, t);
+        };
+    }
+    expand!(2, 0x01, 0xff);
+    expand!(3, 0x00, 0xaa);
+    expand!(4, 0x02, 0xff);
+    expand!(5, 0x00, 0xaa);
+    expand!(6, 0x04, 0xff);
+    expand!(7, 0x00, 0xaa);
+    expand!(8, 0x08, 0xff);
+    expand!(9, 0x00, 0xaa);
+    expand!(10, 0x10, 0xff);
+    expand!(11, 0x00, 0xaa);
+    expand!(12, 0x20, 0xff);
+    expand!(13, 0x00, 0xaa);
+    expand!(14, 0x40, 0xff);
+    keys
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn aesni_encrypt_block(keys: &[__m128i], block: &[u8; BLOCK_LEN]) -> [u8; BLOCK_LEN] {
+    let last = keys.len() - 1;
+    let mut state = _mm_loadu_si128(block.as_ptr() as *const __m128i);
+    state = _mm_xor_si128(state, keys[0]);
+    for key in &keys[1..last] {
+        state = _mm_aesenc_si128(state, *key);
+    }
+    state = _mm_aesenclast_si128(state, keys[last]);
+
+    let mut out = [0_u8; BLOCK_LEN];
+    _mm_storeu_si128(out.as_mut_ptr() as *mut __m128i, state);
+    out
+}
@@ -0,0 +1,702 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+//!
+//! Argon2id password hashing
+//!
+//! Argon2id as defined in RFC 9106, version 0x13. It is memory-hard: every
+//! hash fills `memory_kib` KiB of enclave heap, which makes guessing
+//! passwords on dedicated hardware far more expensive than with PBKDF2. The
+//! Intel SGX SDK has no Argon2 primitive, so it is implemented here on top
+//! of `blake2::Blake2b`.
+//!
+use crate::blake2::{Blake2b, BLAKE2B_MAX_SIZE};
+use crate::util::zeroize;
+use crate::util::ct_eq;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::fmt;
+use core::ptr;
+use core::sync::atomic::{compiler_fence, Ordering};
+use sgx_trts::enclave::rsgx_get_heap_size;
+use sgx_types::*;
+
+/// The length of an Argon2id hash value in bytes.
+pub const ARGON2_TAG_LEN: usize = 32;
+
+/// The minimum salt length accepted by `Argon2`, from RFC 9106.
+pub const ARGON2_MIN_SALT_LEN: usize = 8;
+
+/// The maximum degree of parallelism, from RFC 9106.
+pub const ARGON2_MAX_PARALLELISM: u32 = 0x00ff_ffff;
+
+const ARGON2_VERSION: u32 = 0x13;
+const ARGON2_TYPE_ID: u32 = 2;
+
+// A memory block is 1 KiB, or 128 64-bit words.
+const BLOCK_WORDS: usize = 128;
+// Each lane is split into four segments, which all lanes fill in step.
+const SYNC_POINTS: usize = 4;
+
+type Block = [u64; BLOCK_WORDS];
+
+///
+/// The cost parameters of Argon2id.
+///
+/// The default is the minimum recommended by OWASP: 19 MiB of memory, two
+/// passes and one lane.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Argon2Params {
+    /// The memory size in KiB, at least 8 times `parallelism`, and at most
+    /// half of the enclave heap.
+    pub memory_kib: u32,
+    /// The number of passes over the memory, at least 1.
+    pub iterations: u32,
+    /// The number of lanes, 1 to `ARGON2_MAX_PARALLELISM`. Lanes are
+    /// computed one after the other inside the enclave, but the value is
+    /// part of the hash.
+    pub parallelism: u32,
+}
+
+impl Default for Argon2Params {
+    fn default() -> Argon2Params {
+        Argon2Params {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+impl Argon2Params {
+    fn check(&self) -> SgxError {
+        if self.iterations == 0
+            || self.parallelism == 0
+            || self.parallelism > ARGON2_MAX_PARALLELISM
+            || (self.memory_kib as u64) < 8 * self.parallelism as u64
+        {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        // leave the other half of the heap to the rest of the enclave
+        if (self.memory_kib as usize).saturating_mul(1024) > rsgx_get_heap_size() / 2 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        Ok(())
+    }
+}
+
+///
+/// Argon2id, the hybrid of data-independent and data-dependent memory
+/// access recommended by RFC 9106 for password hashing.
+///
+pub struct Argon2;
+
+impl Argon2 {
+    ///
+    /// hash_password hashes `password` with `salt`.
+    ///
+    /// # Parameters
+    ///
+    /// **password**
+    ///
+    /// The password.
+    ///
+    /// **salt**
+    ///
+    /// The salt, at least `ARGON2_MIN_SALT_LEN` bytes. 16 random bytes are
+    /// recommended.
+    ///
+    /// **params**
+    ///
+    /// The memory size, number of passes and number of lanes.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_trts.a
+    ///
+    /// # Return value
+    ///
+    /// The hash, together with the salt and parameters needed to verify it.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The salt is too short, an input is too long, or a parameter is out of
+    /// range. This includes a memory size above half of the enclave heap.
+    ///
+    /// **SGX_ERROR_OUT_OF_MEMORY**
+    ///
+    /// The memory could not be allocated.
+    ///
+    pub fn hash_password(password: &[u8], salt: &[u8], params: Argon2Params) -> SgxResult<Argon2Hash> {
+        Self::hash_password_keyed(password, salt, &[], &[], params)
+    }
+
+    ///
+    /// hash_password_keyed is `hash_password` with the optional inputs of
+    /// Argon2: a `secret` key, for example a pepper kept in sealed storage,
+    /// and `associated_data`. Both must be supplied again to verify the
+    /// hash with `Argon2Hash::verify_keyed`.
+    ///
+    /// # Errors
+    ///
+    /// The errors are those of `hash_password`.
+    ///
+    pub fn hash_password_keyed(
+        password: &[u8],
+        salt: &[u8],
+        secret: &[u8],
+        associated_data: &[u8],
+        params: Argon2Params,
+    ) -> SgxResult<Argon2Hash> {
+        let mut tag = [0_u8; ARGON2_TAG_LEN];
+        argon2id(password, salt, secret, associated_data, &params, &mut tag)?;
+        Ok(Argon2Hash {
+            params,
+            salt: salt.to_vec(),
+            tag,
+        })
+    }
+}
+
+///
+/// An Argon2id hash value with the salt and parameters it was computed
+/// with.
+///
+/// It is written and parsed in the PHC string format, for example
+/// `$argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>`, with unpadded Base64
+/// salt and hash.
+///
+#[derive(Clone)]
+pub struct Argon2Hash {
+    params: Argon2Params,
+    salt: Vec<u8>,
+    tag: [u8; ARGON2_TAG_LEN],
+}
+
+impl Argon2Hash {
+    ///
+    /// from_phc_str parses a hash in the PHC string format.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The string is malformed, is not an Argon2id version 0x13 hash, or
+    /// holds a hash value that is not `ARGON2_TAG_LEN` bytes long.
+    ///
+    pub fn from_phc_str(s: &str) -> SgxResult<Argon2Hash> {
+        let invalid = sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+        let mut fields = s.split('$');
+        if fields.next() != Some("")
+            || fields.next() != Some("argon2id")
+            || fields.next() != Some("v=19")
+        {
+            return Err(invalid);
+        }
+
+        let mut params = [None; 3];
+        for param in fields.next().ok_or(invalid)?.split(',') {
+            let (name, value) = param.split_once('=').ok_or(invalid)?;
+            let index = match name {
+                "m" => 0,
+                "t" => 1,
+                "p" => 2,
+                _ => return Err(invalid),
+            };
+            // reject duplicates, signs and leading zeros
+            if params[index].is_some()
+                || value.is_empty()
+                || !value.bytes().all(|b| b.is_ascii_digit())
+                || (value.len() > 1 && value.starts_with('0'))
+            {
+                return Err(invalid);
+            }
+            params[index] = Some(value.parse::<u32>().map_err(|_| invalid)?);
+        }
+        let params = match params {
+            [Some(memory_kib), Some(iterations), Some(parallelism)] => Argon2Params {
+                memory_kib,
+                iterations,
+                parallelism,
+            },
+            _ => return Err(invalid),
+        };
+
+        let salt = base64_decode(fields.next().ok_or(invalid)?)?;
+        let tag = base64_decode(fields.next().ok_or(invalid)?)?;
+        if fields.next().is_some() || salt.len() < ARGON2_MIN_SALT_LEN {
+            return Err(invalid);
+        }
+        Ok(Argon2Hash {
+            params,
+            salt,
+            tag: tag.as_slice().try_into().map_err(|_| invalid)?,
+        })
+    }
+
+    ///
+    /// params returns the parameters the hash was computed with.
+    ///
+    pub fn params(&self) -> Argon2Params {
+        self.params
+    }
+
+    ///
+    /// salt returns the salt the hash was computed with.
+    ///
+    pub fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+
+    ///
+    /// as_bytes returns the hash value.
+    ///
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.tag
+    }
+
+    ///
+    /// verify checks whether `password` hashes to this value. The hash
+    /// values are compared in constant time.
+    ///
+    /// A parameter set that can no longer be computed, for example because
+    /// the enclave heap is smaller than when the hash was created, fails
+    /// the verification.
+    ///
+    pub fn verify(&self, password: &[u8]) -> bool {
+        self.verify_keyed(password, &[], &[])
+    }
+
+    ///
+    /// verify_keyed is `verify` for a hash created by
+    /// `Argon2::hash_password_keyed`.
+    ///
+    pub fn verify_keyed(&self, password: &[u8], secret: &[u8], associated_data: &[u8]) -> bool {
+        let mut tag = [0_u8; ARGON2_TAG_LEN];
+        let result = argon2id(password, &self.salt, secret, associated_data, &self.params, &mut tag)
+            .map(|_| ct_eq(&tag, &self.tag))
+            .unwrap_or(false);
+        zeroize(&mut tag);
+        result
+    }
+
+    ///
+    /// to_phc_string encodes the hash in the PHC string format.
+    ///
+    pub fn to_phc_string(&self) -> String {
+        use core::fmt::Write;
+        let mut s = String::new();
+        let _ = write!(s, "{}", self);
+        s
+    }
+}
+
+impl fmt::Display for Argon2Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "$argon2id$v=19$m={},t={},p={}$",
+            self.params.memory_kib, self.params.iterations, self.params.parallelism
+        )?;
+        base64_encode(f, &self.salt)?;
+        f.write_str("$")?;
+        base64_encode(f, &self.tag)
+    }
+}
+
+impl fmt::Debug for Argon2Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+fn argon2id(
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    associated_data: &[u8],
+    params: &Argon2Params,
+    tag: &mut [u8; ARGON2_TAG_LEN],
+) -> SgxError {
+    params.check()?;
+    if salt.len() < ARGON2_MIN_SALT_LEN
+        || [password, salt, secret, associated_data]
+            .iter()
+            .any(|input| input.len() > u32::MAX as usize)
+    {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    let lanes = params.parallelism as usize;
+    let segment_len = params.memory_kib as usize / (SYNC_POINTS * lanes);
+    let lane_len = segment_len * SYNC_POINTS;
+    let block_count = lane_len * lanes;
+
+    let mut memory: Vec<Block> = Vec::new();
+    memory
+        .try_reserve_exact(block_count)
+        .map_err(|_| sgx_status_t::SGX_ERROR_OUT_OF_MEMORY)?;
+    memory.resize(block_count, [0_u64; BLOCK_WORDS]);
+    let mut memory = Memory {
+        blocks: memory,
+        lanes,
+        lane_len,
+        segment_len,
+        block_count: block_count as u32,
+        passes: params.iterations,
+    };
+
+    let mut h0 = [0_u8; BLAKE2B_MAX_SIZE + 8];
+    let result = initial_hash(password, salt, secret, associated_data, params, &mut h0)
+        .and_then(|_| memory.init_lanes(&mut h0))
+        .and_then(|_| {
+            for pass in 0..params.iterations {
+                for slice in 0..SYNC_POINTS {
+                    for lane in 0..lanes {
+                        memory.fill_segment(pass, lane, slice);
+                    }
+                }
+            }
+            memory.finalize(tag)
+        });
+    zeroize(&mut h0);
+    result
+}
+
+// H0, the 64-byte digest of the inputs, followed by room for the block
+// index and lane that `init_lanes` appends.
+fn initial_hash(
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    associated_data: &[u8],
+    params: &Argon2Params,
+    h0: &mut [u8; BLAKE2B_MAX_SIZE + 8],
+) -> SgxError {
+    let mut state = Blake2b::new(None, BLAKE2B_MAX_SIZE)?;
+    for value in [
+        params.parallelism,
+        ARGON2_TAG_LEN as u32,
+        params.memory_kib,
+        params.iterations,
+        ARGON2_VERSION,
+        ARGON2_TYPE_ID,
+    ] {
+        state.update(&value.to_le_bytes())?;
+    }
+    for input in [password, salt, secret, associated_data] {
+        state.update(&(input.len() as u32).to_le_bytes())?;
+        state.update(input)?;
+    }
+    h0[..BLAKE2B_MAX_SIZE].copy_from_slice(state.finalize()?.as_bytes());
+    Ok(())
+}
+
+// H', the variable-length hash function of RFC 9106 section 3.3.
+fn hash_long(input: &[&[u8]], out: &mut [u8]) -> SgxError {
+    let out_len = out.len();
+    let mut state = Blake2b::new(None, out_len.min(BLAKE2B_MAX_SIZE))?;
+    state.update(&(out_len as u32).to_le_bytes())?;
+    for part in input {
+        state.update(part)?;
+    }
+    let mut v = state.finalize()?;
+    if out_len <= BLAKE2B_MAX_SIZE {
+        out.copy_from_slice(v.as_bytes());
+        return Ok(());
+    }
+
+    // chain 64-byte digests, keeping the first half of each, until at
+    // most 64 bytes are left for the last one
+    let mut pos = 0;
+    while out_len - pos > BLAKE2B_MAX_SIZE {
+        out[pos..pos + BLAKE2B_MAX_SIZE / 2].copy_from_slice(&v.as_bytes()[..BLAKE2B_MAX_SIZE / 2]);
+        pos += BLAKE2B_MAX_SIZE / 2;
+        let next_len = (out_len - pos).min(BLAKE2B_MAX_SIZE);
+        v = Blake2b::digest(next_len, v.as_bytes())?;
+    }
+    out[pos..].copy_from_slice(v.as_bytes());
+    Ok(())
+}
+
+struct Memory {
+    blocks: Vec<Block>,
+    lanes: usize,
+    lane_len: usize,
+    segment_len: usize,
+    block_count: u32,
+    passes: u32,
+}
+
+impl Memory {
+    fn init_lanes(&mut self, h0: &mut [u8; BLAKE2B_MAX_SIZE + 8]) -> SgxError {
+        let mut bytes = [0_u8; BLOCK_WORDS * 8];
+        let mut result = Ok(());
+        'lanes: for lane in 0..self.lanes {
+            h0[BLAKE2B_MAX_SIZE + 4..].copy_from_slice(&(lane as u32).to_le_bytes());
+            for index in 0..2 {
+                h0[BLAKE2B_MAX_SIZE..BLAKE2B_MAX_SIZE + 4].copy_from_slice(&(index as u32).to_le_bytes());
+                if let Err(e) = hash_long(&[&h0[..]], &mut bytes) {
+                    result = Err(e);
+                    break 'lanes;
+                }
+                let block = &mut self.blocks[lane * self.lane_len + index];
+                for (word, chunk) in block.iter_mut().zip(bytes.chunks_exact(8)) {
+                    *word = u64::from_le_bytes(chunk.try_into().unwrap());
+                }
+            }
+        }
+        zeroize(&mut bytes);
+        result
+    }
+
+    fn fill_segment(&mut self, pass: u32, lane: usize, slice: usize) {
+        // the first half of the first pass uses data-independent addresses
+        let data_independent = pass == 0 && slice < SYNC_POINTS / 2;
+        let mut input: Block = [0_u64; BLOCK_WORDS];
+        let mut addresses: Block = [0_u64; BLOCK_WORDS];
+        if data_independent {
+            input[0] = pass as u64;
+            input[1] = lane as u64;
+            input[2] = slice as u64;
+            input[3] = self.block_count as u64;
+            input[4] = self.passes as u64;
+            input[5] = ARGON2_TYPE_ID as u64;
+        }
+
+        // the first two blocks of each lane are set by `init_lanes`
+        let start = if pass == 0 && slice == 0 {
+            if data_independent {
+                next_addresses(&mut input, &mut addresses);
+            }
+            2
+        } else {
+            0
+        };
+
+        let lane_start = lane * self.lane_len;
+        for index in start..self.segment_len {
+            let offset = slice * self.segment_len + index;
+            let curr = lane_start + offset;
+            let prev = if offset == 0 {
+                lane_start + self.lane_len - 1
+            } else {
+                curr - 1
+            };
+
+            let pseudo_rand = if data_independent {
+                if index % BLOCK_WORDS == 0 {
+                    next_addresses(&mut input, &mut addresses);
+                }
+                addresses[index % BLOCK_WORDS]
+            } else {
+                self.blocks[prev][0]
+            };
+
+            let ref_lane = if pass == 0 && slice == 0 {
+                lane
+            } else {
+                ((pseudo_rand >> 32) % self.lanes as u64) as usize
+            };
+            let ref_index = self.reference_index(pass, slice, index, pseudo_rand as u32, ref_lane == lane);
+            let reference = ref_lane * self.lane_len + ref_index;
+
+            let mut next = [0_u64; BLOCK_WORDS];
+            compress(&self.blocks[prev], &self.blocks[reference], &mut next);
+            let block = &mut self.blocks[curr];
+            if pass == 0 {
+                *block = next;
+            } else {
+                block.iter_mut().zip(next.iter()).for_each(|(b, n)| *b ^= n);
+            }
+        }
+    }
+
+    // The index, within the reference lane, of the block mixed into block
+    // `index` of the current segment (RFC 9106 section 3.4.2).
+    fn reference_index(&self, pass: u32, slice: usize, index: usize, j1: u32, same_lane: bool) -> usize {
+        // the blocks that are finished, except the one just before
+        let area = if pass == 0 {
+            if slice == 0 || same_lane {
+                slice * self.segment_len + index - 1
+            } else if index == 0 {
+                slice * self.segment_len - 1
+            } else {
+                slice * self.segment_len
+            }
+        } else if same_lane {
+            self.lane_len - self.segment_len + index - 1
+        } else if index == 0 {
+            self.lane_len - self.segment_len - 1
+        } else {
+            self.lane_len - self.segment_len
+        };
+
+        let x = (j1 as u64 * j1 as u64) >> 32;
+        let y = (area as u64 * x) >> 32;
+        let relative = area - 1 - y as usize;
+
+        let start = if pass == 0 || slice == SYNC_POINTS - 1 {
+            0
+        } else {
+            (slice + 1) * self.segment_len
+        };
+        (start + relative) % self.lane_len
+    }
+
+    fn finalize(&mut self, tag: &mut [u8; ARGON2_TAG_LEN]) -> SgxError {
+        // the XOR of the last block of every lane
+        let mut last = self.blocks[self.lane_len - 1];
+        for lane in 1..self.lanes {
+            let block = &self.blocks[lane * self.lane_len + self.lane_len - 1];
+            last.iter_mut().zip(block.iter()).for_each(|(l, b)| *l ^= b);
+        }
+        let mut bytes = [0_u8; BLOCK_WORDS * 8];
+        for (chunk, word) in bytes.chunks_exact_mut(8).zip(last.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        let result = hash_long(&[&bytes], tag);
+        zeroize(&mut bytes);
+        zeroize_block(&mut last);
+        result
+    }
+}
+
+impl Drop for Memory {
+    fn drop(&mut self) {
+        for block in self.blocks.iter_mut() {
+            zeroize_block(block);
+        }
+    }
+}
+
+fn zeroize_block(block: &mut Block) {
+    for word in block.iter_mut() {
+        unsafe { ptr::write_volatile(word, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+// Advances the counter of the address generator and computes the next
+// block of addresses, G(0, G(0, input)).
+fn next_addresses(input: &mut Block, addresses: &mut Block) {
+    const ZERO: Block = [0_u64; BLOCK_WORDS];
+    input[6] += 1;
+    let mut tmp = [0_u64; BLOCK_WORDS];
+    compress(&ZERO, input, &mut tmp);
+    compress(&ZERO, &tmp, addresses);
+}
+
+// The compression function G of RFC 9106 section 3.5.
+fn compress(x: &Block, y: &Block, out: &mut Block) {
+    let mut r = [0_u64; BLOCK_WORDS];
+    for (r, (x, y)) in r.iter_mut().zip(x.iter().zip(y.iter())) {
+        *r = x ^ y;
+    }
+    let mut q = r;
+
+    // apply P to the eight rows of sixteen words, then to the eight
+    // columns, each made of two adjacent words of every row
+    for row in 0..8 {
+        let i = row * 16;
+        permute(
+            &mut q,
+            [i, i + 1, i + 2, i + 3, i + 4, i + 5, i + 6, i + 7,
+             i + 8, i + 9, i + 10, i + 11, i + 12, i + 13, i + 14, i + 15],
+        );
+    }
+    for column in 0..8 {
+        let i = column * 2;
+        permute(
+            &mut q,
+            [i, i + 1, i + 16, i + 17, i + 32, i + 33, i + 48, i + 49,
+             i + 64, i + 65, i + 80, i + 81, i + 96, i + 97, i + 112, i + 113],
+        );
+    }
+
+    for (out, (q, r)) in out.iter_mut().zip(q.iter().zip(r.iter())) {
+        *out = q ^ r;
+    }
+}
+
+// The BLAKE2b round without message words, with the multiplications of
+// BlaMka added to the additions.
+fn permute(v: &mut Block, i: [usize; 16]) {
+    fn g(v: &mut Block, a: usize, b: usize, c: usize, d: usize) {
+        fn blamka(x: u64, y: u64) -> u64 {
+            let m = (x as u32 as u64) * (y as u32 as u64);
+            x.wrapping_add(y).wrapping_add(m.wrapping_mul(2))
+        }
+        v[a] = blamka(v[a], v[b]);
+        v[d] = (v[d] ^ v[a]).rotate_right(32);
+        v[c] = blamka(v[c], v[d]);
+        v[b] = (v[b] ^ v[c]).rotate_right(24);
+        v[a] = blamka(v[a], v[b]);
+        v[d] = (v[d] ^ v[a]).rotate_right(16);
+        v[c] = blamka(v[c], v[d]);
+        v[b] = (v[b] ^ v[c]).rotate_right(63);
+    }
+    g(v, i[0], i[4], i[8], i[12]);
+    g(v, i[1], i[5], i[9], i[13]);
+    g(v, i[2], i[6], i[10], i[14]);
+    g(v, i[3], i[7], i[11], i[15]);
+    g(v, i[0], i[5], i[10], i[15]);
+    g(v, i[1], i[6], i[11], i[12]);
+    g(v, i[2], i[7], i[8], i[13]);
+    g(v, i[3], i[4], i[9], i[14]);
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Unpadded standard Base64, as used by PHC strings.
+fn base64_encode(f: &mut fmt::Formatter<'_>, data: &[u8]) -> fmt::Result {
+    for chunk in data.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0_u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..chunk.len() + 1 {
+            let c = BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize];
+            fmt::Write::write_char(f, c as char)?;
+        }
+    }
+    Ok(())
+}
+
+fn base64_decode(s: &str) -> SgxResult<Vec<u8>> {
+    let invalid = sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    // a single character left over can not encode a byte
+    if s.len() % 4 == 1 {
+        return Err(invalid);
+    }
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    for chunk in s.as_bytes().chunks(4) {
+        let mut n = 0_u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let value = BASE64_ALPHABET.iter().position(|a| a == c).ok_or(invalid)?;
+            n |= (value as u32) << (18 - 6 * i);
+        }
+        let bytes = n.to_be_bytes();
+        let len = chunk.len() - 1;
+        // the unused low bits of a partial chunk must be zero
+        if len < 3 && n & (0x00ff_ffff >> (8 * len)) != 0 {
+            return Err(invalid);
+        }
+        out.extend_from_slice(&bytes[1..1 + len]);
+    }
+    Ok(out)
+}
@@ -0,0 +1,324 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//!
+//! BLAKE2 hash functions
+//!
+//! BLAKE2b and BLAKE2s as defined in RFC 7693, unkeyed for hashing or keyed
+//! for use as a MAC. The Intel SGX SDK has no BLAKE2 primitive, so the
+//! compression functions are implemented here.
+//!
+use crate::util::zeroize;
+use crate::util::ct_eq;
+use core::convert::TryInto;
+use core::fmt;
+use core::ptr;
+use core::sync::atomic::{compiler_fence, Ordering};
+use sgx_types::*;
+
+/// The largest output and key size of BLAKE2b in bytes.
+pub const BLAKE2B_MAX_SIZE: usize = 64;
+
+/// The largest output and key size of BLAKE2s in bytes.
+pub const BLAKE2S_MAX_SIZE: usize = 32;
+
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09_e667_f3bc_c908,
+    0xbb67_ae85_84ca_a73b,
+    0x3c6e_f372_fe94_f82b,
+    0xa54f_f53a_5f1d_36f1,
+    0x510e_527f_ade6_82d1,
+    0x9b05_688c_2b3e_6c1f,
+    0x1f83_d9ab_fb41_bd6b,
+    0x5be0_cd19_137e_2179,
+];
+
+const BLAKE2S_IV: [u32; 8] = [
+    0x6a09_e667,
+    0xbb67_ae85,
+    0x3c6e_f372,
+    0xa54f_f53a,
+    0x510e_527f,
+    0x9b05_688c,
+    0x1f83_d9ab,
+    0x5be0_cd19,
+];
+
+// The message word permutations, one per round; BLAKE2b repeats the first
+// two in rounds 11 and 12.
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+///
+/// A BLAKE2 hash value or MAC tag of up to `N` bytes.
+///
+/// Comparisons with `==` and `verify` take the same time wherever the
+/// values differ.
+///
+#[derive(Clone, Copy)]
+pub struct Blake2Output<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Blake2Output<N> {
+    ///
+    /// as_bytes returns the hash value, as long as the output size the
+    /// state was created with.
+    ///
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    ///
+    /// verify checks, in constant time, whether `tag` equals this value.
+    ///
+    pub fn verify(&self, tag: &[u8]) -> bool {
+        ct_eq(self.as_bytes(), tag)
+    }
+}
+
+impl<const N: usize> PartialEq for Blake2Output<N> {
+    fn eq(&self, other: &Blake2Output<N>) -> bool {
+        self.verify(other.as_bytes())
+    }
+}
+
+impl<const N: usize> Eq for Blake2Output<N> {}
+
+impl<const N: usize> AsRef<[u8]> for Blake2Output<N> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<const N: usize> fmt::Debug for Blake2Output<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for b in self.as_bytes() {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! impl_blake2 {
+    (
+        $(#[$attr:meta])*
+        $name:ident, $word:ty, $counter:ty, $iv:expr, $rounds:expr,
+        ($r1:expr, $r2:expr, $r3:expr, $r4:expr), $max:expr
+    ) => {
+        $(#[$attr])*
+        pub struct $name {
+            h: [$word; 8],
+            block: [u8; 16 * <$word>::BITS as usize / 8],
+            pos: usize,
+            count: $counter,
+            out_len: usize,
+        }
+
+        impl $name {
+            const BLOCK_SIZE: usize = 16 * <$word>::BITS as usize / 8;
+
+            ///
+            /// new creates a hash state with no input processed.
+            ///
+            /// # Parameters
+            ///
+            #[doc = concat!("**key**\n\nThe MAC key, up to ", stringify!($max),
+                " bytes. `None` or an empty key gives the unkeyed hash.")]
+            ///
+            #[doc = concat!("**output_len**\n\nThe size of the hash value, 1 to ",
+                stringify!($max), " bytes.")]
+            ///
+            /// # Errors
+            ///
+            /// **SGX_ERROR_INVALID_PARAMETER**
+            ///
+            /// The key or output size is out of range.
+            ///
+            pub fn new(key: Option<&[u8]>, output_len: usize) -> SgxResult<$name> {
+                let key = key.unwrap_or(&[]);
+                if key.len() > $max || output_len == 0 || output_len > $max {
+                    return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+                }
+
+                let mut h = $iv;
+                h[0] ^= 0x0101_0000 ^ ((key.len() as $word) << 8) ^ output_len as $word;
+                let mut state = $name {
+                    h,
+                    block: [0_u8; Self::BLOCK_SIZE],
+                    pos: 0,
+                    count: 0,
+                    out_len: output_len,
+                };
+                if !key.is_empty() {
+                    // the key padded with zeros is the first block
+                    state.block[..key.len()].copy_from_slice(key);
+                    state.pos = Self::BLOCK_SIZE;
+                }
+                Ok(state)
+            }
+
+            ///
+            /// update processes the next part of the input.
+            ///
+            /// # Errors
+            ///
+            /// This function currently always succeeds.
+            ///
+            pub fn update(&mut self, mut data: &[u8]) -> SgxError {
+                while !data.is_empty() {
+                    // the last block is compressed differently, so a full
+                    // block is only compressed once more input arrives
+                    if self.pos == Self::BLOCK_SIZE {
+                        self.count = self.count.wrapping_add(Self::BLOCK_SIZE as $counter);
+                        self.compress(false);
+                        self.pos = 0;
+                    }
+                    let n = core::cmp::min(Self::BLOCK_SIZE - self.pos, data.len());
+                    self.block[self.pos..self.pos + n].copy_from_slice(&data[..n]);
+                    self.pos += n;
+                    data = &data[n..];
+                }
+                Ok(())
+            }
+
+            ///
+            /// finalize returns the hash value, or the MAC tag of a keyed
+            /// state, and consumes the state.
+            ///
+            /// # Errors
+            ///
+            /// This function currently always succeeds.
+            ///
+            pub fn finalize(mut self) -> SgxResult<Blake2Output<$max>> {
+                self.count = self.count.wrapping_add(self.pos as $counter);
+                self.block[self.pos..].iter_mut().for_each(|b| *b = 0);
+                self.compress(true);
+
+                let mut bytes = [0_u8; $max];
+                for (out, word) in bytes.chunks_exact_mut(<$word>::BITS as usize / 8).zip(self.h.iter()) {
+                    out.copy_from_slice(&word.to_le_bytes());
+                }
+                let result = Blake2Output {
+                    bytes,
+                    len: self.out_len,
+                };
+                zeroize(&mut bytes);
+                Ok(result)
+            }
+
+            ///
+            /// digest computes the unkeyed hash of `data` in one call.
+            ///
+            /// # Errors
+            ///
+            /// **SGX_ERROR_INVALID_PARAMETER**
+            ///
+            /// The output size is out of range.
+            ///
+            pub fn digest(output_len: usize, data: &[u8]) -> SgxResult<Blake2Output<$max>> {
+                let mut state = $name::new(None, output_len)?;
+                state.update(data)?;
+                state.finalize()
+            }
+
+            fn compress(&mut self, last: bool) {
+                const WORD_SIZE: usize = <$word>::BITS as usize / 8;
+                let mut m = [0 as $word; 16];
+                for (w, bytes) in m.iter_mut().zip(self.block.chunks_exact(WORD_SIZE)) {
+                    *w = <$word>::from_le_bytes(bytes.try_into().unwrap());
+                }
+
+                let mut v = [0 as $word; 16];
+                v[..8].copy_from_slice(&self.h);
+                v[8..].copy_from_slice(&$iv);
+                v[12] ^= self.count as $word;
+                v[13] ^= (self.count >> <$word>::BITS) as $word;
+                if last {
+                    v[14] = !v[14];
+                }
+
+                let g = |v: &mut [$word; 16], a: usize, b: usize, c: usize, d: usize, x: $word, y: $word| {
+                    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+                    v[d] = (v[d] ^ v[a]).rotate_right($r1);
+                    v[c] = v[c].wrapping_add(v[d]);
+                    v[b] = (v[b] ^ v[c]).rotate_right($r2);
+                    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+                    v[d] = (v[d] ^ v[a]).rotate_right($r3);
+                    v[c] = v[c].wrapping_add(v[d]);
+                    v[b] = (v[b] ^ v[c]).rotate_right($r4);
+                };
+                for round in 0..$rounds {
+                    let s = &SIGMA[round % 10];
+                    g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+                    g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+                    g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+                    g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+                    g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+                    g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+                    g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+                    g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+                }
+
+                for (i, h) in self.h.iter_mut().enumerate() {
+                    *h ^= v[i] ^ v[i + 8];
+                }
+            }
+        }
+
+        impl Drop for $name {
+            fn drop(&mut self) {
+                for word in self.h.iter_mut() {
+                    unsafe { ptr::write_volatile(word, 0) };
+                }
+                compiler_fence(Ordering::SeqCst);
+                zeroize(&mut self.block);
+            }
+        }
+    };
+}
+
+impl_blake2! {
+    ///
+    /// BLAKE2b, optimized for 64-bit platforms, with a hash value of 1 to
+    /// 64 bytes.
+    ///
+    /// The state, including the key, is erased when the object is dropped.
+    ///
+    Blake2b, u64, u128, BLAKE2B_IV, 12, (32, 24, 16, 63), BLAKE2B_MAX_SIZE
+}
+
+impl_blake2! {
+    ///
+    /// BLAKE2s, optimized for 8 to 32-bit platforms, with a hash value of 1
+    /// to 32 bytes.
+    ///
+    /// The state, including the key, is erased when the object is dropped.
+    ///
+    Blake2s, u32, u64, BLAKE2S_IV, 10, (16, 12, 8, 7), BLAKE2S_MAX_SIZE
+}
@@ -0,0 +1,476 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+//!
+//! BLAKE3 hash function
+//!
+//! BLAKE3 as defined in the BLAKE3 specification, with its three modes:
+//! hashing, keyed hashing for use as a MAC, and key derivation, each with an
+//! extendable output. The Intel SGX SDK has no BLAKE3 primitive, so this is
+//! a portable implementation of the compression function and the chunk tree.
+//!
+use crate::util::zeroize;
+use core::cmp;
+use core::ptr;
+use core::sync::atomic::{compiler_fence, Ordering};
+
+/// The default output size of BLAKE3 in bytes.
+pub const BLAKE3_OUT_LEN: usize = 32;
+
+/// The key size of keyed BLAKE3 in bytes.
+pub const BLAKE3_KEY_LEN: usize = 32;
+
+const BLOCK_LEN: usize = 64;
+const CHUNK_LEN: usize = 1024;
+// enough for 2^54 chunks, the largest input the 64 bit counter can address
+const MAX_DEPTH: usize = 54;
+
+const CHUNK_START: u32 = 1 << 0;
+const CHUNK_END: u32 = 1 << 1;
+const PARENT: u32 = 1 << 2;
+const ROOT: u32 = 1 << 3;
+const KEYED_HASH: u32 = 1 << 4;
+const DERIVE_KEY_CONTEXT: u32 = 1 << 5;
+const DERIVE_KEY_MATERIAL: u32 = 1 << 6;
+
+const IV: [u32; 8] = [
+    0x6a09_e667,
+    0xbb67_ae85,
+    0x3c6e_f372,
+    0xa54f_f53a,
+    0x510e_527f,
+    0x9b05_688c,
+    0x1f83_d9ab,
+    0x5be0_cd19,
+];
+
+// The message word permutation applied between rounds.
+const MSG_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+///
+/// An incremental BLAKE3 hash state.
+///
+/// `finalize` does not consume the state, so more input can be added and
+/// the hash taken again. Keyed hash values used as MAC tags must be compared
+/// with `util::ct_eq`. The key and the chaining values are erased
+/// when the state is dropped.
+///
+pub struct Blake3Hasher {
+    key: [u32; 8],
+    chunk: ChunkState,
+    cv_stack: [[u32; 8]; MAX_DEPTH],
+    cv_stack_len: usize,
+    flags: u32,
+}
+
+impl Blake3Hasher {
+    ///
+    /// new creates a state for the unkeyed hash.
+    ///
+    pub fn new() -> Blake3Hasher {
+        Blake3Hasher::with_key_words(IV, 0)
+    }
+
+    ///
+    /// new_keyed creates a state for the keyed hash, a MAC with a 256 bit key.
+    ///
+    pub fn new_keyed(key: &[u8; BLAKE3_KEY_LEN]) -> Blake3Hasher {
+        Blake3Hasher::with_key_words(words_from_le_bytes(key), KEYED_HASH)
+    }
+
+    ///
+    /// new_derive_key creates a state for deriving keys from the key material
+    /// passed to `update`.
+    ///
+    /// # Parameters
+    ///
+    /// **context**
+    ///
+    /// A hardcoded, globally unique string that names the application and
+    /// the purpose of the key, such as
+    /// `"example.com 2024-01-01 session tokens v1"`. It must not contain
+    /// secrets or vary at runtime.
+    ///
+    pub fn new_derive_key(context: &str) -> Blake3Hasher {
+        let mut context_hasher = Blake3Hasher::with_key_words(IV, DERIVE_KEY_CONTEXT);
+        context_hasher.update(context.as_bytes());
+        let context_key = context_hasher.finalize();
+        Blake3Hasher::with_key_words(words_from_le_bytes(&context_key), DERIVE_KEY_MATERIAL)
+    }
+
+    fn with_key_words(key: [u32; 8], flags: u32) -> Blake3Hasher {
+        Blake3Hasher {
+            key,
+            chunk: ChunkState::new(key, 0, flags),
+            cv_stack: [[0; 8]; MAX_DEPTH],
+            cv_stack_len: 0,
+            flags,
+        }
+    }
+
+    ///
+    /// update processes the next part of the input.
+    ///
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            // a full chunk is only finished once more input arrives, since
+            // the last chunk may be the root
+            if self.chunk.len() == CHUNK_LEN {
+                let cv = self.chunk.output().chaining_value();
+                let total_chunks = self.chunk.chunk_counter + 1;
+                self.add_chunk_chaining_value(cv, total_chunks);
+                self.chunk = ChunkState::new(self.key, total_chunks, self.flags);
+            }
+            let n = cmp::min(CHUNK_LEN - self.chunk.len(), data.len());
+            self.chunk.update(&data[..n]);
+            data = &data[n..];
+        }
+    }
+
+    ///
+    /// finalize returns the 256 bit hash value of the input so far.
+    ///
+    pub fn finalize(&self) -> [u8; BLAKE3_OUT_LEN] {
+        let mut out = [0_u8; BLAKE3_OUT_LEN];
+        self.finalize_xof().fill(&mut out);
+        out
+    }
+
+    ///
+    /// finalize_xof returns a reader for an output of any length. Its first
+    /// 32 bytes are the value returned by `finalize`.
+    ///
+    pub fn finalize_xof(&self) -> Blake3Xof {
+        // merge the right edge of the tree from the bottom up
+        let mut output = self.chunk.output();
+        for cv in self.cv_stack[..self.cv_stack_len].iter().rev() {
+            output = parent_output(cv, &output.chaining_value(), self.key, self.flags);
+        }
+        Blake3Xof {
+            output,
+            counter: 0,
+            block: [0_u8; BLOCK_LEN],
+            pos: BLOCK_LEN,
+        }
+    }
+
+    ///
+    /// reset discards the input and keeps the key or context.
+    ///
+    pub fn reset(&mut self) {
+        self.chunk = ChunkState::new(self.key, 0, self.flags);
+        self.cv_stack_len = 0;
+    }
+
+    ///
+    /// hash computes the unkeyed hash of `data` in one call.
+    ///
+    pub fn hash(data: &[u8]) -> [u8; BLAKE3_OUT_LEN] {
+        let mut hasher = Blake3Hasher::new();
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    ///
+    /// keyed_hash computes the keyed hash of `data` in one call.
+    ///
+    pub fn keyed_hash(key: &[u8; BLAKE3_KEY_LEN], data: &[u8]) -> [u8; BLAKE3_OUT_LEN] {
+        let mut hasher = Blake3Hasher::new_keyed(key);
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    ///
+    /// derive_key derives a 256 bit key from `key_material` in one call.
+    ///
+    pub fn derive_key(context: &str, key_material: &[u8]) -> [u8; BLAKE3_OUT_LEN] {
+        let mut hasher = Blake3Hasher::new_derive_key(context);
+        hasher.update(key_material);
+        hasher.finalize()
+    }
+
+    // Pushes the chaining value of a finished chunk, first merging every
+    // completed subtree. After `total_chunks` chunks there is one subtree
+    // per set bit of the count.
+    fn add_chunk_chaining_value(&mut self, mut cv: [u32; 8], mut total_chunks: u64) {
+        while total_chunks & 1 == 0 {
+            self.cv_stack_len -= 1;
+            let left = self.cv_stack[self.cv_stack_len];
+            cv = parent_output(&left, &cv, self.key, self.flags).chaining_value();
+            total_chunks >>= 1;
+        }
+        self.cv_stack[self.cv_stack_len] = cv;
+        self.cv_stack_len += 1;
+    }
+}
+
+impl Default for Blake3Hasher {
+    fn default() -> Blake3Hasher {
+        Blake3Hasher::new()
+    }
+}
+
+impl Drop for Blake3Hasher {
+    fn drop(&mut self) {
+        zeroize_words(&mut self.key);
+        for cv in self.cv_stack.iter_mut() {
+            zeroize_words(cv);
+        }
+    }
+}
+
+///
+/// The extendable output of a BLAKE3 state.
+///
+/// The output is erased when the reader is dropped.
+///
+pub struct Blake3Xof {
+    output: Output,
+    counter: u64,
+    block: [u8; BLOCK_LEN],
+    pos: usize,
+}
+
+impl Blake3Xof {
+    ///
+    /// fill writes the next `out.len()` bytes of the output.
+    ///
+    pub fn fill(&mut self, out: &mut [u8]) {
+        for byte in out.iter_mut() {
+            if self.pos == BLOCK_LEN {
+                self.block = self.output.root_output_block(self.counter);
+                self.counter += 1;
+                self.pos = 0;
+            }
+            *byte = self.block[self.pos];
+            self.pos += 1;
+        }
+    }
+}
+
+impl Drop for Blake3Xof {
+    fn drop(&mut self) {
+        zeroize_words(&mut self.output.input_cv);
+        zeroize_words(&mut self.output.block_words);
+        zeroize(&mut self.block);
+    }
+}
+
+struct ChunkState {
+    cv: [u32; 8],
+    chunk_counter: u64,
+    block: [u8; BLOCK_LEN],
+    block_len: usize,
+    blocks_compressed: usize,
+    flags: u32,
+}
+
+impl ChunkState {
+    fn new(key: [u32; 8], chunk_counter: u64, flags: u32) -> ChunkState {
+        ChunkState {
+            cv: key,
+            chunk_counter,
+            block: [0_u8; BLOCK_LEN],
+            block_len: 0,
+            blocks_compressed: 0,
+            flags,
+        }
+    }
+
+    fn len(&self) -> usize {
+        BLOCK_LEN * self.blocks_compressed + self.block_len
+    }
+
+    fn start_flag(&self) -> u32 {
+        if self.blocks_compressed == 0 {
+            CHUNK_START
+        } else {
+            0
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            // as in the tree, the last block of the chunk is compressed
+            // differently, so a full block waits for more input
+            if self.block_len == BLOCK_LEN {
+                let words = words_from_le_bytes(&self.block);
+                let out = compress(
+                    &self.cv,
+                    &words,
+                    self.chunk_counter,
+                    BLOCK_LEN as u32,
+                    self.flags | self.start_flag(),
+                );
+                self.cv.copy_from_slice(&out[..8]);
+                self.blocks_compressed += 1;
+                self.block = [0_u8; BLOCK_LEN];
+                self.block_len = 0;
+            }
+            let n = cmp::min(BLOCK_LEN - self.block_len, data.len());
+            self.block[self.block_len..self.block_len + n].copy_from_slice(&data[..n]);
+            self.block_len += n;
+            data = &data[n..];
+        }
+    }
+
+    fn output(&self) -> Output {
+        Output {
+            input_cv: self.cv,
+            block_words: words_from_le_bytes(&self.block),
+            counter: self.chunk_counter,
+            block_len: self.block_len as u32,
+            flags: self.flags | self.start_flag() | CHUNK_END,
+        }
+    }
+}
+
+impl Drop for ChunkState {
+    fn drop(&mut self) {
+        zeroize_words(&mut self.cv);
+        zeroize(&mut self.block);
+    }
+}
+
+// The last compression of a chunk or parent node, kept uncompressed so it
+// can either produce a chaining value or, as the root, the output.
+struct Output {
+    input_cv: [u32; 8],
+    block_words: [u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+}
+
+impl Output {
+    fn chaining_value(&self) -> [u32; 8] {
+        let out = compress(
+            &self.input_cv,
+            &self.block_words,
+            self.counter,
+            self.block_len,
+            self.flags,
+        );
+        let mut cv = [0_u32; 8];
+        cv.copy_from_slice(&out[..8]);
+        cv
+    }
+
+    fn root_output_block(&self, output_block_counter: u64) -> [u8; BLOCK_LEN] {
+        let words = compress(
+            &self.input_cv,
+            &self.block_words,
+            output_block_counter,
+            self.block_len,
+            self.flags | ROOT,
+        );
+        let mut block = [0_u8; BLOCK_LEN];
+        for (bytes, word) in block.chunks_exact_mut(4).zip(words.iter()) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        block
+    }
+}
+
+fn parent_output(left: &[u32; 8], right: &[u32; 8], key: [u32; 8], flags: u32) -> Output {
+    let mut block_words = [0_u32; 16];
+    block_words[..8].copy_from_slice(left);
+    block_words[8..].copy_from_slice(right);
+    Output {
+        input_cv: key,
+        block_words,
+        counter: 0,
+        block_len: BLOCK_LEN as u32,
+        flags: PARENT | flags,
+    }
+}
+
+fn g(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, x: u32, y: u32) {
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(x);
+    state[d] = (state[d] ^ state[a]).rotate_right(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(12);
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(y);
+    state[d] = (state[d] ^ state[a]).rotate_right(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(7);
+}
+
+fn compress(
+    cv: &[u32; 8],
+    block_words: &[u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u32; 16] {
+    let mut state = [
+        cv[0],
+        cv[1],
+        cv[2],
+        cv[3],
+        cv[4],
+        cv[5],
+        cv[6],
+        cv[7],
+        IV[0],
+        IV[1],
+        IV[2],
+        IV[3],
+        counter as u32,
+        (counter >> 32) as u32,
+        block_len,
+        flags,
+    ];
+    let mut m = *block_words;
+    for round in 0..7 {
+        g(&mut state, 0, 4, 8, 12, m[0], m[1]);
+        g(&mut state, 1, 5, 9, 13, m[2], m[3]);
+        g(&mut state, 2, 6, 10, 14, m[4], m[5]);
+        g(&mut state, 3, 7, 11, 15, m[6], m[7]);
+        g(&mut state, 0, 5, 10, 15, m[8], m[9]);
+        g(&mut state, 1, 6, 11, 12, m[10], m[11]);
+        g(&mut state, 2, 7, 8, 13, m[12], m[13]);
+        g(&mut state, 3, 4, 9, 14, m[14], m[15]);
+        if round < 6 {
+            let mut permuted = [0_u32; 16];
+            for (p, &i) in permuted.iter_mut().zip(MSG_PERMUTATION.iter()) {
+                *p = m[i];
+            }
+            m = permuted;
+        }
+    }
+    for i in 0..8 {
+        state[i] ^= state[i + 8];
+        state[i + 8] ^= cv[i];
+    }
+    zeroize_words(&mut m);
+    state
+}
+
+fn words_from_le_bytes<const N: usize>(bytes: &[u8]) -> [u32; N] {
+    let mut words = [0_u32; N];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    words
+}
+
+fn zeroize_words(words: &mut [u32]) {
+    for word in words.iter_mut() {
+        unsafe { ptr::write_volatile(word, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
@@ -0,0 +1,424 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+//!
+//! ChaCha20-Poly1305 authenticated encryption
+//!
+use crate::util::zeroize;
+use core::fmt;
+use core::ptr;
+use core::sync::atomic::{compiler_fence, Ordering};
+use sgx_types::*;
+
+/// The length of a ChaCha20-Poly1305 key.
+pub const CHACHA20_POLY1305_KEY_SIZE: usize = 32;
+
+/// The length of a ChaCha20-Poly1305 nonce.
+pub const CHACHA20_POLY1305_NONCE_SIZE: usize = 12;
+
+/// The length of a ChaCha20-Poly1305 authentication tag, which `seal`
+/// appends to the ciphertext.
+pub const CHACHA20_POLY1305_TAG_SIZE: usize = 16;
+
+// The 32 bit block counter starts at 1, which limits plaintext to
+// 2^32 - 1 blocks of 64 bytes (RFC 8439, section 2.8).
+const MAX_TEXT_LEN: u64 = ((1 << 32) - 1) * 64;
+
+const BLOCK_LEN: usize = 64;
+
+// "expand 32-byte k"
+const SIGMA: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+///
+/// The ChaCha20-Poly1305 AEAD as defined in RFC 8439.
+///
+/// It only uses additions, rotations and XORs, so it runs in constant time
+/// without AES-NI and is as fast in simulation mode as in hardware mode.
+/// Nothing is allocated; all output goes to caller provided buffers. The
+/// key is erased when the object is dropped.
+///
+pub struct ChaCha20Poly1305 {
+    key: [u32; 8],
+}
+
+impl ChaCha20Poly1305 {
+    ///
+    /// new creates a ChaCha20-Poly1305 instance.
+    ///
+    /// # Parameters
+    ///
+    /// **key**
+    ///
+    /// The 256 bit key.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    pub fn new(key: &[u8; CHACHA20_POLY1305_KEY_SIZE]) -> ChaCha20Poly1305 {
+        let mut words = [0_u32; 8];
+        for (w, bytes) in words.iter_mut().zip(key.chunks_exact(4)) {
+            *w = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        ChaCha20Poly1305 { key: words }
+    }
+
+    ///
+    /// seal encrypts and authenticates `plaintext`, and authenticates `aad`.
+    ///
+    /// # Parameters
+    ///
+    /// **nonce**
+    ///
+    /// The 96 bit nonce. It must never be reused with the same key.
+    ///
+    /// **plaintext**
+    ///
+    /// The data to encrypt, at most 2^38 - 64 bytes.
+    ///
+    /// **aad**
+    ///
+    /// Additional data to authenticate.
+    ///
+    /// **sealed**
+    ///
+    /// Receives the ciphertext followed by the `CHACHA20_POLY1305_TAG_SIZE`
+    /// byte tag. It must hold at least
+    /// `plaintext.len() + CHACHA20_POLY1305_TAG_SIZE` bytes.
+    ///
+    /// # Return value
+    ///
+    /// The number of bytes written to `sealed`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The plaintext is too long, or `sealed` is too short.
+    ///
+    pub fn seal(
+        &self,
+        nonce: &[u8; CHACHA20_POLY1305_NONCE_SIZE],
+        plaintext: &[u8],
+        aad: &[u8],
+        sealed: &mut [u8],
+    ) -> SgxResult<usize> {
+        let sealed_len = plaintext
+            .len()
+            .checked_add(CHACHA20_POLY1305_TAG_SIZE)
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        if plaintext.len() as u64 > MAX_TEXT_LEN || sealed.len() < sealed_len {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let (ciphertext, tag_out) = sealed[..sealed_len].split_at_mut(plaintext.len());
+        ciphertext.copy_from_slice(plaintext);
+        self.xor_keystream(nonce, ciphertext);
+        let tag = self.compute_tag(nonce, ciphertext, aad);
+        tag_out.copy_from_slice(&tag);
+        Ok(sealed_len)
+    }
+
+    ///
+    /// open verifies and decrypts data produced by `seal`.
+    ///
+    /// The tag is checked before anything is written to `plaintext`.
+    ///
+    /// # Parameters
+    ///
+    /// **nonce**
+    ///
+    /// The nonce passed to `seal`.
+    ///
+    /// **sealed**
+    ///
+    /// The ciphertext followed by the tag.
+    ///
+    /// **aad**
+    ///
+    /// The additional data passed to `seal`.
+    ///
+    /// **plaintext**
+    ///
+    /// Receives the plaintext. It must hold at least
+    /// `sealed.len() - CHACHA20_POLY1305_TAG_SIZE` bytes.
+    ///
+    /// # Return value
+    ///
+    /// The number of bytes written to `plaintext`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `sealed` is shorter than a tag or too long, or `plaintext` is too
+    /// short.
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The tag does not match: the key, nonce, ciphertext or additional
+    /// data is wrong.
+    ///
+    pub fn open(
+        &self,
+        nonce: &[u8; CHACHA20_POLY1305_NONCE_SIZE],
+        sealed: &[u8],
+        aad: &[u8],
+        plaintext: &mut [u8],
+    ) -> SgxResult<usize> {
+        if sealed.len() < CHACHA20_POLY1305_TAG_SIZE {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let (ciphertext, tag) = sealed.split_at(sealed.len() - CHACHA20_POLY1305_TAG_SIZE);
+        if ciphertext.len() as u64 > MAX_TEXT_LEN || plaintext.len() < ciphertext.len() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let expected_tag = self.compute_tag(nonce, ciphertext, aad);
+        let diff = expected_tag
+            .iter()
+            .zip(tag.iter())
+            .fold(0_u8, |acc, (a, b)| acc | (a ^ b));
+        if diff != 0 {
+            return Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH);
+        }
+
+        let out = &mut plaintext[..ciphertext.len()];
+        out.copy_from_slice(ciphertext);
+        self.xor_keystream(nonce, out);
+        Ok(out.len())
+    }
+
+    // ChaCha20 encryption starting from block 1 (RFC 8439, section 2.4).
+    fn xor_keystream(&self, nonce: &[u8; CHACHA20_POLY1305_NONCE_SIZE], data: &mut [u8]) {
+        let mut keystream = [0_u8; BLOCK_LEN];
+        for (i, chunk) in data.chunks_mut(BLOCK_LEN).enumerate() {
+            chacha20_block(&self.key, i as u32 + 1, nonce, &mut keystream);
+            chunk.iter_mut().zip(keystream.iter()).for_each(|(d, k)| *d ^= *k);
+        }
+        zeroize(&mut keystream);
+    }
+
+    // Poly1305 over the padded aad and ciphertext and their lengths, keyed
+    // with ChaCha20 block 0 (RFC 8439, section 2.8).
+    fn compute_tag(
+        &self,
+        nonce: &[u8; CHACHA20_POLY1305_NONCE_SIZE],
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> [u8; CHACHA20_POLY1305_TAG_SIZE] {
+        let mut block0 = [0_u8; BLOCK_LEN];
+        chacha20_block(&self.key, 0, nonce, &mut block0);
+        let mut poly = Poly1305::new(&block0[..32]);
+        zeroize(&mut block0);
+
+        poly.update_padded(aad);
+        poly.update_padded(ciphertext);
+        let mut lengths = [0_u8; 16];
+        lengths[..8].copy_from_slice(&(aad.len() as u64).to_le_bytes());
+        lengths[8..].copy_from_slice(&(ciphertext.len() as u64).to_le_bytes());
+        poly.update_block(&lengths);
+        poly.finish()
+    }
+}
+
+impl Drop for ChaCha20Poly1305 {
+    fn drop(&mut self) {
+        for w in self.key.iter_mut() {
+            unsafe { ptr::write_volatile(w, 0) };
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+impl fmt::Debug for ChaCha20Poly1305 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChaCha20Poly1305").finish_non_exhaustive()
+    }
+}
+
+macro_rules! quarter_round {
+    ($s:expr, $a:expr, $b:expr, $c:expr, $d:expr) => {
+        $s[$a] = $s[$a].wrapping_add($s[$b]);
+        $s[$d] = ($s[$d] ^ $s[$a]).rotate_left(16);
+        $s[$c] = $s[$c].wrapping_add($s[$d]);
+        $s[$b] = ($s[$b] ^ $s[$c]).rotate_left(12);
+        $s[$a] = $s[$a].wrapping_add($s[$b]);
+        $s[$d] = ($s[$d] ^ $s[$a]).rotate_left(8);
+        $s[$c] = $s[$c].wrapping_add($s[$d]);
+        $s[$b] = ($s[$b] ^ $s[$c]).rotate_left(7);
+    };
+}
+
+// The ChaCha20 block function (RFC 8439, section 2.3).
+fn chacha20_block(
+    key: &[u32; 8],
+    counter: u32,
+    nonce: &[u8; CHACHA20_POLY1305_NONCE_SIZE],
+    out: &mut [u8; BLOCK_LEN],
+) {
+    let mut input = [0_u32; 16];
+    input[..4].copy_from_slice(&SIGMA);
+    input[4..12].copy_from_slice(key);
+    input[12] = counter;
+    for (w, bytes) in input[13..].iter_mut().zip(nonce.chunks_exact(4)) {
+        *w = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+
+    let mut s = input;
+    for _ in 0..10 {
+        quarter_round!(s, 0, 4, 8, 12);
+        quarter_round!(s, 1, 5, 9, 13);
+        quarter_round!(s, 2, 6, 10, 14);
+        quarter_round!(s, 3, 7, 11, 15);
+        quarter_round!(s, 0, 5, 10, 15);
+        quarter_round!(s, 1, 6, 11, 12);
+        quarter_round!(s, 2, 7, 8, 13);
+        quarter_round!(s, 3, 4, 9, 14);
+    }
+
+    for ((o, s), i) in out.chunks_exact_mut(4).zip(s.iter()).zip(input.iter()) {
+        o.copy_from_slice(&s.wrapping_add(*i).to_le_bytes());
+    }
+    for w in s.iter_mut().chain(input.iter_mut()) {
+        unsafe { ptr::write_volatile(w, 0) };
+    }
+}
+
+// Poly1305 (RFC 8439, section 2.5) with 26 bit limbs. The AEAD only ever
+// authenticates whole 16 byte blocks, so there is no partial final block.
+struct Poly1305 {
+    r: [u32; 5],
+    h: [u32; 5],
+    pad: [u32; 4],
+}
+
+impl Poly1305 {
+    fn new(key: &[u8]) -> Poly1305 {
+        let le = |i: usize| u32::from_le_bytes([key[i], key[i + 1], key[i + 2], key[i + 3]]);
+        Poly1305 {
+            // r with the bits required by the clamping cleared
+            r: [
+                le(0) & 0x03ff_ffff,
+                (le(3) >> 2) & 0x03ff_ff03,
+                (le(6) >> 4) & 0x03ff_c0ff,
+                (le(9) >> 6) & 0x03f0_3fff,
+                (le(12) >> 8) & 0x000f_ffff,
+            ],
+            h: [0; 5],
+            pad: [le(16), le(20), le(24), le(28)],
+        }
+    }
+
+    fn update_padded(&mut self, data: &[u8]) {
+        for chunk in data.chunks(16) {
+            let mut block = [0_u8; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.update_block(&block);
+            zeroize(&mut block);
+        }
+    }
+
+    fn update_block(&mut self, m: &[u8; 16]) {
+        let le = |i: usize| u32::from_le_bytes([m[i], m[i + 1], m[i + 2], m[i + 3]]);
+        let [r0, r1, r2, r3, r4] = self.r;
+        let (s1, s2, s3, s4) = (r1 * 5, r2 * 5, r3 * 5, r4 * 5);
+
+        // h += m, with the 2^128 bit of a full block
+        let h0 = self.h[0] + (le(0) & 0x03ff_ffff);
+        let h1 = self.h[1] + ((le(3) >> 2) & 0x03ff_ffff);
+        let h2 = self.h[2] + ((le(6) >> 4) & 0x03ff_ffff);
+        let h3 = self.h[3] + ((le(9) >> 6) & 0x03ff_ffff);
+        let h4 = self.h[4] + ((le(12) >> 8) | (1 << 24));
+
+        // h *= r mod 2^130 - 5
+        let m = |a: u32, b: u32| u64::from(a) * u64::from(b);
+        let d0 = m(h0, r0) + m(h1, s4) + m(h2, s3) + m(h3, s2) + m(h4, s1);
+        let mut d1 = m(h0, r1) + m(h1, r0) + m(h2, s4) + m(h3, s3) + m(h4, s2);
+        let mut d2 = m(h0, r2) + m(h1, r1) + m(h2, r0) + m(h3, s4) + m(h4, s3);
+        let mut d3 = m(h0, r3) + m(h1, r2) + m(h2, r1) + m(h3, r0) + m(h4, s4);
+        let mut d4 = m(h0, r4) + m(h1, r3) + m(h2, r2) + m(h3, r1) + m(h4, r0);
+
+        d1 += d0 >> 26;
+        d2 += d1 >> 26;
+        d3 += d2 >> 26;
+        d4 += d3 >> 26;
+        let mut h0 = (d0 as u32 & 0x03ff_ffff) + (d4 >> 26) as u32 * 5;
+        let h1 = (d1 as u32 & 0x03ff_ffff) + (h0 >> 26);
+        h0 &= 0x03ff_ffff;
+        self.h = [
+            h0,
+            h1,
+            d2 as u32 & 0x03ff_ffff,
+            d3 as u32 & 0x03ff_ffff,
+            d4 as u32 & 0x03ff_ffff,
+        ];
+    }
+
+    fn finish(mut self) -> [u8; 16] {
+        // fully carry h
+        let [mut h0, mut h1, mut h2, mut h3, mut h4] = self.h;
+        h2 += h1 >> 26;
+        h1 &= 0x03ff_ffff;
+        h3 += h2 >> 26;
+        h2 &= 0x03ff_ffff;
+        h4 += h3 >> 26;
+        h3 &= 0x03ff_ffff;
+        h0 += (h4 >> 26) * 5;
+        h4 &= 0x03ff_ffff;
+        h1 += h0 >> 26;
+        h0 &= 0x03ff_ffff;
+
+        // g = h + 5 - 2^130; use it if it does not underflow, i.e. h >= p
+        let mut g0 = h0 + 5;
+        let mut g1 = h1 + (g0 >> 26);
+        g0 &= 0x03ff_ffff;
+        let mut g2 = h2 + (g1 >> 26);
+        g1 &= 0x03ff_ffff;
+        let mut g3 = h3 + (g2 >> 26);
+        g2 &= 0x03ff_ffff;
+        let g4 = (h4 + (g3 >> 26)).wrapping_sub(1 << 26);
+        g3 &= 0x03ff_ffff;
+
+        let use_g = (g4 >> 31).wrapping_sub(1);
+        h0 = (h0 & !use_g) | (g0 & use_g);
+        h1 = (h1 & !use_g) | (g1 & use_g);
+        h2 = (h2 & !use_g) | (g2 & use_g);
+        h3 = (h3 & !use_g) | (g3 & use_g);
+        h4 = (h4 & !use_g) | (g4 & use_g);
+
+        // h + pad mod 2^128
+        let words = [
+            h0 | (h1 << 26),
+            (h1 >> 6) | (h2 << 20),
+            (h2 >> 12) | (h3 << 14),
+            (h3 >> 18) | (h4 << 8),
+        ];
+        let mut tag = [0_u8; 16];
+        let mut carry = 0_u64;
+        for ((out, w), p) in tag.chunks_exact_mut(4).zip(words.iter()).zip(self.pad.iter()) {
+            let f = u64::from(*w) + u64::from(*p) + carry;
+            out.copy_from_slice(&(f as u32).to_le_bytes());
+            carry = f >> 32;
+        }
+
+        for w in self.r.iter_mut().chain(self.h.iter_mut()).chain(self.pad.iter_mut()) {
+            unsafe { ptr::write_volatile(w, 0) };
+        }
+        compiler_fence(Ordering::SeqCst);
+        tag
+    }
+}
@@ -18,6 +18,7 @@
 //!
 //! Cryptographic Functions
 //!
+use alloc::vec::Vec;
 use core::cell::{Cell, RefCell};
 use core::mem;
 use core::ops::{DerefMut, Drop};
@@ -2367,6 +2368,7 @@ fn rsgx_ecdsa_verify_hash(
 pub struct SgxEccHandle {
     handle: RefCell<sgx_ecc_state_handle_t>,
     initflag: Cell<bool>,
+    use_naive_batch: Cell<bool>,
 }
 
 impl SgxEccHandle {
@@ -2377,6 +2379,7 @@ impl SgxEccHandle {
         SgxEccHandle {
             handle: RefCell::new(ptr::null_mut() as sgx_ecc_state_handle_t),
             initflag: Cell::new(false),
+            use_naive_batch: Cell::new(true),
         }
     }
 
@@ -2991,6 +2994,97 @@ impl SgxEccHandle {
         }
     }
 
+    ///
+    /// ecdsa_batch_verify_slice verifies a batch of digital signatures, each with its own public key
+    /// and dataset.
+    ///
+    /// # Description
+    ///
+    /// This function returns true only if every signature in the batch is valid. How the batch is
+    /// verified is selected with set_use_naive_batch:
+    ///
+    /// * naive, the default: every signature is verified with ecdsa_verify_slice, stopping at the
+    ///   first one which is not valid, so the time taken depends on the position of that signature.
+    /// * batched: every dataset is hashed first, then every signature is verified against its hash
+    ///   with ecdsa_verify_hash. All signatures are checked whatever the outcome, so the time taken
+    ///   does not reveal which one is not valid.
+    ///
+    /// The underlying cryptography library does not provide a batch verification algorithm.
+    ///
+    /// The elliptic curve domain parameters must be created by function: open.
+    ///
+    /// # Parameters
+    ///
+    /// **batch**
+    ///
+    /// The public key, signed dataset and signature of every item to verify.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// **true**
+    ///
+    /// All digital signatures are valid, or the batch is empty.
+    ///
+    /// **false**
+    ///
+    /// At least one digital signature is not valid.
+    ///
+    /// # Errors
+    ///
+    /// The errors are those of ecdsa_verify_slice.
+    ///
+    pub fn ecdsa_batch_verify_slice(
+        &self,
+        batch: &[(sgx_ec256_public_t, &[u8], sgx_ec256_signature_t)],
+    ) -> SgxResult<bool> {
+        if !self.initflag.get() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+
+        if self.use_naive_batch.get() {
+            for (public, data, signature) in batch {
+                if !self.ecdsa_verify_slice(data, public, signature)? {
+                    return Ok(false);
+                }
+            }
+            return Ok(true);
+        }
+
+        let hashes = batch
+            .iter()
+            .map(|(_, data, _)| rsgx_sha256_slice(data))
+            .collect::<SgxResult<Vec<sgx_sha256_hash_t>>>()?;
+        let mut valid = true;
+        for ((public, _, signature), hash) in batch.iter().zip(hashes.iter()) {
+            valid &= self.ecdsa_verify_hash(hash, public, signature)?;
+        }
+        Ok(valid)
+    }
+
+    ///
+    /// set_use_naive_batch selects how ecdsa_batch_verify_slice verifies a batch.
+    ///
+    /// # Description
+    ///
+    /// When true, which is the default, the batch is verified one signature at a time and
+    /// verification stops at the first invalid signature. When false, the datasets are hashed up
+    /// front and every signature is checked. The result is the same either way.
+    ///
+    pub fn set_use_naive_batch(&self, naive: bool) {
+        self.use_naive_batch.set(naive);
+    }
+
+    ///
+    /// use_naive_batch returns whether ecdsa_batch_verify_slice verifies every signature on its own.
+    ///
+    pub fn use_naive_batch(&self) -> bool {
+        self.use_naive_batch.get()
+    }
+
     ///
     /// close cleans up and deallocates the ECC 256 GF(p) state that was allocated in function open.
     ///
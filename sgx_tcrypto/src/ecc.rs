@@ -0,0 +1,1451 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//!
+//! Elliptic curve keys
+//!
+//! `EcKeyPair` holds a NIST P-256 key of the Intel SGX cryptography library
+//! and converts it to and from PKCS#8.
+//!
+//! The library only provides NIST P-256, so X25519 (elliptic curve
+//! Diffie-Hellman over Curve25519) is implemented here as the constant-time
+//! Montgomery ladder of RFC 7748, and Ed25519 signatures as defined in
+//! RFC 8032 over the same field arithmetic.
+//!
+use crate::crypto::{rsgx_ecc256_pub_from_priv, SgxEccHandle};
+use crate::util::{ct_eq, zeroize, Zeroize};
+use crate::sha::{Digest, Sha512, SHA512_HASH_SIZE};
+use core::fmt;
+use core::ptr;
+use sgx_types::marker::ContiguousMemory;
+use sgx_types::*;
+
+/// The length of X25519 scalars, public keys and shared secrets.
+pub const X25519_KEY_SIZE: usize = 32;
+
+///
+/// An X25519 public key, the little-endian u-coordinate of a point on
+/// Curve25519.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct X25519PublicKey([u8; X25519_KEY_SIZE]);
+
+impl X25519PublicKey {
+    pub fn from_bytes(bytes: [u8; X25519_KEY_SIZE]) -> X25519PublicKey {
+        X25519PublicKey(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; X25519_KEY_SIZE] {
+        &self.0
+    }
+}
+
+impl From<[u8; X25519_KEY_SIZE]> for X25519PublicKey {
+    fn from(bytes: [u8; X25519_KEY_SIZE]) -> X25519PublicKey {
+        X25519PublicKey(bytes)
+    }
+}
+
+///
+/// The output of `X25519KeyPair::diffie_hellman`.
+///
+/// The secret is not uniformly random and should be passed through a key
+/// derivation function before use. It is erased when dropped.
+///
+pub struct X25519SharedSecret([u8; X25519_KEY_SIZE]);
+
+impl X25519SharedSecret {
+    pub fn as_bytes(&self) -> &[u8; X25519_KEY_SIZE] {
+        &self.0
+    }
+
+    ///
+    /// was_contributory returns false if the secret is all zeros, i.e. the peer
+    /// public key was a point of small order and the secret does not depend
+    /// on our private key. Protocols which require contributory behaviour
+    /// must abort in that case.
+    ///
+    pub fn was_contributory(&self) -> bool {
+        self.0.iter().fold(0_u8, |acc, b| acc | *b) != 0
+    }
+}
+
+impl Zeroize for X25519SharedSecret {
+    fn zeroize(&mut self) {
+        zeroize(&mut self.0);
+    }
+}
+
+impl Drop for X25519SharedSecret {
+    fn drop(&mut self) {
+        Zeroize::zeroize(self);
+    }
+}
+
+impl fmt::Debug for X25519SharedSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("X25519SharedSecret").finish_non_exhaustive()
+    }
+}
+
+///
+/// An X25519 private scalar together with its public key.
+///
+/// The scalar is erased when the key pair is dropped.
+///
+pub struct X25519KeyPair {
+    scalar: [u8; X25519_KEY_SIZE],
+    public: X25519PublicKey,
+}
+
+impl X25519KeyPair {
+    ///
+    /// generate creates a key pair from a random scalar.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_trts.a
+    ///
+    /// # Return value
+    ///
+    /// The new key pair.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// Reading random numbers failed.
+    ///
+    pub fn generate() -> SgxResult<X25519KeyPair> {
+        X25519KeyPair::generate_with_rng(|scalar| {
+            let ret = unsafe { sgx_read_rand(scalar.as_mut_ptr(), scalar.len()) };
+            match ret {
+                sgx_status_t::SGX_SUCCESS => Ok(()),
+                _ => Err(ret),
+            }
+        })
+    }
+
+    ///
+    /// generate_with_rng creates a key pair from a scalar drawn from `fill`.
+    ///
+    /// # Parameters
+    ///
+    /// **fill**
+    ///
+    /// Fills the 32 byte scalar with random bytes, for example from an
+    /// `sgx_rand::SgxRng` or a `prng::CtrDrbg`.
+    ///
+    /// # Return value
+    ///
+    /// The new key pair.
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by `fill`.
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `fill` produced an all-zero scalar.
+    ///
+    pub fn generate_with_rng<F>(fill: F) -> SgxResult<X25519KeyPair>
+    where
+        F: FnOnce(&mut [u8]) -> SgxError,
+    {
+        let mut scalar = [0_u8; X25519_KEY_SIZE];
+        let pair = fill(&mut scalar).and_then(|()| X25519KeyPair::from_scalar(&scalar));
+        zeroize(&mut scalar);
+        pair
+    }
+
+    ///
+    /// from_scalar creates a key pair from an existing private scalar.
+    ///
+    /// # Parameters
+    ///
+    /// **scalar**
+    ///
+    /// The private scalar. It is clamped as described in RFC 7748, so any
+    /// 32 random bytes are a valid scalar.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The scalar is all zeros.
+    ///
+    pub fn from_scalar(scalar: &[u8; X25519_KEY_SIZE]) -> SgxResult<X25519KeyPair> {
+        if scalar.iter().fold(0_u8, |acc, b| acc | *b) == 0 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut pair = X25519KeyPair {
+            scalar: *scalar,
+            public: X25519PublicKey([0_u8; X25519_KEY_SIZE]),
+        };
+        let mut base = [0_u8; X25519_KEY_SIZE];
+        base[0] = 9;
+        pair.public = X25519PublicKey(x25519(&pair.scalar, &base));
+        Ok(pair)
+    }
+
+    pub fn public_key(&self) -> &X25519PublicKey {
+        &self.public
+    }
+
+    ///
+    /// diffie_hellman computes the secret shared with the owner of `peer_public`.
+    ///
+    /// Any 32 bytes are accepted as a public key. Use
+    /// `X25519SharedSecret::was_contributory` to reject small order points.
+    ///
+    pub fn diffie_hellman(&self, peer_public: &X25519PublicKey) -> X25519SharedSecret {
+        X25519SharedSecret(x25519(&self.scalar, &peer_public.0))
+    }
+}
+
+impl Drop for X25519KeyPair {
+    fn drop(&mut self) {
+        zeroize(&mut self.scalar);
+    }
+}
+
+impl fmt::Debug for X25519KeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("X25519KeyPair")
+            .field("public", &self.public)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The length of Ed25519 seeds and public keys.
+pub const ED25519_KEY_SIZE: usize = 32;
+/// The length of Ed25519 signatures.
+pub const ED25519_SIGNATURE_SIZE: usize = 64;
+
+///
+/// An Ed25519 signature, the encoded point R followed by the scalar S.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Ed25519Signature([u8; ED25519_SIGNATURE_SIZE]);
+
+impl Ed25519Signature {
+    pub fn from_bytes(bytes: [u8; ED25519_SIGNATURE_SIZE]) -> Ed25519Signature {
+        Ed25519Signature(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; ED25519_SIGNATURE_SIZE] {
+        &self.0
+    }
+}
+
+impl From<[u8; ED25519_SIGNATURE_SIZE]> for Ed25519Signature {
+    fn from(bytes: [u8; ED25519_SIGNATURE_SIZE]) -> Ed25519Signature {
+        Ed25519Signature(bytes)
+    }
+}
+
+///
+/// An Ed25519 public key, used to verify signatures.
+///
+#[derive(Clone, Copy)]
+pub struct Ed25519VerifyKey {
+    bytes: [u8; ED25519_KEY_SIZE],
+    point: EdPoint,
+}
+
+impl Ed25519VerifyKey {
+    ///
+    /// from_bytes decodes a public key.
+    ///
+    /// # Parameters
+    ///
+    /// **bytes**
+    ///
+    /// The encoded point, as defined in RFC 8032, section 5.1.2.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The bytes are not the canonical encoding of a point on the curve.
+    ///
+    pub fn from_bytes(bytes: &[u8; ED25519_KEY_SIZE]) -> SgxResult<Ed25519VerifyKey> {
+        let point = EdPoint::decompress(bytes).ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        Ok(Ed25519VerifyKey {
+            bytes: *bytes,
+            point,
+        })
+    }
+
+    pub fn as_bytes(&self) -> &[u8; ED25519_KEY_SIZE] {
+        &self.bytes
+    }
+
+    ///
+    /// verify checks a signature of `message` made with this key.
+    ///
+    /// The check runs in constant time and accepts only canonical
+    /// signatures, those whose S is less than the group order.
+    ///
+    /// # Parameters
+    ///
+    /// **message**
+    ///
+    /// The signed message.
+    ///
+    /// **signature**
+    ///
+    /// The signature to verify.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// **true**
+    ///
+    /// The signature is valid.
+    ///
+    /// **false**
+    ///
+    /// The signature is invalid.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The hash calculation failed.
+    ///
+    pub fn verify(&self, message: &[u8], signature: &Ed25519Signature) -> SgxResult<bool> {
+        let (r, s) = signature.0.split_at(32);
+        let s = match Scalar::from_canonical_bytes(s) {
+            Some(s) => s,
+            None => return Ok(false),
+        };
+        let k = Scalar::from_bytes_wide(&sha512(&[r, &self.bytes, message])?);
+
+        // R == [S]B - [k]A, compared in encoded form
+        let check = EdPoint::BASE
+            .mul(&s.to_bytes())
+            .add(&self.point.neg().mul(&k.to_bytes()));
+        Ok(ct_eq(&check.compress(), r))
+    }
+}
+
+impl PartialEq for Ed25519VerifyKey {
+    fn eq(&self, other: &Ed25519VerifyKey) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl Eq for Ed25519VerifyKey {}
+
+impl fmt::Debug for Ed25519VerifyKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Ed25519VerifyKey").field(&self.bytes).finish()
+    }
+}
+
+///
+/// An Ed25519 private key together with its public key.
+///
+/// The private key is erased when the key pair is dropped.
+///
+pub struct Ed25519KeyPair {
+    // the two halves of SHA-512(seed): the clamped secret scalar and the
+    // prefix used to derive nonces
+    scalar: [u8; 32],
+    prefix: [u8; 32],
+    verify_key: Ed25519VerifyKey,
+}
+
+impl Ed25519KeyPair {
+    ///
+    /// generate creates a key pair from a random seed.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_trts.a
+    ///
+    /// # Return value
+    ///
+    /// The new key pair.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// Reading random numbers failed.
+    ///
+    pub fn generate() -> SgxResult<Ed25519KeyPair> {
+        let mut seed = [0_u8; ED25519_KEY_SIZE];
+        let ret = unsafe { sgx_read_rand(seed.as_mut_ptr(), seed.len()) };
+        if ret != sgx_status_t::SGX_SUCCESS {
+            return Err(ret);
+        }
+        let pair = Ed25519KeyPair::from_seed(&seed);
+        zeroize(&mut seed);
+        pair
+    }
+
+    ///
+    /// from_seed creates a key pair from an existing private key.
+    ///
+    /// # Parameters
+    ///
+    /// **seed**
+    ///
+    /// The 32 byte private key of RFC 8032. Any 32 random bytes are a
+    /// valid private key.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The hash calculation failed.
+    ///
+    pub fn from_seed(seed: &[u8; ED25519_KEY_SIZE]) -> SgxResult<Ed25519KeyPair> {
+        let mut h = sha512(&[seed])?;
+        let mut pair = Ed25519KeyPair {
+            scalar: [0_u8; 32],
+            prefix: [0_u8; 32],
+            verify_key: Ed25519VerifyKey {
+                bytes: [0_u8; ED25519_KEY_SIZE],
+                point: EdPoint::IDENTITY,
+            },
+        };
+        pair.scalar.copy_from_slice(&h[..32]);
+        pair.prefix.copy_from_slice(&h[32..]);
+        zeroize(&mut h);
+        pair.scalar[0] &= 248;
+        pair.scalar[31] &= 127;
+        pair.scalar[31] |= 64;
+
+        let point = EdPoint::BASE.mul(&pair.scalar);
+        pair.verify_key = Ed25519VerifyKey {
+            bytes: point.compress(),
+            point,
+        };
+        Ok(pair)
+    }
+
+    pub fn verify_key(&self) -> &Ed25519VerifyKey {
+        &self.verify_key
+    }
+
+    ///
+    /// sign signs `message` as defined in RFC 8032, section 5.1.6.
+    ///
+    /// Signing is deterministic: the same key and message always give the
+    /// same signature.
+    ///
+    /// # Parameters
+    ///
+    /// **message**
+    ///
+    /// The message to sign.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// The signature.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The hash calculation failed.
+    ///
+    pub fn sign(&self, message: &[u8]) -> SgxResult<Ed25519Signature> {
+        let mut h = sha512(&[&self.prefix, message])?;
+        let r = Scalar::from_bytes_wide(&h);
+        zeroize(&mut h);
+        let mut r_bytes = r.to_bytes();
+        let big_r = EdPoint::BASE.mul(&r_bytes).compress();
+        zeroize(&mut r_bytes);
+
+        let k = Scalar::from_bytes_wide(&sha512(&[&big_r, &self.verify_key.bytes, message])?);
+        let a = Scalar::from_bytes_mod_order(&self.scalar);
+        let s = r.add(&k.mul(&a));
+
+        let mut signature = [0_u8; ED25519_SIGNATURE_SIZE];
+        signature[..32].copy_from_slice(&big_r);
+        signature[32..].copy_from_slice(&s.to_bytes());
+        Ok(Ed25519Signature(signature))
+    }
+}
+
+impl Drop for Ed25519KeyPair {
+    fn drop(&mut self) {
+        zeroize(&mut self.scalar);
+        zeroize(&mut self.prefix);
+    }
+}
+
+impl fmt::Debug for Ed25519KeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ed25519KeyPair")
+            .field("verify_key", &self.verify_key)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The length of the PKCS#8 encoding produced by `EcKeyPair::to_pkcs8_der`.
+pub const EC_PKCS8_DER_SIZE: usize = 138;
+
+// id-ecPublicKey (1.2.840.10045.2.1)
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+// prime256v1 (1.2.840.10045.3.1.7)
+const OID_PRIME256V1: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+
+// OneAsymmetricKey { version 0, ecPublicKey/prime256v1, ECPrivateKey {
+// version 1, privateKey, publicKey [1] } } up to the private key.
+const PKCS8_PRIVATE_KEY_PREFIX: [u8; 36] = [
+    0x30, 0x81, 0x87, 0x02, 0x01, 0x00, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02,
+    0x01, 0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x04, 0x6d, 0x30, 0x6b, 0x02,
+    0x01, 0x01, 0x04, 0x20,
+];
+// The [1] tag of the public key up to the uncompressed point.
+const PKCS8_PUBLIC_KEY_PREFIX: [u8; 6] = [0xa1, 0x44, 0x03, 0x42, 0x00, 0x04];
+
+/// Errors returned by `EcKeyPair::from_pkcs8_der`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pkcs8Error {
+    /// The input is not a DER encoded `OneAsymmetricKey` holding an
+    /// `ECPrivateKey`.
+    InvalidEncoding,
+    /// The key is not an elliptic curve key on NIST P-256.
+    UnsupportedAlgorithm,
+    /// The private key is out of range, or does not match the public key
+    /// stored with it.
+    InvalidKey,
+}
+
+impl fmt::Display for Pkcs8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match *self {
+            Pkcs8Error::InvalidEncoding => "malformed PKCS#8 private key",
+            Pkcs8Error::UnsupportedAlgorithm => "the private key is not a P-256 key",
+            Pkcs8Error::InvalidKey => "invalid P-256 private key",
+        })
+    }
+}
+
+///
+/// A NIST P-256 private key together with its public key, in the
+/// little-endian layout of the Intel SGX cryptography library.
+///
+/// The private key is erased when the key pair is dropped.
+///
+pub struct EcKeyPair {
+    private: sgx_ec256_private_t,
+    public: sgx_ec256_public_t,
+}
+
+impl EcKeyPair {
+    ///
+    /// generate creates a random key pair.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// The new key pair.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_OUT_OF_MEMORY**
+    ///
+    /// Not enough memory is available to complete this operation.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The key creation process failed due to an internal cryptography library failure.
+    ///
+    pub fn generate() -> SgxResult<EcKeyPair> {
+        let handle = SgxEccHandle::new();
+        handle.open()?;
+        let (private, public) = handle.create_key_pair()?;
+        Ok(EcKeyPair { private, public })
+    }
+
+    ///
+    /// from_private_key creates a key pair from an existing private key.
+    ///
+    /// # Parameters
+    ///
+    /// **private**
+    ///
+    /// The private key, in little-endian byte order.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The private key is not in the range [1, n - 1].
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// Computing the public key failed due to an internal cryptography library failure.
+    ///
+    pub fn from_private_key(private: &sgx_ec256_private_t) -> SgxResult<EcKeyPair> {
+        let public = rsgx_ecc256_pub_from_priv(private)?;
+        Ok(EcKeyPair {
+            private: *private,
+            public,
+        })
+    }
+
+    pub fn private_key(&self) -> &sgx_ec256_private_t {
+        &self.private
+    }
+
+    pub fn public_key(&self) -> &sgx_ec256_public_t {
+        &self.public
+    }
+
+    ///
+    /// sign_slice computes an ECDSA signature over `data` with the private key.
+    ///
+    /// The signature is verified with `SgxEccHandle::ecdsa_verify_slice` and
+    /// `public_key`.
+    ///
+    pub fn sign_slice<T>(&self, data: &[T]) -> SgxResult<sgx_ec256_signature_t>
+    where
+        T: Copy + ContiguousMemory,
+    {
+        let handle = SgxEccHandle::new();
+        handle.open()?;
+        handle.ecdsa_sign_slice(data, &self.private)
+    }
+
+    ///
+    /// to_pkcs8_der encodes the key pair as a DER `OneAsymmetricKey` (RFC 5958)
+    /// holding an `ECPrivateKey` (RFC 5915) with the public key.
+    ///
+    /// The encoding is the one produced by `openssl pkcs8 -topk8 -nocrypt`. It
+    /// contains the private key in the clear and should be sealed before it
+    /// leaves the enclave.
+    ///
+    pub fn to_pkcs8_der(&self) -> [u8; EC_PKCS8_DER_SIZE] {
+        let mut der = [0_u8; EC_PKCS8_DER_SIZE];
+        let (prefix, rest) = der.split_at_mut(PKCS8_PRIVATE_KEY_PREFIX.len());
+        prefix.copy_from_slice(&PKCS8_PRIVATE_KEY_PREFIX);
+        let (private, rest) = rest.split_at_mut(SGX_ECP256_KEY_SIZE);
+        copy_reversed(private, &self.private.r);
+        let (prefix, public) = rest.split_at_mut(PKCS8_PUBLIC_KEY_PREFIX.len());
+        prefix.copy_from_slice(&PKCS8_PUBLIC_KEY_PREFIX);
+        let (x, y) = public.split_at_mut(SGX_ECP256_KEY_SIZE);
+        copy_reversed(x, &self.public.gx);
+        copy_reversed(y, &self.public.gy);
+        der
+    }
+
+    ///
+    /// from_pkcs8_der decodes a key pair encoded by `to_pkcs8_der` or by
+    /// other PKCS#8 implementations.
+    ///
+    /// # Parameters
+    ///
+    /// **bytes**
+    ///
+    /// An unencrypted PKCS#8 version 1 or 2 encoding of a P-256 key. The
+    /// public key is optional; if it is present it must match the private key.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Errors
+    ///
+    /// **Pkcs8Error::InvalidEncoding**
+    ///
+    /// `bytes` is not valid DER or has the wrong structure.
+    ///
+    /// **Pkcs8Error::UnsupportedAlgorithm**
+    ///
+    /// The key is not a P-256 key.
+    ///
+    /// **Pkcs8Error::InvalidKey**
+    ///
+    /// The private key is out of range or does not match the public key.
+    ///
+    pub fn from_pkcs8_der(bytes: &[u8]) -> Result<EcKeyPair, Pkcs8Error> {
+        let (private, public) = parse_pkcs8(bytes)?;
+
+        let mut key = sgx_ec256_private_t::default();
+        copy_reversed(&mut key.r, private);
+        let pair = EcKeyPair::from_private_key(&key);
+        zeroize(&mut key.r);
+        let pair = pair.map_err(|_| Pkcs8Error::InvalidKey)?;
+
+        if let Some(public) = public {
+            let (x, y) = public.split_at(SGX_ECP256_KEY_SIZE);
+            if !x.iter().eq(pair.public.gx.iter().rev())
+                || !y.iter().eq(pair.public.gy.iter().rev())
+            {
+                return Err(Pkcs8Error::InvalidKey);
+            }
+        }
+        Ok(pair)
+    }
+}
+
+impl Drop for EcKeyPair {
+    fn drop(&mut self) {
+        zeroize(&mut self.private.r);
+    }
+}
+
+impl fmt::Debug for EcKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EcKeyPair")
+            .field("gx", &self.public.gx)
+            .field("gy", &self.public.gy)
+            .finish_non_exhaustive()
+    }
+}
+
+fn copy_reversed(dst: &mut [u8], src: &[u8]) {
+    for (d, s) in dst.iter_mut().zip(src.iter().rev()) {
+        *d = *s;
+    }
+}
+
+// Returns the big-endian private key and, if present, the uncompressed
+// public point without its leading 0x04.
+fn parse_pkcs8(bytes: &[u8]) -> Result<(&[u8], Option<&[u8]>), Pkcs8Error> {
+    let mut der = Der(bytes);
+    let mut info = Der(der.read(TAG_SEQUENCE)?);
+    der.finish()?;
+
+    let version = info.read(TAG_INTEGER)?;
+    if version != [0] && version != [1] {
+        return Err(Pkcs8Error::InvalidEncoding);
+    }
+    let mut algorithm = Der(info.read(TAG_SEQUENCE)?);
+    if algorithm.read(TAG_OID)? != OID_EC_PUBLIC_KEY {
+        return Err(Pkcs8Error::UnsupportedAlgorithm);
+    }
+    if algorithm.read(TAG_OID)? != OID_PRIME256V1 {
+        return Err(Pkcs8Error::UnsupportedAlgorithm);
+    }
+    algorithm.finish()?;
+    let mut ec_key = Der(info.read(TAG_OCTET_STRING)?);
+    // attributes [0] and, in version 2, publicKey [1] are not needed
+    info.skip(0xa0)?;
+    if version == [1] {
+        info.skip(0x81)?;
+    }
+    info.finish()?;
+
+    let mut ec_key = Der(ec_key.read(TAG_SEQUENCE)?);
+    if ec_key.read(TAG_INTEGER)? != [1] {
+        return Err(Pkcs8Error::InvalidEncoding);
+    }
+    let private = ec_key.read(TAG_OCTET_STRING)?;
+    if private.len() != SGX_ECP256_KEY_SIZE {
+        return Err(Pkcs8Error::InvalidKey);
+    }
+    if ec_key.peek() == Some(0xa0) {
+        let mut parameters = Der(ec_key.read(0xa0)?);
+        if parameters.read(TAG_OID)? != OID_PRIME256V1 {
+            return Err(Pkcs8Error::UnsupportedAlgorithm);
+        }
+        parameters.finish()?;
+    }
+    let mut public = None;
+    if ec_key.peek() == Some(0xa1) {
+        let mut field = Der(ec_key.read(0xa1)?);
+        let point = field.read(TAG_BIT_STRING)?;
+        field.finish()?;
+        match point.split_first() {
+            Some((&0, point)) if point.len() == 2 * SGX_ECP256_KEY_SIZE + 1 && point[0] == 0x04 => {
+                public = Some(&point[1..])
+            }
+            _ => return Err(Pkcs8Error::InvalidKey),
+        }
+    }
+    ec_key.finish()?;
+    Ok((private, public))
+}
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+
+// A reader of the DER elements in a byte string.
+struct Der<'a>(&'a [u8]);
+
+impl<'a> Der<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.0.first().copied()
+    }
+
+    // Reads an element with the given tag and returns its contents.
+    fn read(&mut self, tag: u8) -> Result<&'a [u8], Pkcs8Error> {
+        let input = self.0;
+        if input.len() < 2 || input[0] != tag {
+            return Err(Pkcs8Error::InvalidEncoding);
+        }
+        let (len, header) = match input[1] {
+            n if n < 0x80 => (n as usize, 2),
+            0x81 if input.len() >= 3 && input[2] >= 0x80 => (input[2] as usize, 3),
+            0x82 if input.len() >= 4 && input[2] != 0 => {
+                ((input[2] as usize) << 8 | input[3] as usize, 4)
+            }
+            _ => return Err(Pkcs8Error::InvalidEncoding),
+        };
+        if input.len() - header < len {
+            return Err(Pkcs8Error::InvalidEncoding);
+        }
+        let (contents, rest) = input[header..].split_at(len);
+        self.0 = rest;
+        Ok(contents)
+    }
+
+    // Skips an optional element with the given tag.
+    fn skip(&mut self, tag: u8) -> Result<(), Pkcs8Error> {
+        if self.peek() == Some(tag) {
+            self.read(tag)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&self) -> Result<(), Pkcs8Error> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(Pkcs8Error::InvalidEncoding)
+        }
+    }
+}
+
+// The X25519 function of RFC 7748, section 5.
+fn x25519(scalar: &[u8; X25519_KEY_SIZE], u: &[u8; X25519_KEY_SIZE]) -> [u8; X25519_KEY_SIZE] {
+    let mut k = *scalar;
+    k[0] &= 248;
+    k[31] &= 127;
+    k[31] |= 64;
+
+    let x1 = Fe::from_bytes(u);
+    let mut x2 = Fe::ONE;
+    let mut z2 = Fe::ZERO;
+    let mut x3 = x1;
+    let mut z3 = Fe::ONE;
+    let mut swap = 0_u64;
+
+    for t in (0..255).rev() {
+        let bit = ((k[t / 8] >> (t % 8)) & 1) as u64;
+        swap ^= bit;
+        Fe::cswap(swap, &mut x2, &mut x3);
+        Fe::cswap(swap, &mut z2, &mut z3);
+        swap = bit;
+
+        let a = x2.add(&z2);
+        let aa = a.square();
+        let b = x2.sub(&z2);
+        let bb = b.square();
+        let e = aa.sub(&bb);
+        let c = x3.add(&z3);
+        let d = x3.sub(&z3);
+        let da = d.mul(&a);
+        let cb = c.mul(&b);
+        x3 = da.add(&cb).square();
+        z3 = x1.mul(&da.sub(&cb).square());
+        x2 = aa.mul(&bb);
+        z2 = e.mul(&aa.add(&e.mul_small(121665)));
+    }
+    Fe::cswap(swap, &mut x2, &mut x3);
+    Fe::cswap(swap, &mut z2, &mut z3);
+    zeroize(&mut k);
+
+    x2.mul(&z2.invert()).to_bytes()
+}
+
+// An element of GF(2^255 - 19) in five 51-bit limbs.
+#[derive(Clone, Copy)]
+struct Fe([u64; 5]);
+
+const LOW_51_BITS: u64 = (1 << 51) - 1;
+
+impl Fe {
+    const ZERO: Fe = Fe([0; 5]);
+    const ONE: Fe = Fe([1, 0, 0, 0, 0]);
+
+    fn from_bytes(bytes: &[u8; 32]) -> Fe {
+        let load = |i: usize| {
+            let mut word = [0_u8; 8];
+            word.copy_from_slice(&bytes[i..i + 8]);
+            u64::from_le_bytes(word)
+        };
+        // the top bit is ignored
+        Fe([
+            load(0) & LOW_51_BITS,
+            (load(6) >> 3) & LOW_51_BITS,
+            (load(12) >> 6) & LOW_51_BITS,
+            (load(19) >> 1) & LOW_51_BITS,
+            (load(24) >> 12) & LOW_51_BITS,
+        ])
+    }
+
+    fn to_bytes(self) -> [u8; 32] {
+        let mut h = self.carry();
+        // h < 2^255 + 2^13; subtract p if h >= p
+        let mut q = (h.0[0] + 19) >> 51;
+        q = (h.0[1] + q) >> 51;
+        q = (h.0[2] + q) >> 51;
+        q = (h.0[3] + q) >> 51;
+        q = (h.0[4] + q) >> 51;
+        h.0[0] += 19 * q;
+        for i in 0..4 {
+            h.0[i + 1] += h.0[i] >> 51;
+            h.0[i] &= LOW_51_BITS;
+        }
+        h.0[4] &= LOW_51_BITS;
+
+        let words = [
+            h.0[0] | (h.0[1] << 51),
+            (h.0[1] >> 13) | (h.0[2] << 38),
+            (h.0[2] >> 26) | (h.0[3] << 25),
+            (h.0[3] >> 39) | (h.0[4] << 12),
+        ];
+        let mut bytes = [0_u8; 32];
+        for (chunk, word) in bytes.chunks_mut(8).zip(words.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    // Brings every limb below 2^51, except for a small excess in limb 0.
+    fn carry(&self) -> Fe {
+        let mut h = self.0;
+        for i in 0..4 {
+            h[i + 1] += h[i] >> 51;
+            h[i] &= LOW_51_BITS;
+        }
+        h[0] += 19 * (h[4] >> 51);
+        h[4] &= LOW_51_BITS;
+        Fe(h)
+    }
+
+    fn add(&self, other: &Fe) -> Fe {
+        let mut h = [0_u64; 5];
+        for (i, limb) in h.iter_mut().enumerate() {
+            *limb = self.0[i] + other.0[i];
+        }
+        Fe(h).carry()
+    }
+
+    fn sub(&self, other: &Fe) -> Fe {
+        // add 2p so the limbs cannot underflow
+        const TWO_P: [u64; 5] = [
+            0xf_ffff_ffff_ffda,
+            0xf_ffff_ffff_fffe,
+            0xf_ffff_ffff_fffe,
+            0xf_ffff_ffff_fffe,
+            0xf_ffff_ffff_fffe,
+        ];
+        let mut h = [0_u64; 5];
+        for (i, limb) in h.iter_mut().enumerate() {
+            *limb = self.0[i] + TWO_P[i] - other.0[i];
+        }
+        Fe(h).carry()
+    }
+
+    fn mul(&self, other: &Fe) -> Fe {
+        let a = self.0.map(u128::from);
+        let b = other.0.map(u128::from);
+        let b19 = [b[0], b[1] * 19, b[2] * 19, b[3] * 19, b[4] * 19];
+
+        let r = [
+            a[0] * b[0] + a[1] * b19[4] + a[2] * b19[3] + a[3] * b19[2] + a[4] * b19[1],
+            a[0] * b[1] + a[1] * b[0] + a[2] * b19[4] + a[3] * b19[3] + a[4] * b19[2],
+            a[0] * b[2] + a[1] * b[1] + a[2] * b[0] + a[3] * b19[4] + a[4] * b19[3],
+            a[0] * b[3] + a[1] * b[2] + a[2] * b[1] + a[3] * b[0] + a[4] * b19[4],
+            a[0] * b[4] + a[1] * b[3] + a[2] * b[2] + a[3] * b[1] + a[4] * b[0],
+        ];
+        Fe::reduce_wide(r)
+    }
+
+    fn square(&self) -> Fe {
+        self.mul(self)
+    }
+
+    fn mul_small(&self, n: u32) -> Fe {
+        Fe::reduce_wide(self.0.map(|limb| u128::from(limb) * u128::from(n)))
+    }
+
+    fn reduce_wide(mut r: [u128; 5]) -> Fe {
+        for i in 0..4 {
+            r[i + 1] += r[i] >> 51;
+            r[i] &= LOW_51_BITS as u128;
+        }
+        let mut h = r.map(|limb| limb as u64);
+        h[4] &= LOW_51_BITS;
+        // the carry out of the top limb can exceed 64 bits
+        let top = (r[4] >> 51) * 19 + u128::from(h[0]);
+        h[0] = (top as u64) & LOW_51_BITS;
+        h[1] += (top >> 51) as u64;
+        Fe(h)
+    }
+
+    fn pow2k(&self, k: u32) -> Fe {
+        let mut h = *self;
+        for _ in 0..k {
+            h = h.square();
+        }
+        h
+    }
+
+    // Returns (z^(2^250 - 1), z^11), shared by invert and pow_p58.
+    fn pow_2_250_1(&self) -> (Fe, Fe) {
+        let z2 = self.square();
+        let z9 = z2.pow2k(2).mul(self);
+        let z11 = z9.mul(&z2);
+        let z_5_0 = z11.square().mul(&z9);
+        let z_10_0 = z_5_0.pow2k(5).mul(&z_5_0);
+        let z_20_0 = z_10_0.pow2k(10).mul(&z_10_0);
+        let z_40_0 = z_20_0.pow2k(20).mul(&z_20_0);
+        let z_50_0 = z_40_0.pow2k(10).mul(&z_10_0);
+        let z_100_0 = z_50_0.pow2k(50).mul(&z_50_0);
+        let z_200_0 = z_100_0.pow2k(100).mul(&z_100_0);
+        (z_200_0.pow2k(50).mul(&z_50_0), z11)
+    }
+
+    // z^(p - 2)
+    fn invert(&self) -> Fe {
+        let (z_250_0, z11) = self.pow_2_250_1();
+        z_250_0.pow2k(5).mul(&z11)
+    }
+
+    // z^((p - 5) / 8), used to compute square roots
+    fn pow_p58(&self) -> Fe {
+        let (z_250_0, _) = self.pow_2_250_1();
+        z_250_0.pow2k(2).mul(self)
+    }
+
+    // Swaps `a` and `b` if `swap` is 1, in constant time.
+    fn cswap(swap: u64, a: &mut Fe, b: &mut Fe) {
+        let mask = 0_u64.wrapping_sub(swap);
+        for i in 0..5 {
+            let t = mask & (a.0[i] ^ b.0[i]);
+            a.0[i] ^= t;
+            b.0[i] ^= t;
+        }
+    }
+
+    fn neg(&self) -> Fe {
+        Fe::ZERO.sub(self)
+    }
+
+    // Returns 1 if the canonical encoding is odd, the sign of RFC 8032.
+    fn is_negative(&self) -> u8 {
+        self.to_bytes()[0] & 1
+    }
+
+    fn ct_eq(&self, other: &Fe) -> bool {
+        ct_eq(&self.to_bytes(), &other.to_bytes())
+    }
+
+    // Replaces `self` with `other` if `choice` is 1, in constant time.
+    fn cmov(&mut self, other: &Fe, choice: u64) {
+        let mask = 0_u64.wrapping_sub(choice);
+        for i in 0..5 {
+            self.0[i] ^= mask & (self.0[i] ^ other.0[i]);
+        }
+    }
+}
+
+// -121665 / 121666, the constant d of edwards25519
+const ED_D: Fe = Fe([
+    0x0003_4dca_1359_78a3,
+    0x0001_a828_3b15_6ebd,
+    0x0005_e7a2_6001_c029,
+    0x0007_39c6_63a0_3cbb,
+    0x0005_2036_cee2_b6ff,
+]);
+
+const ED_D2: Fe = Fe([
+    0x0006_9b94_26b2_f159,
+    0x0003_5050_762a_dd7a,
+    0x0003_cf44_c003_8052,
+    0x0006_738c_c740_7977,
+    0x0002_406d_9dc5_6dff,
+]);
+
+const SQRT_M1: Fe = Fe([
+    0x0006_1b27_4a0e_a0b0,
+    0x0000_d5a5_fc8f_189d,
+    0x0007_ef5e_9cbd_0c60,
+    0x0007_8595_a680_4c9e,
+    0x0002_b832_4804_fc1d,
+]);
+
+// A point on edwards25519 in extended coordinates, x = X/Z, y = Y/Z and
+// xy = T/Z.
+#[derive(Clone, Copy)]
+struct EdPoint {
+    x: Fe,
+    y: Fe,
+    z: Fe,
+    t: Fe,
+}
+
+impl EdPoint {
+    const IDENTITY: EdPoint = EdPoint {
+        x: Fe::ZERO,
+        y: Fe::ONE,
+        z: Fe::ONE,
+        t: Fe::ZERO,
+    };
+
+    // The base point B of RFC 8032, section 5.1.
+    const BASE: EdPoint = EdPoint {
+        x: Fe([
+            0x0006_2d60_8f25_d51a,
+            0x0004_12a4_b4f6_592a,
+            0x0007_5b71_71a4_b31d,
+            0x0001_ff60_5271_18fe,
+            0x0002_1693_6d3c_d6e5,
+        ]),
+        y: Fe([
+            0x0006_6666_6666_6658,
+            0x0004_cccc_cccc_cccc,
+            0x0001_9999_9999_9999,
+            0x0003_3333_3333_3333,
+            0x0006_6666_6666_6666,
+        ]),
+        z: Fe::ONE,
+        t: Fe([
+            0x0006_8ab3_a5b7_dda3,
+            0x0000_0eea_2a5e_adbb,
+            0x0002_af8d_f483_c27e,
+            0x0003_32b3_7527_4732,
+            0x0006_7875_f0fd_78b7,
+        ]),
+    };
+
+    // Point decoding of RFC 8032, section 5.1.3.
+    fn decompress(bytes: &[u8; 32]) -> Option<EdPoint> {
+        let y = Fe::from_bytes(bytes);
+        let sign = bytes[31] >> 7;
+        let mut canonical = y.to_bytes();
+        canonical[31] |= sign << 7;
+        if canonical != *bytes {
+            return None;
+        }
+
+        // x^2 = u / v
+        let yy = y.square();
+        let u = yy.sub(&Fe::ONE);
+        let v = yy.mul(&ED_D).add(&Fe::ONE);
+        let v3 = v.square().mul(&v);
+        let v7 = v3.square().mul(&v);
+        let mut x = u.mul(&v3).mul(&u.mul(&v7).pow_p58());
+        let vxx = v.mul(&x.square());
+        if !vxx.ct_eq(&u) {
+            if !vxx.ct_eq(&u.neg()) {
+                return None;
+            }
+            x = x.mul(&SQRT_M1);
+        }
+
+        if x.is_negative() != sign {
+            if x.ct_eq(&Fe::ZERO) {
+                return None;
+            }
+            x = x.neg();
+        }
+        Some(EdPoint {
+            x,
+            y,
+            z: Fe::ONE,
+            t: x.mul(&y),
+        })
+    }
+
+    fn compress(&self) -> [u8; 32] {
+        let zinv = self.z.invert();
+        let x = self.x.mul(&zinv);
+        let mut bytes = self.y.mul(&zinv).to_bytes();
+        bytes[31] ^= x.is_negative() << 7;
+        bytes
+    }
+
+    fn neg(&self) -> EdPoint {
+        EdPoint {
+            x: self.x.neg(),
+            y: self.y,
+            z: self.z,
+            t: self.t.neg(),
+        }
+    }
+
+    // The complete addition formula of Hisil, Wong, Carter and Dawson,
+    // which also handles doubling and the identity.
+    fn add(&self, other: &EdPoint) -> EdPoint {
+        let a = self.y.sub(&self.x).mul(&other.y.sub(&other.x));
+        let b = self.y.add(&self.x).mul(&other.y.add(&other.x));
+        let c = self.t.mul(&ED_D2).mul(&other.t);
+        let zz = self.z.mul(&other.z);
+        let d = zz.add(&zz);
+        let e = b.sub(&a);
+        let f = d.sub(&c);
+        let g = d.add(&c);
+        let h = b.add(&a);
+        EdPoint {
+            x: e.mul(&f),
+            y: g.mul(&h),
+            z: f.mul(&g),
+            t: e.mul(&h),
+        }
+    }
+
+    fn double(&self) -> EdPoint {
+        let a = self.x.square();
+        let b = self.y.square();
+        let zz = self.z.square();
+        let c = zz.add(&zz);
+        let h = a.add(&b);
+        let e = h.sub(&self.x.add(&self.y).square());
+        let g = a.sub(&b);
+        let f = c.add(&g);
+        EdPoint {
+            x: e.mul(&f),
+            y: g.mul(&h),
+            z: f.mul(&g),
+            t: e.mul(&h),
+        }
+    }
+
+    fn cmov(&mut self, other: &EdPoint, choice: u64) {
+        self.x.cmov(&other.x, choice);
+        self.y.cmov(&other.y, choice);
+        self.z.cmov(&other.z, choice);
+        self.t.cmov(&other.t, choice);
+    }
+
+    // Multiplies by a little-endian scalar with a fixed 4-bit window. Every
+    // table entry is read for every digit, so the memory access pattern
+    // does not depend on the scalar.
+    fn mul(&self, scalar: &[u8; 32]) -> EdPoint {
+        let mut table = [EdPoint::IDENTITY; 16];
+        for i in 1..16 {
+            table[i] = table[i - 1].add(self);
+        }
+
+        let mut q = EdPoint::IDENTITY;
+        for i in (0..64).rev() {
+            q = q.double().double().double().double();
+            let digit = (scalar[i / 2] >> (4 * (i % 2))) & 15;
+            let mut entry = EdPoint::IDENTITY;
+            for (j, point) in table.iter().enumerate() {
+                // 1 if j == digit, without branching
+                let choice = (u64::from(j as u8 ^ digit)).wrapping_sub(1) >> 63;
+                entry.cmov(point, choice);
+            }
+            q = q.add(&entry);
+        }
+        q
+    }
+}
+
+// The order of the edwards25519 base point, 2^252 +
+// 27742317777372353535851937790883648493.
+const ED_L: [u64; 4] = [
+    0x5812_631a_5cf5_d3ed,
+    0x14de_f9de_a2f7_9cd6,
+    0x0000_0000_0000_0000,
+    0x1000_0000_0000_0000,
+];
+
+// 2^512 mod L, to convert into the Montgomery domain
+const ED_R2: [u64; 4] = [
+    0xa406_11e3_449c_0f01,
+    0xd00e_1ba7_6885_9347,
+    0xceec_73d2_17f5_be65,
+    0x0399_411b_7c30_9a3d,
+];
+
+// -1 / L mod 2^64
+const ED_LFACTOR: u64 = 0xd2b5_1da3_1254_7e1b;
+
+// An integer modulo L in four little-endian 64-bit limbs, always reduced.
+struct Scalar([u64; 4]);
+
+impl Scalar {
+    fn from_bytes_mod_order(bytes: &[u8; 32]) -> Scalar {
+        let mut x = Scalar::load(bytes);
+        // x < 2^256 < 16L
+        for k in (0..4).rev() {
+            x = Scalar::sub_if_ge(&x, &Scalar::shl(&ED_L, k));
+        }
+        Scalar(x)
+    }
+
+    fn from_bytes_wide(bytes: &[u8; 64]) -> Scalar {
+        let mut half = [0_u8; 32];
+        half.copy_from_slice(&bytes[..32]);
+        let lo = Scalar::from_bytes_mod_order(&half);
+        half.copy_from_slice(&bytes[32..]);
+        let hi = Scalar::from_bytes_mod_order(&half);
+        zeroize(&mut half);
+        // lo + hi * 2^256
+        lo.add(&Scalar(Scalar::montgomery_mul(&hi.0, &ED_R2)))
+    }
+
+    fn from_canonical_bytes(bytes: &[u8]) -> Option<Scalar> {
+        let mut b = [0_u8; 32];
+        b.copy_from_slice(bytes);
+        let x = Scalar::from_bytes_mod_order(&b);
+        if ct_eq(&x.to_bytes(), bytes) {
+            Some(x)
+        } else {
+            None
+        }
+    }
+
+    fn to_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0_u8; 32];
+        for (chunk, limb) in bytes.chunks_mut(8).zip(self.0.iter()) {
+            chunk.copy_from_slice(&limb.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn add(&self, other: &Scalar) -> Scalar {
+        let mut sum = [0_u64; 4];
+        let mut carry = 0_u64;
+        for (i, limb) in sum.iter_mut().enumerate() {
+            let v = u128::from(self.0[i]) + u128::from(other.0[i]) + u128::from(carry);
+            *limb = v as u64;
+            carry = (v >> 64) as u64;
+        }
+        // both are below L < 2^253, so the sum cannot carry out
+        Scalar(Scalar::sub_if_ge(&sum, &ED_L))
+    }
+
+    fn mul(&self, other: &Scalar) -> Scalar {
+        let ab = Scalar::montgomery_mul(&self.0, &other.0);
+        Scalar(Scalar::montgomery_mul(&ab, &ED_R2))
+    }
+
+    fn load(bytes: &[u8; 32]) -> [u64; 4] {
+        let mut x = [0_u64; 4];
+        for (limb, chunk) in x.iter_mut().zip(bytes.chunks(8)) {
+            let mut word = [0_u8; 8];
+            word.copy_from_slice(chunk);
+            *limb = u64::from_le_bytes(word);
+        }
+        x
+    }
+
+    // Returns m * 2^k, for m * 2^k < 2^256.
+    fn shl(m: &[u64; 4], k: u32) -> [u64; 4] {
+        if k == 0 {
+            return *m;
+        }
+        let mut x = [0_u64; 4];
+        for i in 0..4 {
+            x[i] = m[i] << k;
+            if i > 0 {
+                x[i] |= m[i - 1] >> (64 - k);
+            }
+        }
+        x
+    }
+
+    // Returns x - m if x >= m and x otherwise, in constant time.
+    fn sub_if_ge(x: &[u64; 4], m: &[u64; 4]) -> [u64; 4] {
+        let mut diff = [0_u64; 4];
+        let mut borrow = 0_u64;
+        for i in 0..4 {
+            let (d, b1) = x[i].overflowing_sub(m[i]);
+            let (d, b2) = d.overflowing_sub(borrow);
+            diff[i] = d;
+            borrow = u64::from(b1 | b2);
+        }
+        // all ones if there was no borrow, i.e. x >= m
+        let mask = borrow.wrapping_sub(1);
+        let mut r = [0_u64; 4];
+        for i in 0..4 {
+            r[i] = x[i] ^ (mask & (x[i] ^ diff[i]));
+        }
+        r
+    }
+
+    // Returns a * b / 2^256 mod L for a, b < L.
+    fn montgomery_mul(a: &[u64; 4], b: &[u64; 4]) -> [u64; 4] {
+        let mut t = [0_u64; 6];
+        for &bi in b.iter() {
+            let mut carry = 0_u128;
+            for j in 0..4 {
+                let v = u128::from(t[j]) + u128::from(a[j]) * u128::from(bi) + carry;
+                t[j] = v as u64;
+                carry = v >> 64;
+            }
+            let v = u128::from(t[4]) + carry;
+            t[4] = v as u64;
+            t[5] = (v >> 64) as u64;
+
+            // add a multiple of L that clears the low limb, then shift
+            let m = t[0].wrapping_mul(ED_LFACTOR);
+            let mut carry = (u128::from(t[0]) + u128::from(m) * u128::from(ED_L[0])) >> 64;
+            for j in 1..4 {
+                let v = u128::from(t[j]) + u128::from(m) * u128::from(ED_L[j]) + carry;
+                t[j - 1] = v as u64;
+                carry = v >> 64;
+            }
+            let v = u128::from(t[4]) + carry;
+            t[3] = v as u64;
+            t[4] = t[5] + (v >> 64) as u64;
+        }
+        // t < 2L, so t[4] is zero
+        Scalar::sub_if_ge(&[t[0], t[1], t[2], t[3]], &ED_L)
+    }
+}
+
+impl Drop for Scalar {
+    fn drop(&mut self) {
+        for limb in self.0.iter_mut() {
+            unsafe { ptr::write_volatile(limb, 0) };
+        }
+    }
+}
+
+fn sha512(parts: &[&[u8]]) -> SgxResult<[u8; SHA512_HASH_SIZE]> {
+    let mut hash = Sha512::new()?;
+    for part in parts {
+        hash.update(part)?;
+    }
+    hash.finalize()
+}
+
@@ -0,0 +1,327 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+//!
+//! ECIES hybrid encryption over NIST P-256
+//!
+//! A fresh ephemeral key pair is generated for every message. The ECDH shared
+//! secret between the ephemeral key and the recipient key is passed through
+//! HKDF-SHA256 to derive an AES-128-GCM key, which encrypts the message. The
+//! HKDF info binds both public keys, so a ciphertext can not be replayed to a
+//! different recipient.
+//!
+use crate::crypto::{rsgx_rijndael128GCM_decrypt, rsgx_rijndael128GCM_encrypt, SgxEccHandle};
+use crate::ecc::EcKeyPair;
+use crate::hmac::Sha256;
+use crate::kdf::Hkdf;
+use crate::util::zeroize;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem;
+use sgx_types::*;
+
+const ECIES_INFO_LABEL: &[u8] = b"SGX-ECIES-P256-HKDF-SHA256-AES128GCM";
+
+const PUBLIC_KEY_SIZE: usize = 2 * SGX_ECP256_KEY_SIZE;
+
+/// The length of the header that `EciesCiphertext::to_vec` puts in front
+/// of the encrypted message: the ephemeral public key, the IV and the tag.
+pub const ECIES_OVERHEAD: usize = PUBLIC_KEY_SIZE + SGX_AESGCM_IV_SIZE + SGX_AESGCM_MAC_SIZE;
+
+///
+/// ECIES encryption to a P-256 public key, built from ECDH, HKDF-SHA256 and
+/// AES-128-GCM.
+///
+pub struct Ecies;
+
+impl Ecies {
+    ///
+    /// encrypt encrypts `plaintext` to the owner of `recipient_pub`, and
+    /// authenticates `aad`.
+    ///
+    /// # Parameters
+    ///
+    /// **recipient_pub**
+    ///
+    /// The public key of the recipient, in little-endian byte order.
+    ///
+    /// **plaintext**
+    ///
+    /// The message to encrypt.
+    ///
+    /// **aad**
+    ///
+    /// Additional data that is authenticated but not encrypted. The same
+    /// data must be passed to `decrypt`.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// The ephemeral public key, IV, ciphertext and tag.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `recipient_pub` is not a point on the curve, or `plaintext` or `aad`
+    /// is too large.
+    ///
+    /// **SGX_ERROR_OUT_OF_MEMORY**
+    ///
+    /// Not enough memory is available to complete this operation.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The encryption failed due to an internal cryptography library failure.
+    ///
+    pub fn encrypt(
+        recipient_pub: &sgx_ec256_public_t,
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> SgxResult<EciesCiphertext> {
+        let ephemeral = EcKeyPair::generate()?;
+        let mut key = derive_key(
+            ephemeral.private_key(),
+            recipient_pub,
+            ephemeral.public_key(),
+            recipient_pub,
+        )?;
+
+        let mut iv = [0_u8; SGX_AESGCM_IV_SIZE];
+        let ret = unsafe { sgx_read_rand(iv.as_mut_ptr(), iv.len()) };
+        if ret != sgx_status_t::SGX_SUCCESS {
+            zeroize(&mut key);
+            return Err(ret);
+        }
+
+        let mut ciphertext = vec![0_u8; plaintext.len()];
+        let mut tag = sgx_aes_gcm_128bit_tag_t::default();
+        let result =
+            rsgx_rijndael128GCM_encrypt(&key, plaintext, &iv, aad, &mut ciphertext, &mut tag);
+        zeroize(&mut key);
+        result?;
+
+        Ok(EciesCiphertext {
+            ephemeral_public: *ephemeral.public_key(),
+            iv,
+            ciphertext,
+            tag,
+        })
+    }
+
+    ///
+    /// decrypt verifies and decrypts a message that was encrypted to
+    /// `recipient`.
+    ///
+    /// # Parameters
+    ///
+    /// **recipient**
+    ///
+    /// The key pair of the recipient.
+    ///
+    /// **ciphertext**
+    ///
+    /// The output of `encrypt`.
+    ///
+    /// **aad**
+    ///
+    /// The additional data that was passed to `encrypt`.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// The decrypted message.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The ephemeral public key is not a point on the curve, or the
+    /// ciphertext or `aad` is too large.
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The ciphertext, tag or `aad` was modified, or the message was not
+    /// encrypted to `recipient`.
+    ///
+    /// **SGX_ERROR_OUT_OF_MEMORY**
+    ///
+    /// Not enough memory is available to complete this operation.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The decryption failed due to an internal cryptography library failure.
+    ///
+    pub fn decrypt(
+        recipient: &EcKeyPair,
+        ciphertext: &EciesCiphertext,
+        aad: &[u8],
+    ) -> SgxResult<Vec<u8>> {
+        let mut key = derive_key(
+            recipient.private_key(),
+            &ciphertext.ephemeral_public,
+            &ciphertext.ephemeral_public,
+            recipient.public_key(),
+        )?;
+
+        let mut plaintext = vec![0_u8; ciphertext.ciphertext.len()];
+        let result = rsgx_rijndael128GCM_decrypt(
+            &key,
+            &ciphertext.ciphertext,
+            &ciphertext.iv,
+            aad,
+            &ciphertext.tag,
+            &mut plaintext,
+        );
+        zeroize(&mut key);
+        if let Err(e) = result {
+            zeroize(&mut plaintext);
+            return Err(e);
+        }
+        Ok(plaintext)
+    }
+}
+
+///
+/// An ECIES encrypted message.
+///
+/// `to_vec` and `from_slice` use the layout ephemeral public key (64 bytes,
+/// `gx || gy` in little-endian byte order) || IV (12 bytes) || tag (16 bytes)
+/// || ciphertext.
+///
+#[derive(Clone, Default)]
+pub struct EciesCiphertext {
+    ephemeral_public: sgx_ec256_public_t,
+    iv: [u8; SGX_AESGCM_IV_SIZE],
+    ciphertext: Vec<u8>,
+    tag: sgx_aes_gcm_128bit_tag_t,
+}
+
+impl EciesCiphertext {
+    pub fn ephemeral_public(&self) -> &sgx_ec256_public_t {
+        &self.ephemeral_public
+    }
+
+    pub fn iv(&self) -> &[u8; SGX_AESGCM_IV_SIZE] {
+        &self.iv
+    }
+
+    pub fn ciphertext(&self) -> &[u8] {
+        &self.ciphertext
+    }
+
+    pub fn tag(&self) -> &sgx_aes_gcm_128bit_tag_t {
+        &self.tag
+    }
+
+    ///
+    /// to_vec serializes the message.
+    ///
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(ECIES_OVERHEAD + self.ciphertext.len());
+        out.extend_from_slice(&self.ephemeral_public.gx);
+        out.extend_from_slice(&self.ephemeral_public.gy);
+        out.extend_from_slice(&self.iv);
+        out.extend_from_slice(&self.tag);
+        out.extend_from_slice(&self.ciphertext);
+        out
+    }
+
+    ///
+    /// from_slice parses a message serialized by `to_vec`.
+    ///
+    /// The ephemeral public key is only checked by `Ecies::decrypt`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `bytes` is shorter than `ECIES_OVERHEAD`.
+    ///
+    pub fn from_slice(bytes: &[u8]) -> SgxResult<EciesCiphertext> {
+        if bytes.len() < ECIES_OVERHEAD {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let (gx, rest) = bytes.split_at(SGX_ECP256_KEY_SIZE);
+        let (gy, rest) = rest.split_at(SGX_ECP256_KEY_SIZE);
+        let (iv, rest) = rest.split_at(SGX_AESGCM_IV_SIZE);
+        let (tag, ciphertext) = rest.split_at(SGX_AESGCM_MAC_SIZE);
+
+        let mut msg = EciesCiphertext::default();
+        msg.ephemeral_public.gx.copy_from_slice(gx);
+        msg.ephemeral_public.gy.copy_from_slice(gy);
+        msg.iv.copy_from_slice(iv);
+        msg.tag.copy_from_slice(tag);
+        msg.ciphertext = ciphertext.to_vec();
+        Ok(msg)
+    }
+}
+
+impl fmt::Debug for EciesCiphertext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EciesCiphertext")
+            .field("gx", &self.ephemeral_public.gx)
+            .field("gy", &self.ephemeral_public.gy)
+            .field("iv", &self.iv)
+            .field("tag", &self.tag)
+            .field("ciphertext_len", &self.ciphertext.len())
+            .finish()
+    }
+}
+
+// Both sides derive the key from the shared secret and the two public keys,
+// in the order ephemeral key, recipient key.
+fn derive_key(
+    private: &sgx_ec256_private_t,
+    peer_public: &sgx_ec256_public_t,
+    ephemeral_public: &sgx_ec256_public_t,
+    recipient_public: &sgx_ec256_public_t,
+) -> SgxResult<sgx_aes_gcm_128bit_key_t> {
+    let handle = SgxEccHandle::new();
+    handle.open()?;
+    if !handle.check_point(peer_public)? {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    let mut shared = handle.compute_shared_dhkey(private, peer_public)?;
+    let prk = Hkdf::<Sha256>::extract(None, &shared.s);
+    zeroize(&mut shared.s);
+    let prk = prk?;
+
+    let mut info = [0_u8; ECIES_INFO_LABEL.len() + 2 * PUBLIC_KEY_SIZE];
+    let (label, keys) = info.split_at_mut(ECIES_INFO_LABEL.len());
+    label.copy_from_slice(ECIES_INFO_LABEL);
+    let keys_iter = keys.chunks_exact_mut(SGX_ECP256_KEY_SIZE);
+    let parts = [
+        &ephemeral_public.gx,
+        &ephemeral_public.gy,
+        &recipient_public.gx,
+        &recipient_public.gy,
+    ];
+    for (dst, src) in keys_iter.zip(parts) {
+        dst.copy_from_slice(src);
+    }
+
+    let mut key: sgx_aes_gcm_128bit_key_t = [0_u8; mem::size_of::<sgx_aes_gcm_128bit_key_t>()];
+    prk.expand(&info, &mut key)?;
+    Ok(key)
+}
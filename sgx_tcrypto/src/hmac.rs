@@ -0,0 +1,275 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+//!
+//! Keyed-hash message authentication code (HMAC)
+//!
+use crate::util::zeroize;
+use crate::util::ct_eq;
+pub use crate::sha::{Digest, Sha1, Sha256, Sha384, Sha512};
+use core::fmt;
+use sgx_types::*;
+
+// The largest block size of the supported hash functions, the rate of
+// SHA3-256.
+const MAX_BLOCK_SIZE: usize = 136;
+
+///
+/// HMAC as defined in RFC 2104, over any hash function implementing
+/// `Digest`.
+///
+/// `Hmac<Sha256>`, `Hmac<Sha384>` and `Hmac<Sha512>` produce 32, 48 and
+/// 64 byte tags respectively; `sha3::Sha3_256` and `sha3::Sha3_512` work
+/// the same way. `Hmac<Sha1>` is only meant for existing protocols. The
+/// keyed state is erased when the object is dropped.
+///
+pub struct Hmac<H: Digest> {
+    inner: Option<H>,
+    // the key XORed with the outer padding, used by finalize
+    opad: [u8; MAX_BLOCK_SIZE],
+}
+
+impl<H, const N: usize> Hmac<H>
+where
+    H: Digest<Output = [u8; N]>,
+{
+    ///
+    /// new creates an HMAC state keyed with `key`.
+    ///
+    /// # Parameters
+    ///
+    /// **key**
+    ///
+    /// The key. It may be of any length; keys longer than the block size
+    /// of the hash are hashed first, as the standard requires.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The key is too long for the hash function.
+    ///
+    /// **SGX_ERROR_OUT_OF_MEMORY**
+    ///
+    /// Not enough memory is available to complete this operation.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The hash calculation failed.
+    ///
+    pub fn new(key: &[u8]) -> SgxResult<Hmac<H>> {
+        let mut pad = [0_u8; MAX_BLOCK_SIZE];
+        let result = Self::with_pad(key, &mut pad);
+        zeroize(&mut pad);
+        result
+    }
+
+    fn with_pad(key: &[u8], pad: &mut [u8; MAX_BLOCK_SIZE]) -> SgxResult<Hmac<H>> {
+        let block = &mut pad[..H::BLOCK_SIZE];
+        if key.len() > H::BLOCK_SIZE {
+            let mut hash = digest::<H, N>(&[key])?;
+            block[..N].copy_from_slice(&hash);
+            zeroize(&mut hash);
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+
+        block.iter_mut().for_each(|b| *b ^= 0x36);
+        let mut inner = H::new()?;
+        inner.update(block)?;
+        // turn the ipad into the opad
+        block.iter_mut().for_each(|b| *b ^= 0x36 ^ 0x5c);
+
+        let mut opad = [0_u8; MAX_BLOCK_SIZE];
+        opad[..H::BLOCK_SIZE].copy_from_slice(block);
+        Ok(Hmac {
+            inner: Some(inner),
+            opad,
+        })
+    }
+
+    ///
+    /// update authenticates the next part of the message.
+    ///
+    /// # Parameters
+    ///
+    /// **data**
+    ///
+    /// The data to authenticate. It may be empty.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The data is too long for the hash function.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The hash calculation failed.
+    ///
+    pub fn update(&mut self, data: &[u8]) -> SgxError {
+        match self.inner {
+            Some(ref mut inner) => inner.update(data),
+            None => Err(sgx_status_t::SGX_ERROR_INVALID_STATE),
+        }
+    }
+
+    ///
+    /// finalize returns the tag of the whole message.
+    ///
+    /// # Return value
+    ///
+    /// The HMAC tag, `N` bytes long.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_OUT_OF_MEMORY**
+    ///
+    /// Not enough memory is available to complete this operation.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The hash calculation failed.
+    ///
+    pub fn finalize(mut self) -> SgxResult<HmacOutput<N>> {
+        let inner = self.inner.take().ok_or(sgx_status_t::SGX_ERROR_INVALID_STATE)?;
+        let mut inner_hash = inner.finalize()?;
+        let tag = digest::<H, N>(&[&self.opad[..H::BLOCK_SIZE], &inner_hash]);
+        zeroize(&mut inner_hash);
+        tag.map(HmacOutput)
+    }
+
+    ///
+    /// mac computes the tag of `data` under `key` in one call.
+    ///
+    /// # Parameters
+    ///
+    /// **key**
+    ///
+    /// The key. It may be of any length.
+    ///
+    /// **data**
+    ///
+    /// The data to authenticate.
+    ///
+    /// # Return value
+    ///
+    /// The HMAC tag, `N` bytes long.
+    ///
+    /// # Errors
+    ///
+    /// See `new` and `update`.
+    ///
+    pub fn mac(key: &[u8], data: &[u8]) -> SgxResult<HmacOutput<N>> {
+        let mut hmac = Self::new(key)?;
+        hmac.update(data)?;
+        hmac.finalize()
+    }
+}
+
+impl<H: Digest> Drop for Hmac<H> {
+    fn drop(&mut self) {
+        zeroize(&mut self.opad);
+    }
+}
+
+fn digest<H, const N: usize>(parts: &[&[u8]]) -> SgxResult<[u8; N]>
+where
+    H: Digest<Output = [u8; N]>,
+{
+    let mut hash = H::new()?;
+    for part in parts {
+        hash.update(part)?;
+    }
+    hash.finalize()
+}
+
+///
+/// An HMAC tag of `N` bytes.
+///
+/// Comparisons with `==` and `verify` take the same time wherever the
+/// tags differ, so the tag can be checked against an expected value
+/// without leaking how many leading bytes match.
+///
+#[derive(Clone, Copy)]
+pub struct HmacOutput<const N: usize>([u8; N]);
+
+impl<const N: usize> HmacOutput<N> {
+    ///
+    /// as_bytes returns the tag bytes.
+    ///
+    pub fn as_bytes(&self) -> &[u8; N] {
+        &self.0
+    }
+
+    ///
+    /// into_bytes returns the tag bytes by value.
+    ///
+    pub fn into_bytes(self) -> [u8; N] {
+        self.0
+    }
+
+    ///
+    /// verify checks, in constant time, whether `tag` equals this tag.
+    ///
+    /// # Return value
+    ///
+    /// **true**
+    ///
+    /// `tag` is exactly `N` bytes long and equal to this tag.
+    ///
+    /// **false**
+    ///
+    /// Otherwise.
+    ///
+    pub fn verify(&self, tag: &[u8]) -> bool {
+        ct_eq(&self.0, tag)
+    }
+}
+
+impl<const N: usize> PartialEq for HmacOutput<N> {
+    fn eq(&self, other: &HmacOutput<N>) -> bool {
+        self.verify(&other.0)
+    }
+}
+
+impl<const N: usize> Eq for HmacOutput<N> {}
+
+impl<const N: usize> AsRef<[u8]> for HmacOutput<N> {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> From<HmacOutput<N>> for [u8; N] {
+    fn from(tag: HmacOutput<N>) -> [u8; N] {
+        tag.0
+    }
+}
+
+impl<const N: usize> fmt::Debug for HmacOutput<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("HmacOutput(")?;
+        for b in self.0.iter() {
+            write!(f, "{:02x}", b)?;
+        }
+        f.write_str(")")
+    }
+}
@@ -0,0 +1,214 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+//!
+//! HMAC-based Extract-and-Expand Key Derivation Function (HKDF)
+//!
+pub use crate::argon2::{Argon2, Argon2Hash, Argon2Params};
+use crate::hmac::{Digest, Hmac};
+pub use crate::pbkdf2::Pbkdf2;
+use crate::util::zeroize;
+use core::marker::PhantomData;
+use sgx_types::*;
+
+// The largest output size of the supported hash functions.
+const MAX_HASH_SIZE: usize = 64;
+
+///
+/// HKDF as defined in RFC 5869, over HMAC with any hash function
+/// implementing `Digest`.
+///
+/// `extract` condenses the input keying material, for example an ECDH
+/// shared secret, into a pseudorandom key. `HkdfPrk::expand` then derives
+/// any number of independent keys from it, each bound to a different
+/// `info` string.
+///
+pub struct Hkdf<H: Digest> {
+    marker: PhantomData<H>,
+}
+
+impl<H, const N: usize> Hkdf<H>
+where
+    H: Digest<Output = [u8; N]>,
+{
+    ///
+    /// extract computes the pseudorandom key HMAC(salt, ikm).
+    ///
+    /// # Parameters
+    ///
+    /// **salt**
+    ///
+    /// An optional, ideally random, salt. `None` is the same as a string
+    /// of zeros as long as the hash output.
+    ///
+    /// **ikm**
+    ///
+    /// The input keying material.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// The pseudorandom key, to be expanded with `HkdfPrk::expand`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// An input is too long for the hash function.
+    ///
+    /// **SGX_ERROR_OUT_OF_MEMORY**
+    ///
+    /// Not enough memory is available to complete this operation.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The HMAC calculation failed.
+    ///
+    pub fn extract(salt: Option<&[u8]>, ikm: &[u8]) -> SgxResult<HkdfPrk<H>> {
+        let zeros = [0_u8; MAX_HASH_SIZE];
+        let salt = salt.unwrap_or(&zeros[..N]);
+        let mut prk = Hmac::<H>::mac(salt, ikm)?.into_bytes();
+        let result = HkdfPrk::from_prk(&prk);
+        zeroize(&mut prk);
+        result
+    }
+}
+
+///
+/// A pseudorandom key produced by `Hkdf::extract`.
+///
+/// The key is erased when the object is dropped.
+///
+pub struct HkdfPrk<H: Digest> {
+    prk: [u8; MAX_HASH_SIZE],
+    marker: PhantomData<H>,
+}
+
+impl<H, const N: usize> HkdfPrk<H>
+where
+    H: Digest<Output = [u8; N]>,
+{
+    ///
+    /// from_prk uses an existing pseudorandom key, skipping the extract
+    /// step.
+    ///
+    /// RFC 5869 allows this when the input keying material is already a
+    /// uniformly random key of the hash length.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `prk` is not exactly as long as the hash output.
+    ///
+    pub fn from_prk(prk: &[u8]) -> SgxResult<HkdfPrk<H>> {
+        if prk.len() != N {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let mut key = [0_u8; MAX_HASH_SIZE];
+        key[..N].copy_from_slice(prk);
+        Ok(HkdfPrk {
+            prk: key,
+            marker: PhantomData,
+        })
+    }
+
+    ///
+    /// as_bytes returns the pseudorandom key.
+    ///
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.prk[..N]
+    }
+
+    ///
+    /// expand fills `out` with key material bound to `info`.
+    ///
+    /// # Parameters
+    ///
+    /// **info**
+    ///
+    /// Context and application specific information, which should differ
+    /// for every key derived from the same pseudorandom key.
+    ///
+    /// **out**
+    ///
+    /// The output keying material, at most 255 times the hash output
+    /// size long.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `out` is longer than 255 times the hash output size.
+    ///
+    /// **SGX_ERROR_OUT_OF_MEMORY**
+    ///
+    /// Not enough memory is available to complete this operation.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The HMAC calculation failed.
+    ///
+    pub fn expand(&self, info: &[u8], out: &mut [u8]) -> SgxError {
+        if out.len() > 255 * N {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut t = [0_u8; MAX_HASH_SIZE];
+        let mut t_len = 0;
+        let mut result = Ok(());
+        for (i, block) in out.chunks_mut(N).enumerate() {
+            // T(i) = HMAC(PRK, T(i - 1) | info | i), where T(0) is empty
+            let next = Hmac::<H>::new(self.as_bytes()).and_then(|mut hmac| {
+                if t_len > 0 {
+                    hmac.update(&t[..t_len])?;
+                }
+                hmac.update(info)?;
+                hmac.update(&[i as u8 + 1])?;
+                hmac.finalize()
+            });
+            match next {
+                Ok(next) => {
+                    t[..N].copy_from_slice(next.as_bytes());
+                    t_len = N;
+                    block.copy_from_slice(&t[..block.len()]);
+                }
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+        zeroize(&mut t);
+        if result.is_err() {
+            zeroize(out);
+        }
+        result
+    }
+}
+
+impl<H: Digest> Drop for HkdfPrk<H> {
+    fn drop(&mut self) {
+        zeroize(&mut self.prk);
+    }
+}
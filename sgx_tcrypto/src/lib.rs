@@ -27,7 +27,29 @@
 #![allow(non_snake_case)]
 #![allow(clippy::too_many_arguments)]
 
+extern crate alloc;
+
+extern crate sgx_trts;
 extern crate sgx_types;
 
 mod crypto;
 pub use self::crypto::*;
+
+pub mod aead;
+pub mod aes;
+pub mod argon2;
+pub mod blake2;
+pub mod blake3;
+pub mod chacha20poly1305;
+pub mod ecc;
+pub mod ecies;
+pub mod hmac;
+pub mod kdf;
+pub mod pbkdf2;
+pub mod prng;
+pub mod rsa;
+pub mod sha;
+pub mod sha3;
+pub mod threshold;
+pub mod util;
+pub mod x509;
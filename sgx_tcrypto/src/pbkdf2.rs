@@ -0,0 +1,146 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//!
+//! Password-Based Key Derivation Function 2 (PBKDF2)
+//!
+use crate::hmac::{Digest, Hmac, Sha256, Sha384};
+use crate::util::zeroize;
+use core::marker::PhantomData;
+use sgx_types::*;
+
+/// The minimum iteration count accepted by `derive`, as recommended for
+/// PBKDF2-HMAC-SHA256 by OWASP following NIST SP 800-132.
+pub const PBKDF2_MIN_ITERATIONS: u32 = 600_000;
+
+/// The maximum length of a derived key.
+pub const PBKDF2_MAX_OUTPUT_LEN: usize = 64;
+
+///
+/// PBKDF2 as defined in RFC 8018, with `Hmac<H>` as the pseudorandom
+/// function, for any hash function implementing `Digest`.
+///
+/// `Pbkdf2<Sha256>`, the default, and `Pbkdf2<Sha512>` are the usual
+/// choices for turning a user password into a key, for example one that
+/// unlocks sealed data. `Pbkdf2<Sha1>` exists to check keys derived by
+/// existing systems, such as those of RFC 6070, and should not be used
+/// for new keys.
+///
+pub struct Pbkdf2<H: Digest = Sha256> {
+    marker: PhantomData<H>,
+}
+
+/// PBKDF2-HMAC-SHA256.
+pub type Pbkdf2Sha256 = Pbkdf2<Sha256>;
+
+/// PBKDF2-HMAC-SHA384.
+pub type Pbkdf2Sha384 = Pbkdf2<Sha384>;
+
+impl<H, const N: usize> Pbkdf2<H>
+where
+    H: Digest<Output = [u8; N]>,
+{
+    ///
+    /// derive fills `out` with a key derived from `password` and `salt`.
+    ///
+    /// # Parameters
+    ///
+    /// **password**
+    ///
+    /// The password, used as the HMAC key.
+    ///
+    /// **salt**
+    ///
+    /// The salt, which should be at least 16 random bytes.
+    ///
+    /// **iterations**
+    ///
+    /// The iteration count, at least `PBKDF2_MIN_ITERATIONS`.
+    ///
+    /// **out**
+    ///
+    /// The derived key, 1 to `PBKDF2_MAX_OUTPUT_LEN` bytes.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The iteration count is too low, or the length of `out` is out of range.
+    ///
+    /// **SGX_ERROR_OUT_OF_MEMORY**
+    ///
+    /// Not enough memory is available to complete this operation.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The HMAC calculation failed.
+    ///
+    pub fn derive(password: &[u8], salt: &[u8], iterations: u32, out: &mut [u8]) -> SgxError {
+        if iterations < PBKDF2_MIN_ITERATIONS {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        Self::derive_unchecked(password, salt, iterations, out)
+    }
+
+    ///
+    /// derive_unchecked is `derive` without the lower bound on the iteration
+    /// count.
+    ///
+    /// It is meant for known-answer tests and for verifying keys derived by
+    /// existing systems. New keys should be derived with `derive`.
+    ///
+    pub fn derive_unchecked(password: &[u8], salt: &[u8], iterations: u32, out: &mut [u8]) -> SgxError {
+        if iterations == 0 || out.is_empty() || out.len() > PBKDF2_MAX_OUTPUT_LEN {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        for (i, block) in out.chunks_mut(N).enumerate() {
+            let index = (i as u32 + 1).to_be_bytes();
+            let mut u = Self::prf(password, &[salt, &index])?;
+            let mut t = u;
+            for _ in 1..iterations {
+                let next = Self::prf(password, &[&u]);
+                zeroize(&mut u);
+                u = match next {
+                    Ok(next) => next,
+                    Err(e) => {
+                        zeroize(&mut t);
+                        return Err(e);
+                    }
+                };
+                t.iter_mut().zip(u.iter()).for_each(|(t, u)| *t ^= *u);
+            }
+            block.copy_from_slice(&t[..block.len()]);
+            zeroize(&mut u);
+            zeroize(&mut t);
+        }
+        Ok(())
+    }
+
+    // HMAC(key, parts[0] || parts[1] || ...)
+    fn prf(key: &[u8], parts: &[&[u8]]) -> SgxResult<[u8; N]> {
+        let mut hmac = Hmac::<H>::new(key)?;
+        for part in parts {
+            hmac.update(part)?;
+        }
+        hmac.finalize().map(|mac| mac.into_bytes())
+    }
+}
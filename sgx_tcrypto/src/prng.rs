@@ -0,0 +1,318 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//!
+//! Deterministic Random Bit Generators (DRBG)
+//!
+use crate::util::zeroize;
+use core::arch::x86_64::*;
+use core::fmt;
+use sgx_types::*;
+
+/// The length of the seed material, entropy input and maximum additional
+/// input of `CtrDrbg`: the AES-256 key length plus the block length.
+pub const CTR_DRBG_SEED_LEN: usize = 48;
+
+/// The maximum number of bytes returned by one call to `CtrDrbg::generate`,
+/// i.e. 2^19 bits.
+pub const CTR_DRBG_MAX_REQUEST_LEN: usize = 1 << 16;
+
+/// The maximum number of `CtrDrbg::generate` calls between reseeds.
+pub const CTR_DRBG_RESEED_INTERVAL: u64 = 1 << 48;
+
+const KEY_LEN: usize = 32;
+const BLOCK_LEN: usize = 16;
+
+/// Errors returned by `CtrDrbg::generate`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrbgError {
+    /// `CTR_DRBG_RESEED_INTERVAL` requests have been served since the last
+    /// reseed; `reseed` must be called before generating more output.
+    ReseedRequired,
+    /// More than `CTR_DRBG_MAX_REQUEST_LEN` bytes were requested.
+    RequestTooLarge,
+    /// The additional input is longer than `CTR_DRBG_SEED_LEN` bytes.
+    AdditionalInputTooLong,
+}
+
+impl fmt::Display for DrbgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match *self {
+            DrbgError::ReseedRequired => "the DRBG must be reseeded",
+            DrbgError::RequestTooLarge => "too many bytes requested from the DRBG",
+            DrbgError::AdditionalInputTooLong => "the additional input is too long",
+        })
+    }
+}
+
+///
+/// CTR_DRBG using AES-256 without a derivation function, as defined in
+/// NIST SP 800-90A Rev. 1, section 10.2.1.
+///
+/// Unlike `sgx_read_rand`, the output is fully determined by the seed
+/// material, which must come from an approved entropy source. Prediction
+/// resistance is provided by calling `reseed` with fresh entropy.
+///
+/// The internal state is erased when the DRBG is dropped.
+///
+pub struct CtrDrbg {
+    key: [u8; KEY_LEN],
+    v: [u8; BLOCK_LEN],
+    reseed_counter: u64,
+}
+
+impl CtrDrbg {
+    ///
+    /// instantiate creates a DRBG from `seed_material`.
+    ///
+    /// # Parameters
+    ///
+    /// **seed_material**
+    ///
+    /// The entropy input XOR the personalization string, exactly
+    /// `CTR_DRBG_SEED_LEN` bytes. Without a derivation function the entropy
+    /// input must be full entropy.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// The instantiated DRBG.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The length of `seed_material` is not `CTR_DRBG_SEED_LEN`.
+    ///
+    pub fn instantiate(seed_material: &[u8]) -> SgxResult<CtrDrbg> {
+        if seed_material.len() != CTR_DRBG_SEED_LEN {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut drbg = CtrDrbg {
+            key: [0_u8; KEY_LEN],
+            v: [0_u8; BLOCK_LEN],
+            reseed_counter: 1,
+        };
+        drbg.update(seed_material);
+        Ok(drbg)
+    }
+
+    ///
+    /// reseed mixes fresh entropy into the state and resets the reseed counter.
+    ///
+    /// # Parameters
+    ///
+    /// **entropy**
+    ///
+    /// The entropy input, exactly `CTR_DRBG_SEED_LEN` bytes.
+    ///
+    /// **additional_input**
+    ///
+    /// Optional additional input, at most `CTR_DRBG_SEED_LEN` bytes.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The length of `entropy` or `additional_input` is out of range.
+    ///
+    pub fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) -> SgxError {
+        if entropy.len() != CTR_DRBG_SEED_LEN || additional_input.len() > CTR_DRBG_SEED_LEN {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut seed_material = [0_u8; CTR_DRBG_SEED_LEN];
+        seed_material.copy_from_slice(entropy);
+        seed_material.iter_mut().zip(additional_input.iter()).for_each(|(s, a)| *s ^= *a);
+        self.update(&seed_material);
+        zeroize(&mut seed_material);
+        self.reseed_counter = 1;
+        Ok(())
+    }
+
+    ///
+    /// generate fills `output` with pseudorandom bytes.
+    ///
+    /// # Parameters
+    ///
+    /// **output**
+    ///
+    /// The buffer to fill, at most `CTR_DRBG_MAX_REQUEST_LEN` bytes.
+    ///
+    /// **additional_input**
+    ///
+    /// Optional additional input, at most `CTR_DRBG_SEED_LEN` bytes.
+    ///
+    /// # Errors
+    ///
+    /// **DrbgError::ReseedRequired**
+    ///
+    /// The reseed interval has been reached.
+    ///
+    /// **DrbgError::RequestTooLarge**
+    ///
+    /// `output` is longer than `CTR_DRBG_MAX_REQUEST_LEN` bytes.
+    ///
+    /// **DrbgError::AdditionalInputTooLong**
+    ///
+    /// `additional_input` is longer than `CTR_DRBG_SEED_LEN` bytes.
+    ///
+    pub fn generate(&mut self, output: &mut [u8], additional_input: &[u8]) -> Result<(), DrbgError> {
+        if self.reseed_counter > CTR_DRBG_RESEED_INTERVAL {
+            return Err(DrbgError::ReseedRequired);
+        }
+        if output.len() > CTR_DRBG_MAX_REQUEST_LEN {
+            return Err(DrbgError::RequestTooLarge);
+        }
+        if additional_input.len() > CTR_DRBG_SEED_LEN {
+            return Err(DrbgError::AdditionalInputTooLong);
+        }
+
+        let mut additional = [0_u8; CTR_DRBG_SEED_LEN];
+        additional[..additional_input.len()].copy_from_slice(additional_input);
+        if !additional_input.is_empty() {
+            self.update(&additional);
+        }
+
+        let mut keys = expand_key(&self.key);
+        for chunk in output.chunks_mut(BLOCK_LEN) {
+            increment(&mut self.v);
+            let mut block = encrypt_block(&keys, &self.v);
+            chunk.copy_from_slice(&block[..chunk.len()]);
+            zeroize(&mut block);
+        }
+        zeroize_keys(&mut keys);
+
+        self.update(&additional);
+        zeroize(&mut additional);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    ///
+    /// uninstantiate erases the internal state and destroys the DRBG.
+    ///
+    pub fn uninstantiate(self) {}
+
+    // CTR_DRBG_Update
+    fn update(&mut self, provided_data: &[u8]) {
+        let mut keys = expand_key(&self.key);
+        let mut temp = [0_u8; CTR_DRBG_SEED_LEN];
+        for chunk in temp.chunks_mut(BLOCK_LEN) {
+            increment(&mut self.v);
+            chunk.copy_from_slice(&encrypt_block(&keys, &self.v));
+        }
+        zeroize_keys(&mut keys);
+
+        temp.iter_mut().zip(provided_data.iter()).for_each(|(t, p)| *t ^= *p);
+        self.key.copy_from_slice(&temp[..KEY_LEN]);
+        self.v.copy_from_slice(&temp[KEY_LEN..]);
+        zeroize(&mut temp);
+    }
+}
+
+impl Drop for CtrDrbg {
+    fn drop(&mut self) {
+        zeroize(&mut self.key);
+        zeroize(&mut self.v);
+    }
+}
+
+impl fmt::Debug for CtrDrbg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CtrDrbg")
+            .field("reseed_counter", &self.reseed_counter)
+            .finish_non_exhaustive()
+    }
+}
+
+// V = (V + 1) mod 2^128
+fn increment(v: &mut [u8; BLOCK_LEN]) {
+    *v = u128::from_be_bytes(*v).wrapping_add(1).to_be_bytes();
+}
+
+// The AES-256 round keys. Every SGX capable processor supports AES-NI.
+type RoundKeys = [__m128i; 15];
+
+fn expand_key(key: &[u8; KEY_LEN]) -> RoundKeys {
+    unsafe { aesni_expand_key(key) }
+}
+
+fn encrypt_block(keys: &RoundKeys, block: &[u8; BLOCK_LEN]) -> [u8; BLOCK_LEN] {
+    unsafe { aesni_encrypt_block(keys, block) }
+}
+
+fn zeroize_keys(keys: &mut RoundKeys) {
+    for k in keys.iter_mut() {
+        unsafe { core::ptr::write_volatile(k, _mm_setzero_si128()) };
+    }
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn aesni_expand_key(key: &[u8; KEY_LEN]) -> RoundKeys {
+    #[inline(always)]
+    unsafe fn mix(mut a: __m128i, t: __m128i) -> __m128i {
+        a = _mm_xor_si128(a, _mm_slli_si128(a, 4));
+        a = _mm_xor_si128(a, _mm_slli_si128(a, 4));
+        a = _mm_xor_si128(a, _mm_slli_si128(a, 4));
+        _mm_xor_si128(a, t)
+    }
+
+    let mut keys = [_mm_setzero_si128(); 15];
+    keys[0] = _mm_loadu_si128(key.as_ptr() as *const __m128i);
+    keys[1] = _mm_loadu_si128(key.as_ptr().add(BLOCK_LEN) as *const __m128i);
+
+    // keys[i] from keys[i - 2], with the temporary word derived from keys[i - 1]
+    macro_rules! expand {
+        ($i:expr, $rcon:expr, $shuffle:expr) => {
+            let t = _mm_aeskeygenassist_si128(keys[$i - 1], $rcon);
+            keys[$i] = mix(keys[$i - 2], _mm_shuffle_epi32(t, $shuffle));
+        };
+    }
+    expand!(2, 0x01, 0xff);
+    expand!(3, 0x00, 0xaa);
+    expand!(4, 0x02, 0xff);
+    expand!(5, 0x00, 0xaa);
+    expand!(6, 0x04, 0xff);
+    expand!(7, 0x00, 0xaa);
+    expand!(8, 0x08, 0xff);
+    expand!(9, 0x00, 0xaa);
+    expand!(10, 0x10, 0xff);
+    expand!(11, 0x00, 0xaa);
+    expand!(12, 0x20, 0xff);
+    expand!(13, 0x00, 0xaa);
+    expand!(14, 0x40, 0xff);
+    keys
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn aesni_encrypt_block(keys: &RoundKeys, block: &[u8; BLOCK_LEN]) -> [u8; BLOCK_LEN] {
+    let mut state = _mm_loadu_si128(block.as_ptr() as *const __m128i);
+    state = _mm_xor_si128(state, keys[0]);
+    for key in &keys[1..14] {
+        state = _mm_aesenc_si128(state, *key);
+    }
+    state = _mm_aesenclast_si128(state, keys[14]);
+
+    let mut out = [0_u8; BLOCK_LEN];
+    _mm_storeu_si128(out.as_mut_ptr() as *mut __m128i, state);
+    out
+}
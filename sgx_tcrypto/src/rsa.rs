@@ -0,0 +1,506 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//!
+//! RSASSA-PSS signatures
+//!
+//! The Intel SGX cryptography library signs with PKCS#1 v1.5 padding only
+//! (`rsgx_rsa3072_sign_msg` and friends) and does not expose the raw RSA
+//! operation, so RSASSA-PSS as defined in RFC 8017 is implemented here:
+//! EMSA-PSS encoding with SHA-256, MGF1-SHA256 and a 32 byte salt over a
+//! Montgomery modular exponentiation. The exponentiation with the private
+//! exponent runs in constant time.
+//!
+//! Key components use the little-endian byte order of
+//! `rsgx_create_rsa_key_pair` and `sgx_rsa3072_key_t`. Signatures are
+//! big-endian octet strings, as in PKCS#1.
+//!
+use crate::util::zeroize;
+use crate::sha::{Digest, Sha256};
+use crate::util::ct_eq;
+use core::convert::TryFrom;
+use core::fmt;
+use sgx_types::*;
+
+/// The modulus size in bytes of 2048-bit RSA keys.
+pub const RSA2048_KEY_SIZE: usize = 256;
+
+/// The length of the random salt in a PSS signature.
+pub const RSA_PSS_SALT_SIZE: usize = SGX_SHA256_HASH_SIZE;
+
+const HASH_SIZE: usize = SGX_SHA256_HASH_SIZE;
+
+// The largest supported modulus in 32-bit limbs.
+const MAX_LIMBS: usize = SGX_RSA3072_KEY_SIZE / 4;
+
+///
+/// An RSA private key for signing with RSASSA-PSS.
+///
+/// `LEN` is the modulus size in bytes; use the `Rsa2048PssKey` and
+/// `Rsa3072PssKey` aliases. The private exponent is erased when the key
+/// is dropped.
+///
+pub struct RsaPssKey<const LEN: usize> {
+    n: [u8; LEN],
+    d: [u8; LEN],
+    e: [u8; SGX_RSA3072_PUB_EXP_SIZE],
+}
+
+pub type Rsa2048PssKey = RsaPssKey<RSA2048_KEY_SIZE>;
+pub type Rsa3072PssKey = RsaPssKey<SGX_RSA3072_KEY_SIZE>;
+
+impl<const LEN: usize> RsaPssKey<LEN> {
+    ///
+    /// new creates a key from its modulus, private exponent and public
+    /// exponent, as returned by `rsgx_create_rsa_key_pair`.
+    ///
+    /// # Parameters
+    ///
+    /// **n**
+    ///
+    /// The little-endian modulus, exactly `LEN` bytes with the top bit set.
+    ///
+    /// **d**
+    ///
+    /// The little-endian private exponent, at most `LEN` bytes.
+    ///
+    /// **e**
+    ///
+    /// The little-endian public exponent, at most 4 bytes.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `LEN` is not 256 or 384, or a component is malformed.
+    ///
+    pub fn new(n: &[u8], d: &[u8], e: &[u8]) -> SgxResult<RsaPssKey<LEN>> {
+        let public = RsaPssPublicKey::<LEN>::new(n, e)?;
+        if d.len() > LEN {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let mut key = RsaPssKey {
+            n: public.n,
+            d: [0_u8; LEN],
+            e: public.e,
+        };
+        key.d[..d.len()].copy_from_slice(d);
+        Ok(key)
+    }
+
+    ///
+    /// public_key returns the public half of the key.
+    ///
+    pub fn public_key(&self) -> RsaPssPublicKey<LEN> {
+        RsaPssPublicKey { n: self.n, e: self.e }
+    }
+
+    ///
+    /// sign computes an RSASSA-PSS signature over `message` with a fresh
+    /// random salt.
+    ///
+    /// The signature is checked against the public key before it is
+    /// returned, so a fault during the computation cannot leak the key.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a, libsgx_trts.a
+    ///
+    /// # Return value
+    ///
+    /// The signature, `LEN` bytes.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_OUT_OF_MEMORY**
+    ///
+    /// Not enough memory is available to complete this operation.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// Reading random numbers or the hash calculation failed, or the
+    /// signature did not verify.
+    ///
+    pub fn sign(&self, message: &[u8]) -> SgxResult<RsaPssSignature<LEN>> {
+        let mut salt = [0_u8; RSA_PSS_SALT_SIZE];
+        let ret = unsafe { sgx_read_rand(salt.as_mut_ptr(), salt.len()) };
+        if ret != sgx_status_t::SGX_SUCCESS {
+            return Err(ret);
+        }
+
+        let m_hash = sha256(&[message])?;
+        let mut em = [0_u8; LEN];
+        emsa_pss_encode(&m_hash, &salt, &mut em)?;
+
+        let modulus = Modulus::new(&self.n);
+        let m = limbs_from_be(&em);
+        let s = modulus.pow(&m, &self.d);
+        if modulus.pow(&s, &self.e)[..] != m[..] {
+            return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+        }
+
+        let mut sig = [0_u8; LEN];
+        limbs_to_be(&s, &mut sig);
+        Ok(RsaPssSignature(sig))
+    }
+}
+
+impl TryFrom<&sgx_rsa3072_key_t> for Rsa3072PssKey {
+    type Error = sgx_status_t;
+
+    fn try_from(key: &sgx_rsa3072_key_t) -> SgxResult<Rsa3072PssKey> {
+        RsaPssKey::new(&key.modulus, &key.d, &key.e)
+    }
+}
+
+impl<const LEN: usize> Drop for RsaPssKey<LEN> {
+    fn drop(&mut self) {
+        zeroize(&mut self.d);
+    }
+}
+
+impl<const LEN: usize> fmt::Debug for RsaPssKey<LEN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RsaPssKey").finish_non_exhaustive()
+    }
+}
+
+///
+/// An RSA public key for verifying RSASSA-PSS signatures.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct RsaPssPublicKey<const LEN: usize> {
+    n: [u8; LEN],
+    e: [u8; SGX_RSA3072_PUB_EXP_SIZE],
+}
+
+pub type Rsa2048PssPublicKey = RsaPssPublicKey<RSA2048_KEY_SIZE>;
+pub type Rsa3072PssPublicKey = RsaPssPublicKey<SGX_RSA3072_KEY_SIZE>;
+
+impl<const LEN: usize> RsaPssPublicKey<LEN> {
+    ///
+    /// new creates a public key from its little-endian modulus and public
+    /// exponent, with the same requirements as `RsaPssKey::new`.
+    ///
+    pub fn new(n: &[u8], e: &[u8]) -> SgxResult<RsaPssPublicKey<LEN>> {
+        if LEN != RSA2048_KEY_SIZE && LEN != SGX_RSA3072_KEY_SIZE {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        if n.len() != LEN || n[0] & 1 == 0 || n[LEN - 1] & 0x80 == 0 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        if e.is_empty() || e.len() > SGX_RSA3072_PUB_EXP_SIZE || e[0] & 1 == 0 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut key = RsaPssPublicKey {
+            n: [0_u8; LEN],
+            e: [0_u8; SGX_RSA3072_PUB_EXP_SIZE],
+        };
+        key.n.copy_from_slice(n);
+        key.e[..e.len()].copy_from_slice(e);
+        if key.e == [1, 0, 0, 0] {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        Ok(key)
+    }
+
+    ///
+    /// modulus returns the little-endian modulus.
+    ///
+    pub fn modulus(&self) -> &[u8; LEN] {
+        &self.n
+    }
+
+    ///
+    /// exponent returns the little-endian public exponent.
+    ///
+    pub fn exponent(&self) -> &[u8; SGX_RSA3072_PUB_EXP_SIZE] {
+        &self.e
+    }
+}
+
+impl TryFrom<&sgx_rsa3072_public_key_t> for Rsa3072PssPublicKey {
+    type Error = sgx_status_t;
+
+    fn try_from(key: &sgx_rsa3072_public_key_t) -> SgxResult<Rsa3072PssPublicKey> {
+        RsaPssPublicKey::new(&key.modulus, &key.exponent)
+    }
+}
+
+///
+/// An RSASSA-PSS signature, a big-endian octet string as long as the
+/// modulus.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct RsaPssSignature<const LEN: usize>([u8; LEN]);
+
+impl<const LEN: usize> RsaPssSignature<LEN> {
+    pub fn from_bytes(bytes: [u8; LEN]) -> RsaPssSignature<LEN> {
+        RsaPssSignature(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; LEN] {
+        &self.0
+    }
+
+    ///
+    /// verify checks an RSASSA-PSS signature over `message`.
+    ///
+    /// The recovered hash is compared in constant time.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_SIGNATURE**
+    ///
+    /// The signature is not valid for `message` under `pub_key`.
+    ///
+    /// **SGX_ERROR_OUT_OF_MEMORY**
+    ///
+    /// Not enough memory is available to complete this operation.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The hash calculation failed.
+    ///
+    pub fn verify(pub_key: &RsaPssPublicKey<LEN>, message: &[u8], sig: &RsaPssSignature<LEN>) -> SgxError {
+        let modulus = Modulus::new(&pub_key.n);
+        let s = limbs_from_be(&sig.0);
+        if !modulus.greater_than(&s) {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_SIGNATURE);
+        }
+        let m = modulus.pow(&s, &pub_key.e);
+        let mut em = [0_u8; LEN];
+        limbs_to_be(&m, &mut em);
+
+        let m_hash = sha256(&[message])?;
+        if emsa_pss_verify(&m_hash, &mut em)? {
+            Ok(())
+        } else {
+            Err(sgx_status_t::SGX_ERROR_INVALID_SIGNATURE)
+        }
+    }
+}
+
+impl<const LEN: usize> From<[u8; LEN]> for RsaPssSignature<LEN> {
+    fn from(bytes: [u8; LEN]) -> RsaPssSignature<LEN> {
+        RsaPssSignature(bytes)
+    }
+}
+
+// EMSA-PSS-ENCODE of RFC 8017 9.1.1 for a modulus of 8 * em.len() bits,
+// so emBits = 8 * em.len() - 1.
+fn emsa_pss_encode(m_hash: &[u8; HASH_SIZE], salt: &[u8; RSA_PSS_SALT_SIZE], em: &mut [u8]) -> SgxError {
+    let db_len = em.len() - HASH_SIZE - 1;
+    let h = sha256(&[&[0_u8; 8], m_hash, salt])?;
+
+    let (db, rest) = em.split_at_mut(db_len);
+    db.iter_mut().for_each(|b| *b = 0);
+    db[db_len - RSA_PSS_SALT_SIZE - 1] = 0x01;
+    db[db_len - RSA_PSS_SALT_SIZE..].copy_from_slice(salt);
+    mgf1_xor(&h, db)?;
+    db[0] &= 0x7f;
+
+    rest[..HASH_SIZE].copy_from_slice(&h);
+    rest[HASH_SIZE] = 0xbc;
+    Ok(())
+}
+
+// EMSA-PSS-VERIFY of RFC 8017 9.1.2, unmasking em in place.
+fn emsa_pss_verify(m_hash: &[u8; HASH_SIZE], em: &mut [u8]) -> SgxResult<bool> {
+    let db_len = em.len() - HASH_SIZE - 1;
+    if em[em.len() - 1] != 0xbc || em[0] & 0x80 != 0 {
+        return Ok(false);
+    }
+
+    let (db, rest) = em.split_at_mut(db_len);
+    let mut h = [0_u8; HASH_SIZE];
+    h.copy_from_slice(&rest[..HASH_SIZE]);
+    mgf1_xor(&h, db)?;
+    db[0] &= 0x7f;
+
+    let ps_len = db_len - RSA_PSS_SALT_SIZE - 1;
+    if db[..ps_len].iter().any(|b| *b != 0) || db[ps_len] != 0x01 {
+        return Ok(false);
+    }
+    let expected = sha256(&[&[0_u8; 8], m_hash, &db[ps_len + 1..]])?;
+    Ok(ct_eq(&h, &expected))
+}
+
+// out ^= MGF1-SHA256(seed, out.len())
+fn mgf1_xor(seed: &[u8], out: &mut [u8]) -> SgxError {
+    for (counter, chunk) in out.chunks_mut(HASH_SIZE).enumerate() {
+        let mask = sha256(&[seed, &(counter as u32).to_be_bytes()])?;
+        chunk.iter_mut().zip(mask.iter()).for_each(|(b, m)| *b ^= *m);
+    }
+    Ok(())
+}
+
+fn sha256(parts: &[&[u8]]) -> SgxResult<[u8; HASH_SIZE]> {
+    let mut hash = Sha256::new()?;
+    for part in parts {
+        hash.update(part)?;
+    }
+    hash.finalize()
+}
+
+type Limbs = [u32; MAX_LIMBS];
+
+// An odd modulus with the constants for Montgomery multiplication.
+struct Modulus {
+    n: Limbs,
+    len: usize,
+    // -n^-1 mod 2^32
+    n0inv: u32,
+    // R^2 mod n, where R = 2^(32 * len)
+    rr: Limbs,
+}
+
+impl Modulus {
+    fn new(n: &[u8]) -> Modulus {
+        let len = n.len() / 4;
+        let mut limbs = [0_u32; MAX_LIMBS];
+        for (limb, chunk) in limbs.iter_mut().zip(n.chunks(4)) {
+            *limb = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+
+        // Newton iteration, each step doubles the number of correct bits
+        let mut inv = 1_u32;
+        for _ in 0..5 {
+            inv = inv.wrapping_mul(2_u32.wrapping_sub(limbs[0].wrapping_mul(inv)));
+        }
+
+        let mut modulus = Modulus {
+            n: limbs,
+            len,
+            n0inv: inv.wrapping_neg(),
+            rr: [0_u32; MAX_LIMBS],
+        };
+
+        // double 1 modulo n until it reaches R^2
+        let mut rr = [0_u32; MAX_LIMBS];
+        rr[0] = 1;
+        for _ in 0..64 * len {
+            let mut carry = 0;
+            for limb in rr[..len].iter_mut() {
+                let top = *limb >> 31;
+                *limb = (*limb << 1) | carry;
+                carry = top;
+            }
+            modulus.reduce_once(&mut rr, carry);
+        }
+        modulus.rr = rr;
+        modulus
+    }
+
+    // a > n, for public values only
+    fn greater_than(&self, a: &Limbs) -> bool {
+        for i in (0..self.len).rev() {
+            if self.n[i] != a[i] {
+                return self.n[i] > a[i];
+            }
+        }
+        false
+    }
+
+    // t = top * R + t - n if that is not negative, in constant time,
+    // given t + top * R < 2n.
+    fn reduce_once(&self, t: &mut Limbs, top: u32) {
+        let mut diff = [0_u32; MAX_LIMBS];
+        let mut borrow = 0_u64;
+        for i in 0..self.len {
+            let x = (t[i] as u64).wrapping_sub(self.n[i] as u64).wrapping_sub(borrow);
+            diff[i] = x as u32;
+            borrow = x >> 63;
+        }
+        let mask = 0_u32.wrapping_sub(top | (1 - borrow as u32));
+        for i in 0..self.len {
+            t[i] = (diff[i] & mask) | (t[i] & !mask);
+        }
+    }
+
+    // a * b / R mod n, for a, b < n
+    fn mul(&self, a: &Limbs, b: &Limbs) -> Limbs {
+        let len = self.len;
+        let mut t = [0_u32; MAX_LIMBS + 2];
+        for &bi in b[..len].iter() {
+            let mut c = 0_u64;
+            for j in 0..len {
+                let x = t[j] as u64 + a[j] as u64 * bi as u64 + c;
+                t[j] = x as u32;
+                c = x >> 32;
+            }
+            let x = t[len] as u64 + c;
+            t[len] = x as u32;
+            t[len + 1] = (x >> 32) as u32;
+
+            let m = t[0].wrapping_mul(self.n0inv);
+            let mut c = (t[0] as u64 + m as u64 * self.n[0] as u64) >> 32;
+            for j in 1..len {
+                let x = t[j] as u64 + m as u64 * self.n[j] as u64 + c;
+                t[j - 1] = x as u32;
+                c = x >> 32;
+            }
+            let x = t[len] as u64 + c;
+            t[len - 1] = x as u32;
+            t[len] = t[len + 1] + (x >> 32) as u32;
+        }
+
+        let mut r = [0_u32; MAX_LIMBS];
+        r[..len].copy_from_slice(&t[..len]);
+        self.reduce_once(&mut r, t[len]);
+        r
+    }
+
+    // base^exp mod n for base < n and a little-endian exponent, with one
+    // squaring and one multiplication per exponent bit
+    fn pow(&self, base: &Limbs, exp: &[u8]) -> Limbs {
+        let mut one = [0_u32; MAX_LIMBS];
+        one[0] = 1;
+        let base = self.mul(base, &self.rr);
+        let mut acc = self.mul(&one, &self.rr);
+        for byte in exp.iter().rev() {
+            for bit in (0..8).rev() {
+                acc = self.mul(&acc, &acc);
+                let product = self.mul(&acc, &base);
+                let mask = 0_u32.wrapping_sub(((byte >> bit) & 1) as u32);
+                for (a, p) in acc[..self.len].iter_mut().zip(product.iter()) {
+                    *a = (*p & mask) | (*a & !mask);
+                }
+            }
+        }
+        self.mul(&acc, &one)
+    }
+}
+
+fn limbs_from_be(bytes: &[u8]) -> Limbs {
+    let mut limbs = [0_u32; MAX_LIMBS];
+    for (i, b) in bytes.iter().rev().enumerate() {
+        limbs[i / 4] |= (*b as u32) << (8 * (i % 4));
+    }
+    limbs
+}
+
+fn limbs_to_be(limbs: &Limbs, out: &mut [u8]) {
+    for (i, b) in out.iter_mut().rev().enumerate() {
+        *b = (limbs[i / 4] >> (8 * (i % 4))) as u8;
+    }
+}
@@ -0,0 +1,557 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+//!
+//! SHA-1, SHA-2 hash functions and SHA-3 extendable-output functions
+//!
+use crate::crypto::{SgxSha1Handle, SgxSha384Handle, SgxShaHandle};
+use core::ptr;
+use core::sync::atomic::{compiler_fence, Ordering};
+use sgx_types::*;
+
+/// The rate of SHAKE128 in bytes, i.e. 1344 bits.
+pub const SHAKE128_RATE: usize = 168;
+
+/// The rate of SHAKE256 in bytes, i.e. 1088 bits.
+pub const SHAKE256_RATE: usize = 136;
+
+// The domain separation bits of SHAKE (1111) followed by the first bit of
+// the pad10*1 padding.
+pub(crate) const SHAKE_PAD: u8 = 0x1f;
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808a,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808b,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008a,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000a,
+    0x0000_0000_8000_808b,
+    0x8000_0000_0000_008b,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800a,
+    0x8000_0000_8000_000a,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+// Rotation offsets of the rho step, in the lane order visited by pi.
+const RHO: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+
+// Lane indices visited by the pi step, starting from lane 1.
+const PI: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+///
+/// The SHAKE128 and SHAKE256 extendable-output functions defined in
+/// FIPS 202.
+///
+/// Input is absorbed with `update`; any amount of output can then be read
+/// with `squeeze`. Successive `squeeze` calls continue the same output
+/// stream, so squeezing 16 bytes twice yields the same bytes as squeezing
+/// 32 bytes once.
+///
+/// The Intel SGX SDK has no SHAKE primitive, so the Keccak-f[1600]
+/// permutation is implemented here. The state is erased when the object is
+/// dropped.
+///
+pub struct ShakeXof {
+    sponge: KeccakSponge,
+}
+
+impl ShakeXof {
+    ///
+    /// new_shake128 creates a SHAKE128 instance, providing up to 128 bits of
+    /// security.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// The SHAKE128 instance, with no input absorbed.
+    ///
+    pub fn new_shake128() -> ShakeXof {
+        ShakeXof {
+            sponge: KeccakSponge::new(SHAKE128_RATE, SHAKE_PAD),
+        }
+    }
+
+    ///
+    /// new_shake256 creates a SHAKE256 instance, providing up to 256 bits of
+    /// security.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// The SHAKE256 instance, with no input absorbed.
+    ///
+    pub fn new_shake256() -> ShakeXof {
+        ShakeXof {
+            sponge: KeccakSponge::new(SHAKE256_RATE, SHAKE_PAD),
+        }
+    }
+
+    ///
+    /// update absorbs `data` into the state.
+    ///
+    /// # Parameters
+    ///
+    /// **data**
+    ///
+    /// The data to absorb. It may be empty.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// `squeeze` has already been called; no more input can be absorbed.
+    ///
+    pub fn update(&mut self, data: &[u8]) -> SgxError {
+        self.sponge.absorb(data)
+    }
+
+    ///
+    /// squeeze fills `output` with the next bytes of the output stream.
+    ///
+    /// The first call finishes absorbing; `update` can no longer be called
+    /// afterwards.
+    ///
+    /// # Parameters
+    ///
+    /// **output**
+    ///
+    /// The buffer to fill. It may be of any length.
+    ///
+    /// # Errors
+    ///
+    /// This function currently always succeeds.
+    ///
+    pub fn squeeze(&mut self, output: &mut [u8]) -> SgxError {
+        self.sponge.squeeze(output);
+        Ok(())
+    }
+}
+
+// A sponge over Keccak-f[1600] with the given rate in bytes. `pad` holds
+// the domain separation bits followed by the first bit of the pad10*1
+// padding.
+pub(crate) struct KeccakSponge {
+    state: [u64; 25],
+    rate: usize,
+    pad: u8,
+    pos: usize,
+    squeezing: bool,
+}
+
+impl KeccakSponge {
+    pub(crate) fn new(rate: usize, pad: u8) -> KeccakSponge {
+        KeccakSponge {
+            state: [0_u64; 25],
+            rate,
+            pad,
+            pos: 0,
+            squeezing: false,
+        }
+    }
+
+    pub(crate) fn absorb(&mut self, data: &[u8]) -> SgxError {
+        if self.squeezing {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+        for &b in data {
+            self.xor_byte(self.pos, b);
+            self.pos += 1;
+            if self.pos == self.rate {
+                keccak_f1600(&mut self.state);
+                self.pos = 0;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn squeeze(&mut self, output: &mut [u8]) {
+        if !self.squeezing {
+            self.xor_byte(self.pos, self.pad);
+            self.xor_byte(self.rate - 1, 0x80);
+            keccak_f1600(&mut self.state);
+            self.pos = 0;
+            self.squeezing = true;
+        }
+        for out in output.iter_mut() {
+            if self.pos == self.rate {
+                keccak_f1600(&mut self.state);
+                self.pos = 0;
+            }
+            *out = (self.state[self.pos / 8] >> (8 * (self.pos % 8))) as u8;
+            self.pos += 1;
+        }
+    }
+
+    #[inline]
+    fn xor_byte(&mut self, pos: usize, b: u8) {
+        self.state[pos / 8] ^= u64::from(b) << (8 * (pos % 8));
+    }
+}
+
+impl Drop for KeccakSponge {
+    fn drop(&mut self) {
+        for lane in self.state.iter_mut() {
+            unsafe { ptr::write_volatile(lane, 0) };
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+#[allow(clippy::needless_range_loop)]
+fn keccak_f1600(a: &mut [u64; 25]) {
+    for rc in ROUND_CONSTANTS.iter() {
+        // theta
+        let mut c = [0_u64; 5];
+        for x in 0..5 {
+            c[x] = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                a[5 * y + x] ^= d;
+            }
+        }
+
+        // rho and pi
+        let mut last = a[1];
+        for i in 0..24 {
+            let j = PI[i];
+            let tmp = a[j];
+            a[j] = last.rotate_left(RHO[i]);
+            last = tmp;
+        }
+
+        // chi
+        for y in 0..5 {
+            let mut row = [0_u64; 5];
+            row.copy_from_slice(&a[5 * y..5 * y + 5]);
+            for x in 0..5 {
+                a[5 * y + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+
+        // iota
+        a[0] ^= rc;
+    }
+}
+
+///
+/// An incremental hash function with a fixed output size.
+///
+/// This is implemented by `Sha1`, `Sha256`, `Sha384`, `Sha512` and the SHA-3 hashes
+/// of the `sha3` module, and lets constructions such as `hmac::Hmac` be
+/// generic over the hash.
+///
+pub trait Digest: Sized {
+    /// The size in bytes of the blocks the input is processed in.
+    const BLOCK_SIZE: usize;
+    /// The size in bytes of the hash value.
+    const OUTPUT_SIZE: usize;
+    /// The hash value, a byte array of `OUTPUT_SIZE` bytes.
+    type Output: AsRef<[u8]>;
+
+    ///
+    /// new creates a hash state with no input processed.
+    ///
+    fn new() -> SgxResult<Self>;
+
+    ///
+    /// update processes the next part of the input.
+    ///
+    fn update(&mut self, data: &[u8]) -> SgxError;
+
+    ///
+    /// finalize returns the hash of all the input and consumes the state.
+    ///
+    fn finalize(self) -> SgxResult<Self::Output>;
+}
+
+///
+/// SHA-1, computed by the SHA1 functions of the Intel SGX SDK.
+///
+/// SHA-1 is not collision resistant. It is provided for interoperability,
+/// e.g. HMAC-SHA1 and PBKDF2-HMAC-SHA1 in existing protocols, and should
+/// not be used for new designs.
+///
+pub struct Sha1 {
+    handle: SgxSha1Handle,
+}
+
+impl Digest for Sha1 {
+    const BLOCK_SIZE: usize = 64;
+    const OUTPUT_SIZE: usize = SGX_SHA1_HASH_SIZE;
+    type Output = sgx_sha1_hash_t;
+
+    fn new() -> SgxResult<Sha1> {
+        let handle = SgxSha1Handle::new();
+        handle.init()?;
+        Ok(Sha1 { handle })
+    }
+
+    fn update(&mut self, data: &[u8]) -> SgxError {
+        // The SDK rejects empty input, but an empty update is a no-op.
+        if data.is_empty() {
+            return Ok(());
+        }
+        self.handle.update_slice(data)
+    }
+
+    fn finalize(self) -> SgxResult<sgx_sha1_hash_t> {
+        self.handle.get_hash()
+    }
+}
+
+///
+/// SHA-256, computed by the SHA256 functions of the Intel SGX SDK.
+///
+pub struct Sha256 {
+    handle: SgxShaHandle,
+}
+
+impl Digest for Sha256 {
+    const BLOCK_SIZE: usize = 64;
+    const OUTPUT_SIZE: usize = SGX_SHA256_HASH_SIZE;
+    type Output = sgx_sha256_hash_t;
+
+    fn new() -> SgxResult<Sha256> {
+        let handle = SgxShaHandle::new();
+        handle.init()?;
+        Ok(Sha256 { handle })
+    }
+
+    fn update(&mut self, data: &[u8]) -> SgxError {
+        // The SDK rejects empty input, but an empty update is a no-op.
+        if data.is_empty() {
+            return Ok(());
+        }
+        self.handle.update_slice(data)
+    }
+
+    fn finalize(self) -> SgxResult<sgx_sha256_hash_t> {
+        self.handle.get_hash()
+    }
+}
+
+///
+/// SHA-384, computed by the SHA384 functions of the Intel SGX SDK.
+///
+pub struct Sha384 {
+    handle: SgxSha384Handle,
+}
+
+impl Digest for Sha384 {
+    const BLOCK_SIZE: usize = 128;
+    const OUTPUT_SIZE: usize = SGX_SHA384_HASH_SIZE;
+    type Output = sgx_sha384_hash_t;
+
+    fn new() -> SgxResult<Sha384> {
+        let handle = SgxSha384Handle::new();
+        handle.init()?;
+        Ok(Sha384 { handle })
+    }
+
+    fn update(&mut self, data: &[u8]) -> SgxError {
+        // The SDK rejects empty input, but an empty update is a no-op.
+        if data.is_empty() {
+            return Ok(());
+        }
+        self.handle.update_slice(data)
+    }
+
+    fn finalize(self) -> SgxResult<sgx_sha384_hash_t> {
+        self.handle.get_hash()
+    }
+}
+
+/// The size of a SHA-512 hash value in bytes.
+pub const SHA512_HASH_SIZE: usize = 64;
+
+const SHA512_BLOCK_SIZE: usize = 128;
+
+const SHA512_IV: [u64; 8] = [
+    0x6a09_e667_f3bc_c908,
+    0xbb67_ae85_84ca_a73b,
+    0x3c6e_f372_fe94_f82b,
+    0xa54f_f53a_5f1d_36f1,
+    0x510e_527f_ade6_82d1,
+    0x9b05_688c_2b3e_6c1f,
+    0x1f83_d9ab_fb41_bd6b,
+    0x5be0_cd19_137e_2179,
+];
+
+const SHA512_K: [u64; 80] = [
+    0x428a_2f98_d728_ae22, 0x7137_4491_23ef_65cd, 0xb5c0_fbcf_ec4d_3b2f, 0xe9b5_dba5_8189_dbbc,
+    0x3956_c25b_f348_b538, 0x59f1_11f1_b605_d019, 0x923f_82a4_af19_4f9b, 0xab1c_5ed5_da6d_8118,
+    0xd807_aa98_a303_0242, 0x1283_5b01_4570_6fbe, 0x2431_85be_4ee4_b28c, 0x550c_7dc3_d5ff_b4e2,
+    0x72be_5d74_f27b_896f, 0x80de_b1fe_3b16_96b1, 0x9bdc_06a7_25c7_1235, 0xc19b_f174_cf69_2694,
+    0xe49b_69c1_9ef1_4ad2, 0xefbe_4786_384f_25e3, 0x0fc1_9dc6_8b8c_d5b5, 0x240c_a1cc_77ac_9c65,
+    0x2de9_2c6f_592b_0275, 0x4a74_84aa_6ea6_e483, 0x5cb0_a9dc_bd41_fbd4, 0x76f9_88da_8311_53b5,
+    0x983e_5152_ee66_dfab, 0xa831_c66d_2db4_3210, 0xb003_27c8_98fb_213f, 0xbf59_7fc7_beef_0ee4,
+    0xc6e0_0bf3_3da8_8fc2, 0xd5a7_9147_930a_a725, 0x06ca_6351_e003_826f, 0x1429_2967_0a0e_6e70,
+    0x27b7_0a85_46d2_2ffc, 0x2e1b_2138_5c26_c926, 0x4d2c_6dfc_5ac4_2aed, 0x5338_0d13_9d95_b3df,
+    0x650a_7354_8baf_63de, 0x766a_0abb_3c77_b2a8, 0x81c2_c92e_47ed_aee6, 0x9272_2c85_1482_353b,
+    0xa2bf_e8a1_4cf1_0364, 0xa81a_664b_bc42_3001, 0xc24b_8b70_d0f8_9791, 0xc76c_51a3_0654_be30,
+    0xd192_e819_d6ef_5218, 0xd699_0624_5565_a910, 0xf40e_3585_5771_202a, 0x106a_a070_32bb_d1b8,
+    0x19a4_c116_b8d2_d0c8, 0x1e37_6c08_5141_ab53, 0x2748_774c_df8e_eb99, 0x34b0_bcb5_e19b_48a8,
+    0x391c_0cb3_c5c9_5a63, 0x4ed8_aa4a_e341_8acb, 0x5b9c_ca4f_7763_e373, 0x682e_6ff3_d6b2_b8a3,
+    0x748f_82ee_5def_b2fc, 0x78a5_636f_4317_2f60, 0x84c8_7814_a1f0_ab72, 0x8cc7_0208_1a64_39ec,
+    0x90be_fffa_2363_1e28, 0xa450_6ceb_de82_bde9, 0xbef9_a3f7_b2c6_7915, 0xc671_78f2_e372_532b,
+    0xca27_3ece_ea26_619c, 0xd186_b8c7_21c0_c207, 0xeada_7dd6_cde0_eb1e, 0xf57d_4f7f_ee6e_d178,
+    0x06f0_67aa_7217_6fba, 0x0a63_7dc5_a2c8_98a6, 0x113f_9804_bef9_0dae, 0x1b71_0b35_131c_471b,
+    0x28db_77f5_2304_7d84, 0x32ca_ab7b_40c7_2493, 0x3c9e_be0a_15c9_bebc, 0x431d_67c4_9c10_0d4c,
+    0x4cc5_d4be_cb3e_42b6, 0x597f_299c_fc65_7e2a, 0x5fcb_6fab_3ad6_faec, 0x6c44_198c_4a47_5817,
+];
+
+///
+/// SHA-512 as defined in FIPS 180-4.
+///
+/// The Intel SGX SDK only provides SHA-256 and SHA-384, so the
+/// compression function is implemented here. The state is erased when the
+/// object is dropped.
+///
+pub struct Sha512 {
+    state: [u64; 8],
+    block: [u8; SHA512_BLOCK_SIZE],
+    pos: usize,
+    len: u128,
+}
+
+impl Digest for Sha512 {
+    const BLOCK_SIZE: usize = SHA512_BLOCK_SIZE;
+    const OUTPUT_SIZE: usize = SHA512_HASH_SIZE;
+    type Output = [u8; SHA512_HASH_SIZE];
+
+    fn new() -> SgxResult<Sha512> {
+        Ok(Sha512 {
+            state: SHA512_IV,
+            block: [0_u8; SHA512_BLOCK_SIZE],
+            pos: 0,
+            len: 0,
+        })
+    }
+
+    fn update(&mut self, data: &[u8]) -> SgxError {
+        self.len += data.len() as u128;
+        for &b in data {
+            self.block[self.pos] = b;
+            self.pos += 1;
+            if self.pos == SHA512_BLOCK_SIZE {
+                sha512_compress(&mut self.state, &self.block);
+                self.pos = 0;
+            }
+        }
+        Ok(())
+    }
+
+    fn finalize(mut self) -> SgxResult<[u8; SHA512_HASH_SIZE]> {
+        let bit_len = self.len << 3;
+        self.block[self.pos] = 0x80;
+        self.pos += 1;
+        if self.pos > SHA512_BLOCK_SIZE - 16 {
+            self.block[self.pos..].iter_mut().for_each(|b| *b = 0);
+            sha512_compress(&mut self.state, &self.block);
+            self.pos = 0;
+        }
+        self.block[self.pos..SHA512_BLOCK_SIZE - 16].iter_mut().for_each(|b| *b = 0);
+        self.block[SHA512_BLOCK_SIZE - 16..].copy_from_slice(&bit_len.to_be_bytes());
+        sha512_compress(&mut self.state, &self.block);
+
+        let mut hash = [0_u8; SHA512_HASH_SIZE];
+        for (out, word) in hash.chunks_exact_mut(8).zip(self.state.iter()) {
+            out.copy_from_slice(&word.to_be_bytes());
+        }
+        Ok(hash)
+    }
+}
+
+impl Drop for Sha512 {
+    fn drop(&mut self) {
+        for word in self.state.iter_mut() {
+            unsafe { ptr::write_volatile(word, 0) };
+        }
+        for b in self.block.iter_mut() {
+            unsafe { ptr::write_volatile(b, 0) };
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+fn sha512_compress(state: &mut [u64; 8], block: &[u8; SHA512_BLOCK_SIZE]) {
+    let mut w = [0_u64; 80];
+    for (w, bytes) in w.iter_mut().zip(block.chunks_exact(8)) {
+        let mut word = [0_u8; 8];
+        word.copy_from_slice(bytes);
+        *w = u64::from_be_bytes(word);
+    }
+    for t in 16..80 {
+        let s0 = w[t - 15].rotate_right(1) ^ w[t - 15].rotate_right(8) ^ (w[t - 15] >> 7);
+        let s1 = w[t - 2].rotate_right(19) ^ w[t - 2].rotate_right(61) ^ (w[t - 2] >> 6);
+        w[t] = w[t - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[t - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (k, w) in SHA512_K.iter().zip(w.iter()) {
+        let s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(*k)
+            .wrapping_add(*w);
+        let s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+
+    for (s, v) in state.iter_mut().zip([a, b, c, d, e, f, g, h].iter()) {
+        *s = s.wrapping_add(*v);
+    }
+}
@@ -0,0 +1,176 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//!
+//! SHA-3 hash functions and extendable-output functions
+//!
+//! The Intel SGX SDK has no SHA-3 primitive, so SHA3-256, SHA3-512,
+//! SHAKE128 and SHAKE256 of FIPS 202 are built here on the Keccak-f[1600]
+//! sponge that also backs `sha::ShakeXof`.
+//!
+use crate::sha::{Digest, KeccakSponge, SHAKE128_RATE, SHAKE256_RATE, SHAKE_PAD};
+use sgx_types::*;
+
+/// The size of a SHA3-256 hash value in bytes.
+pub const SHA3_256_HASH_SIZE: usize = 32;
+
+/// The size of a SHA3-512 hash value in bytes.
+pub const SHA3_512_HASH_SIZE: usize = 64;
+
+pub type Sha3_256Hash = [u8; SHA3_256_HASH_SIZE];
+pub type Sha3_512Hash = [u8; SHA3_512_HASH_SIZE];
+
+// The rates of SHA3-256 and SHA3-512 in bytes, 1088 and 576 bits.
+const SHA3_256_RATE: usize = 136;
+const SHA3_512_RATE: usize = 72;
+
+// The domain separation bits of SHA-3 (01) followed by the first bit of the
+// pad10*1 padding.
+const SHA3_PAD: u8 = 0x06;
+
+macro_rules! impl_sha3 {
+    ($(#[$attr:meta])* $name:ident, $rate:expr, $size:expr, $output:ty) => {
+        $(#[$attr])*
+        pub struct $name {
+            sponge: KeccakSponge,
+        }
+
+        impl $name {
+            ///
+            /// hash computes the hash of `data` in one call.
+            ///
+            /// # Errors
+            ///
+            /// This function currently always succeeds.
+            ///
+            pub fn hash(data: &[u8]) -> SgxResult<$output> {
+                let mut hash = <$name as Digest>::new()?;
+                hash.update(data)?;
+                hash.finalize()
+            }
+        }
+
+        impl Digest for $name {
+            const BLOCK_SIZE: usize = $rate;
+            const OUTPUT_SIZE: usize = $size;
+            type Output = $output;
+
+            fn new() -> SgxResult<$name> {
+                Ok($name {
+                    sponge: KeccakSponge::new($rate, SHA3_PAD),
+                })
+            }
+
+            fn update(&mut self, data: &[u8]) -> SgxError {
+                self.sponge.absorb(data)
+            }
+
+            fn finalize(mut self) -> SgxResult<$output> {
+                let mut hash = [0_u8; $size];
+                self.sponge.squeeze(&mut hash);
+                Ok(hash)
+            }
+        }
+    };
+}
+
+impl_sha3! {
+    ///
+    /// SHA3-256 as defined in FIPS 202.
+    ///
+    /// Like the SHA-2 types it implements `Digest`, so it can be used with
+    /// `hmac::Hmac` and `kdf::Hkdf`. The state is erased when the object is
+    /// dropped.
+    ///
+    Sha3_256, SHA3_256_RATE, SHA3_256_HASH_SIZE, Sha3_256Hash
+}
+
+impl_sha3! {
+    ///
+    /// SHA3-512 as defined in FIPS 202.
+    ///
+    /// See `Sha3_256`.
+    ///
+    Sha3_512, SHA3_512_RATE, SHA3_512_HASH_SIZE, Sha3_512Hash
+}
+
+macro_rules! impl_shake {
+    ($(#[$attr:meta])* $name:ident, $rate:expr) => {
+        $(#[$attr])*
+        pub struct $name {
+            sponge: KeccakSponge,
+        }
+
+        impl $name {
+            pub fn new() -> $name {
+                $name {
+                    sponge: KeccakSponge::new($rate, SHAKE_PAD),
+                }
+            }
+
+            ///
+            /// update absorbs the next part of the input.
+            ///
+            /// # Errors
+            ///
+            /// This function currently always succeeds.
+            ///
+            pub fn update(&mut self, data: &[u8]) -> SgxError {
+                self.sponge.absorb(data)
+            }
+
+            ///
+            /// finalize_xof fills `output`, which may be of any length,
+            /// with the output of the function.
+            ///
+            /// # Errors
+            ///
+            /// This function currently always succeeds.
+            ///
+            pub fn finalize_xof(mut self, output: &mut [u8]) -> SgxError {
+                self.sponge.squeeze(output);
+                Ok(())
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> $name {
+                $name::new()
+            }
+        }
+    };
+}
+
+impl_shake! {
+    ///
+    /// SHAKE128 as defined in FIPS 202, providing up to 128 bits of
+    /// security.
+    ///
+    /// Use `sha::ShakeXof` to read the output in several parts.
+    ///
+    Shake128, SHAKE128_RATE
+}
+
+impl_shake! {
+    ///
+    /// SHAKE256 as defined in FIPS 202, providing up to 256 bits of
+    /// security.
+    ///
+    /// Use `sha::ShakeXof` to read the output in several parts.
+    ///
+    Shake256, SHAKE256_RATE
+}
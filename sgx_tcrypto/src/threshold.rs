@@ -0,0 +1,1223 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+//!
+//! Threshold ECDSA over NIST P-256
+//!
+//! A trusted dealer, normally a short-lived enclave, generates the key and
+//! splits it with Feldman's verifiable secret sharing: any `threshold` of the
+//! `parties` shares can sign, fewer learn nothing about the key, and every
+//! share can be checked against the public commitments.
+//!
+//! ECDSA is not linear in the nonce, so the dealer also generates one-time
+//! presignatures. Each one fixes a nonce `k` and shares `k^-1` and `k^-1 * x`
+//! with the same threshold. A partial signature is then linear in the shares,
+//! and any `threshold` partials interpolate to the standard signature
+//! `s = k^-1 * (z + r * x)`. A presignature must never sign two messages, so
+//! `ThresholdEcdsa::partial_sign` consumes it.
+//!
+//! Every party holds a share of the same presignatures, so two disjoint sets
+//! of `threshold` parties could each sign a different message with one
+//! presignature, and the two signatures would reveal `k` and the key. Keys
+//! therefore need fewer than `2 * threshold` parties: any two signing sets
+//! then share a party, which refuses to use a presignature twice.
+//!
+//! The result is an ordinary ECDSA P-256 signature over the SHA-256 hash of
+//! the message, accepted by `SgxEccHandle::ecdsa_verify_slice`.
+//!
+use crate::crypto::rsgx_sha256_slice;
+use crate::util::zeroize;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ptr;
+use sgx_types::*;
+
+/// The largest number of parties a key can be split between.
+pub const THRESHOLD_MAX_PARTIES: usize = 255;
+
+/// The largest number of presignatures a key can be generated with.
+pub const THRESHOLD_MAX_PRESIGNATURES: usize = 65536;
+
+/// The length of a serialized `PartialSignature`.
+pub const THRESHOLD_PARTIAL_SIGNATURE_SIZE: usize = 8 + 3 * SCALAR_SIZE;
+
+const SCALAR_SIZE: usize = SGX_ECP256_KEY_SIZE;
+const POINT_SIZE: usize = 2 * SGX_ECP256_KEY_SIZE;
+const PRESIGNATURE_SIZE: usize = 1 + 3 * SCALAR_SIZE;
+const SHARE_HEADER_SIZE: usize = 16 + SCALAR_SIZE;
+
+///
+/// Threshold ECDSA with a trusted dealer and one-time presignatures.
+///
+pub struct ThresholdEcdsa;
+
+impl ThresholdEcdsa {
+    ///
+    /// keygen generates a P-256 key and splits it into `parties` shares, any
+    /// `threshold` of which can sign.
+    ///
+    /// The key and the nonces only exist in the calling enclave, which must
+    /// hand each share to its party over a secure channel and then erase
+    /// everything but the public key.
+    ///
+    /// # Parameters
+    ///
+    /// **parties**
+    ///
+    /// The number of shares. Share `i` has index `i + 1`. Must be smaller
+    /// than `2 * threshold`.
+    ///
+    /// **threshold**
+    ///
+    /// The number of shares needed to sign, at least 2.
+    ///
+    /// **presignatures**
+    ///
+    /// The number of signatures the shares can produce.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// The shares and the public key with the Feldman commitments.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `threshold` is smaller than 2 or larger than `parties`, `parties` is
+    /// at least `2 * threshold` or larger than `THRESHOLD_MAX_PARTIES`, or
+    /// `presignatures` is 0 or larger than `THRESHOLD_MAX_PRESIGNATURES`.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The random number generator failed.
+    ///
+    pub fn keygen(
+        parties: usize,
+        threshold: usize,
+        presignatures: usize,
+    ) -> SgxResult<(Vec<ThresholdShare>, ThresholdPublicKey)> {
+        if threshold > parties
+            || parties > THRESHOLD_MAX_PARTIES
+            || !valid_sizes(parties as u32, threshold as u32)
+            || presignatures == 0
+            || presignatures > THRESHOLD_MAX_PRESIGNATURES
+        {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let key = Polynomial::random(threshold, None)?;
+        let mut commitments = Vec::with_capacity(threshold);
+        for coefficient in key.0.iter() {
+            let point = Point::mul_base(coefficient).to_public();
+            commitments.push(point.ok_or(sgx_status_t::SGX_ERROR_UNEXPECTED)?);
+        }
+
+        let mut shares = Vec::with_capacity(parties);
+        for index in 1..=parties as u32 {
+            shares.push(ThresholdShare {
+                index,
+                parties: parties as u32,
+                threshold: threshold as u32,
+                secret: key.eval(index),
+                presignatures: Vec::with_capacity(presignatures),
+            });
+        }
+
+        for _ in 0..presignatures {
+            let (k, r) = loop {
+                let k = Scalar::random()?;
+                let x = Point::mul_base(&k).affine_x();
+                if let Some(r) = x.map(|x| Scalar::from_bytes_reduced(&x.to_bytes())) {
+                    if !r.is_zero() {
+                        break (k, r);
+                    }
+                }
+            };
+            let rho = k.invert();
+            let sigma = rho.mul(&key.0[0]);
+            let rho = Polynomial::random(threshold, Some(rho))?;
+            let sigma = Polynomial::random(threshold, Some(sigma))?;
+            for share in shares.iter_mut() {
+                share.presignatures.push(Some(Presignature {
+                    r: r.clone(),
+                    rho: rho.eval(share.index),
+                    sigma: sigma.eval(share.index),
+                }));
+            }
+        }
+
+        let public = ThresholdPublicKey {
+            parties: parties as u32,
+            threshold: threshold as u32,
+            commitments,
+        };
+        Ok((shares, public))
+    }
+
+    ///
+    /// partial_sign signs `message` with one share and consumes the
+    /// presignature.
+    ///
+    /// All the parties must sign the same message with the same presignature.
+    /// `keygen` makes every two signing sets overlap, so a party that refuses
+    /// to reuse a presignature keeps two messages from being signed with it.
+    /// The updated share must be stored before the partial signature is
+    /// released, or a rollback could make the share sign twice with one
+    /// presignature and leak the key.
+    ///
+    /// # Parameters
+    ///
+    /// **share**
+    ///
+    /// The share of the signing party.
+    ///
+    /// **presignature**
+    ///
+    /// The index of the presignature, agreed on by the parties.
+    ///
+    /// **message**
+    ///
+    /// The message to sign. It is hashed with SHA-256.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `presignature` is out of range, or `message` is empty or too large.
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The presignature was already used.
+    ///
+    pub fn partial_sign(
+        share: &mut ThresholdShare,
+        presignature: usize,
+        message: &[u8],
+    ) -> SgxResult<PartialSignature> {
+        let slot = share
+            .presignatures
+            .get_mut(presignature)
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        if slot.is_none() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+        let digest = rsgx_sha256_slice(message)?;
+        let pre = slot.take().ok_or(sgx_status_t::SGX_ERROR_INVALID_STATE)?;
+
+        // s_i = rho_i * z + r * sigma_i
+        let z = Scalar::from_digest(&digest);
+        let s = pre.rho.mul(&z).add(&pre.r.mul(&pre.sigma));
+        Ok(PartialSignature {
+            index: share.index,
+            presignature: presignature as u32,
+            digest,
+            r: pre.r.to_bytes(),
+            s: s.to_bytes(),
+        })
+    }
+
+    ///
+    /// combine interpolates partial signatures into an ECDSA signature and
+    /// verifies it with the public key.
+    ///
+    /// The first `threshold` partials are used; the rest are only checked to
+    /// be for the same message and presignature.
+    ///
+    /// # Parameters
+    ///
+    /// **public**
+    ///
+    /// The public key the shares belong to.
+    ///
+    /// **partials**
+    ///
+    /// At least `threshold` partial signatures from different parties.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// The signature, in the layout of `sgx_ecdsa_sign`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// There are fewer than `threshold` partials, two partials are from the
+    /// same party, an index is out of range, or the partials are for
+    /// different messages or presignatures.
+    ///
+    /// **SGX_ERROR_INVALID_SIGNATURE**
+    ///
+    /// A partial signature is wrong or is not from a share of `public`.
+    ///
+    pub fn combine(
+        public: &ThresholdPublicKey,
+        partials: &[PartialSignature],
+    ) -> SgxResult<sgx_ec256_signature_t> {
+        let threshold = public.threshold as usize;
+        let first = match partials.first() {
+            Some(first) if partials.len() >= threshold => first,
+            _ => return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        };
+        for (i, partial) in partials.iter().enumerate() {
+            if partial.index == 0
+                || partial.index > public.parties
+                || partial.presignature != first.presignature
+                || partial.digest != first.digest
+                || partial.r != first.r
+                || partials[..i].iter().any(|p| p.index == partial.index)
+            {
+                return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+            }
+        }
+
+        let signers = &partials[..threshold];
+        let mut s = Scalar::zero();
+        for partial in signers {
+            let share = Scalar::from_canonical_bytes(&partial.s)
+                .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+            let lambda = lagrange_at_zero(partial.index, signers.iter().map(|p| p.index));
+            s = s.add(&lambda.mul(&share));
+        }
+        let r = Scalar::from_canonical_bytes(&first.r)
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        let z = Scalar::from_digest(&first.digest);
+
+        let key = Point::from_public(public.public_key())
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        if !verify_digest(&key, &z, &r, &s) {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_SIGNATURE);
+        }
+
+        let mut signature = sgx_ec256_signature_t::default();
+        for (word, bytes) in signature.x.iter_mut().zip(r.to_bytes().chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for (word, bytes) in signature.y.iter_mut().zip(s.to_bytes().chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        Ok(signature)
+    }
+}
+
+///
+/// The public key of a threshold key, with the Feldman commitments to the
+/// coefficients of the sharing polynomial.
+///
+#[derive(Clone)]
+pub struct ThresholdPublicKey {
+    parties: u32,
+    threshold: u32,
+    commitments: Vec<sgx_ec256_public_t>,
+}
+
+impl ThresholdPublicKey {
+    ///
+    /// public_key returns the ECDSA public key, in little-endian byte order.
+    ///
+    pub fn public_key(&self) -> &sgx_ec256_public_t {
+        &self.commitments[0]
+    }
+
+    pub fn parties(&self) -> usize {
+        self.parties as usize
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.threshold as usize
+    }
+
+    ///
+    /// verify_share checks that `share` is a share of this key, so a party
+    /// can detect a dealer that handed out inconsistent shares.
+    ///
+    /// Presignatures are not covered; a bad one makes `combine` fail with
+    /// **SGX_ERROR_INVALID_SIGNATURE**.
+    ///
+    pub fn verify_share(&self, share: &ThresholdShare) -> bool {
+        if share.parties != self.parties
+            || share.threshold != self.threshold
+            || share.index == 0
+            || share.index > self.parties
+        {
+            return false;
+        }
+
+        // sum of index^j * C_j, by Horner's rule
+        let index = Scalar::from_u32(share.index);
+        let mut expected = Point::identity();
+        for commitment in self.commitments.iter().rev() {
+            let commitment = match Point::from_public(commitment) {
+                Some(point) => point,
+                None => return false,
+            };
+            expected = expected.mul(&index).add(&commitment);
+        }
+        match (
+            Point::mul_base(&share.secret).to_public(),
+            expected.to_public(),
+        ) {
+            (Some(actual), Some(expected)) => actual.gx == expected.gx && actual.gy == expected.gy,
+            _ => false,
+        }
+    }
+
+    ///
+    /// to_vec serializes the public key as parties (4 bytes) || threshold
+    /// (4 bytes) || the `threshold` commitments (`gx || gy`), all in
+    /// little-endian byte order.
+    ///
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(8 + self.commitments.len() * POINT_SIZE);
+        out.extend_from_slice(&self.parties.to_le_bytes());
+        out.extend_from_slice(&self.threshold.to_le_bytes());
+        for commitment in self.commitments.iter() {
+            out.extend_from_slice(&commitment.gx);
+            out.extend_from_slice(&commitment.gy);
+        }
+        out
+    }
+
+    ///
+    /// from_slice parses a public key serialized by `to_vec`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The encoding is malformed or a commitment is not on the curve.
+    ///
+    pub fn from_slice(bytes: &[u8]) -> SgxResult<ThresholdPublicKey> {
+        if bytes.len() < 8 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let parties = read_u32(&bytes[0..4]);
+        let threshold = read_u32(&bytes[4..8]);
+        if !valid_sizes(parties, threshold) || bytes.len() - 8 != threshold as usize * POINT_SIZE {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut commitments = Vec::with_capacity(threshold as usize);
+        for point in bytes[8..].chunks_exact(POINT_SIZE) {
+            let mut commitment = sgx_ec256_public_t::default();
+            commitment.gx.copy_from_slice(&point[..SCALAR_SIZE]);
+            commitment.gy.copy_from_slice(&point[SCALAR_SIZE..]);
+            if Point::from_public(&commitment).is_none() {
+                return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+            }
+            commitments.push(commitment);
+        }
+        Ok(ThresholdPublicKey {
+            parties,
+            threshold,
+            commitments,
+        })
+    }
+}
+
+impl fmt::Debug for ThresholdPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThresholdPublicKey")
+            .field("parties", &self.parties)
+            .field("threshold", &self.threshold)
+            .field("gx", &self.commitments[0].gx)
+            .field("gy", &self.commitments[0].gy)
+            .finish_non_exhaustive()
+    }
+}
+
+///
+/// The share of one party, with its shares of the unused presignatures.
+///
+/// The secrets are erased when the share is dropped.
+///
+pub struct ThresholdShare {
+    index: u32,
+    parties: u32,
+    threshold: u32,
+    secret: Scalar,
+    presignatures: Vec<Option<Presignature>>,
+}
+
+struct Presignature {
+    r: Scalar,
+    rho: Scalar,
+    sigma: Scalar,
+}
+
+impl ThresholdShare {
+    ///
+    /// index returns the index of the party, from 1 to `parties`.
+    ///
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+
+    pub fn parties(&self) -> usize {
+        self.parties as usize
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.threshold as usize
+    }
+
+    ///
+    /// presignatures returns the number of presignatures, used or not.
+    ///
+    pub fn presignatures(&self) -> usize {
+        self.presignatures.len()
+    }
+
+    ///
+    /// is_presignature_used returns true if the presignature was consumed by
+    /// `ThresholdEcdsa::partial_sign`, or does not exist.
+    ///
+    pub fn is_presignature_used(&self, presignature: usize) -> bool {
+        !matches!(self.presignatures.get(presignature), Some(Some(_)))
+    }
+
+    ///
+    /// to_vec serializes the share, including which presignatures are used.
+    ///
+    /// The output contains the secret share and must be sealed or encrypted
+    /// before it leaves the enclave.
+    ///
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut out =
+            Vec::with_capacity(SHARE_HEADER_SIZE + self.presignatures.len() * PRESIGNATURE_SIZE);
+        out.extend_from_slice(&self.index.to_le_bytes());
+        out.extend_from_slice(&self.parties.to_le_bytes());
+        out.extend_from_slice(&self.threshold.to_le_bytes());
+        out.extend_from_slice(&(self.presignatures.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.secret.to_bytes());
+        for slot in self.presignatures.iter() {
+            match slot {
+                Some(pre) => {
+                    out.push(1);
+                    out.extend_from_slice(&pre.r.to_bytes());
+                    out.extend_from_slice(&pre.rho.to_bytes());
+                    out.extend_from_slice(&pre.sigma.to_bytes());
+                }
+                None => out.extend_from_slice(&[0_u8; PRESIGNATURE_SIZE]),
+            }
+        }
+        out
+    }
+
+    ///
+    /// from_slice parses a share serialized by `to_vec`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The encoding is malformed.
+    ///
+    pub fn from_slice(bytes: &[u8]) -> SgxResult<ThresholdShare> {
+        let invalid = sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+        if bytes.len() < SHARE_HEADER_SIZE {
+            return Err(invalid);
+        }
+        let index = read_u32(&bytes[0..4]);
+        let parties = read_u32(&bytes[4..8]);
+        let threshold = read_u32(&bytes[8..12]);
+        let count = read_u32(&bytes[12..16]) as usize;
+        if !valid_sizes(parties, threshold)
+            || index == 0
+            || index > parties
+            || count > THRESHOLD_MAX_PRESIGNATURES
+            || bytes.len() - SHARE_HEADER_SIZE != count * PRESIGNATURE_SIZE
+        {
+            return Err(invalid);
+        }
+        let secret = Scalar::from_canonical_bytes(&bytes[16..SHARE_HEADER_SIZE]).ok_or(invalid)?;
+
+        let mut presignatures = Vec::with_capacity(count);
+        for pre in bytes[SHARE_HEADER_SIZE..].chunks_exact(PRESIGNATURE_SIZE) {
+            let (flag, scalars) = pre.split_at(1);
+            let slot = match flag[0] {
+                0 if scalars.iter().all(|&b| b == 0) => None,
+                1 => {
+                    let (r, rest) = scalars.split_at(SCALAR_SIZE);
+                    let (rho, sigma) = rest.split_at(SCALAR_SIZE);
+                    let r = Scalar::from_canonical_bytes(r)
+                        .filter(|r| !r.is_zero())
+                        .ok_or(invalid)?;
+                    Some(Presignature {
+                        r,
+                        rho: Scalar::from_canonical_bytes(rho).ok_or(invalid)?,
+                        sigma: Scalar::from_canonical_bytes(sigma).ok_or(invalid)?,
+                    })
+                }
+                _ => return Err(invalid),
+            };
+            presignatures.push(slot);
+        }
+        Ok(ThresholdShare {
+            index,
+            parties,
+            threshold,
+            secret,
+            presignatures,
+        })
+    }
+}
+
+impl fmt::Debug for ThresholdShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThresholdShare")
+            .field("index", &self.index)
+            .field("parties", &self.parties)
+            .field("threshold", &self.threshold)
+            .field("presignatures", &self.presignatures.len())
+            .finish_non_exhaustive()
+    }
+}
+
+///
+/// The signature share of one party.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PartialSignature {
+    index: u32,
+    presignature: u32,
+    digest: sgx_sha256_hash_t,
+    r: [u8; SCALAR_SIZE],
+    s: [u8; SCALAR_SIZE],
+}
+
+impl PartialSignature {
+    ///
+    /// index returns the index of the party that produced the partial.
+    ///
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+
+    pub fn presignature(&self) -> usize {
+        self.presignature as usize
+    }
+
+    ///
+    /// digest returns the SHA-256 hash of the signed message.
+    ///
+    pub fn digest(&self) -> &sgx_sha256_hash_t {
+        &self.digest
+    }
+
+    ///
+    /// to_bytes serializes the partial as index (4 bytes) || presignature
+    /// (4 bytes) || digest || r || s, with integers in little-endian byte
+    /// order.
+    ///
+    pub fn to_bytes(&self) -> [u8; THRESHOLD_PARTIAL_SIGNATURE_SIZE] {
+        let mut out = [0_u8; THRESHOLD_PARTIAL_SIGNATURE_SIZE];
+        out[0..4].copy_from_slice(&self.index.to_le_bytes());
+        out[4..8].copy_from_slice(&self.presignature.to_le_bytes());
+        out[8..40].copy_from_slice(&self.digest);
+        out[40..72].copy_from_slice(&self.r);
+        out[72..].copy_from_slice(&self.s);
+        out
+    }
+
+    ///
+    /// from_bytes parses a partial serialized by `to_bytes`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `bytes` has the wrong length.
+    ///
+    pub fn from_bytes(bytes: &[u8]) -> SgxResult<PartialSignature> {
+        if bytes.len() != THRESHOLD_PARTIAL_SIGNATURE_SIZE {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let mut partial = PartialSignature {
+            index: read_u32(&bytes[0..4]),
+            presignature: read_u32(&bytes[4..8]),
+            digest: [0; SGX_SHA256_HASH_SIZE],
+            r: [0; SCALAR_SIZE],
+            s: [0; SCALAR_SIZE],
+        };
+        partial.digest.copy_from_slice(&bytes[8..40]);
+        partial.r.copy_from_slice(&bytes[40..72]);
+        partial.s.copy_from_slice(&bytes[72..]);
+        Ok(partial)
+    }
+}
+
+fn valid_sizes(parties: u32, threshold: u32) -> bool {
+    threshold >= 2
+        && parties as usize <= THRESHOLD_MAX_PARTIES
+        && threshold <= parties
+        && parties < 2 * threshold
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+// The Lagrange coefficient of `index` for interpolating at 0 from the
+// points `indices`.
+fn lagrange_at_zero(index: u32, indices: impl Iterator<Item = u32>) -> Scalar {
+    let mut num = Scalar::one();
+    let mut den = Scalar::one();
+    let x = Scalar::from_u32(index);
+    for other in indices.filter(|&other| other != index) {
+        let other = Scalar::from_u32(other);
+        num = num.mul(&other);
+        den = den.mul(&other.sub(&x));
+    }
+    num.mul(&den.invert())
+}
+
+fn verify_digest(key: &Point, z: &Scalar, r: &Scalar, s: &Scalar) -> bool {
+    if r.is_zero() || s.is_zero() {
+        return false;
+    }
+    let w = s.invert();
+    let point = Point::mul_base(&z.mul(&w)).add(&key.mul(&r.mul(&w)));
+    match point.affine_x() {
+        Some(x) => Scalar::from_bytes_reduced(&x.to_bytes()).to_bytes() == r.to_bytes(),
+        None => false,
+    }
+}
+
+// A random polynomial of degree `threshold - 1` over the scalars.
+struct Polynomial(Vec<Scalar>);
+
+impl Polynomial {
+    fn random(threshold: usize, constant: Option<Scalar>) -> SgxResult<Polynomial> {
+        let mut coefficients = Vec::with_capacity(threshold);
+        if let Some(constant) = constant {
+            coefficients.push(constant);
+        }
+        while coefficients.len() < threshold {
+            coefficients.push(Scalar::random()?);
+        }
+        Ok(Polynomial(coefficients))
+    }
+
+    fn eval(&self, x: u32) -> Scalar {
+        let x = Scalar::from_u32(x);
+        let mut y = Scalar::zero();
+        for coefficient in self.0.iter().rev() {
+            y = y.mul(&x).add(coefficient);
+        }
+        y
+    }
+}
+
+// NIST P-256, y^2 = x^3 - 3x + b over GF(p), with a generator of prime
+// order n. Field elements and scalars are kept in Montgomery form in four
+// little-endian 64-bit limbs.
+
+struct Modulus {
+    m: [u64; 4],
+    // -1 / m mod 2^64
+    inv: u64,
+    // 2^512 mod m
+    r2: [u64; 4],
+}
+
+const P256_P: Modulus = Modulus {
+    m: [
+        0xffff_ffff_ffff_ffff,
+        0x0000_0000_ffff_ffff,
+        0x0000_0000_0000_0000,
+        0xffff_ffff_0000_0001,
+    ],
+    inv: 1,
+    r2: [
+        0x0000_0000_0000_0003,
+        0xffff_fffb_ffff_ffff,
+        0xffff_ffff_ffff_fffe,
+        0x0000_0004_ffff_fffd,
+    ],
+};
+
+const P256_N: Modulus = Modulus {
+    m: [
+        0xf3b9_cac2_fc63_2551,
+        0xbce6_faad_a717_9e84,
+        0xffff_ffff_ffff_ffff,
+        0xffff_ffff_0000_0000,
+    ],
+    inv: 0xccd1_c8aa_ee00_bc4f,
+    r2: [
+        0x8324_4c95_be79_eea2,
+        0x4699_799c_49bd_6fa6,
+        0x2845_b239_2b6b_ec59,
+        0x66e1_2d94_f3d9_5620,
+    ],
+};
+
+const P256_B: [u64; 4] = [
+    0x3bce_3c3e_27d2_604b,
+    0x651d_06b0_cc53_b0f6,
+    0xb3eb_bd55_7698_86bc,
+    0x5ac6_35d8_aa3a_93e7,
+];
+
+const P256_GX: [u64; 4] = [
+    0xf4a1_3945_d898_c296,
+    0x7703_7d81_2deb_33a0,
+    0xf8bc_e6e5_63a4_40f2,
+    0x6b17_d1f2_e12c_4247,
+];
+
+const P256_GY: [u64; 4] = [
+    0xcbb6_4068_37bf_51f5,
+    0x2bce_3357_6b31_5ece,
+    0x8ee7_eb4a_7c0f_9e16,
+    0x4fe3_42e2_fe1a_7f9b,
+];
+
+fn load(bytes: &[u8]) -> [u64; 4] {
+    let mut limbs = [0_u64; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(8)) {
+        let mut word = [0_u8; 8];
+        word.copy_from_slice(chunk);
+        *limb = u64::from_le_bytes(word);
+    }
+    limbs
+}
+
+fn store(limbs: &[u64; 4]) -> [u8; 32] {
+    let mut bytes = [0_u8; 32];
+    for (chunk, limb) in bytes.chunks_exact_mut(8).zip(limbs.iter()) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+    bytes
+}
+
+fn add_limbs(a: &[u64; 4], b: &[u64; 4]) -> ([u64; 4], u64) {
+    let mut sum = [0_u64; 4];
+    let mut carry = 0_u64;
+    for i in 0..4 {
+        let (v, c1) = a[i].overflowing_add(b[i]);
+        let (v, c2) = v.overflowing_add(carry);
+        sum[i] = v;
+        carry = u64::from(c1 | c2);
+    }
+    (sum, carry)
+}
+
+fn sub_limbs(a: &[u64; 4], b: &[u64; 4]) -> ([u64; 4], u64) {
+    let mut diff = [0_u64; 4];
+    let mut borrow = 0_u64;
+    for i in 0..4 {
+        let (v, b1) = a[i].overflowing_sub(b[i]);
+        let (v, b2) = v.overflowing_sub(borrow);
+        diff[i] = v;
+        borrow = u64::from(b1 | b2);
+    }
+    (diff, borrow)
+}
+
+// Returns a if mask is all ones, b if it is zero.
+fn select_limbs(mask: u64, a: &[u64; 4], b: &[u64; 4]) -> [u64; 4] {
+    let mut out = [0_u64; 4];
+    for i in 0..4 {
+        out[i] = (a[i] & mask) | (b[i] & !mask);
+    }
+    out
+}
+
+// Reduces x + hi * 2^256 < 2m.
+fn reduce_once(x: &[u64; 4], hi: u64, m: &[u64; 4]) -> [u64; 4] {
+    let (diff, borrow) = sub_limbs(x, m);
+    let keep = 0_u64.wrapping_sub(borrow & !hi & 1);
+    select_limbs(keep, x, &diff)
+}
+
+fn add_mod(a: &[u64; 4], b: &[u64; 4], md: &Modulus) -> [u64; 4] {
+    let (sum, carry) = add_limbs(a, b);
+    reduce_once(&sum, carry, &md.m)
+}
+
+fn sub_mod(a: &[u64; 4], b: &[u64; 4], md: &Modulus) -> [u64; 4] {
+    let (diff, borrow) = sub_limbs(a, b);
+    let (wrapped, _) = add_limbs(&diff, &md.m);
+    select_limbs(0_u64.wrapping_sub(borrow), &wrapped, &diff)
+}
+
+// a * b / 2^256 mod m
+fn mont_mul(a: &[u64; 4], b: &[u64; 4], md: &Modulus) -> [u64; 4] {
+    let mut t = [0_u64; 6];
+    for &bi in b.iter() {
+        let mut carry = 0_u128;
+        for j in 0..4 {
+            let v = u128::from(t[j]) + u128::from(a[j]) * u128::from(bi) + carry;
+            t[j] = v as u64;
+            carry = v >> 64;
+        }
+        let v = u128::from(t[4]) + carry;
+        t[4] = v as u64;
+        t[5] = (v >> 64) as u64;
+
+        let q = t[0].wrapping_mul(md.inv);
+        let mut carry = (u128::from(t[0]) + u128::from(q) * u128::from(md.m[0])) >> 64;
+        for j in 1..4 {
+            let v = u128::from(t[j]) + u128::from(q) * u128::from(md.m[j]) + carry;
+            t[j - 1] = v as u64;
+            carry = v >> 64;
+        }
+        let v = u128::from(t[4]) + carry;
+        t[3] = v as u64;
+        t[4] = t[5] + (v >> 64) as u64;
+    }
+    // t < 2m
+    reduce_once(&[t[0], t[1], t[2], t[3]], t[4], &md.m)
+}
+
+// a^(m - 2), the inverse of a for a prime m. The exponent is public.
+fn mont_invert(a: &[u64; 4], md: &Modulus) -> [u64; 4] {
+    let (e, _) = sub_limbs(&md.m, &[2, 0, 0, 0]);
+    let mut r = mont_mul(&[1, 0, 0, 0], &md.r2, md);
+    for i in (0..256).rev() {
+        r = mont_mul(&r, &r, md);
+        if (e[i / 64] >> (i % 64)) & 1 == 1 {
+            r = mont_mul(&r, a, md);
+        }
+    }
+    r
+}
+
+fn is_less(a: &[u64; 4], m: &[u64; 4]) -> bool {
+    sub_limbs(a, m).1 == 1
+}
+
+fn is_zero_limbs(a: &[u64; 4]) -> bool {
+    a.iter().fold(0, |acc, limb| acc | limb) == 0
+}
+
+#[derive(Clone, Copy)]
+struct FieldElement([u64; 4]);
+
+impl FieldElement {
+    const ZERO: FieldElement = FieldElement([0; 4]);
+
+    fn from_limbs(x: &[u64; 4]) -> FieldElement {
+        FieldElement(mont_mul(x, &P256_P.r2, &P256_P))
+    }
+
+    fn one() -> FieldElement {
+        FieldElement::from_limbs(&[1, 0, 0, 0])
+    }
+
+    fn from_bytes(bytes: &[u8; 32]) -> Option<FieldElement> {
+        let x = load(bytes);
+        if is_less(&x, &P256_P.m) {
+            Some(FieldElement::from_limbs(&x))
+        } else {
+            None
+        }
+    }
+
+    fn to_bytes(self) -> [u8; 32] {
+        store(&mont_mul(&self.0, &[1, 0, 0, 0], &P256_P))
+    }
+
+    fn add(&self, other: &FieldElement) -> FieldElement {
+        FieldElement(add_mod(&self.0, &other.0, &P256_P))
+    }
+
+    fn sub(&self, other: &FieldElement) -> FieldElement {
+        FieldElement(sub_mod(&self.0, &other.0, &P256_P))
+    }
+
+    fn mul(&self, other: &FieldElement) -> FieldElement {
+        FieldElement(mont_mul(&self.0, &other.0, &P256_P))
+    }
+
+    fn square(&self) -> FieldElement {
+        self.mul(self)
+    }
+
+    fn invert(&self) -> FieldElement {
+        FieldElement(mont_invert(&self.0, &P256_P))
+    }
+
+    fn is_zero(&self) -> bool {
+        is_zero_limbs(&self.0)
+    }
+
+    fn select(mask: u64, a: &FieldElement, b: &FieldElement) -> FieldElement {
+        FieldElement(select_limbs(mask, &a.0, &b.0))
+    }
+}
+
+// An integer modulo n, erased when dropped.
+struct Scalar([u64; 4]);
+
+impl Scalar {
+    fn from_limbs(x: &[u64; 4]) -> Scalar {
+        Scalar(mont_mul(x, &P256_N.r2, &P256_N))
+    }
+
+    fn zero() -> Scalar {
+        Scalar([0; 4])
+    }
+
+    fn one() -> Scalar {
+        Scalar::from_u32(1)
+    }
+
+    fn from_u32(x: u32) -> Scalar {
+        Scalar::from_limbs(&[u64::from(x), 0, 0, 0])
+    }
+
+    fn from_canonical_bytes(bytes: &[u8]) -> Option<Scalar> {
+        let x = load(bytes);
+        if is_less(&x, &P256_N.m) {
+            Some(Scalar::from_limbs(&x))
+        } else {
+            None
+        }
+    }
+
+    // x mod n for any 256 bit x, which is less than 2n.
+    fn from_bytes_reduced(bytes: &[u8; 32]) -> Scalar {
+        Scalar::from_limbs(&reduce_once(&load(bytes), 0, &P256_N.m))
+    }
+
+    // The leftmost 256 bits of a hash, as ECDSA uses them.
+    fn from_digest(digest: &sgx_sha256_hash_t) -> Scalar {
+        let mut bytes = *digest;
+        bytes.reverse();
+        Scalar::from_bytes_reduced(&bytes)
+    }
+
+    fn random() -> SgxResult<Scalar> {
+        let mut bytes = [0_u8; 32];
+        loop {
+            let ret = unsafe { sgx_read_rand(bytes.as_mut_ptr(), bytes.len()) };
+            if ret != sgx_status_t::SGX_SUCCESS {
+                return Err(ret);
+            }
+            let scalar = Scalar::from_canonical_bytes(&bytes);
+            zeroize(&mut bytes);
+            if let Some(scalar) = scalar.filter(|k| !k.is_zero()) {
+                return Ok(scalar);
+            }
+        }
+    }
+
+    fn to_limbs(&self) -> [u64; 4] {
+        mont_mul(&self.0, &[1, 0, 0, 0], &P256_N)
+    }
+
+    fn to_bytes(&self) -> [u8; 32] {
+        store(&self.to_limbs())
+    }
+
+    fn add(&self, other: &Scalar) -> Scalar {
+        Scalar(add_mod(&self.0, &other.0, &P256_N))
+    }
+
+    fn sub(&self, other: &Scalar) -> Scalar {
+        Scalar(sub_mod(&self.0, &other.0, &P256_N))
+    }
+
+    fn mul(&self, other: &Scalar) -> Scalar {
+        Scalar(mont_mul(&self.0, &other.0, &P256_N))
+    }
+
+    fn invert(&self) -> Scalar {
+        Scalar(mont_invert(&self.0, &P256_N))
+    }
+
+    fn is_zero(&self) -> bool {
+        is_zero_limbs(&self.0)
+    }
+}
+
+impl Clone for Scalar {
+    fn clone(&self) -> Scalar {
+        Scalar(self.0)
+    }
+}
+
+impl Drop for Scalar {
+    fn drop(&mut self) {
+        for limb in self.0.iter_mut() {
+            unsafe { ptr::write_volatile(limb, 0) };
+        }
+    }
+}
+
+// A point in projective coordinates (X : Y : Z), with x = X / Z and
+// y = Y / Z. The identity is (0 : 1 : 0).
+#[derive(Clone, Copy)]
+struct Point {
+    x: FieldElement,
+    y: FieldElement,
+    z: FieldElement,
+}
+
+impl Point {
+    fn identity() -> Point {
+        Point {
+            x: FieldElement::ZERO,
+            y: FieldElement::one(),
+            z: FieldElement::ZERO,
+        }
+    }
+
+    fn generator() -> Point {
+        Point {
+            x: FieldElement::from_limbs(&P256_GX),
+            y: FieldElement::from_limbs(&P256_GY),
+            z: FieldElement::one(),
+        }
+    }
+
+    fn from_public(key: &sgx_ec256_public_t) -> Option<Point> {
+        let x = FieldElement::from_bytes(&key.gx)?;
+        let y = FieldElement::from_bytes(&key.gy)?;
+        // y^2 = x^3 - 3x + b
+        let three_x = x.add(&x).add(&x);
+        let rhs = x
+            .square()
+            .mul(&x)
+            .sub(&three_x)
+            .add(&FieldElement::from_limbs(&P256_B));
+        if y.square().sub(&rhs).is_zero() {
+            Some(Point {
+                x,
+                y,
+                z: FieldElement::one(),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn to_public(self) -> Option<sgx_ec256_public_t> {
+        if self.z.is_zero() {
+            return None;
+        }
+        let z_inv = self.z.invert();
+        Some(sgx_ec256_public_t {
+            gx: self.x.mul(&z_inv).to_bytes(),
+            gy: self.y.mul(&z_inv).to_bytes(),
+        })
+    }
+
+    fn affine_x(self) -> Option<FieldElement> {
+        if self.z.is_zero() {
+            None
+        } else {
+            Some(self.x.mul(&self.z.invert()))
+        }
+    }
+
+    // Complete addition for a = -3 (Renes, Costello and Batina 2016,
+    // algorithm 4), correct for any two points including equal ones and
+    // the identity.
+    fn add(&self, other: &Point) -> Point {
+        let b = FieldElement::from_limbs(&P256_B);
+        let (x1, y1, z1) = (&self.x, &self.y, &self.z);
+        let (x2, y2, z2) = (&other.x, &other.y, &other.z);
+
+        let mut t0 = x1.mul(x2);
+        let mut t1 = y1.mul(y2);
+        let mut t2 = z1.mul(z2);
+        let mut t3 = x1.add(y1);
+        let mut t4 = x2.add(y2);
+        t3 = t3.mul(&t4);
+        t4 = t0.add(&t1);
+        t3 = t3.sub(&t4);
+        t4 = y1.add(z1);
+        let mut x3 = y2.add(z2);
+        t4 = t4.mul(&x3);
+        x3 = t1.add(&t2);
+        t4 = t4.sub(&x3);
+        x3 = x1.add(z1);
+        let mut y3 = x2.add(z2);
+        x3 = x3.mul(&y3);
+        y3 = t0.add(&t2);
+        y3 = x3.sub(&y3);
+        let mut z3 = b.mul(&t2);
+        x3 = y3.sub(&z3);
+        z3 = x3.add(&x3);
+        x3 = x3.add(&z3);
+        z3 = t1.sub(&x3);
+        x3 = t1.add(&x3);
+        y3 = b.mul(&y3);
+        t1 = t2.add(&t2);
+        t2 = t1.add(&t2);
+        y3 = y3.sub(&t2);
+        y3 = y3.sub(&t0);
+        t1 = y3.add(&y3);
+        y3 = t1.add(&y3);
+        t1 = t0.add(&t0);
+        t0 = t1.add(&t0);
+        t0 = t0.sub(&t2);
+        t1 = t4.mul(&y3);
+        t2 = t0.mul(&y3);
+        y3 = x3.mul(&z3);
+        y3 = y3.add(&t2);
+        x3 = t3.mul(&x3);
+        x3 = x3.sub(&t1);
+        z3 = t4.mul(&z3);
+        t1 = t3.mul(&t0);
+        z3 = z3.add(&t1);
+
+        Point {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    // Double and always add, in time independent of k.
+    fn mul(&self, k: &Scalar) -> Point {
+        let mut bits = k.to_limbs();
+        let mut acc = Point::identity();
+        for i in (0..256).rev() {
+            acc = acc.add(&acc);
+            let sum = acc.add(self);
+            let mask = 0_u64.wrapping_sub((bits[i / 64] >> (i % 64)) & 1);
+            acc = Point {
+                x: FieldElement::select(mask, &sum.x, &acc.x),
+                y: FieldElement::select(mask, &sum.y, &acc.y),
+                z: FieldElement::select(mask, &sum.z, &acc.z),
+            };
+        }
+        for limb in bits.iter_mut() {
+            unsafe { ptr::write_volatile(limb, 0) };
+        }
+        acc
+    }
+
+    fn mul_base(k: &Scalar) -> Point {
+        Point::generator().mul(k)
+    }
+}
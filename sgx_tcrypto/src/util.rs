@@ -0,0 +1,239 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//!
+//! Helpers for handling secret data
+//!
+//! The `ct_` functions run in time, and with a control flow and memory
+//! access pattern, that do not depend on the secret values they are given,
+//! which makes them usable for oblivious algorithms. On x86_64 the
+//! comparisons and selections are single `cmp`/`setcc` and `cmov`
+//! instructions; elsewhere, and under Miri, they are computed with masks.
+//!
+#[cfg(all(target_arch = "x86_64", not(miri)))]
+use core::arch::asm;
+use core::ptr;
+use core::sync::atomic::{compiler_fence, Ordering};
+
+///
+/// ct_eq checks whether `a` and `b` are equal in constant time.
+///
+/// Every byte up to the shorter length is compared, without stopping at
+/// the first difference, and inputs of different lengths compare unequal
+/// without a branch. The time taken depends only on the shorter length:
+/// it reveals neither whether nor where the contents differ, nor whether
+/// the lengths differ, but it does reveal the length of the shorter input.
+/// This makes it suitable for comparing derived keys, MACs and other
+/// secrets of a public length.
+///
+/// # Return value
+///
+/// **true**
+///
+/// `a` and `b` have the same length and contents.
+///
+/// **false**
+///
+/// Otherwise.
+///
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    let mut diff = ct_select_usize(ct_eq_usize(a.len(), b.len()), 0, 1);
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= (x ^ y) as usize;
+    }
+    ct_eq_usize(diff, 0)
+}
+
+///
+/// ct_select returns `a` if `flag` is set and `b` otherwise, without branching.
+///
+pub fn ct_select(flag: bool, a: u8, b: u8) -> u8 {
+    ct_select_usize(flag, a as usize, b as usize) as u8
+}
+
+///
+/// ct_copy copies `src` into `dst` if `flag` is set, and otherwise leaves
+/// `dst` as it is. Every byte of `dst` is written either way.
+///
+/// # Panics
+///
+/// If `src` and `dst` differ in length.
+///
+pub fn ct_copy(src: &[u8], dst: &mut [u8], flag: bool) {
+    assert_eq!(src.len(), dst.len(), "ct_copy: lengths differ");
+    for (d, s) in dst.iter_mut().zip(src.iter()) {
+        *d = ct_select(flag, *s, *d);
+    }
+}
+
+///
+/// zeroize overwrites `buf` with zeros with volatile writes, which the
+/// compiler can not remove even if `buf` is never read again.
+///
+pub fn zeroize(buf: &mut [u8]) {
+    for b in buf.iter_mut() {
+        unsafe { ptr::write_volatile(b, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+///
+/// A value holding secret data that can be erased in place.
+///
+/// The secret types of this crate erase themselves when dropped as well;
+/// `zeroize` is for erasing a secret early, while the value is still
+/// borrowed or kept for its public parts.
+///
+pub trait Zeroize {
+    fn zeroize(&mut self);
+}
+
+impl Zeroize for [u8] {
+    fn zeroize(&mut self) {
+        zeroize(self);
+    }
+}
+
+impl<const N: usize> Zeroize for [u8; N] {
+    fn zeroize(&mut self) {
+        zeroize(self);
+    }
+}
+
+///
+/// ct_select_usize returns `a` if `flag` is set and `b` otherwise, without branching.
+///
+#[cfg(all(target_arch = "x86_64", not(miri)))]
+pub fn ct_select_usize(flag: bool, a: usize, b: usize) -> usize {
+    let mut ret = a;
+    unsafe {
+        asm!(
+            "test {flag}, {flag}",
+            "cmovz {ret}, {b}",
+            flag = in(reg_byte) flag as u8,
+            b = in(reg) b,
+            ret = inout(reg) ret,
+            options(pure, nomem, nostack),
+        );
+    }
+    ret
+}
+
+#[cfg(not(all(target_arch = "x86_64", not(miri))))]
+pub fn ct_select_usize(flag: bool, a: usize, b: usize) -> usize {
+    let mask = opaque((flag as usize).wrapping_neg());
+    b ^ (mask & (a ^ b))
+}
+
+///
+/// ct_eq_usize checks whether `x == y` without branching.
+///
+#[cfg(all(target_arch = "x86_64", not(miri)))]
+pub fn ct_eq_usize(x: usize, y: usize) -> bool {
+    let ret: u8;
+    unsafe {
+        asm!(
+            "cmp {x}, {y}",
+            "sete {ret}",
+            x = in(reg) x,
+            y = in(reg) y,
+            ret = out(reg_byte) ret,
+            options(pure, nomem, nostack),
+        );
+    }
+    ret != 0
+}
+
+#[cfg(not(all(target_arch = "x86_64", not(miri))))]
+pub fn ct_eq_usize(x: usize, y: usize) -> bool {
+    let diff = opaque(x ^ y);
+    ((diff | diff.wrapping_neg()) >> (usize::BITS - 1)) == 0
+}
+
+///
+/// ct_lt_usize checks whether `x < y` without branching.
+///
+#[cfg(all(target_arch = "x86_64", not(miri)))]
+pub fn ct_lt_usize(x: usize, y: usize) -> bool {
+    let ret: u8;
+    unsafe {
+        asm!(
+            "cmp {x}, {y}",
+            "setb {ret}",
+            x = in(reg) x,
+            y = in(reg) y,
+            ret = out(reg_byte) ret,
+            options(pure, nomem, nostack),
+        );
+    }
+    ret != 0
+}
+
+#[cfg(not(all(target_arch = "x86_64", not(miri))))]
+pub fn ct_lt_usize(x: usize, y: usize) -> bool {
+    let (x, y) = (opaque(x), opaque(y));
+    // the borrow out of x - y
+    (((!x & y) | (!(x ^ y) & x.wrapping_sub(y))) >> (usize::BITS - 1)) == 1
+}
+
+// Hides a value from the optimizer, so masks are not turned back into branches.
+#[cfg(not(all(target_arch = "x86_64", not(miri))))]
+fn opaque(value: usize) -> usize {
+    unsafe { ptr::read_volatile(&value) }
+}
+
+#[cfg(test)]
+mod tests {
+    // These also run under `cargo miri test`, which checks the volatile
+    // and mask-based paths for undefined behaviour.
+    use super::*;
+
+    const VALUES: [usize; 6] = [0, 1, 2, 0x7f, usize::MAX - 1, usize::MAX];
+
+    #[test]
+    fn comparisons() {
+        for &x in VALUES.iter() {
+            for &y in VALUES.iter() {
+                assert_eq!(ct_eq_usize(x, y), x == y);
+                assert_eq!(ct_lt_usize(x, y), x < y);
+                assert_eq!(ct_select_usize(true, x, y), x);
+                assert_eq!(ct_select_usize(false, x, y), y);
+            }
+        }
+        assert_eq!(ct_select(true, 1, 2), 1);
+        assert_eq!(ct_select(false, 1, 2), 2);
+    }
+
+    #[test]
+    fn slices() {
+        assert!(ct_eq(b"", b""));
+        assert!(ct_eq(b"secret", b"secret"));
+        assert!(!ct_eq(b"secret", b"secreT"));
+        assert!(!ct_eq(b"secret", b"secret!"));
+        assert!(!ct_eq(b"", b"\0"));
+
+        let mut dst = [1_u8; 4];
+        ct_copy(&[9; 4], &mut dst, false);
+        assert_eq!(dst, [1; 4]);
+        ct_copy(&[9; 4], &mut dst, true);
+        assert_eq!(dst, [9; 4]);
+
+        zeroize(&mut dst);
+        assert_eq!(dst, [0; 4]);
+        zeroize(&mut []);
+    }
+}
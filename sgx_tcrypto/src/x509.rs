@@ -0,0 +1,1106 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+//!
+//! X.509 certificate parsing
+//!
+//! `Certificate` parses the DER encoding of an X.509 v1, v2 or v3
+//! certificate (RFC 5280) without copying it. The whole structure is checked
+//! when the certificate is parsed, so the accessors do not fail. Extensions
+//! are returned undecoded: the RA-TLS quote, for example, is the value of
+//! the extension `1.2.840.113741.1337.6`.
+//!
+//! Parsing a certificate does not validate it. Signatures can be checked
+//! with `Certificate::verify_signature`, which supports ECDSA over NIST
+//! P-256 with SHA-256, the algorithm of RA-TLS certificates, and
+//! RSASSA-PKCS1-v1_5 with SHA-256 and 3072 bit keys.
+//!
+use crate::crypto::{rsgx_rsa3072_verify_slice, SgxEccHandle};
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+use core::str;
+use sgx_types::*;
+
+const TAG_BOOLEAN: u8 = 0x01;
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_UTF8_STRING: u8 = 0x0c;
+const TAG_PRINTABLE_STRING: u8 = 0x13;
+const TAG_IA5_STRING: u8 = 0x16;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_VERSION: u8 = 0xa0;
+const TAG_ISSUER_UNIQUE_ID: u8 = 0x81;
+const TAG_SUBJECT_UNIQUE_ID: u8 = 0x82;
+const TAG_EXTENSIONS: u8 = 0xa3;
+
+// 1.2.840.10045.2.1, 1.2.840.10045.3.1.7, 1.2.840.10045.4.3.2 and 2.5.4.3
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+const OID_PRIME256V1: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const OID_ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+// 1.2.840.113549.1.1.1 and 1.2.840.113549.1.1.11
+const OID_RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+const OID_SHA256_WITH_RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+// The encoding of NULL, the parameters of both RSA algorithms
+const DER_NULL: &[u8] = &[0x05, 0x00];
+
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+///
+/// An X.509 certificate, parsed from its DER or PEM encoding.
+///
+#[derive(Clone)]
+pub struct Certificate<'a> {
+    der: Cow<'a, [u8]>,
+    fields: Fields,
+}
+
+// The offsets of the fields in the encoding, checked by `Fields::parse`.
+#[derive(Clone)]
+struct Fields {
+    tbs: Range<usize>,
+    version: u8,
+    serial: Range<usize>,
+    signature_algorithm: AlgorithmRanges,
+    issuer: Range<usize>,
+    not_before: Range<usize>,
+    not_after: Range<usize>,
+    subject: Range<usize>,
+    public_key: Range<usize>,
+    public_key_algorithm: AlgorithmRanges,
+    public_key_bits: Range<usize>,
+    extensions: Option<Range<usize>>,
+    signature: Range<usize>,
+}
+
+#[derive(Clone)]
+struct AlgorithmRanges {
+    oid: Range<usize>,
+    parameters: Option<Range<usize>>,
+}
+
+impl<'a> Certificate<'a> {
+    ///
+    /// from_der parses the DER encoding of a certificate, without copying it.
+    ///
+    /// # Parameters
+    ///
+    /// **der**
+    ///
+    /// The DER encoding of the certificate. Nothing may follow it.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// The parsed certificate.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The input is not a DER encoded X.509 certificate.
+    ///
+    pub fn from_der(der: &'a [u8]) -> SgxResult<Certificate<'a>> {
+        let fields = Fields::parse(der)?;
+        Ok(Certificate {
+            der: Cow::Borrowed(der),
+            fields,
+        })
+    }
+
+    ///
+    /// from_pem parses the first `CERTIFICATE` block of a PEM document.
+    ///
+    /// Text around the block is ignored, so the first certificate of a PEM
+    /// certificate chain can be read with it as well.
+    ///
+    /// # Parameters
+    ///
+    /// **pem**
+    ///
+    /// The PEM document.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// The parsed certificate, which owns its DER encoding.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The document has no `CERTIFICATE` block, the block is not valid
+    /// Base64, or it does not encode an X.509 certificate.
+    ///
+    pub fn from_pem(pem: &str) -> SgxResult<Certificate<'static>> {
+        let start = pem
+            .find(PEM_BEGIN)
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?
+            + PEM_BEGIN.len();
+        let len = pem[start..]
+            .find(PEM_END)
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        let der = base64_decode(&pem[start..start + len])?;
+        let fields = Fields::parse(&der)?;
+        Ok(Certificate {
+            der: Cow::Owned(der),
+            fields,
+        })
+    }
+
+    ///
+    /// into_owned copies a borrowed encoding, so the certificate can outlive it.
+    ///
+    pub fn into_owned(self) -> Certificate<'static> {
+        Certificate {
+            der: Cow::Owned(self.der.into_owned()),
+            fields: self.fields,
+        }
+    }
+
+    /// The DER encoding of the certificate.
+    pub fn as_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    /// The DER encoding of the `TBSCertificate`, the signed part of the certificate.
+    pub fn tbs_certificate(&self) -> &[u8] {
+        &self.der[self.fields.tbs.clone()]
+    }
+
+    /// The version of the certificate: 1, 2 or 3.
+    pub fn version(&self) -> u8 {
+        self.fields.version
+    }
+
+    /// The serial number, as the big-endian two's complement contents of its INTEGER.
+    pub fn serial_number(&self) -> &[u8] {
+        &self.der[self.fields.serial.clone()]
+    }
+
+    /// The algorithm the issuer signed the certificate with.
+    pub fn signature_algorithm(&self) -> AlgorithmIdentifier<'_> {
+        self.algorithm(&self.fields.signature_algorithm)
+    }
+
+    /// The signature of the issuer over `tbs_certificate`.
+    pub fn signature(&self) -> &[u8] {
+        &self.der[self.fields.signature.clone()]
+    }
+
+    pub fn issuer(&self) -> Name<'_> {
+        Name(&self.der[self.fields.issuer.clone()])
+    }
+
+    pub fn subject(&self) -> Name<'_> {
+        Name(&self.der[self.fields.subject.clone()])
+    }
+
+    /// The start of the validity period.
+    pub fn not_before(&self) -> Time<'_> {
+        self.time(&self.fields.not_before)
+    }
+
+    /// The end of the validity period.
+    pub fn not_after(&self) -> Time<'_> {
+        self.time(&self.fields.not_after)
+    }
+
+    /// The public key of the subject.
+    pub fn public_key(&self) -> SubjectPublicKeyInfo<'_> {
+        SubjectPublicKeyInfo {
+            der: &self.der[self.fields.public_key.clone()],
+            algorithm: self.algorithm(&self.fields.public_key_algorithm),
+            key: &self.der[self.fields.public_key_bits.clone()],
+        }
+    }
+
+    /// The extensions of a version 3 certificate, in the order of the encoding.
+    pub fn extensions(&self) -> Extensions<'_> {
+        let range = self.fields.extensions.clone().unwrap_or(0..0);
+        Extensions(Reader::new(&self.der, range))
+    }
+
+    /// The extension with the given dotted OID, if the certificate has it.
+    pub fn extension(&self, oid: &str) -> Option<Extension<'_>> {
+        self.extensions().find(|ext| ext.oid() == oid)
+    }
+
+    /// Whether the issuer and subject names are equal, as for self-signed certificates.
+    pub fn is_self_issued(&self) -> bool {
+        self.issuer() == self.subject()
+    }
+
+    ///
+    /// verify_signature checks the signature of the certificate with the
+    /// public key of its issuer.
+    ///
+    /// ECDSA over NIST P-256 with SHA-256 and sha256WithRSAEncryption
+    /// (RSASSA-PKCS1-v1_5 with SHA-256) with 3072 bit keys are supported.
+    /// To check a self-signed certificate, pass its own `public_key`.
+    ///
+    /// # Parameters
+    ///
+    /// **issuer_key**
+    ///
+    /// The public key of the issuer, usually `public_key` of the certificate
+    /// of the issuer.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// **true**
+    ///
+    /// The signature is valid.
+    ///
+    /// **false**
+    ///
+    /// The signature is invalid.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_FEATURE_NOT_SUPPORTED**
+    ///
+    /// The certificate is signed with another algorithm, or with an RSA key
+    /// whose modulus is not 3072 bits long.
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The issuer key does not match the signature algorithm: it is not a
+    /// P-256 public key for ECDSA, or not an RSA public key for RSA.
+    ///
+    /// **SGX_ERROR_OUT_OF_MEMORY**
+    ///
+    /// Not enough memory is available to complete this operation.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The verification process failed due to an internal cryptography library failure.
+    ///
+    pub fn verify_signature(&self, issuer_key: &SubjectPublicKeyInfo<'_>) -> SgxResult<bool> {
+        let algorithm = self.signature_algorithm();
+        match (algorithm.oid().as_bytes(), algorithm.parameters()) {
+            (OID_ECDSA_WITH_SHA256, None) => self.verify_ecdsa_p256(issuer_key),
+            // RFC 4055 2.1: the parameters are NULL, but some encoders leave them out
+            (OID_SHA256_WITH_RSA_ENCRYPTION, None | Some(DER_NULL)) => self.verify_rsa3072(issuer_key),
+            _ => Err(sgx_status_t::SGX_ERROR_FEATURE_NOT_SUPPORTED),
+        }
+    }
+
+    fn verify_ecdsa_p256(&self, issuer_key: &SubjectPublicKeyInfo<'_>) -> SgxResult<bool> {
+        let public_key = issuer_key.ec256_public_key()?;
+        let signature = match parse_ecdsa_signature(self.signature()) {
+            Some(signature) => signature,
+            None => return Ok(false),
+        };
+
+        let handle = SgxEccHandle::new();
+        handle.open()?;
+        handle.ecdsa_verify_slice(self.tbs_certificate(), &public_key, &signature)
+    }
+
+    fn verify_rsa3072(&self, issuer_key: &SubjectPublicKeyInfo<'_>) -> SgxResult<bool> {
+        let (modulus, _) = issuer_key.rsa_public_key()?;
+        if modulus.len() != SGX_RSA3072_KEY_SIZE {
+            return Err(sgx_status_t::SGX_ERROR_FEATURE_NOT_SUPPORTED);
+        }
+        let public_key = issuer_key.rsa3072_public_key()?;
+        // RFC 8017 8.2.2: the signature is as long as the modulus
+        if self.signature().len() != SGX_RSA3072_KEY_SIZE {
+            return Ok(false);
+        }
+        let mut signature = sgx_rsa3072_signature_t { signature: [0; SGX_RSA3072_KEY_SIZE] };
+        signature.signature.copy_from_slice(self.signature());
+        rsgx_rsa3072_verify_slice(self.tbs_certificate(), &public_key, &signature)
+    }
+
+    fn algorithm(&self, ranges: &AlgorithmRanges) -> AlgorithmIdentifier<'_> {
+        AlgorithmIdentifier {
+            oid: Oid(&self.der[ranges.oid.clone()]),
+            parameters: ranges.parameters.clone().map(|range| &self.der[range]),
+        }
+    }
+
+    fn time(&self, range: &Range<usize>) -> Time<'_> {
+        // checked to be ASCII digits and 'Z'
+        Time(str::from_utf8(&self.der[range.clone()]).unwrap_or_default())
+    }
+}
+
+impl fmt::Debug for Certificate<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Certificate")
+            .field("version", &self.version())
+            .field("subject", &self.subject())
+            .field("issuer", &self.issuer())
+            .field("not_before", &self.not_before())
+            .field("not_after", &self.not_after())
+            .field("signature_algorithm", &self.signature_algorithm())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Fields {
+    fn parse(der: &[u8]) -> SgxResult<Fields> {
+        let mut outer = Reader::new(der, 0..der.len());
+        let mut certificate = outer.enter(TAG_SEQUENCE)?;
+        outer.finish()?;
+        let tbs = certificate.read_element(TAG_SEQUENCE)?;
+        let signature_algorithm = certificate.read_element(TAG_SEQUENCE)?;
+        let signature = read_bit_string(&mut certificate)?;
+        certificate.finish()?;
+
+        let mut fields = Reader::new(der, tbs.clone()).enter(TAG_SEQUENCE)?;
+        let version = if fields.peek() == Some(TAG_VERSION) {
+            let mut explicit = fields.enter(TAG_VERSION)?;
+            let value = explicit.read(TAG_INTEGER)?;
+            explicit.finish()?;
+            // v1 is the default, so DER leaves it out
+            match der[value] {
+                [1] => 2,
+                [2] => 3,
+                _ => return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+            }
+        } else {
+            1
+        };
+        let serial = fields.read(TAG_INTEGER)?;
+        check_integer(&der[serial.clone()])?;
+        let tbs_signature_algorithm = fields.read_element(TAG_SEQUENCE)?;
+        // RFC 5280 4.1.2.3: both must be the same
+        if der[tbs_signature_algorithm] != der[signature_algorithm.clone()] {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let issuer = read_name(&mut fields)?;
+        let mut validity = fields.enter(TAG_SEQUENCE)?;
+        let not_before = read_time(&mut validity)?;
+        let not_after = read_time(&mut validity)?;
+        validity.finish()?;
+        let subject = read_name(&mut fields)?;
+        let public_key = fields.read_element(TAG_SEQUENCE)?;
+        let mut spki = Reader::new(der, public_key.clone()).enter(TAG_SEQUENCE)?;
+        let public_key_algorithm = read_algorithm(&mut spki)?;
+        let public_key_bits = read_bit_string(&mut spki)?;
+        spki.finish()?;
+
+        for tag in [TAG_ISSUER_UNIQUE_ID, TAG_SUBJECT_UNIQUE_ID] {
+            if fields.peek() == Some(tag) {
+                if version < 2 {
+                    return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+                }
+                fields.read(tag)?;
+            }
+        }
+        let extensions = if fields.peek() == Some(TAG_EXTENSIONS) {
+            if version < 3 {
+                return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+            }
+            let mut explicit = fields.enter(TAG_EXTENSIONS)?;
+            let extensions = explicit.read(TAG_SEQUENCE)?;
+            explicit.finish()?;
+            check_extensions(Reader::new(der, extensions.clone()))?;
+            Some(extensions)
+        } else {
+            None
+        };
+        fields.finish()?;
+
+        Ok(Fields {
+            tbs,
+            version,
+            serial,
+            signature_algorithm: read_algorithm(&mut Reader::new(der, signature_algorithm))?,
+            issuer,
+            not_before,
+            not_after,
+            subject,
+            public_key,
+            public_key_algorithm,
+            public_key_bits,
+            extensions,
+            signature,
+        })
+    }
+}
+
+///
+/// An object identifier, in its DER encoding.
+///
+/// It compares equal to its dotted decimal notation, such as
+/// `"1.2.840.113741.1337.6"`.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Oid<'a>(&'a [u8]);
+
+impl<'a> Oid<'a> {
+    /// The contents octets of the OID, without tag and length.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    // The subidentifiers, each checked by `read_oid` to fit in 63 bits.
+    fn subidentifiers(&self) -> impl Iterator<Item = u64> + 'a {
+        let mut bytes = self.0.iter();
+        core::iter::from_fn(move || {
+            let mut value = 0_u64;
+            for &b in bytes.by_ref() {
+                value = value << 7 | u64::from(b & 0x7f);
+                if b & 0x80 == 0 {
+                    return Some(value);
+                }
+            }
+            None
+        })
+    }
+}
+
+impl fmt::Display for Oid<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, value) in self.subidentifiers().enumerate() {
+            if i == 0 {
+                let first = (value / 40).min(2);
+                write!(f, "{}.{}", first, value - 40 * first)?;
+            } else {
+                write!(f, ".{}", value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Oid<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl PartialEq<str> for Oid<'_> {
+    fn eq(&self, dotted: &str) -> bool {
+        // Compares the dotted notation piece by piece, without allocating.
+        struct Prefix<'s>(&'s str);
+        impl fmt::Write for Prefix<'_> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                self.0 = self.0.strip_prefix(s).ok_or(fmt::Error)?;
+                Ok(())
+            }
+        }
+        let mut rest = Prefix(dotted);
+        fmt::write(&mut rest, format_args!("{}", self)).is_ok() && rest.0.is_empty()
+    }
+}
+
+impl PartialEq<&str> for Oid<'_> {
+    fn eq(&self, dotted: &&str) -> bool {
+        *self == **dotted
+    }
+}
+
+///
+/// An `AlgorithmIdentifier`: the OID of an algorithm and its parameters.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AlgorithmIdentifier<'a> {
+    oid: Oid<'a>,
+    parameters: Option<&'a [u8]>,
+}
+
+impl<'a> AlgorithmIdentifier<'a> {
+    pub fn oid(&self) -> Oid<'a> {
+        self.oid
+    }
+
+    /// The DER encoding of the parameters, if present.
+    pub fn parameters(&self) -> Option<&'a [u8]> {
+        self.parameters
+    }
+}
+
+///
+/// A distinguished name, such as the issuer or subject of a certificate.
+///
+/// Names compare equal if their encodings are equal.
+///
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Name<'a>(&'a [u8]);
+
+impl<'a> Name<'a> {
+    /// The DER encoding of the name.
+    pub fn as_der(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// The first common name (CN) of the name, if it is a UTF8String,
+    /// PrintableString or IA5String.
+    pub fn common_name(&self) -> Option<&'a str> {
+        let mut rdns = Reader::new(self.0, 0..self.0.len())
+            .enter(TAG_SEQUENCE)
+            .ok()?;
+        while !rdns.is_empty() {
+            let mut rdn = rdns.enter(TAG_SET).ok()?;
+            while !rdn.is_empty() {
+                let mut attribute = rdn.enter(TAG_SEQUENCE).ok()?;
+                let oid = attribute.read(TAG_OID).ok()?;
+                let (tag, _, value) = attribute.read_any().ok()?;
+                if self.0[oid] == *OID_COMMON_NAME
+                    && matches!(tag, TAG_UTF8_STRING | TAG_PRINTABLE_STRING | TAG_IA5_STRING)
+                {
+                    return str::from_utf8(&self.0[value]).ok();
+                }
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Debug for Name<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.common_name() {
+            Some(cn) => write!(f, "Name(CN={})", cn),
+            None => write!(f, "Name({} bytes)", self.0.len()),
+        }
+    }
+}
+
+///
+/// A point in time of the validity period, in UTC.
+///
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Time<'a>(&'a str);
+
+impl<'a> Time<'a> {
+    /// The encoded time: `YYMMDDHHMMSSZ` for an UTCTime, `YYYYMMDDHHMMSSZ`
+    /// for a GeneralizedTime.
+    pub fn as_str(&self) -> &'a str {
+        self.0
+    }
+
+    /// The number of seconds since the Unix epoch.
+    pub fn unix_time(&self) -> i64 {
+        let digits = |range: Range<usize>| {
+            self.0.as_bytes()[range]
+                .iter()
+                .fold(0_i64, |n, &b| n * 10 + i64::from(b - b'0'))
+        };
+        // RFC 5280 4.1.2.5.1: UTCTime years from 50 are in the 20th century
+        let (year, rest) = if self.0.len() == 13 {
+            match digits(0..2) {
+                year if year >= 50 => (1900 + year, 2),
+                year => (2000 + year, 2),
+            }
+        } else {
+            (digits(0..4), 4)
+        };
+        let month = digits(rest..rest + 2);
+        let day = digits(rest + 2..rest + 4);
+        let seconds = digits(rest + 4..rest + 6) * 3600
+            + digits(rest + 6..rest + 8) * 60
+            + digits(rest + 8..rest + 10);
+        days_from_civil(year, month, day) * 86400 + seconds
+    }
+}
+
+impl fmt::Debug for Time<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+///
+/// A `SubjectPublicKeyInfo`: the public key of a certificate and its algorithm.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SubjectPublicKeyInfo<'a> {
+    der: &'a [u8],
+    algorithm: AlgorithmIdentifier<'a>,
+    key: &'a [u8],
+}
+
+impl<'a> SubjectPublicKeyInfo<'a> {
+    /// The DER encoding of the `SubjectPublicKeyInfo`, which RA-TLS hashes
+    /// into the report data of the quote.
+    pub fn as_der(&self) -> &'a [u8] {
+        self.der
+    }
+
+    pub fn algorithm(&self) -> AlgorithmIdentifier<'a> {
+        self.algorithm
+    }
+
+    /// The encoded key, such as an uncompressed EC point or an `RSAPublicKey`.
+    pub fn subject_public_key(&self) -> &'a [u8] {
+        self.key
+    }
+
+    ///
+    /// ec256_public_key returns a NIST P-256 key in the little-endian byte
+    /// order of sgx_tcrypto.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The key is not an uncompressed NIST P-256 point.
+    ///
+    pub fn ec256_public_key(&self) -> SgxResult<sgx_ec256_public_t> {
+        let curve = [&[TAG_OID, OID_PRIME256V1.len() as u8][..], OID_PRIME256V1].concat();
+        if self.algorithm.oid.as_bytes() != OID_EC_PUBLIC_KEY
+            || self.algorithm.parameters != Some(&curve[..])
+        {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        match self.key {
+            [0x04, xy @ ..] if xy.len() == 2 * SGX_ECP256_KEY_SIZE => {
+                let (x, y) = xy.split_at(SGX_ECP256_KEY_SIZE);
+                let mut public_key = sgx_ec256_public_t::default();
+                copy_reversed(&mut public_key.gx, x);
+                copy_reversed(&mut public_key.gy, y);
+                Ok(public_key)
+            }
+            _ => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        }
+    }
+
+    ///
+    /// rsa3072_public_key returns an RSA key with a 3072 bit modulus in the
+    /// little-endian byte order of sgx_tcrypto.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The key is not an RSA key, its modulus is not 3072 bits long, or
+    /// its public exponent does not fit in 32 bits.
+    ///
+    pub fn rsa3072_public_key(&self) -> SgxResult<sgx_rsa3072_public_key_t> {
+        let (modulus, exponent) = self.rsa_public_key()?;
+        if modulus.len() != SGX_RSA3072_KEY_SIZE || exponent.len() > SGX_RSA3072_PUB_EXP_SIZE {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let mut public_key = sgx_rsa3072_public_key_t {
+            modulus: [0; SGX_RSA3072_KEY_SIZE],
+            exponent: [0; SGX_RSA3072_PUB_EXP_SIZE],
+        };
+        copy_reversed(&mut public_key.modulus, modulus);
+        copy_reversed(&mut public_key.exponent, exponent);
+        Ok(public_key)
+    }
+
+    // Decodes an RSAPublicKey, SEQUENCE { modulus INTEGER, publicExponent
+    // INTEGER }, into the big-endian magnitudes of both integers.
+    fn rsa_public_key(&self) -> SgxResult<(&'a [u8], &'a [u8])> {
+        if self.algorithm.oid.as_bytes() != OID_RSA_ENCRYPTION
+            || self.algorithm.parameters != Some(DER_NULL)
+        {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let mut outer = Reader::new(self.key, 0..self.key.len());
+        let mut values = outer.enter(TAG_SEQUENCE)?;
+        outer.finish()?;
+        let modulus = read_positive(&mut values)?;
+        let exponent = read_positive(&mut values)?;
+        values.finish()?;
+        Ok((modulus, exponent))
+    }
+}
+
+///
+/// A certificate extension. The value is the undecoded contents of its
+/// OCTET STRING.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Extension<'a> {
+    oid: Oid<'a>,
+    critical: bool,
+    value: &'a [u8],
+}
+
+impl<'a> Extension<'a> {
+    pub fn oid(&self) -> Oid<'a> {
+        self.oid
+    }
+
+    /// Whether a verifier that does not understand the extension must reject the certificate.
+    pub fn critical(&self) -> bool {
+        self.critical
+    }
+
+    pub fn value(&self) -> &'a [u8] {
+        self.value
+    }
+}
+
+///
+/// An iterator over the extensions of a certificate, returned by
+/// `Certificate::extensions`.
+///
+#[derive(Clone)]
+pub struct Extensions<'a>(Reader<'a>);
+
+impl<'a> Iterator for Extensions<'a> {
+    type Item = Extension<'a>;
+
+    fn next(&mut self) -> Option<Extension<'a>> {
+        if self.0.is_empty() {
+            None
+        } else {
+            // checked by `check_extensions`
+            read_extension(&mut self.0).ok()
+        }
+    }
+}
+
+// A reader of the DER elements in `der[pos..end]`, which returns the offsets
+// of what it reads.
+#[derive(Clone)]
+struct Reader<'a> {
+    der: &'a [u8],
+    pos: usize,
+    end: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(der: &'a [u8], range: Range<usize>) -> Reader<'a> {
+        Reader {
+            der,
+            pos: range.start,
+            end: range.end,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.der[self.pos])
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos == self.end
+    }
+
+    fn finish(&self) -> SgxError {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+        }
+    }
+
+    // Reads an element and returns its tag, the range of the element and
+    // the range of its contents. Only the minimal length encodings of DER
+    // are accepted.
+    fn read_any(&mut self) -> SgxResult<(u8, Range<usize>, Range<usize>)> {
+        let input = &self.der[self.pos..self.end];
+        // high tag numbers are not used by certificates
+        if input.len() < 2 || input[0] & 0x1f == 0x1f {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let (len, header) = match input[1] {
+            n if n < 0x80 => (n as usize, 2),
+            n @ 0x81..=0x84 => {
+                let octets = (n & 0x7f) as usize;
+                if input.len() < 2 + octets || input[2] == 0 {
+                    return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+                }
+                let len = input[2..2 + octets]
+                    .iter()
+                    .fold(0_usize, |len, &b| len << 8 | b as usize);
+                if len < 0x80 {
+                    return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+                }
+                (len, 2 + octets)
+            }
+            _ => return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        };
+        if input.len() - header < len {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let start = self.pos;
+        self.pos += header + len;
+        Ok((input[0], start..self.pos, start + header..self.pos))
+    }
+
+    // Reads an element with the given tag and returns the range of its contents.
+    fn read(&mut self, tag: u8) -> SgxResult<Range<usize>> {
+        match self.read_any()? {
+            (t, _, contents) if t == tag => Ok(contents),
+            _ => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        }
+    }
+
+    // Reads an element with the given tag and returns the range of its encoding.
+    fn read_element(&mut self, tag: u8) -> SgxResult<Range<usize>> {
+        match self.read_any()? {
+            (t, element, _) if t == tag => Ok(element),
+            _ => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        }
+    }
+
+    // Reads a constructed element and returns a reader of its contents.
+    fn enter(&mut self, tag: u8) -> SgxResult<Reader<'a>> {
+        let contents = self.read(tag)?;
+        Ok(Reader::new(self.der, contents))
+    }
+}
+
+fn read_oid(reader: &mut Reader<'_>) -> SgxResult<Range<usize>> {
+    let oid = reader.read(TAG_OID)?;
+    let bytes = &reader.der[oid.clone()];
+    if bytes.last().map_or(true, |&b| b & 0x80 != 0) {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    // each subidentifier has no leading 0x80 and fits in 63 bits
+    let mut len = 0;
+    for &b in bytes {
+        if (len == 0 && b == 0x80) || len == 9 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        len = if b & 0x80 == 0 { 0 } else { len + 1 };
+    }
+    Ok(oid)
+}
+
+fn read_algorithm(reader: &mut Reader<'_>) -> SgxResult<AlgorithmRanges> {
+    let mut algorithm = reader.enter(TAG_SEQUENCE)?;
+    let oid = read_oid(&mut algorithm)?;
+    let parameters = if algorithm.is_empty() {
+        None
+    } else {
+        Some(algorithm.read_any()?.1)
+    };
+    algorithm.finish()?;
+    Ok(AlgorithmRanges { oid, parameters })
+}
+
+// Reads a BIT STRING without unused bits and returns the range of its bits.
+fn read_bit_string(reader: &mut Reader<'_>) -> SgxResult<Range<usize>> {
+    let contents = reader.read(TAG_BIT_STRING)?;
+    match reader.der.get(contents.start) {
+        Some(0) => Ok(contents.start + 1..contents.end),
+        _ => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+    }
+}
+
+// Reads a Name, a SEQUENCE OF non-empty SET OF AttributeTypeAndValue, and
+// returns the range of its encoding.
+fn read_name(reader: &mut Reader<'_>) -> SgxResult<Range<usize>> {
+    let name = reader.read_element(TAG_SEQUENCE)?;
+    let mut rdns = Reader::new(reader.der, name.clone()).enter(TAG_SEQUENCE)?;
+    while !rdns.is_empty() {
+        let mut rdn = rdns.enter(TAG_SET)?;
+        if rdn.is_empty() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        while !rdn.is_empty() {
+            let mut attribute = rdn.enter(TAG_SEQUENCE)?;
+            read_oid(&mut attribute)?;
+            attribute.read_any()?;
+            attribute.finish()?;
+        }
+    }
+    Ok(name)
+}
+
+// Reads an UTCTime or GeneralizedTime in the forms allowed by RFC 5280
+// 4.1.2.5 and returns the range of its contents.
+fn read_time(reader: &mut Reader<'_>) -> SgxResult<Range<usize>> {
+    let (tag, _, contents) = reader.read_any()?;
+    let time = &reader.der[contents.clone()];
+    let digits = match (tag, time) {
+        (TAG_UTC_TIME, [digits @ .., b'Z']) if digits.len() == 12 => &digits[2..],
+        (TAG_GENERALIZED_TIME, [digits @ .., b'Z']) if digits.len() == 14 => &digits[4..],
+        _ => return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+    };
+    if !time[..time.len() - 1].iter().all(u8::is_ascii_digit) {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    let field = |i: usize| (digits[i] - b'0') * 10 + (digits[i + 1] - b'0');
+    let (month, day, hour, minute, second) = (field(0), field(2), field(4), field(6), field(8));
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    Ok(contents)
+}
+
+// Reads an Extension: SEQUENCE { extnID, critical DEFAULT FALSE, extnValue }.
+fn read_extension<'a>(reader: &mut Reader<'a>) -> SgxResult<Extension<'a>> {
+    let der = reader.der;
+    let mut extension = reader.enter(TAG_SEQUENCE)?;
+    let oid = read_oid(&mut extension)?;
+    let critical = if extension.peek() == Some(TAG_BOOLEAN) {
+        // DER leaves out the default, so only TRUE can be encoded
+        match der[extension.read(TAG_BOOLEAN)?] {
+            [0xff] => true,
+            _ => return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        }
+    } else {
+        false
+    };
+    let value = extension.read(TAG_OCTET_STRING)?;
+    extension.finish()?;
+    Ok(Extension {
+        oid: Oid(&der[oid]),
+        critical,
+        value: &der[value],
+    })
+}
+
+// Checks a non-empty SEQUENCE OF Extension, in which RFC 5280 4.2 allows
+// each extension only once.
+fn check_extensions(mut reader: Reader<'_>) -> SgxError {
+    let mut oids: Vec<Oid<'_>> = Vec::new();
+    if reader.is_empty() {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    while !reader.is_empty() {
+        let extension = read_extension(&mut reader)?;
+        if oids.contains(&extension.oid) {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        oids.push(extension.oid);
+    }
+    Ok(())
+}
+
+// Checks the contents of an INTEGER: not empty and minimally encoded.
+fn check_integer(contents: &[u8]) -> SgxError {
+    match contents {
+        [] => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        [0x00, b, ..] if b & 0x80 == 0 => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        [0xff, b, ..] if b & 0x80 != 0 => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        _ => Ok(()),
+    }
+}
+
+// Decodes an Ecdsa-Sig-Value, SEQUENCE { r INTEGER, s INTEGER }, into the
+// little-endian words of sgx_tcrypto.
+fn parse_ecdsa_signature(signature: &[u8]) -> Option<sgx_ec256_signature_t> {
+    let mut outer = Reader::new(signature, 0..signature.len());
+    let mut values = outer.enter(TAG_SEQUENCE).ok()?;
+    outer.finish().ok()?;
+    let r = read_unsigned(&mut values)?;
+    let s = read_unsigned(&mut values)?;
+    values.finish().ok()?;
+    Some(sgx_ec256_signature_t { x: r, y: s })
+}
+
+fn read_unsigned(reader: &mut Reader<'_>) -> Option<[u32; SGX_NISTP_ECP256_KEY_SIZE]> {
+    let contents = &reader.der[reader.read(TAG_INTEGER).ok()?];
+    check_integer(contents).ok()?;
+    let value = match contents {
+        [0, rest @ ..] if !rest.is_empty() => rest,
+        [b, ..] if b & 0x80 != 0 => return None,
+        _ => contents,
+    };
+    if value.len() > SGX_ECP256_KEY_SIZE {
+        return None;
+    }
+    let mut be = [0_u8; SGX_ECP256_KEY_SIZE];
+    be[SGX_ECP256_KEY_SIZE - value.len()..].copy_from_slice(value);
+    let mut words = [0_u32; SGX_NISTP_ECP256_KEY_SIZE];
+    for (word, chunk) in words.iter_mut().rev().zip(be.chunks_exact(4)) {
+        *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    Some(words)
+}
+
+// Reads a positive INTEGER and returns its big-endian magnitude.
+fn read_positive<'a>(reader: &mut Reader<'a>) -> SgxResult<&'a [u8]> {
+    let contents = &reader.der[reader.read(TAG_INTEGER)?];
+    check_integer(contents)?;
+    match contents {
+        [0, rest @ ..] if !rest.is_empty() => Ok(rest),
+        [b, ..] if b & 0x80 == 0 && contents != [0] => Ok(contents),
+        _ => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+    }
+}
+
+fn copy_reversed(out: &mut [u8], be: &[u8]) {
+    for (o, b) in out.iter_mut().zip(be.iter().rev()) {
+        *o = *b;
+    }
+}
+
+// The number of days from 1970-01-01 to the given date of the proleptic
+// Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// Decodes standard Base64 with padding, skipping whitespace.
+fn base64_decode(text: &str) -> SgxResult<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let mut bits = 0_u32;
+    let mut nbits = 0;
+    let mut padding = 0;
+    for c in text.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => {
+                padding += 1;
+                continue;
+            }
+            _ => return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        };
+        if padding > 0 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        bits = (bits << 6 | u32::from(value)) & 0xffff;
+        nbits += 6;
+        if nbits >= 8 {
+            nbits -= 8;
+            out.push((bits >> nbits) as u8);
+        }
+    }
+    // the padding completes the last group of four characters
+    if (nbits, padding) != (0, 0) && (nbits, padding) != (4, 2) && (nbits, padding) != (2, 1) {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    Ok(out)
+}
@@ -0,0 +1,315 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! # Local Attestation Secure Channel
+//!
+//! A message channel between two enclaves on the same platform. The channel key is
+//! derived from the AEK of a DH session whose messages are authenticated by local
+//! attestation reports, and each message is protected with AES-GCM.
+//!
+//! An enclave can not call into another enclave directly, so the untrusted transport
+//! (usually a set of OCALLs that the application forwards as ECALLs to the peer) is
+//! supplied by the caller through the [`LocalTransport`] and [`LocalConnector`] traits.
+//!
+use crate::dh::*;
+use alloc::vec::Vec;
+use core::mem;
+use core::ptr;
+use sgx_tcrypto::*;
+use sgx_types::*;
+
+const CHANNEL_I2R_LABEL: [u8; 3] = [0x49, 0x32, 0x52];
+const CHANNEL_R2I_LABEL: [u8; 3] = [0x52, 0x32, 0x49];
+
+const SEQ_SIZE: usize = mem::size_of::<u64>();
+const MAC_SIZE: usize = SGX_AESGCM_MAC_SIZE;
+const IV_SIZE: usize = SGX_AESGCM_IV_SIZE;
+
+/// Size of the header (sequence number and MAC) prepended to every channel frame.
+pub const LOCAL_CHANNEL_FRAME_OVERHEAD: usize = SEQ_SIZE + MAC_SIZE;
+
+/// Untrusted transport used to move encrypted frames between the two enclaves.
+pub trait LocalTransport {
+    ///
+    /// Delivers one frame to the peer enclave.
+    ///
+    fn send_frame(&mut self, peer: sgx_enclave_id_t, frame: &[u8]) -> SgxError;
+    ///
+    /// Receives one frame from the peer enclave.
+    ///
+    fn recv_frame(&mut self, peer: sgx_enclave_id_t) -> SgxResult<Vec<u8>>;
+}
+
+/// Untrusted transport used by the initiator to run the DH handshake.
+pub trait LocalConnector: LocalTransport {
+    ///
+    /// Asks the peer enclave to start a session and returns its msg1.
+    ///
+    fn session_request(&mut self, peer: sgx_enclave_id_t, msg1: &mut SgxDhMsg1) -> SgxError;
+    ///
+    /// Sends msg2 to the peer enclave and returns its msg3.
+    ///
+    fn exchange_report(&mut self, peer: sgx_enclave_id_t, msg2: &SgxDhMsg2)
+        -> SgxResult<SgxDhMsg3>;
+}
+
+/// Secure channel to another enclave established through local attestation.
+pub struct LocalChannel<T: LocalTransport> {
+    transport: T,
+    peer: sgx_enclave_id_t,
+    peer_identity: sgx_dh_session_enclave_identity_t,
+    send_key: sgx_align_key_128bit_t,
+    recv_key: sgx_align_key_128bit_t,
+    send_seq: u64,
+    recv_seq: u64,
+}
+
+impl<T: LocalConnector> LocalChannel<T> {
+    ///
+    /// Establishes a channel with the target enclave, acting as the session initiator.
+    ///
+    /// # Parameters
+    ///
+    /// **target_enclave_id**
+    ///
+    /// The enclave ID of the responder enclave.
+    ///
+    /// **transport**
+    ///
+    /// The untrusted transport carrying the handshake messages and the channel frames.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tservice.a or libsgx_tservice_sim.a (simulation)
+    ///
+    /// The caller must check the identity returned by `peer_identity` before sending
+    /// any secret over the channel.
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by the transport, `SgxDhInitiator::proc_msg1` or
+    /// `SgxDhInitiator::proc_msg3`.
+    ///
+    pub fn connect(target_enclave_id: sgx_enclave_id_t, mut transport: T) -> SgxResult<Self> {
+        let mut initiator = SgxDhInitiator::init_session();
+        let mut msg1 = SgxDhMsg1::default();
+        let mut msg2 = SgxDhMsg2::default();
+        let mut aek = sgx_align_key_128bit_t::default();
+        let mut identity = sgx_dh_session_enclave_identity_t::default();
+
+        transport.session_request(target_enclave_id, &mut msg1)?;
+        initiator.proc_msg1(&msg1, &mut msg2)?;
+        let msg3 = transport.exchange_report(target_enclave_id, &msg2)?;
+        initiator.proc_msg3(&msg3, &mut aek.key, &mut identity)?;
+
+        Self::from_aek(transport, target_enclave_id, &mut aek, identity, true)
+    }
+}
+
+impl<T: LocalTransport> LocalChannel<T> {
+    ///
+    /// Completes a channel as the session responder.
+    ///
+    /// `responder` must have generated the msg1 that the initiator answered with `msg2`.
+    /// The generated msg3 is written to `msg3` and must be returned to the initiator.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tservice.a or libsgx_tservice_sim.a (simulation)
+    ///
+    /// The caller must check the identity returned by `peer_identity` before sending
+    /// any secret over the channel.
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by `SgxDhResponder::proc_msg2`.
+    ///
+    pub fn accept(
+        source_enclave_id: sgx_enclave_id_t,
+        transport: T,
+        responder: &mut SgxDhResponder,
+        msg2: &SgxDhMsg2,
+        msg3: &mut SgxDhMsg3,
+    ) -> SgxResult<Self> {
+        let mut aek = sgx_align_key_128bit_t::default();
+        let mut identity = sgx_dh_session_enclave_identity_t::default();
+
+        responder.proc_msg2(msg2, msg3, &mut aek.key, &mut identity)?;
+
+        Self::from_aek(transport, source_enclave_id, &mut aek, identity, false)
+    }
+
+    fn from_aek(
+        transport: T,
+        peer: sgx_enclave_id_t,
+        aek: &mut sgx_align_key_128bit_t,
+        peer_identity: sgx_dh_session_enclave_identity_t,
+        initiator: bool,
+    ) -> SgxResult<Self> {
+        let i2r = derive_channel_key(&aek.key, &CHANNEL_I2R_LABEL);
+        let r2i = derive_channel_key(&aek.key, &CHANNEL_R2I_LABEL);
+        zeroize_key(aek);
+        let (i2r, r2i) = (i2r?, r2i?);
+
+        let (send_key, recv_key) = if initiator { (i2r, r2i) } else { (r2i, i2r) };
+        Ok(LocalChannel {
+            transport,
+            peer,
+            peer_identity,
+            send_key,
+            recv_key,
+            send_seq: 0,
+            recv_seq: 0,
+        })
+    }
+
+    ///
+    /// Returns the enclave ID of the peer.
+    ///
+    pub fn peer(&self) -> sgx_enclave_id_t {
+        self.peer
+    }
+
+    ///
+    /// Returns the identity of the peer enclave, as attested during the handshake.
+    ///
+    pub fn peer_identity(&self) -> &sgx_dh_session_enclave_identity_t {
+        &self.peer_identity
+    }
+
+    ///
+    /// Encrypts `data` and sends it to the peer.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `data` is too large to be encrypted.
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The message sequence number is exhausted.
+    ///
+    /// Any error returned by the transport.
+    ///
+    pub fn send(&mut self, data: &[u8]) -> SgxError {
+        if data.len() > u32::MAX as usize - LOCAL_CHANNEL_FRAME_OVERHEAD {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let seq = self.send_seq;
+        if seq == u64::MAX {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+
+        let mut frame = vec![0_u8; LOCAL_CHANNEL_FRAME_OVERHEAD + data.len()];
+        let (header, ciphertext) = frame.split_at_mut(LOCAL_CHANNEL_FRAME_OVERHEAD);
+        let seq_bytes = seq.to_le_bytes();
+        let mut mac = sgx_aes_gcm_128bit_tag_t::default();
+        rsgx_rijndael128GCM_encrypt(
+            &self.send_key.key,
+            data,
+            &channel_iv(seq),
+            &seq_bytes,
+            ciphertext,
+            &mut mac,
+        )?;
+        header[..SEQ_SIZE].copy_from_slice(&seq_bytes);
+        header[SEQ_SIZE..].copy_from_slice(&mac);
+
+        self.send_seq += 1;
+        self.transport.send_frame(self.peer, &frame)
+    }
+
+    ///
+    /// Receives the next message from the peer and decrypts it.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The frame is truncated or too large.
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The frame was modified, replayed, reordered or not sent by the peer.
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The message sequence number is exhausted.
+    ///
+    /// Any error returned by the transport.
+    ///
+    pub fn recv(&mut self) -> SgxResult<Vec<u8>> {
+        let seq = self.recv_seq;
+        if seq == u64::MAX {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+
+        let frame = self.transport.recv_frame(self.peer)?;
+        if frame.len() < LOCAL_CHANNEL_FRAME_OVERHEAD || frame.len() > u32::MAX as usize {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let (header, ciphertext) = frame.split_at(LOCAL_CHANNEL_FRAME_OVERHEAD);
+        let seq_bytes = seq.to_le_bytes();
+        if header[..SEQ_SIZE] != seq_bytes {
+            return Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH);
+        }
+        let mut mac = sgx_aes_gcm_128bit_tag_t::default();
+        mac.copy_from_slice(&header[SEQ_SIZE..]);
+
+        let mut plaintext = vec![0_u8; ciphertext.len()];
+        rsgx_rijndael128GCM_decrypt(
+            &self.recv_key.key,
+            ciphertext,
+            &channel_iv(seq),
+            &seq_bytes,
+            &mac,
+            &mut plaintext,
+        )?;
+
+        self.recv_seq += 1;
+        Ok(plaintext)
+    }
+}
+
+impl<T: LocalTransport> Drop for LocalChannel<T> {
+    fn drop(&mut self) {
+        zeroize_key(&mut self.send_key);
+        zeroize_key(&mut self.recv_key);
+    }
+}
+
+fn derive_channel_key(
+    aek: &sgx_key_128bit_t,
+    label: &[u8; 3],
+) -> SgxResult<sgx_align_key_128bit_t> {
+    let mut key = sgx_align_key_128bit_t::default();
+    key.key = rsgx_rijndael128_cmac_slice(aek, label)?;
+    Ok(key)
+}
+
+fn channel_iv(seq: u64) -> [u8; IV_SIZE] {
+    let mut iv = [0_u8; IV_SIZE];
+    iv[..SEQ_SIZE].copy_from_slice(&seq.to_le_bytes());
+    iv
+}
+
+fn zeroize_key(key: &mut sgx_align_key_128bit_t) {
+    for b in key.key.iter_mut() {
+        unsafe { ptr::write_volatile(b, 0) };
+    }
+}
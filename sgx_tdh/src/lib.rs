@@ -42,3 +42,5 @@ mod dh;
 pub use self::dh::*;
 
 mod ecp;
+
+pub mod channel;
@@ -96,6 +96,7 @@ pub mod enclave;
 pub mod memchr;
 pub mod memeq;
 pub mod oom;
+pub mod stack;
 pub mod trts;
 pub mod veh;
 
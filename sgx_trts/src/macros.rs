@@ -76,3 +76,21 @@ macro_rules! global_dtors_object {
         }
     };
 }
+
+/// sgx_check_stack panics unless at least the given number of bytes of
+/// stack are left, see [`stack::probe_stack`](crate::stack::probe_stack).
+///
+/// Put it at the entry of functions which recurse deeply, so that running
+/// out of stack ends in a panic rather than on the guard page.
+#[macro_export]
+macro_rules! sgx_check_stack {
+    ($min_remaining_bytes:expr) => {{
+        let min_remaining_bytes: usize = $min_remaining_bytes;
+        if !$crate::stack::probe_stack(min_remaining_bytes) {
+            panic!(
+                "enclave stack exhausted: less than {} bytes left",
+                min_remaining_bytes
+            );
+        }
+    }};
+}
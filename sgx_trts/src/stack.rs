@@ -0,0 +1,67 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Stack probing
+//!
+//! An enclave thread has a fixed stack of `StackMaxSize` bytes. Running off
+//! its end hits the guard page, which the trusted runtime cannot recover
+//! from. Code about to recurse deeply can check beforehand that enough stack
+//! is left with [`probe_stack`] or the `sgx_check_stack!` macro.
+
+use crate::enclave::SgxThreadData;
+use core::hint::black_box;
+use core::ptr;
+use sgx_types::metadata::SE_PAGE_SIZE;
+
+/// Returns the number of bytes between the current stack pointer and the
+/// stack limit of the calling thread.
+#[inline(never)]
+pub fn remaining_stack() -> usize {
+    let marker = 0_u8;
+    let sp = black_box(&marker) as *const u8 as usize;
+    sp.saturating_sub(SgxThreadData::current().stack_limit())
+}
+
+/// Checks that at least `min_remaining_bytes` of stack are left below the
+/// current stack pointer, and touches every page of that range.
+///
+/// Touching the pages makes the stack page faults, e.g. for committing pages
+/// of a dynamically sized stack, happen here in a controlled manner instead
+/// of somewhere in the middle of the recursion.
+///
+/// Returns `false`, without touching anything, if less than
+/// `min_remaining_bytes` of stack are left.
+#[inline(never)]
+pub fn probe_stack(min_remaining_bytes: usize) -> bool {
+    let marker = 0_u8;
+    let sp = black_box(&marker) as *const u8 as usize;
+    let limit = SgxThreadData::current().stack_limit();
+    if sp < limit || sp - limit < min_remaining_bytes {
+        return false;
+    }
+
+    let bottom = sp - min_remaining_bytes;
+    let mut page = sp & !(SE_PAGE_SIZE - 1);
+    while page >= bottom && page >= limit {
+        unsafe { ptr::read_volatile(page as *const u8) };
+        match page.checked_sub(SE_PAGE_SIZE) {
+            Some(next) => page = next,
+            None => break,
+        }
+    }
+    true
+}
@@ -264,16 +264,24 @@ impl SgxInternalSealedData {
     }
 
     pub fn seal_data(additional_text: &[u8], encrypt_text: &[u8]) -> SgxResult<Self> {
+        Self::seal_data_policy(SGX_KEYPOLICY_MRSIGNER, additional_text, encrypt_text)
+    }
+
+    pub fn seal_data_policy(
+        key_policy: u16,
+        additional_text: &[u8],
+        encrypt_text: &[u8],
+    ) -> SgxResult<Self> {
         /* intel sgx sdk 1.8 */
         let attribute_mask = sgx_attributes_t {
             flags: TSEAL_DEFAULT_FLAGSMASK,
             xfrm: 0,
         };
         /* intel sgx sdk 2.4 */
-        let mut key_policy = SGX_KEYPOLICY_MRSIGNER;
+        let mut key_policy = key_policy;
         let report = rsgx_self_report();
         if (report.body.attributes.flags & SGX_FLAGS_KSS) != 0 {
-            key_policy = SGX_KEYPOLICY_MRSIGNER | KEY_POLICY_KSS;
+            key_policy |= KEY_POLICY_KSS;
         }
 
         Self::seal_data_ex(
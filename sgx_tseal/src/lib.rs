@@ -90,9 +90,14 @@ extern crate sgx_tse;
 extern crate sgx_types;
 
 mod seal;
-pub use self::seal::{SgxSealedData, SgxUnsealedData};
+pub use self::seal::{KeyPolicy, SgxSealedData, SgxUnsealedData};
 
 mod aad;
 pub use self::aad::SgxMacAadata;
 
 mod internal;
+
+pub mod migration;
+
+mod stream;
+pub use self::stream::{StreamingSeal, StreamingUnseal, STREAM_HEADER_FIXED_SIZE, STREAM_TAG_SIZE};
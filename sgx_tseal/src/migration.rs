@@ -0,0 +1,211 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//!
+//! Migration of sealed data between versions of an enclave.
+//!
+//! Data sealed with `KeyPolicy::MrEnclave` can only be unsealed by the enclave
+//! that sealed it. To carry it over to an updated enclave, the two enclaves
+//! first establish a local attestation session (see `sgx_tdh`) and check each
+//! other's identity. The new enclave then derives a `MigrationKey` from the
+//! session key and a fresh nonce, and sends the nonce to the old enclave, which
+//! derives the same key. The old enclave unseals the data and encrypts it under
+//! the migration key with `MigrationKey::export_sealed`, and the new enclave
+//! decrypts it and seals it under its own identity with `MigrationKey::import_sealed`.
+//!
+//! Both functions consume the key, so each key protects a single blob.
+//!
+use crate::internal::*;
+use crate::seal::{KeyPolicy, SgxSealedData};
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::ptr;
+use sgx_tcrypto::*;
+use sgx_trts::trts::*;
+use sgx_types::*;
+
+/// The size of the nonce the migration key is derived with.
+pub const MIGRATION_NONCE_SIZE: usize = 16;
+
+const MIGRATION_KEY_LABEL: &[u8] = b"SGX_TSEAL_MIGRATION";
+
+// nonce || iv || mac || additional text length (u32, little endian)
+const MIGRATION_HEADER_SIZE: usize =
+    MIGRATION_NONCE_SIZE + SGX_AESGCM_IV_SIZE + SGX_AESGCM_MAC_SIZE + 4;
+
+/// A single-use key protecting sealed data in transit from one enclave to another.
+pub struct MigrationKey {
+    key: sgx_key_128bit_t,
+    nonce: [u8; MIGRATION_NONCE_SIZE],
+}
+
+impl MigrationKey {
+    ///
+    /// Derive a migration key with a fresh nonce from the key of a local attestation
+    /// session. This is done by the enclave that receives the data.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The RDRAND instruction fails to generate a random number, or a crypto library failure.
+    ///
+    pub fn generate(session_key: &sgx_key_128bit_t) -> SgxResult<Self> {
+        let mut nonce = [0_u8; MIGRATION_NONCE_SIZE];
+        rsgx_read_rand(&mut nonce)?;
+        Self::with_nonce(session_key, &nonce)
+    }
+
+    ///
+    /// Derive the migration key for the nonce chosen by the receiving enclave. This is
+    /// done by the enclave that sends the data.
+    ///
+    pub fn with_nonce(
+        session_key: &sgx_key_128bit_t,
+        nonce: &[u8; MIGRATION_NONCE_SIZE],
+    ) -> SgxResult<Self> {
+        let mut msg = [0_u8; MIGRATION_KEY_LABEL.len() + MIGRATION_NONCE_SIZE];
+        msg[..MIGRATION_KEY_LABEL.len()].copy_from_slice(MIGRATION_KEY_LABEL);
+        msg[MIGRATION_KEY_LABEL.len()..].copy_from_slice(nonce);
+        let key = rsgx_rijndael128_cmac_slice(session_key, &msg)?;
+        Ok(MigrationKey { key, nonce: *nonce })
+    }
+
+    ///
+    /// Get the nonce the key was derived with.
+    ///
+    pub fn nonce(&self) -> &[u8; MIGRATION_NONCE_SIZE] {
+        &self.nonce
+    }
+
+    ///
+    /// Unseal the sealed data and encrypt it, with its additional text, under the
+    /// migration key. The sealed data must have been sealed by the calling enclave.
+    ///
+    /// # Errors
+    ///
+    /// The errors of `unseal_data`, or **SGX_ERROR_UNEXPECTED** on a crypto library failure.
+    ///
+    pub fn export_sealed<T: ?Sized>(self, sealed_data: &SgxSealedData<T>) -> SgxResult<Vec<u8>> {
+        let unsealed = sealed_data.inner.unseal_data()?;
+        let additional = &unsealed.additional;
+        let mut iv = [0_u8; SGX_AESGCM_IV_SIZE];
+        rsgx_read_rand(&mut iv)?;
+
+        let mut blob =
+            vec![0_u8; MIGRATION_HEADER_SIZE + additional.len() + unsealed.decrypt.len()];
+        let (header, body) = blob.split_at_mut(MIGRATION_HEADER_SIZE);
+        let (aad, encrypt) = body.split_at_mut(additional.len());
+        aad.copy_from_slice(additional);
+
+        let mut mac = sgx_aes_gcm_128bit_tag_t::default();
+        rsgx_rijndael128GCM_encrypt(
+            &self.key,
+            &unsealed.decrypt,
+            &iv,
+            &Self::aad(&self.nonce, additional),
+            encrypt,
+            &mut mac,
+        )?;
+
+        header[..MIGRATION_NONCE_SIZE].copy_from_slice(&self.nonce);
+        let header = &mut header[MIGRATION_NONCE_SIZE..];
+        header[..SGX_AESGCM_IV_SIZE].copy_from_slice(&iv);
+        let header = &mut header[SGX_AESGCM_IV_SIZE..];
+        header[..SGX_AESGCM_MAC_SIZE].copy_from_slice(&mac);
+        header[SGX_AESGCM_MAC_SIZE..].copy_from_slice(&(additional.len() as u32).to_le_bytes());
+        Ok(blob)
+    }
+
+    ///
+    /// Decrypt a blob produced by `export_sealed` and seal the data, with its
+    /// additional text, under the identity of the calling enclave with the given
+    /// key policy.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The blob is malformed.
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The blob was not encrypted under this key, or it was modified.
+    ///
+    /// Otherwise the errors of `seal_with_policy`.
+    ///
+    pub fn import_sealed<'a, T: 'a + ?Sized>(
+        self,
+        blob: &[u8],
+        policy: KeyPolicy,
+    ) -> SgxResult<SgxSealedData<'a, T>> {
+        if blob.len() < MIGRATION_HEADER_SIZE {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let (header, body) = blob.split_at(MIGRATION_HEADER_SIZE);
+        let (nonce, header) = header.split_at(MIGRATION_NONCE_SIZE);
+        let (iv, header) = header.split_at(SGX_AESGCM_IV_SIZE);
+        let (mac, additional_len) = header.split_at(SGX_AESGCM_MAC_SIZE);
+        let mut len = [0_u8; 4];
+        len.copy_from_slice(additional_len);
+        let additional_len = u32::from_le_bytes(len) as usize;
+        if additional_len > body.len() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        // A blob for another key, most likely one that was already used.
+        if nonce != self.nonce {
+            return Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH);
+        }
+        let (additional, encrypt) = body.split_at(additional_len);
+
+        let mut tag = sgx_aes_gcm_128bit_tag_t::default();
+        tag.copy_from_slice(mac);
+        let mut decrypt = vec![0_u8; encrypt.len()];
+        rsgx_rijndael128GCM_decrypt(
+            &self.key,
+            encrypt,
+            iv,
+            &Self::aad(&self.nonce, additional),
+            &tag,
+            &mut decrypt,
+        )?;
+
+        let result = SgxInternalSealedData::seal_data_policy(policy.bits(), additional, &decrypt);
+        decrypt
+            .iter_mut()
+            .for_each(|b| unsafe { ptr::write_volatile(b, 0) });
+        result.map(|x| SgxSealedData {
+            inner: x,
+            marker: PhantomData,
+        })
+    }
+
+    fn aad(nonce: &[u8; MIGRATION_NONCE_SIZE], additional: &[u8]) -> Vec<u8> {
+        let mut aad = Vec::with_capacity(MIGRATION_NONCE_SIZE + additional.len());
+        aad.extend_from_slice(nonce);
+        aad.extend_from_slice(additional);
+        aad
+    }
+}
+
+impl Drop for MigrationKey {
+    fn drop(&mut self) {
+        self.key
+            .iter_mut()
+            .for_each(|b| unsafe { ptr::write_volatile(b, 0) });
+    }
+}
@@ -35,6 +35,28 @@ use core::mem;
 use sgx_types::marker::ContiguousMemory;
 use sgx_types::*;
 
+/// The measurement register the sealing key is derived from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyPolicy {
+    /// Only the same enclave (same MRENCLAVE) can unseal the data.
+    MrEnclave,
+    /// Any enclave of the same signer (same MRSIGNER), with the same or a
+    /// higher security version, can unseal the data.
+    MrSigner,
+}
+
+impl KeyPolicy {
+    ///
+    /// Get the SGX_KEYPOLICY_* value of the policy.
+    ///
+    pub fn bits(&self) -> u16 {
+        match *self {
+            KeyPolicy::MrEnclave => SGX_KEYPOLICY_MRENCLAVE,
+            KeyPolicy::MrSigner => SGX_KEYPOLICY_MRSIGNER,
+        }
+    }
+}
+
 /// The structure about the unsealed data.
 pub struct SgxUnsealedData<'a, T: 'a + ?Sized> {
     pub payload_size: u32,
@@ -99,8 +121,8 @@ impl<'a, T: 'a + Clone + ?Sized> Clone for SgxUnsealedData<'a, T> {
 
 /// The structure about the sealed data.
 pub struct SgxSealedData<'a, T: 'a + ?Sized> {
-    inner: SgxInternalSealedData,
-    marker: PhantomData<&'a T>,
+    pub(crate) inner: SgxInternalSealedData,
+    pub(crate) marker: PhantomData<&'a T>,
 }
 
 impl<'a, T: 'a + ?Sized> Default for SgxSealedData<'a, T> {
@@ -198,6 +220,41 @@ impl<'a, T: 'a + Copy + ContiguousMemory> SgxSealedData<'a, T> {
         })
     }
 
+    ///
+    /// This function is used to AES-GCM encrypt the input data like `seal_data`,
+    /// with a sealing key derived using the given key policy instead of MRSIGNER.
+    /// The attribute mask and misc mask are the defaults of `seal_data`.
+    ///
+    /// Data sealed with `KeyPolicy::MrEnclave` can only be unsealed by the same
+    /// enclave, so it does not survive an update of the enclave.
+    ///
+    /// # Errors
+    ///
+    /// The same as `seal_data`.
+    ///
+    pub fn seal_with_policy(
+        additional_text: &[u8],
+        encrypt_text: &'a T,
+        policy: KeyPolicy,
+    ) -> SgxResult<Self> {
+        let size = mem::size_of::<T>();
+        if size == 0 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let encrypt_slice: &[u8] = unsafe {
+            slice::from_raw_parts(
+                encrypt_text as *const _ as *const u8,
+                mem::size_of_val(encrypt_text),
+            )
+        };
+        let result =
+            SgxInternalSealedData::seal_data_policy(policy.bits(), additional_text, encrypt_slice);
+        result.map(|x| SgxSealedData {
+            inner: x,
+            marker: PhantomData,
+        })
+    }
+
     ///
     /// This function is used to AES-GCM encrypt the input data. Two input data sets
     /// are provided: one is the data to be encrypted; the second is optional additional
@@ -516,6 +573,39 @@ impl<'a, T: 'a + Copy + ContiguousMemory> SgxSealedData<'a, [T]> {
         })
     }
 
+    ///
+    /// This function is used to AES-GCM encrypt the input data like `seal_data`,
+    /// with a sealing key derived using the given key policy instead of MRSIGNER.
+    /// The attribute mask and misc mask are the defaults of `seal_data`.
+    ///
+    /// Data sealed with `KeyPolicy::MrEnclave` can only be unsealed by the same
+    /// enclave, so it does not survive an update of the enclave.
+    ///
+    /// # Errors
+    ///
+    /// The same as `seal_data`.
+    ///
+    pub fn seal_with_policy(
+        additional_text: &[u8],
+        encrypt_text: &'a [T],
+        policy: KeyPolicy,
+    ) -> SgxResult<Self> {
+        let size = mem::size_of::<T>();
+        let len = mem::size_of_val(encrypt_text);
+        if size == 0 || len == 0 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let encrypt_slice: &[u8] =
+            unsafe { slice::from_raw_parts(encrypt_text.as_ptr() as *const u8, len) };
+
+        let result =
+            SgxInternalSealedData::seal_data_policy(policy.bits(), additional_text, encrypt_slice);
+        result.map(|x| SgxSealedData {
+            inner: x,
+            marker: PhantomData,
+        })
+    }
+
     ///
     /// This function is used to AES-GCM encrypt the input data. Two input data sets
     /// are provided: one is the data to be encrypted; the second is optional additional
@@ -791,6 +881,18 @@ impl<'a, T: 'a + ?Sized> SgxSealedData<'a, T> {
         self.inner.get_key_request()
     }
 
+    ///
+    /// Get the key policy the sealing key was derived with, from the key request
+    /// in SgxSealedData.
+    ///
+    pub fn key_policy(&self) -> KeyPolicy {
+        if (self.inner.get_key_request().key_policy & SGX_KEYPOLICY_MRENCLAVE) != 0 {
+            KeyPolicy::MrEnclave
+        } else {
+            KeyPolicy::MrSigner
+        }
+    }
+
     ///
     /// Get a slice of encrypt text in SgxSealedData.
     ///
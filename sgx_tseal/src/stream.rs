@@ -0,0 +1,306 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//!
+//! Streaming sealing and unsealing, for data too large to hold in memory at once.
+//!
+//! A sealed stream is a header, the encrypted data and the AES-GCM tag:
+//!
+//! * The header holds the key request the sealing key is derived with, the length
+//!   of the additional text (u32, little endian) and the additional text.
+//! * The encrypted data has the length of the plain text.
+//! * The tag covers the header and the encrypted data.
+//!
+//! The pieces returned by `StreamingSeal` can be written out as they come, e.g.
+//! to an untrusted file, and read back in chunks of any size into `StreamingUnseal`.
+//! Like `seal_data`, each stream is encrypted under a fresh key, derived with a
+//! random key id.
+//!
+use crate::seal::KeyPolicy;
+use alloc::vec::Vec;
+use core::mem;
+use core::ptr;
+use core::slice;
+use sgx_tcrypto::SgxAesHandle;
+use sgx_trts::memeq::ConsttimeMemEq;
+use sgx_trts::trts::*;
+use sgx_tse::*;
+use sgx_types::*;
+
+/* intel sgx sdk 2.4 */
+const KEY_POLICY_KSS: uint16_t =
+    SGX_KEYPOLICY_CONFIGID | SGX_KEYPOLICY_ISVFAMILYID | SGX_KEYPOLICY_ISVEXTPRODID;
+
+const KEY_REQUEST_SIZE: usize = mem::size_of::<sgx_key_request_t>();
+
+/// The size of the header without the additional text.
+pub const STREAM_HEADER_FIXED_SIZE: usize = KEY_REQUEST_SIZE + 4;
+
+/// The size of the tag at the end of a sealed stream.
+pub const STREAM_TAG_SIZE: usize = SGX_SEAL_TAG_SIZE;
+
+fn get_seal_key(key_request: &sgx_key_request_t) -> SgxResult<sgx_align_key_128bit_t> {
+    rsgx_get_align_key(key_request).map_err(|ret| {
+        if (ret == sgx_status_t::SGX_ERROR_INVALID_CPUSVN)
+            || (ret == sgx_status_t::SGX_ERROR_INVALID_ISVSVN)
+            || (ret == sgx_status_t::SGX_ERROR_OUT_OF_MEMORY)
+        {
+            ret
+        } else {
+            sgx_status_t::SGX_ERROR_MAC_MISMATCH
+        }
+    })
+}
+
+/// Seals a stream of data chunk by chunk.
+pub struct StreamingSeal {
+    handle: SgxAesHandle,
+    header: Vec<u8>,
+}
+
+impl StreamingSeal {
+    ///
+    /// Start sealing a stream under a key derived with the given key policy. The
+    /// additional text is not encrypted, but is covered by the tag.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The additional text is longer than u32::MAX bytes.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// Indicates a crypto library failure or the RDRAND instruction fails to generate a
+    /// random number.
+    ///
+    pub fn new(policy: KeyPolicy, additional_text: &[u8]) -> SgxResult<StreamingSeal> {
+        if additional_text.len() > u32::MAX as usize {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let report = rsgx_self_report();
+        let mut key_policy = policy.bits();
+        if (report.body.attributes.flags & SGX_FLAGS_KSS) != 0 {
+            key_policy |= KEY_POLICY_KSS;
+        }
+        let mut key_id = sgx_key_id_t::default();
+        rsgx_read_rand(&mut key_id.id)?;
+        let key_request = sgx_key_request_t {
+            key_name: SGX_KEYSELECT_SEAL,
+            key_policy,
+            isv_svn: report.body.isv_svn,
+            reserved1: 0_u16,
+            cpu_svn: report.body.cpu_svn,
+            attribute_mask: sgx_attributes_t {
+                flags: TSEAL_DEFAULT_FLAGSMASK,
+                xfrm: 0,
+            },
+            key_id,
+            misc_mask: TSEAL_DEFAULT_MISCMASK,
+            config_svn: report.body.config_svn,
+            reserved2: [0_u8; SGX_KEY_REQUEST_RESERVED2_BYTES],
+        };
+
+        let mut header = Vec::with_capacity(STREAM_HEADER_FIXED_SIZE + additional_text.len());
+        header.extend_from_slice(unsafe {
+            slice::from_raw_parts(
+                &key_request as *const sgx_key_request_t as *const u8,
+                KEY_REQUEST_SIZE,
+            )
+        });
+        header.extend_from_slice(&(additional_text.len() as u32).to_le_bytes());
+        header.extend_from_slice(additional_text);
+
+        let mut seal_key = get_seal_key(&key_request)?;
+        let handle = SgxAesHandle::new();
+        let payload_iv = [0_u8; SGX_SEAL_IV_SIZE];
+        let result = handle.init(&seal_key.key, &payload_iv, &header);
+        seal_key.key = sgx_key_128bit_t::default();
+        result.map(|_| StreamingSeal { handle, header })
+    }
+
+    ///
+    /// Get the header of the sealed stream, to be stored before the encrypted data.
+    ///
+    pub fn header(&self) -> &[u8] {
+        &self.header
+    }
+
+    ///
+    /// Encrypt the next chunk of the stream, the result has the length of the chunk.
+    ///
+    pub fn update(&mut self, chunk: &[u8]) -> SgxResult<Vec<u8>> {
+        let mut encrypt = vec![0_u8; chunk.len()];
+        if !chunk.is_empty() {
+            self.handle.update(chunk, &mut encrypt)?;
+        }
+        Ok(encrypt)
+    }
+
+    ///
+    /// Finish the stream and get its tag, to be stored after the encrypted data.
+    ///
+    pub fn finalize(self) -> SgxResult<Vec<u8>> {
+        self.handle.get_mac().map(|mac| mac.to_vec())
+    }
+}
+
+/// Unseals a stream of data chunk by chunk.
+///
+/// # Warning
+///
+/// `update` returns plain text **before it has been authenticated**. The tag
+/// is only checked by `finalize`: until it succeeds, the data may have been
+/// modified, reordered or truncated by whoever stored the stream. Write it to
+/// a temporary location, or otherwise hold back any effect it has, and
+/// discard it if `finalize` fails.
+pub struct StreamingUnseal {
+    // Decrypting is encrypting with the same key stream, but the tag of the
+    // decrypting handle covers the plain text. The verifying handle encrypts
+    // the plain text again, its tag covers the encrypted data.
+    decryptor: SgxAesHandle,
+    verifier: SgxAesHandle,
+    key_policy: KeyPolicy,
+    additional: Vec<u8>,
+    // The last bytes seen, which may be the tag.
+    pending: Vec<u8>,
+}
+
+impl StreamingUnseal {
+    ///
+    /// Get the size of the header that starts with `prefix`, once `prefix` holds at
+    /// least `STREAM_HEADER_FIXED_SIZE` bytes.
+    ///
+    pub fn header_len(prefix: &[u8]) -> Option<usize> {
+        if prefix.len() < STREAM_HEADER_FIXED_SIZE {
+            return None;
+        }
+        let mut len = [0_u8; 4];
+        len.copy_from_slice(&prefix[KEY_REQUEST_SIZE..STREAM_HEADER_FIXED_SIZE]);
+        STREAM_HEADER_FIXED_SIZE.checked_add(u32::from_le_bytes(len) as usize)
+    }
+
+    ///
+    /// Start unsealing a stream with the header written by `StreamingSeal`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The header is malformed.
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The sealing key cannot be derived, e.g. the stream was sealed by another enclave.
+    ///
+    /// **SGX_ERROR_INVALID_CPUSVN**, **SGX_ERROR_INVALID_ISVSVN**
+    ///
+    /// The stream was sealed on a platform or by an enclave with a higher security version.
+    ///
+    pub fn new(header: &[u8]) -> SgxResult<StreamingUnseal> {
+        if StreamingUnseal::header_len(header) != Some(header.len()) {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let key_request: sgx_key_request_t =
+            unsafe { ptr::read_unaligned(header.as_ptr() as *const sgx_key_request_t) };
+        if key_request.key_name != SGX_KEYSELECT_SEAL {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let key_policy = if (key_request.key_policy & SGX_KEYPOLICY_MRENCLAVE) != 0 {
+            KeyPolicy::MrEnclave
+        } else {
+            KeyPolicy::MrSigner
+        };
+
+        let mut seal_key = get_seal_key(&key_request)?;
+        rsgx_lfence();
+
+        let payload_iv = [0_u8; SGX_SEAL_IV_SIZE];
+        let decryptor = SgxAesHandle::new();
+        let verifier = SgxAesHandle::new();
+        // the decrypting handle authenticates nothing, skip the header
+        let result = decryptor
+            .init(&seal_key.key, &payload_iv, &[])
+            .and_then(|_| verifier.init(&seal_key.key, &payload_iv, header));
+        seal_key.key = sgx_key_128bit_t::default();
+        result.map(|_| StreamingUnseal {
+            decryptor,
+            verifier,
+            key_policy,
+            additional: header[STREAM_HEADER_FIXED_SIZE..].to_vec(),
+            pending: Vec::with_capacity(STREAM_TAG_SIZE),
+        })
+    }
+
+    ///
+    /// Get the key policy the stream was sealed with.
+    ///
+    pub fn key_policy(&self) -> KeyPolicy {
+        self.key_policy
+    }
+
+    ///
+    /// Get a slice of the additional text of the stream.
+    ///
+    pub fn get_additional_txt(&self) -> &[u8] {
+        &self.additional
+    }
+
+    ///
+    /// Decrypt the next chunk of the stream, which ends with the tag. The result is
+    /// shorter than the chunk while the tag may still be ahead.
+    ///
+    /// The result is not authenticated yet and must not be trusted before
+    /// `finalize` succeeds. A modified stream is only detected there.
+    ///
+    pub fn update(&mut self, chunk: &[u8]) -> SgxResult<Vec<u8>> {
+        self.pending.extend_from_slice(chunk);
+        let len = self.pending.len().saturating_sub(STREAM_TAG_SIZE);
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+        let encrypt: Vec<u8> = self.pending.drain(..len).collect();
+
+        let mut decrypt = vec![0_u8; len];
+        self.decryptor.update(&encrypt, &mut decrypt)?;
+        let mut reencrypt = encrypt;
+        self.verifier.update(&decrypt, &mut reencrypt)?;
+        Ok(decrypt)
+    }
+
+    ///
+    /// Finish the stream and check its tag.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The stream was modified or truncated.
+    ///
+    pub fn finalize(self) -> SgxError {
+        if self.pending.len() != STREAM_TAG_SIZE {
+            return Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH);
+        }
+        let mac = self.verifier.get_mac()?;
+        if mac[..].consttime_memeq(&self.pending[..]) {
+            Ok(())
+        } else {
+            Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH)
+        }
+    }
+}
@@ -0,0 +1,163 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use crate::boxed::Box;
+use crate::fmt;
+use crate::hash::{Hash, Hasher, SipHasher13};
+use crate::sys;
+use crate::vec;
+
+/// A Bloom filter of `BITS` bits using `K` hash functions.
+///
+/// A Bloom filter answers set membership queries with no false negatives
+/// and a tunable rate of false positives, in a fixed amount of memory. For
+/// `n` expected items and a target false positive rate `p`, choose
+/// `BITS = -n * ln(p) / ln(2)^2` and `K = BITS / n * ln(2)`; e.g. 9586 bits
+/// and 7 hashes for 1000 items at 1%.
+///
+/// The `K` hash functions are SipHash-1-3 keyed with values derived from a
+/// 16-byte seed. Filters can only be compared or merged if they share the
+/// same seed, so a filter sent to a peer must be accompanied by its
+/// [`seed`](BloomFilter::seed).
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BloomFilter;
+///
+/// let mut revoked = BloomFilter::<9586, 7>::new();
+/// revoked.insert(&"cert-1");
+///
+/// assert!(revoked.contains(&"cert-1"));
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct BloomFilter<const BITS: usize, const K: usize> {
+    bits: Box<[u64]>,
+    seed: [u8; 16],
+}
+
+impl<const BITS: usize, const K: usize> BloomFilter<BITS, K> {
+    /// Creates an empty filter with a random seed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `BITS` or `K` is zero.
+    pub fn new() -> BloomFilter<BITS, K> {
+        let (k0, k1) = sys::hashmap_random_keys();
+        let mut seed = [0_u8; 16];
+        seed[..8].copy_from_slice(&k0.to_le_bytes());
+        seed[8..].copy_from_slice(&k1.to_le_bytes());
+        BloomFilter::with_seed(seed)
+    }
+
+    /// Creates an empty filter whose hash functions are derived from `seed`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `BITS` or `K` is zero.
+    pub fn with_seed(seed: [u8; 16]) -> BloomFilter<BITS, K> {
+        assert!(BITS > 0, "a Bloom filter needs at least one bit");
+        assert!(K > 0, "a Bloom filter needs at least one hash function");
+        BloomFilter {
+            bits: vec![0_u64; (BITS + 63) / 64].into_boxed_slice(),
+            seed,
+        }
+    }
+
+    /// Returns the seed the hash functions are derived from.
+    pub fn seed(&self) -> [u8; 16] {
+        self.seed
+    }
+
+    /// Adds `item` to the filter.
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        for i in 0..K {
+            let bit = self.bit_index(i, item);
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    /// Returns `true` if `item` may have been added to the filter, and
+    /// `false` if it definitely has not.
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        (0..K).all(|i| {
+            let bit = self.bit_index(i, item);
+            self.bits[bit / 64] & (1 << (bit % 64)) != 0
+        })
+    }
+
+    /// Adds all items of `other` to this filter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filters were created with different seeds.
+    pub fn merge(&mut self, other: &BloomFilter<BITS, K>) {
+        assert!(self.seed == other.seed, "cannot merge Bloom filters with different seeds");
+        for (word, other) in self.bits.iter_mut().zip(other.bits.iter()) {
+            *word |= *other;
+        }
+    }
+
+    /// Estimates the probability that [`contains`] returns `true` for an
+    /// item which has not been added, based on the fraction of bits set.
+    ///
+    /// [`contains`]: BloomFilter::contains
+    pub fn false_positive_rate(&self) -> f64 {
+        let ones: u32 = self.bits.iter().map(|w| w.count_ones()).sum();
+        (ones as f64 / BITS as f64).powi(K as i32)
+    }
+
+    /// Returns `true` if no item has been added to the filter.
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|w| *w == 0)
+    }
+
+    /// Removes all items from the filter, keeping the seed.
+    pub fn clear(&mut self) {
+        self.bits.iter_mut().for_each(|w| *w = 0);
+    }
+
+    fn bit_index<T: Hash + ?Sized>(&self, i: usize, item: &T) -> usize {
+        let mut k0 = [0_u8; 8];
+        let mut k1 = [0_u8; 8];
+        k0.copy_from_slice(&self.seed[..8]);
+        k1.copy_from_slice(&self.seed[8..]);
+        // Derive the i-th key pair from the seed, so that every hash
+        // function is an independently keyed SipHash.
+        let mut hasher = SipHasher13::new_with_keys(
+            u64::from_le_bytes(k0) ^ (i as u64),
+            u64::from_le_bytes(k1).wrapping_add((i as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)),
+        );
+        item.hash(&mut hasher);
+        (hasher.finish() % BITS as u64) as usize
+    }
+}
+
+impl<const BITS: usize, const K: usize> Default for BloomFilter<BITS, K> {
+    fn default() -> BloomFilter<BITS, K> {
+        BloomFilter::new()
+    }
+}
+
+impl<const BITS: usize, const K: usize> fmt::Debug for BloomFilter<BITS, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BloomFilter")
+            .field("bits", &BITS)
+            .field("hashes", &K)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,324 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use crate::collections::hash_map::RandomState;
+use crate::fmt;
+use crate::hash::{BuildHasher, Hash, Hasher};
+use crate::iter::{Flatten, FromIterator, FusedIterator};
+use crate::slice;
+use crate::vec::Vec;
+
+// Slot markers of the hash index. Other values are an entry position + 1.
+const EMPTY: usize = 0;
+const DELETED: usize = usize::MAX;
+
+const MIN_SLOTS: usize = 8;
+
+/// A hash set that iterates in insertion order.
+///
+/// Items are kept in a `Vec` in the order they were first inserted, and an
+/// open-addressing hash index maps each item to its position. Inserting an
+/// item that is already present does not move it.
+///
+/// `insert`, `contains` and `remove` take O(1) time on average:
+///
+/// * The index is kept at most 3/4 full and is rebuilt at twice the size of
+///   the live items when it would overflow, so a lookup probes O(1) slots on
+///   average, and the O(n) rebuild happens at most once every Ω(n) inserts.
+/// * `remove` leaves a hole in the `Vec` instead of shifting the items after
+///   it. Once holes outnumber items, the `Vec` is compacted together with the
+///   index rebuild, which again costs O(n) after Ω(n) removals.
+///
+/// [`get_index`] is O(1) as long as no holes are pending, and O(n)
+/// otherwise.
+///
+/// [`get_index`]: IndexSet::get_index
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::IndexSet;
+///
+/// let mut seen = IndexSet::new();
+/// for id in [7, 3, 7, 1, 3] {
+///     seen.insert(id);
+/// }
+/// assert_eq!(seen.iter().copied().collect::<Vec<_>>(), [7, 3, 1]);
+/// assert_eq!(seen.get_index(1), Some(&3));
+/// ```
+#[derive(Clone)]
+pub struct IndexSet<T> {
+    entries: Vec<Option<T>>,
+    slots: Vec<usize>,
+    // non-empty slots, including deleted ones
+    used_slots: usize,
+    len: usize,
+    hash_builder: RandomState,
+}
+
+impl<T> IndexSet<T> {
+    /// Creates an empty set.
+    pub fn new() -> IndexSet<T> {
+        IndexSet {
+            entries: Vec::new(),
+            slots: Vec::new(),
+            used_slots: 0,
+            len: 0,
+            hash_builder: RandomState::new(),
+        }
+    }
+
+    /// Returns the number of items in the set.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the set contains no items.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the item at position `index` in insertion order.
+    pub fn get_index(&self, index: usize) -> Option<&T> {
+        if self.entries.len() == self.len {
+            self.entries.get(index).and_then(Option::as_ref)
+        } else {
+            self.iter().nth(index)
+        }
+    }
+
+    /// Returns an iterator over the items in insertion order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            inner: self.entries.iter().flatten(),
+            remaining: self.len,
+        }
+    }
+
+    /// Removes all items.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.slots.iter_mut().for_each(|s| *s = EMPTY);
+        self.used_slots = 0;
+        self.len = 0;
+    }
+}
+
+impl<T: Hash + Eq> IndexSet<T> {
+    /// Creates an empty set with room for at least `capacity` items.
+    pub fn with_capacity(capacity: usize) -> IndexSet<T> {
+        let mut set = IndexSet::new();
+        set.entries.reserve(capacity);
+        set.slots = vec![EMPTY; slot_count(capacity)];
+        set
+    }
+
+    /// Adds `item` to the end of the set.
+    ///
+    /// Returns `false`, and leaves the set unchanged, if an equal item is
+    /// already present.
+    pub fn insert(&mut self, item: T) -> bool {
+        let hash = self.hash(&item);
+        if self.find(hash, &item).is_ok() {
+            return false;
+        }
+        if (self.used_slots + 1) * 4 > self.slots.len() * 3 {
+            self.rebuild(self.len + 1);
+        }
+        let slot = match self.find(hash, &item) {
+            Ok(_) => unreachable!(),
+            Err(slot) => slot,
+        };
+        if self.slots[slot] == EMPTY {
+            self.used_slots += 1;
+        }
+        self.entries.push(Some(item));
+        self.slots[slot] = self.entries.len();
+        self.len += 1;
+        true
+    }
+
+    /// Returns `true` if the set contains `item`.
+    pub fn contains(&self, item: &T) -> bool {
+        self.find(self.hash(item), item).is_ok()
+    }
+
+    /// Returns the position of `item` in insertion order.
+    pub fn get_index_of(&self, item: &T) -> Option<usize> {
+        let slot = self.find(self.hash(item), item).ok()?;
+        let pos = self.slots[slot] - 1;
+        if self.entries.len() == self.len {
+            Some(pos)
+        } else {
+            Some(self.entries[..pos].iter().filter(|e| e.is_some()).count())
+        }
+    }
+
+    /// Removes `item` from the set. The order of the other items is kept.
+    ///
+    /// Returns `false` if `item` was not present.
+    pub fn remove(&mut self, item: &T) -> bool {
+        let slot = match self.find(self.hash(item), item) {
+            Ok(slot) => slot,
+            Err(_) => return false,
+        };
+        let pos = self.slots[slot] - 1;
+        self.slots[slot] = DELETED;
+        self.entries[pos] = None;
+        self.len -= 1;
+
+        if self.entries.len() - self.len > self.len {
+            self.rebuild(self.len);
+        } else if pos + 1 == self.entries.len() {
+            // A hole at the end needs no bookkeeping.
+            while let Some(None) = self.entries.last() {
+                self.entries.pop();
+            }
+        }
+        true
+    }
+
+    fn hash(&self, item: &T) -> u64 {
+        let mut hasher = self.hash_builder.build_hasher();
+        item.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    // Returns the slot holding `item`, or the slot to insert it into.
+    fn find(&self, hash: u64, item: &T) -> Result<usize, usize> {
+        if self.slots.is_empty() {
+            return Err(0);
+        }
+        let mask = self.slots.len() - 1;
+        let mut slot = hash as usize & mask;
+        let mut first_deleted = None;
+        loop {
+            match self.slots[slot] {
+                EMPTY => return Err(first_deleted.unwrap_or(slot)),
+                DELETED => {
+                    first_deleted.get_or_insert(slot);
+                }
+                pos => {
+                    if self.entries[pos - 1].as_ref() == Some(item) {
+                        return Ok(slot);
+                    }
+                }
+            }
+            slot = (slot + 1) & mask;
+        }
+    }
+
+    // Compacts the entries and rebuilds the index for `capacity` items.
+    fn rebuild(&mut self, capacity: usize) {
+        self.entries.retain(Option::is_some);
+        self.slots = vec![EMPTY; slot_count(capacity)];
+        self.used_slots = 0;
+        for pos in 0..self.entries.len() {
+            let item = self.entries[pos].as_ref().unwrap();
+            let slot = match self.find(self.hash(item), item) {
+                Ok(_) => unreachable!(),
+                Err(slot) => slot,
+            };
+            self.slots[slot] = pos + 1;
+            self.used_slots += 1;
+        }
+    }
+}
+
+// Twice as many slots as items, so that the index is half full right after
+// a rebuild.
+fn slot_count(capacity: usize) -> usize {
+    capacity.saturating_mul(2).next_power_of_two().max(MIN_SLOTS)
+}
+
+impl<T> Default for IndexSet<T> {
+    fn default() -> IndexSet<T> {
+        IndexSet::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for IndexSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Hash + Eq> FromIterator<T> for IndexSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> IndexSet<T> {
+        let mut set = IndexSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: Hash + Eq> Extend<T> for IndexSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(item);
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a IndexSet<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+/// An iterator over the items of an [`IndexSet`] in insertion order.
+///
+/// This `struct` is created by [`IndexSet::iter`].
+pub struct Iter<'a, T> {
+    inner: Flatten<slice::Iter<'a, Option<T>>>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let item = self.inner.next()?;
+        self.remaining -= 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Iter {
+            inner: self.inner.clone(),
+            remaining: self.remaining,
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T: fmt::Debug> fmt::Debug for Iter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
@@ -422,13 +422,23 @@ pub use alloc_crate::collections::{linked_list, vec_deque};
 pub use alloc_crate::collections::{BTreeMap, BTreeSet, BinaryHeap};
 pub use alloc_crate::collections::{LinkedList, VecDeque};
 
+pub use self::bloom::BloomFilter;
 pub use self::hash_map::HashMap;
 pub use self::hash_set::HashSet;
+pub use self::index_set::IndexSet;
+pub use self::secure_map::SecureMap;
+#[cfg(feature = "untrusted_time")]
+pub use self::timed_cache::TimedCache;
 
 pub use alloc_crate::collections::TryReserveError;
 pub use alloc_crate::collections::TryReserveErrorKind;
 
+mod bloom;
 mod hash;
+mod index_set;
+mod secure_map;
+#[cfg(feature = "untrusted_time")]
+mod timed_cache;
 
 pub mod hash_map {
     //! A hash map implemented with quadratic probing and SIMD lookup.
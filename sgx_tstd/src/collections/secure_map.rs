@@ -0,0 +1,415 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use crate::boxed::Box;
+use crate::fmt;
+use crate::hash::{Hash, Hasher, SipHasher13};
+use crate::mem;
+use crate::sys;
+use crate::vec::Vec;
+
+// Blocks per bucket of an ORAM tree.
+const Z: usize = 4;
+// Entries per bucket of the hash table.
+const SLOTS: usize = 8;
+// Leaves packed into one block of a position map ORAM.
+const POSITIONS_PER_BLOCK: usize = 16;
+// Position maps up to this size are scanned instead of being stored in an ORAM.
+const MAX_FLAT_POSITIONS: usize = 64;
+
+/// A hash map whose memory access pattern does not depend on the keys
+/// looked up.
+///
+/// The host can observe which pages, and with some effort which cache lines,
+/// an enclave touches. For an ordinary [`HashMap`] that reveals which entry
+/// was accessed, and over time which keys are popular. `SecureMap` stores its
+/// entries in a [Path ORAM], in which every operation reads and rewrites a
+/// path of the tree chosen uniformly at random, so that the sequence of
+/// memory accesses looks the same whatever the keys are. The ORAM's position
+/// map is itself stored in smaller ORAMs, recursively, giving `O(log² N)`
+/// time per operation for a map of capacity `N`.
+///
+/// Keys are assigned to two buckets of a hash table with a secret, randomly
+/// keyed SipHash, and both buckets are always accessed. [`get`] and
+/// [`remove`] read two ORAM paths, [`insert`] three.
+///
+/// This is a research-grade implementation with the following limits:
+///
+/// * The capacity is fixed when the map is created.
+/// * Only the accesses to the ORAM tree are oblivious. The small stash and
+///   the scans over a bucket branch on the data, which may still be
+///   observable through the timing of a single operation.
+/// * The number of entries is not hidden.
+/// * All operations, including [`get`], take `&mut self`, because every
+///   access moves the data it touched to a new random position.
+///
+/// [`HashMap`]: crate::collections::HashMap
+/// [Path ORAM]: https://eprint.iacr.org/2013/280
+/// [`get`]: SecureMap::get
+/// [`insert`]: SecureMap::insert
+/// [`remove`]: SecureMap::remove
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::SecureMap;
+///
+/// let mut sessions = SecureMap::with_capacity(1024);
+/// sessions.insert("alice", 1);
+/// sessions.insert("bob", 2);
+///
+/// assert_eq!(sessions.get(&"alice"), Some(1));
+/// assert_eq!(sessions.remove(&"bob"), Some(2));
+/// assert_eq!(sessions.get(&"bob"), None);
+/// ```
+pub struct SecureMap<K, V> {
+    oram: PathOram<[Option<(K, V)>; SLOTS]>,
+    buckets: usize,
+    len: usize,
+    capacity: usize,
+    hash_keys: [(u64, u64); 2],
+    trace: Trace,
+}
+
+impl<K: Hash + Eq, V: Clone> SecureMap<K, V> {
+    /// Creates an empty map which can hold up to `capacity` entries.
+    pub fn with_capacity(capacity: usize) -> SecureMap<K, V> {
+        let buckets = capacity.max(1);
+        SecureMap {
+            oram: PathOram::new(buckets, 0),
+            buckets,
+            len: 0,
+            capacity,
+            hash_keys: [sys::hashmap_random_keys(), sys::hashmap_random_keys()],
+            trace: None,
+        }
+    }
+
+    /// Returns a copy of the value corresponding to the key.
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        let (b1, b2) = self.bucket_indices(key);
+        let mut value = None;
+        for b in [b1, b2] {
+            let found = self.oram.access(b, &mut self.trace, |bucket| {
+                let mut found = None;
+                for (k, v) in bucket.iter().flatten() {
+                    if k == key {
+                        found = Some(v.clone());
+                    }
+                }
+                found
+            });
+            value = value.or(found);
+        }
+        value
+    }
+
+    /// Returns `true` if the map contains a value for the key.
+    pub fn contains_key(&mut self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Inserts a key-value pair into the map, returning the old value if the
+    /// key was present.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not present and the map is full, or in the
+    /// negligibly unlikely case that both buckets of the key are full.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (b1, b2) = self.bucket_indices(&key);
+        let mut entry = Some((key, value));
+        // A new key may not be placed in either bucket once the map is full.
+        let full = self.len >= self.capacity;
+
+        // Update the entry if it is in the first bucket, and count how full
+        // that bucket is.
+        let (old, load1) = self.oram.access(b1, &mut self.trace, |bucket| {
+            let (hit, _, load) = scan(bucket, &entry.as_ref().unwrap().0);
+            (hit.map(|i| replace_value(&mut bucket[i], &mut entry)), load)
+        });
+        if old.is_some() {
+            self.dummy_access(b2);
+            self.dummy_access(b1);
+            return old;
+        }
+
+        // Update the entry if it is in the second bucket, or add it there if
+        // that bucket is the emptier one.
+        let old = self.oram.access(b2, &mut self.trace, |bucket| {
+            let (hit, free, load2) = scan(bucket, &entry.as_ref().unwrap().0);
+            match (hit, free) {
+                (Some(i), _) => Some(replace_value(&mut bucket[i], &mut entry)),
+                (None, Some(i)) if load2 < load1 && !full => {
+                    bucket[i] = entry.take();
+                    None
+                }
+                _ => None,
+            }
+        });
+        if old.is_some() {
+            self.dummy_access(b1);
+            return old;
+        }
+
+        assert!(entry.is_none() || !full, "SecureMap is full");
+        self.oram.access(b1, &mut self.trace, |bucket| {
+            if entry.is_some() {
+                let slot = bucket.iter_mut().find(|slot| slot.is_none());
+                *slot.expect("SecureMap bucket overflow") = entry.take();
+            }
+        });
+        self.len += 1;
+        None
+    }
+
+    /// Removes a key from the map, returning its value if the key was
+    /// present.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let (b1, b2) = self.bucket_indices(key);
+        let mut value = None;
+        for b in [b1, b2] {
+            let removed = self.oram.access(b, &mut self.trace, |bucket| {
+                let (hit, _, _) = scan(bucket, key);
+                hit.and_then(|i| bucket[i].take()).map(|(_, v)| v)
+            });
+            value = value.or(removed);
+        }
+        if value.is_some() {
+            self.len -= 1;
+        }
+        value
+    }
+
+    fn dummy_access(&mut self, b: usize) {
+        self.oram.access(b, &mut self.trace, |_| ());
+    }
+
+    fn bucket_indices(&self, key: &K) -> (usize, usize) {
+        let index = |(k0, k1): (u64, u64)| {
+            let mut hasher = SipHasher13::new_with_keys(k0, k1);
+            key.hash(&mut hasher);
+            (hasher.finish() % self.buckets as u64) as usize
+        };
+        (index(self.hash_keys[0]), index(self.hash_keys[1]))
+    }
+}
+
+impl<K, V> SecureMap<K, V> {
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of entries the map can hold.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Starts recording the ORAM paths read by the map.
+    ///
+    /// This is meant for analysing the access pattern of the map, see
+    /// [`take_trace`](SecureMap::take_trace).
+    pub fn start_trace(&mut self) {
+        self.trace = Some(Vec::new());
+    }
+
+    /// Stops recording and returns the ORAM paths read since
+    /// [`start_trace`](SecureMap::start_trace) was called.
+    ///
+    /// Every path is returned as the level of the ORAM, `0` for the one
+    /// holding the entries and higher levels for the position maps, and the
+    /// leaf the path leads to. Returns an empty vector if no trace was being
+    /// recorded.
+    pub fn take_trace(&mut self) -> Vec<(usize, usize)> {
+        self.trace.take().unwrap_or_default()
+    }
+}
+
+impl<K, V> fmt::Debug for SecureMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecureMap")
+            .field("len", &self.len)
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+// Returns the slot holding `key`, the first free slot and the number of
+// occupied slots.
+fn scan<K: Eq, V>(bucket: &[Option<(K, V)>], key: &K) -> (Option<usize>, Option<usize>, usize) {
+    let mut hit = None;
+    let mut free = None;
+    let mut load = 0;
+    for (i, slot) in bucket.iter().enumerate() {
+        match slot {
+            Some((k, _)) => {
+                load += 1;
+                if k == key {
+                    hit = Some(i);
+                }
+            }
+            None => {
+                if free.is_none() {
+                    free = Some(i);
+                }
+            }
+        }
+    }
+    (hit, free, load)
+}
+
+fn replace_value<K, V>(slot: &mut Option<(K, V)>, entry: &mut Option<(K, V)>) -> V {
+    let (_, value) = entry.take().unwrap();
+    mem::replace(&mut slot.as_mut().unwrap().1, value)
+}
+
+// (level, leaf) of every path read, if recording
+type Trace = Option<Vec<(usize, usize)>>;
+
+fn random_leaf(leaves: usize) -> usize {
+    sys::hashmap_random_keys().0 as usize & (leaves - 1)
+}
+
+struct Block<T> {
+    id: usize,
+    leaf: usize,
+    data: T,
+}
+
+// Path ORAM over blocks `0..n` holding a `T` each. A block which has never
+// been accessed holds `T::default()`.
+struct PathOram<T> {
+    level: usize,
+    height: u32,
+    // Buckets of `Z` slots in heap order, the root first.
+    tree: Vec<Option<Block<T>>>,
+    stash: Vec<Block<T>>,
+    positions: PositionMap,
+}
+
+impl<T: Default> PathOram<T> {
+    fn new(n: usize, level: usize) -> PathOram<T> {
+        let height = n.max(2).next_power_of_two().trailing_zeros();
+        let buckets = (2 << height) - 1;
+        PathOram {
+            level,
+            height,
+            tree: (0..buckets * Z).map(|_| None).collect(),
+            stash: Vec::new(),
+            positions: PositionMap::new(n, level),
+        }
+    }
+
+    fn access<R, F: FnOnce(&mut T) -> R>(&mut self, id: usize, trace: &mut Trace, f: F) -> R {
+        let leaves = 1 << self.height;
+        let new_leaf = random_leaf(leaves);
+        let leaf = self.positions.remap(id, new_leaf, leaves, trace);
+        if let Some(trace) = trace {
+            trace.push((self.level, leaf));
+        }
+
+        for depth in 0..=self.height {
+            let bucket = self.bucket(leaf, depth);
+            for slot in &mut self.tree[bucket * Z..(bucket + 1) * Z] {
+                if let Some(block) = slot.take() {
+                    self.stash.push(block);
+                }
+            }
+        }
+
+        let mut index = None;
+        for (i, block) in self.stash.iter().enumerate() {
+            if block.id == id {
+                index = Some(i);
+            }
+        }
+        let index = index.unwrap_or_else(|| {
+            self.stash.push(Block { id, leaf: new_leaf, data: T::default() });
+            self.stash.len() - 1
+        });
+        let block = &mut self.stash[index];
+        block.leaf = new_leaf;
+        let ret = f(&mut block.data);
+
+        // Write the stash back along the path, each block as deep as its
+        // own path allows.
+        for depth in (0..=self.height).rev() {
+            let bucket = self.bucket(leaf, depth);
+            let mut slot = bucket * Z;
+            let mut i = 0;
+            while i < self.stash.len() && slot < (bucket + 1) * Z {
+                if self.bucket(self.stash[i].leaf, depth) == bucket {
+                    self.tree[slot] = Some(self.stash.swap_remove(i));
+                    slot += 1;
+                } else {
+                    i += 1;
+                }
+            }
+        }
+        ret
+    }
+
+    fn bucket(&self, leaf: usize, depth: u32) -> usize {
+        (1 << depth) - 1 + (leaf >> (self.height - depth))
+    }
+}
+
+// Maps block ids to leaves. A stored `0` means the block has not been
+// assigned a leaf yet, other values are the leaf plus one.
+enum PositionMap {
+    Flat(Vec<usize>),
+    Oram(Box<PathOram<[usize; POSITIONS_PER_BLOCK]>>),
+}
+
+impl PositionMap {
+    fn new(n: usize, level: usize) -> PositionMap {
+        if n <= MAX_FLAT_POSITIONS {
+            PositionMap::Flat(vec![0; n])
+        } else {
+            let blocks = (n + POSITIONS_PER_BLOCK - 1) / POSITIONS_PER_BLOCK;
+            PositionMap::Oram(Box::new(PathOram::new(blocks, level + 1)))
+        }
+    }
+
+    // Sets the leaf of `id` to `new_leaf` and returns its previous leaf.
+    fn remap(&mut self, id: usize, new_leaf: usize, leaves: usize, trace: &mut Trace) -> usize {
+        let old = match self {
+            PositionMap::Flat(positions) => swap_position(positions, id, new_leaf),
+            PositionMap::Oram(oram) => oram.access(id / POSITIONS_PER_BLOCK, trace, |positions| {
+                swap_position(positions, id % POSITIONS_PER_BLOCK, new_leaf)
+            }),
+        };
+        if old == 0 { random_leaf(leaves) } else { old - 1 }
+    }
+}
+
+// Touches every position, so that the access does not depend on `index`.
+fn swap_position(positions: &mut [usize], index: usize, new_leaf: usize) -> usize {
+    let mut old = 0;
+    for (i, position) in positions.iter_mut().enumerate() {
+        let mask = ((i == index) as usize).wrapping_neg();
+        old |= *position & mask;
+        *position = ((new_leaf + 1) & mask) | (*position & !mask);
+    }
+    old
+}
@@ -0,0 +1,168 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+use crate::borrow::Borrow;
+use crate::collections::hash_map::{self, HashMap};
+use crate::fmt;
+use crate::hash::Hash;
+use crate::time::{Duration, Instant};
+
+/// A hash map whose entries expire a fixed time after they were inserted.
+///
+/// Expired entries are never returned by lookups. They keep occupying
+/// memory until they are overwritten, removed, or evicted in bulk by
+/// [`remove_expired`].
+///
+/// Ages are measured with [`Instant`], which reads the monotonic clock of
+/// the host through an OCALL. The host can therefore make entries expire
+/// early or late, but it cannot make a lookup return an entry that was
+/// never inserted. Do not rely on expiry alone where a stale entry would be
+/// a security problem, e.g. for revoked credentials.
+///
+/// [`remove_expired`]: TimedCache::remove_expired
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::TimedCache;
+/// use std::time::Duration;
+///
+/// let mut sessions = TimedCache::new(Duration::from_secs(300));
+/// sessions.insert("token", 42);
+/// assert_eq!(sessions.get("token"), Some(&42));
+/// assert_eq!(*sessions.get_or_insert_with("other", || 7), 7);
+/// ```
+pub struct TimedCache<K, V> {
+    map: HashMap<K, (Instant, V)>,
+    ttl: Duration,
+}
+
+impl<K: Hash + Eq, V> TimedCache<K, V> {
+    /// Creates an empty cache whose entries expire `ttl` after they were
+    /// inserted.
+    pub fn new(ttl: Duration) -> TimedCache<K, V> {
+        TimedCache { map: HashMap::new(), ttl }
+    }
+
+    /// Returns the time to live of the entries.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Inserts an entry, replacing any previous entry for `key` and
+    /// restarting its time to live.
+    ///
+    /// Returns the previous value if it had not expired yet.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let now = Instant::_now();
+        self.map
+            .insert(key, (now, value))
+            .and_then(|(inserted, old)| if self.is_expired(inserted, now) { None } else { Some(old) })
+    }
+
+    /// Returns the value for `key`, or `None` if there is none or it has
+    /// expired.
+    pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq,
+    {
+        let now = Instant::_now();
+        match self.map.get(key) {
+            Some(&(inserted, ref value)) if !self.is_expired(inserted, now) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the cache holds an unexpired value for `key`.
+    pub fn contains_key<Q: ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Returns the value for `key`, inserting the result of `f` if there is
+    /// none or it has expired.
+    ///
+    /// An unexpired value keeps its original time to live.
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, key: K, f: F) -> &V {
+        let now = Instant::_now();
+        let ttl = self.ttl;
+        match self.map.entry(key) {
+            hash_map::Entry::Occupied(entry) => {
+                let entry = entry.into_mut();
+                if now.saturating_duration_since(entry.0) >= ttl {
+                    *entry = (now, f());
+                }
+                &entry.1
+            }
+            hash_map::Entry::Vacant(entry) => &entry.insert((now, f())).1,
+        }
+    }
+
+    /// Removes the entry for `key`, returning its value if it had not
+    /// expired yet.
+    pub fn remove<Q: ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq,
+    {
+        let now = Instant::_now();
+        self.map
+            .remove(key)
+            .and_then(|(inserted, value)| if self.is_expired(inserted, now) { None } else { Some(value) })
+    }
+
+    /// Evicts all expired entries, returning how many were removed.
+    pub fn remove_expired(&mut self) -> usize {
+        let now = Instant::_now();
+        let ttl = self.ttl;
+        let before = self.map.len();
+        self.map.retain(|_, &mut (inserted, _)| now.saturating_duration_since(inserted) < ttl);
+        before - self.map.len()
+    }
+
+    /// Returns the number of entries, including expired entries that have
+    /// not been evicted yet.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the cache holds no entries, expired or not.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+    fn is_expired(&self, inserted: Instant, now: Instant) -> bool {
+        now.saturating_duration_since(inserted) >= self.ttl
+    }
+}
+
+impl<K, V> fmt::Debug for TimedCache<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimedCache")
+            .field("len", &self.map.len())
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
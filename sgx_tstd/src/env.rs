@@ -26,12 +26,14 @@
 //! and those without will return a [`String`].
 
 #![allow(clippy::needless_doctest_main)]
+use crate::cell::RefCell;
 use crate::error::Error;
 use crate::ffi::{OsStr, OsString};
 use crate::fmt;
 use crate::io;
 use crate::path::{Path, PathBuf};
 use crate::sys::os as os_imp;
+use crate::vec;
 
 /// Returns the current working directory as a [`PathBuf`].
 ///
@@ -198,6 +200,99 @@ impl fmt::Debug for VarsOs {
     }
 }
 
+/// The program name reported by [`args`] unless overridden.
+const DEFAULT_PROGRAM_NAME: &str = "enclave";
+
+thread_local! {
+    static ARGS_OVERRIDE: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// An iterator over the arguments of the enclave, yielding a [`String`] value
+/// for each argument.
+///
+/// This structure is created by [`env::args()`] or [`Args::from_vec`]. See
+/// their documentation for more.
+///
+/// [`env::args()`]: args
+pub struct Args {
+    inner: vec::IntoIter<String>,
+}
+
+impl Args {
+    /// Creates an iterator over the given arguments. By convention the first
+    /// one is the program name.
+    pub fn from_vec(args: Vec<String>) -> Args {
+        Args { inner: args.into_iter() }
+    }
+}
+
+/// Returns the arguments the enclave was "started" with.
+///
+/// An enclave has no command line, so the arguments are simulated: unless
+/// [`override_args`] was called on the current thread, this returns just the
+/// program name, `"enclave"`. This lets code that is configured through
+/// arguments, such as hyperparameter sweeps in the samples, be driven from
+/// inside the enclave.
+///
+/// # Examples
+///
+/// ```
+/// use std::env;
+///
+/// env::override_args(vec!["enclave".to_owned(), "--epochs=10".to_owned()]);
+/// let args: Vec<String> = env::args().collect();
+/// assert_eq!(args, ["enclave", "--epochs=10"]);
+/// ```
+#[must_use]
+pub fn args() -> Args {
+    let args = ARGS_OVERRIDE
+        .with(|args| args.borrow().clone())
+        .unwrap_or_else(|| vec![DEFAULT_PROGRAM_NAME.to_owned()]);
+    Args::from_vec(args)
+}
+
+/// Replaces the arguments returned by [`args`] on the current thread.
+///
+/// Other threads are not affected. Iterators returned by [`args`] before the
+/// call keep the old arguments.
+pub fn override_args(args: Vec<String>) {
+    ARGS_OVERRIDE.with(|slot| *slot.borrow_mut() = Some(args));
+}
+
+/// Undoes [`override_args`] on the current thread, so that [`args`] returns
+/// the default arguments again.
+pub fn reset_args() {
+    ARGS_OVERRIDE.with(|slot| *slot.borrow_mut() = None);
+}
+
+impl Iterator for Args {
+    type Item = String;
+    fn next(&mut self) -> Option<String> {
+        self.inner.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Args {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl DoubleEndedIterator for Args {
+    fn next_back(&mut self) -> Option<String> {
+        self.inner.next_back()
+    }
+}
+
+impl fmt::Debug for Args {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Args").field("args", &self.inner.as_slice()).finish()
+    }
+}
+
 /// Fetches the environment variable `key` from the current process.
 ///
 /// # Errors
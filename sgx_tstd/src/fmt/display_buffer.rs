@@ -0,0 +1,150 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use crate::fmt;
+use crate::str;
+
+const TRUNCATION_MARKER: &str = "...";
+
+/// A fixed-capacity buffer of `N` bytes for formatting without allocating.
+///
+/// `DisplayBuffer` implements [`fmt::Write`], so it can be the target of
+/// [`write!`]. Output that does not fit is cut off at a character boundary
+/// and ends in `"..."`, keeping the total within `N` bytes. Writing never
+/// fails, so formatting into a `DisplayBuffer` is as cheap as it gets on a
+/// tight EPC budget.
+///
+/// # Examples
+///
+/// ```
+/// use std::fmt::{DisplayBuffer, Write};
+///
+/// let mut buf = DisplayBuffer::<16>::new();
+/// write!(buf, "error {} in {}", 42, "ecall_handle_request").unwrap();
+/// assert_eq!(buf.as_str(), "error 42 in e...");
+/// assert!(buf.is_truncated());
+/// ```
+#[derive(Clone, Copy)]
+pub struct DisplayBuffer<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+    truncated: bool,
+}
+
+impl<const N: usize> DisplayBuffer<N> {
+    /// Creates an empty buffer.
+    pub const fn new() -> DisplayBuffer<N> {
+        DisplayBuffer {
+            buf: [0; N],
+            len: 0,
+            truncated: false,
+        }
+    }
+
+    /// Returns the formatted output, including the truncation marker if the
+    /// output did not fit.
+    pub fn as_str(&self) -> &str {
+        // Only whole characters are ever copied into the buffer.
+        unsafe { str::from_utf8_unchecked(&self.buf[..self.len]) }
+    }
+
+    /// Returns the length of [`as_str`](DisplayBuffer::as_str) in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if nothing has been written to the buffer.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if some of the output was cut off.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Empties the buffer.
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.truncated = false;
+    }
+
+    fn push(&mut self, s: &str) {
+        self.buf[self.len..self.len + s.len()].copy_from_slice(s.as_bytes());
+        self.len += s.len();
+    }
+}
+
+impl<const N: usize> fmt::Write for DisplayBuffer<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.truncated {
+            return Ok(());
+        }
+        if s.len() <= N - self.len {
+            self.push(s);
+            return Ok(());
+        }
+
+        // Keep what fits in front of the marker, which may mean dropping
+        // some of the output already written.
+        let marker = &TRUNCATION_MARKER[..TRUNCATION_MARKER.len().min(N)];
+        let room = N - marker.len();
+        if self.len > room {
+            self.len = floor_char_boundary(self.as_str(), room);
+        } else {
+            let end = floor_char_boundary(s, room - self.len);
+            self.push(&s[..end]);
+        }
+        self.push(marker);
+        self.truncated = true;
+        Ok(())
+    }
+}
+
+fn floor_char_boundary(s: &str, mut index: usize) -> usize {
+    while !s.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+impl<const N: usize> Default for DisplayBuffer<N> {
+    fn default() -> DisplayBuffer<N> {
+        DisplayBuffer::new()
+    }
+}
+
+impl<const N: usize> fmt::Display for DisplayBuffer<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<const N: usize> fmt::Debug for DisplayBuffer<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize> From<&dyn fmt::Display> for DisplayBuffer<N> {
+    /// Formats `value` into a new buffer, truncating it if necessary.
+    fn from(value: &dyn fmt::Display) -> DisplayBuffer<N> {
+        let mut buf = DisplayBuffer::new();
+        let _ = fmt::Write::write_fmt(&mut buf, format_args!("{}", value));
+        buf
+    }
+}
@@ -107,6 +107,40 @@ impl<R: Read> BufReader<R> {
     pub fn with_capacity(capacity: usize, inner: R) -> BufReader<R> {
         BufReader { inner, buf: Buffer::with_capacity(capacity) }
     }
+
+    /// Like [`BufRead::fill_buf`], but fails instead of returning an empty
+    /// slice.
+    ///
+    /// If the internal buffer is empty and the underlying reader yields no
+    /// more data, an error of kind [`io::ErrorKind::UnexpectedEof`] is
+    /// returned. This is useful for parsers that need more input to make
+    /// progress, where an empty buffer means the stream ended or is stuck.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io::{BufReader, ErrorKind};
+    /// use std::net::TcpStream;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let stream = TcpStream::connect("127.0.0.1:8080")?;
+    ///     let mut reader = BufReader::new(stream);
+    ///     match reader.fill_buf_checked() {
+    ///         Ok(buf) => println!("{} bytes available", buf.len()),
+    ///         Err(e) if e.kind() == ErrorKind::UnexpectedEof => println!("stream ended"),
+    ///         Err(e) => return Err(e),
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn fill_buf_checked(&mut self) -> io::Result<&[u8]> {
+        let buf = self.buf.fill_buf(&mut self.inner)?;
+        if buf.is_empty() {
+            Err(io::const_io_error!(io::ErrorKind::UnexpectedEof, "no data available from the underlying reader"))
+        } else {
+            Ok(buf)
+        }
+    }
 }
 
 impl<R> BufReader<R> {
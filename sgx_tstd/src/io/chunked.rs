@@ -0,0 +1,300 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Fixed-size chunking of byte streams, hiding the length of each message.
+
+use crate::cmp;
+use crate::fmt;
+use crate::io::{self, Read, Write};
+use crate::vec::Vec;
+
+// Every chunk starts with the number of data bytes in it, as a big-endian
+// u32.
+const LEN_SIZE: usize = 4;
+
+/// Wraps a writer and only ever writes to it in chunks of exactly
+/// `chunk_size` bytes.
+///
+/// Every chunk starts with a 4-byte big-endian length, followed by up to
+/// `chunk_size - 4` bytes of data. Data is buffered until a full chunk is
+/// available. Calling [`flush`] fills the rest of a partial chunk with
+/// `pad_byte` and writes it out, so an observer of the underlying writer
+/// only learns how many chunks were written. Dropping the writer does the
+/// same but ignores any error.
+///
+/// [`ChunkedReader`] uses the length to remove the padding again, so any
+/// data, including data ending in `pad_byte`, reads back unchanged.
+///
+/// [`flush`]: Write::flush
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{ChunkedReader, ChunkedWriter, Read, Write};
+///
+/// let mut writer = ChunkedWriter::new(Vec::new(), 64, 0);
+/// writer.write_all(b"hello enclave").unwrap();
+/// let padded = writer.into_inner().unwrap();
+/// assert_eq!(padded.len(), 64);
+///
+/// let mut reader = ChunkedReader::new(&padded[..], 64, 0);
+/// let mut message = String::new();
+/// reader.read_to_string(&mut message).unwrap();
+/// assert_eq!(message, "hello enclave");
+/// ```
+pub struct ChunkedWriter<W: Write> {
+    inner: Option<W>,
+    buf: Vec<u8>,
+    chunk_size: usize,
+    pad_byte: u8,
+    panicked: bool,
+}
+
+impl<W: Write> ChunkedWriter<W> {
+    /// Creates a new `ChunkedWriter` which writes chunks of `chunk_size`
+    /// bytes, padded with `pad_byte`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is not larger than the 4-byte length, or if
+    /// the data of a chunk does not fit in a `u32`.
+    pub fn new(inner: W, chunk_size: usize, pad_byte: u8) -> ChunkedWriter<W> {
+        assert!(valid_chunk_size(chunk_size), "invalid chunk size");
+        let mut buf = Vec::with_capacity(chunk_size);
+        buf.resize(LEN_SIZE, 0);
+        ChunkedWriter {
+            inner: Some(inner),
+            buf,
+            chunk_size,
+            pad_byte,
+            panicked: false,
+        }
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().unwrap()
+    }
+
+    /// Gets a mutable reference to the underlying writer.
+    ///
+    /// Writing directly to the underlying writer breaks the chunking.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.as_mut().unwrap()
+    }
+
+    /// Returns the size of the chunks.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Pads and writes out the buffered data, flushes the underlying writer
+    /// and returns it.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.flush()?;
+        Ok(self.inner.take().unwrap())
+    }
+
+    fn has_data(&self) -> bool {
+        self.buf.len() > LEN_SIZE
+    }
+
+    fn write_chunk(&mut self) -> io::Result<()> {
+        let filled = self.buf.len();
+        let len = (filled - LEN_SIZE) as u32;
+        self.buf[..LEN_SIZE].copy_from_slice(&len.to_be_bytes());
+        self.buf.resize(self.chunk_size, self.pad_byte);
+
+        self.panicked = true;
+        let r = self.inner.as_mut().unwrap().write_all(&self.buf);
+        self.panicked = false;
+        if let Err(e) = r {
+            self.buf.truncate(filled);
+            return Err(e);
+        }
+
+        self.buf.truncate(LEN_SIZE);
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for ChunkedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.buf.len() == self.chunk_size {
+            self.write_chunk()?;
+        }
+        let n = cmp::min(buf.len(), self.chunk_size - self.buf.len());
+        self.buf.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.has_data() {
+            self.write_chunk()?;
+        }
+        self.get_mut().flush()
+    }
+}
+
+impl<W: Write> Drop for ChunkedWriter<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() && !self.panicked && self.has_data() {
+            // dtors should not panic, so we ignore a failed write
+            let _r = self.write_chunk();
+        }
+    }
+}
+
+impl<W: Write + fmt::Debug> fmt::Debug for ChunkedWriter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkedWriter")
+            .field("inner", &self.inner)
+            .field("chunk_size", &self.chunk_size)
+            .field("pad_byte", &self.pad_byte)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Wraps a reader of a stream produced by [`ChunkedWriter`] and yields the
+/// data with the padding removed.
+///
+/// The underlying reader is read a whole chunk at a time, and everything
+/// after the data length given at the start of the chunk is dropped. Reads
+/// fail with [`ErrorKind::UnexpectedEof`] if the stream ends in the middle
+/// of a chunk, and with [`ErrorKind::InvalidData`] if a chunk claims more
+/// data than fits in it.
+///
+/// [`ErrorKind::UnexpectedEof`]: io::ErrorKind::UnexpectedEof
+/// [`ErrorKind::InvalidData`]: io::ErrorKind::InvalidData
+pub struct ChunkedReader<R: Read> {
+    inner: R,
+    buf: Vec<u8>,
+    pos: usize,
+    chunk_size: usize,
+    pad_byte: u8,
+    done: bool,
+}
+
+impl<R: Read> ChunkedReader<R> {
+    /// Creates a new `ChunkedReader` which reads chunks of `chunk_size`
+    /// bytes, padded with `pad_byte`.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`ChunkedWriter::new`].
+    pub fn new(inner: R, chunk_size: usize, pad_byte: u8) -> ChunkedReader<R> {
+        assert!(valid_chunk_size(chunk_size), "invalid chunk size");
+        ChunkedReader {
+            inner,
+            buf: Vec::with_capacity(chunk_size),
+            pos: 0,
+            chunk_size,
+            pad_byte,
+            done: false,
+        }
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Unwraps this `ChunkedReader`, returning the underlying reader.
+    ///
+    /// Data of the current chunk which has not been read yet is lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn read_chunk(&mut self) -> io::Result<()> {
+        self.buf.resize(self.chunk_size, 0);
+        self.pos = 0;
+
+        let mut filled = 0;
+        while filled < self.chunk_size {
+            match self.inner.read(&mut self.buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.buf.clear();
+                    return Err(e);
+                }
+            }
+        }
+        if filled == 0 {
+            self.buf.clear();
+            self.done = true;
+            return Ok(());
+        }
+        if filled < self.chunk_size {
+            self.buf.clear();
+            return Err(io::const_io_error!(
+                io::ErrorKind::UnexpectedEof,
+                "chunked stream truncated"
+            ));
+        }
+
+        let mut len = [0_u8; LEN_SIZE];
+        len.copy_from_slice(&self.buf[..LEN_SIZE]);
+        let len = u32::from_be_bytes(len) as usize;
+        if len > self.chunk_size - LEN_SIZE {
+            self.buf.clear();
+            return Err(io::const_io_error!(
+                io::ErrorKind::InvalidData,
+                "invalid chunk length"
+            ));
+        }
+        self.buf.truncate(LEN_SIZE + len);
+        self.pos = LEN_SIZE;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for ChunkedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            if self.done || buf.is_empty() {
+                return Ok(0);
+            }
+            self.read_chunk()?;
+        }
+        let n = cmp::min(buf.len(), self.buf.len() - self.pos);
+        buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl<R: Read + fmt::Debug> fmt::Debug for ChunkedReader<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkedReader")
+            .field("inner", &self.inner)
+            .field("chunk_size", &self.chunk_size)
+            .field("pad_byte", &self.pad_byte)
+            .finish_non_exhaustive()
+    }
+}
+
+fn valid_chunk_size(chunk_size: usize) -> bool {
+    chunk_size > LEN_SIZE && chunk_size - LEN_SIZE <= u32::MAX as usize
+}
@@ -18,7 +18,7 @@
 mod repr_unpacked;
 use repr_unpacked::Repr;
 
-use crate::convert::From;
+use crate::convert::{From, TryFrom};
 use crate::error;
 use crate::fmt;
 use crate::result;
@@ -347,7 +347,7 @@ pub enum ErrorKind {
     #[doc(hidden)]
     Uncategorized,
 
-    /// SGX error status
+    /// SGX error status, for the statuses without a more specific kind.
     SgxError,
 }
 
@@ -447,6 +447,55 @@ impl From<sgx_status_t> for Error {
     }
 }
 
+/// Recovers the SGX status of an I/O error.
+///
+/// Errors created from an `sgx_status_t` give back that status. Other errors
+/// of the kinds that SGX statuses map to give back the corresponding common
+/// status, e.g. [`ErrorKind::InvalidInput`] gives `SGX_ERROR_INVALID_PARAMETER`.
+/// The remaining errors are returned unchanged.
+impl TryFrom<Error> for sgx_status_t {
+    type Error = Error;
+
+    fn try_from(err: Error) -> result::Result<sgx_status_t, Error> {
+        if let Some(status) = err.raw_sgx_error() {
+            return Ok(status);
+        }
+        match err.kind() {
+            ErrorKind::InvalidInput => Ok(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+            ErrorKind::OutOfMemory => Ok(sgx_status_t::SGX_ERROR_OUT_OF_MEMORY),
+            ErrorKind::Unsupported => Ok(sgx_status_t::SGX_ERROR_FEATURE_NOT_SUPPORTED),
+            ErrorKind::ResourceBusy => Ok(sgx_status_t::SGX_ERROR_BUSY),
+            ErrorKind::PermissionDenied => Ok(sgx_status_t::SGX_ERROR_NO_PRIVILEGE),
+            ErrorKind::Other => Ok(sgx_status_t::SGX_ERROR_UNEXPECTED),
+            _ => Err(err),
+        }
+    }
+}
+
+/// The kind of an error created from an SGX status: common statuses map to
+/// the matching [`ErrorKind`], the others to [`ErrorKind::SgxError`].
+fn decode_sgx_error_kind(status: sgx_status_t) -> ErrorKind {
+    match status {
+        sgx_status_t::SGX_ERROR_UNEXPECTED => ErrorKind::Other,
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER => ErrorKind::InvalidInput,
+        sgx_status_t::SGX_ERROR_OUT_OF_MEMORY | sgx_status_t::SGX_ERROR_OUT_OF_EPC => {
+            ErrorKind::OutOfMemory
+        }
+        sgx_status_t::SGX_ERROR_MAC_MISMATCH | sgx_status_t::SGX_ERROR_FILE_NOT_SGX_FILE => {
+            ErrorKind::InvalidData
+        }
+        sgx_status_t::SGX_ERROR_FEATURE_NOT_SUPPORTED
+        | sgx_status_t::SGX_ERROR_UNSUPPORTED_CONFIG => ErrorKind::Unsupported,
+        sgx_status_t::SGX_ERROR_BUSY | sgx_status_t::SGX_ERROR_DEVICE_BUSY => {
+            ErrorKind::ResourceBusy
+        }
+        sgx_status_t::SGX_ERROR_NO_PRIVILEGE
+        | sgx_status_t::SGX_ERROR_SERVICE_INVALID_PRIVILEGE => ErrorKind::PermissionDenied,
+        sgx_status_t::SGX_ERROR_SERVICE_TIMEOUT => ErrorKind::TimedOut,
+        _ => ErrorKind::SgxError,
+    }
+}
+
 impl Error {
     /// Creates a new I/O error from a known kind of error as well as an
     /// arbitrary error payload.
@@ -882,7 +931,7 @@ impl Error {
             ErrorData::Custom(c) => c.kind,
             ErrorData::Simple(kind) => kind,
             ErrorData::SimpleMessage(m) => m.kind,
-            ErrorData::SgxStatus(..) => ErrorKind::SgxError,
+            ErrorData::SgxStatus(status) => decode_sgx_error_kind(status),
         }
     }
 }
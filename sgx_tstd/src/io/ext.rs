@@ -0,0 +1,149 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Reading and writing integers in a fixed byte order.
+
+use crate::io::{Read, Result, Write};
+use crate::mem;
+
+macro_rules! read_int {
+    ($($(#[$attr:meta])* $name:ident -> $ty:ty, $from:ident;)*) => {$(
+        $(#[$attr])*
+        #[inline]
+        fn $name(&mut self) -> Result<$ty> {
+            let mut buf = [0_u8; mem::size_of::<$ty>()];
+            self.read_exact(&mut buf)?;
+            Ok(<$ty>::$from(buf))
+        }
+    )*};
+}
+
+macro_rules! write_int {
+    ($($(#[$attr:meta])* $name:ident($ty:ty), $to:ident;)*) => {$(
+        $(#[$attr])*
+        #[inline]
+        fn $name(&mut self, v: $ty) -> Result<()> {
+            self.write_all(&v.$to())
+        }
+    )*};
+}
+
+/// Extends [`Read`] with methods for reading integers.
+///
+/// The methods read exactly as many bytes as the integer is wide, with
+/// [`read_exact`]. They fail with [`ErrorKind::UnexpectedEof`] if the reader
+/// runs out of data first, in which case the bytes read so far are lost.
+///
+/// [`read_exact`]: Read::read_exact
+/// [`ErrorKind::UnexpectedEof`]: crate::io::ErrorKind::UnexpectedEof
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{Cursor, ReadExt};
+///
+/// let mut rdr = Cursor::new(vec![0x16, 0x03, 0x03, 0x00, 0x2a]);
+/// assert_eq!(rdr.read_u8().unwrap(), 0x16);
+/// assert_eq!(rdr.read_u16_be().unwrap(), 0x0303);
+/// assert_eq!(rdr.read_u16_be().unwrap(), 42);
+/// ```
+pub trait ReadExt: Read {
+    read_int! {
+        /// Reads an unsigned 8 bit integer.
+        read_u8 -> u8, from_be_bytes;
+        /// Reads a signed 8 bit integer.
+        read_i8 -> i8, from_be_bytes;
+        /// Reads an unsigned 16 bit integer in big-endian byte order.
+        read_u16_be -> u16, from_be_bytes;
+        /// Reads an unsigned 32 bit integer in big-endian byte order.
+        read_u32_be -> u32, from_be_bytes;
+        /// Reads an unsigned 64 bit integer in big-endian byte order.
+        read_u64_be -> u64, from_be_bytes;
+        /// Reads a signed 16 bit integer in big-endian byte order.
+        read_i16_be -> i16, from_be_bytes;
+        /// Reads a signed 32 bit integer in big-endian byte order.
+        read_i32_be -> i32, from_be_bytes;
+        /// Reads a signed 64 bit integer in big-endian byte order.
+        read_i64_be -> i64, from_be_bytes;
+        /// Reads an unsigned 16 bit integer in little-endian byte order.
+        read_u16_le -> u16, from_le_bytes;
+        /// Reads an unsigned 32 bit integer in little-endian byte order.
+        read_u32_le -> u32, from_le_bytes;
+        /// Reads an unsigned 64 bit integer in little-endian byte order.
+        read_u64_le -> u64, from_le_bytes;
+        /// Reads a signed 16 bit integer in little-endian byte order.
+        read_i16_le -> i16, from_le_bytes;
+        /// Reads a signed 32 bit integer in little-endian byte order.
+        read_i32_le -> i32, from_le_bytes;
+        /// Reads a signed 64 bit integer in little-endian byte order.
+        read_i64_le -> i64, from_le_bytes;
+    }
+}
+
+impl<R: Read + ?Sized> ReadExt for R {}
+
+/// Extends [`Write`] with methods for writing integers.
+///
+/// The methods write the integer with [`write_all`].
+///
+/// [`write_all`]: Write::write_all
+///
+/// # Examples
+///
+/// ```
+/// use std::io::WriteExt;
+///
+/// let mut wtr = Vec::new();
+/// wtr.write_u8(0x17).unwrap();
+/// wtr.write_u16_be(0x0303).unwrap();
+/// wtr.write_u16_be(42).unwrap();
+/// assert_eq!(wtr, [0x17, 0x03, 0x03, 0x00, 0x2a]);
+/// ```
+pub trait WriteExt: Write {
+    write_int! {
+        /// Writes an unsigned 8 bit integer.
+        write_u8(u8), to_be_bytes;
+        /// Writes a signed 8 bit integer.
+        write_i8(i8), to_be_bytes;
+        /// Writes an unsigned 16 bit integer in big-endian byte order.
+        write_u16_be(u16), to_be_bytes;
+        /// Writes an unsigned 32 bit integer in big-endian byte order.
+        write_u32_be(u32), to_be_bytes;
+        /// Writes an unsigned 64 bit integer in big-endian byte order.
+        write_u64_be(u64), to_be_bytes;
+        /// Writes a signed 16 bit integer in big-endian byte order.
+        write_i16_be(i16), to_be_bytes;
+        /// Writes a signed 32 bit integer in big-endian byte order.
+        write_i32_be(i32), to_be_bytes;
+        /// Writes a signed 64 bit integer in big-endian byte order.
+        write_i64_be(i64), to_be_bytes;
+        /// Writes an unsigned 16 bit integer in little-endian byte order.
+        write_u16_le(u16), to_le_bytes;
+        /// Writes an unsigned 32 bit integer in little-endian byte order.
+        write_u32_le(u32), to_le_bytes;
+        /// Writes an unsigned 64 bit integer in little-endian byte order.
+        write_u64_le(u64), to_le_bytes;
+        /// Writes a signed 16 bit integer in little-endian byte order.
+        write_i16_le(i16), to_le_bytes;
+        /// Writes a signed 32 bit integer in little-endian byte order.
+        write_i32_le(i32), to_le_bytes;
+        /// Writes a signed 64 bit integer in little-endian byte order.
+        write_i64_le(i64), to_le_bytes;
+    }
+}
+
+impl<W: Write + ?Sized> WriteExt for W {}
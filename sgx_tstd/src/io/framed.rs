@@ -0,0 +1,292 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Byte streams made of frames encoded and decoded by the caller.
+//!
+//! These adapters only buffer and move bytes; the frame format comes from
+//! the closures they are given. They are meant to drive codecs that work
+//! on whole frames, such as the chunked stream encryption of
+//! `sgx_tcrypto::aead`.
+
+use crate::cmp;
+use crate::fmt;
+use crate::io::{self, Read, Write};
+use crate::vec::Vec;
+
+/// Wraps a writer and writes everything written to it as frames of up to
+/// `frame_size` bytes of data, each turned into bytes by `encode`.
+///
+/// Data is buffered until `frame_size` bytes are available, then
+/// `encode(data, false)` is called and its output written to the
+/// underlying writer. Calling [`flush`] encodes the buffered data as a
+/// shorter frame. The stream must be terminated with [`finish`], which
+/// encodes the rest of the data, possibly none, with `last` set; dropping
+/// the writer does the same but ignores any error.
+///
+/// [`flush`]: Write::flush
+/// [`finish`]: FramedWriter::finish
+///
+/// # Examples
+///
+/// Frames of `len: u8 | last: u8 | data`:
+///
+/// ```
+/// use std::io::{FramedReader, FramedWriter, Read, Write};
+///
+/// let mut writer = FramedWriter::new(Vec::new(), 255, |data: &[u8], last| {
+///     let mut frame = vec![data.len() as u8, last as u8];
+///     frame.extend_from_slice(data);
+///     Ok(frame)
+/// });
+/// writer.write_all(b"hello enclave").unwrap();
+/// let framed = writer.finish().unwrap();
+///
+/// let mut done = false;
+/// let mut reader = FramedReader::new(&framed[..], |inner: &mut &[u8]| {
+///     if done {
+///         return Ok(None);
+///     }
+///     let mut header = [0_u8; 2];
+///     inner.read_exact(&mut header)?;
+///     let mut data = vec![0_u8; header[0] as usize];
+///     inner.read_exact(&mut data)?;
+///     done = header[1] == 1;
+///     Ok(Some(data))
+/// });
+/// let mut message = String::new();
+/// reader.read_to_string(&mut message).unwrap();
+/// assert_eq!(message, "hello enclave");
+/// ```
+pub struct FramedWriter<W, F>
+where
+    W: Write,
+    F: FnMut(&[u8], bool) -> io::Result<Vec<u8>>,
+{
+    inner: Option<W>,
+    encode: F,
+    buf: Vec<u8>,
+    frame_size: usize,
+    panicked: bool,
+}
+
+impl<W, F> FramedWriter<W, F>
+where
+    W: Write,
+    F: FnMut(&[u8], bool) -> io::Result<Vec<u8>>,
+{
+    /// Creates a new `FramedWriter` which encodes frames of up to
+    /// `frame_size` bytes of data with `encode`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame_size` is zero.
+    pub fn new(inner: W, frame_size: usize, encode: F) -> FramedWriter<W, F> {
+        assert!(frame_size > 0, "invalid frame size");
+        FramedWriter {
+            inner: Some(inner),
+            encode,
+            buf: Vec::with_capacity(frame_size),
+            frame_size,
+            panicked: false,
+        }
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().unwrap()
+    }
+
+    /// Gets a mutable reference to the underlying writer.
+    ///
+    /// Writing directly to the underlying writer corrupts the stream.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.as_mut().unwrap()
+    }
+
+    /// Encodes the buffered data as the final frame, flushes the underlying
+    /// writer and returns it.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_frame(true)?;
+        self.get_mut().flush()?;
+        Ok(self.inner.take().unwrap())
+    }
+
+    fn write_frame(&mut self, last: bool) -> io::Result<()> {
+        let frame = (self.encode)(&self.buf, last)?;
+
+        self.panicked = true;
+        let r = self.inner.as_mut().unwrap().write_all(&frame);
+        self.panicked = false;
+        r?;
+
+        self.buf.clear();
+        Ok(())
+    }
+}
+
+impl<W, F> Write for FramedWriter<W, F>
+where
+    W: Write,
+    F: FnMut(&[u8], bool) -> io::Result<Vec<u8>>,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.buf.len() == self.frame_size {
+            self.write_frame(false)?;
+        }
+        let n = cmp::min(buf.len(), self.frame_size - self.buf.len());
+        self.buf.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            self.write_frame(false)?;
+        }
+        self.get_mut().flush()
+    }
+}
+
+impl<W, F> Drop for FramedWriter<W, F>
+where
+    W: Write,
+    F: FnMut(&[u8], bool) -> io::Result<Vec<u8>>,
+{
+    fn drop(&mut self) {
+        if self.inner.is_some() && !self.panicked {
+            // dtors should not panic, so we ignore a failed write
+            let _r = self.write_frame(true);
+        }
+    }
+}
+
+impl<W, F> fmt::Debug for FramedWriter<W, F>
+where
+    W: Write + fmt::Debug,
+    F: FnMut(&[u8], bool) -> io::Result<Vec<u8>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FramedWriter")
+            .field("inner", &self.inner)
+            .field("frame_size", &self.frame_size)
+            .field("buffered", &self.buf.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Wraps a reader of a stream of frames and yields the data decoded from
+/// them by `decode`.
+///
+/// Whenever the data of the previous frame has been read, `decode` is
+/// called with the underlying reader to read and decode the next frame. It
+/// returns `Ok(None)` at the end of the stream, which it has to recognize
+/// from the frames themselves: a stream that ends before its final frame
+/// should be reported as [`ErrorKind::UnexpectedEof`], which is what
+/// [`read_exact`] on the underlying reader does. Errors from `decode` are
+/// returned from `read`; the stream should not be read any further after
+/// one.
+///
+/// See [`FramedWriter`] for an example.
+///
+/// [`ErrorKind::UnexpectedEof`]: io::ErrorKind::UnexpectedEof
+/// [`read_exact`]: Read::read_exact
+pub struct FramedReader<R, F>
+where
+    R: Read,
+    F: FnMut(&mut R) -> io::Result<Option<Vec<u8>>>,
+{
+    inner: R,
+    decode: F,
+    buf: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R, F> FramedReader<R, F>
+where
+    R: Read,
+    F: FnMut(&mut R) -> io::Result<Option<Vec<u8>>>,
+{
+    /// Creates a new `FramedReader` which decodes frames with `decode`.
+    pub fn new(inner: R, decode: F) -> FramedReader<R, F> {
+        FramedReader {
+            inner,
+            decode,
+            buf: Vec::new(),
+            pos: 0,
+            done: false,
+        }
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Unwraps this `FramedReader`, returning the underlying reader.
+    ///
+    /// Decoded data which has not been read yet is lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn read_frame(&mut self) -> io::Result<()> {
+        self.pos = 0;
+        self.buf.clear();
+        match (self.decode)(&mut self.inner)? {
+            Some(data) => self.buf = data,
+            None => self.done = true,
+        }
+        Ok(())
+    }
+}
+
+impl<R, F> Read for FramedReader<R, F>
+where
+    R: Read,
+    F: FnMut(&mut R) -> io::Result<Option<Vec<u8>>>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            if self.done || buf.is_empty() {
+                return Ok(0);
+            }
+            self.read_frame()?;
+        }
+        let n = cmp::min(buf.len(), self.buf.len() - self.pos);
+        buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl<R, F> fmt::Debug for FramedReader<R, F>
+where
+    R: Read + fmt::Debug,
+    F: FnMut(&mut R) -> io::Result<Option<Vec<u8>>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FramedReader")
+            .field("inner", &self.inner)
+            .field("buffered", &(self.buf.len() - self.pos))
+            .finish_non_exhaustive()
+    }
+}
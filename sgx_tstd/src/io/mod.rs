@@ -284,9 +284,13 @@ pub use self::stdio::{_eprint, _print};
 pub use self::stdio::{stderr, stdin, stdout, Stderr, StderrLock, Stdin, StdinLock, Stdout, StdoutLock};
 pub use self::{
     buffered::{BufReader, BufWriter, IntoInnerError, LineWriter},
+    chunked::{ChunkedReader, ChunkedWriter},
     copy::copy,
     cursor::Cursor,
     error::{Error, ErrorKind, Result},
+    ext::{ReadExt, WriteExt},
+    framed::{FramedReader, FramedWriter},
+    tee::{TeeReader, TeeWriter},
     util::{empty, repeat, sink, Empty, Repeat, Sink},
 };
 
@@ -294,14 +298,18 @@ pub use self::readbuf::{BorrowedBuf, BorrowedCursor};
 pub(crate) use error::const_io_error;
 
 mod buffered;
+mod chunked;
 pub(crate) mod copy;
 mod cursor;
 mod error;
+mod ext;
+mod framed;
 mod impls;
 pub mod prelude;
 mod readbuf;
 #[cfg(feature = "stdio")]
 mod stdio;
+mod tee;
 mod util;
 
 pub trait IsTerminal: crate::sealed::Sealed {
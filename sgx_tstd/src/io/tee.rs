@@ -0,0 +1,211 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+//! Readers and writers that mirror a stream to a second sink.
+
+use crate::fmt;
+use crate::io::{self, Read, Write};
+
+/// Wraps a reader and copies every byte read from it to a writer.
+///
+/// This is meant for audit logging: everything the enclave consumes from
+/// `R` also ends up in `W`, for instance an `SgxFile` that can be replayed
+/// later.
+///
+/// Errors from the reader are returned as usual. The tee writer is
+/// secondary: if writing to it fails, the error is logged to stderr and
+/// kept for [`tee_error`], the read still succeeds, and nothing more is
+/// written to the tee, so the copy is always a prefix of the data read.
+///
+/// [`tee_error`]: TeeReader::tee_error
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{Read, TeeReader};
+///
+/// let mut log = Vec::new();
+/// let mut reader = TeeReader::new(&b"hello enclave"[..], &mut log);
+/// let mut message = String::new();
+/// reader.read_to_string(&mut message).unwrap();
+/// drop(reader);
+/// assert_eq!(log, b"hello enclave");
+/// ```
+pub struct TeeReader<R: Read, W: Write> {
+    reader: R,
+    tee: W,
+    tee_error: Option<io::Error>,
+}
+
+impl<R: Read, W: Write> TeeReader<R, W> {
+    /// Creates a new `TeeReader` which copies everything read from `reader`
+    /// to `tee`.
+    pub fn new(reader: R, tee: W) -> TeeReader<R, W> {
+        TeeReader { reader, tee, tee_error: None }
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    ///
+    /// Data read directly from the underlying reader is not copied.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Gets a reference to the tee writer.
+    pub fn tee_ref(&self) -> &W {
+        &self.tee
+    }
+
+    /// Returns the error that stopped the copy to the tee writer, if any.
+    pub fn tee_error(&self) -> Option<&io::Error> {
+        self.tee_error.as_ref()
+    }
+
+    /// Unwraps this `TeeReader`, returning the reader and the tee writer.
+    pub fn into_inner(self) -> (R, W) {
+        (self.reader, self.tee)
+    }
+}
+
+impl<R: Read, W: Write> Read for TeeReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        if n > 0 && self.tee_error.is_none() {
+            if let Err(e) = self.tee.write_all(&buf[..n]) {
+                record_secondary_error(&mut self.tee_error, e, "TeeReader");
+            }
+        }
+        Ok(n)
+    }
+}
+
+impl<R: Read + fmt::Debug, W: Write + fmt::Debug> fmt::Debug for TeeReader<R, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TeeReader")
+            .field("reader", &self.reader)
+            .field("tee", &self.tee)
+            .field("tee_error", &self.tee_error)
+            .finish()
+    }
+}
+
+/// A writer that mirrors everything written to a primary writer into a
+/// secondary one.
+///
+/// Only the primary writer decides the outcome of a write: its errors are
+/// returned, and exactly the bytes it accepted are passed on to the
+/// secondary writer. If the secondary writer fails, the error is logged to
+/// stderr and kept for [`secondary_error`], and the secondary writer is not
+/// used again, so its copy is always a prefix of the primary's.
+///
+/// [`secondary_error`]: TeeWriter::secondary_error
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{TeeWriter, Write};
+///
+/// let mut writer = TeeWriter::new(Vec::new(), Vec::new());
+/// writer.write_all(b"hello enclave").unwrap();
+/// let (primary, secondary) = writer.into_inner();
+/// assert_eq!(primary, secondary);
+/// ```
+pub struct TeeWriter<W1: Write, W2: Write> {
+    primary: W1,
+    secondary: W2,
+    secondary_error: Option<io::Error>,
+}
+
+impl<W1: Write, W2: Write> TeeWriter<W1, W2> {
+    /// Creates a new `TeeWriter` which writes to `primary` and mirrors the
+    /// data to `secondary`.
+    pub fn new(primary: W1, secondary: W2) -> TeeWriter<W1, W2> {
+        TeeWriter { primary, secondary, secondary_error: None }
+    }
+
+    /// Gets a reference to the primary writer.
+    pub fn get_ref(&self) -> &W1 {
+        &self.primary
+    }
+
+    /// Gets a mutable reference to the primary writer.
+    ///
+    /// Data written directly to the primary writer is not mirrored.
+    pub fn get_mut(&mut self) -> &mut W1 {
+        &mut self.primary
+    }
+
+    /// Gets a reference to the secondary writer.
+    pub fn secondary_ref(&self) -> &W2 {
+        &self.secondary
+    }
+
+    /// Returns the error that stopped the mirroring to the secondary writer,
+    /// if any.
+    pub fn secondary_error(&self) -> Option<&io::Error> {
+        self.secondary_error.as_ref()
+    }
+
+    /// Unwraps this `TeeWriter`, returning the primary and secondary writers.
+    pub fn into_inner(self) -> (W1, W2) {
+        (self.primary, self.secondary)
+    }
+}
+
+impl<W1: Write, W2: Write> Write for TeeWriter<W1, W2> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.primary.write(buf)?;
+        if n > 0 && self.secondary_error.is_none() {
+            if let Err(e) = self.secondary.write_all(&buf[..n]) {
+                record_secondary_error(&mut self.secondary_error, e, "TeeWriter");
+            }
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.primary.flush()?;
+        if self.secondary_error.is_none() {
+            if let Err(e) = self.secondary.flush() {
+                record_secondary_error(&mut self.secondary_error, e, "TeeWriter");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W1: Write + fmt::Debug, W2: Write + fmt::Debug> fmt::Debug for TeeWriter<W1, W2> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TeeWriter")
+            .field("primary", &self.primary)
+            .field("secondary", &self.secondary)
+            .field("secondary_error", &self.secondary_error)
+            .finish()
+    }
+}
+
+fn record_secondary_error(slot: &mut Option<io::Error>, err: io::Error, name: &str) {
+    #[cfg(feature = "stdio")]
+    io::attempt_print_to_stderr(format_args!("{}: copy stopped: {}\n", name, err));
+    #[cfg(not(feature = "stdio"))]
+    let _ = name;
+    *slot = Some(err);
+}
@@ -182,7 +182,8 @@ pub use sgx_trts::{
     global_ctors_object,
     global_dtors_object,
     is_x86_feature_detected,
-    is_cpu_feature_supported
+    is_cpu_feature_supported,
+    sgx_check_stack
 };
 
 extern crate sgx_tprotected_fs;
@@ -203,12 +204,10 @@ pub mod prelude;
 // Public module declarations and re-exports
 pub use alloc_crate::borrow;
 pub use alloc_crate::boxed;
-pub use alloc_crate::fmt;
 pub use alloc_crate::format;
 pub use alloc_crate::rc;
 pub use alloc_crate::slice;
 pub use alloc_crate::str;
-pub use alloc_crate::string;
 pub use alloc_crate::vec;
 pub use core::any;
 pub use core::array;
@@ -256,15 +255,19 @@ pub mod collections;
 pub mod env;
 pub mod error;
 pub mod ffi;
+pub mod fmt;
 pub mod sgxfs;
 #[cfg(feature = "untrusted_fs")]
 pub mod fs;
 pub mod io;
+#[cfg(feature = "log")]
+pub mod log;
 pub mod net;
 pub mod num;
 pub mod os;
 pub mod panic;
 pub mod path;
+pub mod string;
 pub mod sync;
 pub mod time;
 pub mod enclave;
@@ -0,0 +1,270 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+//! Structured logging to the untrusted runtime.
+//!
+//! Records are created with the [`enclave_log!`] macro, which captures the
+//! module path, file and line of the call site, and are passed to the
+//! installed [`Logger`]. The default logger, [`OcallLogger`], sends every
+//! record to the host with `u_log_ocall` (see `sgx_log.edl`), where
+//! `sgx_urts` writes it as a line of JSON.
+//!
+//! A production enclave (an enclave without the `DEBUG` attribute) never
+//! emits records more verbose than [`PRODUCTION_MAX_LEVEL`], whatever the
+//! maximum level set with [`set_max_level`]. Keep secrets out of `Warn`
+//! and `Error` records.
+//!
+//! [`enclave_log!`]: crate::enclave_log
+
+use crate::fmt;
+use core::cmp;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use sgx_types::*;
+
+/// The maximum level of a production enclave.
+pub const PRODUCTION_MAX_LEVEL: Level = Level::Warn;
+
+// the largest message passed to the host, the rest is dropped
+const MAX_MESSAGE_SIZE: usize = 0x4000;
+
+/// The verbosity of a log record, with the values of the `log` crate.
+#[repr(usize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    fn from_usize(n: usize) -> Option<Level> {
+        match n {
+            1 => Some(Level::Error),
+            2 => Some(Level::Warn),
+            3 => Some(Level::Info),
+            4 => Some(Level::Debug),
+            5 => Some(Level::Trace),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the level, as printed by the `log` crate.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// A log record and the location it comes from.
+#[derive(Clone, Debug)]
+pub struct Record<'a> {
+    level: Level,
+    module_path: &'a str,
+    file: &'a str,
+    line: u32,
+    args: fmt::Arguments<'a>,
+}
+
+impl<'a> Record<'a> {
+    pub fn new(
+        level: Level,
+        module_path: &'a str,
+        file: &'a str,
+        line: u32,
+        args: fmt::Arguments<'a>,
+    ) -> Record<'a> {
+        Record { level, module_path, file, line, args }
+    }
+
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    pub fn module_path(&self) -> &'a str {
+        self.module_path
+    }
+
+    pub fn file(&self) -> &'a str {
+        self.file
+    }
+
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    pub fn args(&self) -> &fmt::Arguments<'a> {
+        &self.args
+    }
+}
+
+/// A sink for log records.
+pub trait Logger: Sync + Send {
+    /// Logs the record. Records above the maximum level are not passed on.
+    fn log(&self, record: &Record<'_>);
+}
+
+/// The default logger: sends the records to the host.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OcallLogger;
+
+impl Logger for OcallLogger {
+    fn log(&self, record: &Record<'_>) {
+        let message = fmt::format(*record.args());
+        let mut len = cmp::min(message.len(), MAX_MESSAGE_SIZE);
+        while !message.is_char_boundary(len) {
+            len -= 1;
+        }
+        let module = record.module_path();
+        let file = record.file();
+        unsafe {
+            u_log_ocall(
+                record.level() as u32,
+                module.as_ptr(),
+                module.len(),
+                file.as_ptr(),
+                file.len(),
+                record.line(),
+                message.as_ptr(),
+                len,
+            );
+        }
+    }
+}
+
+/// The error returned by [`set_logger`] if a logger is already installed.
+#[derive(Clone, Copy, Debug)]
+pub struct SetLoggerError(());
+
+impl fmt::Display for SetLoggerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a logger is already installed")
+    }
+}
+
+impl crate::error::Error for SetLoggerError {}
+
+const UNINITIALIZED: usize = 0;
+const INITIALIZING: usize = 1;
+const INITIALIZED: usize = 2;
+
+const DEBUG_UNKNOWN: usize = 0;
+const DEBUG_ENCLAVE: usize = 1;
+const PRODUCTION_ENCLAVE: usize = 2;
+
+static mut LOGGER: &dyn Logger = &OcallLogger;
+static STATE: AtomicUsize = AtomicUsize::new(UNINITIALIZED);
+static MAX_LEVEL: AtomicUsize = AtomicUsize::new(Level::Info as usize);
+static ENCLAVE_MODE: AtomicUsize = AtomicUsize::new(DEBUG_UNKNOWN);
+
+/// Installs the logger used instead of [`OcallLogger`]. It can be done once.
+pub fn set_logger(logger: &'static dyn Logger) -> Result<(), SetLoggerError> {
+    match STATE.compare_exchange(
+        UNINITIALIZED,
+        INITIALIZING,
+        Ordering::SeqCst,
+        Ordering::SeqCst,
+    ) {
+        Ok(_) => {
+            unsafe { LOGGER = logger };
+            STATE.store(INITIALIZED, Ordering::SeqCst);
+            Ok(())
+        }
+        Err(_) => Err(SetLoggerError(())),
+    }
+}
+
+/// Returns the installed logger.
+pub fn logger() -> &'static dyn Logger {
+    if STATE.load(Ordering::SeqCst) == INITIALIZED {
+        unsafe { LOGGER }
+    } else {
+        &OcallLogger
+    }
+}
+
+/// Sets the most verbose level that is logged, `Info` by default.
+///
+/// A production enclave is still limited to [`PRODUCTION_MAX_LEVEL`].
+pub fn set_max_level(level: Level) {
+    MAX_LEVEL.store(level as usize, Ordering::Relaxed);
+}
+
+/// Returns the most verbose level that is logged.
+pub fn max_level() -> Level {
+    let level = Level::from_usize(MAX_LEVEL.load(Ordering::Relaxed)).unwrap_or(Level::Info);
+    if is_debug_enclave() {
+        level
+    } else {
+        cmp::min(level, PRODUCTION_MAX_LEVEL)
+    }
+}
+
+/// Returns `true` if a record of this level would be logged.
+pub fn enabled(level: Level) -> bool {
+    level <= max_level()
+}
+
+fn is_debug_enclave() -> bool {
+    match ENCLAVE_MODE.load(Ordering::Relaxed) {
+        DEBUG_ENCLAVE => true,
+        PRODUCTION_ENCLAVE => false,
+        _ => {
+            let report = unsafe { sgx_self_report() };
+            let debug = !report.is_null()
+                && unsafe { (*report).body.attributes.flags } & SGX_FLAGS_DEBUG != 0;
+            let mode = if debug { DEBUG_ENCLAVE } else { PRODUCTION_ENCLAVE };
+            ENCLAVE_MODE.store(mode, Ordering::Relaxed);
+            debug
+        }
+    }
+}
+
+#[doc(hidden)]
+pub fn __private_log(
+    level: Level,
+    args: fmt::Arguments<'_>,
+    &(module_path, file, line): &(&'static str, &'static str, u32),
+) {
+    if enabled(level) {
+        logger().log(&Record::new(level, module_path, file, line, args));
+    }
+}
+
+extern "C" {
+    pub fn u_log_ocall(
+        level: u32,
+        module: *const u8,
+        module_len: usize,
+        file: *const u8,
+        file_len: usize,
+        line: u32,
+        message: *const u8,
+        message_len: usize,
+    ) -> sgx_status_t;
+}
@@ -162,6 +162,39 @@ macro_rules! print { ($($arg:tt)*) => ({}) }
 #[cfg_attr(not(test), rustc_diagnostic_item = "println_macro")]
 macro_rules! println { ($($arg:tt)*) => ({}) }
 
+/// Logs a record through [`log::logger`] if its level is enabled.
+///
+/// The record carries the module path, file and line of the call site. Without
+/// the `log` feature the macro expands to nothing.
+///
+/// [`log::logger`]: crate::log::logger
+///
+/// # Examples
+///
+/// ```
+/// use std::log::Level;
+///
+/// enclave_log!(Level::Info, "session {} established", 1);
+/// ```
+#[cfg(feature = "log")]
+#[macro_export]
+macro_rules! enclave_log {
+    ($lvl:expr, $($arg:tt)+) => {{
+        let lvl = $lvl;
+        if $crate::log::enabled(lvl) {
+            $crate::log::__private_log(
+                lvl,
+                $crate::format_args!($($arg)+),
+                &($crate::module_path!(), $crate::file!(), $crate::line!()),
+            );
+        }
+    }};
+}
+
+#[cfg(not(feature = "log"))]
+#[macro_export]
+macro_rules! enclave_log { ($lvl:expr, $($arg:tt)+) => ({}) }
+
 /// Prints to the standard error.
 ///
 /// Equivalent to the [`print!`] macro, except that output goes to
@@ -0,0 +1,273 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+use crate::error::Error;
+use crate::fmt;
+use crate::io;
+use crate::net::{IpAddr, SocketAddr};
+#[cfg(feature = "net")]
+use crate::net::{TcpStream, ToSocketAddrs};
+use crate::string::{String, ToString};
+use crate::vec::Vec;
+
+/// An allowlist of the peers an enclave may connect to.
+///
+/// Host names are resolved, and sockets are often connected, by the
+/// untrusted host, which can therefore send the enclave to a server of its
+/// choosing. An `IpFilter` is configured by the enclave itself, typically
+/// once in a [`OnceLock`], with `(hostname pattern, address range, port)`
+/// rules, and [`check`] is called with the address of every connection
+/// before it is used.
+///
+/// A host name pattern is either a name, matched case-insensitively, a
+/// wildcard `*.example.com` that matches any subdomain of `example.com`
+/// but not `example.com` itself, or `*` for any name. An address range is
+/// an address or a CIDR block such as `10.0.0.0/8` or `2001:db8::/32`;
+/// IPv4-mapped IPv6 addresses are matched as IPv4 addresses.
+///
+/// The filter cannot tell whether the host answered a DNS query honestly,
+/// only that the address it returned is one the enclave is willing to talk
+/// to. It complements, and does not replace, authenticating the peer, e.g.
+/// with TLS.
+///
+/// [`OnceLock`]: crate::sync::OnceLock
+/// [`check`]: IpFilter::check
+///
+/// # Examples
+///
+/// ```
+/// use std::net::{IpFilter, SocketAddr};
+/// use std::sync::OnceLock;
+///
+/// static FILTER: OnceLock<IpFilter> = OnceLock::new();
+///
+/// let filter = FILTER.get_or_init(|| {
+///     IpFilter::new()
+///         .allow("*.example.com", "203.0.113.0/24", Some(443))
+///         .unwrap()
+/// });
+/// let addr: SocketAddr = "203.0.113.7:443".parse().unwrap();
+/// assert!(filter.check("api.example.com", &addr).is_ok());
+/// let addr: SocketAddr = "198.51.100.7:443".parse().unwrap();
+/// assert!(filter.check("api.example.com", &addr).is_err());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct IpFilter {
+    rules: Vec<Rule>,
+}
+
+#[derive(Clone, Debug)]
+struct Rule {
+    host: HostPattern,
+    network: IpAddr,
+    prefix_len: u8,
+    port: Option<u16>,
+}
+
+#[derive(Clone, Debug)]
+enum HostPattern {
+    Any,
+    // lower-cased, including the leading dot
+    Suffix(String),
+    // lower-cased
+    Exact(String),
+}
+
+impl IpFilter {
+    /// Creates a filter that rejects everything.
+    pub fn new() -> IpFilter {
+        IpFilter { rules: Vec::new() }
+    }
+
+    /// Adds a rule allowing connections for host names matching
+    /// `hostname_pattern` to addresses in `ip_range` on `port`, or on any
+    /// port if `port` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if the
+    /// pattern is empty or `ip_range` is not an address or CIDR block.
+    pub fn allow(mut self, hostname_pattern: &str, ip_range: &str, port: Option<u16>) -> io::Result<IpFilter> {
+        let host = HostPattern::parse(hostname_pattern)?;
+        let (network, prefix_len) = parse_range(ip_range)?;
+        self.rules.push(Rule { host, network, prefix_len, port });
+        Ok(self)
+    }
+
+    /// Checks that a connection to `addr` for `hostname` is allowed by at
+    /// least one rule.
+    pub fn check(&self, hostname: &str, addr: &SocketAddr) -> Result<(), PolicyViolation> {
+        if self.rules.iter().any(|rule| rule.matches(hostname, addr)) {
+            Ok(())
+        } else {
+            Err(PolicyViolation { hostname: hostname.to_string(), addr: *addr })
+        }
+    }
+
+    /// Resolves `hostname` and connects to the first resolved address that
+    /// passes [`check`].
+    ///
+    /// [`check`]: IpFilter::check
+    ///
+    /// # Errors
+    ///
+    /// Fails with the error of the last connection attempt, or with an
+    /// error of kind [`io::ErrorKind::PermissionDenied`] wrapping a
+    /// [`PolicyViolation`] if no resolved address is allowed.
+    #[cfg(feature = "net")]
+    pub fn connect(&self, hostname: &str, port: u16) -> io::Result<TcpStream> {
+        let mut last_err = None;
+        for addr in (hostname, port).to_socket_addrs()? {
+            if let Err(violation) = self.check(hostname, &addr) {
+                if last_err.is_none() {
+                    last_err = Some(violation.into());
+                }
+                continue;
+            }
+            match TcpStream::connect(addr) {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            io::const_io_error!(io::ErrorKind::InvalidInput, "could not resolve to any addresses")
+        }))
+    }
+}
+
+impl Rule {
+    fn matches(&self, hostname: &str, addr: &SocketAddr) -> bool {
+        self.port.map_or(true, |port| port == addr.port())
+            && self.host.matches(hostname)
+            && in_range(self.network, self.prefix_len, addr.ip())
+    }
+}
+
+impl HostPattern {
+    fn parse(pattern: &str) -> io::Result<HostPattern> {
+        let pattern = pattern.to_ascii_lowercase();
+        if pattern == "*" {
+            return Ok(HostPattern::Any);
+        }
+        // A fully qualified name may end with a dot.
+        let name = pattern.strip_suffix('.').unwrap_or(&pattern);
+        let (wildcard, domain) = match name.strip_prefix("*.") {
+            Some(domain) => (true, domain),
+            None => (false, name),
+        };
+        if domain.is_empty() || domain.starts_with('.') || domain.contains('*') {
+            Err(invalid_pattern())
+        } else if wildcard {
+            Ok(HostPattern::Suffix(name[1..].to_string()))
+        } else {
+            Ok(HostPattern::Exact(domain.to_string()))
+        }
+    }
+
+    fn matches(&self, hostname: &str) -> bool {
+        let hostname = hostname.trim_end_matches('.');
+        match *self {
+            HostPattern::Any => true,
+            HostPattern::Exact(ref name) => hostname.eq_ignore_ascii_case(name),
+            HostPattern::Suffix(ref suffix) => {
+                hostname.len() > suffix.len()
+                    && hostname.is_char_boundary(hostname.len() - suffix.len())
+                    && hostname[hostname.len() - suffix.len()..].eq_ignore_ascii_case(suffix)
+            }
+        }
+    }
+}
+
+fn invalid_pattern() -> io::Error {
+    io::const_io_error!(io::ErrorKind::InvalidInput, "invalid host name pattern")
+}
+
+fn parse_range(range: &str) -> io::Result<(IpAddr, u8)> {
+    let invalid = || io::const_io_error!(io::ErrorKind::InvalidInput, "invalid address range");
+    let (addr, prefix_len) = match range.split_once('/') {
+        Some((addr, len)) => (addr, Some(len.parse::<u8>().map_err(|_| invalid())?)),
+        None => (range, None),
+    };
+    let addr = canonical(addr.parse::<IpAddr>().map_err(|_| invalid())?);
+    let max_len = if addr.is_ipv4() { 32 } else { 128 };
+    let prefix_len = prefix_len.unwrap_or(max_len);
+    if prefix_len > max_len {
+        return Err(invalid());
+    }
+    Ok((addr, prefix_len))
+}
+
+fn canonical(addr: IpAddr) -> IpAddr {
+    match addr {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(addr, IpAddr::V4),
+        IpAddr::V4(_) => addr,
+    }
+}
+
+fn in_range(network: IpAddr, prefix_len: u8, addr: IpAddr) -> bool {
+    match (network, canonical(addr)) {
+        (IpAddr::V4(net), IpAddr::V4(addr)) => {
+            prefix_matches(&net.octets(), &addr.octets(), prefix_len)
+        }
+        (IpAddr::V6(net), IpAddr::V6(addr)) => {
+            prefix_matches(&net.octets(), &addr.octets(), prefix_len)
+        }
+        _ => false,
+    }
+}
+
+fn prefix_matches(network: &[u8], addr: &[u8], prefix_len: u8) -> bool {
+    let full = (prefix_len / 8) as usize;
+    let rest = prefix_len % 8;
+    if network[..full] != addr[..full] {
+        return false;
+    }
+    rest == 0 || (network[full] ^ addr[full]) >> (8 - rest) == 0
+}
+
+/// The error returned by [`IpFilter::check`] when no rule allows a
+/// connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolicyViolation {
+    hostname: String,
+    addr: SocketAddr,
+}
+
+impl PolicyViolation {
+    /// The host name the connection was made for.
+    pub fn hostname(&self) -> &str {
+        &self.hostname
+    }
+
+    /// The rejected peer address.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "connection to {} for {} is not allowed", self.addr, self.hostname)
+    }
+}
+
+impl Error for PolicyViolation {}
+
+impl From<PolicyViolation> for io::Error {
+    fn from(violation: PolicyViolation) -> io::Error {
+        io::Error::new(io::ErrorKind::PermissionDenied, violation)
+    }
+}
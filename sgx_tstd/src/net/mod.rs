@@ -30,6 +30,7 @@
 //!   and [`SocketAddrV6`] are respectively IPv4 and IPv6 socket addresses
 //! * [`ToSocketAddrs`] is a trait that used for generic address resolution when interacting
 //!   with networking objects like [`TcpListener`], [`TcpStream`] or [`UdpSocket`]
+//! * [`resolve_ipv4`] resolves a host name to a single, checked IPv4 address
 //! * Other types are return or parameter types for various methods in this module
 //!
 //! Rust disables inheritance of socket objects to child processes by default when possible.  For
@@ -39,18 +40,24 @@
 use crate::io::{self, ErrorKind};
 
 pub use self::ip_addr::{IpAddr, Ipv4Addr, Ipv6Addr, Ipv6MulticastScope};
+pub use self::ip_filter::{IpFilter, PolicyViolation};
 pub use self::parser::AddrParseError;
+#[cfg(feature = "net")]
+pub use self::resolve::resolve_ipv4;
 pub use self::socket_addr::{SocketAddr, SocketAddrV4, SocketAddrV6, ToSocketAddrs};
 #[cfg(feature = "net")]
 pub use self::tcp::IntoIncoming;
 #[cfg(feature = "net")]
-pub use self::tcp::{Incoming, TcpListener, TcpStream};
+pub use self::tcp::{socketpair, Incoming, TcpListener, TcpStream};
 #[cfg(feature = "net")]
 pub use self::udp::UdpSocket;
 
 mod display_buffer;
 mod ip_addr;
+mod ip_filter;
 mod parser;
+#[cfg(feature = "net")]
+mod resolve;
 mod socket_addr;
 #[cfg(feature = "net")]
 mod tcp;
@@ -0,0 +1,80 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use crate::ffi::CString;
+use crate::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4};
+use sgx_libc::ocall::dns_resolve;
+use sgx_types::{sgx_status_t, SgxResult};
+
+/// Resolves `hostname` to an IPv4 socket address on the untrusted host.
+///
+/// [`ToSocketAddrs`] copies the `addrinfo` list of `getaddrinfo` into the
+/// enclave. This function uses an OCALL of its own instead,
+/// `u_dns_resolve_ocall` of `sgx_net.edl`, through which the host returns
+/// exactly one address: the 16 bytes of an IPv4-mapped IPv6 address. The
+/// bytes are checked before they are used, and the result is never the
+/// unspecified, broadcast or a multicast address.
+///
+/// The host still chooses the answer. An enclave should pin the addresses
+/// it expects, for example by checking the result against a constant list
+/// of [`IpAddr`] or with an [`IpFilter`], and authenticate the peer, e.g.
+/// with TLS.
+///
+/// [`ToSocketAddrs`]: crate::net::ToSocketAddrs
+/// [`IpAddr`]: crate::net::IpAddr
+/// [`IpFilter`]: crate::net::IpFilter
+///
+/// # Errors
+///
+/// Returns `SGX_ERROR_INVALID_PARAMETER` if `hostname` contains a NUL byte,
+/// `SGX_ERROR_NETWORK_FAILURE` if the host could not resolve it and
+/// `SGX_ERROR_UNEXPECTED` if the OCALL failed or returned anything but the
+/// IPv4-mapped address of a host.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::net::{resolve_ipv4, IpAddr, Ipv4Addr};
+///
+/// const ALLOWED_ADDRESSES: &[IpAddr] = &[IpAddr::V4(Ipv4Addr::LOCALHOST)];
+///
+/// let addr = resolve_ipv4("localhost", 8443).unwrap();
+/// assert!(ALLOWED_ADDRESSES.contains(&addr.ip()));
+/// ```
+pub fn resolve_ipv4(hostname: &str, port: u16) -> SgxResult<SocketAddr> {
+    let hostname = CString::new(hostname).map_err(|_| sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+    let mut addr = [0_u8; 16];
+    match unsafe { dns_resolve(hostname.as_ptr(), port, &mut addr) } {
+        0 => {}
+        sgx_libc::EAI_SYSTEM if crate::sys::os::errno() == sgx_libc::ESGX => {
+            return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
+        }
+        _ => return Err(sgx_status_t::SGX_ERROR_NETWORK_FAILURE),
+    }
+    let ip = ipv4_from_mapped(addr).ok_or(sgx_status_t::SGX_ERROR_UNEXPECTED)?;
+    Ok(SocketAddr::V4(SocketAddrV4::new(ip, port)))
+}
+
+// Returns the IPv4 address of `::ffff:a.b.c.d`, if it is one of a host.
+fn ipv4_from_mapped(addr: [u8; 16]) -> Option<Ipv4Addr> {
+    let ip = Ipv6Addr::from(addr).to_ipv4_mapped()?;
+    if ip.is_unspecified() || ip.is_broadcast() || ip.is_multicast() {
+        None
+    } else {
+        Some(ip)
+    }
+}
@@ -641,6 +641,35 @@ impl TcpStream {
     }
 }
 
+/// Creates a pair of connected streams.
+///
+/// The streams are the two ends of a Unix domain stream socket created by
+/// `socketpair(AF_UNIX, SOCK_STREAM, 0)` outside the enclave. Data written
+/// to one end can be read from the other, which makes the pair a stand-in
+/// for a TCP connection when testing protocol code without binding a port.
+///
+/// As the streams are not TCP sockets, methods which deal with addresses
+/// or TCP options, such as [`TcpStream::peer_addr`] or
+/// [`TcpStream::set_nodelay`], return an error.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io::prelude::*;
+/// use std::net;
+///
+/// let (mut client, mut server) = net::socketpair().expect("socketpair failed");
+/// client.write_all(b"ping").unwrap();
+///
+/// let mut buf = [0; 4];
+/// server.read_exact(&mut buf).unwrap();
+/// assert_eq!(&buf, b"ping");
+/// ```
+pub fn socketpair() -> io::Result<(TcpStream, TcpStream)> {
+    let (a, b) = net_imp::TcpStream::new_pair()?;
+    Ok((TcpStream(a), TcpStream(b)))
+}
+
 // In addition to the `impl`s here, `TcpStream` also has `impl`s for
 // `AsFd`/`From<OwnedFd>`/`Into<OwnedFd>` and
 // `AsRawFd`/`IntoRawFd`/`FromRawFd`, on Unix and WASI, and
@@ -19,6 +19,7 @@
 
 pub mod linux;
 pub mod raw;
+pub mod sgx;
 pub mod unix;
 
 pub mod fd;
@@ -0,0 +1,110 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+use crate::fmt;
+use crate::sync::OnceLock;
+use sgx_types::{sgx_attributes_t, sgx_self_report, SGX_HASH_SIZE};
+
+/// The identity of the running enclave, as recorded in its own report.
+///
+/// Log messages and remote attestation payloads often need the enclave's
+/// `MRENCLAVE` and `MRSIGNER`. [`EnclaveMeasurement::current`] creates a
+/// report targeted at the enclave itself on first use and keeps the
+/// identity fields for the lifetime of the enclave.
+///
+/// # Examples
+///
+/// ```
+/// use std::os::sgx::EnclaveMeasurement;
+///
+/// let measurement = EnclaveMeasurement::current();
+/// println!("running as {:?}, svn {}", measurement, measurement.isv_svn());
+/// ```
+#[derive(Clone, Copy)]
+pub struct EnclaveMeasurement {
+    mrenclave: [u8; SGX_HASH_SIZE],
+    mrsigner: [u8; SGX_HASH_SIZE],
+    isv_prod_id: u16,
+    isv_svn: u16,
+    attributes: sgx_attributes_t,
+}
+
+static CURRENT: OnceLock<EnclaveMeasurement> = OnceLock::new();
+
+impl EnclaveMeasurement {
+    /// Returns the measurement of the running enclave.
+    ///
+    /// The first call creates a self report; later calls return the cached
+    /// result.
+    pub fn current() -> &'static EnclaveMeasurement {
+        CURRENT.get_or_init(|| {
+            let body = unsafe { &(*sgx_self_report()).body };
+            EnclaveMeasurement {
+                mrenclave: body.mr_enclave.m,
+                mrsigner: body.mr_signer.m,
+                isv_prod_id: body.isv_prod_id,
+                isv_svn: body.isv_svn,
+                attributes: body.attributes,
+            }
+        })
+    }
+
+    /// The hash of the enclave's initial contents, `MRENCLAVE`.
+    pub fn mrenclave(&self) -> [u8; SGX_HASH_SIZE] {
+        self.mrenclave
+    }
+
+    /// The hash of the public key that signed the enclave, `MRSIGNER`.
+    pub fn mrsigner(&self) -> [u8; SGX_HASH_SIZE] {
+        self.mrsigner
+    }
+
+    /// The product ID assigned by the signer.
+    pub fn isv_prod_id(&self) -> u16 {
+        self.isv_prod_id
+    }
+
+    /// The security version number assigned by the signer.
+    pub fn isv_svn(&self) -> u16 {
+        self.isv_svn
+    }
+
+    /// The enclave's attributes, such as whether it is in debug mode.
+    pub fn attributes(&self) -> sgx_attributes_t {
+        self.attributes
+    }
+}
+
+impl fmt::Debug for EnclaveMeasurement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Hex<'a>(&'a [u8]);
+
+        impl fmt::Debug for Hex<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+            }
+        }
+
+        f.debug_struct("EnclaveMeasurement")
+            .field("mrenclave", &Hex(&self.mrenclave))
+            .field("mrsigner", &Hex(&self.mrsigner))
+            .field("isv_prod_id", &self.isv_prod_id)
+            .field("isv_svn", &self.isv_svn)
+            .field("attributes.flags", &format_args!("{:#x}", self.attributes.flags))
+            .field("attributes.xfrm", &format_args!("{:#x}", self.attributes.xfrm))
+            .finish()
+    }
+}
@@ -243,8 +243,44 @@ where
     *hook = Hook::Custom(Box::new(move |info| hook_fn(&prev, info)));
 }
 
+fn payload_as_str<'a>(info: &'a PanicInfo<'_>) -> &'a str {
+    match info.payload().downcast_ref::<&'static str>() {
+        Some(s) => s,
+        None => match info.payload().downcast_ref::<String>() {
+            Some(s) => &s[..],
+            None => "Box<dyn Any>",
+        },
+    }
+}
+
+// Sends the panic to the host as an error record of the enclave log, so that
+// it is not lost when stdio is not available or not captured.
+#[cfg(feature = "log")]
+fn report_panic(info: &PanicInfo<'_>) {
+    use crate::log::{self, Level, Record};
+
+    if !log::enabled(Level::Error) {
+        return;
+    }
+    // The current implementation always returns `Some`.
+    let location = info.location().unwrap();
+    let msg = payload_as_str(info);
+    log::logger().log(&Record::new(
+        Level::Error,
+        "panic",
+        location.file(),
+        location.line(),
+        format_args!("panicked at '{msg}', {location}"),
+    ));
+}
+
 #[cfg(not(feature = "stdio"))]
-fn default_hook(_info: &PanicInfo<'_>) {}
+fn default_hook(info: &PanicInfo<'_>) {
+    #[cfg(feature = "log")]
+    report_panic(info);
+    #[cfg(not(feature = "log"))]
+    let _ = info;
+}
 
 #[cfg(feature = "stdio")]
 fn default_hook(info: &PanicInfo<'_>) {
@@ -260,13 +296,7 @@ fn default_hook(info: &PanicInfo<'_>) {
     // The current implementation always returns `Some`.
     let location = info.location().unwrap();
 
-    let msg = match info.payload().downcast_ref::<&'static str>() {
-        Some(s) => *s,
-        None => match info.payload().downcast_ref::<String>() {
-            Some(s) => &s[..],
-            None => "Box<dyn Any>",
-        },
-    };
+    let msg = payload_as_str(info);
     let thread = thread_info::current_thread();
     let name = thread.as_ref().and_then(|t| t.name()).unwrap_or("<unnamed>");
 
@@ -292,12 +322,19 @@ fn default_hook(info: &PanicInfo<'_>) {
                         );
                     }
                 }
+                // Not configured: debug builds still print a short backtrace.
+                None if cfg!(debug_assertions) => {
+                    drop(backtrace::print(err, crate::sys::backtrace::PrintFmt::Short))
+                }
                 // If backtraces aren't supported, do nothing.
                 None => {}
             }
         }
     };
 
+    #[cfg(feature = "log")]
+    report_panic(info);
+
     if let Some(local) = set_output_capture(None) {
         write(&mut *local.lock().unwrap_or_else(|e| e.into_inner()));
         set_output_capture(Some(local));
@@ -95,6 +95,72 @@ pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> io::Result
     SgxFile::create(path)?.write_all(contents.as_ref())
 }
 
+/// Append a slice to the contents of a file.
+///
+/// This function will create a file if it does not exist.
+///
+/// This is a convenience function for using OpenOptions::append and write_all
+/// with fewer imports.
+///
+pub fn append<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> io::Result<()> {
+    append_with(path, None, contents)
+}
+
+/// Read the entire contents of a file into a bytes vector, using `key` to
+/// decrypt it.
+///
+/// A `key` of `None` uses the automatic key derived from the enclave's
+/// signer, as [`read`] does.
+///
+pub fn read_with<P: AsRef<Path>>(path: P, key: Option<&sgx_key_128bit_t>) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    SgxFile::open_with(path, key, None)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Read the entire contents of a file into a string, using `key` to decrypt
+/// it.
+///
+/// A `key` of `None` uses the automatic key derived from the enclave's
+/// signer, as [`read_to_string`] does.
+///
+pub fn read_to_string_with<P: AsRef<Path>>(
+    path: P,
+    key: Option<&sgx_key_128bit_t>,
+) -> io::Result<String> {
+    let mut string = String::new();
+    SgxFile::open_with(path, key, None)?.read_to_string(&mut string)?;
+    Ok(string)
+}
+
+/// Write a slice as the entire contents of a file, using `key` to encrypt it.
+///
+/// A `key` of `None` uses the automatic key derived from the enclave's
+/// signer, as [`write`] does.
+///
+pub fn write_with<P: AsRef<Path>, C: AsRef<[u8]>>(
+    path: P,
+    key: Option<&sgx_key_128bit_t>,
+    contents: C,
+) -> io::Result<()> {
+    SgxFile::create_with(path, key, None)?.write_all(contents.as_ref())
+}
+
+/// Append a slice to the contents of a file, using `key` to encrypt it.
+///
+/// The file must have been written with the same key.
+///
+pub fn append_with<P: AsRef<Path>, C: AsRef<[u8]>>(
+    path: P,
+    key: Option<&sgx_key_128bit_t>,
+    contents: C,
+) -> io::Result<()> {
+    OpenOptions::new()
+        .append(true)
+        .open_with(path, key, None)?
+        .write_all(contents.as_ref())
+}
+
 impl SgxFile {
     /// Attempts to open a file in read-only mode.
     ///
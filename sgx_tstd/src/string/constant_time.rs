@@ -0,0 +1,91 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use crate::fmt;
+use crate::string::String;
+use sgx_trts::memeq::ConsttimeMemEq;
+
+/// A string holding a secret, such as a password or an authentication
+/// token, that is compared in constant time.
+///
+/// `==` compares the contents with [`ConsttimeMemEq`], so the time taken
+/// does not depend on where two strings of the same length differ. Strings
+/// of different lengths compare unequal straight away, so the length of the
+/// secret is not protected.
+///
+/// [`Display`] and [`Debug`] print `<redacted>` to keep the secret out of
+/// logs; use [`expose_secret`] to get at the contents on purpose.
+///
+/// [`Display`]: fmt::Display
+/// [`Debug`]: fmt::Debug
+/// [`expose_secret`]: ConstantTimeString::expose_secret
+///
+/// # Examples
+///
+/// ```
+/// use std::string::ConstantTimeString;
+///
+/// let token = ConstantTimeString::new(String::from("s3cr3t"));
+/// assert_eq!(token, ConstantTimeString::new(String::from("s3cr3t")));
+/// assert_eq!(format!("{}", token), "<redacted>");
+/// assert_eq!(token.expose_secret(), "s3cr3t");
+/// ```
+#[derive(Clone, Default)]
+pub struct ConstantTimeString(String);
+
+impl ConstantTimeString {
+    /// Wraps `s`.
+    pub fn new(s: String) -> ConstantTimeString {
+        ConstantTimeString(s)
+    }
+
+    /// Returns the secret.
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+
+    /// Unwraps the secret.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl PartialEq for ConstantTimeString {
+    fn eq(&self, other: &ConstantTimeString) -> bool {
+        self.0.as_bytes().consttime_memeq(other.0.as_bytes())
+    }
+}
+
+impl Eq for ConstantTimeString {}
+
+impl From<String> for ConstantTimeString {
+    fn from(s: String) -> ConstantTimeString {
+        ConstantTimeString(s)
+    }
+}
+
+impl fmt::Display for ConstantTimeString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+impl fmt::Debug for ConstantTimeString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
@@ -0,0 +1,165 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use crate::borrow::Borrow;
+use crate::fmt;
+use crate::ops::Deref;
+use crate::sync::Arc;
+
+/// A thread-safe reference-counted pointer to a value on the enclave heap.
+///
+/// `SgxArc` is an [`Arc`] with the operations enclave code needs to manage
+/// shared state that outlives a single ECALL: taking the value back once
+/// the last other reference is gone ([`try_unwrap`]), recognizing the same
+/// allocation behind two handles ([`ptr_eq`]), and mutating the value in
+/// place on a path where it is known to have a single user
+/// ([`get_mut_unchecked`]).
+///
+/// [`try_unwrap`]: SgxArc::try_unwrap
+/// [`ptr_eq`]: SgxArc::ptr_eq
+/// [`get_mut_unchecked`]: SgxArc::get_mut_unchecked
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::SgxArc;
+///
+/// let a = SgxArc::new(5);
+/// let b = SgxArc::clone(&a);
+/// assert!(SgxArc::ptr_eq(&a, &b));
+/// assert_eq!(SgxArc::strong_count(&a), 2);
+///
+/// let a = SgxArc::try_unwrap(a).unwrap_err();
+/// drop(b);
+/// assert_eq!(SgxArc::try_unwrap(a).unwrap(), 5);
+/// ```
+pub struct SgxArc<T: ?Sized> {
+    inner: Arc<T>,
+}
+
+impl<T> SgxArc<T> {
+    /// Moves `value` to the enclave heap.
+    pub fn new(value: T) -> SgxArc<T> {
+        SgxArc { inner: Arc::new(value) }
+    }
+
+    /// Returns the inner value if `this` is the only strong reference to
+    /// it, and `this` back otherwise.
+    ///
+    /// Weak references do not prevent the value from being returned.
+    pub fn try_unwrap(this: SgxArc<T>) -> Result<T, SgxArc<T>> {
+        Arc::try_unwrap(this.inner).map_err(|inner| SgxArc { inner })
+    }
+}
+
+impl<T: ?Sized> SgxArc<T> {
+    /// Returns a mutable reference to the value, without checking that
+    /// `this` is the only reference to it.
+    ///
+    /// Use [`Arc::get_mut`] through [`as_arc`] where the check is
+    /// affordable.
+    ///
+    /// [`as_arc`]: SgxArc::as_arc
+    ///
+    /// # Safety
+    ///
+    /// For as long as the returned reference is alive, no other `SgxArc`
+    /// or weak reference to the same allocation may be used to access the
+    /// value.
+    pub unsafe fn get_mut_unchecked(this: &mut SgxArc<T>) -> &mut T {
+        &mut *(Arc::as_ptr(&this.inner) as *mut T)
+    }
+
+    /// Gets the number of strong references to the value.
+    ///
+    /// Another thread may change the count at any time, so the result is
+    /// only exact while no other thread holds a reference.
+    pub fn strong_count(this: &SgxArc<T>) -> usize {
+        Arc::strong_count(&this.inner)
+    }
+
+    /// Returns `true` if the two `SgxArc`s point to the same allocation.
+    pub fn ptr_eq(this: &SgxArc<T>, other: &SgxArc<T>) -> bool {
+        Arc::ptr_eq(&this.inner, &other.inner)
+    }
+
+    /// Gets the underlying [`Arc`].
+    pub fn as_arc(this: &SgxArc<T>) -> &Arc<T> {
+        &this.inner
+    }
+
+    /// Unwraps the underlying [`Arc`].
+    pub fn into_arc(this: SgxArc<T>) -> Arc<T> {
+        this.inner
+    }
+}
+
+impl<T: ?Sized> Clone for SgxArc<T> {
+    fn clone(&self) -> SgxArc<T> {
+        SgxArc { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl<T: ?Sized> Deref for SgxArc<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: ?Sized> AsRef<T> for SgxArc<T> {
+    fn as_ref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: ?Sized> Borrow<T> for SgxArc<T> {
+    fn borrow(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: ?Sized> From<Arc<T>> for SgxArc<T> {
+    fn from(inner: Arc<T>) -> SgxArc<T> {
+        SgxArc { inner }
+    }
+}
+
+impl<T> From<T> for SgxArc<T> {
+    fn from(value: T) -> SgxArc<T> {
+        SgxArc::new(value)
+    }
+}
+
+impl<T: Default> Default for SgxArc<T> {
+    fn default() -> SgxArc<T> {
+        SgxArc::new(T::default())
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for SgxArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for SgxArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
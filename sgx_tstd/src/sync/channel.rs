@@ -0,0 +1,207 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use crate::fmt;
+use crate::hint;
+use crate::marker::PhantomData;
+use crate::mem;
+use crate::ptr;
+use crate::sync::atomic::{AtomicU64, Ordering};
+use sgx_trts::trts::rsgx_raw_is_outside_enclave;
+use sgx_types::marker::ContiguousMemory;
+use sgx_types::{sgx_channel_header_t, sgx_status_t, SgxError, SgxResult};
+
+/// The sending half of a ring buffer in untrusted memory, through which an
+/// enclave streams values to the host while an ECALL is still running.
+///
+/// The host allocates the buffer with `sgx_urts::channel::SgxChannelReader`,
+/// passes it to the ECALL as a `[user_check]` pointer, and drains it from
+/// another thread with `read_available`. The enclave only ever holds one
+/// slot's worth of data at a time, so results of any size can be returned
+/// without buffering them in the EPC.
+///
+/// Values are copied byte for byte into untrusted memory, so `T` must not
+/// contain pointers, and anything confidential must be encrypted first.
+/// The host controls the shared header: the channel validates the buffer
+/// once in [`from_raw_parts`], keeps its own write position, and fails
+/// with `SGX_ERROR_UNEXPECTED` if the read position reported by the host
+/// is inconsistent with it.
+///
+/// [`from_raw_parts`]: SgxChannel::from_raw_parts
+///
+/// # Examples
+///
+/// ```
+/// use sgx_types::sgx_status_t;
+/// use std::sync::SgxChannel;
+///
+/// #[no_mangle]
+/// pub extern "C" fn ecall_select_all(buf: *mut u8, len: usize) -> sgx_status_t {
+///     let mut chan = match unsafe { SgxChannel::<u64>::from_raw_parts(buf, len) } {
+///         Ok(chan) => chan,
+///         Err(e) => return e,
+///     };
+///     for row in 0..10_000_u64 {
+///         if let Err(e) = chan.send(&row) {
+///             return e;
+///         }
+///     }
+///     sgx_status_t::SGX_SUCCESS
+/// }
+/// ```
+pub struct SgxChannel<T: Copy + ContiguousMemory> {
+    header: *mut sgx_channel_header_t,
+    slots: *mut u8,
+    slot_count: u64,
+    written: u64,
+    marker: PhantomData<T>,
+}
+
+impl<T: Copy + ContiguousMemory> SgxChannel<T> {
+    /// Takes over the ring buffer of `len` bytes at `buf`, which the host
+    /// has initialized for values of type `T`.
+    ///
+    /// # Safety
+    ///
+    /// `buf` must stay valid until the channel is dropped, and nothing but
+    /// the host's reader may access it in the meantime.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `buf` is null, misaligned or not entirely outside the enclave, or
+    /// the header does not describe slots of `T` that fit into `len` bytes.
+    pub unsafe fn from_raw_parts(buf: *mut u8, len: usize) -> SgxResult<SgxChannel<T>> {
+        let header_len = mem::size_of::<sgx_channel_header_t>();
+        if buf.is_null()
+            || buf as usize % mem::align_of::<sgx_channel_header_t>() != 0
+            || mem::align_of::<T>() > mem::align_of::<sgx_channel_header_t>()
+            || len < header_len
+            || !rsgx_raw_is_outside_enclave(buf, len)
+        {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        // Read the host-controlled fields once.
+        let header = buf as *mut sgx_channel_header_t;
+        let slot_size = ptr::read_volatile(ptr::addr_of!((*header).slot_size));
+        let slot_count = ptr::read_volatile(ptr::addr_of!((*header).slot_count));
+        let slots_len = slot_count.checked_mul(slot_size);
+        if slot_size != mem::size_of::<T>() as u64
+            || slot_count == 0
+            || slots_len.map_or(true, |n| n > (len - header_len) as u64)
+        {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut channel = SgxChannel {
+            header,
+            slots: buf.add(header_len),
+            slot_count,
+            written: 0,
+            marker: PhantomData,
+        };
+        channel.written = channel.write_count().load(Ordering::Relaxed);
+        Ok(channel)
+    }
+
+    /// Copies `value` into the buffer if there is a free slot.
+    ///
+    /// Returns `Ok(false)` if the host has not drained the buffer yet.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The host reported a read position ahead of the data written so far,
+    /// or more than a buffer behind it.
+    pub fn try_send(&mut self, value: &T) -> SgxResult<bool> {
+        let read = self.read_count().load(Ordering::Acquire);
+        let pending = self
+            .written
+            .checked_sub(read)
+            .filter(|n| *n <= self.slot_count)
+            .ok_or(sgx_status_t::SGX_ERROR_UNEXPECTED)?;
+        if pending == self.slot_count {
+            return Ok(false);
+        }
+
+        let slot = (self.written % self.slot_count) as usize;
+        unsafe {
+            ptr::copy_nonoverlapping(
+                value as *const T as *const u8,
+                self.slots.add(slot * mem::size_of::<T>()),
+                mem::size_of::<T>(),
+            );
+        }
+        self.written += 1;
+        self.write_count().store(self.written, Ordering::Release);
+        Ok(true)
+    }
+
+    /// Copies `value` into the buffer, spinning until the host frees a slot.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// See [`try_send`](SgxChannel::try_send).
+    pub fn send(&mut self, value: &T) -> SgxError {
+        while !self.try_send(value)? {
+            hint::spin_loop();
+        }
+        Ok(())
+    }
+
+    /// Returns the number of values sent so far.
+    pub fn sent(&self) -> u64 {
+        self.written
+    }
+
+    /// Tells the host that no more values will be sent.
+    ///
+    /// This is also done when the channel is dropped.
+    pub fn close(self) {}
+
+    fn write_count(&self) -> &AtomicU64 {
+        unsafe { &*(ptr::addr_of_mut!((*self.header).write_count) as *const AtomicU64) }
+    }
+
+    fn read_count(&self) -> &AtomicU64 {
+        unsafe { &*(ptr::addr_of_mut!((*self.header).read_count) as *const AtomicU64) }
+    }
+
+    fn closed(&self) -> &AtomicU64 {
+        unsafe { &*(ptr::addr_of_mut!((*self.header).closed) as *const AtomicU64) }
+    }
+}
+
+impl<T: Copy + ContiguousMemory> Drop for SgxChannel<T> {
+    fn drop(&mut self) {
+        self.closed().store(1, Ordering::Release);
+    }
+}
+
+impl<T: Copy + ContiguousMemory> fmt::Debug for SgxChannel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SgxChannel")
+            .field("slot_count", &self.slot_count)
+            .field("sent", &self.written)
+            .finish_non_exhaustive()
+    }
+}
@@ -561,6 +561,52 @@ impl SgxCondvar {
     pub fn notify_all(&self) {
         self.inner.notify_all()
     }
+
+    /// Wakes up all blocked threads on this condvar if `predicate` holds for
+    /// the data protected by `guard`.
+    ///
+    /// Evaluating the predicate under the lock that waiters pass to [`wait`]
+    /// guarantees that a waiter either sees the state change before it
+    /// blocks, or is woken up by this call.
+    ///
+    /// Returns whether [`notify_all`] was called.
+    ///
+    /// [`wait`]: Self::wait
+    /// [`notify_all`]: Self::notify_all
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::{Arc, SgxMutex as Mutex, SgxCondvar as Condvar};
+    /// use std::thread;
+    ///
+    /// let pair = Arc::new((Mutex::new(0), Condvar::new()));
+    /// let pair2 = Arc::clone(&pair);
+    ///
+    /// thread::spawn(move|| {
+    ///     let (lock, cvar) = &*pair2;
+    ///     for _ in 0..10 {
+    ///         let mut count = lock.lock().unwrap();
+    ///         *count += 1;
+    ///         // Only wake the waiters once all the work is done.
+    ///         cvar.broadcast_if(&count, |count| *count == 10);
+    ///     }
+    /// });
+    ///
+    /// let (lock, cvar) = &*pair;
+    /// let count = cvar.wait_while(lock.lock().unwrap(), |count| *count < 10).unwrap();
+    /// assert_eq!(*count, 10);
+    /// ```
+    pub fn broadcast_if<T, P>(&self, guard: &SgxMutexGuard<'_, T>, predicate: P) -> bool
+    where
+        P: FnOnce(&T) -> bool,
+    {
+        let notify = predicate(&**guard);
+        if notify {
+            self.notify_all();
+        }
+        notify
+    }
 }
 
 impl fmt::Debug for SgxCondvar {
@@ -0,0 +1,292 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use crate::cell::UnsafeCell;
+use crate::fmt;
+use crate::ops::{Deref, DerefMut};
+use crate::sync::atomic::{AtomicUsize, Ordering};
+use crate::sync::{poison, LockResult, PoisonError, SgxCondvar, SgxMutex, TryLockError, TryLockResult};
+
+// Waiters sleep on the condition variable of their ticket modulo `SLOTS`, so
+// an unlock only wakes the next ticket holder, plus whoever shares its slot
+// when more than `SLOTS` threads are queued.
+const SLOTS: usize = 16;
+
+/// A mutual exclusion primitive that grants the lock in request order.
+///
+/// [`SgxMutex`] makes no fairness guarantee: a thread that unlocks and
+/// immediately locks again usually wins over the threads that are already
+/// waiting, so under high contention some threads can starve. `SgxFairMutex`
+/// is a ticket lock. Each [`lock`] call takes the next ticket and waits until
+/// that ticket is served; each unlock serves the next ticket and wakes its
+/// holder. Threads therefore acquire the lock first in, first out.
+///
+/// Fairness costs throughput: the lock cannot be reacquired by the thread
+/// that just released it while others wait, so every hand-off involves a
+/// wake-up. Prefer [`SgxMutex`] unless starvation is an actual problem.
+///
+/// Like [`SgxMutex`], the lock is poisoned when a thread panics while
+/// holding it.
+///
+/// [`lock`]: SgxFairMutex::lock
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::{Arc, SgxFairMutex};
+/// use std::thread;
+///
+/// let counter = Arc::new(SgxFairMutex::new(0));
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         let counter = Arc::clone(&counter);
+///         thread::spawn(move || *counter.lock().unwrap() += 1)
+///     })
+///     .collect();
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+/// assert_eq!(*counter.lock().unwrap(), 4);
+/// ```
+pub struct SgxFairMutex<T: ?Sized> {
+    next_ticket: AtomicUsize,
+    // Only written with `state` held, so that a waiter cannot miss the
+    // notification for its ticket.
+    serving: AtomicUsize,
+    state: SgxMutex<()>,
+    slots: [SgxCondvar; SLOTS],
+    poison: poison::Flag,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for SgxFairMutex<T> {}
+unsafe impl<T: ?Sized + Send> Sync for SgxFairMutex<T> {}
+
+/// An RAII guard of a locked [`SgxFairMutex`]. The lock is passed to the
+/// next ticket holder when the guard is dropped.
+///
+/// This structure is created by the [`lock`] and [`try_lock`] methods on
+/// [`SgxFairMutex`].
+///
+/// [`lock`]: SgxFairMutex::lock
+/// [`try_lock`]: SgxFairMutex::try_lock
+#[must_use = "if unused the SgxFairMutex will immediately unlock"]
+#[clippy::has_significant_drop]
+pub struct SgxFairMutexGuard<'a, T: ?Sized + 'a> {
+    lock: &'a SgxFairMutex<T>,
+    poison: poison::Guard,
+}
+
+impl<T: ?Sized> !Send for SgxFairMutexGuard<'_, T> {}
+unsafe impl<T: ?Sized + Sync> Sync for SgxFairMutexGuard<'_, T> {}
+
+impl<T> SgxFairMutex<T> {
+    /// Creates a new fair mutex in an unlocked state ready for use.
+    #[allow(clippy::declare_interior_mutable_const)]
+    pub const fn new(t: T) -> SgxFairMutex<T> {
+        const SLOT: SgxCondvar = SgxCondvar::new();
+        SgxFairMutex {
+            next_ticket: AtomicUsize::new(0),
+            serving: AtomicUsize::new(0),
+            state: SgxMutex::new(()),
+            slots: [SLOT; SLOTS],
+            poison: poison::Flag::new(),
+            data: UnsafeCell::new(t),
+        }
+    }
+}
+
+impl<T: ?Sized> SgxFairMutex<T> {
+    /// Acquires the mutex, blocking the current thread until every thread
+    /// that called `lock` earlier has released it.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error once the mutex is acquired.
+    ///
+    /// # Panics
+    ///
+    /// Locking the mutex again in the thread which already holds it
+    /// deadlocks.
+    pub fn lock(&self) -> LockResult<SgxFairMutexGuard<'_, T>> {
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+        if self.serving.load(Ordering::Acquire) != ticket {
+            let slot = &self.slots[ticket % SLOTS];
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            while self.serving.load(Ordering::Acquire) != ticket {
+                state = slot.wait(state).unwrap_or_else(PoisonError::into_inner);
+            }
+        }
+        unsafe { SgxFairMutexGuard::new(self) }
+    }
+
+    /// Attempts to acquire the mutex without blocking. This succeeds only
+    /// if the mutex is unlocked and no thread is waiting for it.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return the [`Poisoned`] error if the mutex would
+    /// otherwise be acquired.
+    ///
+    /// If the mutex could not be acquired because it is held or waited for,
+    /// then this call will return the [`WouldBlock`] error.
+    ///
+    /// [`Poisoned`]: TryLockError::Poisoned
+    /// [`WouldBlock`]: TryLockError::WouldBlock
+    pub fn try_lock(&self) -> TryLockResult<SgxFairMutexGuard<'_, T>> {
+        let serving = self.serving.load(Ordering::Acquire);
+        match self.next_ticket.compare_exchange(
+            serving,
+            serving.wrapping_add(1),
+            Ordering::Acquire,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => unsafe { Ok(SgxFairMutexGuard::new(self)?) },
+            Err(_) => Err(TryLockError::WouldBlock),
+        }
+    }
+
+    /// Determines whether the mutex is poisoned.
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.poison.get()
+    }
+
+    /// Clears the poisoned state from the mutex.
+    #[inline]
+    pub fn clear_poison(&self) {
+        self.poison.clear();
+    }
+
+    /// Consumes this mutex, returning the underlying data.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error instead.
+    pub fn into_inner(self) -> LockResult<T>
+    where
+        T: Sized,
+    {
+        let data = self.data.into_inner();
+        poison::map_result(self.poison.borrow(), |()| data)
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error instead.
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        let data = self.data.get_mut();
+        poison::map_result(self.poison.borrow(), |()| data)
+    }
+
+    fn unlock(&self) {
+        let next = self.serving.load(Ordering::Relaxed).wrapping_add(1);
+        {
+            let _state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            self.serving.store(next, Ordering::Release);
+        }
+        if self.next_ticket.load(Ordering::Relaxed) != next {
+            self.slots[next % SLOTS].notify_all();
+        }
+    }
+}
+
+impl<T> From<T> for SgxFairMutex<T> {
+    /// Creates a new fair mutex in an unlocked state ready for use.
+    /// This is equivalent to [`SgxFairMutex::new`].
+    fn from(t: T) -> Self {
+        SgxFairMutex::new(t)
+    }
+}
+
+impl<T: ?Sized + Default> Default for SgxFairMutex<T> {
+    /// Creates a `SgxFairMutex<T>`, with the `Default` value for T.
+    fn default() -> SgxFairMutex<T> {
+        SgxFairMutex::new(Default::default())
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for SgxFairMutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("SgxFairMutex");
+        match self.try_lock() {
+            Ok(guard) => {
+                d.field("data", &&*guard);
+            }
+            Err(TryLockError::Poisoned(err)) => {
+                d.field("data", &&**err.get_ref());
+            }
+            Err(TryLockError::WouldBlock) => {
+                struct LockedPlaceholder;
+                impl fmt::Debug for LockedPlaceholder {
+                    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        f.write_str("<locked>")
+                    }
+                }
+                d.field("data", &LockedPlaceholder);
+            }
+        }
+        d.field("poisoned", &self.poison.get());
+        d.finish_non_exhaustive()
+    }
+}
+
+impl<'mutex, T: ?Sized> SgxFairMutexGuard<'mutex, T> {
+    unsafe fn new(lock: &'mutex SgxFairMutex<T>) -> LockResult<SgxFairMutexGuard<'mutex, T>> {
+        poison::map_result(lock.poison.guard(), |guard| SgxFairMutexGuard { lock, poison: guard })
+    }
+}
+
+impl<T: ?Sized> Deref for SgxFairMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for SgxFairMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for SgxFairMutexGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.poison.done(&self.poison);
+        self.lock.unlock();
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for SgxFairMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for SgxFairMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
@@ -0,0 +1,270 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+use crate::boxed::Box;
+use crate::fmt;
+use crate::hint;
+use crate::ops::{Deref, DerefMut};
+use crate::ptr;
+use crate::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
+/// A type that can be linked into an [`SgxMpscQueue`].
+///
+/// # Safety
+///
+/// `next` must always return the same `AtomicPtr`, embedded in `self`, and
+/// nothing but the queue may access it while the node is queued.
+pub unsafe trait IntrusiveNode {
+    /// The link to the next node in the queue.
+    fn next(&self) -> &AtomicPtr<Self>
+    where
+        Self: Sized;
+}
+
+/// A multi-producer, single-consumer queue of intrusively linked nodes.
+///
+/// Unlike [`mpsc::channel`], the queue never allocates: each `T` carries
+/// its own link (see [`IntrusiveNode`]), so pushing a node only swaps a
+/// couple of pointers. This makes it suitable for high-frequency signaling
+/// between enclave threads, where the cost of a heap allocation per message
+/// dominates.
+///
+/// Producers never block or retry. The consumer may briefly spin if it
+/// catches a producer between its two stores. Only one thread may pop at a
+/// time; a concurrent `pop` panics.
+///
+/// The queue owns the nodes pushed onto it. Nodes still queued when the
+/// queue is dropped are freed as [`IntrusiveBox`]es.
+///
+/// [`mpsc::channel`]: crate::sync::mpsc::channel
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::atomic::AtomicPtr;
+/// use std::sync::{IntrusiveBox, IntrusiveNode, SgxMpscQueue};
+///
+/// struct Packet {
+///     next: AtomicPtr<Packet>,
+///     len: usize,
+/// }
+///
+/// unsafe impl IntrusiveNode for Packet {
+///     fn next(&self) -> &AtomicPtr<Packet> {
+///         &self.next
+///     }
+/// }
+///
+/// let queue = SgxMpscQueue::new();
+/// queue.push_box(IntrusiveBox::new(Packet { next: AtomicPtr::default(), len: 64 }));
+/// assert_eq!(queue.pop_box().unwrap().len, 64);
+/// assert!(queue.pop_box().is_none());
+/// ```
+pub struct SgxMpscQueue<T: IntrusiveNode> {
+    head: AtomicPtr<T>,
+    tail: AtomicPtr<T>,
+    popping: AtomicBool,
+}
+
+unsafe impl<T: IntrusiveNode + Send> Send for SgxMpscQueue<T> {}
+unsafe impl<T: IntrusiveNode + Send> Sync for SgxMpscQueue<T> {}
+
+impl<T: IntrusiveNode> SgxMpscQueue<T> {
+    /// Creates an empty queue.
+    pub const fn new() -> SgxMpscQueue<T> {
+        SgxMpscQueue {
+            head: AtomicPtr::new(ptr::null_mut()),
+            tail: AtomicPtr::new(ptr::null_mut()),
+            popping: AtomicBool::new(false),
+        }
+    }
+
+    /// Appends `node` to the back of the queue.
+    ///
+    /// # Safety
+    ///
+    /// `node` must come from [`IntrusiveBox::into_raw`] and must not
+    /// already be queued. The queue takes ownership of the node until it is
+    /// popped; if the queue is dropped first, the node is freed with it.
+    pub unsafe fn push(&self, node: *mut T) {
+        (*node).next().store(ptr::null_mut(), Ordering::Relaxed);
+        let prev = self.tail.swap(node, Ordering::AcqRel);
+        if prev.is_null() {
+            self.head.store(node, Ordering::Release);
+        } else {
+            (*prev).next().store(node, Ordering::Release);
+        }
+    }
+
+    /// Removes the node at the front of the queue, returning `None` if the
+    /// queue is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if another thread is popping from the queue at the same time.
+    pub fn pop(&self) -> Option<*mut T> {
+        let _guard = PopGuard::new(&self.popping);
+
+        let head = self.head.load(Ordering::Acquire);
+        if head.is_null() {
+            return None;
+        }
+        let mut next = unsafe { (*head).next().load(Ordering::Acquire) };
+        if next.is_null() {
+            // `head` looks like the last node. Unlink it unless a producer
+            // has already swapped in a successor.
+            if self
+                .tail
+                .compare_exchange(head, ptr::null_mut(), Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                // A producer may have pushed onto the now empty queue and
+                // set `head` already; leave its node in place.
+                let _ = self.head.compare_exchange(
+                    head,
+                    ptr::null_mut(),
+                    Ordering::AcqRel,
+                    Ordering::Relaxed,
+                );
+                return Some(head);
+            }
+            // The successor is being linked; wait for the producer's store.
+            loop {
+                next = unsafe { (*head).next().load(Ordering::Acquire) };
+                if !next.is_null() {
+                    break;
+                }
+                hint::spin_loop();
+            }
+        }
+        self.head.store(next, Ordering::Release);
+        Some(head)
+    }
+
+    /// Appends a boxed node to the back of the queue.
+    pub fn push_box(&self, node: IntrusiveBox<T>) {
+        unsafe { self.push(IntrusiveBox::into_raw(node)) }
+    }
+
+    /// Removes the boxed node at the front of the queue.
+    ///
+    /// # Panics
+    ///
+    /// Panics if another thread is popping from the queue at the same time.
+    pub fn pop_box(&self) -> Option<IntrusiveBox<T>> {
+        // Every node was pushed by `push_box`, or by `push` with a pointer
+        // from `IntrusiveBox::into_raw`.
+        self.pop().map(|node| unsafe { IntrusiveBox::from_raw(node) })
+    }
+
+    /// Returns `true` if the queue holds no nodes.
+    ///
+    /// With concurrent producers the result may be stale by the time it is
+    /// returned.
+    pub fn is_empty(&self) -> bool {
+        self.tail.load(Ordering::Acquire).is_null()
+    }
+}
+
+impl<T: IntrusiveNode> Drop for SgxMpscQueue<T> {
+    fn drop(&mut self) {
+        // `&mut self` rules out producers, so `pop` never spins here.
+        while self.pop_box().is_some() {}
+    }
+}
+
+impl<T: IntrusiveNode> Default for SgxMpscQueue<T> {
+    fn default() -> SgxMpscQueue<T> {
+        SgxMpscQueue::new()
+    }
+}
+
+impl<T: IntrusiveNode> fmt::Debug for SgxMpscQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SgxMpscQueue").field("is_empty", &self.is_empty()).finish()
+    }
+}
+
+struct PopGuard<'a>(&'a AtomicBool);
+
+impl<'a> PopGuard<'a> {
+    fn new(popping: &'a AtomicBool) -> PopGuard<'a> {
+        assert!(!popping.swap(true, Ordering::Acquire), "concurrent pop from SgxMpscQueue");
+        PopGuard(popping)
+    }
+}
+
+impl Drop for PopGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// An owned, heap-allocated node for an [`SgxMpscQueue`].
+///
+/// The node is allocated once and can be pushed and popped any number of
+/// times without further allocation.
+pub struct IntrusiveBox<T: IntrusiveNode> {
+    node: Box<T>,
+}
+
+impl<T: IntrusiveNode> IntrusiveBox<T> {
+    /// Moves `value` to the enclave heap.
+    pub fn new(value: T) -> IntrusiveBox<T> {
+        IntrusiveBox { node: Box::new(value) }
+    }
+
+    /// Consumes the box, returning the raw node pointer.
+    pub fn into_raw(b: IntrusiveBox<T>) -> *mut T {
+        Box::into_raw(b.node)
+    }
+
+    /// Takes back ownership of a node returned by [`into_raw`].
+    ///
+    /// # Safety
+    ///
+    /// `node` must come from [`into_raw`] and must not be queued.
+    ///
+    /// [`into_raw`]: IntrusiveBox::into_raw
+    pub unsafe fn from_raw(node: *mut T) -> IntrusiveBox<T> {
+        IntrusiveBox { node: Box::from_raw(node) }
+    }
+
+    /// Unwraps the value.
+    pub fn into_inner(b: IntrusiveBox<T>) -> T {
+        *b.node
+    }
+}
+
+impl<T: IntrusiveNode> Deref for IntrusiveBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.node
+    }
+}
+
+impl<T: IntrusiveNode> DerefMut for IntrusiveBox<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.node
+    }
+}
+
+impl<T: IntrusiveNode + fmt::Debug> fmt::Debug for IntrusiveBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
@@ -30,12 +30,18 @@
 pub use alloc_crate::sync::{Arc, Weak};
 pub use core::sync::atomic;
 
+pub use self::arc::SgxArc;
 pub use self::barrier::{Barrier, BarrierWaitResult};
+pub use self::channel::SgxChannel;
 pub use self::condvar::{SgxCondvar, WaitTimeoutResult};
-pub use self::mutex::{SgxMutex, SgxMutexGuard};
+pub use self::fair_mutex::{SgxFairMutex, SgxFairMutexGuard};
+pub use self::intrusive_queue::{IntrusiveBox, IntrusiveNode, SgxMpscQueue};
+pub use self::mutex::{SgxMappedMutexGuard, SgxMutex, SgxMutexGuard};
 pub use self::once::{Once, OnceState, ONCE_INIT};
 pub use self::poison::{LockResult, PoisonError, TryLockError, TryLockResult};
 pub use self::rwlock::{SgxRwLock, SgxRwLockReadGuard, SgxRwLockWriteGuard};
+pub use self::spin_mutex::{SpinMutex, SpinMutexGuard};
+pub use self::spin_rwlock::{SpinRwLock, SpinRwLockReadGuard, SpinRwLockWriteGuard};
 pub use self::spinlock::{SgxSpinlock, SgxSpinlockGuard};
 
 pub use self::lazy_lock::LazyLock;
@@ -45,13 +51,20 @@ pub(crate) use self::spinlock::SgxThreadSpinlock;
 
 #[cfg(feature = "thread")]
 pub mod mpsc;
+pub mod watch;
 
+mod arc;
 mod barrier;
+mod channel;
 mod condvar;
+mod fair_mutex;
+mod intrusive_queue;
 mod lazy_lock;
 mod mutex;
 mod once;
 mod once_lock;
 mod poison;
 mod rwlock;
+mod spin_mutex;
+mod spin_rwlock;
 mod spinlock;
@@ -28,10 +28,17 @@
 //!
 
 use crate::cell::UnsafeCell;
+use crate::cmp;
 use crate::fmt;
+use crate::marker::PhantomData;
+use crate::mem::ManuallyDrop;
 use crate::ops::{Deref, DerefMut};
-use crate::sync::{poison, LockResult, TryLockError, TryLockResult};
+use crate::ptr::{self, NonNull};
+use crate::sync::{poison, LockResult, PoisonError, SgxCondvar, TryLockError, TryLockResult};
 use crate::sys_common::mutex as sys;
+use crate::time::{Duration, Instant};
+#[cfg(not(feature = "untrusted_time"))]
+use crate::untrusted::time::InstantEx;
 
 /// A mutual exclusion primitive useful for protecting shared data
 ///
@@ -340,6 +347,84 @@ impl<T: ?Sized> SgxMutex<T> {
         }
     }
 
+    /// Attempts to acquire this lock, blocking the current thread for at most
+    /// `timeout`.
+    ///
+    /// If the lock could not be acquired before the timeout expired, then
+    /// [`Err`] is returned. Otherwise, an RAII guard is returned. The lock will
+    /// be unlocked when the guard is dropped.
+    ///
+    /// The timeout is measured with the untrusted system clock, so the host
+    /// can make this function return early or late; it must not be relied on
+    /// for security. Like [`try_lock`], this function does not guarantee any
+    /// fairness between the threads waiting for the lock.
+    ///
+    /// [`try_lock`]: Self::try_lock
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return the [`Poisoned`] error once the mutex is
+    /// acquired.
+    ///
+    /// If the mutex could not be acquired before the timeout expired, then
+    /// this call will return the [`WouldBlock`] error.
+    ///
+    /// [`Poisoned`]: TryLockError::Poisoned
+    /// [`WouldBlock`]: TryLockError::WouldBlock
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::{Arc, SgxMutex as Mutex};
+    /// use std::thread;
+    /// use std::time::Duration;
+    ///
+    /// let mutex = Arc::new(Mutex::new(0));
+    /// let guard = mutex.lock().unwrap();
+    ///
+    /// let c_mutex = Arc::clone(&mutex);
+    /// thread::spawn(move || {
+    ///     assert!(c_mutex.try_lock_for(Duration::from_millis(10)).is_err());
+    /// }).join().expect("thread::spawn failed");
+    ///
+    /// drop(guard);
+    /// assert!(mutex.try_lock_for(Duration::from_millis(10)).is_ok());
+    /// ```
+    pub fn try_lock_for(&self, timeout: Duration) -> TryLockResult<SgxMutexGuard<'_, T>> {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.try_lock_until(deadline),
+            None => self.lock().map_err(TryLockError::from),
+        }
+    }
+
+    /// Attempts to acquire this lock, blocking the current thread until
+    /// `deadline` at the latest.
+    ///
+    /// This is [`try_lock_for`] with an absolute deadline, which is
+    /// convenient when several operations must complete within the same
+    /// time budget.
+    ///
+    /// [`try_lock_for`]: Self::try_lock_for
+    ///
+    /// # Errors
+    ///
+    /// The errors are those of [`try_lock_for`].
+    pub fn try_lock_until(&self, deadline: Instant) -> TryLockResult<SgxMutexGuard<'_, T>> {
+        loop {
+            match self.try_lock() {
+                Err(TryLockError::WouldBlock) => {}
+                res => return res,
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(TryLockError::WouldBlock);
+            }
+            let guard = TIMED_WAIT_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+            let _ = TIMED_WAIT_CVAR.wait_timeout(guard, cmp::min(deadline - now, RETRY_INTERVAL));
+        }
+    }
+
     /// Immediately drops the guard, and consequently unlocks the mutex.
     ///
     /// This function is equivalent to calling [`drop`] on the guard but is more self-documenting.
@@ -471,6 +556,49 @@ impl<T: ?Sized> SgxMutex<T> {
     }
 }
 
+impl<T> SgxMutex<Option<T>> {
+    /// Acquires the mutex, initializing the protected value with `f` if it
+    /// is still `None`, and returns a guard to the value itself.
+    ///
+    /// This replaces the usual `if guard.is_none() { *guard = Some(init()) }`
+    /// dance for lazily initialized singletons. Initialization runs with the
+    /// mutex held, so `f` is called at most once however many threads race to
+    /// lock the mutex, unless it panics, in which case the value stays `None`
+    /// and the next caller runs its own `f`.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error once the mutex is acquired and the
+    /// value initialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use std::sync::SgxMutex as Mutex;
+    ///
+    /// static CACHE: Mutex<Option<HashMap<u32, u32>>> = Mutex::new(None);
+    ///
+    /// CACHE.lock_or_init(HashMap::new).unwrap().insert(1, 2);
+    /// assert_eq!(CACHE.lock_or_init(HashMap::new).unwrap().get(&1), Some(&2));
+    /// ```
+    pub fn lock_or_init<F>(&self, f: F) -> LockResult<SgxMappedMutexGuard<'_, T>>
+    where
+        F: FnOnce() -> T,
+    {
+        let (mut guard, poisoned) = match self.lock() {
+            Ok(guard) => (guard, false),
+            Err(err) => (err.into_inner(), true),
+        };
+        if guard.is_none() {
+            *guard = Some(f());
+        }
+        let guard = SgxMutexGuard::map(guard, |data| data.as_mut().unwrap());
+        if poisoned { Err(PoisonError::new(guard)) } else { Ok(guard) }
+    }
+}
+
 impl<T> From<T> for SgxMutex<T> {
     /// Creates a new mutex in an unlocked state ready for use.
     /// This is equivalent to [`Mutex::new`].
@@ -553,6 +681,88 @@ impl<T: ?Sized + fmt::Display> fmt::Display for SgxMutexGuard<'_, T> {
     }
 }
 
+impl<'a, T: ?Sized> SgxMutexGuard<'a, T> {
+    /// Makes a [`SgxMappedMutexGuard`] for a component of the locked data,
+    /// e.g. a field of a struct or the value of an `Option`.
+    ///
+    /// The mutex is already locked, so this cannot fail. This is an
+    /// associated function that needs to be used as `SgxMutexGuard::map(...)`,
+    /// so that it does not shadow a method on the data.
+    pub fn map<U: ?Sized, F>(orig: Self, f: F) -> SgxMappedMutexGuard<'a, U>
+    where
+        F: FnOnce(&mut T) -> &mut U,
+    {
+        let data = NonNull::from(f(unsafe { &mut *orig.lock.data.get() }));
+        let orig = ManuallyDrop::new(orig);
+        SgxMappedMutexGuard {
+            data,
+            inner: &orig.lock.inner,
+            poison_flag: &orig.lock.poison,
+            poison: unsafe { ptr::read(&orig.poison) },
+            _variance: PhantomData,
+        }
+    }
+}
+
+/// An RAII mutex guard returned by [`SgxMutexGuard::map`] and
+/// [`SgxMutex::lock_or_init`], which gives access to a component of the
+/// locked data. The mutex is unlocked when this structure is dropped.
+#[must_use = "if unused the Mutex will immediately unlock"]
+#[clippy::has_significant_drop]
+pub struct SgxMappedMutexGuard<'a, T: ?Sized + 'a> {
+    data: NonNull<T>,
+    inner: &'a sys::MovableMutex,
+    poison_flag: &'a poison::Flag,
+    poison: poison::Guard,
+    _variance: PhantomData<&'a mut T>,
+}
+
+impl<T: ?Sized> !Send for SgxMappedMutexGuard<'_, T> {}
+unsafe impl<T: ?Sized + Sync> Sync for SgxMappedMutexGuard<'_, T> {}
+
+impl<T: ?Sized> Deref for SgxMappedMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { self.data.as_ref() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for SgxMappedMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { self.data.as_mut() }
+    }
+}
+
+impl<T: ?Sized> Drop for SgxMappedMutexGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            self.poison_flag.done(&self.poison);
+            self.inner.raw_unlock();
+        }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for SgxMappedMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for SgxMappedMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+// Threads blocked in `try_lock_until` retry the mutex at least every
+// `RETRY_INTERVAL`, sleeping in between on a condition variable which is
+// never notified. Unlocking a mutex therefore costs nothing extra.
+static TIMED_WAIT_LOCK: SgxMutex<()> = SgxMutex::new(());
+static TIMED_WAIT_CVAR: SgxCondvar = SgxCondvar::new();
+const RETRY_INTERVAL: Duration = Duration::from_millis(1);
+
 pub fn guard_lock<'a, T: ?Sized>(guard: &SgxMutexGuard<'a, T>) -> &'a sys::MovableMutex {
     &guard.lock.inner
 }
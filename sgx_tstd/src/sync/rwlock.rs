@@ -17,9 +17,12 @@
 
 use crate::cell::UnsafeCell;
 use crate::fmt;
+use crate::hint;
+use crate::mem::MaybeUninit;
 use crate::ops::{Deref, DerefMut};
-use crate::ptr::NonNull;
-use crate::sync::{poison, LockResult, TryLockError, TryLockResult};
+use crate::ptr::{self, NonNull};
+use crate::sync::atomic::{self, AtomicUsize, Ordering};
+use crate::sync::{poison, LockResult, PoisonError, TryLockError, TryLockResult};
 use crate::sys_common::rwlock as sys;
 
 /// A reader-writer lock
@@ -92,6 +95,8 @@ use crate::sys_common::rwlock as sys;
 pub struct SgxRwLock<T: ?Sized> {
     inner: sys::MovableRwLock,
     poison: poison::Flag,
+    // Odd while a writer holds the lock, see `optimistic_read`.
+    seq: AtomicUsize,
     data: UnsafeCell<T>,
 }
 
@@ -158,9 +163,82 @@ impl<T> SgxRwLock<T> {
         SgxRwLock {
             inner: sys::MovableRwLock::new(),
             poison: poison::Flag::new(),
+            seq: AtomicUsize::new(0),
             data: UnsafeCell::new(t),
         }
     }
+
+    /// Reads the data without taking the lock, in the manner of a seqlock.
+    ///
+    /// `f` is called with a copy of the data which is only made if no writer
+    /// held the lock while it was taken, so it always sees a consistent value.
+    /// If a writer interfered, or if `f` returns `None`, the read is retried.
+    /// After a number of failed attempts the read lock is taken, so readers
+    /// make progress even under a steady stream of writers.
+    ///
+    /// Unlike [`read`], this does not write to any shared memory, which makes
+    /// it much cheaper for small values that are read far more often than
+    /// they are written. The copy is made with volatile reads and discarded
+    /// unseen if it may be torn, which is why `T` must be [`Copy`].
+    ///
+    /// Poisoning is ignored: a value left behind by a panicking writer is
+    /// passed to `f` like any other.
+    ///
+    /// [`read`]: Self::read
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::SgxRwLock as RwLock;
+    ///
+    /// let lock = RwLock::new((1, 2));
+    ///
+    /// let sum = lock.optimistic_read(|&(a, b)| Some(a + b));
+    /// assert_eq!(sum, 3);
+    /// ```
+    pub fn optimistic_read<R, F>(&self, f: F) -> R
+    where
+        T: Copy,
+        F: Fn(&T) -> Option<R>,
+    {
+        const OPTIMISTIC_RETRIES: usize = 64;
+
+        for _ in 0..OPTIMISTIC_RETRIES {
+            if let Some(data) = self.try_optimistic_copy() {
+                if let Some(r) = f(&data) {
+                    return r;
+                }
+            }
+            hint::spin_loop();
+        }
+
+        loop {
+            let guard = self.read().unwrap_or_else(PoisonError::into_inner);
+            if let Some(r) = f(&guard) {
+                return r;
+            }
+        }
+    }
+
+    fn try_optimistic_copy(&self) -> Option<T>
+    where
+        T: Copy,
+    {
+        let seq = self.seq.load(Ordering::Acquire);
+        if seq & 1 != 0 {
+            return None;
+        }
+        // SAFETY: the copy is not looked at until the sequence number has
+        // been checked, and a `MaybeUninit` may hold a torn value.
+        let data = unsafe { ptr::read_volatile(self.data.get() as *const MaybeUninit<T>) };
+        atomic::fence(Ordering::Acquire);
+        if self.seq.load(Ordering::Relaxed) == seq {
+            // SAFETY: no writer held the lock while the data was copied.
+            Some(unsafe { data.assume_init() })
+        } else {
+            None
+        }
+    }
 }
 
 impl<T: ?Sized> SgxRwLock<T> {
@@ -523,6 +601,9 @@ impl<'rwlock, T: ?Sized> SgxRwLockWriteGuard<'rwlock, T> {
     // SAFETY: if and only if `lock.inner.write()` (or `lock.inner.try_write()`) has been
     // successfully called from the same thread before instantiating this object.
     unsafe fn new(lock: &'rwlock SgxRwLock<T>) -> LockResult<SgxRwLockWriteGuard<'rwlock, T>> {
+        // Tell optimistic readers that the data is about to change.
+        lock.seq.fetch_add(1, Ordering::Relaxed);
+        atomic::fence(Ordering::Release);
         poison::map_result(lock.poison.guard(), |guard| SgxRwLockWriteGuard { lock, poison: guard })
     }
 }
@@ -588,6 +669,7 @@ impl<T: ?Sized> Drop for SgxRwLockReadGuard<'_, T> {
 impl<T: ?Sized> Drop for SgxRwLockWriteGuard<'_, T> {
     fn drop(&mut self) {
         self.lock.poison.done(&self.poison);
+        self.lock.seq.fetch_add(1, Ordering::Release);
         // SAFETY: the conditions of `SgxRwLockWriteGuard::new` were satisfied when created.
         unsafe {
             self.lock.inner.write_unlock();
@@ -0,0 +1,299 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+use crate::cell::UnsafeCell;
+use crate::fmt;
+use crate::hint;
+use crate::ops::{Deref, DerefMut};
+use crate::sync::atomic::{AtomicBool, Ordering};
+use crate::sync::{poison, LockResult, TryLockError, TryLockResult};
+
+// A waiter spins `1 << step` times between two attempts, doubling up to
+// `1 << SPIN_LIMIT` iterations.
+const SPIN_LIMIT: u32 = 6;
+
+/// Exponential backoff for the busy-wait loops of the spin locks.
+pub(super) struct Backoff {
+    step: u32,
+}
+
+impl Backoff {
+    pub(super) const fn new() -> Backoff {
+        Backoff { step: 0 }
+    }
+
+    pub(super) fn spin(&mut self) {
+        for _ in 0..1_u32 << self.step {
+            hint::spin_loop();
+        }
+        if self.step < SPIN_LIMIT {
+            self.step += 1;
+        }
+    }
+}
+
+/// A mutual exclusion primitive that busy-waits instead of blocking.
+///
+/// [`SgxMutex`] is built on `sgx_thread_mutex_t`: a contended lock puts the
+/// waiting thread to sleep with an OCALL, and the unlock wakes it with
+/// another one. For critical sections of a few instructions, such as bumping
+/// a counter or reading a map entry, those enclave transitions cost far more
+/// than the work done under the lock. `SpinMutex` never leaves the enclave: a
+/// waiter spins on an atomic flag, backing off exponentially with
+/// [`hint::spin_loop`].
+///
+/// Spinning burns the CPU for as long as the lock is held, so `SpinMutex`
+/// only suits locks that are held briefly and by few threads. Prefer
+/// [`SgxMutex`] when the critical section performs I/O or may run long.
+///
+/// The API mirrors [`SgxMutex`], including poisoning when a thread panics
+/// while holding the lock.
+///
+/// [`SgxMutex`]: crate::sync::SgxMutex
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::{Arc, SpinMutex};
+/// use std::thread;
+///
+/// let counter = Arc::new(SpinMutex::new(0));
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         let counter = Arc::clone(&counter);
+///         thread::spawn(move || *counter.lock().unwrap() += 1)
+///     })
+///     .collect();
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+/// assert_eq!(*counter.lock().unwrap(), 4);
+/// ```
+pub struct SpinMutex<T: ?Sized> {
+    locked: AtomicBool,
+    poison: poison::Flag,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for SpinMutex<T> {}
+unsafe impl<T: ?Sized + Send> Sync for SpinMutex<T> {}
+
+/// An RAII guard of a locked [`SpinMutex`]. The lock is released when the
+/// guard is dropped.
+///
+/// This structure is created by the [`lock`] and [`try_lock`] methods on
+/// [`SpinMutex`].
+///
+/// [`lock`]: SpinMutex::lock
+/// [`try_lock`]: SpinMutex::try_lock
+#[must_use = "if unused the SpinMutex will immediately unlock"]
+#[clippy::has_significant_drop]
+pub struct SpinMutexGuard<'a, T: ?Sized + 'a> {
+    lock: &'a SpinMutex<T>,
+    poison: poison::Guard,
+}
+
+impl<T: ?Sized> !Send for SpinMutexGuard<'_, T> {}
+unsafe impl<T: ?Sized + Sync> Sync for SpinMutexGuard<'_, T> {}
+
+impl<T> SpinMutex<T> {
+    /// Creates a new spin mutex in an unlocked state ready for use.
+    #[inline]
+    pub const fn new(t: T) -> SpinMutex<T> {
+        SpinMutex {
+            locked: AtomicBool::new(false),
+            poison: poison::Flag::new(),
+            data: UnsafeCell::new(t),
+        }
+    }
+}
+
+impl<T: ?Sized> SpinMutex<T> {
+    /// Acquires the mutex, spinning until it is available.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error once the mutex is acquired.
+    ///
+    /// # Panics
+    ///
+    /// Locking the mutex again in the thread which already holds it spins
+    /// forever.
+    pub fn lock(&self) -> LockResult<SpinMutexGuard<'_, T>> {
+        let mut backoff = Backoff::new();
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            // Wait on a plain load so that the cache line is not bounced
+            // between the waiters.
+            while self.locked.load(Ordering::Relaxed) {
+                backoff.spin();
+            }
+        }
+        unsafe { SpinMutexGuard::new(self) }
+    }
+
+    /// Attempts to acquire the mutex without spinning.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return the [`Poisoned`] error if the mutex would
+    /// otherwise be acquired.
+    ///
+    /// If the mutex could not be acquired because it is already locked, then
+    /// this call will return the [`WouldBlock`] error.
+    ///
+    /// [`Poisoned`]: TryLockError::Poisoned
+    /// [`WouldBlock`]: TryLockError::WouldBlock
+    pub fn try_lock(&self) -> TryLockResult<SpinMutexGuard<'_, T>> {
+        match self
+            .locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+        {
+            Ok(_) => unsafe { Ok(SpinMutexGuard::new(self)?) },
+            Err(_) => Err(TryLockError::WouldBlock),
+        }
+    }
+
+    /// Immediately drops the guard, and consequently unlocks the mutex.
+    ///
+    /// This function is equivalent to calling [`drop`] on the guard but is
+    /// more self-documenting.
+    pub fn unlock(guard: SpinMutexGuard<'_, T>) {
+        drop(guard);
+    }
+
+    /// Determines whether the mutex is poisoned.
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.poison.get()
+    }
+
+    /// Clears the poisoned state from the mutex.
+    #[inline]
+    pub fn clear_poison(&self) {
+        self.poison.clear();
+    }
+
+    /// Consumes this mutex, returning the underlying data.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error instead.
+    pub fn into_inner(self) -> LockResult<T>
+    where
+        T: Sized,
+    {
+        let data = self.data.into_inner();
+        poison::map_result(self.poison.borrow(), |()| data)
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error instead.
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        let data = self.data.get_mut();
+        poison::map_result(self.poison.borrow(), |()| data)
+    }
+}
+
+impl<T> From<T> for SpinMutex<T> {
+    /// Creates a new spin mutex in an unlocked state ready for use.
+    /// This is equivalent to [`SpinMutex::new`].
+    fn from(t: T) -> Self {
+        SpinMutex::new(t)
+    }
+}
+
+impl<T: ?Sized + Default> Default for SpinMutex<T> {
+    /// Creates a `SpinMutex<T>`, with the `Default` value for T.
+    fn default() -> SpinMutex<T> {
+        SpinMutex::new(Default::default())
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for SpinMutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("SpinMutex");
+        match self.try_lock() {
+            Ok(guard) => {
+                d.field("data", &&*guard);
+            }
+            Err(TryLockError::Poisoned(err)) => {
+                d.field("data", &&**err.get_ref());
+            }
+            Err(TryLockError::WouldBlock) => {
+                struct LockedPlaceholder;
+                impl fmt::Debug for LockedPlaceholder {
+                    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        f.write_str("<locked>")
+                    }
+                }
+                d.field("data", &LockedPlaceholder);
+            }
+        }
+        d.field("poisoned", &self.poison.get());
+        d.finish_non_exhaustive()
+    }
+}
+
+impl<'mutex, T: ?Sized> SpinMutexGuard<'mutex, T> {
+    unsafe fn new(lock: &'mutex SpinMutex<T>) -> LockResult<SpinMutexGuard<'mutex, T>> {
+        poison::map_result(lock.poison.guard(), |guard| SpinMutexGuard { lock, poison: guard })
+    }
+}
+
+impl<T: ?Sized> Deref for SpinMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for SpinMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for SpinMutexGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.poison.done(&self.poison);
+        self.lock.locked.store(false, Ordering::Release);
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for SpinMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for SpinMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
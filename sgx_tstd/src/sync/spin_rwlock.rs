@@ -0,0 +1,372 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+use crate::cell::UnsafeCell;
+use crate::fmt;
+use crate::ops::{Deref, DerefMut};
+use crate::sync::atomic::{AtomicUsize, Ordering};
+use crate::sync::spin_mutex::Backoff;
+use crate::sync::{poison, LockResult, TryLockError, TryLockResult};
+
+// The lowest bit of the state is set while a writer holds the lock, the
+// remaining bits count the readers.
+const WRITER: usize = 1;
+const READER: usize = 2;
+
+/// A reader-writer lock that busy-waits instead of blocking.
+///
+/// This is the spinning counterpart of [`SgxRwLock`], as [`SpinMutex`] is of
+/// [`SgxMutex`]: waiting threads spin inside the enclave, backing off
+/// exponentially, rather than sleeping through an OCALL. It suits data that
+/// is read often under short critical sections, such as a lookup table.
+///
+/// The lock does not prioritize writers. A writer spins until no reader
+/// holds the lock, so a steady stream of readers can keep it waiting.
+///
+/// The API mirrors [`SgxRwLock`]. The lock is poisoned only when a writer
+/// panics while holding it.
+///
+/// [`SgxRwLock`]: crate::sync::SgxRwLock
+/// [`SpinMutex`]: crate::sync::SpinMutex
+/// [`SgxMutex`]: crate::sync::SgxMutex
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::SpinRwLock;
+///
+/// let lock = SpinRwLock::new(5);
+/// {
+///     let r1 = lock.read().unwrap();
+///     let r2 = lock.read().unwrap();
+///     assert_eq!(*r1 + *r2, 10);
+/// }
+/// *lock.write().unwrap() += 1;
+/// assert_eq!(*lock.read().unwrap(), 6);
+/// ```
+pub struct SpinRwLock<T: ?Sized> {
+    state: AtomicUsize,
+    poison: poison::Flag,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for SpinRwLock<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for SpinRwLock<T> {}
+
+/// RAII structure used to release the shared read access of a
+/// [`SpinRwLock`] when dropped.
+///
+/// This structure is created by the [`read`] and [`try_read`] methods on
+/// [`SpinRwLock`].
+///
+/// [`read`]: SpinRwLock::read
+/// [`try_read`]: SpinRwLock::try_read
+#[must_use = "if unused the SpinRwLock will immediately unlock"]
+#[clippy::has_significant_drop]
+pub struct SpinRwLockReadGuard<'a, T: ?Sized + 'a> {
+    lock: &'a SpinRwLock<T>,
+}
+
+impl<T: ?Sized> !Send for SpinRwLockReadGuard<'_, T> {}
+unsafe impl<T: ?Sized + Sync> Sync for SpinRwLockReadGuard<'_, T> {}
+
+/// RAII structure used to release the exclusive write access of a
+/// [`SpinRwLock`] when dropped.
+///
+/// This structure is created by the [`write`] and [`try_write`] methods on
+/// [`SpinRwLock`].
+///
+/// [`write`]: SpinRwLock::write
+/// [`try_write`]: SpinRwLock::try_write
+#[must_use = "if unused the SpinRwLock will immediately unlock"]
+#[clippy::has_significant_drop]
+pub struct SpinRwLockWriteGuard<'a, T: ?Sized + 'a> {
+    lock: &'a SpinRwLock<T>,
+    poison: poison::Guard,
+}
+
+impl<T: ?Sized> !Send for SpinRwLockWriteGuard<'_, T> {}
+unsafe impl<T: ?Sized + Sync> Sync for SpinRwLockWriteGuard<'_, T> {}
+
+impl<T> SpinRwLock<T> {
+    /// Creates a new instance of a `SpinRwLock<T>` which is unlocked.
+    #[inline]
+    pub const fn new(t: T) -> SpinRwLock<T> {
+        SpinRwLock {
+            state: AtomicUsize::new(0),
+            poison: poison::Flag::new(),
+            data: UnsafeCell::new(t),
+        }
+    }
+}
+
+impl<T: ?Sized> SpinRwLock<T> {
+    /// Locks this lock with shared read access, spinning until no writer
+    /// holds it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the lock is poisoned. The
+    /// failure will occur immediately after the lock has been acquired.
+    ///
+    /// # Panics
+    ///
+    /// Taking a read lock in the thread which holds the write lock spins
+    /// forever.
+    pub fn read(&self) -> LockResult<SpinRwLockReadGuard<'_, T>> {
+        let mut backoff = Backoff::new();
+        loop {
+            let state = self.state.load(Ordering::Relaxed);
+            if state & WRITER == 0
+                && self
+                    .state
+                    .compare_exchange_weak(state, state + READER, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
+                break;
+            }
+            backoff.spin();
+        }
+        unsafe { SpinRwLockReadGuard::new(self) }
+    }
+
+    /// Attempts to acquire this lock with shared read access.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the [`Poisoned`] error if the lock is
+    /// poisoned and would otherwise be acquired.
+    ///
+    /// This function will return the [`WouldBlock`] error if a writer holds
+    /// the lock.
+    ///
+    /// [`Poisoned`]: TryLockError::Poisoned
+    /// [`WouldBlock`]: TryLockError::WouldBlock
+    pub fn try_read(&self) -> TryLockResult<SpinRwLockReadGuard<'_, T>> {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            if state & WRITER != 0 {
+                return Err(TryLockError::WouldBlock);
+            }
+            // Only another reader can have changed the state, retry.
+            match self.state.compare_exchange_weak(
+                state,
+                state + READER,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return unsafe { Ok(SpinRwLockReadGuard::new(self)?) },
+                Err(current) => state = current,
+            }
+        }
+    }
+
+    /// Locks this lock with exclusive write access, spinning until no
+    /// other writer or reader holds it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the lock is poisoned. An
+    /// error will be returned when the lock is acquired.
+    ///
+    /// # Panics
+    ///
+    /// Taking the write lock in the thread which already holds the lock
+    /// spins forever.
+    pub fn write(&self) -> LockResult<SpinRwLockWriteGuard<'_, T>> {
+        let mut backoff = Backoff::new();
+        while self
+            .state
+            .compare_exchange_weak(0, WRITER, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            while self.state.load(Ordering::Relaxed) != 0 {
+                backoff.spin();
+            }
+        }
+        unsafe { SpinRwLockWriteGuard::new(self) }
+    }
+
+    /// Attempts to lock this lock with exclusive write access.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the [`Poisoned`] error if the lock is
+    /// poisoned and would otherwise be acquired.
+    ///
+    /// This function will return the [`WouldBlock`] error if the lock is
+    /// held by a reader or a writer.
+    ///
+    /// [`Poisoned`]: TryLockError::Poisoned
+    /// [`WouldBlock`]: TryLockError::WouldBlock
+    pub fn try_write(&self) -> TryLockResult<SpinRwLockWriteGuard<'_, T>> {
+        match self
+            .state
+            .compare_exchange(0, WRITER, Ordering::Acquire, Ordering::Relaxed)
+        {
+            Ok(_) => unsafe { Ok(SpinRwLockWriteGuard::new(self)?) },
+            Err(_) => Err(TryLockError::WouldBlock),
+        }
+    }
+
+    /// Determines whether the lock is poisoned.
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.poison.get()
+    }
+
+    /// Clears the poisoned state from the lock.
+    #[inline]
+    pub fn clear_poison(&self) {
+        self.poison.clear();
+    }
+
+    /// Consumes this `SpinRwLock`, returning the underlying data.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the lock is poisoned.
+    pub fn into_inner(self) -> LockResult<T>
+    where
+        T: Sized,
+    {
+        let data = self.data.into_inner();
+        poison::map_result(self.poison.borrow(), |()| data)
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the lock is poisoned.
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        let data = self.data.get_mut();
+        poison::map_result(self.poison.borrow(), |()| data)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for SpinRwLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("SpinRwLock");
+        match self.try_read() {
+            Ok(guard) => {
+                d.field("data", &&*guard);
+            }
+            Err(TryLockError::Poisoned(err)) => {
+                d.field("data", &&**err.get_ref());
+            }
+            Err(TryLockError::WouldBlock) => {
+                struct LockedPlaceholder;
+                impl fmt::Debug for LockedPlaceholder {
+                    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        f.write_str("<locked>")
+                    }
+                }
+                d.field("data", &LockedPlaceholder);
+            }
+        }
+        d.field("poisoned", &self.poison.get());
+        d.finish_non_exhaustive()
+    }
+}
+
+impl<T: Default> Default for SpinRwLock<T> {
+    /// Creates a new `SpinRwLock<T>`, with the `Default` value for T.
+    fn default() -> SpinRwLock<T> {
+        SpinRwLock::new(Default::default())
+    }
+}
+
+impl<T> From<T> for SpinRwLock<T> {
+    /// Creates a new instance of a `SpinRwLock<T>` which is unlocked.
+    /// This is equivalent to [`SpinRwLock::new`].
+    fn from(t: T) -> Self {
+        SpinRwLock::new(t)
+    }
+}
+
+impl<'rwlock, T: ?Sized> SpinRwLockReadGuard<'rwlock, T> {
+    unsafe fn new(lock: &'rwlock SpinRwLock<T>) -> LockResult<SpinRwLockReadGuard<'rwlock, T>> {
+        poison::map_result(lock.poison.borrow(), |()| SpinRwLockReadGuard { lock })
+    }
+}
+
+impl<'rwlock, T: ?Sized> SpinRwLockWriteGuard<'rwlock, T> {
+    unsafe fn new(lock: &'rwlock SpinRwLock<T>) -> LockResult<SpinRwLockWriteGuard<'rwlock, T>> {
+        poison::map_result(lock.poison.guard(), |guard| SpinRwLockWriteGuard { lock, poison: guard })
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for SpinRwLockReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for SpinRwLockReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for SpinRwLockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for SpinRwLockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: ?Sized> Deref for SpinRwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Deref for SpinRwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for SpinRwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for SpinRwLockReadGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.state.fetch_sub(READER, Ordering::Release);
+    }
+}
+
+impl<T: ?Sized> Drop for SpinRwLockWriteGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.poison.done(&self.poison);
+        self.lock.state.store(0, Ordering::Release);
+    }
+}
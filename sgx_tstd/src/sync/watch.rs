@@ -0,0 +1,225 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! Single-producer, multi-consumer channel that only keeps the latest value.
+//!
+//! A watch channel broadcasts state changes, such as a new configuration or
+//! a new attestation status, to any number of threads. The [`Sender`] stores
+//! a value and wakes every [`Receiver`]; each receiver then sees the value
+//! that is current when it looks, and skips any values sent in between.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::watch;
+//! use std::thread;
+//!
+//! let (tx, mut rx) = watch::channel(0);
+//! let handle = thread::spawn(move || rx.recv().unwrap());
+//! tx.send(1).unwrap();
+//! assert_eq!(handle.join().unwrap(), 1);
+//! ```
+
+use crate::error;
+use crate::fmt;
+use crate::sync::{Arc, SgxCondvar as Condvar, SgxMutex as Mutex};
+
+/// Creates a watch channel holding `initial`.
+///
+/// The receiver starts out having seen `initial`, so its first
+/// [`recv`](Receiver::recv) waits for the next [`send`](Sender::send).
+#[must_use]
+pub fn channel<T: Clone>(initial: T) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            value: initial,
+            version: 0,
+            receivers: 1,
+            closed: false,
+        }),
+        changed: Condvar::new(),
+    });
+    let receiver = Receiver { shared: shared.clone(), seen: 0 };
+    (Sender { shared }, receiver)
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    changed: Condvar,
+}
+
+struct State<T> {
+    value: T,
+    // Bumped by every send, so receivers can tell whether they are behind.
+    version: u64,
+    receivers: usize,
+    // Set when the sender is dropped.
+    closed: bool,
+}
+
+/// The sending half of a watch channel.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// The receiving half of a watch channel.
+///
+/// Clones start out having seen what the original has seen.
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    seen: u64,
+}
+
+/// An error returned from [`Sender::send`] when every receiver has been
+/// dropped. It contains the value that could not be sent.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct SendError<T>(pub T);
+
+/// An error returned from [`Receiver::recv`] when the sender has been
+/// dropped and the receiver has seen its last value.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct RecvError;
+
+impl<T: Clone> Sender<T> {
+    /// Replaces the value of the channel and wakes all receivers.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value back if there are no receivers left.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.receivers == 0 {
+            return Err(SendError(value));
+        }
+        state.value = value;
+        state.version = state.version.wrapping_add(1);
+        self.shared.changed.notify_all();
+        Ok(())
+    }
+
+    /// Returns the number of receivers still connected.
+    pub fn receiver_count(&self) -> usize {
+        self.shared.state.lock().unwrap().receivers
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        // a panic while the lock was held must not abort in the destructor
+        let mut state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.closed = true;
+        self.shared.changed.notify_all();
+    }
+}
+
+impl<T: Clone> Receiver<T> {
+    /// Blocks until a value this receiver has not seen yet is sent, and
+    /// returns it.
+    ///
+    /// If several values were sent in the meantime, only the latest one is
+    /// returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RecvError`] once the sender has been dropped and its last
+    /// value has already been seen.
+    pub fn recv(&mut self) -> Result<T, RecvError> {
+        let mut state = self.shared.state.lock().unwrap();
+        while state.version == self.seen {
+            if state.closed {
+                return Err(RecvError);
+            }
+            state = self.shared.changed.wait(state).unwrap();
+        }
+        self.seen = state.version;
+        Ok(state.value.clone())
+    }
+
+    /// Returns the latest value if this receiver has not seen it yet,
+    /// without blocking.
+    pub fn try_recv(&mut self) -> Option<T> {
+        let state = self.shared.state.lock().unwrap();
+        if state.version == self.seen {
+            return None;
+        }
+        self.seen = state.version;
+        Some(state.value.clone())
+    }
+
+    /// Returns the latest value, whether or not it has been seen, without
+    /// marking it as seen.
+    pub fn current(&self) -> T {
+        self.shared.state.lock().unwrap().value.clone()
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Receiver<T> {
+        self.shared.state.lock().unwrap().receivers += 1;
+        Receiver { shared: self.shared.clone(), seen: self.seen }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap_or_else(|e| e.into_inner()).receivers -= 1;
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "sending on a closed channel".fmt(f)
+    }
+}
+
+impl<T: Send> error::Error for SendError<T> {
+    #[allow(deprecated)]
+    fn description(&self) -> &str {
+        "sending on a closed channel"
+    }
+}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "receiving on a closed channel".fmt(f)
+    }
+}
+
+impl error::Error for RecvError {
+    #[allow(deprecated)]
+    fn description(&self) -> &str {
+        "receiving on a closed channel"
+    }
+}
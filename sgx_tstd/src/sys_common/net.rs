@@ -209,6 +209,11 @@ impl TcpStream {
         Ok(TcpStream { inner: sock })
     }
 
+    pub fn new_pair() -> io::Result<(TcpStream, TcpStream)> {
+        let (a, b) = Socket::new_pair(c::AF_UNIX, c::SOCK_STREAM)?;
+        Ok((TcpStream { inner: a }, TcpStream { inner: b }))
+    }
+
     pub fn connect(addr: io::Result<&SocketAddr>) -> io::Result<TcpStream> {
         let addr = addr?;
 
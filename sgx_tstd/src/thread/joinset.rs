@@ -0,0 +1,166 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use super::{spawn, JoinHandle, Result};
+use crate::collections::HashMap;
+use crate::fmt;
+use crate::sync::mpsc::{channel, Receiver, Sender};
+use crate::vec::Vec;
+
+/// A collection of threads spawned and joined as a group.
+///
+/// Threads are joined in the order in which they finish, not in the order
+/// in which they were spawned. All threads still in the set when it is
+/// dropped are joined, so dropping a `JoinSet` blocks until every thread
+/// has finished.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread::JoinSet;
+///
+/// let mut set = JoinSet::new();
+/// set.spawn(|| 1).spawn(|| 2);
+///
+/// let mut sum = 0;
+/// while let Some(res) = set.join_next() {
+///     sum += res.unwrap();
+/// }
+/// assert_eq!(sum, 3);
+/// ```
+pub struct JoinSet<T> {
+    handles: HashMap<usize, JoinHandle<T>>,
+    next_id: usize,
+    tx: Sender<usize>,
+    rx: Receiver<usize>,
+}
+
+// Reports the id of the thread to the set once the thread's main function
+// has returned or unwound.
+struct Notify {
+    id: usize,
+    tx: Sender<usize>,
+}
+
+impl Drop for Notify {
+    fn drop(&mut self) {
+        // The receiver lives as long as the set, and the set joins every
+        // thread before it goes away.
+        let _ = self.tx.send(self.id);
+    }
+}
+
+impl<T: Send + 'static> JoinSet<T> {
+    /// Creates an empty `JoinSet`.
+    pub fn new() -> JoinSet<T> {
+        let (tx, rx) = channel();
+        JoinSet {
+            handles: HashMap::new(),
+            next_id: 0,
+            tx,
+            rx,
+        }
+    }
+
+    /// Spawns a new thread running `f` and adds it to the set.
+    ///
+    /// Returns the set itself, so that calls can be chained.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the thread cannot be created, see [`spawn`].
+    pub fn spawn<F>(&mut self, f: F) -> &mut JoinSet<T>
+    where
+        F: FnOnce() -> T,
+        F: Send + 'static,
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let notify = Notify {
+            id,
+            tx: self.tx.clone(),
+        };
+        let handle = spawn(move || {
+            let _notify = notify;
+            f()
+        });
+        self.handles.insert(id, handle);
+        self
+    }
+
+    /// Waits for the next thread in the set to finish and returns its
+    /// result.
+    ///
+    /// Returns `None` if the set is empty. If the thread panicked, [`Err`]
+    /// is returned with the panic payload, as with [`JoinHandle::join`].
+    ///
+    /// [`Err`]: crate::result::Result::Err
+    pub fn join_next(&mut self) -> Option<Result<T>> {
+        if self.handles.is_empty() {
+            return None;
+        }
+        loop {
+            let id = self.rx.recv().expect("JoinSet channel disconnected");
+            if let Some(handle) = self.handles.remove(&id) {
+                return Some(handle.join());
+            }
+        }
+    }
+
+    /// Joins every thread in the set, returning the results in the order
+    /// in which the threads finished.
+    pub fn join_all(&mut self) -> Vec<Result<T>> {
+        let mut results = Vec::with_capacity(self.handles.len());
+        while let Some(res) = self.join_next() {
+            results.push(res);
+        }
+        results
+    }
+
+    /// Returns the number of threads in the set which have not been joined.
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Returns `true` if the set contains no threads.
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+}
+
+impl<T: Send + 'static> Default for JoinSet<T> {
+    fn default() -> JoinSet<T> {
+        JoinSet::new()
+    }
+}
+
+impl<T> Drop for JoinSet<T> {
+    fn drop(&mut self) {
+        for (_, handle) in self.handles.drain() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl<T> fmt::Debug for JoinSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinSet")
+            .field("len", &self.handles.len())
+            .finish_non_exhaustive()
+    }
+}
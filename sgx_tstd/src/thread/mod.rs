@@ -59,9 +59,13 @@ pub use sgx_trts::enclave::SgxThreadPolicy;
 #[macro_use]
 mod local;
 
+#[cfg(feature = "thread")]
+mod joinset;
 #[cfg(feature = "thread")]
 mod scoped;
 
+#[cfg(feature = "thread")]
+pub use joinset::JoinSet;
 #[cfg(feature = "thread")]
 pub use scoped::{scope, Scope, ScopedJoinHandle};
 
@@ -67,6 +67,40 @@
 //! In this way, `vec[0]` would panic. But `should_panic!` catches it. Thus
 //! `foo_panic` would pass the unit test.
 //!
+//! To run one test function with several inputs, give it arguments and
+//! list it with its argument sets through `rsgx_parameterized_tests!`:
+//!
+//! ```
+//! rsgx_unit_tests!(foo, rsgx_parameterized_tests!(bar, [1, "a"], [2, "b"]));
+//! ```
+//!
+//! With the `thread` feature, a test that hangs need not hang the whole
+//! run. Given a timeout in milliseconds, `rsgx_unit_tests!` runs every
+//! test in a thread of its own and reports a test that exceeds the
+//! timeout as timed out:
+//!
+//! ```
+//! rsgx_unit_tests!(timeout_ms = 5000, foo, bar, zoo);
+//! ```
+//!
+//! SGX offers no way to interrupt another enclave thread, so a timed out
+//! test is not stopped. It keeps running in the background and occupies
+//! a TCS until it returns. A test that may run long should call
+//! `rsgx_unit_test_check_timeout` in its loops. The call panics once the
+//! test has timed out, which unwinds the test and runs its destructors.
+//!
+//! The enclave then needs:
+//!
+//! * `TCSNum` of at least 2 in the enclave config: one TCS for the thread
+//!   running `rsgx_unit_tests!` and one for the test thread. Add one more
+//!   for every test that may time out without calling
+//!   `rsgx_unit_test_check_timeout`. If no TCS is left, a test fails
+//!   without running.
+//! * `sgx_thread.edl` and `sgx_time.edl` imported in its EDL, for
+//!   creating threads and measuring the timeout. The timeout is measured
+//!   with the untrusted clock of the host.
+//! * The `thread` feature of sgx_tstd, which this feature enables.
+//!
 
 #![cfg_attr(not(target_env = "sgx"), no_std)]
 #![cfg_attr(
@@ -78,7 +112,21 @@
 #[macro_use]
 extern crate sgx_tstd as std;
 
+#[cfg(feature = "thread")]
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::panic::UnwindSafe;
 use std::string::String;
+#[cfg(feature = "thread")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "thread")]
+use std::sync::mpsc::{self, RecvTimeoutError};
+#[cfg(feature = "thread")]
+use std::sync::Arc;
+#[cfg(feature = "thread")]
+use std::thread;
+#[cfg(feature = "thread")]
+use std::time::Duration;
 use std::vec::Vec;
 
 /// This macro implements the fail test.
@@ -121,6 +169,14 @@ macro_rules! should_panic {
 /// case function as arguments and then execute them sequentially. It prints
 /// the statistics on the test result at the end, and returns the amount of
 /// failed tests. meaning if everything works the return vlaue will be 0.
+/// Test functions taking arguments can be listed through
+/// `rsgx_parameterized_tests!`.
+///
+/// With the `thread` feature, `timeout_ms = <milliseconds>` may precede the
+/// test cases. Each test then runs in a thread of its own, and one that
+/// does not finish in time is reported as timed out. The amount returned
+/// includes the timed out tests. See the crate documentation for the
+/// requirements on the enclave.
 ///
 /// One test fails if and only if it panics. For fail test (similar to
 /// `#[should_panic]` in Rust, one should wrap the line which would panic with
@@ -144,6 +200,19 @@ macro_rules! should_panic {
 /// ```
 #[macro_export]
 macro_rules! rsgx_unit_tests {
+    (
+        timeout_ms = $timeout : expr, $($f : expr),* $(,)?
+    ) => {
+        {
+            rsgx_unit_test_start();
+            let mut ntestcases: u64 = 0u64;
+            let mut failurecases: ::std::vec::Vec<String> = Vec::new();
+            let mut timeoutcases: ::std::vec::Vec<String> = Vec::new();
+            let timeout = ::std::time::Duration::from_millis($timeout);
+            $($crate::TimedUnitTest::run_with_timeout($f, &mut ntestcases, &mut failurecases, &mut timeoutcases, stringify!($f), timeout);)*
+            rsgx_unit_test_end_with_timeouts(ntestcases, failurecases, timeoutcases)
+        }
+    };
     (
         $($f : expr),* $(,)?
     ) => {
@@ -151,12 +220,187 @@ macro_rules! rsgx_unit_tests {
             rsgx_unit_test_start();
             let mut ntestcases: u64 = 0u64;
             let mut failurecases: ::std::vec::Vec<String> = Vec::new();
-            $(rsgx_unit_test(&mut ntestcases, &mut failurecases, $f,stringify!($f));)*
+            $($crate::UnitTest::run($f, &mut ntestcases, &mut failurecases, stringify!($f));)*
             rsgx_unit_test_end(ntestcases, failurecases)
         }
     }
 }
 
+/// This macro declares a parameterized test case.
+///
+/// `rsgx_parameterized_tests!` takes a test function followed by a list of
+/// argument sets, each in brackets. Listed in `rsgx_unit_tests!`, it calls
+/// the function once with each set. Every set counts as one test in the
+/// statistics and is reported by the function name and the argument
+/// values, so the arguments must implement `Debug`.
+///
+/// ```
+/// fn sum(a: u32, b: u32, c: u32) {
+///     assert_eq!(a + b, c);
+/// }
+///
+/// rsgx_unit_tests!(
+///     foo,
+///     rsgx_parameterized_tests!(sum, [1, 2, 3], [2, 2, 4]),
+/// );
+/// ```
+///
+/// This runs three tests: `foo`, `sum(1, 2, 3)` and `sum(2, 2, 4)`.
+#[macro_export]
+macro_rules! rsgx_parameterized_tests {
+    (
+        $f : expr, $([$($arg : expr),* $(,)?]),+ $(,)?
+    ) => {
+        {
+            let mut sets = ::std::vec::Vec::new();
+            $(sets.push(($($arg,)*));)+
+            $crate::ParameterizedTests::new($f, stringify!($f), sets)
+        }
+    }
+}
+
+/// A test case that `rsgx_unit_tests!` can run: a function taking no
+/// arguments, or the argument sets of `rsgx_parameterized_tests!`.
+pub trait UnitTest {
+    fn run(self, ncases: &mut u64, failurecases: &mut Vec<String>, name: &str);
+}
+
+impl<F, R> UnitTest for F
+where
+    F: FnOnce() -> R + UnwindSafe,
+{
+    fn run(self, ncases: &mut u64, failurecases: &mut Vec<String>, name: &str) {
+        rsgx_unit_test(ncases, failurecases, self, name)
+    }
+}
+
+/// A test function and the argument sets to call it with, built by
+/// `rsgx_parameterized_tests!`.
+#[must_use]
+pub struct ParameterizedTests<F, A> {
+    f: F,
+    name: &'static str,
+    sets: Vec<A>,
+}
+
+impl<F, A> ParameterizedTests<F, A> {
+    pub fn new(f: F, name: &'static str, sets: Vec<A>) -> ParameterizedTests<F, A> {
+        ParameterizedTests { f, name, sets }
+    }
+}
+
+impl<F, A> UnitTest for ParameterizedTests<F, A>
+where
+    F: Copy + UnwindSafe,
+    A: TestArgs<F> + UnwindSafe,
+{
+    fn run(self, ncases: &mut u64, failurecases: &mut Vec<String>, _name: &str) {
+        let f = self.f;
+        for args in self.sets {
+            let name = format!("{}({})", self.name, args.describe());
+            rsgx_unit_test(ncases, failurecases, move || args.call(f), &name);
+        }
+    }
+}
+
+/// An argument set of a parameterized test: a tuple of the arguments of
+/// `F`, each of which implements `Debug`.
+pub trait TestArgs<F> {
+    fn call(self, f: F);
+    fn describe(&self) -> String;
+}
+
+macro_rules! impl_test_args {
+    ($($arg : ident),+) => {
+        impl<F, R, $($arg),+> TestArgs<F> for ($($arg,)+)
+        where
+            F: FnOnce($($arg),+) -> R,
+            $($arg: Debug),+
+        {
+            #[allow(non_snake_case)]
+            fn call(self, f: F) {
+                let ($($arg,)+) = self;
+                f($($arg),+);
+            }
+
+            #[allow(non_snake_case)]
+            fn describe(&self) -> String {
+                let ($($arg,)+) = self;
+                let mut args = Vec::new();
+                $(args.push(format!("{:?}", $arg));)+
+                args.join(", ")
+            }
+        }
+    };
+}
+
+impl_test_args!(A1);
+impl_test_args!(A1, A2);
+impl_test_args!(A1, A2, A3);
+impl_test_args!(A1, A2, A3, A4);
+impl_test_args!(A1, A2, A3, A4, A5);
+impl_test_args!(A1, A2, A3, A4, A5, A6);
+
+/// A test case that `rsgx_unit_tests!` can run in a thread of its own,
+/// under a timeout.
+#[cfg(feature = "thread")]
+pub trait TimedUnitTest {
+    fn run_with_timeout(
+        self,
+        ncases: &mut u64,
+        failurecases: &mut Vec<String>,
+        timeoutcases: &mut Vec<String>,
+        name: &str,
+        timeout: Duration,
+    );
+}
+
+#[cfg(feature = "thread")]
+impl<F, R> TimedUnitTest for F
+where
+    F: FnOnce() -> R + UnwindSafe + Send + 'static,
+{
+    fn run_with_timeout(
+        self,
+        ncases: &mut u64,
+        failurecases: &mut Vec<String>,
+        timeoutcases: &mut Vec<String>,
+        name: &str,
+        timeout: Duration,
+    ) {
+        rsgx_unit_test_timeout(ncases, failurecases, timeoutcases, self, name, timeout)
+    }
+}
+
+#[cfg(feature = "thread")]
+impl<F, A> TimedUnitTest for ParameterizedTests<F, A>
+where
+    F: Copy + UnwindSafe + Send + 'static,
+    A: TestArgs<F> + UnwindSafe + Send + 'static,
+{
+    fn run_with_timeout(
+        self,
+        ncases: &mut u64,
+        failurecases: &mut Vec<String>,
+        timeoutcases: &mut Vec<String>,
+        _name: &str,
+        timeout: Duration,
+    ) {
+        let f = self.f;
+        for args in self.sets {
+            let name = format!("{}({})", self.name, args.describe());
+            rsgx_unit_test_timeout(
+                ncases,
+                failurecases,
+                timeoutcases,
+                move || args.call(f),
+                &name,
+                timeout,
+            );
+        }
+    }
+}
+
 /// A prologue function for Rust SGX unit testing.
 ///
 /// To initiate the test environment, `rsgx_unit_tests!` macro would trigger
@@ -172,8 +416,21 @@ pub fn rsgx_unit_test_start() {
 /// a list of failed tests and the statistics.
 /// It will return the amount of failed tests. (success == 0)
 pub fn rsgx_unit_test_end(ntestcases: u64, failurecases: Vec<String>) -> usize {
+    rsgx_unit_test_end_with_timeouts(ntestcases, failurecases, Vec::new())
+}
+
+/// An epilogue function for Rust SGX unit testing with a timeout.
+///
+/// Like `rsgx_unit_test_end`, but also lists the timed out tests and
+/// counts them apart from the failed ones. It returns the amount of
+/// failed and timed out tests.
+pub fn rsgx_unit_test_end_with_timeouts(
+    ntestcases: u64,
+    failurecases: Vec<String>,
+    timeoutcases: Vec<String>,
+) -> usize {
     let ntotal = ntestcases as usize;
-    let nsucc = ntestcases as usize - failurecases.len();
+    let nsucc = ntestcases as usize - failurecases.len() - timeoutcases.len();
 
     if !failurecases.is_empty() {
         print!("\nfailures: ");
@@ -185,19 +442,39 @@ pub fn rsgx_unit_test_end(ntestcases: u64, failurecases: Vec<String>) -> usize {
         );
     }
 
+    if !timeoutcases.is_empty() {
+        print!("\ntimeouts: ");
+        println!(
+            "    {}",
+            timeoutcases
+                .iter()
+                .fold(String::new(), |s, per| s + "\n    " + per)
+        );
+    }
+
     if ntotal == nsucc {
         print!("\ntest result \x1B[1;32mok\x1B[0m. ");
     } else {
         print!("\ntest result \x1B[1;31mFAILED\x1B[0m. ");
     }
 
-    println!(
-        "{} tested, {} passed, {} failed",
-        ntotal,
-        nsucc,
-        ntotal - nsucc
-    );
-    failurecases.len()
+    if timeoutcases.is_empty() {
+        println!(
+            "{} tested, {} passed, {} failed",
+            ntotal,
+            nsucc,
+            ntotal - nsucc
+        );
+    } else {
+        println!(
+            "{} tested, {} passed, {} failed, {} timed out",
+            ntotal,
+            nsucc,
+            failurecases.len(),
+            timeoutcases.len()
+        );
+    }
+    failurecases.len() + timeoutcases.len()
 }
 
 /// Perform one test case at a time.
@@ -227,3 +504,75 @@ where
         }
     }
 }
+
+#[cfg(feature = "thread")]
+thread_local! {
+    // set by rsgx_unit_test_timeout when the test on this thread times out
+    static TIMED_OUT: RefCell<Option<Arc<AtomicBool>>> = const { RefCell::new(None) };
+}
+
+/// Perform one test case in a thread of its own, under a timeout.
+///
+/// Like `rsgx_unit_test`, but the test runs in a new thread. If it does not
+/// finish within `timeout`, the test is recorded in `timeoutcases` and left
+/// running; from then on `rsgx_unit_test_check_timeout` panics in it. If
+/// no thread can be created, the test fails.
+#[cfg(feature = "thread")]
+#[allow(clippy::print_literal)]
+pub fn rsgx_unit_test_timeout<F, R>(
+    ncases: &mut u64,
+    failurecases: &mut Vec<String>,
+    timeoutcases: &mut Vec<String>,
+    f: F,
+    name: &str,
+    timeout: Duration,
+) where
+    F: FnOnce() -> R + UnwindSafe + Send + 'static,
+{
+    *ncases += 1;
+    let timed_out = Arc::new(AtomicBool::new(false));
+    let flag = timed_out.clone();
+    let (tx, rx) = mpsc::channel();
+    let spawned = thread::Builder::new().spawn(move || {
+        TIMED_OUT.with(|t| *t.borrow_mut() = Some(flag));
+        let passed = std::panic::catch_unwind(|| {
+            f();
+        })
+        .is_ok();
+        let _ = tx.send(passed);
+    });
+
+    let result = match spawned {
+        Ok(_) => rx.recv_timeout(timeout),
+        Err(_) => Err(RecvTimeoutError::Disconnected),
+    };
+    match result {
+        Ok(true) => {
+            println!("{} {} ... {}!", "testing", name, "\x1B[1;32mok\x1B[0m");
+        }
+        Err(RecvTimeoutError::Timeout) => {
+            timed_out.store(true, Ordering::Release);
+            println!("{} {} ... {}!", "testing", name, "\x1B[1;33mtimeout\x1B[0m");
+            timeoutcases.push(String::from(name));
+        }
+        Ok(false) | Err(RecvTimeoutError::Disconnected) => {
+            println!("{} {} ... {}!", "testing", name, "\x1B[1;31mfailed\x1B[0m");
+            failurecases.push(String::from(name));
+        }
+    }
+}
+
+/// Stop the current test if it has timed out.
+///
+/// Panics if the current thread runs a test that `rsgx_unit_tests!` has
+/// already reported as timed out, so that the test unwinds and its
+/// destructors run. Does nothing otherwise, including outside a test run
+/// with a timeout.
+#[cfg(feature = "thread")]
+pub fn rsgx_unit_test_check_timeout() {
+    let timed_out = TIMED_OUT
+        .with(|t| matches!(t.borrow().as_ref(), Some(flag) if flag.load(Ordering::Acquire)));
+    if timed_out {
+        panic!("test timed out");
+    }
+}
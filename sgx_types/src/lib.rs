@@ -75,6 +75,9 @@ pub use self::error::*;
 mod function;
 pub use self::function::*;
 
+mod quote;
+pub use self::quote::*;
+
 pub mod cpu_feature;
 pub mod marker;
 pub mod metadata;
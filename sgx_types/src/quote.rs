@@ -0,0 +1,326 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+use crate::*;
+use core::error::Error;
+use core::fmt;
+use core::mem;
+use core::ptr;
+
+const QUOTE_VERSION_4: uint16_t = 4;
+
+const HEADER_SIZE: usize = mem::size_of::<sgx_quote4_header_t>();
+const REPORT_BODY_SIZE: usize = mem::size_of::<sgx_report2_body_t>();
+const QE_REPORT_SIZE: usize = mem::size_of::<sgx_report_body_t>();
+const SIGNATURE_SIZE: usize = 64;
+
+const _: () = assert!(mem::size_of::<sgx_quote4_t>() == HEADER_SIZE + REPORT_BODY_SIZE + 4);
+const _: () = assert!(mem::size_of::<TdReport>() == TDX_REPORT_SIZE);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuoteParseError {
+    /* the quote ends before a structure it announces */
+    TooShort,
+    /* the quote has bytes past the structures it announces */
+    TrailingData,
+    UnsupportedVersion(uint16_t),
+    UnsupportedAttestationKeyType(uint16_t),
+    UnsupportedTeeType(uint32_t),
+    /* the signature data does not carry QE report certification data */
+    InvalidCertificationData,
+}
+
+impl fmt::Display for QuoteParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            QuoteParseError::TooShort => f.write_str("quote is truncated"),
+            QuoteParseError::TrailingData => f.write_str("quote has trailing data"),
+            QuoteParseError::UnsupportedVersion(v) => write!(f, "unsupported quote version {}", v),
+            QuoteParseError::UnsupportedAttestationKeyType(t) => {
+                write!(f, "unsupported attestation key type {}", t)
+            }
+            QuoteParseError::UnsupportedTeeType(t) => write!(f, "unsupported TEE type {:#x}", t),
+            QuoteParseError::InvalidCertificationData => {
+                f.write_str("quote has no QE report certification data")
+            }
+        }
+    }
+}
+
+impl Error for QuoteParseError {}
+
+/// Certification data in a quote: a type (`sgx_ql_cert_key_type_t`) and the
+/// data of that type.
+#[derive(Clone, Copy, Debug)]
+pub struct QuoteCertificationData<'a> {
+    pub cert_type: uint16_t,
+    pub data: &'a [uint8_t],
+}
+
+/// A version 4 quote of a TDX trust domain, borrowing the quote bytes.
+///
+/// The layout is `sgx_quote4_t` with a TD report body, followed by the ECDSA
+/// signature data (`sgx_ecdsa_sig_data_v4_t`). Its certification data holds
+/// the QE report (`sgx_qe_report_certification_data_t`), which in turn ends
+/// with the certification data of the PCK, usually the certificate chain.
+#[derive(Clone, Copy)]
+pub struct Quote4<'a> {
+    header: sgx_quote4_header_t,
+    report_body: sgx_report2_body_t,
+    signed_data: &'a [uint8_t],
+    signature: &'a [uint8_t],
+    attestation_key: &'a [uint8_t],
+    qe_report_body: sgx_report_body_t,
+    qe_report: &'a [uint8_t],
+    qe_report_signature: &'a [uint8_t],
+    qe_auth_data: &'a [uint8_t],
+    certification_data: QuoteCertificationData<'a>,
+}
+
+impl<'a> Quote4<'a> {
+    /// Checks the layout of `bytes` and splits it into its parts.
+    ///
+    /// The version must be 4, the TEE type TDX and the attestation key
+    /// ECDSA-256-with-P-256. The signature data must be exactly as long as
+    /// the quote announces and every nested structure must fit in it. No
+    /// signature is checked.
+    pub fn parse(bytes: &'a [uint8_t]) -> Result<Quote4<'a>, QuoteParseError> {
+        if bytes.len() < mem::size_of::<sgx_quote4_t>() {
+            return Err(QuoteParseError::TooShort);
+        }
+        let mut reader = Reader(bytes);
+        let header: sgx_quote4_header_t = read_struct(reader.take(HEADER_SIZE)?);
+        if header.version != QUOTE_VERSION_4 {
+            return Err(QuoteParseError::UnsupportedVersion(header.version));
+        }
+        if header.tee_type != TEE_TYPE_TDX {
+            return Err(QuoteParseError::UnsupportedTeeType(header.tee_type));
+        }
+        if header.att_key_type != sgx_ql_attestation_algorithm_id_t::SGX_QL_ALG_ECDSA_P256 as uint16_t {
+            return Err(QuoteParseError::UnsupportedAttestationKeyType(header.att_key_type));
+        }
+        let report_body = read_struct(reader.take(REPORT_BODY_SIZE)?);
+        let signed_data = &bytes[..HEADER_SIZE + REPORT_BODY_SIZE];
+
+        let signature_data_len = reader.u32()? as usize;
+        let mut sig = Reader(reader.take(signature_data_len)?);
+        reader.finish()?;
+
+        let signature = sig.take(SIGNATURE_SIZE)?;
+        let attestation_key = sig.take(SIGNATURE_SIZE)?;
+        let outer = sig.certification_data()?;
+        sig.finish()?;
+        if outer.cert_type != sgx_ql_cert_key_type_t::ECDSA_SIG_AUX_DATA as uint16_t {
+            return Err(QuoteParseError::InvalidCertificationData);
+        }
+
+        let mut qe = Reader(outer.data);
+        let qe_report = qe.take(QE_REPORT_SIZE)?;
+        let qe_report_signature = qe.take(SIGNATURE_SIZE)?;
+        let qe_auth_size = qe.u16()? as usize;
+        let qe_auth_data = qe.take(qe_auth_size)?;
+        let certification_data = qe.certification_data()?;
+        qe.finish()?;
+
+        Ok(Quote4 {
+            header,
+            report_body,
+            signed_data,
+            signature,
+            attestation_key,
+            qe_report_body: read_struct(qe_report),
+            qe_report,
+            qe_report_signature,
+            qe_auth_data,
+            certification_data,
+        })
+    }
+
+    pub fn header(&self) -> &sgx_quote4_header_t {
+        &self.header
+    }
+
+    pub fn version(&self) -> uint16_t {
+        self.header.version
+    }
+
+    pub fn tee_type(&self) -> uint32_t {
+        self.header.tee_type
+    }
+
+    /// The TD report body: TCB SVN, TDX module and TD measurements, and the
+    /// report data chosen by the TD.
+    pub fn report_body(&self) -> &sgx_report2_body_t {
+        &self.report_body
+    }
+
+    pub fn tdx_module(&self) -> sgx_tdx_module_t {
+        sgx_tdx_module_t {
+            mr_seam: self.report_body.mr_seam,
+            mrsigner_seam: self.report_body.mrsigner_seam,
+            attributes: self.report_body.seam_attributes,
+        }
+    }
+
+    pub fn td_attributes(&self) -> sgx_tdx_attributes_t {
+        sgx_tdx_attributes_t::from(self.report_body.td_attributes)
+    }
+
+    /// The header and TD report body, which `signature` signs.
+    pub fn signed_data(&self) -> &'a [uint8_t] {
+        self.signed_data
+    }
+
+    /// The ECDSA signature over `signed_data`, r and s, big-endian.
+    pub fn signature(&self) -> &'a [uint8_t] {
+        self.signature
+    }
+
+    /// The attestation public key of the QE, x and y, big-endian.
+    pub fn attestation_key(&self) -> &'a [uint8_t] {
+        self.attestation_key
+    }
+
+    pub fn qe_report_body(&self) -> &sgx_report_body_t {
+        &self.qe_report_body
+    }
+
+    /// The raw QE report body, which `qe_report_signature` signs.
+    pub fn qe_report(&self) -> &'a [uint8_t] {
+        self.qe_report
+    }
+
+    /// The ECDSA signature of the PCK over `qe_report`.
+    pub fn qe_report_signature(&self) -> &'a [uint8_t] {
+        self.qe_report_signature
+    }
+
+    pub fn qe_auth_data(&self) -> &'a [uint8_t] {
+        self.qe_auth_data
+    }
+
+    /// The certification data of the PCK.
+    pub fn certification_data(&self) -> QuoteCertificationData<'a> {
+        self.certification_data
+    }
+
+    /// The PEM encoded PCK certificate chain, if the quote carries one.
+    pub fn pck_cert_chain(&self) -> Option<&'a [uint8_t]> {
+        if self.certification_data.cert_type == sgx_ql_cert_key_type_t::PCK_CERT_CHAIN as uint16_t {
+            Some(self.certification_data.data)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Debug for Quote4<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Quote4")
+            .field("version", &self.version())
+            .field("tee_type", &self.tee_type())
+            .field("mr_td", &&self.report_body.mr_td.m[..])
+            .field("td_attributes", &self.td_attributes())
+            .field("cert_type", &self.certification_data.cert_type)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The TDREPORT_STRUCT returned by TDG.MR.REPORT, see `tdx_att_get_report`.
+///
+/// It is MACed for local verification only; a quote carries the fields
+/// a remote verifier needs in `sgx_report2_body_t`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TdReport {
+    pub report_mac_struct: sgx_report2_mac_struct_t,
+    pub tee_tcb_info: tee_tcb_info_t,
+    pub reserved: [uint8_t; SGX_REPORT2_RESERVED_BYTES],
+    pub td_info: tee_info_t,
+}
+
+impl TdReport {
+    pub fn from_bytes(bytes: &[uint8_t; TDX_REPORT_SIZE]) -> TdReport {
+        read_struct(bytes)
+    }
+
+    pub fn report_data(&self) -> &tee_report_data_t {
+        &self.report_mac_struct.report_data
+    }
+
+    pub fn tdx_module(&self) -> sgx_tdx_module_t {
+        sgx_tdx_module_t {
+            mr_seam: self.tee_tcb_info.mr_seam,
+            mrsigner_seam: self.tee_tcb_info.mr_seam_signer,
+            attributes: self.tee_tcb_info.attributes,
+        }
+    }
+
+    pub fn td_attributes(&self) -> sgx_tdx_attributes_t {
+        sgx_tdx_attributes_t::from(self.td_info.attributes)
+    }
+}
+
+impl From<&tdx_report_t> for TdReport {
+    fn from(report: &tdx_report_t) -> TdReport {
+        TdReport::from_bytes(&report.d)
+    }
+}
+
+struct Reader<'a>(&'a [uint8_t]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [uint8_t], QuoteParseError> {
+        if self.0.len() < len {
+            return Err(QuoteParseError::TooShort);
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u16(&mut self) -> Result<uint16_t, QuoteParseError> {
+        let b = self.take(2)?;
+        Ok(uint16_t::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<uint32_t, QuoteParseError> {
+        let b = self.take(4)?;
+        Ok(uint32_t::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn certification_data(&mut self) -> Result<QuoteCertificationData<'a>, QuoteParseError> {
+        let cert_type = self.u16()?;
+        let size = self.u32()? as usize;
+        let data = self.take(size)?;
+        Ok(QuoteCertificationData { cert_type, data })
+    }
+
+    fn finish(&self) -> Result<(), QuoteParseError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(QuoteParseError::TrailingData)
+        }
+    }
+}
+
+// T must be a plain C structure; the caller has checked the length.
+fn read_struct<T: Copy>(bytes: &[uint8_t]) -> T {
+    assert_eq!(bytes.len(), mem::size_of::<T>());
+    unsafe { ptr::read_unaligned(bytes.as_ptr() as *const T) }
+}
@@ -19,6 +19,7 @@ use crate::error::*;
 use crate::marker::ContiguousMemory;
 use crate::*;
 use core::default::Default;
+use core::fmt;
 
 //
 // sgx_attributes.h
@@ -142,6 +143,16 @@ impl_struct_ContiguousMemory! {
     sgx_dh_session_t;
 }
 
+impl sgx_dh_session_enclave_identity_t {
+    pub fn mr_enclave(&self) -> MrEnclave {
+        MrEnclave(self.mr_enclave.m)
+    }
+
+    pub fn mr_signer(&self) -> MrSigner {
+        MrSigner(self.mr_signer.m)
+    }
+}
+
 impl_enum! {
     #[repr(u32)]
     #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -421,6 +432,61 @@ impl_struct! {
     }
 }
 
+/* Typed measurements: an MRENCLAVE only compares equal to an MRENCLAVE, an MRSIGNER to an MRSIGNER. */
+macro_rules! impl_measurement {
+    ($($(#[$attr:meta])* $name:ident;)*) => {
+        $(
+            $(#[$attr])*
+            #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+            pub struct $name(pub [uint8_t; SGX_HASH_SIZE]);
+
+            impl From<sgx_measurement_t> for $name {
+                fn from(measurement: sgx_measurement_t) -> $name {
+                    $name(measurement.m)
+                }
+            }
+
+            impl From<$name> for sgx_measurement_t {
+                fn from(measurement: $name) -> sgx_measurement_t {
+                    sgx_measurement_t { m: measurement.0 }
+                }
+            }
+
+            impl TryFrom<&[uint8_t]> for $name {
+                type Error = sgx_status_t;
+
+                fn try_from(bytes: &[uint8_t]) -> Result<$name, sgx_status_t> {
+                    <[uint8_t; SGX_HASH_SIZE]>::try_from(bytes)
+                        .map($name)
+                        .map_err(|_| sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+                }
+            }
+
+            impl AsRef<[uint8_t]> for $name {
+                fn as_ref(&self) -> &[uint8_t] {
+                    &self.0
+                }
+            }
+
+            impl fmt::Display for $name {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    for b in self.0.iter() {
+                        write!(f, "{:02x}", b)?;
+                    }
+                    Ok(())
+                }
+            }
+        )*
+    }
+}
+
+impl_measurement! {
+    /// The measurement of an enclave's contents (MRENCLAVE).
+    MrEnclave;
+    /// The hash of the public key an enclave is signed with (MRSIGNER).
+    MrSigner;
+}
+
 pub type sgx_mac_t = [uint8_t; SGX_MAC_SIZE];
 
 impl_copy_clone! {
@@ -492,6 +558,22 @@ impl_struct_default! {
     sgx_report_t; //432
 }
 
+impl sgx_target_info_t {
+    pub fn mr_enclave(&self) -> MrEnclave {
+        MrEnclave(self.mr_enclave.m)
+    }
+}
+
+impl sgx_report_body_t {
+    pub fn mr_enclave(&self) -> MrEnclave {
+        MrEnclave(self.mr_enclave.m)
+    }
+
+    pub fn mr_signer(&self) -> MrSigner {
+        MrSigner(self.mr_signer.m)
+    }
+}
+
 impl_struct_ContiguousMemory! {
     sgx_target_info_t;
     sgx_report_body_t;
@@ -654,6 +736,7 @@ pub const SGX_ECP256_KEY_SIZE: size_t = 32;
 pub const SGX_NISTP_ECP256_KEY_SIZE: size_t = SGX_ECP256_KEY_SIZE / 4;
 pub const SGX_AESGCM_IV_SIZE: size_t = 12;
 pub const SGX_AESGCM_KEY_SIZE: size_t = 16;
+pub const SGX_AESGCM256_KEY_SIZE: size_t = 32;
 pub const SGX_AESGCM_MAC_SIZE: size_t = 16;
 pub const SGX_HMAC256_KEY_SIZE: size_t = 32;
 pub const SGX_HMAC256_MAC_SIZE: size_t = 32;
@@ -742,6 +825,7 @@ pub type sgx_sha256_hash_t = [uint8_t; SGX_SHA256_HASH_SIZE];
 pub type sgx_sha384_hash_t = [uint8_t; SGX_SHA384_HASH_SIZE];
 
 pub type sgx_aes_gcm_128bit_key_t = [uint8_t; SGX_AESGCM_KEY_SIZE];
+pub type sgx_aes_gcm_256bit_key_t = [uint8_t; SGX_AESGCM256_KEY_SIZE];
 pub type sgx_aes_gcm_128bit_tag_t = [uint8_t; SGX_AESGCM_MAC_SIZE];
 pub type sgx_hmac_256bit_key_t = [uint8_t; SGX_HMAC256_KEY_SIZE];
 pub type sgx_hmac_256bit_tag_t = [uint8_t; SGX_HMAC256_MAC_SIZE];
@@ -1679,6 +1763,36 @@ impl_packed_struct! {
         pub rt_mr: [tee_measurement_t; 4],
         pub report_data: tee_report_data_t,
     }
+
+    /* the identity of the TDX module, the first fields of sgx_report2_body_t after tee_tcb_svn */
+    pub struct sgx_tdx_module_t {
+        pub mr_seam: tee_measurement_t,
+        pub mrsigner_seam: tee_measurement_t,
+        pub attributes: tee_attributes_t,
+    }
+}
+
+pub const TEE_TYPE_SGX: uint32_t = 0x0000_0000;
+pub const TEE_TYPE_TDX: uint32_t = 0x0000_0081;
+
+impl_bitflags! {
+    /* TD attributes, see TDATTRIBUTES in the Intel TDX Module ABI specification */
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub struct sgx_tdx_attributes_t: uint64_t {
+        const DEBUG             = 0x0000_0000_0000_0001;
+        const SEPT_VE_DISABLE   = 0x0000_0000_1000_0000;
+        const PKS               = 0x0000_0000_4000_0000;
+        const KL                = 0x0000_0000_8000_0000;
+        const PERFMON           = 0x8000_0000_0000_0000;
+    }
+}
+
+impl From<tee_attributes_t> for sgx_tdx_attributes_t {
+    /* reserved bits are dropped */
+    fn from(attributes: tee_attributes_t) -> sgx_tdx_attributes_t {
+        let bits = (attributes.a[1] as uint64_t) << 32 | attributes.a[0] as uint64_t;
+        sgx_tdx_attributes_t::from_bits_truncate(bits)
+    }
 }
 
 //
@@ -2130,3 +2244,17 @@ pub const SGX_MM_EXCEPTION_CONTINUE_SEARCH: int32_t = 0;
 // Return value used by the EMM #PF handler to indicate
 // to the dispatcher that it should stop searching and continue execution.
 pub const SGX_MM_EXCEPTION_CONTINUE_EXECUTION: int32_t = -1;
+
+// The header of the ring buffer in untrusted memory through which an enclave
+// streams values to the host. It is followed by slot_count slots of
+// slot_size bytes. write_count, read_count and closed are accessed
+// atomically.
+impl_struct! {
+    pub struct sgx_channel_header_t {
+        pub write_count: uint64_t,
+        pub read_count: uint64_t,
+        pub slot_size: uint64_t,
+        pub slot_count: uint64_t,
+        pub closed: uint64_t,
+    }
+}
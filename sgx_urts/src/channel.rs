@@ -0,0 +1,150 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//! The host side of `sgx_tstd::sync::SgxChannel`.
+
+use sgx_types::marker::ContiguousMemory;
+use sgx_types::sgx_channel_header_t;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The receiving half of a ring buffer through which an enclave streams
+/// values of type `T` to the host.
+///
+/// Pass [`as_mut_ptr`] and [`byte_len`] to an ECALL that builds an
+/// `SgxChannel<T>` from them, and call [`read_available`] from another
+/// thread while the ECALL runs. Once [`is_closed`] returns `true`, one
+/// last `read_available` returns the remaining values.
+///
+/// [`as_mut_ptr`]: SgxChannelReader::as_mut_ptr
+/// [`byte_len`]: SgxChannelReader::byte_len
+/// [`read_available`]: SgxChannelReader::read_available
+/// [`is_closed`]: SgxChannelReader::is_closed
+pub struct SgxChannelReader<T: Copy + ContiguousMemory> {
+    // A leaked Box<[u64]>, shared with the enclave. u64 words keep the
+    // header aligned.
+    buf: *mut u64,
+    words: usize,
+    slot_count: u64,
+    read: u64,
+    marker: PhantomData<T>,
+}
+
+// The buffer is only shared with the enclave thread through raw pointers.
+unsafe impl<T: Copy + ContiguousMemory + Send> Send for SgxChannelReader<T> {}
+
+impl<T: Copy + ContiguousMemory> SgxChannelReader<T> {
+    /// Allocates a buffer with room for `slot_count` values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `slot_count` is zero, `T` is zero-sized or needs more than
+    /// 8-byte alignment.
+    pub fn new(slot_count: usize) -> SgxChannelReader<T> {
+        assert!(slot_count > 0, "a channel needs at least one slot");
+        assert!(mem::size_of::<T>() > 0, "zero-sized values cannot be sent");
+        assert!(mem::align_of::<T>() <= mem::align_of::<u64>(), "values are overaligned");
+
+        let len = mem::size_of::<sgx_channel_header_t>() + slot_count * mem::size_of::<T>();
+        let words = (len + mem::size_of::<u64>() - 1) / mem::size_of::<u64>();
+        let buf = Box::into_raw(vec![0_u64; words].into_boxed_slice()) as *mut u64;
+        let reader = SgxChannelReader {
+            buf,
+            words,
+            slot_count: slot_count as u64,
+            read: 0,
+            marker: PhantomData,
+        };
+        let header = reader.header();
+        unsafe {
+            (*header).slot_size = mem::size_of::<T>() as u64;
+            (*header).slot_count = slot_count as u64;
+        }
+        reader
+    }
+
+    /// Returns the address of the buffer, to be passed to the enclave.
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.buf as *mut u8
+    }
+
+    /// Returns the length of the buffer in bytes.
+    pub fn byte_len(&self) -> usize {
+        mem::size_of::<sgx_channel_header_t>() + self.slot_count as usize * mem::size_of::<T>()
+    }
+
+    /// Returns `true` once the enclave has closed its end of the channel.
+    pub fn is_closed(&self) -> bool {
+        self.counter(unsafe { ptr::addr_of_mut!((*self.header()).closed) })
+            .load(Ordering::Acquire)
+            != 0
+    }
+
+    /// Returns the values the enclave has sent since the last call, and
+    /// frees their slots.
+    ///
+    /// If the enclave reports more values than fit into the buffer, only
+    /// the last `slot_count` of them are returned.
+    pub fn read_available(&mut self) -> Vec<T> {
+        let header = self.header();
+        let written = self
+            .counter(unsafe { ptr::addr_of_mut!((*header).write_count) })
+            .load(Ordering::Acquire);
+        let start = self.read.max(written.saturating_sub(self.slot_count));
+
+        let slots = unsafe { (self.buf as *const u8).add(mem::size_of::<sgx_channel_header_t>()) };
+        let values = (start..written)
+            .map(|i| {
+                let slot = (i % self.slot_count) as usize;
+                unsafe { ptr::read_unaligned(slots.add(slot * mem::size_of::<T>()) as *const T) }
+            })
+            .collect();
+
+        self.read = written.max(self.read);
+        self.counter(unsafe { ptr::addr_of_mut!((*header).read_count) })
+            .store(self.read, Ordering::Release);
+        values
+    }
+
+    fn header(&self) -> *mut sgx_channel_header_t {
+        self.buf as *mut sgx_channel_header_t
+    }
+
+    fn counter(&self, field: *mut u64) -> &AtomicU64 {
+        unsafe { &*(field as *const AtomicU64) }
+    }
+}
+
+impl<T: Copy + ContiguousMemory> Drop for SgxChannelReader<T> {
+    fn drop(&mut self) {
+        unsafe {
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(self.buf, self.words)));
+        }
+    }
+}
+
+impl<T: Copy + ContiguousMemory> fmt::Debug for SgxChannelReader<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SgxChannelReader")
+            .field("slot_count", &self.slot_count)
+            .field("read", &self.read)
+            .finish()
+    }
+}
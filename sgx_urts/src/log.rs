@@ -0,0 +1,85 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+use libc::{c_char, size_t};
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::slice;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const LEVEL_NAMES: [&str; 6] = ["OFF", "ERROR", "WARN", "INFO", "DEBUG", "TRACE"];
+
+unsafe fn lossy_str(p: *const c_char, len: size_t) -> String {
+    if p.is_null() || len == 0 {
+        return String::new();
+    }
+    String::from_utf8_lossy(slice::from_raw_parts(p as *const u8, len)).into_owned()
+}
+
+fn push_json_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Writes a log record of `sgx_tstd::log` to stderr as one line of JSON:
+///
+/// `{"ts":1700000000.123,"level":"INFO","module":"app","file":"src/lib.rs","line":42,"message":"..."}`
+#[no_mangle]
+pub extern "C" fn u_log_ocall(
+    level: u32,
+    module: *const c_char,
+    module_len: size_t,
+    file: *const c_char,
+    file_len: size_t,
+    line: u32,
+    message: *const c_char,
+    message_len: size_t,
+) {
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let level = LEVEL_NAMES.get(level as usize).copied().unwrap_or("UNKNOWN");
+
+    let mut out = String::with_capacity(128 + message_len);
+    let _ = write!(
+        out,
+        "{{\"ts\":{}.{:03},\"level\":\"{}\",\"module\":",
+        ts.as_secs(),
+        ts.subsec_millis(),
+        level
+    );
+    push_json_str(&mut out, &unsafe { lossy_str(module, module_len) });
+    out.push_str(",\"file\":");
+    push_json_str(&mut out, &unsafe { lossy_str(file, file_len) });
+    let _ = write!(out, ",\"line\":{},\"message\":", line);
+    push_json_str(&mut out, &unsafe { lossy_str(message, message_len) });
+    out.push_str("}\n");
+
+    let _ = io::stderr().lock().write_all(out.as_bytes());
+}
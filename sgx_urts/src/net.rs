@@ -16,7 +16,10 @@
 // under the License..
 
 use libc::{self, addrinfo, c_char, c_int};
+use std::ffi::CString;
 use std::io::Error;
+use std::mem;
+use std::ptr;
 
 #[no_mangle]
 pub extern "C" fn u_getaddrinfo_ocall(
@@ -48,3 +51,47 @@ pub extern "C" fn u_freeaddrinfo_ocall(res: *mut addrinfo) {
 pub extern "C" fn u_gai_strerror_ocall(errcode: c_int) -> *const c_char {
     unsafe { libc::gai_strerror(errcode) }
 }
+
+#[no_mangle]
+pub extern "C" fn u_dns_resolve_ocall(
+    error: *mut c_int,
+    hostname: *const c_char,
+    port: u16,
+    addr: *mut [u8; 16],
+) -> c_int {
+    let mut errno = 0;
+    let mut hints: addrinfo = unsafe { mem::zeroed() };
+    hints.ai_family = libc::AF_INET;
+    hints.ai_socktype = libc::SOCK_STREAM;
+    hints.ai_flags = libc::AI_NUMERICSERV;
+    let service = CString::new(port.to_string()).unwrap();
+    let mut res: *mut addrinfo = ptr::null_mut();
+
+    let mut ret = unsafe { libc::getaddrinfo(hostname, service.as_ptr(), &hints, &mut res) };
+    if ret == libc::EAI_SYSTEM {
+        errno = Error::last_os_error().raw_os_error().unwrap_or(0);
+    }
+    if ret == 0 {
+        let info = unsafe { &*res };
+        if info.ai_family == libc::AF_INET && !info.ai_addr.is_null() {
+            // the first address, as the IPv4-mapped IPv6 address ::ffff:a.b.c.d
+            let sin = unsafe { &*(info.ai_addr as *const libc::sockaddr_in) };
+            let mut mapped = [0_u8; 16];
+            mapped[10] = 0xff;
+            mapped[11] = 0xff;
+            mapped[12..].copy_from_slice(&sin.sin_addr.s_addr.to_ne_bytes());
+            unsafe {
+                *addr = mapped;
+            }
+        } else {
+            ret = libc::EAI_NONAME;
+        }
+        unsafe { libc::freeaddrinfo(res) };
+    }
+    if !error.is_null() {
+        unsafe {
+            *error = errno;
+        }
+    }
+    ret
+}
@@ -16,6 +16,7 @@
 // under the License..
 
 use libc::{self, c_int, c_void, iovec, msghdr, size_t, sockaddr, socklen_t, ssize_t};
+use std::cmp;
 use std::io::Error;
 
 #[no_mangle]
@@ -196,6 +197,10 @@ pub extern "C" fn u_recvfrom_ocall(
     let ret = unsafe { libc::recvfrom(sockfd, buf, len, flags, src_addr, addrlen_out) };
     if ret < 0 {
         errno = Error::last_os_error().raw_os_error().unwrap_or(0);
+    } else {
+        // The kernel reports the full length of a truncated address, but the
+        // enclave rejects a length larger than the buffer it passed in.
+        unsafe { *addrlen_out = cmp::min(*addrlen_out, addrlen_in) };
     }
     if !error.is_null() {
         unsafe {
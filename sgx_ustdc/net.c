@@ -17,8 +17,12 @@
 
 #include <sys/types.h>
 #include <sys/socket.h>
+#include <netinet/in.h>
 #include <netdb.h>
 #include <errno.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
 
 int u_getaddrinfo_ocall(int *error,
                         const char *node,
@@ -41,4 +45,41 @@ void u_freeaddrinfo_ocall(struct addrinfo *res)
 const char *u_gai_strerror_ocall(int errcode)
 {
     return gai_strerror(errcode);
-}
\ No newline at end of file
+}
+
+int u_dns_resolve_ocall(int *error,
+                        const char *hostname,
+                        uint16_t port,
+                        uint8_t addr[16])
+{
+    struct addrinfo hints;
+    struct addrinfo *res = NULL;
+    char service[6];
+    int ret;
+
+    memset(&hints, 0, sizeof(hints));
+    hints.ai_family = AF_INET;
+    hints.ai_socktype = SOCK_STREAM;
+    hints.ai_flags = AI_NUMERICSERV;
+    snprintf(service, sizeof(service), "%u", (unsigned int)port);
+
+    ret = getaddrinfo(hostname, service, &hints, &res);
+    if (error) {
+        *error = ret == EAI_SYSTEM ? errno : 0;
+    }
+    if (ret != 0) {
+        return ret;
+    }
+    if (res->ai_family != AF_INET || res->ai_addr == NULL) {
+        freeaddrinfo(res);
+        return EAI_NONAME;
+    }
+
+    /* the first address, as the IPv4-mapped IPv6 address ::ffff:a.b.c.d */
+    memset(addr, 0, 10);
+    addr[10] = 0xff;
+    addr[11] = 0xff;
+    memcpy(addr + 12, &((struct sockaddr_in *)res->ai_addr)->sin_addr, 4);
+    freeaddrinfo(res);
+    return 0;
+}